@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.23;
+
+import {BottomUpCheckpoint} from "../../src/structs/CrossNet.sol";
+import {QuorumInfo, QuorumObjKind} from "../../src/structs/Quorum.sol";
+
+/// @notice A child gateway serving the checkpoint getters used by the relayer, without the
+///         validators: the quorum of a checkpoint is reached as soon as it is stored.
+contract GatewayCheckpointMock {
+    event QuorumReached(QuorumObjKind objKind, uint256 height, bytes32 objHash, uint256 quorumWeight);
+
+    uint256 public bottomUpCheckPeriod;
+    uint64 public majorityPercentage;
+
+    /// @dev The abi encoded checkpoints, by height.
+    mapping(uint256 => bytes) internal checkpoints;
+    mapping(uint256 => QuorumInfo) internal quorums;
+    mapping(uint256 => address[]) internal signatories;
+    mapping(uint256 => bytes[]) internal signatures;
+
+    constructor(uint256 _bottomUpCheckPeriod, uint64 _majorityPercentage) {
+        bottomUpCheckPeriod = _bottomUpCheckPeriod;
+        majorityPercentage = _majorityPercentage;
+    }
+
+    /// @notice The diamond loupe lookup of the relayer, this gateway implements the latest
+    ///         checkpoint version only.
+    function facetAddress(bytes4 selector) external view returns (address) {
+        if (selector == this.getCheckpointSignatureBundle.selector) {
+            return address(this);
+        }
+        return address(0);
+    }
+
+    /// @notice Stores the abi encoded `checkpoint` with its signatures and emits `QuorumReached`.
+    function reachQuorum(
+        bytes calldata checkpoint,
+        uint256 weight,
+        address[] calldata _signatories,
+        bytes[] calldata _signatures
+    ) external {
+        BottomUpCheckpoint memory ch = abi.decode(checkpoint, (BottomUpCheckpoint));
+        uint256 h = ch.blockHeight;
+        bytes32 hash = keccak256(checkpoint);
+
+        checkpoints[h] = checkpoint;
+        quorums[h] = QuorumInfo({
+            hash: hash,
+            rootHash: bytes32(0),
+            threshold: weight,
+            currentWeight: weight,
+            reached: true
+        });
+        signatories[h] = _signatories;
+        delete signatures[h];
+        for (uint256 i; i < _signatures.length; ) {
+            signatures[h].push(_signatures[i]);
+            unchecked {
+                ++i;
+            }
+        }
+
+        emit QuorumReached(QuorumObjKind.Checkpoint, h, hash, weight);
+    }
+
+    function getCheckpointSignatureBundle(
+        uint256 h
+    )
+        external
+        view
+        returns (
+            BottomUpCheckpoint memory ch,
+            QuorumInfo memory info,
+            address[] memory _signatories,
+            bytes[] memory _signatures
+        )
+    {
+        if (checkpoints[h].length != 0) {
+            ch = abi.decode(checkpoints[h], (BottomUpCheckpoint));
+        }
+        return (ch, quorums[h], signatories[h], signatures[h]);
+    }
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.23;
+
+import {BottomUpCheckpoint} from "../../src/structs/CrossNet.sol";
+import {InvalidCheckpointEpoch, InvalidSignatureLength} from "../../src/errors/IPCErrors.sol";
+
+/// @notice A parent subnet actor committing the checkpoints submitted by the relayer in order,
+///         without checking their signatures.
+contract SubnetActorCheckpointMock {
+    uint256 public bottomUpCheckPeriod;
+    uint256 public lastBottomUpCheckpointHeight;
+    /// @notice The number of accepted submissions, including the re-submissions of committed checkpoints.
+    uint256 public submissions;
+
+    /// @dev The abi encoded committed checkpoints, by height.
+    mapping(uint256 => bytes) internal committed;
+
+    constructor(uint256 _bottomUpCheckPeriod) {
+        bottomUpCheckPeriod = _bottomUpCheckPeriod;
+    }
+
+    /// @notice The diamond loupe lookup of the relayer, this actor implements the latest
+    ///         checkpoint version only.
+    function facetAddress(bytes4 selector) external view returns (address) {
+        if (selector == this.submitCheckpoint.selector) {
+            return address(this);
+        }
+        return address(0);
+    }
+
+    function submitCheckpoint(
+        BottomUpCheckpoint calldata checkpoint,
+        address[] calldata signatories,
+        bytes[] calldata signatures
+    ) external {
+        if (signatories.length != signatures.length) {
+            revert InvalidSignatureLength();
+        }
+
+        uint256 h = checkpoint.blockHeight;
+        if (h == lastBottomUpCheckpointHeight + bottomUpCheckPeriod) {
+            committed[h] = abi.encode(checkpoint);
+            lastBottomUpCheckpointHeight = h;
+        } else if (h > lastBottomUpCheckpointHeight) {
+            revert InvalidCheckpointEpoch();
+        }
+        submissions += 1;
+    }
+
+    function bottomUpCheckpointAtEpoch(
+        uint256 epoch
+    ) external view returns (bool exists, BottomUpCheckpoint memory checkpoint) {
+        if (committed[epoch].length == 0) {
+            return (false, checkpoint);
+        }
+        return (true, abi.decode(committed[epoch], (BottomUpCheckpoint)));
+    }
+}
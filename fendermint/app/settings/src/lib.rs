@@ -120,6 +120,8 @@ pub struct TopDownSettings {
     pub proposal_delay: BlockHeight,
     /// The max number of blocks one should make the topdown proposal
     pub max_proposal_range: BlockHeight,
+    /// The splitting of the top down messages into batches, off unless scheduled.
+    pub batching: Option<TopDownBatchingSettings>,
     /// Parent syncing cron period, in seconds
     #[serde_as(as = "DurationSeconds<u64>")]
     pub polling_interval: Duration,
@@ -143,6 +145,17 @@ pub struct TopDownSettings {
     pub parent_gateway: Address,
 }
 
+/// The application of the top down messages in batches, without the ones already applied,
+/// which changes the execution of the parent finality: all the validators of the subnet have
+/// to activate it at the same height.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TopDownBatchingSettings {
+    /// The first child block height applying the top down messages in batches.
+    pub activation_height: BlockHeight,
+    /// The max number of top down messages applied in the child at once.
+    pub max_msgs_per_batch: usize,
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize, Clone)]
 pub struct IpcSettings {
@@ -37,6 +37,8 @@ use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::version::NetworkVersion;
+use ipc_provider::bootstrap::SNAPSHOTS_QUERY_PATH;
+use ipc_provider::finality_votes::{FinalityVoteStatus, FINALITY_VOTES_QUERY_PATH};
 use num_traits::Zero;
 use serde::{Deserialize, Serialize};
 use tendermint::abci::request::CheckTxKind;
@@ -531,6 +533,34 @@ where
     /// Query the application for data at the current or past height.
     #[instrument(skip(self))]
     async fn query(&self, request: request::Query) -> AbciResult<response::Query> {
+        // The votes are not part of the state, they are tallied by this node.
+        if request.path == FINALITY_VOTES_QUERY_PATH {
+            let status = atomically(|| self.chain_env.parent_finality_votes.status()).await;
+            let value = serde_json::to_vec(&FinalityVoteStatus::from(status))
+                .context("error encoding the parent finality votes")?;
+            return Ok(response::Query {
+                value: value.into(),
+                ..Default::default()
+            });
+        }
+
+        // The snapshots are offered by this node, none if it does not take them.
+        if request.path == SNAPSHOTS_QUERY_PATH {
+            let offers = match self.snapshots {
+                Some(ref client) => atomically(|| client.list_snapshots())
+                    .await
+                    .iter()
+                    .map(to_snapshot_offer)
+                    .collect(),
+                None => Vec::new(),
+            };
+            let value = serde_json::to_vec(&offers).context("error encoding the snapshots")?;
+            return Ok(response::Query {
+                value: value.into(),
+                ..Default::default()
+            });
+        }
+
         let db = self.state_store_clone();
         let height = FvmQueryHeight::from(request.height.value());
         let (state_params, block_height) = self.state_params_at_height(height)?;
@@ -295,6 +295,10 @@ async fn new_genesis_from_parent(
                 provider_http: args.parent_endpoint.clone(),
                 provider_timeout: None,
                 auth_token: None,
+                provider_ws: None,
+                proxy: None,
+                block_time: None,
+                provider_max_concurrency: None,
                 registry_addr: args.parent_registry,
                 gateway_addr: args.parent_gateway,
             }),
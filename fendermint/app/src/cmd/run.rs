@@ -15,7 +15,7 @@ use fendermint_vm_interpreter::fvm::upgrades::UpgradeScheduler;
 use fendermint_vm_interpreter::{
     bytes::{BytesMessageInterpreter, ProposalPrepareMode},
     chain::{ChainMessageInterpreter, CheckpointPool},
-    fvm::{Broadcaster, FvmMessageInterpreter, ValidatorContext},
+    fvm::{Broadcaster, FvmMessageInterpreter, TopDownBatching, ValidatorContext},
     signed::SignedMessageInterpreter,
 };
 use fendermint_vm_resolver::ipld::IpldResolver;
@@ -117,7 +117,18 @@ async fn run(settings: Settings) -> anyhow::Result<()> {
         UpgradeScheduler::new(),
     );
     let interpreter = SignedMessageInterpreter::new(interpreter);
-    let interpreter = ChainMessageInterpreter::<_, NamespaceBlockstore>::new(interpreter);
+    let mut interpreter = ChainMessageInterpreter::<_, NamespaceBlockstore>::new(interpreter);
+    if let Some(batching) = settings
+        .ipc
+        .topdown
+        .as_ref()
+        .and_then(|t| t.batching.as_ref())
+    {
+        interpreter = interpreter.with_topdown_batching(TopDownBatching {
+            activation_height: batching.activation_height,
+            max_batch_size: batching.max_msgs_per_batch,
+        });
+    }
     let interpreter =
         BytesMessageInterpreter::new(interpreter, ProposalPrepareMode::AppendOnly, false);
 
@@ -353,6 +364,10 @@ fn make_ipc_provider_proxy(settings: &Settings) -> anyhow::Result<IPCProviderPro
                 .unwrap(),
             provider_timeout: topdown_config.parent_http_timeout,
             auth_token: topdown_config.parent_http_auth_token.as_ref().cloned(),
+            provider_ws: None,
+            proxy: None,
+            block_time: None,
+            provider_max_concurrency: None,
             registry_addr: topdown_config.parent_registry,
             gateway_addr: topdown_config.parent_gateway,
         }),
@@ -11,6 +11,7 @@ use fendermint_vm_interpreter::fvm::{
 use fendermint_vm_message::signed::DomainHash;
 use fendermint_vm_snapshot::{SnapshotItem, SnapshotManifest};
 use fvm_shared::{address::Address, error::ExitCode, event::StampedEvent, ActorID};
+use ipc_provider::bootstrap::SnapshotOffer;
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, num::NonZeroU32};
@@ -408,6 +409,19 @@ pub fn to_snapshot(snapshot: SnapshotItem) -> anyhow::Result<tendermint::abci::t
     })
 }
 
+/// Convert a snapshot manifest to the offer shown to the new validators bootstrapping a node.
+pub fn to_snapshot_offer(snapshot: &SnapshotItem) -> SnapshotOffer {
+    let manifest = &snapshot.manifest;
+    SnapshotOffer {
+        block_height: manifest.block_height,
+        version: manifest.version,
+        size: manifest.size,
+        chunks: manifest.chunks,
+        checksum: manifest.checksum.to_string(),
+        state_root: manifest.state_params.state_root.to_string(),
+    }
+}
+
 /// Parse a Tendermint ABCI snapshot offer to a manifest.
 pub fn from_snapshot(
     offer: tendermint::abci::request::OfferSnapshot,
@@ -310,6 +310,12 @@ impl DockerMaterializer {
         let mut config = if !file_name.exists() {
             IpcCliConfig {
                 keystore_path: Some("~/.ipc".to_string()),
+                network: None,
+                proxy: None,
+                confirmation_threshold: None,
+                rpc_max_concurrency: None,
+                log_levels: Default::default(),
+                keystores: Default::default(),
                 subnets: Default::default(),
             }
         } else {
@@ -511,6 +517,10 @@ impl DockerMaterializer {
                     provider_http: url,
                     provider_timeout: Some(Duration::from_secs(30)),
                     auth_token: None,
+                    provider_ws: None,
+                    proxy: None,
+                    block_time: None,
+                    provider_max_concurrency: None,
                     registry_addr: submit_config.deployment.registry.into(),
                     gateway_addr: submit_config.deployment.gateway.into(),
                 }),
@@ -1064,6 +1074,12 @@ mod tests {
     fn test_ipc_cli_config_toml_roundtrip() {
         let mut config0 = IpcCliConfig {
             keystore_path: Some("~/.ipc".to_string()),
+            network: None,
+            proxy: None,
+            confirmation_threshold: None,
+            rpc_max_concurrency: None,
+            log_levels: Default::default(),
+            keystores: Default::default(),
             subnets: Default::default(),
         };
 
@@ -1073,6 +1089,10 @@ mod tests {
                 provider_http: url::Url::parse("http://example.net").unwrap(),
                 provider_timeout: Some(Duration::from_secs(30)),
                 auth_token: None,
+                provider_ws: None,
+                proxy: None,
+                block_time: None,
+                provider_max_concurrency: None,
                 registry_addr: ipc::SUBNETREGISTRY_ACTOR_ADDR,
                 gateway_addr: ipc::GATEWAY_ACTOR_ADDR,
             }),
@@ -83,6 +83,8 @@ pub type ChainMessageCheckRes = Result<SignedMessageCheckRes, IllegalMessage>;
 pub struct ChainMessageInterpreter<I, DB> {
     inner: I,
     gateway_caller: GatewayCaller<DB>,
+    /// The application of the top down messages in batches, if scheduled.
+    topdown_batching: Option<topdown::TopDownBatching>,
 }
 
 impl<I, DB> ChainMessageInterpreter<I, DB> {
@@ -90,8 +92,14 @@ impl<I, DB> ChainMessageInterpreter<I, DB> {
         Self {
             inner,
             gateway_caller: GatewayCaller::default(),
+            topdown_batching: None,
         }
     }
+
+    pub fn with_topdown_batching(mut self, topdown_batching: topdown::TopDownBatching) -> Self {
+        self.topdown_batching = Some(topdown_batching);
+        self
+    }
 }
 
 #[async_trait]
@@ -339,9 +347,14 @@ where
                         "chain interpreter received topdown msgs",
                     );
 
-                    let ret = topdown::execute_topdown_msgs(&self.gateway_caller, &mut state, msgs)
-                        .await
-                        .context("failed to execute top down messages")?;
+                    let ret = topdown::execute_topdown_msgs(
+                        &self.gateway_caller,
+                        &mut state,
+                        msgs,
+                        self.topdown_batching,
+                    )
+                    .await
+                    .context("failed to execute top down messages")?;
 
                     tracing::debug!("chain interpreter applied topdown msgs");
 
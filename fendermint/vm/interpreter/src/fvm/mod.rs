@@ -27,6 +27,7 @@ use fvm_ipld_blockstore::Blockstore;
 pub use genesis::FvmGenesisOutput;
 pub use query::FvmQueryRet;
 use tendermint_rpc::Client;
+pub use topdown::TopDownBatching;
 
 pub use self::broadcast::Broadcaster;
 use self::{state::ipc::GatewayCaller, upgrades::UpgradeScheduler};
@@ -287,6 +287,11 @@ impl<DB: Blockstore + Clone> GatewayCaller<DB> {
         Ok(r.into_return())
     }
 
+    /// The nonce of the next top-down message the gateway expects to apply.
+    pub fn applied_top_down_nonce(&self, state: &mut FvmExecState<DB>) -> anyhow::Result<u64> {
+        self.getter.call(state, |c| c.applied_top_down_nonce())
+    }
+
     pub fn get_latest_parent_finality(
         &self,
         state: &mut FvmExecState<DB>,
@@ -38,17 +38,104 @@ where
     Ok((prev_height, prev_finality))
 }
 
-/// Execute the top down messages implicitly. Before the execution, mint to the gateway of the funds
-/// transferred in the messages, and increase the circulating supply with the incoming value.
+/// The application of the top down messages in batches of at most `max_batch_size` messages,
+/// without the ones already applied, from the child block `activation_height` on. Before it,
+/// all the messages of a finality are applied at once and as they are, as filtering or
+/// splitting them changes the execution of the finality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopDownBatching {
+    pub activation_height: BlockHeight,
+    pub max_batch_size: usize,
+}
+
+impl TopDownBatching {
+    /// Whether the batching applies to the messages executed at `height`.
+    pub fn is_active_at(&self, height: BlockHeight) -> bool {
+        height >= self.activation_height
+    }
+}
+
+/// Execute the top down messages implicitly. Before the execution, mint to the gateway of the
+/// funds transferred in the messages, and increase the circulating supply with the incoming
+/// value.
+///
+/// Once `batching` is active at the current height, the messages already applied in the child
+/// are dropped and the rest executed in batches, each minting for its own messages, see
+/// [`batch_topdown_msgs`]. All the batches are applied even if one fails: the gateway answers
+/// the messages whose nonce it does not expect with a receipt, instead of the messages being
+/// lost with the finality committed.
 pub async fn execute_topdown_msgs<DB>(
     gateway_caller: &GatewayCaller<DB>,
     state: &mut FvmExecState<DB>,
     messages: Vec<IpcEnvelope>,
+    batching: Option<TopDownBatching>,
 ) -> anyhow::Result<FvmApplyRet>
 where
     DB: Blockstore + Sync + Send + Clone + 'static,
 {
-    let minted_tokens = tokens_to_mint(&messages);
+    let height = state.block_height() as BlockHeight;
+    let Some(batching) = batching.filter(|b| b.is_active_at(height)) else {
+        mint_for_batch(gateway_caller, state, &messages)?;
+        return gateway_caller.apply_cross_messages(state, messages);
+    };
+
+    let applied_nonce = gateway_caller
+        .applied_top_down_nonce(state)
+        .context("failed to get the applied top down nonce")?;
+
+    let total = messages.len();
+    let batches = batch_topdown_msgs(messages, applied_nonce, batching.max_batch_size);
+    let messages = batches.iter().map(Vec::len).sum::<usize>();
+    if messages < total {
+        tracing::info!(
+            applied_nonce,
+            dropped = total - messages,
+            "dropped top down messages already applied"
+        );
+    }
+
+    // the gateway is still called without messages, for the execution to have a result
+    if batches.is_empty() {
+        return gateway_caller.apply_cross_messages(state, vec![]);
+    }
+
+    let mut ret: Option<FvmApplyRet> = None;
+    for batch in batches {
+        let from_nonce = batch[0].nonce;
+        tracing::debug!(
+            from_nonce,
+            messages = batch.len(),
+            "applying top down message batch"
+        );
+        mint_for_batch(gateway_caller, state, &batch)?;
+
+        let batch_ret = gateway_caller.apply_cross_messages(state, batch)?;
+        if !batch_ret.apply_ret.msg_receipt.exit_code.is_success() {
+            tracing::error!(
+                from_nonce,
+                exit_code = batch_ret.apply_ret.msg_receipt.exit_code.value(),
+                "failed to apply top down message batch"
+            );
+        }
+        ret = Some(match ret {
+            Some(ret) => merge_apply_rets(ret, batch_ret),
+            None => batch_ret,
+        });
+    }
+    Ok(ret.expect("at least one batch"))
+}
+
+/// Mint to the gateway the funds transferred in a batch of messages, and increase the
+/// circulating supply with the incoming value.
+fn mint_for_batch<DB>(
+    gateway_caller: &GatewayCaller<DB>,
+    state: &mut FvmExecState<DB>,
+    batch: &[IpcEnvelope],
+) -> anyhow::Result<()>
+where
+    DB: Blockstore + Sync + Send + Clone + 'static,
+{
+    let minted_tokens = tokens_to_mint(batch);
     tracing::debug!(token = minted_tokens.to_string(), "tokens to mint in child");
 
     if !minted_tokens.is_zero() {
@@ -60,6 +147,137 @@ where
             *circ_supply += minted_tokens;
         });
     }
+    Ok(())
+}
+
+/// Prepares the top down messages for their application in the child, whose gateway expects
+/// `applied_nonce` next: drops the messages with a nonce already applied and the exact
+/// duplicates, and splits the rest into batches of at most `max_batch_size` messages, in the
+/// order of their nonces.
+///
+/// Nonce gaps and conflicting messages with the same nonce are logged but left to the gateway,
+/// which rejects them with a receipt: failing here would halt the subnet.
+pub fn batch_topdown_msgs(
+    mut messages: Vec<IpcEnvelope>,
+    applied_nonce: u64,
+    max_batch_size: usize,
+) -> Vec<Vec<IpcEnvelope>> {
+    messages.retain(|m| m.nonce >= applied_nonce);
+    messages.sort_by_key(|m| m.nonce);
+
+    let mut deduped: Vec<IpcEnvelope> = Vec::with_capacity(messages.len());
+    for msg in messages {
+        if deduped
+            .iter()
+            .rev()
+            .take_while(|m| m.nonce == msg.nonce)
+            .any(|m| *m == msg)
+        {
+            continue;
+        }
+        if deduped.last().is_some_and(|last| last.nonce == msg.nonce) {
+            tracing::warn!(nonce = msg.nonce, "conflicting top down messages");
+        }
+        deduped.push(msg);
+    }
+
+    let mut expected = applied_nonce;
+    for msg in &deduped {
+        if msg.nonce > expected {
+            tracing::warn!(
+                expected,
+                nonce = msg.nonce,
+                "gap in the nonces of the top down messages"
+            );
+        }
+        expected = msg.nonce + 1;
+    }
+
+    deduped
+        .chunks(max_batch_size.max(1))
+        .map(|c| c.to_vec())
+        .collect()
+}
+
+/// Accumulates the result of a batch into the result of the previous ones, keeping the exit
+/// code of the first failed batch.
+fn merge_apply_rets(mut acc: FvmApplyRet, ret: FvmApplyRet) -> FvmApplyRet {
+    let receipt = &mut acc.apply_ret.msg_receipt;
+    if receipt.exit_code.is_success() {
+        receipt.exit_code = ret.apply_ret.msg_receipt.exit_code;
+        receipt.return_data = ret.apply_ret.msg_receipt.return_data;
+        acc.apply_ret.failure_info = ret.apply_ret.failure_info;
+    }
+    receipt.gas_used += ret.apply_ret.msg_receipt.gas_used;
+    acc.apply_ret.events.extend(ret.apply_ret.events);
+    acc.apply_ret.exec_trace.extend(ret.apply_ret.exec_trace);
+    acc.gas_limit += ret.gas_limit;
+    acc.emitters.extend(ret.emitters);
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_topdown_msgs, TopDownBatching};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::cross::IpcEnvelope;
+    use ipc_api::subnet_id::SubnetID;
+
+    fn msg(nonce: u64, value: u64) -> IpcEnvelope {
+        let mut msg = IpcEnvelope::new_release_msg(
+            &SubnetID::new(1, vec![Address::new_id(1000)]),
+            &Address::new_id(100),
+            &Address::new_id(101),
+            TokenAmount::from_atto(value),
+        )
+        .unwrap();
+        msg.nonce = nonce;
+        msg
+    }
+
+    fn nonces(batches: &[Vec<IpcEnvelope>]) -> Vec<Vec<u64>> {
+        batches
+            .iter()
+            .map(|b| b.iter().map(|m| m.nonce).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_batches_in_nonce_order() {
+        let msgs = vec![msg(3, 1), msg(1, 1), msg(2, 1), msg(4, 1), msg(5, 1)];
+        let batches = batch_topdown_msgs(msgs, 1, 2);
+        assert_eq!(nonces(&batches), vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+        assert!(batch_topdown_msgs(vec![], 7, 2).is_empty());
+    }
+
+    #[test]
+    fn test_drops_applied_and_duplicates() {
+        let msgs = vec![msg(0, 1), msg(1, 1), msg(2, 1), msg(2, 1), msg(3, 1)];
+        let batches = batch_topdown_msgs(msgs, 2, 10);
+        assert_eq!(nonces(&batches), vec![vec![2, 3]]);
+
+        // all of them applied already
+        assert!(batch_topdown_msgs(vec![msg(0, 1), msg(1, 1)], 2, 10).is_empty());
+    }
+
+    #[test]
+    fn test_passes_gaps_and_conflicts() {
+        let batches = batch_topdown_msgs(vec![msg(4, 1), msg(2, 1)], 2, 10);
+        assert_eq!(nonces(&batches), vec![vec![2, 4]]);
+
+        let batches = batch_topdown_msgs(vec![msg(2, 1), msg(2, 5), msg(2, 1)], 2, 10);
+        assert_eq!(nonces(&batches), vec![vec![2, 2]]);
+    }
 
-    gateway_caller.apply_cross_messages(state, messages)
+    #[test]
+    fn test_batching_activation() {
+        let batching = TopDownBatching {
+            activation_height: 10,
+            max_batch_size: 2,
+        };
+        assert!(!batching.is_active_at(9));
+        assert!(batching.is_active_at(10));
+    }
 }
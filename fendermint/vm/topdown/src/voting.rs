@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use async_stm::{abort, atomically_or_err, retry, Stm, StmResult, TVar};
+use fvm_shared::clock::ChainEpoch;
+use ipc_provider::finality_votes::{BlockVotes, FinalityVoteStatus, HeightVotes, ValidatorPower};
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::{fmt::Debug, time::Duration};
 
@@ -33,6 +36,17 @@ pub enum Error<K = ValidatorKey, V: AsRef<[u8]> = BlockHash> {
     Equivocation(K, BlockHeight, V, V),
 }
 
+/// A snapshot of the vote tally, for diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteStatus<K = ValidatorKey, V = BlockHash> {
+    pub power_table: Vec<(K, Weight)>,
+    pub quorum_threshold: Weight,
+    /// The finalized chain of the parent, from the block finalized in the ledger.
+    pub chain: Vec<(BlockHeight, Option<V>)>,
+    /// The votes received by height, then by block hash.
+    pub votes: Vec<(BlockHeight, Vec<(V, Vec<K>)>)>,
+}
+
 /// Keep track of votes being gossiped about parent chain finality
 /// and tally up the weights of the validators on the child subnet,
 /// so that we can ask for proposals that are not going to be voted
@@ -273,6 +287,29 @@ where
         Ok(None)
     }
 
+    /// Take a snapshot of the tally, e.g. to find out why no quorum is reached.
+    pub fn status(&self) -> Stm<VoteStatus<K, V>> {
+        let power_table = self.power_table.read()?;
+        let chain = self.chain.read()?;
+        let votes = self.votes.read()?;
+
+        Ok(VoteStatus {
+            power_table: power_table.iter().map(|(k, w)| (k.clone(), *w)).collect(),
+            quorum_threshold: self.quorum_threshold()?,
+            chain: chain.iter().map(|(h, b)| (*h, b.clone())).collect(),
+            votes: votes
+                .iter()
+                .map(|(h, vs)| {
+                    let vs = vs
+                        .iter()
+                        .map(|(b, ks)| (b.clone(), ks.iter().cloned().collect()))
+                        .collect();
+                    (*h, vs)
+                })
+                .collect(),
+        })
+    }
+
     /// Call when a new finalized block is added to the ledger, to clear out all preceding blocks.
     ///
     /// After this operation the minimum item in the chain will the new finalized block.
@@ -319,6 +356,80 @@ where
     }
 }
 
+impl From<VoteStatus> for FinalityVoteStatus {
+    fn from(status: VoteStatus) -> Self {
+        let weights = status
+            .power_table
+            .iter()
+            .cloned()
+            .collect::<std::collections::HashMap<_, _>>();
+        let mut validators = status
+            .power_table
+            .iter()
+            .map(|(k, w)| ValidatorPower {
+                validator: validator_address(k),
+                power: *w,
+            })
+            .collect::<Vec<_>>();
+        validators.sort_by(|a, b| a.validator.cmp(&b.validator));
+
+        let last_finalized_height = status.chain.first().map(|(h, _)| *h).unwrap_or_default();
+        let latest_height = status.chain.last().map(|(h, _)| *h).unwrap_or_default();
+
+        let mut heights = BTreeMap::<BlockHeight, HeightVotes>::new();
+        let entry = |h: BlockHeight| HeightVotes {
+            height: h as ChainEpoch,
+            block_hash: None,
+            blocks: vec![],
+        };
+        for (h, hash) in status.chain.into_iter().skip(1) {
+            heights.insert(
+                h,
+                HeightVotes {
+                    block_hash: hash.map(|h| format!("0x{}", hex::encode(h))),
+                    ..entry(h)
+                },
+            );
+        }
+        for (h, blocks) in status.votes {
+            let height = heights.entry(h).or_insert_with(|| entry(h));
+            for (hash, keys) in blocks {
+                let mut voters = keys.iter().map(validator_address).collect::<Vec<_>>();
+                voters.sort();
+                height.blocks.push(BlockVotes {
+                    block_hash: format!("0x{}", hex::encode(hash)),
+                    voters,
+                    weight: keys.iter().filter_map(|k| weights.get(k)).sum(),
+                });
+            }
+            height
+                .blocks
+                .sort_by(|a, b| a.block_hash.cmp(&b.block_hash));
+        }
+
+        Self {
+            last_finalized_height: last_finalized_height as ChainEpoch,
+            latest_height: latest_height as ChainEpoch,
+            quorum_threshold: status.quorum_threshold,
+            validators,
+            heights: heights.into_values().collect(),
+        }
+    }
+}
+
+/// The Ethereum address of a validator, the way the operators know it, or its public key if
+/// it is not a secp256k1 one.
+fn validator_address(key: &ValidatorKey) -> String {
+    let pk: libp2p::identity::PublicKey = key.clone().into();
+    match pk.clone().try_into_secp256k1() {
+        Ok(pk) => {
+            let hash = ethers::utils::keccak256(&pk.to_bytes_uncompressed()[1..]);
+            format!("0x{}", hex::encode(&hash[12..]))
+        }
+        Err(_) => format!("0x{}", hex::encode(pk.encode_protobuf())),
+    }
+}
+
 /// Poll the vote tally for new finalized blocks and publish a vote about them if the validator is part of the power table.
 pub async fn publish_vote_loop<V, F>(
     vote_tally: VoteTally,
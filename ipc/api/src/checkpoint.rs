@@ -26,7 +26,8 @@ lazy_static! {
 
 pub type Signature = Vec<u8>;
 
-/// The event emitted
+/// The event emitted by the gateway of a child subnet when the signatures collected for a
+/// certificate, e.g. a bottom-up checkpoint, reach its quorum.
 #[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub struct QuorumReachedEvent {
     pub obj_kind: u8,
@@ -34,17 +35,38 @@ pub struct QuorumReachedEvent {
     /// The checkpoint hash
     pub obj_hash: Vec<u8>,
     pub quorum_weight: TokenAmount,
+    /// The number of validators that signed the checkpoint, once its bundle is known.
+    #[serde(default)]
+    pub signatories: Option<u64>,
+    /// The hash of the child block the event was emitted in, if the backend reports it.
+    #[serde(default)]
+    pub block_hash: Option<Vec<u8>>,
+}
+
+impl QuorumReachedEvent {
+    /// Records the signatories of the bundle of the checkpoint of the event.
+    pub fn with_bundle(mut self, bundle: &BottomUpCheckpointBundle) -> Self {
+        self.signatories = Some(bundle.signatories.len() as u64);
+        self
+    }
 }
 
 impl Display for QuorumReachedEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "QuorumReachedEvent<height: {}, checkpoint: {}, quorum_weight: {}>",
+            "QuorumReachedEvent<height: {}, checkpoint: {}, quorum_weight: {}",
             self.height,
             hex::encode(&self.obj_hash),
             self.quorum_weight
-        )
+        )?;
+        if let Some(signatories) = self.signatories {
+            write!(f, ", signatories: {signatories}")?;
+        }
+        if let Some(block_hash) = &self.block_hash {
+            write!(f, ", block: {}", hex::encode(block_hash))?;
+        }
+        write!(f, ">")
     }
 }
 
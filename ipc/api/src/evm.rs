@@ -193,9 +193,11 @@ cross_msg_types!(xnet_messaging_facet);
 cross_msg_types!(gateway_messenger_facet);
 cross_msg_types!(lib_gateway);
 cross_msg_types!(subnet_actor_checkpointing_facet);
+cross_msg_types!(subnet_actor_getter_facet);
 
 bottom_up_checkpoint_conversion!(gateway_getter_facet);
 bottom_up_checkpoint_conversion!(subnet_actor_checkpointing_facet);
+bottom_up_checkpoint_conversion!(subnet_actor_getter_facet);
 bottom_up_msg_batch_conversion!(gateway_getter_facet);
 
 impl TryFrom<SupplySource> for subnet_actor_diamond::SupplySource {
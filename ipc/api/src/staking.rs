@@ -8,11 +8,12 @@ use ethers::utils::hex;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use ipc_actors_abis::{lib_staking_change_log, subnet_actor_getter_facet};
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
 pub type ConfigurationNumber = u64;
 
-#[derive(Clone, Debug, num_enum::TryFromPrimitive)]
+#[derive(Clone, Debug, Serialize, Deserialize, num_enum::TryFromPrimitive)]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum StakingOperation {
@@ -22,14 +23,14 @@ pub enum StakingOperation {
     SetFederatedPower = 3,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StakingChangeRequest {
     pub configuration_number: ConfigurationNumber,
     pub change: StakingChange,
 }
 
 /// The change request to validator staking
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StakingChange {
     pub op: StakingOperation,
     pub payload: Vec<u8>,
@@ -54,7 +55,7 @@ impl TryFrom<lib_staking_change_log::NewStakingChangeRequestFilter> for StakingC
 }
 
 /// The staking validator information
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ValidatorStakingInfo {
     confirmed_collateral: TokenAmount,
     total_collateral: TokenAmount,
@@ -86,7 +87,7 @@ impl TryFrom<subnet_actor_getter_facet::ValidatorInfo> for ValidatorStakingInfo
 }
 
 /// The full validator information with
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ValidatorInfo {
     pub staking: ValidatorStakingInfo,
     /// If the validator is active in block production
@@ -3,13 +3,14 @@
 
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_actors_abis::subnet_actor_getter_facet;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     eth_to_fil_amount, ethers_address_to_fil_address,
     evm::{fil_to_eth_amount, payload_to_evm_address},
 };
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Validator {
     pub addr: Address,
     pub metadata: Vec<u8>,
@@ -0,0 +1,46 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Debug;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::checkpoint::attestation::SignedAttestation;
+use ipc_provider::schema;
+
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to verify a status attestation signed by a relayer.
+pub(crate) struct VerifyAttestation;
+
+#[async_trait]
+impl CommandLineHandler for VerifyAttestation {
+    type Arguments = VerifyAttestationArgs;
+
+    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("verify attestation with args: {:?}", arguments);
+
+        let json = std::fs::read_to_string(&arguments.file)
+            .with_context(|| format!("cannot read {}", arguments.file))?;
+        let signed = schema::from_json::<SignedAttestation>(&json)?;
+        signed.verify()?;
+
+        let attestation = &signed.attestation;
+        println!(
+            "valid attestation by relayer {:?}: last committed height {} of {} at {}",
+            attestation.relayer,
+            attestation.last_committed_height,
+            attestation.subnet,
+            attestation.timestamp
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Verify the signature of a status attestation published by a relayer")]
+pub(crate) struct VerifyAttestationArgs {
+    #[arg(long, help = "The JSON file of the signed attestation")]
+    pub file: String,
+}
@@ -23,7 +23,7 @@ impl CommandLineHandler for GetBottomUpBundles {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get bottom up bundles with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         for h in arguments.from_epoch..=arguments.to_epoch {
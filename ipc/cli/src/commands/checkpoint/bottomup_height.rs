@@ -24,7 +24,7 @@ impl CommandLineHandler for LastBottomUpCheckpointHeight {
             arguments
         );
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         let height = provider.last_bottom_up_checkpoint_height(&subnet).await?;
@@ -0,0 +1,78 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use clap::Args;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::doctor::{doctor, DoctorOptions};
+use ipc_provider::schema;
+
+use crate::commands::{f64_to_token_amount, require_fil_addr_from_str};
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to check the setup of the relayer of a subnet.
+pub(crate) struct RelayerDoctor;
+
+#[async_trait]
+impl CommandLineHandler for RelayerDoctor {
+    type Arguments = RelayerDoctorArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("relayer doctor with args: {:?}", arguments);
+
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let mut options = DoctorOptions::default();
+        if let Some(submitter) = &arguments.submitter {
+            options = options.with_submitter(require_fil_addr_from_str(submitter)?);
+        }
+        if let Some(keystore) = &arguments.keystore {
+            options = options.with_keystore(keystore);
+        }
+        if let Some(min_balance) = arguments.min_balance {
+            options = options.with_min_balance(f64_to_token_amount(min_balance)?);
+        }
+
+        let report = doctor(&global.config_path(), &subnet, &options).await;
+        if arguments.json {
+            println!("{}", schema::to_json_pretty(&report)?);
+        } else {
+            println!("{report}");
+        }
+
+        if !report.healthy() {
+            return Err(anyhow!("the relayer of {subnet} is not ready"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Check the setup of the relayer of a child subnet, with fixes for the failures")]
+pub(crate) struct RelayerDoctorArgs {
+    #[arg(long, help = "The child subnet to relay the checkpoints of")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The hex encoded address of the submitter, by default the key of the keystore"
+    )]
+    pub submitter: Option<String>,
+    #[arg(
+        long,
+        help = "The keystore of the config to sign with, by default the keystore of the relayer role if any"
+    )]
+    pub keystore: Option<String>,
+    #[arg(
+        long,
+        help = "The balance of the submitter below which to warn, in whole FIL"
+    )]
+    pub min_balance: Option<f64>,
+    #[arg(
+        long,
+        help = "Print the report as json, in the versioned `relayer_doctor` schema"
+    )]
+    pub json: bool,
+}
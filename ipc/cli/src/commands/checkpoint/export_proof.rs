@@ -0,0 +1,56 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::checkpoint::archive::CheckpointArchive;
+use ipc_provider::expand_tilde;
+
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to export the proof bundle of a checkpoint archived by the relayer.
+pub(crate) struct ExportProofBundle;
+
+#[async_trait]
+impl CommandLineHandler for ExportProofBundle {
+    type Arguments = ExportProofBundleArgs;
+
+    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("export proof bundle with args: {:?}", arguments);
+
+        let archive = CheckpointArchive::open(expand_tilde(&arguments.archive_dir))?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        let bundle = archive.export_proof_bundle(
+            &subnet,
+            arguments.height,
+            expand_tilde(&arguments.output),
+        )?;
+        println!(
+            "exported checkpoint({}) committed at parent height {} in tx {}",
+            bundle.height(),
+            bundle.receipt.epoch,
+            bundle.receipt.tx_hash
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Export the proof bundle of a checkpoint archived by the relayer")]
+pub(crate) struct ExportProofBundleArgs {
+    #[arg(long, help = "The archive directory of the relayer")]
+    pub archive_dir: String,
+    #[arg(long, help = "The child subnet of the checkpoint")]
+    pub subnet: String,
+    #[arg(long, help = "The height of the checkpoint")]
+    pub height: ChainEpoch,
+    #[arg(long, help = "The file to export the proof bundle to")]
+    pub output: String,
+}
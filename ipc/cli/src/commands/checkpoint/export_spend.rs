@@ -0,0 +1,64 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::checkpoint::archive::CheckpointArchive;
+use ipc_provider::checkpoint::report::{ReportFormat, SpendReport};
+use ipc_provider::expand_tilde;
+use ipc_provider::pagination::HeightRange;
+
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to export the gas spend of the checkpoints archived by the relayer.
+pub(crate) struct ExportSpendReport;
+
+#[async_trait]
+impl CommandLineHandler for ExportSpendReport {
+    type Arguments = ExportSpendReportArgs;
+
+    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("export spend report with args: {:?}", arguments);
+
+        let archive = CheckpointArchive::open(expand_tilde(&arguments.archive_dir))?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let format = ReportFormat::from_str(&arguments.format)?;
+
+        let heights = HeightRange::new(arguments.from.unwrap_or_default(), arguments.to);
+        let report = SpendReport::from_archive(&archive, &subnet, heights)?;
+        report.export(format, expand_tilde(&arguments.output))?;
+        println!(
+            "exported the spend of {} checkpoints, {} atto in total",
+            report.records.len(),
+            report.total_fee()
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Export the gas spend of the checkpoints archived by the relayer")]
+pub(crate) struct ExportSpendReportArgs {
+    #[arg(long, help = "The archive directory of the relayer")]
+    pub archive_dir: String,
+    #[arg(long, help = "The child subnet of the checkpoints")]
+    pub subnet: String,
+    #[arg(long, help = "The lowest height of the checkpoints to export")]
+    pub from: Option<ChainEpoch>,
+    #[arg(long, help = "The highest height of the checkpoints to export")]
+    pub to: Option<ChainEpoch>,
+    #[arg(
+        long,
+        default_value = "csv",
+        help = "The format of the report, csv or parquet"
+    )]
+    pub format: String,
+    #[arg(long, help = "The file to export the report to")]
+    pub output: String,
+}
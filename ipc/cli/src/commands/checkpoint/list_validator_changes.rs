@@ -9,9 +9,10 @@ use async_trait::async_trait;
 use clap::Args;
 use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::pagination::{HeightRange, PageRequest};
 
 use crate::commands::get_ipc_provider;
-use crate::{CommandLineHandler, GlobalArguments};
+use crate::{require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
 
 /// The command to list validator changes committed in a subnet.
 pub(crate) struct ListValidatorChanges;
@@ -23,12 +24,26 @@ impl CommandLineHandler for ListValidatorChanges {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("list validator changes with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
-        for h in arguments.from_epoch..=arguments.to_epoch {
-            let changes = provider.get_validator_changeset(&subnet, h).await?;
-            log::info!("changes at height: {h} are: {:?}", changes.value);
+        let validator = match &arguments.validator {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+
+        let heights = HeightRange::new(arguments.from_epoch, arguments.to_epoch);
+        let page = PageRequest::new(arguments.cursor.clone(), arguments.limit);
+        let page = provider
+            .list_validator_changes(&subnet, heights, validator, &page)
+            .await?;
+
+        for (h, change) in page.items {
+            log::info!("change at height: {h} is: {:?}", change);
+        }
+
+        if let Some(cursor) = page.next_cursor {
+            println!("next cursor: {cursor}");
         }
 
         Ok(())
@@ -42,6 +57,18 @@ pub(crate) struct ListValidatorChangesArgs {
     pub subnet: String,
     #[arg(long, help = "Include checkpoints from this epoch")]
     pub from_epoch: ChainEpoch,
-    #[arg(long, help = "Include checkpoints up to this epoch")]
-    pub to_epoch: ChainEpoch,
+    #[arg(
+        long,
+        help = "Include checkpoints up to this epoch, up to the chain head if not set"
+    )]
+    pub to_epoch: Option<ChainEpoch>,
+    #[arg(long, help = "Only include the changes of this validator")]
+    pub validator: Option<String>,
+    #[arg(long, help = "The maximum number of items to list")]
+    pub limit: Option<usize>,
+    #[arg(
+        long,
+        help = "List the items after this cursor, printed with the previous page"
+    )]
+    pub cursor: Option<String>,
 }
@@ -1,9 +1,13 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
+use crate::commands::checkpoint::attestation::{VerifyAttestation, VerifyAttestationArgs};
 use crate::commands::checkpoint::bottomup_bundles::{GetBottomUpBundles, GetBottomUpBundlesArgs};
 use crate::commands::checkpoint::bottomup_height::{
     LastBottomUpCheckpointHeight, LastBottomUpCheckpointHeightArgs,
 };
+use crate::commands::checkpoint::doctor::{RelayerDoctor, RelayerDoctorArgs};
+use crate::commands::checkpoint::export_proof::{ExportProofBundle, ExportProofBundleArgs};
+use crate::commands::checkpoint::export_spend::{ExportSpendReport, ExportSpendReportArgs};
 use crate::commands::checkpoint::list_checkpoints::{
     ListBottomUpCheckpoints, ListBottomUpCheckpointsArgs,
 };
@@ -14,20 +18,30 @@ use crate::commands::checkpoint::quorum_reached::{
     GetQuorumReacehdEvents, GetQuorumReachedEventsArgs,
 };
 use crate::commands::checkpoint::relayer::{BottomUpRelayer, BottomUpRelayerArgs};
+use crate::commands::checkpoint::relayers::{
+    AllowRelayer, DisallowRelayer, ListRelayers, ListRelayersArgs, SetRelayerArgs,
+};
+use crate::commands::checkpoint::status::{CheckpointStatus, CheckpointStatusArgs};
 use crate::{CommandLineHandler, GlobalArguments};
 use clap::{Args, Subcommand};
 
+mod attestation;
 mod bottomup_bundles;
 mod bottomup_height;
+mod doctor;
+mod export_proof;
+mod export_spend;
 mod list_checkpoints;
 mod list_validator_changes;
 mod quorum_reached;
 mod relayer;
+mod relayers;
+mod status;
 
 #[derive(Debug, Args)]
 #[command(name = "checkpoint", about = "checkpoint related commands")]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct CheckpointCommandsArgs {
+pub struct CheckpointCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
@@ -37,6 +51,7 @@ impl CheckpointCommandsArgs {
         match &self.command {
             Commands::ListBottomup(args) => ListBottomUpCheckpoints::handle(global, args).await,
             Commands::Relayer(args) => BottomUpRelayer::handle(global, args).await,
+            Commands::RelayerDoctor(args) => RelayerDoctor::handle(global, args).await,
             Commands::ListValidatorChanges(args) => {
                 ListValidatorChanges::handle(global, args).await
             }
@@ -47,6 +62,13 @@ impl CheckpointCommandsArgs {
             Commands::LastBottomupCheckpointHeight(args) => {
                 LastBottomUpCheckpointHeight::handle(global, args).await
             }
+            Commands::Status(args) => CheckpointStatus::handle(global, args).await,
+            Commands::ExportProof(args) => ExportProofBundle::handle(global, args).await,
+            Commands::ExportSpend(args) => ExportSpendReport::handle(global, args).await,
+            Commands::ListRelayers(args) => ListRelayers::handle(global, args).await,
+            Commands::AllowRelayer(args) => AllowRelayer::handle(global, args).await,
+            Commands::DisallowRelayer(args) => DisallowRelayer::handle(global, args).await,
+            Commands::VerifyAttestation(args) => VerifyAttestation::handle(global, args).await,
         }
     }
 }
@@ -55,8 +77,18 @@ impl CheckpointCommandsArgs {
 pub(crate) enum Commands {
     ListBottomup(ListBottomUpCheckpointsArgs),
     Relayer(BottomUpRelayerArgs),
+    RelayerDoctor(RelayerDoctorArgs),
     ListValidatorChanges(ListValidatorChangesArgs),
     ListBottomupBundle(GetBottomUpBundlesArgs),
     QuorumReachedEvents(GetQuorumReachedEventsArgs),
     LastBottomupCheckpointHeight(LastBottomUpCheckpointHeightArgs),
+    Status(CheckpointStatusArgs),
+    ExportProof(ExportProofBundleArgs),
+    ExportSpend(ExportSpendReportArgs),
+    ListRelayers(ListRelayersArgs),
+    #[command(about = "Allow a relayer to submit the checkpoints of a permissioned subnet")]
+    AllowRelayer(SetRelayerArgs),
+    #[command(about = "Revoke the permission of a relayer to submit the checkpoints of a subnet")]
+    DisallowRelayer(SetRelayerArgs),
+    VerifyAttestation(VerifyAttestationArgs),
 }
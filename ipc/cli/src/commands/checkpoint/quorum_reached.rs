@@ -9,6 +9,7 @@ use async_trait::async_trait;
 use clap::Args;
 use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::pagination::{HeightRange, PageRequest};
 
 use crate::commands::get_ipc_provider;
 use crate::{CommandLineHandler, GlobalArguments};
@@ -23,14 +24,21 @@ impl CommandLineHandler for GetQuorumReacehdEvents {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get quorum reached events with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
-        for h in arguments.from_epoch..=arguments.to_epoch {
-            let events = provider.quorum_reached_events(&subnet, h).await?;
-            for e in events {
-                println!("{e}");
-            }
+        let heights = HeightRange::new(arguments.from_epoch, arguments.to_epoch);
+        let page = PageRequest::new(arguments.cursor.clone(), arguments.limit);
+        let page = provider
+            .list_quorum_reached_events(&subnet, heights, &page)
+            .await?;
+
+        for (_, e) in page.items {
+            println!("{e}");
+        }
+
+        if let Some(cursor) = page.next_cursor {
+            println!("next cursor: {cursor}");
         }
 
         Ok(())
@@ -44,6 +52,16 @@ pub(crate) struct GetQuorumReachedEventsArgs {
     pub subnet: String,
     #[arg(long, help = "Include events from this epoch")]
     pub from_epoch: ChainEpoch,
-    #[arg(long, help = "Include events up to this epoch")]
-    pub to_epoch: ChainEpoch,
+    #[arg(
+        long,
+        help = "Include events up to this epoch, up to the chain head if not set"
+    )]
+    pub to_epoch: Option<ChainEpoch>,
+    #[arg(long, help = "The maximum number of items to list")]
+    pub limit: Option<usize>,
+    #[arg(
+        long,
+        help = "List the items after this cursor, printed with the previous page"
+    )]
+    pub cursor: Option<String>,
 }
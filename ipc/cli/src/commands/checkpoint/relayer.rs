@@ -1,23 +1,53 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 
-use crate::commands::get_subnet_config;
+use crate::commands::{f64_to_token_amount, get_subnet_config};
 use crate::{require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use async_trait::async_trait;
+use axum::routing::get;
 use clap::Args;
+use ethers::signers::Signer;
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::evm::payload_to_evm_address;
 use ipc_api::subnet_id::SubnetID;
-use ipc_provider::checkpoint::BottomUpCheckpointManager;
+use ipc_provider::breaker::{DEFAULT_COOL_DOWN, DEFAULT_FAILURE_THRESHOLD};
+use ipc_provider::checkpoint::allowlist::check_relayer_allowed;
+use ipc_provider::checkpoint::archive::CheckpointArchive;
+use ipc_provider::checkpoint::attestation::Attester;
+use ipc_provider::checkpoint::audit::SubmissionAudit;
+use ipc_provider::checkpoint::escalation::FeeEscalation;
+use ipc_provider::checkpoint::limits::BundleLimits;
+use ipc_provider::checkpoint::policy::MessagePolicy;
+use ipc_provider::checkpoint::report::{ReportFormat, SpendReportExporter};
+use ipc_provider::checkpoint::state::{RecentErrors, StateReporter};
+use ipc_provider::checkpoint::{BottomUpCheckpointManager, EmptyCheckpointPolicy};
 use ipc_provider::config::Config;
-use ipc_provider::new_evm_keystore_from_config;
+use ipc_provider::drift::{DriftMonitor, DriftThresholds};
+use ipc_provider::journal::TxJournal;
+use ipc_provider::key_source::KeySource;
+use ipc_provider::keystores::{NamedKeystore, RELAYER_ROLE};
+use ipc_provider::manager::evm::{set_global_rpc_concurrency, Forwarder, Urgency};
+use ipc_provider::manager::EthSubnetManager;
+use ipc_provider::release::KnownReleases;
+use ipc_provider::topup::{SubmitterTopUp, TopUpPolicy};
+use ipc_provider::webhook::{WebhookConfig, WebhookDispatcher};
+use ipc_provider::{expand_tilde, monitor, IpcProvider};
 use ipc_wallet::EvmKeyStore;
+use prometheus::Registry;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use url::Url;
 
 const DEFAULT_POLLING_INTERVAL: u64 = 15;
+const DEFAULT_MAX_HELD_EMPTY_CHECKPOINTS: usize = 10;
+const DEFAULT_PROFILE_SUMMARY_INTERVAL: u64 = 300;
+const DEFAULT_FEE_ESCALATION_PERIODS: ChainEpoch = 2;
+const DEFAULT_SPEND_REPORT_INTERVAL: u64 = 86400;
 
 /// The command to run the bottom up relayer in the background.
 pub(crate) struct BottomUpRelayer;
@@ -31,16 +61,61 @@ impl CommandLineHandler for BottomUpRelayer {
 
         let config_path = global.config_path();
         let config = Arc::new(Config::from_file(&config_path)?);
-        let mut keystore = new_evm_keystore_from_config(config)?;
-        let submitter = match (arguments.submitter.as_ref(), keystore.get_default()?) {
-            (Some(submitter), _) => require_fil_addr_from_str(submitter)?,
-            (None, Some(addr)) => {
-                log::info!("using default address: {addr:?}");
-                Address::try_from(addr)?
-            }
-            _ => {
-                return Err(anyhow!("no submitter address provided"));
-            }
+        if let Some(limit) = config.rpc_max_concurrency {
+            set_global_rpc_concurrency(limit)?;
+        }
+        // the keystore of the relayer role, if any, replaces the key source
+        let named = match arguments
+            .keystore
+            .as_deref()
+            .or_else(|| config.keystore_of_role(RELAYER_ROLE))
+        {
+            Some(name) => Some(NamedKeystore::open(config.clone(), name).await?),
+            None => None,
+        };
+        let keystore = match &named {
+            Some(named) => named.keys.clone(),
+            None => Arc::new(RwLock::new(
+                arguments.key_source.evm_keystore(config.clone()).await?,
+            )),
+        };
+        // observers don't submit, so they don't need a submitter
+        let mut signer = None;
+        let submitter = if arguments.observe {
+            None
+        } else {
+            let submitter = arguments
+                .submitter
+                .as_deref()
+                .map(require_fil_addr_from_str)
+                .transpose()?;
+            let address = submitter
+                .as_ref()
+                .map(|s| payload_to_evm_address(s.payload()))
+                .transpose()?;
+            signer = match &named {
+                Some(named) => named.signer.clone(),
+                None => arguments.key_source.remote_signer(address).await?,
+            };
+
+            let default = keystore.write().unwrap().get_default()?;
+            Some(match (submitter, &signer, default) {
+                (Some(submitter), _, _) => submitter,
+                (None, Some(signer), _) => {
+                    log::info!(
+                        "using the address of the remote signer: {:?}",
+                        signer.address()
+                    );
+                    ethers_address_to_fil_address(&signer.address())?
+                }
+                (None, None, Some(addr)) => {
+                    log::info!("using default address: {addr:?}");
+                    Address::try_from(addr)?
+                }
+                _ => {
+                    return Err(anyhow!("no submitter address provided"));
+                }
+            })
         };
 
         let subnet = SubnetID::from_str(&arguments.subnet)?;
@@ -51,28 +126,314 @@ impl CommandLineHandler for BottomUpRelayer {
         let child = get_subnet_config(&config_path, &subnet)?;
         let parent = get_subnet_config(&config_path, &parent)?;
 
+        if let Some(path) = &arguments.known_releases {
+            let releases = KnownReleases::from_file(expand_tilde(path))?;
+            IpcProvider::new_read_only_from_config(config_path.clone())?
+                .verify_contracts(&subnet, &releases)
+                .await?;
+        }
+
+        if let Some(submitter) = submitter {
+            let manager = EthSubnetManager::from_subnet_with_wallet_store(&parent, None)?;
+            check_relayer_allowed(&manager, &subnet, &submitter).await?;
+        }
+
+        if let Some(interval) = arguments.drift_check_interval_sec {
+            let block_time = IpcProvider::new_read_only_from_config(config_path.clone())?
+                .block_time(&subnet)
+                .await?;
+            let mut thresholds = DriftThresholds::default();
+            if let Some(v) = arguments.max_height_drift {
+                thresholds = thresholds.with_max_height_drift(v);
+            }
+            if let Some(v) = arguments.max_clock_skew_sec {
+                thresholds = thresholds.with_max_clock_skew(Duration::from_secs(v));
+            }
+            let drift = DriftMonitor::new(
+                EthSubnetManager::from_subnet_with_wallet_store(&parent, None)?,
+                EthSubnetManager::from_subnet_with_wallet_store(&child, None)?,
+                subnet.to_string(),
+                block_time,
+                thresholds,
+            );
+            tokio::spawn(drift.run(Duration::from_secs(interval)));
+        }
+
+        let journal = match &arguments.journal_path {
+            Some(path) => Some(Arc::new(TxJournal::open(expand_tilde(path))?)),
+            None => None,
+        };
+
+        if let Some(treasury) = &arguments.top_up_treasury {
+            let submitter = submitter.ok_or_else(|| anyhow!("observers cannot be topped up"))?;
+            let (Some(threshold), Some(amount)) =
+                (arguments.top_up_threshold, arguments.top_up_amount)
+            else {
+                return Err(anyhow!("the top-up needs a threshold and an amount"));
+            };
+            let mut policy = TopUpPolicy::new(
+                f64_to_token_amount(threshold)?,
+                f64_to_token_amount(amount)?,
+            )?;
+            if let Some(cap) = arguments.top_up_daily_cap {
+                policy = policy.with_cap(f64_to_token_amount(cap)?);
+            }
+            let mut treasury_manager =
+                EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?;
+            if let Some(journal) = &journal {
+                treasury_manager = treasury_manager.with_journal(journal.clone());
+            }
+            let mut top_up = SubmitterTopUp::new(
+                treasury_manager,
+                require_fil_addr_from_str(treasury)?,
+                submitter,
+                policy,
+                subnet.to_string(),
+            );
+            if let Some(journal) = &journal {
+                top_up = top_up.with_journal(journal.clone());
+            }
+            tokio::spawn(top_up.run(Duration::from_secs(arguments.top_up_interval_sec)));
+        }
+
+        if let Some(interval) = arguments.attestation_interval_sec {
+            let submitter = submitter.ok_or_else(|| anyhow!("observers cannot attest"))?;
+            if arguments.attestation_file.is_none() && arguments.attestation_url.is_none() {
+                return Err(anyhow!(
+                    "the attestations need a file or a URL to be published to"
+                ));
+            }
+            let mut signing =
+                EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?;
+            if let Some(signer) = &signer {
+                signing = signing.with_signer(signer.clone());
+            }
+            let mut attester = Attester::new(signing, subnet.clone(), submitter)?;
+            if let Some(path) = &arguments.attestation_file {
+                attester = attester.with_file(expand_tilde(path));
+            }
+            if let Some(url) = &arguments.attestation_url {
+                attester = attester.with_url(url.clone());
+            }
+            tokio::spawn(attester.run(Duration::from_secs(interval)));
+        }
+
         let mut manager = BottomUpCheckpointManager::new_evm_manager(
             parent.clone(),
             child.clone(),
-            Arc::new(RwLock::new(keystore)),
+            keystore,
+            journal,
         )
         .await?;
 
+        if let Some(signer) = signer {
+            manager = manager.with_signer(signer);
+        }
+        if let Some(forwarder) = config.forwarder_of_subnet(&subnet) {
+            let forwarder = Forwarder::try_from(forwarder)?;
+            log::info!(
+                "submitting through forwarder {:?}, paid for by {:?}",
+                forwarder.address,
+                forwarder.payer
+            );
+            manager = manager.with_forwarder(forwarder);
+        }
+
+        if let Some(dir) = &arguments.archive_dir {
+            let archive = CheckpointArchive::open(expand_tilde(dir))?;
+            if let Some(reports) = &arguments.spend_report_dir {
+                let exporter = SpendReportExporter::new(
+                    archive.clone(),
+                    child.id.clone(),
+                    expand_tilde(reports),
+                    Duration::from_secs(
+                        arguments
+                            .spend_report_interval_sec
+                            .unwrap_or(DEFAULT_SPEND_REPORT_INTERVAL),
+                    ),
+                )?
+                .with_format(ReportFormat::from_str(&arguments.spend_report_format)?);
+                tokio::spawn(exporter.run());
+            }
+            manager = manager.with_archive(Arc::new(archive));
+        } else if arguments.spend_report_dir.is_some() {
+            return Err(anyhow!(
+                "the spend reports are exported from the archive-dir"
+            ));
+        }
+
+        if let Some(dir) = &arguments.audit_dir {
+            let audit = SubmissionAudit::open(expand_tilde(dir))?;
+            manager = manager.with_audit(Arc::new(audit));
+        }
+
+        if let Some(path) = &arguments.state_file {
+            let errors = RecentErrors::default();
+            let recorded = errors.clone();
+            manager = manager.on_failure(move |failure| {
+                recorded.record(failure.checkpoint.block_height, failure.error);
+                async { Ok(()) }
+            });
+            let mut reporter = StateReporter::new(
+                EthSubnetManager::from_subnet_with_wallet_store(&parent, None)?,
+                EthSubnetManager::from_subnet_with_wallet_store(&child, None)?,
+                subnet.clone(),
+                expand_tilde(path),
+            )?
+            .with_kept_reports(arguments.state_file_keep)
+            .with_errors(errors);
+            if let Some(submitter) = submitter {
+                reporter = reporter.with_submitter(submitter);
+            }
+            tokio::spawn(reporter.run(Duration::from_secs(arguments.state_file_interval_sec)));
+        }
+
         if let Some(v) = arguments.finalization_blocks {
             manager = manager.with_finalization_blocks(v as ChainEpoch);
         }
+        if let Some(v) = arguments.quorum_threshold {
+            manager = manager.with_quorum_threshold(v)?;
+        }
+        if let Some(v) = arguments.call_timeout_sec {
+            manager = manager.with_call_timeout(Duration::from_secs(v));
+        }
+        if let Some(v) = arguments.submission_timeout_sec {
+            manager = manager.with_submission_timeout(Duration::from_secs(v));
+        }
+        if let Some(v) = arguments.submission_jitter_sec {
+            manager = manager.with_submission_jitter(Duration::from_secs(v));
+        }
+        if let Some(v) = arguments.status_check_interval_sec {
+            manager = manager.with_status_check_interval(Duration::from_secs(v));
+        }
+
+        let mut limits = BundleLimits::default();
+        if let Some(v) = arguments.max_checkpoint_msgs {
+            limits = limits.with_max_msgs(v);
+        }
+        if let Some(v) = arguments.max_calldata_bytes {
+            limits = limits.with_max_calldata_bytes(v);
+        }
+        manager = manager.with_bundle_limits(limits);
+        if let Some(v) = arguments.rpc_batch_size {
+            manager = manager.with_rpc_batch_size(v);
+        }
+        if let Some(v) = arguments.prefetch_limit {
+            manager = manager.with_prefetch_limit(v);
+        }
+
+        if arguments.breaker_failure_threshold.is_some()
+            || arguments.breaker_cool_down_sec.is_some()
+        {
+            manager = manager.with_circuit_breaker(
+                arguments
+                    .breaker_failure_threshold
+                    .unwrap_or(DEFAULT_FAILURE_THRESHOLD),
+                arguments
+                    .breaker_cool_down_sec
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_COOL_DOWN),
+            );
+        }
+
+        manager = manager.with_empty_checkpoints(match arguments.empty_checkpoints.as_str() {
+            "submit" => EmptyCheckpointPolicy::Submit,
+            "skip" => EmptyCheckpointPolicy::Skip,
+            "batch" => EmptyCheckpointPolicy::Batch {
+                max_held: arguments
+                    .max_held_empty_checkpoints
+                    .unwrap_or(DEFAULT_MAX_HELD_EMPTY_CHECKPOINTS),
+            },
+            p => return Err(anyhow!("unknown empty checkpoints policy: {p}")),
+        });
+
+        let fee_urgency = Urgency::from_str(&arguments.fee_urgency)?;
+        let max_fee_urgency = match &arguments.max_fee_urgency {
+            Some(u) => Urgency::from_str(u)?,
+            None => fee_urgency,
+        };
+        manager = manager.with_fee_escalation(FeeEscalation::new(
+            fee_urgency,
+            max_fee_urgency,
+            arguments
+                .fee_escalation_periods
+                .unwrap_or(DEFAULT_FEE_ESCALATION_PERIODS),
+        ));
+
+        let mut policy = MessagePolicy::default();
+        for recipient in &arguments.deny_recipient {
+            policy = policy.with_denied_recipient(require_fil_addr_from_str(recipient)?);
+        }
+        for recipient in &arguments.allow_recipient {
+            policy = policy.with_allowed_recipient(require_fil_addr_from_str(recipient)?);
+        }
+        if let Some(v) = arguments.max_message_value {
+            policy = policy.with_max_value(f64_to_token_amount(v)?);
+        }
+        if !policy.is_empty() {
+            manager = manager.with_submission_policy(Arc::new(policy));
+        }
+
+        if !arguments.webhook.is_empty() {
+            let webhooks = arguments
+                .webhook
+                .iter()
+                .map(|url| WebhookConfig {
+                    url: url.clone(),
+                    secret: arguments.webhook_secret.clone(),
+                })
+                .collect();
+            manager = manager.with_webhooks(Arc::new(WebhookDispatcher::new(webhooks)?));
+        }
+
+        if arguments.profile {
+            manager = manager.with_profiling(Duration::from_secs(
+                arguments
+                    .profile_summary_sec
+                    .unwrap_or(DEFAULT_PROFILE_SUMMARY_INTERVAL),
+            ));
+        }
+
+        if let Some(addr) = arguments.metrics_address {
+            serve_metrics(addr, monitor::setup(vec![])?)?;
+        }
 
         let interval = Duration::from_secs(
             arguments
                 .checkpoint_interval_sec
                 .unwrap_or(DEFAULT_POLLING_INTERVAL),
         );
-        manager.run(submitter, interval).await;
+        match submitter {
+            Some(submitter) => manager.run(submitter, interval).await,
+            None => manager.run_observer(interval).await,
+        }
 
         Ok(())
     }
 }
 
+/// Serves the metrics of `registry` at `/metrics` on `listen_addr` in the background.
+fn serve_metrics(listen_addr: SocketAddr, registry: Registry) -> anyhow::Result<()> {
+    let router = axum::Router::new().route(
+        "/metrics",
+        get(move || {
+            let registry = registry.clone();
+            async move { monitor::encode(&registry) }
+        }),
+    );
+    let server = axum::Server::try_bind(&listen_addr)
+        .with_context(|| format!("cannot bind metrics endpoint to {listen_addr}"))?
+        .serve(router.into_make_service());
+
+    log::info!("serving metrics at {listen_addr}");
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            log::error!("metrics server stopped: {e}");
+        }
+    });
+    Ok(())
+}
+
 #[derive(Debug, Args)]
 #[command(about = "Start the bottom up relayer daemon")]
 pub(crate) struct BottomUpRelayerArgs {
@@ -88,4 +449,253 @@ pub(crate) struct BottomUpRelayerArgs {
     pub finalization_blocks: Option<u64>,
     #[arg(long, help = "The hex encoded address of the submitter")]
     pub submitter: Option<String>,
+    #[arg(
+        long,
+        default_value = "keystore",
+        help = "Where to read the submitter key from: keystore, env:<VAR>, stdin or fd:<N>, and with their features vault-kv:<mount>/<path>, vault-transit:<mount>/<key>, aws-kms:<key id> or gcp-kms:<key version>"
+    )]
+    pub key_source: KeySource,
+    #[arg(
+        long,
+        help = "The keystore of the config to sign with instead of the key source, by default the keystore of the relayer role if any"
+    )]
+    pub keystore: Option<String>,
+    #[arg(
+        long,
+        help = "Only watch and verify the checkpoints committed in the parent, without submitting"
+    )]
+    pub observe: bool,
+    #[arg(
+        long,
+        help = "The file to journal submissions in, to recover them after a crash"
+    )]
+    pub journal_path: Option<String>,
+    #[arg(
+        long,
+        help = "The file of the code hashes of the known contract releases, to warn about unknown or outdated contracts on start"
+    )]
+    pub known_releases: Option<String>,
+    #[arg(
+        long,
+        help = "The directory to archive the committed checkpoints in, with the evidence of their submission"
+    )]
+    pub archive_dir: Option<String>,
+    #[arg(
+        long,
+        help = "The directory to periodically export the gas spend of the archived checkpoints to, requires the archive-dir"
+    )]
+    pub spend_report_dir: Option<String>,
+    #[arg(
+        long,
+        help = "The directory to record every submission attempt in, with its exact calldata and the decoded checkpoint"
+    )]
+    pub audit_dir: Option<String>,
+    #[arg(
+        long,
+        help = "The JSON file to periodically write the state of the relayer to, with its heights, lag, balance and last errors"
+    )]
+    pub state_file: Option<String>,
+    #[arg(
+        long,
+        default_value = "60",
+        help = "The number of seconds between two writes of the state file"
+    )]
+    pub state_file_interval_sec: u64,
+    #[arg(
+        long,
+        default_value = "5",
+        help = "The number of previous state files kept, rotated with a numeric suffix"
+    )]
+    pub state_file_keep: usize,
+    #[arg(
+        long,
+        help = "The number of seconds between two status attestations signed by the submitter, none if not set"
+    )]
+    pub attestation_interval_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The JSON file to write the latest signed status attestation to"
+    )]
+    pub attestation_file: Option<String>,
+    #[arg(long, help = "The URL to post the signed status attestations to")]
+    pub attestation_url: Option<Url>,
+    #[arg(
+        long,
+        help = "The number of seconds between two exports of the spend report"
+    )]
+    pub spend_report_interval_sec: Option<u64>,
+    #[arg(
+        long,
+        default_value = "csv",
+        help = "The format of the spend reports, csv or parquet"
+    )]
+    pub spend_report_format: String,
+    #[arg(
+        long,
+        help = "The percentage of the total validator weight that must sign a checkpoint before it is submitted, defaults to the contract quorum"
+    )]
+    pub quorum_threshold: Option<u8>,
+    #[arg(
+        long,
+        help = "The number of seconds between two checks of the clock skew and height drift of the child against the parent"
+    )]
+    pub drift_check_interval_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The relative difference between the actual and expected child progression to alert on, e.g. 0.25"
+    )]
+    pub max_height_drift: Option<f64>,
+    #[arg(
+        long,
+        help = "The number of seconds the child head timestamp may differ from the parent one by before alerting"
+    )]
+    pub max_clock_skew_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The address of the treasury to top up the submitter from, its key must be in the keystore of the relayer"
+    )]
+    pub top_up_treasury: Option<String>,
+    #[arg(
+        long,
+        help = "The balance of the submitter below which it is topped up, in whole FIL"
+    )]
+    pub top_up_threshold: Option<f64>,
+    #[arg(
+        long,
+        help = "The amount sent to the submitter by each top-up, in whole FIL"
+    )]
+    pub top_up_amount: Option<f64>,
+    #[arg(
+        long,
+        help = "The total amount sent to the submitter within a day, in whole FIL, defaults to a single top-up"
+    )]
+    pub top_up_daily_cap: Option<f64>,
+    #[arg(
+        long,
+        default_value = "60",
+        help = "The number of seconds between two checks of the balance of the submitter"
+    )]
+    pub top_up_interval_sec: u64,
+    #[arg(
+        long,
+        help = "The number of seconds after which a subnet query times out, the checkpoint submissions are only bounded by the submission timeout"
+    )]
+    pub call_timeout_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The number of seconds after which a submission attempt is abandoned"
+    )]
+    pub submission_timeout_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The maximum number of seconds the delay between two submissions randomly deviates from the checkpoint interval by"
+    )]
+    pub submission_jitter_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The number of seconds between two checks that the subnet is still active in the parent, the relayer stops once it is killed or de-registered"
+    )]
+    pub status_check_interval_sec: Option<u64>,
+    #[arg(
+        long,
+        help = "The maximum number of messages of a checkpoint accepted by the parent, defaults to the limit of the contracts"
+    )]
+    pub max_checkpoint_msgs: Option<usize>,
+    #[arg(
+        long,
+        help = "The maximum size in bytes of the submission calldata accepted by the parent nodes"
+    )]
+    pub max_calldata_bytes: Option<usize>,
+    #[arg(
+        long,
+        help = "The number of child heights whose events and bundles are queried in one batch request, 1 to disable batching"
+    )]
+    pub rpc_batch_size: Option<usize>,
+    #[arg(
+        long,
+        help = "The maximum number of checkpoints fetched ahead of the one being submitted"
+    )]
+    pub prefetch_limit: Option<usize>,
+    #[arg(
+        long,
+        help = "The address to serve the prometheus metrics at, e.g. 0.0.0.0:9184"
+    )]
+    pub metrics_address: Option<SocketAddr>,
+    #[arg(
+        long,
+        help = "The number of consecutive failures after which calls to a subnet are short-circuited"
+    )]
+    pub breaker_failure_threshold: Option<u32>,
+    #[arg(
+        long,
+        help = "The number of seconds calls to a failing subnet are short-circuited for"
+    )]
+    pub breaker_cool_down_sec: Option<u64>,
+    #[arg(
+        long,
+        default_value = "submit",
+        value_parser = ["submit", "skip", "batch"],
+        help = "How to relay checkpoints without messages and validator changes"
+    )]
+    pub empty_checkpoints: String,
+    #[arg(
+        long,
+        help = "The maximum number of empty checkpoints held back with the batch policy"
+    )]
+    pub max_held_empty_checkpoints: Option<usize>,
+    #[arg(
+        long,
+        default_value = "high",
+        value_parser = ["low", "normal", "high"],
+        help = "The fee urgency of the checkpoints submitted on time"
+    )]
+    pub fee_urgency: String,
+    #[arg(
+        long,
+        value_parser = ["low", "normal", "high"],
+        help = "The fee urgency the overdue checkpoints are escalated up to, by default they are not escalated"
+    )]
+    pub max_fee_urgency: Option<String>,
+    #[arg(
+        long,
+        help = "The number of checkpoint periods behind after which the fee urgency is raised by one tier"
+    )]
+    pub fee_escalation_periods: Option<ChainEpoch>,
+    #[arg(
+        long,
+        help = "Refuse to relay the checkpoints with messages to this address, can be repeated"
+    )]
+    pub deny_recipient: Vec<String>,
+    #[arg(
+        long,
+        help = "Only relay the checkpoints whose messages go to these addresses, can be repeated"
+    )]
+    pub allow_recipient: Vec<String>,
+    #[arg(
+        long,
+        help = "Refuse to relay the checkpoints with a message above this value, in whole FIL"
+    )]
+    pub max_message_value: Option<f64>,
+    #[arg(
+        long,
+        help = "The url to notify of committed, refused or divergent checkpoints, can be repeated"
+    )]
+    pub webhook: Vec<Url>,
+    #[arg(
+        long,
+        env = "IPC_WEBHOOK_SECRET",
+        hide_env_values = true,
+        help = "The secret to sign the webhook payloads with"
+    )]
+    pub webhook_secret: Option<String>,
+    #[arg(
+        long,
+        help = "Record the time spent in every phase of the submissions, exposed as metrics and logged periodically"
+    )]
+    pub profile: bool,
+    #[arg(
+        long,
+        help = "The number of seconds between two summaries of the profiled timings"
+    )]
+    pub profile_summary_sec: Option<u64>,
 }
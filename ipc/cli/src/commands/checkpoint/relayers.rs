@@ -0,0 +1,108 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The management of the allowlist of the relayers of the subnets restricting the submission of
+//! their checkpoints.
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_api::subnet_id::SubnetID;
+
+use crate::commands::{get_ipc_provider, require_fil_addr_from_str};
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to list the relayers allowed to submit the checkpoints of a subnet.
+pub(crate) struct ListRelayers;
+
+#[async_trait]
+impl CommandLineHandler for ListRelayers {
+    type Arguments = ListRelayersArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("list relayers with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        match provider.relayer_allowlist(&subnet).await? {
+            None => println!("subnet {subnet} does not restrict its relayers"),
+            Some(relayers) if relayers.is_empty() => {
+                println!("no relayer is allowed to submit the checkpoints of {subnet}")
+            }
+            Some(relayers) => {
+                for relayer in relayers {
+                    println!("{relayer}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the relayers allowed to submit the checkpoints of a permissioned subnet")]
+pub(crate) struct ListRelayersArgs {
+    #[arg(long, help = "The subnet whose relayers to list")]
+    pub subnet: String,
+}
+
+/// The command to allow a relayer to submit the checkpoints of a subnet.
+pub(crate) struct AllowRelayer;
+
+#[async_trait]
+impl CommandLineHandler for AllowRelayer {
+    type Arguments = SetRelayerArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("allow relayer with args: {:?}", arguments);
+        set_relayer_allowed(global, arguments, true).await
+    }
+}
+
+/// The command to revoke the permission of a relayer to submit the checkpoints of a subnet.
+pub(crate) struct DisallowRelayer;
+
+#[async_trait]
+impl CommandLineHandler for DisallowRelayer {
+    type Arguments = SetRelayerArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("disallow relayer with args: {:?}", arguments);
+        set_relayer_allowed(global, arguments, false).await
+    }
+}
+
+async fn set_relayer_allowed(
+    global: &GlobalArguments,
+    arguments: &SetRelayerArgs,
+    allowed: bool,
+) -> anyhow::Result<()> {
+    let provider = get_ipc_provider(global).await?;
+    let subnet = SubnetID::from_str(&arguments.subnet)?;
+    let relayer = require_fil_addr_from_str(&arguments.relayer)?;
+    let from = require_fil_addr_from_str(&arguments.from)?;
+
+    let epoch = provider
+        .set_relayer_allowed(&from, &subnet, &relayer, allowed)
+        .await?;
+    let verb = if allowed { "allowed" } else { "disallowed" };
+    println!("relayer {relayer} {verb} for {subnet} at epoch {epoch}");
+
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SetRelayerArgs {
+    #[arg(
+        long,
+        help = "The owner of the subnet, signing and paying for this transaction"
+    )]
+    pub from: String,
+    #[arg(long, help = "The permissioned subnet")]
+    pub subnet: String,
+    #[arg(long, help = "The address of the relayer submitting the checkpoints")]
+    pub relayer: String,
+}
@@ -0,0 +1,69 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::schema;
+
+use crate::commands::{get_ipc_provider, require_fil_addr_from_str};
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to inspect the state of the bottom-up checkpoints of a subnet.
+pub(crate) struct CheckpointStatus;
+
+#[async_trait]
+impl CommandLineHandler for CheckpointStatus {
+    type Arguments = CheckpointStatusArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("checkpoint status with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let submitter = match &arguments.submitter {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+
+        let status = provider
+            .inspect_checkpoints(&subnet, submitter.as_ref(), arguments.scan_limit)
+            .await?;
+        if arguments.json {
+            println!("{}", schema::to_json_pretty(&status)?);
+        } else {
+            print!("{status}");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Show the state of the bottom up checkpoints of a child subnet, to debug its relayers"
+)]
+pub(crate) struct CheckpointStatusArgs {
+    #[arg(long, help = "The target subnet to perform query")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The address submitting the checkpoints, to show its balance and nonce"
+    )]
+    pub submitter: Option<String>,
+    #[arg(
+        long,
+        default_value = "1000",
+        help = "The maximum number of child heights scanned for quorum events"
+    )]
+    pub scan_limit: ChainEpoch,
+    #[arg(
+        long,
+        help = "Print the status as json, in the versioned `checkpoint_inspection` schema"
+    )]
+    pub json: bool,
+}
@@ -13,7 +13,7 @@ use crate::{CommandLineHandler, GlobalArguments};
 #[derive(Debug, Args)]
 #[command(name = "config", about = "config related commands")]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct ConfigCommandsArgs {
+pub struct ConfigCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
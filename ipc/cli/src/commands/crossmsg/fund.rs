@@ -7,12 +7,13 @@ use clap::Args;
 use fvm_shared::bigint::BigInt;
 use fvm_shared::econ::TokenAmount;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::recipient::Recipient;
 use num_traits::Num;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    apply_confirmation, f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to send funds to a subnet from parent
@@ -25,14 +26,17 @@ impl CommandLineHandler for Fund {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("fund operation with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
+        apply_confirmation(&mut provider, arguments.yes);
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
         let to = match &arguments.to {
-            Some(address) => Some(require_fil_addr_from_str(address)?),
+            Some(recipient) => {
+                Some(provider.resolve_recipient(&subnet, &Recipient::from_str(recipient)?)?)
+            }
             None => None,
         };
         let gateway_addr = match &arguments.gateway_address {
@@ -66,13 +70,18 @@ pub(crate) struct FundArgs {
     pub from: Option<String>,
     #[arg(
         long,
-        help = "The address to send funds to (if not set, amount sent to from address)"
+        help = "The address or address book name to send funds to (if not set, amount sent to from address)"
     )]
     pub to: Option<String>,
     #[arg(long, help = "The subnet to fund")]
     pub subnet: String,
     #[arg(help = "The amount to fund in FIL, in whole FIL")]
     pub amount: f64,
+    #[arg(
+        long,
+        help = "Confirm the operation even if it moves more than the confirmation threshold"
+    )]
+    pub yes: bool,
 }
 
 pub struct PreFund;
@@ -84,7 +93,7 @@ impl CommandLineHandler for PreFund {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("pre-fund subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -127,14 +136,17 @@ impl CommandLineHandler for FundWithToken {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("fund with token operation with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
+        apply_confirmation(&mut provider, arguments.yes);
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
         let to = match &arguments.to {
-            Some(address) => Some(require_fil_addr_from_str(address)?),
+            Some(recipient) => {
+                Some(provider.resolve_recipient(&subnet, &Recipient::from_str(recipient)?)?)
+            }
             None => None,
         };
 
@@ -158,11 +170,16 @@ pub(crate) struct FundWithTokenArgs {
     pub from: Option<String>,
     #[arg(
         long,
-        help = "The address to send funds to (if not set, amount sent to from address)"
+        help = "The address or address book name to send funds to (if not set, amount sent to from address)"
     )]
     pub to: Option<String>,
     #[arg(long, help = "The subnet to fund")]
     pub subnet: String,
     #[arg(help = "The amount to fund in erc20, in the token's precision unit")]
     pub amount: String,
+    #[arg(
+        long,
+        help = "Confirm the operation even if it moves more than the confirmation threshold"
+    )]
+    pub yes: bool,
 }
@@ -4,13 +4,14 @@ use self::fund::{FundWithToken, FundWithTokenArgs, PreFund, PreFundArgs};
 use self::release::{PreRelease, PreReleaseArgs};
 use self::topdown_cross::{
     LatestParentFinality, LatestParentFinalityArgs, ListTopdownMsgs, ListTopdownMsgsArgs,
+    ParentFinalityVotes, ParentFinalityVotesArgs,
 };
 use crate::commands::crossmsg::fund::Fund;
-use crate::commands::crossmsg::propagate::Propagate;
+use crate::commands::crossmsg::propagate::{Postbox, Propagate, StuckMsgs};
 use crate::commands::crossmsg::release::Release;
 use crate::{CommandLineHandler, GlobalArguments};
 use fund::FundArgs;
-use propagate::PropagateArgs;
+use propagate::{PostboxArgs, PropagateArgs, StuckMsgsArgs};
 use release::ReleaseArgs;
 
 use clap::{Args, Subcommand};
@@ -23,7 +24,7 @@ mod topdown_cross;
 #[derive(Debug, Args)]
 #[command(name = "crossmsg", about = "cross network messages related commands")]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct CrossMsgsCommandsArgs {
+pub struct CrossMsgsCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
@@ -37,8 +38,11 @@ impl CrossMsgsCommandsArgs {
             Commands::Release(args) => Release::handle(global, args).await,
             Commands::PreRelease(args) => PreRelease::handle(global, args).await,
             Commands::Propagate(args) => Propagate::handle(global, args).await,
+            Commands::StuckMsgs(args) => StuckMsgs::handle(global, args).await,
+            Commands::Postbox(args) => Postbox::handle(global, args).await,
             Commands::ListTopdownMsgs(args) => ListTopdownMsgs::handle(global, args).await,
             Commands::ParentFinality(args) => LatestParentFinality::handle(global, args).await,
+            Commands::ParentFinalityVotes(args) => ParentFinalityVotes::handle(global, args).await,
         }
     }
 }
@@ -51,6 +55,9 @@ pub(crate) enum Commands {
     Release(ReleaseArgs),
     PreRelease(PreReleaseArgs),
     Propagate(PropagateArgs),
+    StuckMsgs(StuckMsgsArgs),
+    Postbox(PostboxArgs),
     ListTopdownMsgs(ListTopdownMsgsArgs),
     ParentFinality(LatestParentFinalityArgs),
+    ParentFinalityVotes(ParentFinalityVotesArgs),
 }
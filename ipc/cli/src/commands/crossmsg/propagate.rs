@@ -2,11 +2,17 @@
 // SPDX-License-Identifier: MIT
 //! Propagate cli command handler.
 
+use anyhow::anyhow;
 use async_trait::async_trait;
 use clap::Args;
-use std::fmt::Debug;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use std::{fmt::Debug, str::FromStr, time::Duration};
 
-use crate::{CommandLineHandler, GlobalArguments};
+use crate::{
+    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
+    GlobalArguments,
+};
 
 /// The command to propagate a message in the postbox.
 pub(crate) struct Propagate;
@@ -15,8 +21,24 @@ pub(crate) struct Propagate;
 impl CommandLineHandler for Propagate {
     type Arguments = PropagateArgs;
 
-    async fn handle(_global: &GlobalArguments, _arguments: &Self::Arguments) -> anyhow::Result<()> {
-        todo!()
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("propagate operation with args: {:?}", arguments);
+
+        let mut provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let from = match &arguments.from {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+        let key = hex::decode(arguments.postbox_msg_key.trim_start_matches("0x"))
+            .map_err(|e| anyhow!("invalid postbox message key: {e}"))?;
+
+        let fee = f64_to_token_amount(arguments.fee)?;
+
+        provider.propagate(subnet, None, from, key, fee).await?;
+        println!("message propagated");
+
+        Ok(())
     }
 }
 
@@ -31,4 +53,177 @@ pub(crate) struct PropagateArgs {
     pub subnet: String,
     #[arg(help = "The message cid to propagate")]
     pub postbox_msg_key: String,
+    #[arg(
+        long,
+        default_value = "0",
+        help = "The propagation fee to pay, in whole FIL"
+    )]
+    pub fee: f64,
+}
+
+/// The command to list, and propagate, the messages of the committed checkpoints of a subnet
+/// stuck in the postbox of its parent.
+pub(crate) struct StuckMsgs;
+
+#[async_trait]
+impl CommandLineHandler for StuckMsgs {
+    type Arguments = StuckMsgsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("stuck messages with args: {:?}", arguments);
+
+        let mut provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let parent = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("root does not have parent"))?;
+        let from = match &arguments.from {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+        let fee = f64_to_token_amount(arguments.fee)?;
+
+        let stuck = provider
+            .stuck_cross_msgs(
+                &subnet,
+                arguments.from_epoch,
+                Duration::from_secs(arguments.timeout_sec),
+            )
+            .await?;
+        if stuck.is_empty() {
+            println!("no stuck messages");
+            return Ok(());
+        }
+
+        for msg in stuck {
+            println!("{msg}");
+            if !arguments.propagate {
+                continue;
+            }
+            match provider
+                .propagate(
+                    parent.clone(),
+                    None,
+                    from,
+                    msg.postbox_key.clone(),
+                    fee.clone(),
+                )
+                .await
+            {
+                Ok(()) => println!("  propagated"),
+                Err(e) => println!("  cannot propagate: {e}"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the cross-messages of a subnet stuck in the postbox of its parent")]
+pub(crate) struct StuckMsgsArgs {
+    #[arg(long, help = "The subnet whose checkpoints carried the messages")]
+    pub subnet: String,
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Include checkpoints from this epoch"
+    )]
+    pub from_epoch: ChainEpoch,
+    #[arg(
+        long,
+        default_value = "3600",
+        help = "Only include checkpoints at least this old, in seconds"
+    )]
+    pub timeout_sec: u64,
+    #[arg(long, help = "Propagate the stuck messages from the parent")]
+    pub propagate: bool,
+    #[arg(long, help = "The address that pays for the propagation gas")]
+    pub from: Option<String>,
+    #[arg(
+        long,
+        default_value = "0",
+        help = "The propagation fee to pay, in whole FIL"
+    )]
+    pub fee: f64,
+}
+
+/// The command to list, and propagate, the messages in the postbox of a subnet.
+pub(crate) struct Postbox;
+
+#[async_trait]
+impl CommandLineHandler for Postbox {
+    type Arguments = PostboxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("postbox with args: {:?}", arguments);
+
+        let mut provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let source = SubnetID::from_str(&arguments.source)?;
+        let to_epoch = match arguments.to_epoch {
+            Some(epoch) => epoch,
+            None => provider.get_chain_head_height(&source).await?,
+        };
+
+        let msgs = provider
+            .postbox_msgs(&subnet, &source, arguments.from_epoch, to_epoch)
+            .await?;
+        if msgs.is_empty() {
+            println!("no messages in the postbox");
+            return Ok(());
+        }
+        for msg in &msgs {
+            println!("{msg}");
+        }
+        if !arguments.propagate {
+            return Ok(());
+        }
+
+        let from = match &arguments.from {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+        let keys = msgs.into_iter().map(|msg| msg.key).collect();
+        let outcomes = provider
+            .propagate_postbox_msgs(&subnet, from, keys, f64_to_token_amount(arguments.fee)?)
+            .await;
+        for (key, outcome) in outcomes {
+            match outcome {
+                Ok(()) => println!("propagated 0x{}", hex::encode(key)),
+                Err(e) => println!("cannot propagate 0x{}: {e}", hex::encode(key)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the messages in the postbox of a subnet, received from a neighbour")]
+pub(crate) struct PostboxArgs {
+    #[arg(long, help = "The subnet whose gateway holds the postbox")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The parent or child subnet the messages were received from"
+    )]
+    pub source: String,
+    #[arg(long, help = "Include messages from this epoch of the source subnet")]
+    pub from_epoch: ChainEpoch,
+    #[arg(
+        long,
+        help = "Include messages up to this epoch of the source subnet, its head if not set"
+    )]
+    pub to_epoch: Option<ChainEpoch>,
+    #[arg(long, help = "Propagate the messages found")]
+    pub propagate: bool,
+    #[arg(long, help = "The address that pays for the propagation gas")]
+    pub from: Option<String>,
+    #[arg(
+        long,
+        default_value = "0",
+        help = "The propagation fee to pay, in whole FIL"
+    )]
+    pub fee: f64,
 }
@@ -5,11 +5,12 @@
 use async_trait::async_trait;
 use clap::Args;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::recipient::Recipient;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    apply_confirmation, f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to release funds from a child to a parent
@@ -22,14 +23,21 @@ impl CommandLineHandler for Release {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("release operation with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
+        apply_confirmation(&mut provider, arguments.yes);
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
+        // the released funds are received in the parent
+        let parent = subnet
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("no parent found"))?;
         let to = match &arguments.to {
-            Some(address) => Some(require_fil_addr_from_str(address)?),
+            Some(recipient) => {
+                Some(provider.resolve_recipient(&parent, &Recipient::from_str(recipient)?)?)
+            }
             None => None,
         };
         let gateway_addr = match &arguments.gateway_address {
@@ -63,13 +71,18 @@ pub(crate) struct ReleaseArgs {
     pub from: Option<String>,
     #[arg(
         long,
-        help = "The address to release funds to (if not set, amount sent to from address)"
+        help = "The address or address book name to release funds to (if not set, amount sent to from address)"
     )]
     pub to: Option<String>,
     #[arg(long, help = "The subnet to release funds from")]
     pub subnet: String,
     #[arg(help = "The amount to release in FIL, in whole FIL")]
     pub amount: f64,
+    #[arg(
+        long,
+        help = "Confirm the operation even if it moves more than the confirmation threshold"
+    )]
+    pub yes: bool,
 }
 
 pub struct PreRelease;
@@ -81,7 +94,7 @@ impl CommandLineHandler for PreRelease {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("pre-release subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -9,9 +9,13 @@ use async_trait::async_trait;
 use clap::Args;
 use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::finality_votes::query_finality_votes;
+use ipc_provider::jsonrpc::JsonRpcClientImpl;
+use ipc_provider::pagination::{HeightRange, PageRequest};
+use url::Url;
 
 use crate::commands::get_ipc_provider;
-use crate::{CommandLineHandler, GlobalArguments};
+use crate::{require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
 
 /// The command to list top down cross messages in a subnet
 pub(crate) struct ListTopdownMsgs;
@@ -23,26 +27,33 @@ impl CommandLineHandler for ListTopdownMsgs {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("list topdown messages with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
-        for h in arguments.from..=arguments.to {
-            let result = provider.get_top_down_msgs(&subnet, h).await?;
+        let address = match &arguments.address {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+
+        let heights = HeightRange::new(arguments.from, arguments.to);
+        let page = PageRequest::new(arguments.cursor.clone(), arguments.limit);
+        let page = provider
+            .list_top_down_msgs(&subnet, heights, address, &page)
+            .await?;
+
+        for (h, msg) in page.items {
             println!(
-                "block height: {}, block hash: {}, number of messages: {}",
+                "block height: {}, from: {}, to: {}, message: {}, nonce: {} ",
                 h,
-                hex::encode(result.block_hash),
-                result.value.len()
+                msg.from.to_string()?,
+                msg.to.to_string()?,
+                hex::encode(msg.message),
+                msg.nonce
             );
-            for msg in result.value {
-                println!(
-                    "from: {}, to: {}, message: {}, nonce: {} ",
-                    msg.from.to_string()?,
-                    msg.to.to_string()?,
-                    hex::encode(msg.message),
-                    msg.nonce
-                );
-            }
+        }
+
+        if let Some(cursor) = page.next_cursor {
+            println!("next cursor: {cursor}");
         }
 
         Ok(())
@@ -56,8 +67,20 @@ pub(crate) struct ListTopdownMsgsArgs {
     pub subnet: String,
     #[arg(long, help = "Include topdown messages starting from this epoch")]
     pub from: ChainEpoch,
-    #[arg(long, help = "Include topdown messages to this epoch")]
-    pub to: ChainEpoch,
+    #[arg(
+        long,
+        help = "Include topdown messages to this epoch, up to the chain head if not set"
+    )]
+    pub to: Option<ChainEpoch>,
+    #[arg(long, help = "Only include the messages from or to this address")]
+    pub address: Option<String>,
+    #[arg(long, help = "The maximum number of items to list")]
+    pub limit: Option<usize>,
+    #[arg(
+        long,
+        help = "List the items after this cursor, printed with the previous page"
+    )]
+    pub cursor: Option<String>,
 }
 
 pub(crate) struct LatestParentFinality;
@@ -69,7 +92,7 @@ impl CommandLineHandler for LatestParentFinality {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("latest parent finality: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         println!("{}", provider.latest_parent_finality(&subnet).await?);
@@ -83,3 +106,60 @@ pub(crate) struct LatestParentFinalityArgs {
     #[arg(long, help = "The subnet id to check parent finality")]
     pub subnet: String,
 }
+
+/// The command to show the votes on the parent finality tallied by a child node.
+pub(crate) struct ParentFinalityVotes;
+
+#[async_trait]
+impl CommandLineHandler for ParentFinalityVotes {
+    type Arguments = ParentFinalityVotesArgs;
+
+    async fn handle(_global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("parent finality votes: {:?}", arguments);
+
+        let client = JsonRpcClientImpl::new(arguments.node.clone(), None);
+        let status = query_finality_votes(&client).await?;
+        let progress = status.quorum_progress();
+
+        println!(
+            "last finalized height: {}, latest final height: {}",
+            status.last_finalized_height, status.latest_height
+        );
+        match progress.quorum_height {
+            Some(h) => println!("quorum reached at height {h}"),
+            None => println!(
+                "no quorum: {} of the {} power required, out of {}",
+                progress.weight,
+                progress.threshold,
+                status.total_power()
+            ),
+        }
+        for validator in &progress.missing {
+            println!("no vote from {validator}");
+        }
+        for height in &status.heights {
+            for block in &height.blocks {
+                let agrees = height.block_hash.as_ref() == Some(&block.block_hash);
+                println!(
+                    "height {}, block {}{}: weight {}, voters: {}",
+                    height.height,
+                    block.block_hash,
+                    if agrees { "" } else { " (not final here)" },
+                    block.weight,
+                    block.voters.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Show the votes on the parent finality tallied by a child node")]
+pub(crate) struct ParentFinalityVotesArgs {
+    #[arg(
+        long,
+        help = "The CometBFT RPC url of the child node, e.g. http://localhost:26657"
+    )]
+    pub node: Url,
+}
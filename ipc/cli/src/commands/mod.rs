@@ -1,6 +1,9 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 //! This mod contains the different command line implementations.
+//!
+//! The subcommands are also exposed as `clap` definitions with their handlers, so that other
+//! binaries can embed them, see [`command`] and [`run_matches`].
 
 mod checkpoint;
 mod config;
@@ -10,13 +13,10 @@ mod subnet;
 mod util;
 mod wallet;
 
-use crate::commands::checkpoint::CheckpointCommandsArgs;
-use crate::commands::crossmsg::CrossMsgsCommandsArgs;
-use crate::commands::util::UtilCommandsArgs;
 use crate::GlobalArguments;
 use anyhow::{anyhow, Context, Result};
 
-use clap::{Command, CommandFactory, Parser, Subcommand};
+use clap::{ArgMatches, Args, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
 use clap_complete::{generate, Generator, Shell};
 use fvm_shared::econ::TokenAmount;
 use ipc_api::ethers_address_to_fil_address;
@@ -24,22 +24,26 @@ use ipc_api::ethers_address_to_fil_address;
 use fvm_shared::address::set_current_network;
 use ipc_api::subnet_id::SubnetID;
 use ipc_provider::config::{Config, Subnet};
+use ipc_provider::confirmation::ValueOperation;
 use std::fmt::Debug;
-use std::io;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 use std::str::FromStr;
 
-use crate::commands::config::ConfigCommandsArgs;
-use crate::commands::wallet::WalletCommandsArgs;
-use subnet::SubnetCommandsArgs;
+pub use checkpoint::CheckpointCommandsArgs;
+pub use config::ConfigCommandsArgs;
+pub use crossmsg::CrossMsgsCommandsArgs;
+pub use subnet::SubnetCommandsArgs;
+pub use util::UtilCommandsArgs;
+pub use wallet::WalletCommandsArgs;
 
 /// We only support up to 9 decimal digits for transaction
 const FIL_AMOUNT_NANO_DIGITS: u32 = 9;
 
-/// The collection of all subcommands to be called, see clap's documentation for usage. Internal
-/// to the current mode. Register a new command accordingly.
+/// The collection of all subcommands to be called, see clap's documentation for usage.
+/// Register a new command accordingly.
 #[derive(Debug, Subcommand)]
-enum Commands {
+pub enum Commands {
     // Daemon(LaunchDaemonArgs),
     Config(ConfigCommandsArgs),
     Subnet(SubnetCommandsArgs),
@@ -49,6 +53,20 @@ enum Commands {
     Util(UtilCommandsArgs),
 }
 
+impl Commands {
+    pub async fn handle(&self, global: &GlobalArguments) -> Result<()> {
+        match self {
+            // Commands::Daemon(args) => LaunchDaemon::handle(global, args).await,
+            Commands::Config(args) => args.handle(global).await,
+            Commands::Subnet(args) => args.handle(global).await,
+            Commands::CrossMsg(args) => args.handle(global).await,
+            Commands::Wallet(args) => args.handle(global).await,
+            Commands::Checkpoint(args) => args.handle(global).await,
+            Commands::Util(args) => args.handle(global).await,
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(
     name = "ipc-agent",
@@ -83,6 +101,27 @@ struct GlobalOptions {
     pub cmd: Vec<String>,
 }
 
+/// Initializes the logger at the `info` level, with the levels of the components from the
+/// `log_levels` of the config file if it can be read. The filters of `RUST_LOG` are applied
+/// last, a component is only overridden by a filter on its own targets though.
+pub fn init_logger() {
+    let levels = GlobalOptions::try_parse()
+        .ok()
+        .and_then(|g| {
+            // the addresses of the config are parsed for the network
+            set_current_network(g.global_params.network());
+            g.global_params.config().ok()
+        })
+        .map(|c| c.log_levels)
+        .unwrap_or_default();
+
+    env_logger::Builder::new()
+        .parse_filters("info")
+        .parse_filters(&levels.directives())
+        .parse_env(env_logger::Env::default())
+        .init();
+}
+
 /// The `cli` method exposed to handle all the cli commands, ideally from main.
 ///
 /// # Examples
@@ -125,29 +164,85 @@ pub async fn cli() -> anyhow::Result<()> {
     } else {
         let global = &args.global_params;
         if let Some(c) = &args.command {
-            let r = match &c {
-                // Commands::Daemon(args) => LaunchDaemon::handle(global, args).await,
-                Commands::Config(args) => args.handle(global).await,
-                Commands::Subnet(args) => args.handle(global).await,
-                Commands::CrossMsg(args) => args.handle(global).await,
-                Commands::Wallet(args) => args.handle(global).await,
-                Commands::Checkpoint(args) => args.handle(global).await,
-                Commands::Util(args) => args.handle(global).await,
-            };
-
-            r.with_context(|| format!("error processing command {:?}", args.command))
+            c.handle(global)
+                .await
+                .with_context(|| format!("error processing command {:?}", args.command))
         } else {
             Ok(())
         }
     }
 }
 
+/// The definition of the ipc command line, with the global arguments and all the subcommands,
+/// to embed it as a subcommand of another binary, e.g.
+/// ```ignore
+/// let matches = Command::new("node")
+///     .subcommand(ipc_cli::command().name("ipc"))
+///     .get_matches();
+/// if let Some(("ipc", matches)) = matches.subcommand() {
+///     ipc_cli::run_matches(matches).await?;
+/// }
+/// ```
+pub fn command() -> Command {
+    Commands::augment_subcommands(GlobalArguments::augment_args(Command::new("ipc")))
+        .about("The IPC agent command line tool")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+}
+
+/// Runs the subcommand matched by the [`command`] definition, with its global arguments.
+pub async fn run_matches(matches: &ArgMatches) -> Result<()> {
+    let global = GlobalArguments::from_arg_matches(matches)?;
+    set_current_network(global.network());
+
+    let command = Commands::from_arg_matches(matches)?;
+    command
+        .handle(&global)
+        .await
+        .with_context(|| format!("error processing command {command:?}"))
+}
+
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut io::stdout());
 }
 
-pub(crate) fn get_ipc_provider(global: &GlobalArguments) -> Result<ipc_provider::IpcProvider> {
-    ipc_provider::IpcProvider::new_from_config(global.config_path())
+/// The provider of the config, signing for the subnets assigned to a named keystore with it.
+pub(crate) async fn get_ipc_provider(
+    global: &GlobalArguments,
+) -> Result<ipc_provider::IpcProvider> {
+    ipc_provider::IpcProvider::new_from_config(global.config_path())?
+        .with_keystores()
+        .await
+}
+
+/// Confirms the operations moving more than the confirmation threshold of the config, upfront
+/// with `yes` or else by asking on the terminal.
+pub(crate) fn apply_confirmation(provider: &mut ipc_provider::IpcProvider, yes: bool) {
+    if let Some(policy) = provider.confirmation_policy().cloned() {
+        let policy = if yes {
+            policy.confirmed()
+        } else {
+            policy.with_callback(prompt_confirmation)
+        };
+        provider.set_confirmation_policy(Some(policy));
+    }
+}
+
+/// Asks on the terminal whether `op` can go ahead. Without a terminal, e.g. in a script, the
+/// operation is not confirmed.
+fn prompt_confirmation(op: &ValueOperation) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{op} is above the confirmation threshold, proceed? [y/N] ");
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
 pub(crate) fn f64_to_token_amount(f: f64) -> anyhow::Result<TokenAmount> {
@@ -176,16 +271,15 @@ pub(crate) fn get_subnet_config(
     subnet: &SubnetID,
 ) -> Result<Subnet> {
     let config = Config::from_file(&config_path)?;
-    Ok(config
-        .subnets
-        .get(subnet)
-        .ok_or_else(|| anyhow!("{subnet} is not configured"))?
-        .clone())
+    config
+        .subnet(subnet)
+        .ok_or_else(|| anyhow!("{subnet} is not configured"))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::f64_to_token_amount;
+    use crate::{command, f64_to_token_amount, Commands, GlobalArguments};
+    use clap::{Command, FromArgMatches};
     use fvm_shared::econ::TokenAmount;
 
     #[test]
@@ -193,4 +287,29 @@ mod tests {
         let amount = f64_to_token_amount(1000000.1f64).unwrap();
         assert_eq!(amount, TokenAmount::from_nano(1000000100000000u128));
     }
+
+    #[test]
+    fn test_embedded_command() {
+        let matches = Command::new("node")
+            .subcommand(command().name("ipc"))
+            .try_get_matches_from([
+                "node",
+                "ipc",
+                "--config-path",
+                "/tmp/config.toml",
+                "wallet",
+                "list",
+                "--wallet-type",
+                "evm",
+            ])
+            .unwrap();
+        let Some(("ipc", matches)) = matches.subcommand() else {
+            panic!("ipc subcommand not matched");
+        };
+
+        let global = GlobalArguments::from_arg_matches(matches).unwrap();
+        assert_eq!(global.config_path(), "/tmp/config.toml");
+        let command = Commands::from_arg_matches(matches).unwrap();
+        assert!(matches!(command, Commands::Wallet(_)));
+    }
 }
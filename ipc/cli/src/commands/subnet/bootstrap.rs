@@ -5,7 +5,9 @@
 use async_trait::async_trait;
 use clap::Args;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::schema;
 use std::{fmt::Debug, str::FromStr};
+use url::Url;
 
 use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
 
@@ -19,7 +21,7 @@ impl CommandLineHandler for AddBootstrap {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("add subnet bootstrap with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -53,7 +55,7 @@ impl CommandLineHandler for ListBootstraps {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("add subnet bootstrap with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         print!(
@@ -77,3 +79,43 @@ pub struct ListBootstrapsArgs {
     #[arg(long, help = "The subnet to list bootstraps from")]
     pub subnet: String,
 }
+
+/// The command to show how a new validator bootstraps its node in a subnet
+pub struct BootstrapInfo;
+
+#[async_trait]
+impl CommandLineHandler for BootstrapInfo {
+    type Arguments = BootstrapInfoArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("subnet bootstrap info with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        let info = provider.bootstrap_info(&subnet, &arguments.node).await?;
+        if arguments.json {
+            println!("{}", schema::to_json_pretty(&info)?);
+        } else {
+            print!("{info}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "bootstrap-info",
+    about = "Show the genesis, bootstrap nodes and snapshots a new validator bootstraps from"
+)]
+pub struct BootstrapInfoArgs {
+    #[arg(long, help = "The subnet to bootstrap a node in")]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The CometBFT RPC url of a child node offering snapshots, e.g. http://localhost:26657"
+    )]
+    pub node: Url,
+    #[arg(long, help = "Print the information as JSON")]
+    pub json: bool,
+}
@@ -25,7 +25,7 @@ impl CreateSubnet {
         global: &GlobalArguments,
         arguments: &CreateSubnetArgs,
     ) -> anyhow::Result<String> {
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let parent = SubnetID::from_str(&arguments.parent)?;
 
         let from = match &arguments.from {
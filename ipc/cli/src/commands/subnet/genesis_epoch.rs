@@ -20,7 +20,7 @@ impl CommandLineHandler for GenesisEpoch {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get genesis epoch with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         let ls = provider.genesis_epoch(&subnet).await?;
@@ -9,8 +9,8 @@ use num_traits::Zero;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    apply_confirmation, f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to join a subnet
@@ -23,7 +23,8 @@ impl CommandLineHandler for JoinSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("join subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
+        apply_confirmation(&mut provider, arguments.yes);
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -69,6 +70,11 @@ pub struct JoinSubnetArgs {
         help = "Optionally add an initial balance to the validator in genesis in the subnet"
     )]
     pub initial_balance: Option<f64>,
+    #[arg(
+        long,
+        help = "Confirm the operation even if it moves more than the confirmation threshold"
+    )]
+    pub yes: bool,
 }
 
 /// The command to stake in a subnet from validator
@@ -81,7 +87,7 @@ impl CommandLineHandler for StakeSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("join subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -117,7 +123,7 @@ impl CommandLineHandler for UnstakeSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("join subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
@@ -4,7 +4,9 @@
 
 use async_trait::async_trait;
 use clap::Args;
+use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::pagination::PageRequest;
 use std::fmt::Debug;
 use std::str::FromStr;
 
@@ -20,7 +22,7 @@ impl CommandLineHandler for ListSubnets {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("list subnets with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.parent)?;
 
         let gateway_addr = match &arguments.gateway_address {
@@ -28,14 +30,20 @@ impl CommandLineHandler for ListSubnets {
             None => None,
         };
 
-        let ls = provider.list_child_subnets(gateway_addr, &subnet).await?;
+        let page = PageRequest::new(arguments.cursor.clone(), arguments.limit);
+        let page = provider
+            .list_child_subnets_page_at(gateway_addr, &subnet, arguments.at_height, &page)
+            .await?;
 
-        for (_, s) in ls.iter() {
+        for s in page.items.iter() {
             println!(
                 "{} - collateral: {} FIL, circ.supply: {} FIL, genesis: {}",
                 s.id, s.stake, s.circ_supply, s.genesis_epoch
             );
         }
+        if let Some(cursor) = page.next_cursor {
+            println!("next cursor: {cursor}");
+        }
 
         Ok(())
     }
@@ -51,4 +59,16 @@ pub(crate) struct ListSubnetsArgs {
     pub gateway_address: Option<String>,
     #[arg(long, help = "The network id to query child subnets")]
     pub parent: String,
+    #[arg(long, help = "The maximum number of items to list")]
+    pub limit: Option<usize>,
+    #[arg(
+        long,
+        help = "List the items after this cursor, printed with the previous page"
+    )]
+    pub cursor: Option<String>,
+    #[arg(
+        long,
+        help = "The height of the network to read the state at instead of its head, requires an archive node"
+    )]
+    pub at_height: Option<ChainEpoch>,
 }
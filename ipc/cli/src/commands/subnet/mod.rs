@@ -17,7 +17,10 @@ use crate::commands::subnet::validator::{ValidatorInfo, ValidatorInfoArgs};
 use crate::{CommandLineHandler, GlobalArguments};
 use clap::{Args, Subcommand};
 
-use self::bootstrap::{AddBootstrap, AddBootstrapArgs, ListBootstraps, ListBootstrapsArgs};
+use self::bootstrap::{
+    AddBootstrap, AddBootstrapArgs, BootstrapInfo, BootstrapInfoArgs, ListBootstraps,
+    ListBootstrapsArgs,
+};
 use self::join::{StakeSubnet, StakeSubnetArgs, UnstakeSubnet, UnstakeSubnetArgs};
 use self::leave::{Claim, ClaimArgs};
 use self::rpc::{ChainIdSubnet, ChainIdSubnetArgs};
@@ -41,7 +44,7 @@ mod validator;
     about = "subnet related commands such as create, join and etc"
 )]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct SubnetCommandsArgs {
+pub struct SubnetCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
@@ -62,6 +65,7 @@ impl SubnetCommandsArgs {
             Commands::Claim(args) => Claim::handle(global, args).await,
             Commands::AddBootstrap(args) => AddBootstrap::handle(global, args).await,
             Commands::ListBootstraps(args) => ListBootstraps::handle(global, args).await,
+            Commands::BootstrapInfo(args) => BootstrapInfo::handle(global, args).await,
             Commands::GenesisEpoch(args) => GenesisEpoch::handle(global, args).await,
             Commands::GetValidator(args) => ValidatorInfo::handle(global, args).await,
             Commands::ShowGatewayContractCommitSha(args) => {
@@ -87,6 +91,7 @@ pub(crate) enum Commands {
     Claim(ClaimArgs),
     AddBootstrap(AddBootstrapArgs),
     ListBootstraps(ListBootstrapsArgs),
+    BootstrapInfo(BootstrapInfoArgs),
     GenesisEpoch(GenesisEpochArgs),
     GetValidator(ValidatorInfoArgs),
     ShowGatewayContractCommitSha(ShowGatewayContractCommitShaArgs),
@@ -20,7 +20,7 @@ impl CommandLineHandler for RPCSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get rpc for subnet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.network)?;
         let conn = match provider.connection(&subnet) {
             None => return Err(anyhow::anyhow!("target subnet not found")),
@@ -50,7 +50,7 @@ impl CommandLineHandler for ChainIdSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get chain-id for subnet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.network)?;
         let conn = match provider.connection(&subnet) {
             None => return Err(anyhow::anyhow!("target subnet not found")),
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use clap::Args;
 use ipc_api::subnet_id::SubnetID;
+use ipc_provider::recipient::Recipient;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
@@ -21,20 +22,17 @@ impl CommandLineHandler for SendValue {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("send value in subnet with args: {:?}", arguments);
 
-        let mut provider = get_ipc_provider(global)?;
+        let mut provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let from = match &arguments.from {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
 
+        let to = provider.resolve_recipient(&subnet, &Recipient::from_str(&arguments.to)?)?;
+
         provider
-            .send_value(
-                &subnet,
-                from,
-                require_fil_addr_from_str(&arguments.to)?,
-                f64_to_token_amount(arguments.amount)?,
-            )
+            .send_value(&subnet, from, to, f64_to_token_amount(arguments.amount)?)
             .await
     }
 }
@@ -44,7 +42,7 @@ impl CommandLineHandler for SendValue {
 pub(crate) struct SendValueArgs {
     #[arg(long, help = "The address to send value from")]
     pub from: Option<String>,
-    #[arg(long, help = "The address to send value to")]
+    #[arg(long, help = "The address or address book name to send value to")]
     pub to: String,
     #[arg(long, help = "The subnet of the addresses")]
     pub subnet: String,
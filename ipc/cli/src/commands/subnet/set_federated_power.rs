@@ -20,7 +20,7 @@ impl CommandLineHandler for crate::commands::subnet::SetFederatedPower {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("set federated power with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
         let addresses: Vec<Address> = arguments
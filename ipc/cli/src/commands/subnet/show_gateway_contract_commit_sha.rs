@@ -19,7 +19,7 @@ impl CommandLineHandler for ShowGatewayContractCommitSha {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("show contract commit sha with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.network)?;
 
         let commit_sha = provider.get_commit_sha(&subnet).await?;
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use clap::Args;
 use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
 use std::fmt::Debug;
 use std::str::FromStr;
@@ -21,11 +22,13 @@ impl CommandLineHandler for ValidatorInfo {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("get validator info with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
         let validator = Address::from_str(&arguments.validator)?;
 
-        let validator_info = provider.get_validator_info(&subnet, &validator).await?;
+        let validator_info = provider
+            .get_validator_info_at(&subnet, &validator, arguments.at_height)
+            .await?;
         println!("{}", validator_info);
 
         Ok(())
@@ -39,4 +42,9 @@ pub(crate) struct ValidatorInfoArgs {
     pub subnet: String,
     #[arg(long, help = "The validator address")]
     pub validator: String,
+    #[arg(
+        long,
+        help = "The height of the parent to read the state at instead of its head, requires an archive node"
+    )]
+    pub at_height: Option<ChainEpoch>,
 }
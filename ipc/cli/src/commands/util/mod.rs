@@ -11,7 +11,7 @@ mod f4;
 #[derive(Debug, Args)]
 #[command(name = "util", about = "util commands")]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct UtilCommandsArgs {
+pub struct UtilCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
@@ -0,0 +1,118 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet address book cli handlers
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::recipient::parse_address;
+use ipc_wallet::AddressBookEntry;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+pub(crate) struct AddressBookAdd;
+
+#[async_trait]
+impl CommandLineHandler for AddressBookAdd {
+    type Arguments = AddressBookAddArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("add address book entry with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        parse_address(&arguments.address)?;
+        let subnet = match &arguments.subnet {
+            Some(subnet) => Some(SubnetID::from_str(subnet)?.to_string()),
+            None => None,
+        };
+
+        provider
+            .address_book()?
+            .write()
+            .unwrap()
+            .insert(AddressBookEntry {
+                name: arguments.name.clone(),
+                address: arguments.address.clone(),
+                subnet,
+            })
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Add a named recipient to the address book")]
+pub(crate) struct AddressBookAddArgs {
+    #[arg(long, help = "The name of the recipient, e.g. treasury")]
+    pub name: String,
+    #[arg(long, help = "The address of the recipient")]
+    pub address: String,
+    #[arg(
+        long,
+        help = "The subnet the name is scoped to (if not set, the name is used in all subnets)"
+    )]
+    pub subnet: Option<String>,
+}
+
+pub(crate) struct AddressBookRemove;
+
+#[async_trait]
+impl CommandLineHandler for AddressBookRemove {
+    type Arguments = AddressBookRemoveArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("remove address book entry with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let subnet = match &arguments.subnet {
+            Some(subnet) => Some(SubnetID::from_str(subnet)?.to_string()),
+            None => None,
+        };
+
+        let book = provider.address_book()?;
+        if !book
+            .write()
+            .unwrap()
+            .remove(&arguments.name, subnet.as_deref())?
+        {
+            return Err(anyhow::anyhow!(
+                "{} not found in the address book",
+                arguments.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Remove a named recipient from the address book")]
+pub(crate) struct AddressBookRemoveArgs {
+    #[arg(long, help = "The name of the recipient")]
+    pub name: String,
+    #[arg(long, help = "The subnet the name is scoped to, if any")]
+    pub subnet: Option<String>,
+}
+
+pub(crate) struct AddressBookList;
+
+#[async_trait]
+impl CommandLineHandler for AddressBookList {
+    type Arguments = AddressBookListArgs;
+
+    async fn handle(global: &GlobalArguments, _arguments: &Self::Arguments) -> anyhow::Result<()> {
+        let provider = get_ipc_provider(global).await?;
+        let book = provider.address_book()?;
+        for entry in book.read().unwrap().entries() {
+            print!("Name: {}\tAddress: {}", entry.name, entry.address);
+            match &entry.subnet {
+                Some(subnet) => println!("\tSubnet: {subnet}"),
+                None => println!(),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the named recipients of the address book")]
+pub(crate) struct AddressBookListArgs {}
@@ -0,0 +1,87 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet token approvals cli handlers
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::schema;
+use std::{fmt::Debug, str::FromStr};
+
+use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
+
+pub(crate) struct WalletApprovals;
+
+#[async_trait]
+impl CommandLineHandler for WalletApprovals {
+    type Arguments = WalletApprovalsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("wallet approvals with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let owner = require_fil_addr_from_str(&arguments.owner)?;
+
+        let approvals = provider.list_approvals(&owner).await?;
+        if arguments.json {
+            println!("{}", schema::to_json_pretty(&approvals)?);
+        } else {
+            print!("{approvals}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "List the allowances over the supply tokens granted to the IPC contracts")]
+pub(crate) struct WalletApprovalsArgs {
+    #[arg(long, help = "The address that granted the allowances")]
+    pub owner: String,
+    #[arg(long, help = "Print the approvals as JSON")]
+    pub json: bool,
+}
+
+pub(crate) struct WalletRevokeApproval;
+
+#[async_trait]
+impl CommandLineHandler for WalletRevokeApproval {
+    type Arguments = WalletRevokeApprovalArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("wallet revoke approval with args: {:?}", arguments);
+
+        let mut provider = get_ipc_provider(global).await?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let from = match &arguments.from {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+        let token = require_fil_addr_from_str(&arguments.token)?;
+        let spender = require_fil_addr_from_str(&arguments.spender)?;
+
+        let epoch = provider
+            .revoke_approval(&subnet, from, &token, &spender)
+            .await?;
+        println!("approval revoked in epoch: {epoch}");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Revoke the allowance over an ERC20 token granted to an IPC contract")]
+pub(crate) struct WalletRevokeApprovalArgs {
+    #[arg(
+        long,
+        help = "The subnet the token and the IPC contract are deployed in"
+    )]
+    pub subnet: String,
+    #[arg(long, help = "The address that granted the allowance")]
+    pub from: Option<String>,
+    #[arg(long, help = "The address of the ERC20 token")]
+    pub token: String,
+    #[arg(
+        long,
+        help = "The address of the gateway or registry allowed to take the tokens"
+    )]
+    pub spender: String,
+}
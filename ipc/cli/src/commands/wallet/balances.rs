@@ -2,13 +2,14 @@
 // SPDX-License-Identifier: MIT
 //! Wallet balances cli handler
 
+use anyhow::Context;
 use async_trait::async_trait;
 use clap::Args;
 use futures_util::future::join_all;
-use fvm_shared::{address::Address, econ::TokenAmount};
+use fvm_shared::{address::Address, clock::ChainEpoch, econ::TokenAmount};
 use ipc_api::ethers_address_to_fil_address;
 use ipc_api::subnet_id::SubnetID;
-use ipc_wallet::{EthKeyAddress, EvmKeyStore, WalletType};
+use ipc_wallet::{EvmKeyStore, WalletType};
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
@@ -22,56 +23,33 @@ impl CommandLineHandler for WalletBalances {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("list wallets with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
-        let mut errors = Vec::new();
 
         match wallet_type {
             WalletType::Evm => {
                 let wallet = provider.evm_wallet()?;
-                let addresses = wallet.read().unwrap().list()?;
-                let r = addresses
-                    .iter()
-                    .map(|addr| {
-                        let provider = provider.clone();
-                        let subnet = subnet.clone();
-                        async move {
-                            provider
-                                .wallet_balance(
-                                    &subnet,
-                                    &ethers_address_to_fil_address(&(addr.clone()).into())?,
-                                )
-                                .await
-                                .map(|balance| (balance, addr))
-                        }
-                    })
+                let addresses = wallet
+                    .read()
+                    .unwrap()
+                    .list()?
+                    .into_iter()
+                    .filter(|addr| addr.to_string() != "default-key")
                     .collect::<Vec<_>>();
+                let fil_addresses = addresses
+                    .iter()
+                    .map(|addr| ethers_address_to_fil_address(&(addr.clone()).into()))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
 
-                let v: Vec<anyhow::Result<(TokenAmount, &EthKeyAddress)>> = join_all(r).await;
-
-                for r in v.into_iter() {
-                    match r {
-                        Ok(i) => {
-                            let (balance, addr) = i;
-                            if addr.to_string() != "default-key" {
-                                println!("{} - Balance: {}", addr.to_string(), balance);
-                            }
-                        }
-                        Err(e) => {
-                            errors.push(e);
-                        }
-                    }
-                }
-
-                if !errors.is_empty() {
-                    let error = errors
-                        .into_iter()
-                        .fold(anyhow::anyhow!("Error fetching balances"), |acc, err| {
-                            acc.context(err)
-                        });
-                    return Err(error);
+                // read at once, in a single call if the subnet supports it
+                let balances = provider
+                    .wallet_balances_at(&subnet, &fil_addresses, arguments.at_height)
+                    .await
+                    .context("Error fetching balances")?;
+                for (addr, balance) in addresses.iter().zip(balances) {
+                    println!("{} - Balance: {}", addr.to_string(), balance);
                 }
             }
             WalletType::Fvm => {
@@ -82,9 +60,10 @@ impl CommandLineHandler for WalletBalances {
                     .map(|addr| {
                         let provider = provider.clone();
                         let subnet = subnet.clone();
+                        let at_height = arguments.at_height;
                         async move {
                             provider
-                                .wallet_balance(&subnet, addr)
+                                .wallet_balance_at(&subnet, addr, at_height)
                                 .await
                                 .map(|balance| (balance, addr))
                         }
@@ -112,4 +91,9 @@ pub(crate) struct WalletBalancesArgs {
     pub subnet: String,
     #[arg(long, help = "The type of the wallet, i.e. fvm, evm")]
     pub wallet_type: String,
+    #[arg(
+        long,
+        help = "The height of the subnet to read the state at instead of its head, requires an archive node"
+    )]
+    pub at_height: Option<ChainEpoch>,
 }
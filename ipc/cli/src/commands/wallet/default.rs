@@ -19,7 +19,7 @@ impl CommandLineHandler for WalletSetDefault {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("remove wallet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
 
         match wallet_type {
@@ -56,7 +56,7 @@ impl CommandLineHandler for WalletGetDefault {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("remove wallet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
 
         match wallet_type {
@@ -5,7 +5,7 @@
 use anyhow::bail;
 use async_trait::async_trait;
 use clap::{ArgGroup, Args};
-use ipc_wallet::WalletType;
+use ipc_wallet::{SecretString, WalletType};
 use std::fmt::Debug;
 use std::str::FromStr;
 
@@ -20,7 +20,7 @@ impl CommandLineHandler for WalletImport {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("import wallet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
 
         if let Some(key) = &arguments.private_key {
@@ -29,24 +29,29 @@ impl CommandLineHandler for WalletImport {
             }
             println!(
                 "{:?}",
-                provider.import_evm_key_from_privkey(key)?.to_string()
+                provider
+                    .import_evm_key_from_privkey(key.expose_secret())?
+                    .to_string()
             );
             Ok(())
         } else {
             // Get keyinfo from file or stdin
             let keyinfo = if arguments.path.is_some() {
-                std::fs::read_to_string(arguments.path.as_ref().unwrap())?
+                SecretString::new(std::fs::read_to_string(arguments.path.as_ref().unwrap())?)
             } else {
                 // FIXME: Accept keyinfo from stdin
                 bail!("stdin not supported yet")
             };
 
             match wallet_type {
-                WalletType::Fvm => println!("{:?}", provider.import_fvm_key(&keyinfo)?),
+                WalletType::Fvm => {
+                    println!("{:?}", provider.import_fvm_key(keyinfo.expose_secret())?)
+                }
                 WalletType::Evm => {
+                    let keyinfo = keyinfo.expose_secret();
                     let key = provider
-                        .import_evm_key_from_privkey(&keyinfo)
-                        .or_else(|_| provider.import_evm_key_from_json(&keyinfo))?;
+                        .import_evm_key_from_privkey(keyinfo)
+                        .or_else(|_| provider.import_evm_key_from_json(keyinfo))?;
 
                     println!("{:?}", key.to_string())
                 }
@@ -77,5 +82,5 @@ pub(crate) struct WalletImportArgs {
         group = "key_source",
         help = "The evm private key to import if path is not specified"
     )]
-    pub private_key: Option<String>,
+    pub private_key: Option<SecretString>,
 }
@@ -17,7 +17,7 @@ impl CommandLineHandler for WalletList {
     type Arguments = WalletListArgs;
 
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
         match wallet_type {
             WalletType::Evm => {
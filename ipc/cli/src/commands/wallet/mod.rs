@@ -2,10 +2,18 @@
 // SPDX-License-Identifier: MIT
 use crate::{CommandLineHandler, GlobalArguments};
 
+use crate::commands::wallet::approvals::{
+    WalletApprovals, WalletApprovalsArgs, WalletRevokeApproval, WalletRevokeApprovalArgs,
+};
 use crate::commands::wallet::balances::{WalletBalances, WalletBalancesArgs};
 use crate::commands::wallet::new::{WalletNew, WalletNewArgs};
+use crate::commands::wallet::portfolio::{WalletPortfolio, WalletPortfolioArgs};
 use clap::{Args, Subcommand};
 
+use self::address_book::{
+    AddressBookAdd, AddressBookAddArgs, AddressBookList, AddressBookListArgs, AddressBookRemove,
+    AddressBookRemoveArgs,
+};
 use self::default::{
     WalletGetDefault, WalletGetDefaultArgs, WalletSetDefault, WalletSetDefaultArgs,
 };
@@ -14,18 +22,21 @@ use self::import::{WalletImport, WalletImportArgs};
 use self::list::{WalletList, WalletListArgs};
 use self::remove::{WalletRemove, WalletRemoveArgs};
 
+mod address_book;
+mod approvals;
 mod balances;
 mod default;
 mod export;
 mod import;
 mod list;
 mod new;
+mod portfolio;
 mod remove;
 
 #[derive(Debug, Args)]
 #[command(name = "wallet", about = "wallet related commands")]
 #[command(args_conflicts_with_subcommands = true)]
-pub(crate) struct WalletCommandsArgs {
+pub struct WalletCommandsArgs {
     #[command(subcommand)]
     command: Commands,
 }
@@ -35,6 +46,9 @@ impl WalletCommandsArgs {
         match &self.command {
             Commands::New(args) => WalletNew::handle(global, args).await,
             Commands::Balances(args) => WalletBalances::handle(global, args).await,
+            Commands::Portfolio(args) => WalletPortfolio::handle(global, args).await,
+            Commands::Approvals(args) => WalletApprovals::handle(global, args).await,
+            Commands::RevokeApproval(args) => WalletRevokeApproval::handle(global, args).await,
             Commands::Import(args) => WalletImport::handle(global, args).await,
             Commands::Export(args) => WalletExport::handle(global, args).await,
             Commands::Remove(args) => WalletRemove::handle(global, args).await,
@@ -42,6 +56,9 @@ impl WalletCommandsArgs {
             Commands::GetDefault(args) => WalletGetDefault::handle(global, args).await,
             Commands::PubKey(args) => WalletPublicKey::handle(global, args).await,
             Commands::List(args) => WalletList::handle(global, args).await,
+            Commands::AddressBookAdd(args) => AddressBookAdd::handle(global, args).await,
+            Commands::AddressBookRemove(args) => AddressBookRemove::handle(global, args).await,
+            Commands::AddressBookList(args) => AddressBookList::handle(global, args).await,
         }
     }
 }
@@ -50,6 +67,9 @@ impl WalletCommandsArgs {
 pub(crate) enum Commands {
     New(WalletNewArgs),
     Balances(WalletBalancesArgs),
+    Portfolio(WalletPortfolioArgs),
+    Approvals(WalletApprovalsArgs),
+    RevokeApproval(WalletRevokeApprovalArgs),
     Import(WalletImportArgs),
     Export(WalletExportArgs),
     Remove(WalletRemoveArgs),
@@ -57,4 +77,7 @@ pub(crate) enum Commands {
     GetDefault(WalletGetDefaultArgs),
     PubKey(WalletPublicKeyArgs),
     List(WalletListArgs),
+    AddressBookAdd(AddressBookAddArgs),
+    AddressBookRemove(AddressBookRemoveArgs),
+    AddressBookList(AddressBookListArgs),
 }
@@ -20,7 +20,7 @@ impl CommandLineHandler for WalletNew {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("create new wallet with args: {:?}", arguments);
 
-        let provider = get_ipc_provider(global)?;
+        let provider = get_ipc_provider(global).await?;
 
         let wallet_type = WalletType::from_str(&arguments.wallet_type)?;
         match wallet_type {
@@ -0,0 +1,41 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet portfolio cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::schema;
+use std::fmt::Debug;
+
+use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
+
+pub(crate) struct WalletPortfolio;
+
+#[async_trait]
+impl CommandLineHandler for WalletPortfolio {
+    type Arguments = WalletPortfolioArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("wallet portfolio with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global).await?;
+        let address = require_fil_addr_from_str(&arguments.address)?;
+
+        let portfolio = provider.portfolio(&address).await;
+        if arguments.json {
+            println!("{}", schema::to_json_pretty(&portfolio)?);
+        } else {
+            print!("{portfolio}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Show the balances of an address in every configured subnet, with their totals")]
+pub(crate) struct WalletPortfolioArgs {
+    #[arg(long, help = "The address whose balances to show")]
+    pub address: String,
+    #[arg(long, help = "Print the portfolio as JSON")]
+    pub json: bool,
+}
@@ -3,7 +3,7 @@
 
 #[tokio::main]
 async fn main() {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    ipc_cli::init_logger();
 
     if let Err(e) = ipc_cli::cli().await {
         log::error!("main process failed: {e:#}");
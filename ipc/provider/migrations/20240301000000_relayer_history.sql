@@ -0,0 +1,24 @@
+-- History of the quorum events observed and the checkpoint submissions made by the relayer.
+
+CREATE TABLE IF NOT EXISTS quorum_events (
+    subnet TEXT NOT NULL,
+    height BIGINT NOT NULL,
+    obj_kind INTEGER NOT NULL,
+    obj_hash TEXT NOT NULL,
+    quorum_weight TEXT NOT NULL,
+    observed_at BIGINT NOT NULL,
+    PRIMARY KEY (subnet, height, obj_kind)
+);
+
+CREATE TABLE IF NOT EXISTS submissions (
+    subnet TEXT NOT NULL,
+    height BIGINT NOT NULL,
+    attempt BIGINT NOT NULL,
+    submitter TEXT NOT NULL,
+    started_at BIGINT NOT NULL,
+    finished_at BIGINT,
+    status TEXT NOT NULL,
+    parent_epoch BIGINT,
+    error TEXT,
+    PRIMARY KEY (subnet, height, attempt)
+);
@@ -0,0 +1,148 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The allowances over the ERC20 tokens supplying the subnets granted to the IPC contracts,
+//! e.g. by the deposits to the subnets, for their owners to audit and revoke them.
+
+use crate::config;
+use crate::lotus::message::deserialize::deserialize_token_amount_from_str;
+use crate::lotus::message::serialize::serialize_token_amount_to_atto;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The IPC contracts of a subnet able to take the tokens of an owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcContract {
+    Gateway,
+    Registry,
+}
+
+impl IpcContract {
+    pub const ALL: [IpcContract; 2] = [IpcContract::Gateway, IpcContract::Registry];
+
+    /// The address of the contract in `subnet`.
+    pub fn address(&self, subnet: &config::Subnet) -> Address {
+        match self {
+            IpcContract::Gateway => subnet.gateway_addr(),
+            IpcContract::Registry => subnet.registry_addr(),
+        }
+    }
+
+    /// The contract of `subnet` at `address`, if any.
+    pub fn of(subnet: &config::Subnet, address: &Address) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|contract| contract.address(subnet) == *address)
+    }
+}
+
+impl Display for IpcContract {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpcContract::Gateway => write!(f, "gateway"),
+            IpcContract::Registry => write!(f, "registry"),
+        }
+    }
+}
+
+/// The allowance over an ERC20 token granted to an IPC contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenApproval {
+    /// The subnet the token and the contract are deployed in.
+    pub subnet: String,
+    pub token: String,
+    pub contract: IpcContract,
+    pub spender: String,
+    /// The allowance in atto units of the token.
+    #[serde(
+        serialize_with = "serialize_token_amount_to_atto",
+        deserialize_with = "deserialize_token_amount_from_str"
+    )]
+    pub allowance: TokenAmount,
+}
+
+/// The allowances granted by an owner to the IPC contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenApprovals {
+    pub owner: String,
+    pub approvals: Vec<TokenApproval>,
+}
+
+impl TokenApprovals {
+    /// Keeps the `approvals` of `owner` with an allowance left, in the order of the subnets.
+    pub fn new(owner: String, mut approvals: Vec<TokenApproval>) -> Self {
+        approvals.retain(|a| a.allowance.is_positive());
+        approvals.sort_by(|a, b| {
+            (&a.subnet, &a.token, a.contract).cmp(&(&b.subnet, &b.token, b.contract))
+        });
+        Self { owner, approvals }
+    }
+}
+
+impl Display for TokenApprovals {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "owner: {}", self.owner)?;
+        if self.approvals.is_empty() {
+            writeln!(f, "no approvals")?;
+        }
+        for a in &self.approvals {
+            writeln!(
+                f,
+                "{}: {} of token {} by the {} {}",
+                a.subnet, a.allowance, a.token, a.contract, a.spender
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IpcContract, TokenApproval, TokenApprovals};
+    use fvm_shared::econ::TokenAmount;
+
+    fn approval(subnet: &str, contract: IpcContract, atto: u64) -> TokenApproval {
+        TokenApproval {
+            subnet: subnet.to_string(),
+            token: "t410ftoken".to_string(),
+            contract,
+            spender: format!("t410f{contract}"),
+            allowance: TokenAmount::from_atto(atto),
+        }
+    }
+
+    #[test]
+    fn test_approvals_left() {
+        let approvals = TokenApprovals::new(
+            "t410fowner".to_string(),
+            vec![
+                approval("/r314159/t410fa", IpcContract::Registry, 3),
+                approval("/r314159/t410fa", IpcContract::Gateway, 0),
+                approval("/r314159", IpcContract::Registry, 2),
+                approval("/r314159", IpcContract::Gateway, 5),
+            ],
+        );
+
+        let left = approvals
+            .approvals
+            .iter()
+            .map(|a| (a.subnet.as_str(), a.contract))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            left,
+            vec![
+                ("/r314159", IpcContract::Gateway),
+                ("/r314159", IpcContract::Registry),
+                ("/r314159/t410fa", IpcContract::Registry),
+            ]
+        );
+
+        let json = serde_json::to_string(&approvals).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TokenApprovals>(&json).unwrap(),
+            approvals
+        );
+    }
+}
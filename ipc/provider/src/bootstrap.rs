@@ -0,0 +1,232 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The information a new validator of a child subnet needs to bootstrap its node: the genesis
+//! epoch and bootstrap nodes registered in the parent, and the state snapshots offered by a
+//! running child node, to state sync from instead of replaying the chain from its genesis.
+//!
+//! A child node with snapshots enabled serves the ones it offers to its peers as an ABCI query
+//! at [`SNAPSHOTS_QUERY_PATH`], read through its CometBFT RPC, which also serves the header the
+//! state sync of CometBFT must trust.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::{Display, Formatter};
+use url::Url;
+
+use crate::jsonrpc::JsonRpcClient;
+
+/// The path of the ABCI query of the snapshots offered by a node.
+pub const SNAPSHOTS_QUERY_PATH: &str = "/ipc/snapshots";
+
+/// A state snapshot offered by a child node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotOffer {
+    /// The height of the block the snapshot was taken at.
+    pub block_height: u64,
+    /// The version of the format of the snapshot.
+    pub version: u32,
+    pub size: u64,
+    pub chunks: u32,
+    /// The hex encoded SHA2 hash of the content of the snapshot.
+    pub checksum: String,
+    /// The CID of the root of the state in the snapshot.
+    pub state_root: String,
+}
+
+/// The header the state sync of a new node trusts, to verify the snapshot it restores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSyncTrust {
+    pub height: u64,
+    /// The hex encoded hash of the block at `height`.
+    pub hash: String,
+    /// The CometBFT RPC the light client of the new node verifies the headers with.
+    pub rpc_server: String,
+}
+
+/// How a new validator of a subnet bootstraps its node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetBootstrapInfo {
+    pub subnet: String,
+    /// The parent epoch the genesis of the subnet is built from.
+    pub genesis_epoch: ChainEpoch,
+    /// The endpoints of the bootstrap nodes advertised in the parent.
+    pub bootstrap_nodes: Vec<String>,
+    /// The snapshots offered by the queried child node, none if it does not take any.
+    pub snapshots: Vec<SnapshotOffer>,
+    /// The header to trust to state sync from the latest snapshot, if any.
+    pub trust: Option<StateSyncTrust>,
+}
+
+impl SubnetBootstrapInfo {
+    /// The latest snapshot offered.
+    pub fn latest_snapshot(&self) -> Option<&SnapshotOffer> {
+        self.snapshots.iter().max_by_key(|s| s.block_height)
+    }
+
+    /// The `[statesync]` section of the CometBFT config of a new node to state sync from the
+    /// latest snapshot, if any. CometBFT needs two RPC servers, the same one is given twice.
+    pub fn statesync_config(&self) -> Option<String> {
+        let trust = self.trust.as_ref()?;
+        Some(format!(
+            "[statesync]\n\
+             enable = true\n\
+             rpc_servers = \"{rpc},{rpc}\"\n\
+             trust_height = {}\n\
+             trust_hash = \"{}\"\n",
+            trust.height,
+            trust.hash,
+            rpc = trust.rpc_server
+        ))
+    }
+}
+
+impl Display for SubnetBootstrapInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "subnet: {}", self.subnet)?;
+        writeln!(f, "genesis epoch: {}", self.genesis_epoch)?;
+        if self.bootstrap_nodes.is_empty() {
+            writeln!(f, "no bootstrap nodes advertised")?;
+        }
+        for node in &self.bootstrap_nodes {
+            writeln!(f, "bootstrap node: {node}")?;
+        }
+        match self.latest_snapshot() {
+            None => writeln!(f, "no snapshot offered, sync from the genesis")?,
+            Some(s) => writeln!(
+                f,
+                "latest snapshot: height {}, {} bytes in {} chunks, state root {}",
+                s.block_height, s.size, s.chunks, s.state_root
+            )?,
+        }
+        if let Some(config) = self.statesync_config() {
+            write!(f, "\n{config}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AbciQueryResult {
+    response: AbciQueryResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct AbciQueryResponse {
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    info: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitResult {
+    signed_header: SignedHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedHeader {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    block_id: BlockId,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockId {
+    hash: String,
+}
+
+/// Queries the snapshots offered by the child node behind the CometBFT RPC `client`.
+pub async fn query_snapshot_offers(client: &impl JsonRpcClient) -> Result<Vec<SnapshotOffer>> {
+    let result: AbciQueryResult = client
+        .request(
+            "abci_query",
+            json!({
+                "path": SNAPSHOTS_QUERY_PATH,
+                "data": "",
+                "height": "0",
+                "prove": false,
+            }),
+        )
+        .await?;
+    let response = result.response;
+    if response.code != 0 {
+        return Err(anyhow!(
+            "cannot query the snapshots: {} (code {})",
+            response.info,
+            response.code
+        ));
+    }
+    let value = base64::engine::general_purpose::STANDARD
+        .decode(response.value.unwrap_or_default())
+        .context("invalid snapshots encoding")?;
+    serde_json::from_slice(&value).context("invalid snapshots")
+}
+
+/// Queries the header at `height` to trust from the child node behind the CometBFT RPC
+/// `client`, reachable by the new nodes at `rpc_server`.
+pub async fn query_state_sync_trust(
+    client: &impl JsonRpcClient,
+    rpc_server: &Url,
+    height: u64,
+) -> Result<StateSyncTrust> {
+    let result: CommitResult = client
+        .request("commit", json!({ "height": height.to_string() }))
+        .await
+        .with_context(|| format!("cannot query the commit at height {height}"))?;
+    Ok(StateSyncTrust {
+        height,
+        hash: result.signed_header.commit.block_id.hash,
+        rpc_server: rpc_server.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SnapshotOffer, StateSyncTrust, SubnetBootstrapInfo};
+
+    fn offer(block_height: u64) -> SnapshotOffer {
+        SnapshotOffer {
+            block_height,
+            version: 1,
+            size: 1024,
+            chunks: 1,
+            checksum: "00".to_string(),
+            state_root: "bafy".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_statesync_config() {
+        let mut info = SubnetBootstrapInfo {
+            subnet: "/r314159/t410f".to_string(),
+            genesis_epoch: 100,
+            bootstrap_nodes: vec![],
+            snapshots: vec![offer(2000), offer(3000), offer(1000)],
+            trust: None,
+        };
+        assert_eq!(info.latest_snapshot().map(|s| s.block_height), Some(3000));
+        assert_eq!(info.statesync_config(), None);
+
+        info.trust = Some(StateSyncTrust {
+            height: 3000,
+            hash: "ABCD".to_string(),
+            rpc_server: "http://node:26657/".to_string(),
+        });
+        assert_eq!(
+            info.statesync_config().unwrap(),
+            "[statesync]\n\
+             enable = true\n\
+             rpc_servers = \"http://node:26657/,http://node:26657/\"\n\
+             trust_height = 3000\n\
+             trust_hash = \"ABCD\"\n"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Circuit breaker guarding the calls made to a subnet endpoint.
+//!
+//! After a number of consecutive failures the breaker opens and calls fail immediately
+//! with [`CircuitOpen`] until the cool-down period elapses. Then a call is let through
+//! as a probe: if it succeeds the breaker closes again, otherwise it reopens.
+
+use crate::clock::{default_clock, Clock};
+use crate::monitor;
+use anyhow::Result;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The default number of consecutive failures that opens the breaker.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// The default time the breaker stays open before letting a probe through.
+pub const DEFAULT_COOL_DOWN: Duration = Duration::from_secs(30);
+
+/// The error returned while the breaker is open.
+#[derive(Debug, thiserror::Error)]
+#[error("circuit breaker for {0} is open")]
+pub struct CircuitOpen(pub String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl BreakerState {
+    fn as_gauge(&self) -> i64 {
+        match self {
+            BreakerState::Closed => 0,
+            BreakerState::Open => 1,
+            BreakerState::HalfOpen => 2,
+        }
+    }
+}
+
+impl Display for BreakerState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open => write!(f, "open"),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+pub struct CircuitBreaker {
+    /// The name of the guarded endpoint, used in errors, logs and metrics.
+    endpoint: String,
+    failure_threshold: u32,
+    cool_down: Duration,
+    clock: Arc<dyn Clock>,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(endpoint: impl Into<String>, failure_threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            failure_threshold: failure_threshold.max(1),
+            cool_down,
+            clock: default_clock(),
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                opened_at: None,
+            }),
+        }
+    }
+
+    /// Measures the cool-down with `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Runs `f` through the breaker, failing fast with [`CircuitOpen`] if it is open.
+    pub async fn call<F, R>(&self, f: F) -> Result<R>
+    where
+        F: Future<Output = Result<R>>,
+    {
+        self.acquire()?;
+        let r = f.await;
+        self.record(r.is_ok());
+        r
+    }
+
+    fn acquire(&self) -> Result<(), CircuitOpen> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != BreakerState::Open {
+            return Ok(());
+        }
+
+        match inner.opened_at {
+            Some(t) if self.clock.now().duration_since(t) < self.cool_down => {
+                Err(CircuitOpen(self.endpoint.clone()))
+            }
+            _ => {
+                self.transition(&mut inner, BreakerState::HalfOpen);
+                Ok(())
+            }
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if success {
+            inner.failures = 0;
+            if inner.state != BreakerState::Closed {
+                self.transition(&mut inner, BreakerState::Closed);
+            }
+            return;
+        }
+
+        inner.failures += 1;
+        let should_open = match inner.state {
+            BreakerState::Closed => inner.failures >= self.failure_threshold,
+            BreakerState::HalfOpen => true,
+            // A call started before the breaker opened, the cool-down is already running.
+            BreakerState::Open => false,
+        };
+        if should_open {
+            inner.opened_at = Some(self.clock.now());
+            self.transition(&mut inner, BreakerState::Open);
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, to: BreakerState) {
+        if to == BreakerState::Open {
+            log::warn!(
+                "circuit breaker for {} opened after {} consecutive failures, cooling down for {:?}",
+                self.endpoint,
+                inner.failures,
+                self.cool_down
+            );
+        } else {
+            log::info!("circuit breaker for {} is {to}", self.endpoint);
+        }
+
+        inner.state = to;
+        monitor::CIRCUIT_BREAKER_STATE
+            .with_label_values(&[&self.endpoint])
+            .set(to.as_gauge());
+        monitor::CIRCUIT_BREAKER_TRANSITIONS
+            .with_label_values(&[&self.endpoint, &to.to_string()])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BreakerState, CircuitBreaker, CircuitOpen};
+    use crate::clock::ManualClock;
+    use anyhow::anyhow;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_breaker_opens_and_recovers() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_millis(50));
+
+        for _ in 0..2 {
+            let r: anyhow::Result<()> = breaker.call(async { Err(anyhow!("boom")) }).await;
+            assert!(r.is_err());
+        }
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // short-circuited without running the call
+        let r = breaker.call(async { Ok(()) }).await;
+        assert!(r.unwrap_err().downcast_ref::<CircuitOpen>().is_some());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        breaker.call(async { Ok(()) }).await.unwrap();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(10));
+
+        let _ = breaker.call(async { Err::<(), _>(anyhow!("boom")) }).await;
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let _ = breaker.call(async { Err::<(), _>(anyhow!("boom")) }).await;
+        assert_eq!(breaker.state(), BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_cool_down_with_manual_clock() {
+        let clock = ManualClock::new();
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(30))
+            .with_clock(Arc::new(clock.clone()));
+
+        let _ = breaker.call(async { Err::<(), _>(anyhow!("boom")) }).await;
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        clock.advance(Duration::from_secs(29));
+        assert!(breaker.call(async { Ok(()) }).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        clock.advance(Duration::from_secs(1));
+        breaker.call(async { Ok(()) }).await.unwrap();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
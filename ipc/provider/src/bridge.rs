@@ -0,0 +1,127 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The bridging of the ERC20 token supplying a subnet, see [`crate::IpcProvider::deposit_token`]
+//! and [`crate::IpcProvider::withdraw_token`].
+//!
+//! A deposit approves the gateway of the parent to take the tokens if its allowance is not
+//! enough, locks them in the gateway, and waits for the subnet to mint them, i.e. to commit
+//! the finality of the parent at the height of the deposit. A withdrawal burns the funds in
+//! the subnet and waits for the checkpoint carrying it to be committed in the parent, where
+//! the gateway transfers the tokens back.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The default interval between two checks of the confirmation of a bridging.
+pub const DEFAULT_BRIDGE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The default time to wait for the confirmation of a bridging, which includes the finality
+/// of the parent for deposits and a checkpoint period for withdrawals.
+pub const DEFAULT_BRIDGE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeOptions {
+    pub poll_interval: Duration,
+    /// How long to wait for the other side to confirm the bridging.
+    pub timeout: Duration,
+}
+
+impl Default for BridgeOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: DEFAULT_BRIDGE_POLL_INTERVAL,
+            timeout: DEFAULT_BRIDGE_TIMEOUT,
+        }
+    }
+}
+
+/// The steps of a bridging, reported as they are done.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum BridgeProgress {
+    /// The gateway was approved to take the tokens of the deposit at `height` of the parent.
+    Approved { height: ChainEpoch },
+    /// The allowance of the gateway already covered the deposit.
+    AlreadyApproved,
+    /// The tokens were locked in the gateway at `height` of the parent.
+    Deposited { height: ChainEpoch },
+    /// The subnet committed the finality of the parent at `parent_height`, past the deposit,
+    /// and minted the funds.
+    Minted { parent_height: ChainEpoch },
+    /// The funds were burnt at `height` of the subnet.
+    Released { height: ChainEpoch },
+    /// The checkpoint at `checkpoint_height`, past the release, was committed in the parent,
+    /// which transferred the tokens.
+    Withdrawn { checkpoint_height: ChainEpoch },
+}
+
+/// Waits for the height returned by `query` to reach `target`, returning it. The errors of
+/// the queries are retried until the timeout of `options`.
+pub(crate) async fn wait_for_height<F, Fut>(
+    what: &str,
+    target: ChainEpoch,
+    options: &BridgeOptions,
+    query: F,
+) -> Result<ChainEpoch>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<ChainEpoch>>,
+{
+    let deadline = Instant::now() + options.timeout;
+    let mut last = None;
+    loop {
+        match query().await {
+            Ok(height) if height >= target => return Ok(height),
+            Ok(height) => last = Some(height),
+            Err(e) => log::warn!("cannot query the {what}: {e}"),
+        }
+
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "{what} did not reach {target} within {:?}, last at {last:?}",
+                options.timeout
+            ));
+        }
+        log::debug!("waiting for the {what} at {last:?} to reach {target}");
+        tokio::time::sleep(options.poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wait_for_height, BridgeOptions};
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_wait_for_height() {
+        let options = BridgeOptions {
+            poll_interval: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+        };
+        let height = AtomicI64::new(0);
+        let reached = wait_for_height("height", 3, &options, || async {
+            match height.fetch_add(1, Ordering::SeqCst) {
+                // a failed query is retried
+                1 => Err(anyhow!("unavailable")),
+                h => Ok(h),
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(reached, 3);
+
+        let options = BridgeOptions {
+            poll_interval: Duration::from_millis(1),
+            timeout: Duration::ZERO,
+        };
+        let err = wait_for_height("height", 10, &options, || async { Ok(5) })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("last at Some(5)"));
+    }
+}
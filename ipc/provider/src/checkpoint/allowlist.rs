@@ -0,0 +1,39 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The check of a relayer against the allowlist of a subnet restricting the submission of its
+//! checkpoints, so that a relayer missing from it fails at startup with the request to send to
+//! the owner of the subnet, instead of having every submission reverted.
+
+use crate::manager::SubnetQuery;
+use anyhow::Result;
+use fvm_shared::address::Address;
+use ipc_api::subnet_id::SubnetID;
+
+/// A relayer missing from the allowlist of a permissioned subnet.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "relayer {relayer} is not allowed to submit the checkpoints of {subnet}: ask the owner of \
+     the subnet to allow it with `ipc-cli checkpoint allow-relayer --subnet {subnet} \
+     --relayer {relayer} --from <owner>`"
+)]
+pub struct RelayerNotAllowed {
+    pub subnet: SubnetID,
+    pub relayer: Address,
+}
+
+/// Checks that `relayer` can submit the checkpoints of `subnet` to its `parent`, failing with
+/// [`RelayerNotAllowed`] otherwise. Any relayer passes if the subnet does not restrict them.
+pub async fn check_relayer_allowed(
+    parent: &dyn SubnetQuery,
+    subnet: &SubnetID,
+    relayer: &Address,
+) -> Result<()> {
+    if parent.is_relayer_allowed(subnet, relayer).await? {
+        return Ok(());
+    }
+    Err(RelayerNotAllowed {
+        subnet: subnet.clone(),
+        relayer: *relayer,
+    }
+    .into())
+}
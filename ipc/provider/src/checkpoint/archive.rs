@@ -0,0 +1,225 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Archive of the committed checkpoints with the evidence of their submission.
+//!
+//! Every checkpoint committed by the relayer is archived as a [`ProofBundle`] in the directory
+//! of its subnet, one JSON file per height. A bundle is self-contained: third parties can check
+//! the signatures of the checkpoint against the validators of the subnet, and the calldata and
+//! events of the submission against the block of the parent chain it was executed in.
+
+use crate::clock::{unix_secs, TokioClock};
+use crate::manager::{CheckpointReceipt, SubmissionProof};
+use anyhow::{anyhow, Context, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::BottomUpCheckpointBundle;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// The version of the format of the proof bundles.
+pub const PROOF_BUNDLE_VERSION: u32 = 1;
+
+/// A committed checkpoint, its signatures and the evidence of its submission in the parent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub version: u32,
+    pub subnet: String,
+    /// The checkpoint with the signatures of the validators it was submitted with.
+    pub bundle: BottomUpCheckpointBundle,
+    pub receipt: CheckpointReceipt,
+    pub proof: SubmissionProof,
+    /// Unix timestamp in seconds of when the bundle was archived.
+    pub archived_at: i64,
+}
+
+impl ProofBundle {
+    pub fn new(
+        subnet: &SubnetID,
+        bundle: BottomUpCheckpointBundle,
+        receipt: CheckpointReceipt,
+        proof: SubmissionProof,
+    ) -> Self {
+        let archived_at = unix_secs(&TokioClock) as i64;
+        Self {
+            version: PROOF_BUNDLE_VERSION,
+            subnet: subnet.to_string(),
+            bundle,
+            receipt,
+            proof,
+            archived_at,
+        }
+    }
+
+    pub fn height(&self) -> ChainEpoch {
+        self.bundle.checkpoint.block_height
+    }
+}
+
+/// The proof bundles of the committed checkpoints, in one directory per subnet.
+#[derive(Debug, Clone)]
+pub struct CheckpointArchive {
+    dir: PathBuf,
+}
+
+impl CheckpointArchive {
+    /// Opens the archive at `dir`, creating it if it does not exist yet.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create checkpoint archive at {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// Archives `bundle`, unless a bundle is archived at the same height already: the first
+    /// submission committing a checkpoint is kept. Returns whether `bundle` was archived.
+    pub fn archive(&self, subnet: &SubnetID, bundle: &ProofBundle) -> Result<bool> {
+        let path = self.path(subnet, bundle.height());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // written aside and linked in place, so that a crash never leaves a truncated bundle
+        // and an existing one is never replaced
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(bundle)?)
+            .with_context(|| format!("cannot write proof bundle to {}", tmp.display()))?;
+        let linked = match fs::hard_link(&tmp, &path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => {
+                Err(e).with_context(|| format!("cannot link proof bundle to {}", path.display()))
+            }
+        };
+        fs::remove_file(&tmp)
+            .with_context(|| format!("cannot remove proof bundle at {}", tmp.display()))?;
+        linked
+    }
+
+    /// The proof bundle of the checkpoint of `subnet` at `height`, if archived.
+    pub fn get(&self, subnet: &SubnetID, height: ChainEpoch) -> Result<Option<ProofBundle>> {
+        let path = self.path(subnet, height);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("cannot read proof bundle at {}", path.display()))?;
+        let bundle = serde_json::from_str(&content)
+            .with_context(|| format!("cannot parse proof bundle at {}", path.display()))?;
+        Ok(Some(bundle))
+    }
+
+    /// The heights of the archived checkpoints of `subnet`, in ascending order.
+    pub fn heights(&self, subnet: &SubnetID) -> Result<Vec<ChainEpoch>> {
+        let dir = self.subnet_dir(subnet);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut heights = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                path.file_stem()?.to_str()?.parse().ok()
+            })
+            .collect::<Vec<ChainEpoch>>();
+        heights.sort();
+        Ok(heights)
+    }
+
+    /// Exports the proof bundle of the checkpoint of `subnet` at `height` to the file `out`.
+    pub fn export_proof_bundle(
+        &self,
+        subnet: &SubnetID,
+        height: ChainEpoch,
+        out: impl AsRef<Path>,
+    ) -> Result<ProofBundle> {
+        let bundle = self
+            .get(subnet, height)?
+            .ok_or_else(|| anyhow!("no archived checkpoint of {subnet} at height {height}"))?;
+        let out = out.as_ref();
+        fs::write(out, serde_json::to_vec_pretty(&bundle)?)
+            .with_context(|| format!("cannot export proof bundle to {}", out.display()))?;
+        Ok(bundle)
+    }
+
+    fn subnet_dir(&self, subnet: &SubnetID) -> PathBuf {
+        // the subnet ids are paths, e.g. /r123/t410...
+        let name = subnet.to_string().trim_start_matches('/').replace('/', "_");
+        self.dir.join(name)
+    }
+
+    fn path(&self, subnet: &SubnetID, height: ChainEpoch) -> PathBuf {
+        self.subnet_dir(subnet).join(format!("{height}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CheckpointArchive, ProofBundle};
+    use crate::manager::{CheckpointReceipt, SubmissionProof};
+    use fvm_shared::address::Address;
+    use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle};
+    use ipc_api::subnet_id::SubnetID;
+
+    fn proof_bundle(subnet: &SubnetID, height: i64) -> ProofBundle {
+        let bundle = BottomUpCheckpointBundle {
+            checkpoint: BottomUpCheckpoint {
+                subnet_id: subnet.clone(),
+                block_height: height,
+                block_hash: vec![1; 32],
+                next_configuration_number: 0,
+                msgs: vec![],
+            },
+            signatures: vec![vec![2; 65]],
+            signatories: vec![Address::new_id(100)],
+        };
+        let receipt = CheckpointReceipt {
+            epoch: 50,
+            tx_hash: "0x01".to_string(),
+            gas_used: Some(21000),
+            effective_gas_price: Some(1_000_000_000),
+        };
+        let proof = SubmissionProof {
+            tx_hash: "0x01".to_string(),
+            calldata: "0x02".to_string(),
+            block_number: 50,
+            block_hash: "0x03".to_string(),
+            receipts_root: "0x04".to_string(),
+            events: vec![],
+        };
+        ProofBundle::new(subnet, bundle, receipt, proof)
+    }
+
+    #[test]
+    fn test_archive_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = CheckpointArchive::open(dir.path().join("archive")).unwrap();
+        let subnet = SubnetID::new_from_parent(&SubnetID::new_root(123), Address::new_id(10));
+
+        for height in [20, 10] {
+            assert!(archive
+                .archive(&subnet, &proof_bundle(&subnet, height))
+                .unwrap());
+        }
+        assert_eq!(archive.heights(&subnet).unwrap(), vec![10, 20]);
+
+        // the bundle archived first is kept
+        let first = archive.get(&subnet, 10).unwrap().unwrap();
+        let mut again = proof_bundle(&subnet, 10);
+        again.receipt.tx_hash = "0x05".to_string();
+        assert!(!archive.archive(&subnet, &again).unwrap());
+        assert_eq!(archive.get(&subnet, 10).unwrap().unwrap(), first);
+        assert!(archive.get(&subnet, 30).unwrap().is_none());
+
+        let out = dir.path().join("proof.json");
+        let exported = archive.export_proof_bundle(&subnet, 10, &out).unwrap();
+        let read: ProofBundle =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(read, exported);
+        assert_eq!(read.height(), 10);
+
+        assert!(archive.export_proof_bundle(&subnet, 30, &out).is_err());
+    }
+}
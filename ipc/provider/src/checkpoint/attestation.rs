@@ -0,0 +1,226 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Status attestations signed by a relayer, for the communities of the subnets to verify which
+//! relayer keeps the checkpoints of a subnet committed, e.g. to build reputation or reward
+//! schemes on top of them.
+//!
+//! An attestation states the last checkpoint height committed in the parent at a time. It is
+//! signed as an EIP-191 personal message by the key of the relayer, so that anyone can check
+//! it with the usual wallet tooling, and published to a file and, optionally, posted to a URL.
+
+use crate::checkpoint::state::write_rotated;
+use crate::clock::{unix_secs, TokioClock};
+use crate::manager::{BottomUpCheckpointRelayer, SubnetQuery};
+use crate::schema;
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Signature, H160};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+/// The version of the signed message of the attestations.
+pub const ATTESTATION_VERSION: u32 = 1;
+
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The status of a relayer at a time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusAttestation {
+    pub version: u32,
+    pub subnet: String,
+    /// The ethereum address of the relayer, which signs the attestation.
+    pub relayer: H160,
+    pub last_committed_height: ChainEpoch,
+    /// Unix timestamp in seconds of the attestation.
+    pub timestamp: u64,
+}
+
+impl StatusAttestation {
+    /// The message signed by the relayer.
+    pub fn message(&self) -> String {
+        format!(
+            "IPC relayer status attestation\n\
+             version: {}\n\
+             subnet: {}\n\
+             relayer: {:?}\n\
+             last committed height: {}\n\
+             timestamp: {}",
+            self.version, self.subnet, self.relayer, self.last_committed_height, self.timestamp
+        )
+    }
+}
+
+/// An attestation with the signature of its relayer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    #[serde(flatten)]
+    pub attestation: StatusAttestation,
+    /// The hex encoded signature of the message of the attestation.
+    pub signature: String,
+}
+
+impl SignedAttestation {
+    /// Checks that the attestation is signed by its relayer.
+    pub fn verify(&self) -> Result<()> {
+        let attestation = &self.attestation;
+        if attestation.version != ATTESTATION_VERSION {
+            return Err(anyhow!(
+                "unsupported attestation version {}",
+                attestation.version
+            ));
+        }
+        let signature = hex::decode(self.signature.trim_start_matches("0x"))
+            .context("invalid signature encoding")?;
+        let signature = Signature::try_from(signature.as_slice())?;
+        let signer = signature.recover(attestation.message().as_bytes())?;
+        if signer != attestation.relayer {
+            return Err(anyhow!(
+                "attestation signed by {signer:?}, not by the relayer {:?}",
+                attestation.relayer
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Signs and publishes the status of the relayer of a subnet periodically.
+pub struct Attester<P> {
+    parent: P,
+    subnet: SubnetID,
+    relayer: Address,
+    path: Option<PathBuf>,
+    url: Option<Url>,
+    client: reqwest::Client,
+}
+
+impl<P: SubnetQuery + BottomUpCheckpointRelayer> Attester<P> {
+    /// Attests the status of `relayer`, the submitter of the checkpoints of `subnet`, signing
+    /// with its key in the manager of the `parent`.
+    pub fn new(parent: P, subnet: SubnetID, relayer: Address) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(PUBLISH_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            parent,
+            subnet,
+            relayer,
+            path: None,
+            url: None,
+            client,
+        })
+    }
+
+    /// Writes the latest attestation to `path`.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Posts the attestations to `url`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    /// Signs the current status of the relayer.
+    pub async fn attest(&self) -> Result<SignedAttestation> {
+        let status = self.parent.checkpoint_status(&self.subnet).await?;
+        let attestation = StatusAttestation {
+            version: ATTESTATION_VERSION,
+            subnet: self.subnet.to_string(),
+            relayer: payload_to_evm_address(self.relayer.payload())?,
+            last_committed_height: status.last_committed_height,
+            timestamp: unix_secs(&TokioClock),
+        };
+        let signature = self
+            .parent
+            .sign_message(&self.relayer, attestation.message().as_bytes())
+            .await?;
+        Ok(SignedAttestation {
+            attestation,
+            signature: format!("0x{}", hex::encode(signature)),
+        })
+    }
+
+    /// Signs the current status and publishes it.
+    pub async fn attest_now(&self) -> Result<SignedAttestation> {
+        let signed = self.attest().await?;
+        if let Some(path) = &self.path {
+            write_rotated(path, 0, schema::to_json_pretty(&signed)?.as_bytes())?;
+        }
+        if let Some(url) = &self.url {
+            let response = self
+                .client
+                .post(url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(schema::to_json(&signed)?)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "attestation rejected by {url} with status {}",
+                    response.status()
+                ));
+            }
+        }
+        Ok(signed)
+    }
+
+    /// Publishes an attestation every `interval`, forever. The failures are only logged.
+    pub async fn run(self, interval: Duration) {
+        log::info!(
+            "launching the status attestations of relayer {} of {}",
+            self.relayer,
+            self.subnet
+        );
+
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match self.attest_now().await {
+                Ok(signed) => log::debug!(
+                    "attested last committed height {}",
+                    signed.attestation.last_committed_height
+                ),
+                Err(e) => log::error!("cannot attest the status of {}: {e}", self.relayer),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignedAttestation, StatusAttestation, ATTESTATION_VERSION};
+    use ethers::signers::{LocalWallet, Signer};
+
+    #[tokio::test]
+    async fn test_verify_attestation() {
+        let wallet = LocalWallet::from_bytes(&[1u8; 32]).unwrap();
+        let attestation = StatusAttestation {
+            version: ATTESTATION_VERSION,
+            subnet: "/r314159/t410fkzrz3mlkyufisiuae3scumllgalzuu3wxlxa2ly".to_string(),
+            relayer: wallet.address(),
+            last_committed_height: 1200,
+            timestamp: 1_700_000_000,
+        };
+        let signature = wallet
+            .sign_message(attestation.message())
+            .await
+            .unwrap()
+            .to_vec();
+        let mut signed = SignedAttestation {
+            attestation,
+            signature: format!("0x{}", hex::encode(signature)),
+        };
+        signed.verify().unwrap();
+
+        // a claim of more work than signed for
+        signed.attestation.last_committed_height = 1300;
+        assert!(signed.verify().is_err());
+    }
+}
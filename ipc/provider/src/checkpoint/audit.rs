@@ -0,0 +1,301 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Audit of the checkpoint submissions, for the postmortems of the submissions rejected or
+//! misinterpreted by the parent.
+//!
+//! In audit mode every submission attempt is recorded, committed or not, with the exact
+//! calldata sent to the subnet actor, its 32 bytes words, and a human-readable rendering of the
+//! checkpoint and signatures it encodes. The records are JSON files in one directory per
+//! subnet, named after the height of the checkpoint and the time of the attempt.
+
+use crate::manager::CheckpointReceipt;
+use anyhow::{Context, Result};
+use ethers::types::{Bytes, H160, H256};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::BottomUpCheckpoint;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The size of the function selector at the start of the calldata.
+const SELECTOR_BYTES: usize = 4;
+/// The size of the words of the ABI encoding.
+const WORD_BYTES: usize = 32;
+
+/// The checkpoint of a submission, rendered for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedCheckpoint {
+    pub subnet_id: String,
+    pub block_height: ChainEpoch,
+    pub block_hash: String,
+    pub next_configuration_number: u64,
+    /// One line per message: its nonce, kind, route, value and payload size.
+    pub msgs: Vec<String>,
+    pub signatories: Vec<String>,
+    pub signatures: Vec<String>,
+}
+
+impl DecodedCheckpoint {
+    pub fn new(
+        checkpoint: &BottomUpCheckpoint,
+        signatures: &[Vec<u8>],
+        signatories: &[Address],
+    ) -> Self {
+        Self {
+            subnet_id: checkpoint.subnet_id.to_string(),
+            block_height: checkpoint.block_height,
+            block_hash: hex_string(&checkpoint.block_hash),
+            next_configuration_number: checkpoint.next_configuration_number,
+            msgs: checkpoint.msgs.iter().map(render_msg).collect(),
+            signatories: signatories.iter().map(Address::to_string).collect(),
+            signatures: signatures.iter().map(|s| hex_string(s)).collect(),
+        }
+    }
+}
+
+fn render_msg(msg: &IpcEnvelope) -> String {
+    format!(
+        "nonce {}: {:?} from {} to {}, value {}, {} bytes of payload",
+        msg.nonce,
+        msg.kind,
+        msg.from,
+        msg.to,
+        msg.value,
+        msg.message.len()
+    )
+}
+
+/// The outcome of a submission attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Committed {
+        tx_hash: String,
+        epoch: ChainEpoch,
+        gas_used: Option<u64>,
+    },
+    Failed {
+        /// The transaction, if it was sent before failing.
+        tx_hash: Option<String>,
+        error: String,
+    },
+}
+
+impl AuditOutcome {
+    pub fn new(result: &Result<CheckpointReceipt>, tx_hash: Option<H256>) -> Self {
+        match result {
+            Ok(receipt) => AuditOutcome::Committed {
+                tx_hash: receipt.tx_hash.clone(),
+                epoch: receipt.epoch,
+                gas_used: receipt.gas_used,
+            },
+            Err(e) => AuditOutcome::Failed {
+                tx_hash: tx_hash.map(|h| format!("{h:?}")),
+                error: format!("{e:#}"),
+            },
+        }
+    }
+}
+
+/// The record of a submission attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionAuditRecord {
+    pub subnet: String,
+    pub height: ChainEpoch,
+    pub submitter: String,
+    /// The subnet actor the calldata was sent to.
+    pub contract: String,
+    pub abi_version: String,
+    /// The calldata as sent, hex encoded.
+    pub calldata: String,
+    /// The selector and the 32 bytes words of the calldata, with their offset in the arguments.
+    pub calldata_words: Vec<String>,
+    pub checkpoint: DecodedCheckpoint,
+    pub outcome: AuditOutcome,
+    /// Unix timestamp in milliseconds of the record.
+    pub recorded_at: i64,
+}
+
+impl SubmissionAuditRecord {
+    pub fn new(
+        submitter: &Address,
+        contract: H160,
+        abi_version: impl ToString,
+        calldata: &Bytes,
+        checkpoint: DecodedCheckpoint,
+        outcome: AuditOutcome,
+    ) -> Self {
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        Self {
+            subnet: checkpoint.subnet_id.clone(),
+            height: checkpoint.block_height,
+            submitter: submitter.to_string(),
+            contract: format!("{contract:?}"),
+            abi_version: abi_version.to_string(),
+            calldata: hex_string(calldata),
+            calldata_words: calldata_words(calldata),
+            checkpoint,
+            outcome,
+            recorded_at,
+        }
+    }
+}
+
+/// The selector of `calldata` and its words, each with its offset in the arguments.
+pub fn calldata_words(calldata: &[u8]) -> Vec<String> {
+    if calldata.len() < SELECTOR_BYTES {
+        return vec![hex_string(calldata)];
+    }
+    let (selector, args) = calldata.split_at(SELECTOR_BYTES);
+    let mut words = vec![format!("selector: {}", hex_string(selector))];
+    words.extend(
+        args.chunks(WORD_BYTES)
+            .enumerate()
+            .map(|(i, word)| format!("{:#06x}: {}", i * WORD_BYTES, hex::encode(word))),
+    );
+    words
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// The records of the submission attempts, in one directory per subnet.
+#[derive(Debug, Clone)]
+pub struct SubmissionAudit {
+    dir: PathBuf,
+}
+
+impl SubmissionAudit {
+    /// Opens the audit at `dir`, creating it if it does not exist yet.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create submission audit at {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// Writes `record` next to the other attempts of the same checkpoint.
+    pub fn record(&self, record: &SubmissionAuditRecord) -> Result<PathBuf> {
+        let dir = self.dir.join(dir_name(&record.subnet));
+        fs::create_dir_all(&dir)?;
+        let mut path = dir.join(format!("{}-{}.json", record.height, record.recorded_at));
+        // two attempts in the same millisecond keep both records
+        let mut n = 1;
+        while path.exists() {
+            path = dir.join(format!("{}-{}-{n}.json", record.height, record.recorded_at));
+            n += 1;
+        }
+        fs::write(&path, serde_json::to_vec_pretty(record)?)
+            .with_context(|| format!("cannot write submission audit to {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The records of the attempts to submit the checkpoint of `subnet` at `height`, oldest
+    /// first.
+    pub fn records(
+        &self,
+        subnet: &SubnetID,
+        height: ChainEpoch,
+    ) -> Result<Vec<SubmissionAuditRecord>> {
+        let dir = self.dir.join(dir_name(&subnet.to_string()));
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let prefix = format!("{height}-");
+        let mut records = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let matches = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(&prefix) && n.ends_with(".json"))
+                .unwrap_or_default();
+            if !matches {
+                continue;
+            }
+            let content = fs::read_to_string(&path)?;
+            let record: SubmissionAuditRecord = serde_json::from_str(&content)
+                .with_context(|| format!("cannot parse submission audit {}", path.display()))?;
+            records.push(record);
+        }
+        records.sort_by_key(|r| r.recorded_at);
+        Ok(records)
+    }
+}
+
+fn dir_name(subnet: &str) -> String {
+    // the subnet ids are paths, e.g. /r123/t410...
+    subnet.trim_start_matches('/').replace('/', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        calldata_words, AuditOutcome, DecodedCheckpoint, SubmissionAudit, SubmissionAuditRecord,
+    };
+    use ethers::types::{Bytes, H160};
+    use fvm_shared::address::Address;
+    use ipc_api::checkpoint::BottomUpCheckpoint;
+    use ipc_api::subnet_id::SubnetID;
+
+    #[test]
+    fn test_calldata_words() {
+        let mut calldata = vec![0xab, 0xcd, 0xef, 0x01];
+        calldata.extend([0u8; 31]);
+        calldata.push(7);
+        calldata.extend([1u8; 32]);
+
+        let words = calldata_words(&calldata);
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0], "selector: 0xabcdef01");
+        assert_eq!(words[1], format!("0x0000: {}07", "00".repeat(31)));
+        assert_eq!(words[2], format!("0x0020: {}", "01".repeat(32)));
+    }
+
+    #[test]
+    fn test_record_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = SubmissionAudit::open(dir.path()).unwrap();
+        let subnet = SubnetID::new_from_parent(&SubnetID::new_root(123), Address::new_id(10));
+        let checkpoint = BottomUpCheckpoint {
+            subnet_id: subnet.clone(),
+            block_height: 10,
+            block_hash: vec![1; 32],
+            next_configuration_number: 2,
+            msgs: vec![],
+        };
+        let decoded = DecodedCheckpoint::new(&checkpoint, &[vec![2; 65]], &[Address::new_id(1)]);
+        assert_eq!(decoded.signatures, vec![format!("0x{}", "02".repeat(65))]);
+
+        let failed = AuditOutcome::Failed {
+            tx_hash: None,
+            error: "execution reverted".to_string(),
+        };
+        for _ in 0..2 {
+            let record = SubmissionAuditRecord::new(
+                &Address::new_id(1),
+                H160::repeat_byte(3),
+                "v2",
+                &Bytes::from(vec![1, 2, 3, 4, 5]),
+                decoded.clone(),
+                failed.clone(),
+            );
+            audit.record(&record).unwrap();
+        }
+
+        let records = audit.records(&subnet, 10).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].calldata, "0x0102030405");
+        assert_eq!(records[0].checkpoint, decoded);
+        assert_eq!(records[0].outcome, failed);
+        assert!(audit.records(&subnet, 20).unwrap().is_empty());
+    }
+}
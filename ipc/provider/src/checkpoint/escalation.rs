@@ -0,0 +1,105 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Escalation of the fee urgency of the checkpoint submissions as the subnet falls behind.
+//!
+//! The checkpoints are committed in order, one per period, so a subnet whose checkpoints are
+//! stuck, e.g. because of a fee spike in the parent, only catches up once they are included.
+//! The further behind the parent the subnet is, counted in checkpoint periods, the higher the
+//! fee urgency of the submissions, up to a cap.
+
+use crate::manager::evm::Urgency;
+use fvm_shared::clock::ChainEpoch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEscalation {
+    /// The urgency of the checkpoints that are on time.
+    base: Urgency,
+    /// The highest urgency the checkpoints are escalated to.
+    cap: Urgency,
+    /// The number of periods behind after which the urgency is raised by one tier.
+    periods_per_tier: ChainEpoch,
+}
+
+impl Default for FeeEscalation {
+    /// The checkpoints are always submitted with high urgency.
+    fn default() -> Self {
+        Self::fixed(Urgency::High)
+    }
+}
+
+impl FeeEscalation {
+    /// Raises the urgency from `base` by one tier every `periods_per_tier` periods behind,
+    /// never above `cap`.
+    pub fn new(base: Urgency, cap: Urgency, periods_per_tier: ChainEpoch) -> Self {
+        Self {
+            base,
+            cap: cap.max(base),
+            periods_per_tier: periods_per_tier.max(1),
+        }
+    }
+
+    /// Always submits with `urgency`.
+    pub fn fixed(urgency: Urgency) -> Self {
+        Self::new(urgency, urgency, 1)
+    }
+
+    /// The urgency of a submission when the subnet is `periods_behind` the parent.
+    pub fn urgency(&self, periods_behind: ChainEpoch) -> Urgency {
+        let mut urgency = self.base;
+        for _ in 0..periods_behind.max(0) / self.periods_per_tier {
+            if urgency >= self.cap {
+                break;
+            }
+            urgency = urgency.raised();
+        }
+        urgency.min(self.cap)
+    }
+}
+
+/// The number of checkpoint periods the parent is behind the child at `child_height`, not
+/// counting the period of the checkpoint that is naturally in flight.
+pub fn periods_behind(
+    last_committed: ChainEpoch,
+    child_height: ChainEpoch,
+    period: ChainEpoch,
+) -> ChainEpoch {
+    if period <= 0 {
+        return 0;
+    }
+    ((child_height - last_committed) / period - 1).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{periods_behind, FeeEscalation};
+    use crate::manager::evm::Urgency;
+
+    #[test]
+    fn test_escalation_within_cap() {
+        let escalation = FeeEscalation::new(Urgency::Low, Urgency::Normal, 2);
+        assert_eq!(escalation.urgency(0), Urgency::Low);
+        assert_eq!(escalation.urgency(1), Urgency::Low);
+        assert_eq!(escalation.urgency(2), Urgency::Normal);
+        assert_eq!(escalation.urgency(100), Urgency::Normal);
+
+        let escalation = FeeEscalation::new(Urgency::Normal, Urgency::High, 3);
+        assert_eq!(escalation.urgency(2), Urgency::Normal);
+        assert_eq!(escalation.urgency(3), Urgency::High);
+
+        assert_eq!(FeeEscalation::default().urgency(0), Urgency::High);
+        assert_eq!(
+            FeeEscalation::fixed(Urgency::Low).urgency(100),
+            Urgency::Low
+        );
+    }
+
+    #[test]
+    fn test_periods_behind() {
+        // the checkpoint of the current period is not overdue yet
+        assert_eq!(periods_behind(100, 150, 10), 4);
+        assert_eq!(periods_behind(100, 119, 10), 0);
+        assert_eq!(periods_behind(100, 120, 10), 1);
+        assert_eq!(periods_behind(100, 90, 10), 0);
+        assert_eq!(periods_behind(100, 150, 0), 0);
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The height arithmetic of the bottom-up relayer, free of any query to the subnets.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use std::cmp::max;
+use std::ops::RangeInclusive;
+
+/// The height of the next checkpoint accepted by the parent, one period after the last
+/// committed one.
+pub(crate) fn next_submission_height(
+    last_committed: ChainEpoch,
+    period: ChainEpoch,
+) -> Result<ChainEpoch> {
+    if period <= 0 {
+        return Err(anyhow!("invalid bottom up checkpoint period: {period}"));
+    }
+    if last_committed < 0 {
+        return Err(anyhow!(
+            "invalid last bottom up checkpoint height: {last_committed}"
+        ));
+    }
+    last_committed
+        .checked_add(period)
+        .ok_or_else(|| anyhow!("bottom up checkpoint height overflows after {last_committed}"))
+}
+
+/// The last height of the child considered final, `finalization_blocks` behind its head. The
+/// genesis block is never considered.
+pub(crate) fn finalized_height(current: ChainEpoch, finalization_blocks: ChainEpoch) -> ChainEpoch {
+    max(1, current.saturating_sub(max(0, finalization_blocks)))
+}
+
+/// The heights of the child to scan for quorum events, from right after the last committed
+/// checkpoint up to the `finalized` height, or `None` while the next checkpoint is not final.
+pub(crate) fn scan_range(
+    last_committed: ChainEpoch,
+    period: ChainEpoch,
+    finalized: ChainEpoch,
+) -> Result<Option<RangeInclusive<ChainEpoch>>> {
+    let next = next_submission_height(last_committed, period)?;
+    if finalized < next {
+        return Ok(None);
+    }
+    Ok(Some(last_committed + 1..=finalized))
+}
+
+/// The heights after `last_verified` and before `last_committed` at which a checkpoint may
+/// have been committed, one period apart. If the period changed from `previous` to `period`
+/// since `last_verified`, the change may have happened at any committed height, so the heights
+/// of both periods are covered by stepping by their greatest common divisor.
+pub(crate) fn committed_candidates(
+    last_verified: ChainEpoch,
+    last_committed: ChainEpoch,
+    previous: ChainEpoch,
+    period: ChainEpoch,
+) -> Result<Vec<ChainEpoch>> {
+    let step = gcd(
+        next_submission_height(0, previous)?,
+        next_submission_height(0, period)?,
+    );
+    let mut heights = vec![];
+    let mut h = next_submission_height(last_verified, step)?;
+    while h < last_committed {
+        heights.push(h);
+        h += step;
+    }
+    Ok(heights)
+}
+
+fn gcd(a: ChainEpoch, b: ChainEpoch) -> ChainEpoch {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{committed_candidates, finalized_height, next_submission_height, scan_range};
+    use fvm_shared::clock::ChainEpoch;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+    use std::cmp::max;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_invalid_heights() {
+        assert!(next_submission_height(0, 0).is_err());
+        assert!(next_submission_height(0, -10).is_err());
+        assert!(next_submission_height(-1, 10).is_err());
+        assert!(next_submission_height(ChainEpoch::MAX, 1).is_err());
+        assert_eq!(next_submission_height(0, 10).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_finalized_height_edges() {
+        assert_eq!(finalized_height(0, 0), 1);
+        assert_eq!(finalized_height(-5, 0), 1);
+        assert_eq!(finalized_height(10, -3), 10);
+        assert_eq!(finalized_height(ChainEpoch::MIN, ChainEpoch::MAX), 1);
+        assert_eq!(finalized_height(20, 5), 15);
+    }
+
+    #[test]
+    fn test_candidates_across_period_change() {
+        assert_eq!(committed_candidates(10, 40, 10, 10).unwrap(), vec![20, 30]);
+        assert!(committed_candidates(10, 20, 10, 10).unwrap().is_empty());
+        assert!(committed_candidates(10, 40, 10, 0).is_err());
+
+        // the period went from 10 to 4 after the checkpoint at 30, then 34 and 38 were
+        // committed, all of them are verified
+        let candidates = committed_candidates(10, 38, 10, 4).unwrap();
+        assert_eq!(candidates, (12..38).step_by(2).collect::<Vec<_>>());
+        assert!([20, 30, 34].iter().all(|h| candidates.contains(h)));
+    }
+
+    #[quickcheck]
+    fn prop_next_submission_is_one_period_ahead(last: u32, period: u16) -> TestResult {
+        if period == 0 {
+            return TestResult::discard();
+        }
+        let (last, period) = (last as ChainEpoch, period as ChainEpoch);
+        let next = next_submission_height(last, period).unwrap();
+        TestResult::from_bool(next > last && next - last == period)
+    }
+
+    #[quickcheck]
+    fn prop_finalized_height_bounds(current: i64, finalization_blocks: i64) -> bool {
+        let finalized = finalized_height(current, finalization_blocks);
+        finalized >= 1 && finalized <= max(1, current)
+    }
+
+    #[quickcheck]
+    fn prop_finalized_height_is_monotonic(
+        current: u32,
+        advance: u16,
+        finalization_blocks: u16,
+    ) -> bool {
+        let (current, finalization_blocks) =
+            (current as ChainEpoch, finalization_blocks as ChainEpoch);
+        finalized_height(current, finalization_blocks)
+            <= finalized_height(current + advance as ChainEpoch, finalization_blocks)
+            && finalized_height(current, finalization_blocks + 1)
+                <= finalized_height(current, finalization_blocks)
+    }
+
+    /// The range never skips a height after the last committed checkpoint, covers the next
+    /// submission height and stops at the finalized height.
+    #[quickcheck]
+    fn prop_scan_range_covers_next_submission(
+        last: u32,
+        period: u16,
+        finalized: u32,
+    ) -> TestResult {
+        if period == 0 {
+            return TestResult::discard();
+        }
+        let (last, period, finalized) = (
+            last as ChainEpoch,
+            period as ChainEpoch,
+            finalized as ChainEpoch,
+        );
+        let next = next_submission_height(last, period).unwrap();
+        match scan_range(last, period, finalized).unwrap() {
+            None => TestResult::from_bool(finalized < next),
+            Some(range) => TestResult::from_bool(
+                *range.start() == last + 1 && *range.end() == finalized && range.contains(&next),
+            ),
+        }
+    }
+
+    /// Runs the relayer over a child whose head advances, and whose checkpoint period may
+    /// change, between the rounds. In every round the relayer commits all the checkpoints its
+    /// scan reaches, as the parent only accepts them in order: no period is skipped, no
+    /// checkpoint is committed twice and no height is scanned twice within a round.
+    #[quickcheck]
+    fn prop_rounds_never_skip_a_period(rounds: Vec<(u8, u8)>, finalization_blocks: u8) -> bool {
+        let finalization_blocks = finalization_blocks as ChainEpoch;
+        let mut head: ChainEpoch = 0;
+        let mut last: ChainEpoch = 0;
+        let mut committed = vec![];
+
+        for (advance, period) in rounds {
+            head += advance as ChainEpoch;
+            let period = max(1, period as ChainEpoch);
+            let finalized = finalized_height(head, finalization_blocks);
+
+            let Some(range) = scan_range(last, period, finalized).unwrap() else {
+                if finalized >= last + period {
+                    return false;
+                }
+                continue;
+            };
+
+            let mut scanned = HashSet::new();
+            for h in range {
+                if !scanned.insert(h) {
+                    return false;
+                }
+                // a checkpoint is cut one period after the last committed one
+                if h == next_submission_height(last, period).unwrap() {
+                    committed.push(h);
+                    last = h;
+                }
+            }
+            if finalized >= last + period {
+                return false;
+            }
+        }
+
+        committed.windows(2).all(|w| w[0] < w[1])
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! User provided callbacks on the lifecycle of checkpoint submissions.
+
+use crate::checkpoint::policy::PolicyViolation;
+use crate::manager::{CheckpointReceipt, ChildSubnetStatus};
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::BottomUpCheckpoint;
+use ipc_api::subnet_id::SubnetID;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub type HookFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+type Hook<E> = Arc<dyn Fn(E) -> HookFuture + Send + Sync>;
+
+/// A checkpoint that was submitted to the parent successfully.
+#[derive(Debug, Clone)]
+pub struct SubmissionSuccess {
+    pub checkpoint: BottomUpCheckpoint,
+    pub receipt: CheckpointReceipt,
+}
+
+/// A checkpoint whose submission to the parent failed.
+#[derive(Debug, Clone)]
+pub struct SubmissionFailure {
+    pub checkpoint: BottomUpCheckpoint,
+    pub error: String,
+}
+
+/// A checkpoint refused by a submission policy, which is not relayed.
+#[derive(Debug, Clone)]
+pub struct SubmissionRefused {
+    pub checkpoint: BottomUpCheckpoint,
+    pub violations: Vec<PolicyViolation>,
+}
+
+/// What differs between a checkpoint committed in the parent and the child chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// The committed block hash is not the hash of the child block at that height.
+    BlockHash,
+    /// The committed messages are not the ones of the child checkpoint.
+    MessagesRoot,
+    /// Any other field differs from the child checkpoint, e.g. the configuration number.
+    Checkpoint,
+}
+
+impl Display for DivergenceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DivergenceKind::BlockHash => write!(f, "block_hash"),
+            DivergenceKind::MessagesRoot => write!(f, "messages_root"),
+            DivergenceKind::Checkpoint => write!(f, "checkpoint"),
+        }
+    }
+}
+
+/// A checkpoint committed in the parent that does not match the child chain, either an
+/// equivocation of the child validators or a tampering by the relayer.
+#[derive(Debug, Clone)]
+pub struct CheckpointDivergence {
+    pub committed: BottomUpCheckpoint,
+    /// The checkpoint of the child at the same height.
+    pub expected: BottomUpCheckpoint,
+    /// The hash of the child block at the checkpoint height.
+    pub chain_block_hash: Vec<u8>,
+    pub committed_messages_root: [u8; 32],
+    pub expected_messages_root: [u8; 32],
+    pub kinds: Vec<DivergenceKind>,
+}
+
+/// The child subnet was killed or de-registered in the parent, the manager stops.
+#[derive(Debug, Clone)]
+pub struct SubnetTermination {
+    pub subnet: SubnetID,
+    pub status: ChildSubnetStatus,
+    /// The height of the last checkpoint committed in the parent.
+    pub last_committed_height: ChainEpoch,
+}
+
+/// The hooks registered on a [`super::BottomUpCheckpointManager`], run in registration order.
+#[derive(Default, Clone)]
+pub struct CheckpointHooks {
+    before_submit: Vec<Hook<BottomUpCheckpoint>>,
+    success: Vec<Hook<SubmissionSuccess>>,
+    failure: Vec<Hook<SubmissionFailure>>,
+    refused: Vec<Hook<SubmissionRefused>>,
+    divergence: Vec<Hook<CheckpointDivergence>>,
+    terminated: Vec<Hook<SubnetTermination>>,
+}
+
+impl CheckpointHooks {
+    pub fn on_before_submit<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(BottomUpCheckpoint) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.before_submit.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    pub fn on_success<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(SubmissionSuccess) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.success.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    pub fn on_failure<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(SubmissionFailure) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.failure.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    pub fn on_refused<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(SubmissionRefused) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.refused.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    pub fn on_divergence<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(CheckpointDivergence) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.divergence.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    pub fn on_terminated<F, Fut>(&mut self, f: F)
+    where
+        F: Fn(SubnetTermination) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.terminated.push(Arc::new(move |e| Box::pin(f(e))));
+    }
+
+    /// Runs the `before_submit` hooks, the first one failing vetoes the submission.
+    pub(crate) async fn before_submit(&self, checkpoint: &BottomUpCheckpoint) -> Result<()> {
+        for hook in &self.before_submit {
+            hook(checkpoint.clone()).await.map_err(|e| {
+                anyhow!(
+                    "submission of checkpoint({}) vetoed by hook: {e}",
+                    checkpoint.block_height
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs the `success` hooks, their errors are only logged.
+    pub(crate) async fn success(&self, event: SubmissionSuccess) {
+        for hook in &self.success {
+            if let Err(e) = hook(event.clone()).await {
+                log::error!(
+                    "success hook failed for checkpoint({}): {e}",
+                    event.checkpoint.block_height
+                );
+            }
+        }
+    }
+
+    /// Runs the `failure` hooks, their errors are only logged.
+    pub(crate) async fn failure(&self, event: SubmissionFailure) {
+        for hook in &self.failure {
+            if let Err(e) = hook(event.clone()).await {
+                log::error!(
+                    "failure hook failed for checkpoint({}): {e}",
+                    event.checkpoint.block_height
+                );
+            }
+        }
+    }
+
+    /// Runs the `refused` hooks, their errors are only logged.
+    pub(crate) async fn refused(&self, event: SubmissionRefused) {
+        for hook in &self.refused {
+            if let Err(e) = hook(event.clone()).await {
+                log::error!(
+                    "refused hook failed for checkpoint({}): {e}",
+                    event.checkpoint.block_height
+                );
+            }
+        }
+    }
+
+    /// Runs the `divergence` hooks, their errors are only logged.
+    pub(crate) async fn divergence(&self, event: CheckpointDivergence) {
+        for hook in &self.divergence {
+            if let Err(e) = hook(event.clone()).await {
+                log::error!(
+                    "divergence hook failed for checkpoint({}): {e}",
+                    event.committed.block_height
+                );
+            }
+        }
+    }
+
+    /// Runs the `terminated` hooks, their errors are only logged.
+    pub(crate) async fn terminated(&self, event: SubnetTermination) {
+        for hook in &self.terminated {
+            if let Err(e) = hook(event.clone()).await {
+                log::error!("terminated hook failed for subnet {}: {e}", event.subnet);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CheckpointHooks, SubmissionSuccess};
+    use crate::manager::CheckpointReceipt;
+    use anyhow::anyhow;
+    use ipc_api::checkpoint::BottomUpCheckpoint;
+    use ipc_api::subnet_id::SubnetID;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn checkpoint() -> BottomUpCheckpoint {
+        BottomUpCheckpoint {
+            subnet_id: SubnetID::default(),
+            block_height: 10,
+            block_hash: vec![],
+            next_configuration_number: 0,
+            msgs: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_before_submit_veto() {
+        let mut hooks = CheckpointHooks::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let c = calls.clone();
+        hooks.on_before_submit(move |_| {
+            c.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow!("not now")) }
+        });
+        let c = calls.clone();
+        hooks.on_before_submit(move |_| {
+            c.fetch_add(1, Ordering::SeqCst);
+            async { Ok(()) }
+        });
+
+        assert!(hooks.before_submit(&checkpoint()).await.is_err());
+        // the hooks after the veto are not run
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_success_hooks_run_in_order() {
+        let mut hooks = CheckpointHooks::default();
+        let order = Arc::new(std::sync::Mutex::new(vec![]));
+
+        for i in 0..3 {
+            let o = order.clone();
+            hooks.on_success(move |e: SubmissionSuccess| {
+                o.lock().unwrap().push((i, e.receipt.epoch));
+                async { Ok(()) }
+            });
+        }
+
+        hooks
+            .success(SubmissionSuccess {
+                checkpoint: checkpoint(),
+                receipt: CheckpointReceipt {
+                    epoch: 100,
+                    tx_hash: "0x01".to_string(),
+                    gas_used: None,
+                    effective_gas_price: None,
+                },
+            })
+            .await;
+
+        assert_eq!(*order.lock().unwrap(), vec![(0, 100), (1, 100), (2, 100)]);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! One-shot inspection of the state of the bottom-up checkpoints of a subnet, as seen by a
+//! relayer, to debug a relayer that does not submit.
+
+use super::heights::next_submission_height;
+use crate::manager::SubnetManager;
+use anyhow::Result;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The number of heights of the child queried for quorum events at once.
+const SCAN_BATCH_SIZE: usize = 50;
+
+/// The state of the bottom-up checkpoints of a subnet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayerInspection {
+    pub subnet: String,
+    pub period: ChainEpoch,
+    pub last_committed_height: ChainEpoch,
+    pub next_submission_height: ChainEpoch,
+    /// The height of the head of the child.
+    pub child_height: ChainEpoch,
+    /// The last height of the child scanned for quorum events.
+    pub scanned_height: ChainEpoch,
+    /// The checkpoints that reached quorum in the child and are not committed in the parent.
+    pub pending_quorums: Vec<PendingQuorum>,
+    pub submitter: Option<SubmitterInspection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingQuorum {
+    pub height: ChainEpoch,
+    /// The hex encoded hash of the checkpoint.
+    pub checkpoint_hash: String,
+}
+
+/// The account submitting the checkpoints in the parent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitterInspection {
+    pub address: String,
+    pub balance: String,
+    /// The nonce of the next transaction of the submitter, including the pending ones.
+    pub next_nonce: u64,
+}
+
+impl Display for RelayerInspection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "subnet: {}", self.subnet)?;
+        writeln!(f, "checkpoint period: {}", self.period)?;
+        writeln!(f, "last committed height: {}", self.last_committed_height)?;
+        writeln!(f, "next submission height: {}", self.next_submission_height)?;
+        writeln!(f, "child height: {}", self.child_height)?;
+        writeln!(f, "scanned up to height: {}", self.scanned_height)?;
+        writeln!(f, "pending quorums: {}", self.pending_quorums.len())?;
+        for q in self.pending_quorums.iter() {
+            writeln!(
+                f,
+                "  height: {}, checkpoint: {}",
+                q.height, q.checkpoint_hash
+            )?;
+        }
+        if let Some(s) = &self.submitter {
+            writeln!(f, "submitter: {}", s.address)?;
+            writeln!(f, "  balance: {}", s.balance)?;
+            writeln!(f, "  next nonce: {}", s.next_nonce)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inspects the checkpoints of `subnet` committed in `parent` and the quorums reached in
+/// `child`, scanning at most `scan_limit` heights of the child after the last committed one.
+pub async fn inspect(
+    parent: &dyn SubnetManager,
+    child: &dyn SubnetManager,
+    subnet: &SubnetID,
+    submitter: Option<&Address>,
+    scan_limit: ChainEpoch,
+) -> Result<RelayerInspection> {
+    let status = parent.checkpoint_status(subnet).await?;
+    let next = next_submission_height(status.last_committed_height, status.period)?;
+    let child_height = child.current_epoch().await?;
+
+    let scanned_height = child_height.min(status.last_committed_height + scan_limit.max(0));
+    let heights = (status.last_committed_height + 1..=scanned_height).collect::<Vec<_>>();
+    let mut pending_quorums = vec![];
+    for chunk in heights.chunks(SCAN_BATCH_SIZE) {
+        for event in child
+            .quorum_reached_events_at(chunk)
+            .await?
+            .into_iter()
+            .flatten()
+        {
+            if event.height > status.last_committed_height {
+                pending_quorums.push(PendingQuorum {
+                    height: event.height,
+                    checkpoint_hash: hex::encode(&event.obj_hash),
+                });
+            }
+        }
+    }
+    pending_quorums.sort_by_key(|q| q.height);
+
+    let submitter = match submitter {
+        Some(address) => Some(SubmitterInspection {
+            address: address.to_string(),
+            balance: parent.wallet_balance(address, None).await?.to_string(),
+            next_nonce: parent.next_nonce(address).await?,
+        }),
+        None => None,
+    };
+
+    Ok(RelayerInspection {
+        subnet: subnet.to_string(),
+        period: status.period,
+        last_committed_height: status.last_committed_height,
+        next_submission_height: next,
+        child_height,
+        scanned_height,
+        pending_quorums,
+        submitter,
+    })
+}
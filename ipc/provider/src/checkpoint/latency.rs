@@ -0,0 +1,73 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The split of the latency of a checkpoint between the child validators and the relayer.
+//!
+//! The quorum formation is the time between the child block at the checkpoint height and the
+//! child block where the quorum of its signatures was reached, both from the timestamps of the
+//! child blocks: it is the time the validators took to sign. The time between the quorum and
+//! the successful submission is the one taken by the relayer.
+
+use fvm_shared::clock::ChainEpoch;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// The number of checkpoints whose quorum time is remembered until their submission.
+const MAX_TRACKED: usize = 1024;
+
+/// The times the quorum of the checkpoints was reached, until they are submitted.
+#[derive(Debug, Default)]
+pub(crate) struct QuorumLatency {
+    reached: Mutex<BTreeMap<ChainEpoch, u64>>,
+}
+
+impl QuorumLatency {
+    /// Whether the quorum of the checkpoint at `height` was recorded already, the quorum events
+    /// being found again by every round until the checkpoint is committed.
+    pub fn is_recorded(&self, height: ChainEpoch) -> bool {
+        self.reached.lock().unwrap().contains_key(&height)
+    }
+
+    /// Records that the quorum of the checkpoint at `height` was reached at the `reached_at`
+    /// timestamp.
+    pub fn record(&self, height: ChainEpoch, reached_at: u64) {
+        let mut reached = self.reached.lock().unwrap();
+        reached.insert(height, reached_at);
+        while reached.len() > MAX_TRACKED {
+            reached.pop_first();
+        }
+    }
+
+    /// The seconds between the quorum of the checkpoint at `height` and its submission at the
+    /// `now` timestamp, if its quorum was recorded. The checkpoint and the earlier ones, which
+    /// are committed too, are forgotten.
+    pub fn submitted(&self, height: ChainEpoch, now: u64) -> Option<u64> {
+        let mut reached = self.reached.lock().unwrap();
+        let at = reached.get(&height).copied();
+        *reached = reached.split_off(&(height + 1));
+        at.map(|at| now.saturating_sub(at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuorumLatency;
+
+    #[test]
+    fn test_quorum_to_submission() {
+        let latency = QuorumLatency::default();
+        latency.record(10, 1000);
+        latency.record(20, 1100);
+        latency.record(30, 1200);
+        assert!(latency.is_recorded(20));
+
+        assert_eq!(latency.submitted(20, 1150), Some(50));
+        // the earlier checkpoints are committed with it
+        assert!(!latency.is_recorded(10));
+        assert!(!latency.is_recorded(20));
+        assert!(latency.is_recorded(30));
+
+        // not recorded, e.g. found before a restart
+        assert_eq!(latency.submitted(40, 1300), None);
+        assert!(!latency.is_recorded(30));
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Validation of the checkpoint bundles against the limits of the parent before they are
+//! submitted.
+//!
+//! A bundle breaking a limit of the contracts or of the parent nodes otherwise fails in the gas
+//! estimation, with a revert or a rejection that does not say what is wrong with it. Checking
+//! the limits beforehand identifies the offending field of the bundle instead.
+
+use crate::manager::evm::CheckpointAbiVersion;
+use anyhow::Result;
+use ethers::abi::{Token, Tokenizable};
+use ipc_actors_abis::subnet_actor_checkpointing_facet;
+use ipc_api::checkpoint::BottomUpCheckpointBundle;
+use ipc_api::evm::payload_to_evm_address;
+use std::collections::HashSet;
+
+/// The maximum number of messages of a checkpoint, `MAX_MSGS_PER_BATCH` of the contracts.
+pub const DEFAULT_MAX_MSGS: usize = 10;
+/// The maximum size of the submission calldata, the default maximum size of a transaction
+/// accepted by the mempool of the parent nodes.
+pub const DEFAULT_MAX_CALLDATA_BYTES: usize = 128 * 1024;
+/// The size of a secp256k1 signature with its recovery id.
+const SIGNATURE_BYTES: usize = 65;
+
+/// A bundle field breaking a limit.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BundleLimitError {
+    #[error("{field} has {count} entries, above the limit of {limit}")]
+    TooMany {
+        field: String,
+        count: usize,
+        limit: usize,
+    },
+    #[error("{field} is empty")]
+    Empty { field: String },
+    #[error("{field} has {count} entries for {expected} in {other}")]
+    LengthMismatch {
+        field: String,
+        count: usize,
+        other: String,
+        expected: usize,
+    },
+    #[error("{field} is {size} bytes instead of {expected}")]
+    InvalidSize {
+        field: String,
+        size: usize,
+        expected: usize,
+    },
+    #[error("{field} is invalid: {reason}")]
+    Invalid { field: String, reason: String },
+    #[error("calldata of {size} bytes above the limit of {limit}, {field} takes {field_size}")]
+    CalldataTooLarge {
+        size: usize,
+        limit: usize,
+        /// The largest field of the calldata.
+        field: String,
+        field_size: usize,
+    },
+}
+
+/// The limits of the bundles accepted by the parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleLimits {
+    pub max_msgs: usize,
+    pub max_calldata_bytes: usize,
+}
+
+impl Default for BundleLimits {
+    fn default() -> Self {
+        Self {
+            max_msgs: DEFAULT_MAX_MSGS,
+            max_calldata_bytes: DEFAULT_MAX_CALLDATA_BYTES,
+        }
+    }
+}
+
+impl BundleLimits {
+    pub fn with_max_msgs(mut self, max_msgs: usize) -> Self {
+        self.max_msgs = max_msgs;
+        self
+    }
+
+    pub fn with_max_calldata_bytes(mut self, max_calldata_bytes: usize) -> Self {
+        self.max_calldata_bytes = max_calldata_bytes;
+        self
+    }
+
+    /// Checks `bundle` against the limits, returning the first field breaking one.
+    pub fn check(&self, bundle: &BottomUpCheckpointBundle) -> Result<(), BundleLimitError> {
+        let msgs = &bundle.checkpoint.msgs;
+        if msgs.len() > self.max_msgs {
+            return Err(BundleLimitError::TooMany {
+                field: "checkpoint.msgs".to_string(),
+                count: msgs.len(),
+                limit: self.max_msgs,
+            });
+        }
+
+        if bundle.signatures.is_empty() {
+            return Err(BundleLimitError::Empty {
+                field: "signatures".to_string(),
+            });
+        }
+        if bundle.signatures.len() != bundle.signatories.len() {
+            return Err(BundleLimitError::LengthMismatch {
+                field: "signatures".to_string(),
+                count: bundle.signatures.len(),
+                other: "signatories".to_string(),
+                expected: bundle.signatories.len(),
+            });
+        }
+        if let Some(i) = bundle
+            .signatures
+            .iter()
+            .position(|s| s.len() != SIGNATURE_BYTES)
+        {
+            return Err(BundleLimitError::InvalidSize {
+                field: format!("signatures[{i}]"),
+                size: bundle.signatures[i].len(),
+                expected: SIGNATURE_BYTES,
+            });
+        }
+        let mut seen = HashSet::new();
+        let mut signatories = Vec::with_capacity(bundle.signatories.len());
+        for (i, signatory) in bundle.signatories.iter().enumerate() {
+            let field = || format!("signatories[{i}]");
+            if !seen.insert(signatory) {
+                return Err(BundleLimitError::Invalid {
+                    field: field(),
+                    reason: format!("{signatory} signed twice"),
+                });
+            }
+            let address = payload_to_evm_address(signatory.payload()).map_err(|e| {
+                BundleLimitError::Invalid {
+                    field: field(),
+                    reason: e.to_string(),
+                }
+            })?;
+            signatories.push(address);
+        }
+
+        self.check_calldata(bundle, signatories)
+    }
+
+    /// Checks the size of the submission calldata, in the layout of the latest contracts.
+    fn check_calldata(
+        &self,
+        bundle: &BottomUpCheckpointBundle,
+        signatories: Vec<ethers::types::H160>,
+    ) -> Result<(), BundleLimitError> {
+        let invalid = |field: String| {
+            move |e: anyhow::Error| BundleLimitError::Invalid {
+                field,
+                reason: e.to_string(),
+            }
+        };
+        let signatures = bundle
+            .signatures
+            .iter()
+            .cloned()
+            .map(ethers::types::Bytes::from)
+            .collect::<Vec<_>>();
+        let calldata = CheckpointAbiVersion::LATEST
+            .bindings()
+            .encode_submit_checkpoint(bundle.checkpoint.clone(), signatories, signatures.clone())
+            .map_err(invalid("checkpoint".to_string()))?;
+        if calldata.len() <= self.max_calldata_bytes {
+            return Ok(());
+        }
+
+        // the largest of the messages and of the signatures is the offending field
+        let mut largest = ("signatures".to_string(), encoded_size(signatures));
+        for (i, msg) in bundle.checkpoint.msgs.iter().enumerate() {
+            let field = format!("checkpoint.msgs[{i}]");
+            let msg = subnet_actor_checkpointing_facet::IpcEnvelope::try_from(msg.clone())
+                .map_err(invalid(field.clone()))?;
+            let size = encoded_size(vec![msg]);
+            if size > largest.1 {
+                largest = (field, size);
+            }
+        }
+        Err(BundleLimitError::CalldataTooLarge {
+            size: calldata.len(),
+            limit: self.max_calldata_bytes,
+            field: largest.0,
+            field_size: largest.1,
+        })
+    }
+}
+
+/// The size of the ABI encoding of `values` as an array.
+fn encoded_size<T: Tokenizable>(values: Vec<T>) -> usize {
+    ethers::abi::encode(&[Token::Array(
+        values.into_iter().map(Tokenizable::into_token).collect(),
+    )])
+    .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BundleLimitError, BundleLimits};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle};
+    use ipc_api::cross::IpcEnvelope;
+    use ipc_api::subnet_id::SubnetID;
+
+    fn validator(i: u8) -> Address {
+        Address::new_delegated(10, &[i; 20]).unwrap()
+    }
+
+    fn subnet() -> SubnetID {
+        SubnetID::new_from_parent(&SubnetID::new_root(123), validator(100))
+    }
+
+    fn msg(nonce: u64, size: usize) -> IpcEnvelope {
+        let mut msg = IpcEnvelope::new_release_msg(
+            &subnet(),
+            &Address::new_id(1),
+            &Address::new_id(2),
+            TokenAmount::from_whole(1),
+        )
+        .unwrap();
+        msg.nonce = nonce;
+        msg.message = vec![1; size];
+        msg
+    }
+
+    fn bundle(msgs: Vec<IpcEnvelope>, signers: u8) -> BottomUpCheckpointBundle {
+        BottomUpCheckpointBundle {
+            checkpoint: BottomUpCheckpoint {
+                subnet_id: subnet(),
+                block_height: 100,
+                block_hash: vec![0; 32],
+                next_configuration_number: 0,
+                msgs,
+            },
+            signatures: (0..signers).map(|_| vec![1; 65]).collect(),
+            signatories: (0..signers).map(validator).collect(),
+        }
+    }
+
+    #[test]
+    fn test_valid_bundle() {
+        let limits = BundleLimits::default();
+        limits.check(&bundle(vec![msg(0, 100)], 3)).unwrap();
+    }
+
+    #[test]
+    fn test_offending_fields() {
+        let limits = BundleLimits::default().with_max_msgs(2);
+        let error = |b: &BottomUpCheckpointBundle| limits.check(b).unwrap_err().to_string();
+
+        let b = bundle((0..3).map(|n| msg(n, 0)).collect(), 1);
+        assert_eq!(
+            error(&b),
+            "checkpoint.msgs has 3 entries, above the limit of 2"
+        );
+
+        assert_eq!(error(&bundle(vec![], 0)), "signatures is empty");
+
+        let mut b = bundle(vec![], 3);
+        b.signatures.pop();
+        assert_eq!(error(&b), "signatures has 2 entries for 3 in signatories");
+
+        let mut b = bundle(vec![], 3);
+        b.signatures[1] = vec![1; 64];
+        assert_eq!(error(&b), "signatures[1] is 64 bytes instead of 65");
+
+        let mut b = bundle(vec![], 3);
+        b.signatories[2] = validator(0);
+        assert!(matches!(
+            limits.check(&b),
+            Err(BundleLimitError::Invalid { field, .. }) if field == "signatories[2]"
+        ));
+    }
+
+    #[test]
+    fn test_calldata_too_large() {
+        let limits = BundleLimits::default().with_max_calldata_bytes(4096);
+        let b = bundle(vec![msg(0, 10), msg(1, 5000)], 3);
+        match limits.check(&b) {
+            Err(BundleLimitError::CalldataTooLarge {
+                size, limit, field, ..
+            }) => {
+                assert!(size > limit);
+                assert_eq!(field, "checkpoint.msgs[1]");
+            }
+            r => panic!("unexpected result: {r:?}"),
+        }
+    }
+}
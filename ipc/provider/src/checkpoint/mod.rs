@@ -0,0 +1,1409 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bottom up checkpoint manager
+
+pub mod allowlist;
+pub mod archive;
+pub mod attestation;
+pub mod audit;
+pub mod escalation;
+mod heights;
+pub mod hooks;
+pub mod inspect;
+mod latency;
+pub mod limits;
+mod observer;
+pub mod pacing;
+mod pipeline;
+pub mod planner;
+pub mod policy;
+pub mod profile;
+pub mod quorum;
+pub mod replay;
+pub mod report;
+pub mod service;
+pub mod state;
+
+use crate::breaker::{CircuitBreaker, DEFAULT_COOL_DOWN, DEFAULT_FAILURE_THRESHOLD};
+use crate::checkpoint::archive::{CheckpointArchive, ProofBundle};
+use crate::checkpoint::audit::SubmissionAudit;
+use crate::checkpoint::escalation::{periods_behind, FeeEscalation};
+use crate::checkpoint::hooks::{
+    CheckpointDivergence, CheckpointHooks, SubmissionFailure, SubmissionRefused, SubmissionSuccess,
+    SubnetTermination,
+};
+use crate::checkpoint::latency::QuorumLatency;
+use crate::checkpoint::limits::BundleLimits;
+use crate::checkpoint::pacing::{SubmissionPacer, SubmissionPriority};
+use crate::checkpoint::pipeline::{pipeline, PipelineSender};
+use crate::checkpoint::planner::{ReadyCheckpoint, SubmissionAction, SubmissionPlanner};
+use crate::checkpoint::policy::SubmissionPolicy;
+use crate::checkpoint::profile::{timed, Phase, SubmissionProfiler};
+use crate::checkpoint::quorum::CHECKPOINT_OBJ_KIND;
+use crate::clock::{default_clock, timeout, Clock};
+use crate::config::Subnet;
+use crate::head::{ChainHeadTracker, DEFAULT_HEAD_POLL_INTERVAL};
+use crate::history::RelayerHistory;
+use crate::journal::TxJournal;
+use crate::logging::{SCANNER_TARGET, SUBMITTER_TARGET};
+use crate::manager::evm::{Forwarder, Urgency};
+use crate::manager::{
+    BottomUpCheckpointRelayer, CheckpointReceipt, CheckpointStatus, ChildGatewayClient,
+    EthSubnetManager, EvmSigner,
+};
+use crate::monitor;
+use crate::webhook::{
+    CheckpointCommitted, CheckpointRefused, DivergenceDetected, SubnetTerminated,
+    WebhookDispatcher, WebhookEvent,
+};
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent};
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{EthKeyAddress, PersistentKeyStore};
+use rand::Rng;
+use std::cmp::{max, min};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// The default deadline of a single query to the parent or child subnet.
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(60);
+/// The default deadline of a whole submission attempt, including waiting for the receipts.
+const DEFAULT_SUBMISSION_TIMEOUT: Duration = Duration::from_secs(600);
+/// The default number of heights of the child queried at once.
+const DEFAULT_RPC_BATCH_SIZE: usize = 50;
+/// The default number of checkpoints fetched ahead of the one being submitted.
+const DEFAULT_PREFETCH_LIMIT: usize = 8;
+/// The default interval between two checks that the child subnet is still active in the parent.
+const DEFAULT_STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How the relayer handles checkpoints that carry no cross-net messages and no validator changes.
+///
+/// The parent only accepts checkpoints in order, one per checkpoint period, so a new empty
+/// checkpoint can never be dropped altogether: what can be saved is the re-submission of one
+/// that is already committed, and the latency of the empty ones can be traded for fewer
+/// submission rounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyCheckpointPolicy {
+    /// Relay every checkpoint.
+    #[default]
+    Submit,
+    /// Do not re-submit empty checkpoints that are already committed in the parent.
+    Skip,
+    /// Like `Skip`, and also hold back new empty checkpoints until a non-empty one is ready,
+    /// or until `max_held` of them are pending, and then relay them all in order.
+    Batch { max_held: usize },
+}
+
+/// Tracks the config required for bottom up checkpoint submissions
+/// parent/child subnet and checkpoint period.
+pub struct CheckpointConfig {
+    parent: Subnet,
+    child: Subnet,
+    /// The latest checkpoint period read from the parent, which can change on chain.
+    period: AtomicI64,
+}
+
+/// Manages the submission of bottom up checkpoint. It checks if the submitter has already
+/// submitted in the `last_checkpoint_height`, if not, it will submit the checkpoint at that height.
+/// Then it will submit at the next submission height for the new checkpoint.
+pub struct BottomUpCheckpointManager<P, C = P> {
+    metadata: CheckpointConfig,
+    parent_handler: P,
+    child_handler: C,
+    /// The number of blocks away from the chain head that is considered final
+    finalization_blocks: ChainEpoch,
+    /// The percentage of the total validator weight that must have signed a checkpoint
+    /// before it is submitted. If not set, the contract quorum is used.
+    quorum_threshold: Option<u8>,
+    /// The deadline of every query made to the parent and child subnets. It does not cover
+    /// the submission of a checkpoint to the parent, i.e. the fee, nonce and receipt calls of
+    /// its transaction, which is only bounded by `submission_timeout`.
+    call_timeout: Duration,
+    /// The deadline of a submission attempt, after which it is abandoned until the next one
+    submission_timeout: Duration,
+    /// The circuit breakers guarding the calls to the parent and child endpoints
+    parent_breaker: CircuitBreaker,
+    child_breaker: CircuitBreaker,
+    /// How checkpoints with no messages and no validator changes are relayed
+    empty_checkpoints: EmptyCheckpointPolicy,
+    /// The user provided callbacks on the checkpoint submissions
+    hooks: CheckpointHooks,
+    /// The policies refusing to relay checkpoints from their content
+    policies: Vec<Arc<dyn SubmissionPolicy>>,
+    /// The value of the `relayer` label of the metrics of this manager
+    metrics_label: String,
+    /// Where the observed quorum events and the submission attempts are recorded
+    history: Option<Arc<dyn RelayerHistory>>,
+    /// Where the committed checkpoints are archived with the evidence of their submission
+    archive: Option<Arc<CheckpointArchive>>,
+    /// The maximum random deviation from the submission interval between two rounds
+    submission_jitter: Duration,
+    /// The delay before the first round, to spread relayers sharing the same schedule
+    phase_offset: Duration,
+    /// Records the timings of the phases of the submissions, if profiling
+    profiler: Option<Arc<SubmissionProfiler>>,
+    /// The number of heights of the child whose events and bundles are queried at once
+    rpc_batch_size: usize,
+    /// The maximum number of checkpoints fetched ahead of the one being submitted
+    prefetch_limit: usize,
+    /// How the fee urgency of the submissions rises as the checkpoints become overdue
+    fee_escalation: FeeEscalation,
+    /// The head of the child shared with the other components following it, if any
+    child_head: Option<ChainHeadTracker>,
+    /// The interval between two checks that the child is still active in the parent, also
+    /// checked after every failed round
+    status_check_interval: Duration,
+    /// The limits of the parent the bundles are checked against before they are submitted
+    bundle_limits: BundleLimits,
+    /// The source of time of the schedule of the rounds, the timeouts and the cool-downs
+    clock: Arc<dyn Clock>,
+    /// When the quorum of the checkpoints found was reached, until they are submitted
+    quorum_latency: QuorumLatency,
+    /// The pacing of the submissions shared with the other relayers of the parent, if any
+    pacer: Option<Arc<SubmissionPacer>>,
+    /// The priority of the submissions of the subnet when they are paced
+    priority: SubmissionPriority,
+}
+
+impl<P: BottomUpCheckpointRelayer, C: ChildGatewayClient> BottomUpCheckpointManager<P, C> {
+    /// Creates the manager with the handlers of the parent and of the child, which can be of
+    /// different types. The checkpoint period is read from the gateway of the child if the
+    /// parent cannot be queried, e.g. during an outage of the parent, and from the parent again
+    /// on every round.
+    pub async fn new(
+        parent: Subnet,
+        child: Subnet,
+        parent_handler: P,
+        child_handler: C,
+    ) -> Result<Self> {
+        let period = match parent_handler.checkpoint_period(&child.id).await {
+            Ok(period) => period,
+            Err(e) => {
+                log::warn!(
+                    "cannot get the checkpoint period of {} from the parent, reading it from \
+                     the child: {e}",
+                    child.id
+                );
+                child_checkpoint_period(&child_handler, &child.id)
+                    .await
+                    .map_err(|c| {
+                        anyhow!(
+                            "cannot get bottom up checkpoint period: {e}, nor from the child: {c}"
+                        )
+                    })?
+            }
+        };
+        let metrics_label = child.id.to_string();
+        let parent_breaker = CircuitBreaker::new(
+            parent.id.to_string(),
+            DEFAULT_FAILURE_THRESHOLD,
+            DEFAULT_COOL_DOWN,
+        );
+        let child_breaker = CircuitBreaker::new(
+            child.id.to_string(),
+            DEFAULT_FAILURE_THRESHOLD,
+            DEFAULT_COOL_DOWN,
+        );
+        Ok(Self {
+            metadata: CheckpointConfig {
+                parent,
+                child,
+                period: AtomicI64::new(period),
+            },
+            parent_handler,
+            child_handler,
+            finalization_blocks: 0,
+            quorum_threshold: None,
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+            submission_timeout: DEFAULT_SUBMISSION_TIMEOUT,
+            parent_breaker,
+            child_breaker,
+            empty_checkpoints: EmptyCheckpointPolicy::default(),
+            hooks: CheckpointHooks::default(),
+            policies: vec![],
+            metrics_label,
+            history: None,
+            archive: None,
+            submission_jitter: Duration::ZERO,
+            phase_offset: Duration::ZERO,
+            profiler: None,
+            rpc_batch_size: DEFAULT_RPC_BATCH_SIZE,
+            prefetch_limit: DEFAULT_PREFETCH_LIMIT,
+            fee_escalation: FeeEscalation::default(),
+            child_head: None,
+            status_check_interval: DEFAULT_STATUS_CHECK_INTERVAL,
+            bundle_limits: BundleLimits::default(),
+            clock: default_clock(),
+            quorum_latency: QuorumLatency::default(),
+            pacer: None,
+            priority: SubmissionPriority::default(),
+        })
+    }
+
+    pub fn with_finalization_blocks(mut self, finalization_blocks: ChainEpoch) -> Self {
+        self.finalization_blocks = finalization_blocks;
+        self
+    }
+
+    /// Only submit checkpoints signed by at least `percentage` of the total validator weight,
+    /// which must be higher than the contract quorum to have any effect.
+    pub fn with_quorum_threshold(mut self, percentage: u8) -> Result<Self> {
+        if percentage > 100 {
+            return Err(anyhow!("invalid quorum threshold percentage: {percentage}"));
+        }
+        self.quorum_threshold = Some(percentage);
+        Ok(self)
+    }
+
+    /// Fails the queries to the subnets not completing within `timeout`. The submissions of
+    /// the checkpoints are not queries, a transaction whose receipt takes longer than a query
+    /// must not be abandoned, see [`Self::with_submission_timeout`] instead.
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    pub fn with_submission_timeout(mut self, timeout: Duration) -> Self {
+        self.submission_timeout = timeout;
+        self
+    }
+
+    /// Sets the `relayer` label of the metrics, by default the child subnet id.
+    pub fn with_metrics_label(mut self, label: impl Into<String>) -> Self {
+        self.metrics_label = label.into();
+        self
+    }
+
+    /// Notifies the webhooks of every checkpoint committed or refused by this manager, and of
+    /// the termination of the child. The
+    /// notifications are delivered in the background, not to delay the submissions.
+    pub fn with_webhooks(self, dispatcher: Arc<WebhookDispatcher>) -> Self {
+        let d = dispatcher.clone();
+        let r = dispatcher.clone();
+        let t = dispatcher.clone();
+        self.on_success(move |s| {
+            notify(
+                d.clone(),
+                WebhookEvent::CheckpointCommitted(CheckpointCommitted::from(&s)),
+            )
+        })
+        .on_refused(move |s| {
+            notify(
+                r.clone(),
+                WebhookEvent::CheckpointRefused(CheckpointRefused::from(&s)),
+            )
+        })
+        .on_divergence(move |d| {
+            notify(
+                dispatcher.clone(),
+                WebhookEvent::CheckpointDivergence(DivergenceDetected::from(&d)),
+            )
+        })
+        .on_terminated(move |e| {
+            notify(
+                t.clone(),
+                WebhookEvent::SubnetTerminated(SubnetTerminated::from(&e)),
+            )
+        })
+    }
+
+    /// Refuses to relay the checkpoints violating `policy`, along with the other policies,
+    /// running the `refused` hooks instead.
+    pub fn with_submission_policy(mut self, policy: Arc<dyn SubmissionPolicy>) -> Self {
+        self.policies.push(policy);
+        self
+    }
+
+    pub fn with_history(mut self, history: Arc<dyn RelayerHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Archives every checkpoint committed by this relayer in `archive`, with its signatures
+    /// and the evidence of its submission, see [`archive`].
+    pub fn with_archive(mut self, archive: Arc<CheckpointArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    pub fn with_empty_checkpoints(mut self, policy: EmptyCheckpointPolicy) -> Self {
+        self.empty_checkpoints = policy;
+        self
+    }
+
+    /// Waits a random delay within `submission interval ± jitter` between two rounds, so that
+    /// relayers started on the same schedule do not hit the parent at the same time.
+    pub fn with_submission_jitter(mut self, jitter: Duration) -> Self {
+        self.submission_jitter = jitter;
+        self
+    }
+
+    /// Delays the first round by `offset`.
+    pub fn with_phase_offset(mut self, offset: Duration) -> Self {
+        self.phase_offset = offset;
+        self
+    }
+
+    /// Queries the events and bundles of up to `size` heights of the child at once, in a
+    /// single round-trip if its handler supports batching.
+    pub fn with_rpc_batch_size(mut self, size: usize) -> Self {
+        self.rpc_batch_size = max(1, size);
+        self
+    }
+
+    /// Fetches up to `limit` checkpoints ahead of the one being submitted, while it waits
+    /// for its receipt.
+    pub fn with_prefetch_limit(mut self, limit: usize) -> Self {
+        self.prefetch_limit = max(1, limit);
+        self
+    }
+
+    /// Raises the fee urgency of the submissions as the subnet falls behind the parent, by
+    /// default they are always submitted with high urgency.
+    pub fn with_fee_escalation(mut self, escalation: FeeEscalation) -> Self {
+        self.fee_escalation = escalation;
+        self
+    }
+
+    /// Reads the height of the child from `tracker`, instead of querying it every round.
+    pub fn with_child_head(mut self, tracker: ChainHeadTracker) -> Self {
+        self.child_head = Some(tracker);
+        self
+    }
+
+    /// Records the timings of the queries of the rounds with `profiler`.
+    pub fn with_profiler(mut self, profiler: Arc<SubmissionProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Registers a hook run before a checkpoint is submitted. If it fails, the submission
+    /// is abandoned and retried in the next round.
+    pub fn on_before_submit<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(BottomUpCheckpoint) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_before_submit(f);
+        self
+    }
+
+    /// Registers a hook run after a checkpoint is submitted successfully.
+    pub fn on_success<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(SubmissionSuccess) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_success(f);
+        self
+    }
+
+    /// Registers a hook run after the submission of a checkpoint failed.
+    pub fn on_failure<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(SubmissionFailure) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_failure(f);
+        self
+    }
+
+    /// Registers a hook run when a checkpoint is refused by a submission policy.
+    pub fn on_refused<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(SubmissionRefused) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_refused(f);
+        self
+    }
+
+    /// Registers a hook run when an observer finds a committed checkpoint diverging from the
+    /// child chain.
+    pub fn on_divergence<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(CheckpointDivergence) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_divergence(f);
+        self
+    }
+
+    /// Registers a hook run when the child subnet is found killed or de-registered in the
+    /// parent, right before the manager stops.
+    pub fn on_terminated<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(SubnetTermination) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.hooks.on_terminated(f);
+        self
+    }
+
+    /// Checks that the child subnet is still active in the parent every `interval`, and after
+    /// every failed round, stopping the manager once it is killed or de-registered.
+    pub fn with_status_check_interval(mut self, interval: Duration) -> Self {
+        self.status_check_interval = interval;
+        self
+    }
+
+    /// Waits for the turn given by `pacer` before every submission, to share the transaction
+    /// budget of the blocks of the parent with the other relayers paced by it.
+    pub fn with_pacer(mut self, pacer: Arc<SubmissionPacer>) -> Self {
+        self.pacer = Some(pacer);
+        self
+    }
+
+    /// Relays the checkpoints of the subnet before the ones of lower `priority` when they are
+    /// paced, whatever their lag.
+    pub fn with_submission_priority(mut self, priority: SubmissionPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Checks the bundles against `limits` before submitting them, instead of the limits of the
+    /// default contracts and parent nodes.
+    pub fn with_bundle_limits(mut self, limits: BundleLimits) -> Self {
+        self.bundle_limits = limits;
+        self
+    }
+
+    /// Opens the circuit breakers of the parent and child endpoints after `failure_threshold`
+    /// consecutive failures, failing fast during `cool_down`.
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cool_down: Duration) -> Self {
+        self.parent_breaker = CircuitBreaker::new(
+            self.metadata.parent.id.to_string(),
+            failure_threshold,
+            cool_down,
+        )
+        .with_clock(self.clock.clone());
+        self.child_breaker = CircuitBreaker::new(
+            self.metadata.child.id.to_string(),
+            failure_threshold,
+            cool_down,
+        )
+        .with_clock(self.clock.clone());
+        self
+    }
+
+    /// Schedules the rounds, times out the calls and submissions and cools down the circuit
+    /// breakers with `clock`, e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.parent_breaker = self.parent_breaker.with_clock(clock.clone());
+        self.child_breaker = self.child_breaker.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+}
+
+/// A handler of any backend.
+pub type DynCheckpointRelayer = Box<dyn BottomUpCheckpointRelayer>;
+
+/// A client of the gateway of a child subnet of any runtime.
+pub type DynChildGatewayClient = Box<dyn ChildGatewayClient>;
+
+impl BottomUpCheckpointManager<DynCheckpointRelayer, DynChildGatewayClient> {
+    /// Creates the manager with boxed handlers, whose backends can be picked at runtime.
+    pub async fn new_dyn(
+        parent: Subnet,
+        child: Subnet,
+        parent_handler: impl BottomUpCheckpointRelayer + 'static,
+        child_handler: impl ChildGatewayClient + 'static,
+    ) -> Result<Self> {
+        Self::new(
+            parent,
+            child,
+            Box::new(parent_handler),
+            Box::new(child_handler),
+        )
+        .await
+    }
+}
+
+impl BottomUpCheckpointManager<EthSubnetManager> {
+    /// Creates the manager with evm handlers. If a `journal` is passed, all the checkpoint
+    /// submissions are recorded in it so that they can be recovered after a crash.
+    /// The strategies of the handlers are picked from the features of their endpoints.
+    pub async fn new_evm_manager(
+        parent: Subnet,
+        child: Subnet,
+        keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+        journal: Option<Arc<TxJournal>>,
+    ) -> Result<Self> {
+        let mut parent_handler =
+            EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?
+                .with_detected_capabilities()
+                .await;
+        if let Some(journal) = journal {
+            parent_handler = parent_handler.with_journal(journal);
+        }
+        let child_handler =
+            EthSubnetManager::from_subnet_with_wallet_store(&child, Some(keystore))?
+                .with_detected_capabilities()
+                .await;
+        let child_head =
+            child_handler.chain_head_tracker(child.id.to_string(), DEFAULT_HEAD_POLL_INTERVAL);
+        Ok(Self::new(parent, child, parent_handler, child_handler)
+            .await?
+            .with_child_head(child_head))
+    }
+}
+
+impl<C: ChildGatewayClient> BottomUpCheckpointManager<EthSubnetManager, C> {
+    /// Submits the checkpoints of the address of `signer` with it instead of with the keystore.
+    pub fn with_signer(mut self, signer: EvmSigner) -> Self {
+        self.parent_handler = self.parent_handler.with_signer(signer);
+        self
+    }
+
+    /// Submits the checkpoints through `forwarder`, paid for by its payer instead of the
+    /// submitter.
+    pub fn with_forwarder(mut self, forwarder: Forwarder) -> Self {
+        self.parent_handler = self.parent_handler.with_forwarder(forwarder);
+        self
+    }
+
+    /// Records every submission attempt in `audit`, with its exact calldata and the checkpoint
+    /// it encodes.
+    pub fn with_audit(mut self, audit: Arc<SubmissionAudit>) -> Self {
+        self.parent_handler = self.parent_handler.with_audit(audit);
+        self
+    }
+
+    /// Profiles the rounds, including the transactions of the submissions, and logs a summary
+    /// of their timings every `summary_interval`. The timings are labelled with the current
+    /// metrics label.
+    pub fn with_profiling(mut self, summary_interval: Duration) -> Self {
+        let profiler = Arc::new(SubmissionProfiler::new(
+            self.metrics_label.clone(),
+            summary_interval,
+        ));
+        self.parent_handler = self.parent_handler.with_profiler(profiler.clone());
+        self.with_profiler(profiler)
+    }
+}
+
+impl<P: BottomUpCheckpointRelayer, C: ChildGatewayClient> Display
+    for BottomUpCheckpointManager<P, C>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "bottom-up relayer, parent: {:}, child: {:}",
+            self.metadata.parent.id, self.metadata.child.id
+        )
+    }
+}
+
+impl<P, C> BottomUpCheckpointManager<P, C>
+where
+    P: BottomUpCheckpointRelayer + Send + Sync + 'static,
+    C: ChildGatewayClient + Send + Sync + 'static,
+{
+    /// Getter for the parent subnet this checkpoint manager is handling
+    pub fn parent_subnet(&self) -> &Subnet {
+        &self.metadata.parent
+    }
+
+    /// Getter for the target subnet this checkpoint manager is handling
+    pub fn child_subnet(&self) -> &Subnet {
+        &self.metadata.child
+    }
+
+    /// The checkpoint period that the current manager is submitting upon, as of the last round
+    pub fn checkpoint_period(&self) -> ChainEpoch {
+        self.metadata.period.load(Ordering::Relaxed)
+    }
+
+    /// Records the checkpoint `period` read from the parent in a round, returning the previous
+    /// one if it changed on chain since the last round.
+    fn update_period(&self, period: ChainEpoch) -> Option<ChainEpoch> {
+        let previous = self.metadata.period.swap(period, Ordering::Relaxed);
+        if previous == period {
+            return None;
+        }
+        log::warn!(
+            "checkpoint period of {} changed from {previous} to {period}",
+            self.metadata.child.id
+        );
+        Some(previous)
+    }
+
+    /// Run the bottom up checkpoint submission daemon in the foreground, until the child subnet
+    /// is killed or de-registered in the parent
+    pub async fn run(self, submitter: Address, submission_interval: Duration) {
+        log::info!("launching {self} for {submitter}");
+
+        // Settle the submissions that were in flight when the relayer last stopped before
+        // submitting anything new, so that checkpoints are neither lost nor submitted twice.
+        if let Err(e) = self.parent_handler.reconcile_pending_txs().await {
+            log::error!("cannot reconcile pending transactions: {e}");
+        }
+
+        if !self.phase_offset.is_zero() {
+            log::info!(
+                "delaying the first round of {self} by {:?}",
+                self.phase_offset
+            );
+            self.clock.sleep(self.phase_offset).await;
+        }
+
+        let mut status_checked = None;
+        let mut failed = false;
+        loop {
+            if self.terminated(&mut status_checked, failed).await {
+                return;
+            }
+
+            failed = true;
+            match timeout(
+                self.clock.as_ref(),
+                self.submission_timeout,
+                self.submit_checkpoint(&submitter),
+            )
+            .await
+            {
+                Ok(Ok(())) => failed = false,
+                Ok(Err(e)) => {
+                    monitor::RELAYER_FAILED_SUBMISSIONS
+                        .with_label_values(&[&self.metrics_label])
+                        .inc();
+                    log::error!("cannot submit checkpoint for submitter: {submitter} due to {e}");
+                }
+                Err(_) => {
+                    monitor::RELAYER_TIMED_OUT_SUBMISSIONS
+                        .with_label_values(&[&self.metrics_label])
+                        .inc();
+                    log::error!(
+                        "submission attempt for submitter: {submitter} timed out after {:?}",
+                        self.submission_timeout
+                    );
+                }
+            }
+
+            if let Some(profiler) = &self.profiler {
+                profiler.log_summary_if_due();
+            }
+
+            self.clock
+                .sleep(jittered(submission_interval, self.submission_jitter))
+                .await;
+        }
+    }
+
+    /// Whether the child subnet was killed or de-registered in the parent, checked if the last
+    /// round `failed` or if the last check, at `checked`, is older than the check interval. The
+    /// `terminated` hooks are run when it is.
+    async fn terminated(&self, checked: &mut Option<Instant>, failed: bool) -> bool {
+        let now = self.clock.now();
+        if !failed && checked.is_some_and(|c| now.duration_since(c) < self.status_check_interval) {
+            return false;
+        }
+        *checked = Some(now);
+
+        let child = &self.metadata.child.id;
+        let status = match self
+            .call(
+                &self.parent_breaker,
+                "child_subnet_status",
+                self.parent_handler.child_subnet_status(child),
+            )
+            .await
+        {
+            Ok(status) if status.is_terminal() => status,
+            Ok(_) => return false,
+            Err(e) => {
+                log::warn!("cannot check the status of {child} in the parent: {e}");
+                return false;
+            }
+        };
+
+        let last_committed_height = self
+            .checkpoint_status()
+            .await
+            .map(|s| s.last_committed_height)
+            .unwrap_or_default();
+        log::error!(
+            "subnet {child} is {status} in the parent after checkpoint({last_committed_height}), \
+             stopping {self}"
+        );
+        self.hooks
+            .terminated(SubnetTermination {
+                subnet: child.clone(),
+                status,
+                last_committed_height,
+            })
+            .await;
+        true
+    }
+
+    /// The current height of the child, from its head tracker if there is one.
+    async fn child_height(&self) -> Result<ChainEpoch> {
+        match &self.child_head {
+            Some(tracker) => {
+                self.call(&self.child_breaker, "current_epoch", tracker.latest())
+                    .await
+            }
+            None => {
+                self.call(
+                    &self.child_breaker,
+                    "current_epoch",
+                    self.child_handler.current_epoch(),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Submit the checkpoint from the target submitter address
+    pub async fn submit_checkpoint(&self, submitter: &Address) -> Result<()> {
+        // the period is read every round, and the heights of the round follow from it and from
+        // the last committed height, so a change on chain applies from the next checkpoint on
+        let status = self.checkpoint_status().await?;
+        self.update_period(status.period);
+        let planner = self.planner(status.period);
+
+        let current_height = self.timed(Phase::HeightFetch, self.child_height()).await?;
+        let behind = periods_behind(status.last_committed_height, current_height, status.period);
+        let urgency = self.urgency(behind);
+
+        // The checkpoints are submitted as they are fetched, and up to `prefetch_limit` of the
+        // next ones are fetched while the previous submissions wait for their receipts. The
+        // fetching waits for the submissions beyond that.
+        let (tx, mut rx) = pipeline(self.prefetch_limit, &self.metrics_label);
+        let fetch = self.fetch_round(&planner, status.last_committed_height, current_height, tx);
+        let submit = async move {
+            let mut round = planner.round();
+            while let Some(fetched) = rx.recv().await {
+                let actions = match fetched {
+                    Fetched::LastCommitted(bundle) => vec![round.last_committed(bundle)],
+                    Fetched::Ready(ready) => round.ready(ready),
+                };
+                for action in actions {
+                    self.execute(submitter, action, urgency, behind).await?;
+                }
+                if round.is_done() {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(round.held())
+        };
+        // a failed fetch does not abort the submission in flight, the checkpoints fetched
+        // before it are still submitted
+        let (fetched, held) = tokio::join!(fetch, submit);
+        let held = held?;
+        fetched?;
+
+        monitor::RELAYER_HELD_EMPTY_CHECKPOINTS
+            .with_label_values(&[&self.metrics_label])
+            .set(held.len() as i64);
+        if !held.is_empty() {
+            log::info!(
+                "holding back {} empty checkpoints until a non-empty one is ready",
+                held.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// The planner of a round with the current checkpoint `period`, from the settings of the
+    /// manager.
+    fn planner(&self, period: ChainEpoch) -> SubmissionPlanner {
+        SubmissionPlanner::new(
+            period,
+            self.finalization_blocks,
+            self.quorum_threshold,
+            self.empty_checkpoints,
+        )
+    }
+
+    /// The fee urgency of the submissions of a round, from how many periods the parent is
+    /// `behind` the child.
+    fn urgency(&self, behind: ChainEpoch) -> Urgency {
+        monitor::RELAYER_PERIODS_BEHIND
+            .with_label_values(&[&self.metrics_label])
+            .set(behind);
+
+        let urgency = self.fee_escalation.urgency(behind);
+        if behind > 0 {
+            log::info!(
+                "{} checkpoint periods behind the child, submitting with {urgency:?} urgency",
+                behind
+            );
+        }
+        urgency
+    }
+
+    /// Runs a query against one of the subnets through its circuit breaker, failing if it
+    /// does not complete within the call timeout.
+    async fn call<F, R>(&self, breaker: &CircuitBreaker, name: &'static str, f: F) -> Result<R>
+    where
+        F: Future<Output = Result<R>>,
+    {
+        breaker
+            .call(async {
+                match timeout(self.clock.as_ref(), self.call_timeout, f).await {
+                    Ok(r) => r,
+                    Err(_) => {
+                        monitor::RELAYER_TIMED_OUT_CALLS
+                            .with_label_values(&[&self.metrics_label, name])
+                            .inc();
+                        Err(anyhow!("{name} timed out after {:?}", self.call_timeout))
+                    }
+                }
+            })
+            .await
+    }
+
+    /// Runs `f`, recording its timing in `phase` if profiling.
+    async fn timed<F: Future>(&self, phase: Phase, f: F) -> F::Output {
+        timed(self.profiler.as_deref(), phase, f).await
+    }
+
+    /// The current checkpoint period and the height of the last checkpoint committed in the
+    /// parent, read at once if the parent handler supports it.
+    async fn checkpoint_status(&self) -> Result<CheckpointStatus> {
+        self.timed(
+            Phase::HeightFetch,
+            self.call(
+                &self.parent_breaker,
+                "checkpoint_status",
+                self.parent_handler
+                    .checkpoint_status(&self.metadata.child.id),
+            ),
+        )
+        .await
+        .map_err(|e| anyhow!("cannot obtain the last bottom up checkpoint height due to: {e:}"))
+    }
+
+    /// Queries the last committed checkpoint, and the checkpoints that reached their quorum
+    /// since then up to `current_height`, from the child, sending them to the submissions in
+    /// order. Stops early if the submissions are over.
+    async fn fetch_round(
+        &self,
+        planner: &SubmissionPlanner,
+        last_committed: ChainEpoch,
+        current_height: ChainEpoch,
+        tx: PipelineSender<Fetched>,
+    ) -> Result<()> {
+        if last_committed == 0 {
+            log::debug!(target: SCANNER_TARGET, "no previous checkpoint yet");
+        } else {
+            let bundle = self
+                .timed(
+                    Phase::BundleFetch,
+                    self.call(
+                        &self.child_breaker,
+                        "checkpoint_bundle_at",
+                        self.child_handler.checkpoint_bundle_at(last_committed),
+                    ),
+                )
+                .await?;
+            log::debug!(target: SCANNER_TARGET, "bottom up bundle: {bundle:?}");
+            if tx.send(Fetched::LastCommitted(bundle)).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        log::debug!(
+            target: SCANNER_TARGET,
+            "last committed height: {last_committed}, current height: {current_height}"
+        );
+
+        match planner.scan_range(last_committed, current_height)? {
+            Some(range) => self.fetch_ready_checkpoints(planner, range, tx).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Fetches the checkpoints of the quorum events in the `range` of heights of the child, up
+    /// to the first one that cannot be submitted yet, sending them to the submissions.
+    async fn fetch_ready_checkpoints(
+        &self,
+        planner: &SubmissionPlanner,
+        range: RangeInclusive<ChainEpoch>,
+        tx: PipelineSender<Fetched>,
+    ) -> Result<()> {
+        log::debug!(
+            target: SCANNER_TARGET,
+            "start querying quorum reached events from : {} to {}",
+            range.start(),
+            range.end()
+        );
+
+        let mut start = *range.start();
+        while start <= *range.end() {
+            let end = min(*range.end(), start + self.rpc_batch_size as ChainEpoch - 1);
+            let heights = (start..=end).collect::<Vec<_>>();
+            start = end + 1;
+
+            let events_at = self
+                .timed(
+                    Phase::EventScan,
+                    self.call(
+                        &self.child_breaker,
+                        "quorum_reached_events",
+                        self.child_handler.quorum_reached_events_at(&heights),
+                    ),
+                )
+                .await?;
+
+            let mut events = vec![];
+            for (h, mut found) in heights.iter().zip(events_at) {
+                // the quorum events of the other certificates are relayed by their own relayers
+                found.retain(|e| e.obj_kind == CHECKPOINT_OBJ_KIND);
+                if found.is_empty() {
+                    log::debug!(target: SCANNER_TARGET, "no reached events at height : {h}");
+                    continue;
+                }
+
+                log::debug!(target: SCANNER_TARGET, "found reached events at height : {h}");
+                self.measure_quorum_formation(*h, &found).await;
+
+                if let Some(history) = &self.history {
+                    for event in &found {
+                        if let Err(e) = history
+                            .record_quorum_event(&self.metadata.child.id, event)
+                            .await
+                        {
+                            log::error!(
+                                target: SCANNER_TARGET,
+                                "cannot record quorum event in history: {e}"
+                            );
+                        }
+                    }
+                }
+                events.extend(found);
+            }
+            if events.is_empty() {
+                continue;
+            }
+
+            let bundle_heights = events.iter().map(|e| e.height).collect::<Vec<_>>();
+            let bundles = self
+                .timed(
+                    Phase::BundleFetch,
+                    self.call(
+                        &self.child_breaker,
+                        "checkpoint_bundle_at",
+                        self.child_handler.checkpoint_bundles_at(&bundle_heights),
+                    ),
+                )
+                .await?;
+
+            for (event, bundle) in events.into_iter().zip(bundles) {
+                log::debug!(target: SCANNER_TARGET, "bottom up bundle: {bundle:?}");
+                let event = event.with_bundle(&bundle);
+                monitor::RELAYER_QUORUM_SIGNATORIES
+                    .with_label_values(&[&self.metrics_label])
+                    .set(bundle.signatories.len() as i64);
+
+                let quorum = if planner.needs_quorum() {
+                    Some(
+                        self.call(
+                            &self.child_breaker,
+                            "checkpoint_quorum_at",
+                            self.child_handler.checkpoint_quorum_at(event.height),
+                        )
+                        .await?,
+                    )
+                } else {
+                    None
+                };
+
+                let reaches_quorum = planner.reaches_quorum(quorum.as_ref());
+                let sent = tx
+                    .send(Fetched::Ready(ReadyCheckpoint {
+                        event,
+                        bundle,
+                        quorum,
+                    }))
+                    .await
+                    .is_ok();
+                // the submissions are over, or the checkpoints are committed in order and the
+                // later ones wait for this one
+                if !sent || !reaches_quorum {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the time the validators took to reach the quorum of the checkpoints of the
+    /// `events` found in the child block at `height`, the first time they are found. It is
+    /// only measured, a failure does not fail the round.
+    async fn measure_quorum_formation(&self, height: ChainEpoch, events: &[QuorumReachedEvent]) {
+        let mut reached_at = None;
+        for event in events {
+            if self.quorum_latency.is_recorded(event.height) {
+                continue;
+            }
+            let timestamps = async {
+                let reached = match reached_at {
+                    Some(t) => t,
+                    None => self.block_timestamp_at(height).await?,
+                };
+                Ok::<_, anyhow::Error>((reached, self.block_timestamp_at(event.height).await?))
+            };
+            match timestamps.await {
+                Ok((reached, checkpoint)) => {
+                    reached_at = Some(reached);
+                    self.quorum_latency.record(event.height, reached);
+                    monitor::RELAYER_QUORUM_FORMATION_SECONDS
+                        .with_label_values(&[&self.metrics_label])
+                        .observe(reached.saturating_sub(checkpoint) as f64);
+                }
+                Err(e) => log::debug!(
+                    target: SCANNER_TARGET,
+                    "cannot measure the quorum formation of checkpoint({}): {e}",
+                    event.height
+                ),
+            }
+        }
+    }
+
+    async fn block_timestamp_at(&self, height: ChainEpoch) -> Result<u64> {
+        self.call(
+            &self.child_breaker,
+            "block_timestamp_at",
+            self.child_handler.block_timestamp_at(height),
+        )
+        .await
+    }
+
+    /// Executes an action of the plan of a round.
+    async fn execute(
+        &self,
+        submitter: &Address,
+        action: SubmissionAction,
+        urgency: Urgency,
+        behind: ChainEpoch,
+    ) -> Result<()> {
+        match action {
+            SubmissionAction::Submit(bundle) => {
+                self.submit_bundle(submitter, bundle, urgency, behind, true)
+                    .await
+            }
+            SubmissionAction::Resubmit(bundle) => {
+                self.submit_bundle(submitter, bundle, urgency, behind, false)
+                    .await
+            }
+            SubmissionAction::SkipEmpty(height) => {
+                log::debug!(
+                    target: SUBMITTER_TARGET,
+                    "skipping the re-submission of empty checkpoint({height})"
+                );
+                monitor::RELAYER_SKIPPED_EMPTY_CHECKPOINTS
+                    .with_label_values(&[&self.metrics_label])
+                    .inc();
+                Ok(())
+            }
+            SubmissionAction::WaitForQuorum {
+                height,
+                current_weight,
+            } => {
+                log::info!(
+                    target: SUBMITTER_TARGET,
+                    "checkpoint({height}) signature weight {current_weight} below the {}% threshold, delaying submission",
+                    self.quorum_threshold.unwrap_or_default()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Fails if `checkpoint` violates any of the submission policies, after alerting about it.
+    async fn check_policies(&self, checkpoint: &BottomUpCheckpoint) -> Result<()> {
+        let mut violations = vec![];
+        for policy in &self.policies {
+            violations.extend(policy.check(checkpoint).await?);
+        }
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let height = checkpoint.block_height;
+        let reasons = violations
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        monitor::RELAYER_REFUSED_CHECKPOINTS
+            .with_label_values(&[&self.metrics_label])
+            .inc();
+        log::error!(
+            target: SUBMITTER_TARGET,
+            "refusing to relay checkpoint({height}) violating the submission policy: {reasons}"
+        );
+        self.hooks
+            .refused(SubmissionRefused {
+                checkpoint: checkpoint.clone(),
+                violations,
+            })
+            .await;
+        Err(anyhow!(
+            "checkpoint({height}) refused by the submission policy: {reasons}"
+        ))
+    }
+
+    /// Archives the submission of `bundle`, the errors are only logged as they do not affect
+    /// the relaying.
+    async fn archive_submission(
+        &self,
+        archive: &CheckpointArchive,
+        bundle: BottomUpCheckpointBundle,
+        receipt: &CheckpointReceipt,
+    ) {
+        let height = bundle.checkpoint.block_height;
+        let archived = match self.parent_handler.submission_proof(receipt).await {
+            Ok(proof) => {
+                let bundle =
+                    ProofBundle::new(&self.metadata.child.id, bundle, receipt.clone(), proof);
+                archive.archive(&self.metadata.child.id, &bundle)
+            }
+            Err(e) => Err(e),
+        };
+        match archived {
+            Ok(true) => {}
+            Ok(false) => log::warn!(
+                target: SUBMITTER_TARGET,
+                "checkpoint({height}) archived already, keeping the archived submission"
+            ),
+            Err(e) => log::error!(
+                target: SUBMITTER_TARGET,
+                "cannot archive the submission of checkpoint({height}): {e}"
+            ),
+        }
+    }
+
+    /// Submits `bundle` to the parent. The submission is only archived, and the success hooks
+    /// only run, if it `commits` the checkpoint, not for the re-submission of the last
+    /// committed one.
+    async fn submit_bundle(
+        &self,
+        submitter: &Address,
+        bundle: BottomUpCheckpointBundle,
+        urgency: Urgency,
+        behind: ChainEpoch,
+        commits: bool,
+    ) -> Result<()> {
+        let height = bundle.checkpoint.block_height;
+        let checkpoint = bundle.checkpoint.clone();
+        if let Err(e) = self.bundle_limits.check(&bundle) {
+            // the parent would reject the bundle on every attempt, without saying why
+            self.hooks
+                .failure(SubmissionFailure {
+                    checkpoint,
+                    error: e.to_string(),
+                })
+                .await;
+            return Err(anyhow!(
+                "checkpoint({height}) breaks the limits of the parent: {e}"
+            ));
+        }
+        self.check_policies(&checkpoint).await?;
+        self.hooks.before_submit(&checkpoint).await?;
+
+        if let Some(pacer) = &self.pacer {
+            pacer.acquire(self.priority, behind).await;
+        }
+
+        let attempt = match &self.history {
+            Some(history) => history
+                .record_attempt(&self.metadata.child.id, height, submitter)
+                .await
+                .map_err(|e| {
+                    log::error!(
+                        target: SUBMITTER_TARGET,
+                        "cannot record submission attempt in history: {e}"
+                    )
+                })
+                .ok(),
+            None => None,
+        };
+
+        // the bundle is consumed by the submission, only the one committing it is archived
+        let archived = self
+            .archive
+            .as_ref()
+            .filter(|_| commits)
+            .map(|_| bundle.clone());
+        // not bounded by the call timeout, the transaction may wait for its receipt longer
+        let result = self
+            .parent_handler
+            .submit_checkpoint_with_urgency(
+                submitter,
+                bundle.checkpoint,
+                bundle.signatures,
+                bundle.signatories,
+                urgency,
+            )
+            .await;
+
+        if let (Some(history), Some(attempt)) = (&self.history, attempt) {
+            let r = result.as_ref().map(|r| r.epoch).map_err(|e| e.to_string());
+            if let Err(e) = history.record_result(&attempt, r).await {
+                log::error!(
+                    target: SUBMITTER_TARGET,
+                    "cannot record submission result in history: {e}"
+                );
+            }
+        }
+
+        let receipt = match result {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                self.hooks
+                    .failure(SubmissionFailure {
+                        checkpoint,
+                        error: e.to_string(),
+                    })
+                    .await;
+                return Err(anyhow!("cannot submit bottom up checkpoint due to: {e:}"));
+            }
+        };
+        monitor::RELAYER_SUBMITTED_CHECKPOINTS
+            .with_label_values(&[&self.metrics_label])
+            .inc();
+        let now = self
+            .clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if let Some(seconds) = self.quorum_latency.submitted(height, now) {
+            monitor::RELAYER_QUORUM_TO_SUBMISSION_SECONDS
+                .with_label_values(&[&self.metrics_label])
+                .observe(seconds as f64);
+        }
+        log::info!(
+            target: SUBMITTER_TARGET,
+            "submitted bottom up checkpoint({}) in parent at height {} in tx {}",
+            height,
+            receipt.epoch,
+            receipt.tx_hash
+        );
+        if !commits {
+            return Ok(());
+        }
+        if let (Some(archive), Some(bundle)) = (&self.archive, archived) {
+            self.archive_submission(archive, bundle, &receipt).await;
+        }
+        self.hooks
+            .success(SubmissionSuccess {
+                checkpoint,
+                receipt,
+            })
+            .await;
+        Ok(())
+    }
+}
+
+/// A checkpoint fetched for the submissions of a round.
+enum Fetched {
+    /// The last checkpoint committed in the parent.
+    LastCommitted(BottomUpCheckpointBundle),
+    /// The next checkpoint that reached its quorum in the child.
+    Ready(ReadyCheckpoint),
+}
+
+/// Posts the event to the webhooks in the background, so that slow receivers do not hold the
+/// relayer back.
+async fn notify(dispatcher: Arc<WebhookDispatcher>, event: WebhookEvent) -> Result<()> {
+    tokio::spawn(async move {
+        if let Err(e) = dispatcher.dispatch(&event).await {
+            log::error!(
+                target: SUBMITTER_TARGET,
+                "cannot notify checkpoint({}) event: {e}",
+                event.height()
+            );
+        }
+    });
+    Ok(())
+}
+
+/// The checkpoint period set in the gateway of the `child` subnet, checking that the child
+/// handler does query its gateway.
+async fn child_checkpoint_period(
+    child_handler: &impl ChildGatewayClient,
+    child: &SubnetID,
+) -> Result<ChainEpoch> {
+    let served = child_handler.gateway_subnet_id().await?;
+    if served != *child {
+        return Err(anyhow!(
+            "the child endpoint serves {served} instead of {child}"
+        ));
+    }
+    child_handler.gateway_checkpoint_period().await
+}
+
+/// A random delay uniformly picked within `interval ± jitter`, never negative.
+fn jittered(interval: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return interval;
+    }
+    let low = interval.saturating_sub(jitter);
+    let high = interval.saturating_add(jitter);
+    rand::thread_rng().gen_range(low..=high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{jittered, BottomUpCheckpointManager, DynCheckpointRelayer, DynChildGatewayClient};
+    use crate::jsonrpc::JsonRpcClientImpl;
+    use crate::lotus::client::LotusJsonRPCClient;
+    use crate::manager::{BottomUpCheckpointRelayer, ChildGatewayClient, EthSubnetManager};
+    use fvm_shared::address::Address;
+    use std::time::Duration;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_managers_are_send_sync() {
+        assert_send_sync::<EthSubnetManager>();
+        assert_send_sync::<DynCheckpointRelayer>();
+        assert_send_sync::<LotusJsonRPCClient<JsonRpcClientImpl>>();
+        assert_send_sync::<BottomUpCheckpointManager<EthSubnetManager>>();
+        assert_send_sync::<BottomUpCheckpointManager<EthSubnetManager, DynCheckpointRelayer>>();
+        assert_send_sync::<BottomUpCheckpointManager<EthSubnetManager, DynChildGatewayClient>>();
+    }
+
+    /// Never called, it does not compile if the manager cannot run under `tokio::spawn`.
+    #[allow(dead_code)]
+    fn assert_spawnable<P, C>(
+        relayer: BottomUpCheckpointManager<P, C>,
+        round: BottomUpCheckpointManager<P, C>,
+        observer: BottomUpCheckpointManager<P, C>,
+        submitter: Address,
+    ) where
+        P: BottomUpCheckpointRelayer + 'static,
+        C: ChildGatewayClient + 'static,
+    {
+        tokio::spawn(relayer.run(submitter, Duration::from_secs(1)));
+        tokio::spawn(async move { round.submit_checkpoint(&submitter).await });
+        tokio::spawn(observer.run_observer(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_jittered_interval() {
+        let interval = Duration::from_secs(15);
+        assert_eq!(jittered(interval, Duration::ZERO), interval);
+
+        for _ in 0..100 {
+            let delay = jittered(interval, Duration::from_secs(5));
+            assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(20));
+        }
+
+        // a jitter larger than the interval never makes the delay negative
+        for _ in 0..100 {
+            assert!(jittered(interval, Duration::from_secs(60)) <= Duration::from_secs(75));
+        }
+    }
+}
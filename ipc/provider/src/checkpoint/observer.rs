@@ -0,0 +1,192 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Observer mode of the checkpoint manager: instead of submitting, it watches the checkpoints
+//! committed in the parent by other relayers and verifies them against the child.
+//!
+//! Besides the checkpoint of the child at the same height, the committed block hash is checked
+//! against the child chain itself, so that a divergence raises an alert (metrics, the divergence
+//! hooks and webhooks) as early warning of an equivocation of the child validators or of a
+//! tampering by a relayer.
+
+use super::heights::committed_candidates;
+use super::hooks::{CheckpointDivergence, DivergenceKind};
+use super::BottomUpCheckpointManager;
+use crate::manager::{BottomUpCheckpointRelayer, ChildGatewayClient};
+use crate::monitor;
+use anyhow::Result;
+use ethers::abi::Tokenizable;
+use ethers::utils::keccak256;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::subnet_actor_getter_facet;
+use ipc_api::cross::IpcEnvelope;
+use std::time::Duration;
+
+impl<P, C> BottomUpCheckpointManager<P, C>
+where
+    P: BottomUpCheckpointRelayer + Send + Sync + 'static,
+    C: ChildGatewayClient + Send + Sync + 'static,
+{
+    /// Run the checkpoint commitment watcher in the foreground, without submitting anything,
+    /// until the child subnet is killed or de-registered in the parent.
+    pub async fn run_observer(self, poll_interval: Duration) {
+        log::info!("launching observer for {self}");
+
+        let mut last_verified = None;
+        let mut status_checked = None;
+        let mut failed = false;
+        loop {
+            if self.terminated(&mut status_checked, failed).await {
+                return;
+            }
+
+            failed = true;
+            match self.observe(last_verified).await {
+                Ok(h) => {
+                    last_verified = h;
+                    failed = false;
+                }
+                Err(e) => log::error!("cannot verify committed checkpoints: {e}"),
+            }
+
+            self.clock.sleep(poll_interval).await;
+        }
+    }
+
+    /// Verifies the checkpoints committed since `last_verified`, returning the last height
+    /// verified.
+    async fn observe(&self, last_verified: Option<ChainEpoch>) -> Result<Option<ChainEpoch>> {
+        let status = self.checkpoint_status().await?;
+        let last_committed = status.last_committed_height;
+        let previous = self.update_period(status.period).unwrap_or(status.period);
+        let current_height = self.child_height().await?;
+        monitor::OBSERVER_COMMIT_LAG
+            .with_label_values(&[&self.metrics_label])
+            .set(current_height - last_committed);
+
+        if last_committed == 0 {
+            log::debug!("no checkpoint committed yet");
+            return Ok(last_verified);
+        }
+
+        // Checkpoints are usually committed every period, but a full batch of messages can be
+        // committed in between, so the heights without a commitment are skipped.
+        let mut heights = match last_verified {
+            Some(h) => committed_candidates(h, last_committed, previous, status.period)?,
+            None => vec![],
+        };
+        if last_verified != Some(last_committed) {
+            heights.push(last_committed);
+        }
+
+        for height in heights {
+            self.verify_committed(height).await?;
+        }
+
+        Ok(Some(last_committed))
+    }
+
+    /// Compares the checkpoint committed in the parent at `height` with the one of the child.
+    async fn verify_committed(&self, height: ChainEpoch) -> Result<()> {
+        let committed = self
+            .call(
+                &self.parent_breaker,
+                "committed_checkpoint_at",
+                self.parent_handler
+                    .committed_checkpoint_at(&self.metadata.child.id, height),
+            )
+            .await?;
+        let Some(committed) = committed else {
+            return Ok(());
+        };
+
+        let bundle = self
+            .call(
+                &self.child_breaker,
+                "checkpoint_bundle_at",
+                self.child_handler.checkpoint_bundle_at(height),
+            )
+            .await?;
+
+        let chain_block_hash = self
+            .call(
+                &self.child_breaker,
+                "block_hash_at",
+                self.child_handler.block_hash_at(height),
+            )
+            .await?;
+
+        let expected = bundle.checkpoint;
+        let committed_messages_root = messages_root(&committed.msgs)?;
+        let expected_messages_root = messages_root(&expected.msgs)?;
+
+        let mut kinds = vec![];
+        if committed.block_hash != chain_block_hash {
+            kinds.push(DivergenceKind::BlockHash);
+        }
+        if committed_messages_root != expected_messages_root {
+            kinds.push(DivergenceKind::MessagesRoot);
+        }
+        if kinds.is_empty() && committed != expected {
+            kinds.push(DivergenceKind::Checkpoint);
+        }
+
+        if kinds.is_empty() {
+            log::debug!("committed checkpoint({height}) matches the child");
+            monitor::OBSERVER_VERIFIED_CHECKPOINTS
+                .with_label_values(&[&self.metrics_label])
+                .inc();
+            monitor::OBSERVER_LAST_VERIFIED_HEIGHT
+                .with_label_values(&[&self.metrics_label])
+                .set(height);
+            return Ok(());
+        }
+
+        log::error!(
+            "DIVERGENCE: committed checkpoint({height}) of {} does not match the child chain ({}), committed block hash: {}, chain block hash: {}, committed messages root: {}, expected messages root: {}",
+            self.metadata.child.id,
+            kinds
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            hex::encode(&committed.block_hash),
+            hex::encode(&chain_block_hash),
+            hex::encode(committed_messages_root),
+            hex::encode(expected_messages_root),
+        );
+        monitor::OBSERVER_DIVERGENT_CHECKPOINTS
+            .with_label_values(&[&self.metrics_label])
+            .inc();
+        monitor::OBSERVER_LAST_DIVERGENT_HEIGHT
+            .with_label_values(&[&self.metrics_label])
+            .set(height);
+        for kind in &kinds {
+            monitor::OBSERVER_DIVERGENCE_ALERTS
+                .with_label_values(&[&self.metrics_label, &kind.to_string()])
+                .inc();
+        }
+
+        self.hooks
+            .divergence(CheckpointDivergence {
+                committed,
+                expected,
+                chain_block_hash,
+                committed_messages_root,
+                expected_messages_root,
+                kinds,
+            })
+            .await;
+
+        Ok(())
+    }
+}
+
+/// The keccak hash of the abi encoded messages, as the contracts compute it.
+fn messages_root(msgs: &[IpcEnvelope]) -> Result<[u8; 32]> {
+    let msgs = msgs
+        .iter()
+        .cloned()
+        .map(subnet_actor_getter_facet::IpcEnvelope::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(keccak256(ethers::abi::encode(&[msgs.into_token()])))
+}
@@ -0,0 +1,252 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Pacing of the checkpoint submissions of the relayers sharing a parent.
+//!
+//! When many subnets need a submission at once, e.g. after an outage of the parent, their
+//! relayers all send their transactions in the same blocks and bid the base fee of the parent
+//! up against each other. A [`SubmissionPacer`] shared by the relayers of a parent admits at
+//! most a budget of submissions per parent block, measured with the block time of the parent,
+//! and lets the subnets of the highest [`SubmissionPriority`] go first, then the ones furthest
+//! behind.
+
+use crate::clock::{default_clock, Clock};
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// The priority of the submissions of a subnet when they are paced, e.g. to relay the
+/// production subnets before the experimental ones.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// The submissions waiting for their turn, the highest priority first, then the most periods
+/// behind, then in the order they arrived.
+type Ticket = (Reverse<SubmissionPriority>, Reverse<ChainEpoch>, u64);
+
+/// Admits at most `budget` submissions to the parent per block.
+pub struct SubmissionPacer {
+    budget: usize,
+    block_time: Duration,
+    clock: Arc<dyn Clock>,
+    state: Mutex<PacerState>,
+    turn: Notify,
+}
+
+#[derive(Debug, Default)]
+struct PacerState {
+    /// The start of the block the submissions are admitted in.
+    block_start: Option<Instant>,
+    admitted: usize,
+    waiting: BTreeSet<Ticket>,
+    next_ticket: u64,
+}
+
+impl PacerState {
+    /// Moves to the block of `now`, returning the time it ends at.
+    fn block_end(&mut self, now: Instant, block_time: Duration) -> Instant {
+        let start = match self.block_start {
+            Some(start) if now < start + block_time => start,
+            Some(start) => {
+                let blocks = (now - start).as_nanos() / block_time.as_nanos();
+                start + block_time * blocks as u32
+            }
+            None => now,
+        };
+        if self.block_start != Some(start) {
+            self.block_start = Some(start);
+            self.admitted = 0;
+        }
+        start + block_time
+    }
+}
+
+impl SubmissionPacer {
+    /// Admits at most `budget` submissions every `block_time` of the parent.
+    pub fn new(budget: usize, block_time: Duration) -> Result<Self> {
+        if budget == 0 {
+            return Err(anyhow!("the submission budget per block must be positive"));
+        }
+        if block_time.is_zero() {
+            return Err(anyhow!("the block time of the parent must be positive"));
+        }
+        Ok(Self {
+            budget,
+            block_time,
+            clock: default_clock(),
+            state: Mutex::new(PacerState::default()),
+            turn: Notify::new(),
+        })
+    }
+
+    /// Measures the blocks with `clock`, e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Waits for the turn of a submission of `priority` of a subnet `periods_behind` the
+    /// parent. A submission cancelled while waiting gives its turn to the next one.
+    pub async fn acquire(&self, priority: SubmissionPriority, periods_behind: ChainEpoch) {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = (
+                Reverse(priority),
+                Reverse(periods_behind),
+                state.next_ticket,
+            );
+            state.next_ticket += 1;
+            state.waiting.insert(ticket);
+            ticket
+        };
+        let _waiting = Waiting {
+            pacer: self,
+            ticket,
+        };
+
+        loop {
+            // registered before checking the state, not to miss the turn given in between
+            let turn = self.turn.notified();
+            tokio::pin!(turn);
+            turn.as_mut().enable();
+
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = self.clock.now();
+                let block_end = state.block_end(now, self.block_time);
+                if state.admitted >= self.budget {
+                    Wait::NextBlock(block_end - now)
+                } else if state.waiting.first() == Some(&ticket) {
+                    state.waiting.remove(&ticket);
+                    state.admitted += 1;
+                    Wait::Admitted
+                } else {
+                    Wait::Turn
+                }
+            };
+
+            match wait {
+                Wait::Admitted => {
+                    // the next submission may fit in the budget left
+                    self.turn.notify_waiters();
+                    return;
+                }
+                Wait::Turn => turn.await,
+                Wait::NextBlock(delay) => {
+                    log::debug!("submission budget of the parent block spent, waiting {delay:?}");
+                    tokio::select! {
+                        _ = self.clock.sleep(delay) => {}
+                        _ = turn => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Wait {
+    Admitted,
+    /// Wait for the submissions further behind to be admitted first.
+    Turn,
+    /// Wait for the budget of the next block.
+    NextBlock(Duration),
+}
+
+/// Removes a cancelled submission from the waiting ones.
+struct Waiting<'a> {
+    pacer: &'a SubmissionPacer,
+    ticket: Ticket,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let removed = self
+            .pacer
+            .state
+            .lock()
+            .unwrap()
+            .waiting
+            .remove(&self.ticket);
+        if removed {
+            self.pacer.turn.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SubmissionPacer, SubmissionPriority};
+    use crate::clock::ManualClock;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Queues the submissions behind a spent budget, and returns the order they are
+    /// admitted in, one per block.
+    async fn admission_order(submissions: Vec<(SubmissionPriority, i64)>) -> Vec<i64> {
+        let clock = ManualClock::new();
+        let pacer = Arc::new(
+            SubmissionPacer::new(1, Duration::from_secs(10))
+                .unwrap()
+                .with_clock(Arc::new(clock.clone())),
+        );
+        // the first submission of the block is admitted right away
+        pacer.acquire(SubmissionPriority::Normal, 0).await;
+
+        let admitted = Arc::new(Mutex::new(vec![]));
+        let mut tasks = vec![];
+        for (priority, lag) in submissions {
+            let (pacer, admitted) = (pacer.clone(), admitted.clone());
+            tasks.push(tokio::spawn(async move {
+                pacer.acquire(priority, lag).await;
+                admitted.lock().unwrap().push(lag);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(admitted.lock().unwrap().is_empty());
+
+        for n in 1..=tasks.len() {
+            clock.advance(Duration::from_secs(10));
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert_eq!(admitted.lock().unwrap().len(), n);
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        Arc::try_unwrap(admitted).unwrap().into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pacing_by_lag() {
+        let normal = SubmissionPriority::Normal;
+        assert_eq!(
+            admission_order(vec![(normal, 1), (normal, 5), (normal, 3)]).await,
+            vec![5, 3, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pacing_by_priority() {
+        assert_eq!(
+            admission_order(vec![
+                (SubmissionPriority::Low, 9),
+                (SubmissionPriority::Normal, 5),
+                (SubmissionPriority::High, 0),
+                (SubmissionPriority::Normal, 7),
+            ])
+            .await,
+            vec![0, 7, 5, 9]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bounded pipeline between the scanning of the child and the submissions to the parent.
+//!
+//! The scanning runs ahead of the submissions by at most the capacity of the pipeline, and
+//! waits for them when it is full, so that a slow parent holds the scanning back during long
+//! catch-ups instead of piling up the fetched checkpoints in memory. The waits are measured,
+//! for a relayer constantly held back by its parent to show in the metrics.
+
+use crate::monitor;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// A pipeline of `capacity` items, whose metrics are labelled with `label`.
+pub(super) fn pipeline<T>(
+    capacity: usize,
+    label: &str,
+) -> (PipelineSender<T>, PipelineReceiver<T>) {
+    let (tx, rx) = mpsc::channel(capacity);
+    let queued = Queued {
+        count: Arc::new(AtomicI64::new(0)),
+        label: label.to_string(),
+    };
+    queued.add(0);
+    (
+        PipelineSender {
+            tx,
+            queued: queued.clone(),
+        },
+        PipelineReceiver { rx, queued },
+    )
+}
+
+/// The number of items in the pipeline, tracked on both ends for the gauge.
+#[derive(Clone)]
+struct Queued {
+    count: Arc<AtomicI64>,
+    label: String,
+}
+
+impl Queued {
+    fn add(&self, delta: i64) {
+        let count = self.count.fetch_add(delta, Ordering::SeqCst) + delta;
+        monitor::RELAYER_PIPELINE_QUEUED
+            .with_label_values(&[&self.label])
+            .set(count);
+    }
+}
+
+pub(super) struct PipelineSender<T> {
+    tx: mpsc::Sender<T>,
+    queued: Queued,
+}
+
+impl<T> PipelineSender<T> {
+    /// Sends `item`, waiting for the receiver to make room if the pipeline is full. Returns
+    /// the item back if the receiver is dropped.
+    pub async fn send(&self, item: T) -> Result<(), T> {
+        let item = match self.tx.try_send(item) {
+            Ok(()) => {
+                self.queued.add(1);
+                return Ok(());
+            }
+            Err(TrySendError::Closed(item)) => return Err(item),
+            Err(TrySendError::Full(item)) => item,
+        };
+
+        let label = &self.queued.label;
+        log::debug!("pipeline of {label} full, waiting for the submissions");
+        monitor::RELAYER_PIPELINE_STALLS
+            .with_label_values(&[label])
+            .inc();
+        let started = Instant::now();
+        let sent = self.tx.send(item).await.map_err(|e| e.0);
+        monitor::RELAYER_PIPELINE_STALLED_SECONDS
+            .with_label_values(&[label])
+            .inc_by(started.elapsed().as_secs_f64());
+        if sent.is_ok() {
+            self.queued.add(1);
+        }
+        sent
+    }
+}
+
+pub(super) struct PipelineReceiver<T> {
+    rx: mpsc::Receiver<T>,
+    queued: Queued,
+}
+
+impl<T> PipelineReceiver<T> {
+    /// The next item, none once the sender is dropped and the pipeline drained.
+    pub async fn recv(&mut self) -> Option<T> {
+        let item = self.rx.recv().await;
+        if item.is_some() {
+            self.queued.add(-1);
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pipeline;
+    use crate::monitor;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_full_pipeline_stalls_the_sender() {
+        let label = "test_full_pipeline_stalls_the_sender";
+        let (tx, mut rx) = pipeline(2, label);
+        let queued = || {
+            monitor::RELAYER_PIPELINE_QUEUED
+                .with_label_values(&[label])
+                .get()
+        };
+        let stalls = || {
+            monitor::RELAYER_PIPELINE_STALLS
+                .with_label_values(&[label])
+                .get()
+        };
+
+        tx.send(1).await.unwrap();
+        tx.send(2).await.unwrap();
+        assert_eq!(queued(), 2);
+        assert_eq!(stalls(), 0);
+
+        // the third item waits for the receiver to make room
+        let send = tokio::spawn(async move {
+            tx.send(3).await.unwrap();
+            tx
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!send.is_finished());
+        assert_eq!(rx.recv().await, Some(1));
+
+        let tx = send.await.unwrap();
+        assert_eq!(stalls(), 1);
+        assert_eq!(queued(), 2);
+
+        drop(tx);
+        assert_eq!(rx.recv().await, Some(2));
+        assert_eq!(rx.recv().await, Some(3));
+        assert_eq!(rx.recv().await, None);
+        assert_eq!(queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_receiver_returns_the_item() {
+        let (tx, rx) = pipeline(1, "test_dropped_receiver_returns_the_item");
+        drop(rx);
+        assert_eq!(tx.send(7).await, Err(7));
+    }
+}
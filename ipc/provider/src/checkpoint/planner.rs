@@ -0,0 +1,413 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The decisions of a submission round of the bottom-up relayer.
+//!
+//! [`SubmissionPlanner`] turns a [`RoundSnapshot`] of the parent and child subnets into the
+//! [`SubmissionAction`]s of the round, without querying them, so that the strategy can be
+//! tested deterministically. [`super::BottomUpCheckpointManager`] feeds the checkpoints to a
+//! [`RoundPlan`] as it fetches them, and executes the actions as soon as they are unlocked.
+
+use crate::checkpoint::{heights, EmptyCheckpointPolicy};
+use crate::manager::CheckpointQuorum;
+use anyhow::Result;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent};
+use std::ops::RangeInclusive;
+
+/// A checkpoint whose quorum was reached in the child.
+#[derive(Debug, Clone)]
+pub struct ReadyCheckpoint {
+    /// The quorum event of the checkpoint, with the signatories of its bundle.
+    pub event: QuorumReachedEvent,
+    pub bundle: BottomUpCheckpointBundle,
+    /// The signature weight of the checkpoint, only queried if a quorum threshold is set.
+    pub quorum: Option<CheckpointQuorum>,
+}
+
+/// The state of the subnets at the start of a round.
+#[derive(Debug, Clone, Default)]
+pub struct RoundSnapshot {
+    /// The bundle of the last checkpoint committed in the parent, if any.
+    pub last_committed: Option<BottomUpCheckpointBundle>,
+    /// The checkpoints of the quorum events found in the scanned heights, in their order.
+    pub ready: Vec<ReadyCheckpoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmissionAction {
+    /// Submit the bundle to the parent.
+    Submit(BottomUpCheckpointBundle),
+    /// Submit again the bundle of the last committed checkpoint, which does not advance the
+    /// committed height: the side effects of a commitment are not repeated.
+    Resubmit(BottomUpCheckpointBundle),
+    /// Do not re-submit the committed empty checkpoint at this height.
+    SkipEmpty(ChainEpoch),
+    /// The signature weight of the checkpoint at `height` is below the threshold. Checkpoints
+    /// are committed in order, so the round stops there and it is retried in the next one.
+    WaitForQuorum {
+        height: ChainEpoch,
+        current_weight: TokenAmount,
+    },
+}
+
+/// The actions of a round, in the order they are executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubmissionPlan {
+    pub actions: Vec<SubmissionAction>,
+    /// The heights of the empty checkpoints held back at the end of the round.
+    pub held: Vec<ChainEpoch>,
+}
+
+/// Decides what a round submits, from the settings of the relayer.
+#[derive(Debug, Clone, Copy)]
+pub struct SubmissionPlanner {
+    period: ChainEpoch,
+    finalization_blocks: ChainEpoch,
+    quorum_threshold: Option<u8>,
+    empty_checkpoints: EmptyCheckpointPolicy,
+}
+
+impl SubmissionPlanner {
+    pub fn new(
+        period: ChainEpoch,
+        finalization_blocks: ChainEpoch,
+        quorum_threshold: Option<u8>,
+        empty_checkpoints: EmptyCheckpointPolicy,
+    ) -> Self {
+        Self {
+            period,
+            finalization_blocks,
+            quorum_threshold,
+            empty_checkpoints,
+        }
+    }
+
+    /// The heights of the child to scan for quorum events, or `None` while the next checkpoint
+    /// is not final.
+    pub fn scan_range(
+        &self,
+        last_committed: ChainEpoch,
+        current_height: ChainEpoch,
+    ) -> Result<Option<RangeInclusive<ChainEpoch>>> {
+        let finalized = heights::finalized_height(current_height, self.finalization_blocks);
+        heights::scan_range(last_committed, self.period, finalized)
+    }
+
+    /// Whether the signature weight of the checkpoints has to be part of the snapshots.
+    pub fn needs_quorum(&self) -> bool {
+        self.quorum_threshold.is_some()
+    }
+
+    /// Whether a checkpoint with the signature weight `quorum` can be submitted. The later
+    /// checkpoints need not be part of the snapshot if it cannot.
+    pub fn reaches_quorum(&self, quorum: Option<&CheckpointQuorum>) -> bool {
+        match (self.quorum_threshold, quorum) {
+            (None, _) => true,
+            (Some(percentage), Some(quorum)) => quorum.reaches(percentage),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Plans a round whose checkpoints are fed as they are fetched.
+    pub fn round(&self) -> RoundPlan {
+        RoundPlan {
+            planner: *self,
+            held: vec![],
+            done: false,
+        }
+    }
+
+    pub fn plan(&self, snapshot: RoundSnapshot) -> SubmissionPlan {
+        let mut round = self.round();
+        let mut plan = SubmissionPlan::default();
+
+        if let Some(bundle) = snapshot.last_committed {
+            plan.actions.push(round.last_committed(bundle));
+        }
+        for ready in snapshot.ready {
+            if round.is_done() {
+                break;
+            }
+            plan.actions.extend(round.ready(ready));
+        }
+
+        plan.held = round.held();
+        plan
+    }
+}
+
+/// The planning of a round as its checkpoints are fetched, so that the first ones can be
+/// submitted while the next ones are still being fetched.
+#[derive(Debug, Clone)]
+pub struct RoundPlan {
+    planner: SubmissionPlanner,
+    /// The empty checkpoints held back, the parent only accepts them in order so they are
+    /// relayed right before the next non-empty one
+    held: Vec<BottomUpCheckpointBundle>,
+    done: bool,
+}
+
+impl RoundPlan {
+    /// The action on the last checkpoint committed in the parent.
+    pub fn last_committed(&mut self, bundle: BottomUpCheckpointBundle) -> SubmissionAction {
+        // the checkpoint is already committed in the parent, so an empty one has nothing
+        // left to execute there
+        if self.planner.empty_checkpoints != EmptyCheckpointPolicy::Submit
+            && is_empty(&bundle.checkpoint)
+        {
+            SubmissionAction::SkipEmpty(bundle.checkpoint.block_height)
+        } else {
+            SubmissionAction::Resubmit(bundle)
+        }
+    }
+
+    /// The actions unlocked by the next checkpoint of the round, in order.
+    pub fn ready(&mut self, ready: ReadyCheckpoint) -> Vec<SubmissionAction> {
+        if self.done {
+            return vec![];
+        }
+
+        if !self.planner.reaches_quorum(ready.quorum.as_ref()) {
+            self.done = true;
+            return vec![SubmissionAction::WaitForQuorum {
+                height: ready.bundle.checkpoint.block_height,
+                current_weight: ready.quorum.map(|q| q.current_weight).unwrap_or_default(),
+            }];
+        }
+
+        if let EmptyCheckpointPolicy::Batch { max_held } = self.planner.empty_checkpoints {
+            if is_empty(&ready.bundle.checkpoint) && self.held.len() + 1 < max_held {
+                self.held.push(ready.bundle);
+                return vec![];
+            }
+        }
+
+        let mut actions = self
+            .held
+            .drain(..)
+            .map(SubmissionAction::Submit)
+            .collect::<Vec<_>>();
+        actions.push(SubmissionAction::Submit(ready.bundle));
+        actions
+    }
+
+    /// Whether the round stopped at a checkpoint below the quorum threshold, the later ones
+    /// wait for it.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The heights of the empty checkpoints held back.
+    pub fn held(&self) -> Vec<ChainEpoch> {
+        self.held
+            .iter()
+            .map(|bundle| bundle.checkpoint.block_height)
+            .collect()
+    }
+}
+
+/// A checkpoint is empty if it carries neither cross-net messages nor validator changes.
+fn is_empty(checkpoint: &BottomUpCheckpoint) -> bool {
+    checkpoint.msgs.is_empty() && checkpoint.next_configuration_number == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReadyCheckpoint, RoundSnapshot, SubmissionAction, SubmissionPlanner};
+    use crate::checkpoint::EmptyCheckpointPolicy;
+    use crate::manager::CheckpointQuorum;
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent};
+    use ipc_api::subnet_id::SubnetID;
+    use std::str::FromStr;
+
+    fn bundle(height: ChainEpoch, empty: bool) -> BottomUpCheckpointBundle {
+        BottomUpCheckpointBundle {
+            checkpoint: BottomUpCheckpoint {
+                subnet_id: SubnetID::from_str(
+                    "/r314159/t410f6b2qto756ox3qfoonq4ii6pdrylxwyretgpixuy",
+                )
+                .unwrap(),
+                block_height: height,
+                block_hash: vec![0; 32],
+                next_configuration_number: if empty { 0 } else { 1 },
+                msgs: vec![],
+            },
+            signatures: vec![],
+            signatories: vec![],
+        }
+    }
+
+    fn ready(height: ChainEpoch, empty: bool) -> ReadyCheckpoint {
+        let bundle = bundle(height, empty);
+        let event = QuorumReachedEvent {
+            obj_kind: 0,
+            height,
+            obj_hash: vec![0; 32],
+            quorum_weight: TokenAmount::from_atto(1),
+            signatories: None,
+            block_hash: None,
+        }
+        .with_bundle(&bundle);
+        ReadyCheckpoint {
+            event,
+            bundle,
+            quorum: None,
+        }
+    }
+
+    fn planner(policy: EmptyCheckpointPolicy) -> SubmissionPlanner {
+        SubmissionPlanner::new(10, 0, None, policy)
+    }
+
+    fn heights(actions: &[SubmissionAction]) -> Vec<ChainEpoch> {
+        actions
+            .iter()
+            .filter_map(|a| match a {
+                SubmissionAction::Submit(b) | SubmissionAction::Resubmit(b) => {
+                    Some(b.checkpoint.block_height)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_range() {
+        let planner = SubmissionPlanner::new(10, 5, None, EmptyCheckpointPolicy::Submit);
+        assert_eq!(planner.scan_range(0, 14).unwrap(), None);
+        assert_eq!(planner.scan_range(0, 15).unwrap(), Some(1..=10));
+        assert_eq!(planner.scan_range(10, 40).unwrap(), Some(11..=35));
+    }
+
+    #[test]
+    fn test_resubmits_last_committed() {
+        let snapshot = RoundSnapshot {
+            last_committed: Some(bundle(10, true)),
+            ready: vec![],
+        };
+
+        let plan = planner(EmptyCheckpointPolicy::Submit).plan(snapshot.clone());
+        assert_eq!(
+            plan.actions,
+            vec![SubmissionAction::Resubmit(bundle(10, true))]
+        );
+
+        let plan = planner(EmptyCheckpointPolicy::Skip).plan(snapshot);
+        assert_eq!(plan.actions, vec![SubmissionAction::SkipEmpty(10)]);
+    }
+
+    #[test]
+    fn test_no_previous_checkpoint() {
+        let plan = planner(EmptyCheckpointPolicy::Submit).plan(RoundSnapshot::default());
+        assert!(plan.actions.is_empty());
+        assert!(plan.held.is_empty());
+    }
+
+    #[test]
+    fn test_batches_empty_checkpoints() {
+        let planner = planner(EmptyCheckpointPolicy::Batch { max_held: 3 });
+
+        // held back until a non-empty one is ready
+        let plan = planner.plan(RoundSnapshot {
+            last_committed: None,
+            ready: vec![ready(10, true), ready(20, false), ready(30, true)],
+        });
+        assert_eq!(heights(&plan.actions), vec![10, 20]);
+        assert_eq!(plan.held, vec![30]);
+
+        // or until `max_held` of them are pending
+        let plan = planner.plan(RoundSnapshot {
+            last_committed: None,
+            ready: vec![ready(10, true), ready(20, true), ready(30, true)],
+        });
+        assert_eq!(heights(&plan.actions), vec![10, 20, 30]);
+        assert!(plan.held.is_empty());
+    }
+
+    #[test]
+    fn test_waits_for_quorum() {
+        let planner = SubmissionPlanner::new(10, 0, Some(80), EmptyCheckpointPolicy::Submit);
+        let quorum = |weight| CheckpointQuorum {
+            threshold: TokenAmount::from_atto(67),
+            current_weight: TokenAmount::from_atto(weight),
+            majority_percentage: 67,
+        };
+        assert!(planner.needs_quorum());
+        assert!(!planner.reaches_quorum(None));
+
+        let plan = planner.plan(RoundSnapshot {
+            last_committed: None,
+            ready: vec![
+                ReadyCheckpoint {
+                    quorum: Some(quorum(90)),
+                    ..ready(10, false)
+                },
+                ReadyCheckpoint {
+                    quorum: Some(quorum(70)),
+                    ..ready(20, false)
+                },
+                ReadyCheckpoint {
+                    quorum: Some(quorum(90)),
+                    ..ready(30, false)
+                },
+            ],
+        });
+        assert_eq!(
+            plan.actions[1..],
+            [SubmissionAction::WaitForQuorum {
+                height: 20,
+                current_weight: TokenAmount::from_atto(70)
+            }]
+        );
+        assert_eq!(heights(&plan.actions), vec![10]);
+    }
+
+    #[test]
+    fn test_incremental_round_matches_plan() {
+        let planner = planner(EmptyCheckpointPolicy::Batch { max_held: 3 });
+        let checkpoints = vec![
+            ready(10, true),
+            ready(20, false),
+            ready(30, true),
+            ready(40, true),
+            ready(50, true),
+            ready(60, true),
+        ];
+
+        let mut round = planner.round();
+        let mut actions = vec![round.last_committed(bundle(0, true))];
+        // the first submissions are unlocked before the later checkpoints are known
+        assert_eq!(heights(&round.ready(checkpoints[0].clone())), vec![]);
+        assert_eq!(heights(&round.ready(checkpoints[1].clone())), vec![10, 20]);
+        for c in &checkpoints[2..] {
+            actions.extend(round.ready(c.clone()));
+        }
+
+        let plan = planner.plan(RoundSnapshot {
+            last_committed: Some(bundle(0, true)),
+            ready: checkpoints,
+        });
+        assert_eq!(heights(&plan.actions), vec![10, 20, 30, 40, 50]);
+        assert_eq!(heights(&actions), vec![30, 40, 50]);
+        assert_eq!(round.held(), plan.held);
+        assert_eq!(plan.held, vec![60]);
+    }
+
+    #[test]
+    fn test_round_is_done_below_quorum() {
+        let planner = SubmissionPlanner::new(10, 0, Some(80), EmptyCheckpointPolicy::Submit);
+        let mut round = planner.round();
+        assert!(!round.is_done());
+
+        let actions = round.ready(ready(10, false));
+        assert!(matches!(
+            actions[..],
+            [SubmissionAction::WaitForQuorum { height: 10, .. }]
+        ));
+        assert!(round.is_done());
+        assert!(round.ready(ready(20, false)).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Policies on the content of the checkpoints, refusing to relay the ones carrying cross-net
+//! messages the operator of the relayer must not relay, e.g. for compliance.
+//!
+//! The parent only accepts the checkpoints in order, so a refused checkpoint holds back the
+//! relaying of the subnet: it is refused again, and alerted about, on every round until the
+//! policy changes or another relayer submits it.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::checkpoint::BottomUpCheckpoint;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// Decides whether a checkpoint is relayed from its content.
+#[async_trait]
+pub trait SubmissionPolicy: Send + Sync {
+    /// The violations of the policy by `checkpoint`, which is relayed if there are none. An
+    /// error fails the submission attempt, which is retried on the next round.
+    async fn check(&self, checkpoint: &BottomUpCheckpoint) -> Result<Vec<PolicyViolation>>;
+}
+
+/// A cross-net message of a checkpoint refused by a [`SubmissionPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    /// The nonce of the message in the checkpoint.
+    pub nonce: u64,
+    pub reason: ViolationReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationReason {
+    /// The recipient of the message is denied.
+    DeniedRecipient(Address),
+    /// The recipient of the message is not in the allowed ones.
+    RecipientNotAllowed(Address),
+    /// The message carries more value than allowed.
+    ValueAboveLimit {
+        value: TokenAmount,
+        limit: TokenAmount,
+    },
+}
+
+impl Display for PolicyViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            ViolationReason::DeniedRecipient(to) => {
+                write!(f, "message {} to denied recipient {to}", self.nonce)
+            }
+            ViolationReason::RecipientNotAllowed(to) => {
+                write!(f, "message {} to recipient {to} not allowed", self.nonce)
+            }
+            ViolationReason::ValueAboveLimit { value, limit } => {
+                write!(f, "message {} of {value} above {limit}", self.nonce)
+            }
+        }
+    }
+}
+
+/// Refuses the messages to denied recipients, to recipients outside of the allowed ones if
+/// they are restricted, and above a value limit.
+#[derive(Debug, Clone, Default)]
+pub struct MessagePolicy {
+    denied: HashSet<Address>,
+    allowed: Option<HashSet<Address>>,
+    max_value: Option<TokenAmount>,
+}
+
+impl MessagePolicy {
+    pub fn with_denied_recipient(mut self, recipient: Address) -> Self {
+        self.denied.insert(recipient);
+        self
+    }
+
+    /// Only allows the messages to `recipient` and the other allowed recipients.
+    pub fn with_allowed_recipient(mut self, recipient: Address) -> Self {
+        self.allowed
+            .get_or_insert_with(HashSet::new)
+            .insert(recipient);
+        self
+    }
+
+    pub fn with_max_value(mut self, limit: TokenAmount) -> Self {
+        self.max_value = Some(limit);
+        self
+    }
+
+    /// Whether the policy refuses anything at all.
+    pub fn is_empty(&self) -> bool {
+        self.denied.is_empty() && self.allowed.is_none() && self.max_value.is_none()
+    }
+}
+
+#[async_trait]
+impl SubmissionPolicy for MessagePolicy {
+    async fn check(&self, checkpoint: &BottomUpCheckpoint) -> Result<Vec<PolicyViolation>> {
+        let mut violations = vec![];
+        for msg in &checkpoint.msgs {
+            let to = msg.to.raw_addr()?;
+            let mut violate = |reason| {
+                violations.push(PolicyViolation {
+                    nonce: msg.nonce,
+                    reason,
+                })
+            };
+
+            if self.denied.contains(&to) {
+                violate(ViolationReason::DeniedRecipient(to));
+            }
+            if matches!(&self.allowed, Some(allowed) if !allowed.contains(&to)) {
+                violate(ViolationReason::RecipientNotAllowed(to));
+            }
+            if let Some(limit) = &self.max_value {
+                if msg.value > *limit {
+                    violate(ViolationReason::ValueAboveLimit {
+                        value: msg.value.clone(),
+                        limit: limit.clone(),
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessagePolicy, SubmissionPolicy, ViolationReason};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::checkpoint::BottomUpCheckpoint;
+    use ipc_api::cross::IpcEnvelope;
+    use ipc_api::subnet_id::SubnetID;
+
+    fn checkpoint(msgs: Vec<(Address, u64)>) -> BottomUpCheckpoint {
+        let subnet = SubnetID::new_from_parent(&SubnetID::new_root(123), Address::new_id(100));
+        let msgs = msgs
+            .into_iter()
+            .enumerate()
+            .map(|(nonce, (to, value))| {
+                let mut msg = IpcEnvelope::new_release_msg(
+                    &subnet,
+                    &Address::new_id(1),
+                    &to,
+                    TokenAmount::from_whole(value),
+                )
+                .unwrap();
+                msg.nonce = nonce as u64;
+                msg
+            })
+            .collect();
+        BottomUpCheckpoint {
+            subnet_id: subnet,
+            block_height: 10,
+            block_hash: vec![],
+            next_configuration_number: 0,
+            msgs,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_policy() {
+        let denied = Address::new_id(10);
+        let allowed = Address::new_id(11);
+        let policy = MessagePolicy::default()
+            .with_denied_recipient(denied)
+            .with_allowed_recipient(allowed)
+            .with_max_value(TokenAmount::from_whole(5));
+
+        let ok = checkpoint(vec![(allowed, 5)]);
+        assert!(policy.check(&ok).await.unwrap().is_empty());
+
+        let refused = checkpoint(vec![(allowed, 1), (denied, 1), (allowed, 6)]);
+        let reasons = policy
+            .check(&refused)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.nonce, v.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, ViolationReason::DeniedRecipient(denied)),
+                (1, ViolationReason::RecipientNotAllowed(denied)),
+                (
+                    2,
+                    ViolationReason::ValueAboveLimit {
+                        value: TokenAmount::from_whole(6),
+                        limit: TokenAmount::from_whole(5),
+                    }
+                ),
+            ]
+        );
+
+        assert!(MessagePolicy::default().is_empty());
+        assert!(!policy.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Self-profiling of the bottom-up relayer.
+//!
+//! When enabled, the time spent in every phase of the submissions is observed in the
+//! `relayer_phase_seconds` histogram, and a summary of the phases is logged periodically.
+
+use crate::monitor;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The phases of a submission round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// The queries of the last committed height and of the head of the child.
+    HeightFetch,
+    /// The queries of the quorum events in the child.
+    EventScan,
+    /// The queries of the checkpoint bundles in the child.
+    BundleFetch,
+    /// The estimation of the gas and fees of a submission.
+    GasEstimate,
+    /// The signing and broadcast of a submission.
+    Broadcast,
+    /// Waiting for the receipt of a submission.
+    ReceiptWait,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::HeightFetch => "height_fetch",
+            Phase::EventScan => "event_scan",
+            Phase::BundleFetch => "bundle_fetch",
+            Phase::GasEstimate => "gas_estimate",
+            Phase::Broadcast => "broadcast",
+            Phase::ReceiptWait => "receipt_wait",
+        }
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The timings of a phase since the last summary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhaseStats {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl PhaseStats {
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.count as u32
+    }
+}
+
+/// Records the timings of the phases of the submissions of a relayer.
+pub struct SubmissionProfiler {
+    /// The value of the `relayer` label of the histogram
+    label: String,
+    summary_interval: Duration,
+    stats: Mutex<BTreeMap<Phase, PhaseStats>>,
+    last_summary: Mutex<Instant>,
+}
+
+impl SubmissionProfiler {
+    pub fn new(label: impl Into<String>, summary_interval: Duration) -> Self {
+        Self {
+            label: label.into(),
+            summary_interval,
+            stats: Mutex::new(BTreeMap::new()),
+            last_summary: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn record(&self, phase: Phase, elapsed: Duration) {
+        monitor::RELAYER_PHASE_SECONDS
+            .with_label_values(&[&self.label, phase.as_str()])
+            .observe(elapsed.as_secs_f64());
+
+        let mut stats = self.stats.lock().unwrap();
+        let s = stats.entry(phase).or_default();
+        s.count += 1;
+        s.total += elapsed;
+        s.max = s.max.max(elapsed);
+    }
+
+    /// Runs `f`, recording the time it took in `phase`, whether it failed or not.
+    pub async fn time<F: Future>(&self, phase: Phase, f: F) -> F::Output {
+        let started = Instant::now();
+        let r = f.await;
+        self.record(phase, started.elapsed());
+        r
+    }
+
+    /// The timings recorded since the last summary, by phase.
+    pub fn stats(&self) -> BTreeMap<Phase, PhaseStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Logs the timings recorded since the last summary and resets them, if the summary
+    /// interval elapsed.
+    pub fn log_summary_if_due(&self) {
+        {
+            let mut last_summary = self.last_summary.lock().unwrap();
+            if last_summary.elapsed() < self.summary_interval {
+                return;
+            }
+            *last_summary = Instant::now();
+        }
+
+        let stats = std::mem::take(&mut *self.stats.lock().unwrap());
+        if stats.is_empty() {
+            return;
+        }
+        let summary = stats
+            .iter()
+            .map(|(phase, s)| {
+                format!(
+                    "{phase}: {} calls, mean {:?}, max {:?}",
+                    s.count,
+                    s.mean(),
+                    s.max
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        log::info!("relayer {} phase timings: {summary}", self.label);
+    }
+}
+
+/// Runs `f`, recording its timing in `phase` if there is a profiler.
+pub(crate) async fn timed<F: Future>(
+    profiler: Option<&SubmissionProfiler>,
+    phase: Phase,
+    f: F,
+) -> F::Output {
+    match profiler {
+        Some(p) => p.time(phase, f).await,
+        None => f.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Phase, SubmissionProfiler};
+    use std::time::Duration;
+
+    #[test]
+    fn test_records_phase_stats() {
+        let profiler = SubmissionProfiler::new("test", Duration::ZERO);
+        profiler.record(Phase::EventScan, Duration::from_millis(10));
+        profiler.record(Phase::EventScan, Duration::from_millis(30));
+        profiler.record(Phase::ReceiptWait, Duration::from_secs(2));
+
+        let stats = profiler.stats();
+        let scan = &stats[&Phase::EventScan];
+        assert_eq!(scan.count, 2);
+        assert_eq!(scan.mean(), Duration::from_millis(20));
+        assert_eq!(scan.max, Duration::from_millis(30));
+        assert_eq!(stats[&Phase::ReceiptWait].count, 1);
+        assert!(!stats.contains_key(&Phase::Broadcast));
+
+        // the summary resets the timings
+        profiler.log_summary_if_due();
+        assert!(profiler.stats().is_empty());
+    }
+
+    #[test]
+    fn test_summary_waits_for_interval() {
+        let profiler = SubmissionProfiler::new("test", Duration::from_secs(3600));
+        profiler.record(Phase::HeightFetch, Duration::from_millis(5));
+        profiler.log_summary_if_due();
+        assert_eq!(profiler.stats()[&Phase::HeightFetch].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_times_futures() {
+        let profiler = SubmissionProfiler::new("test", Duration::ZERO);
+        let r = profiler
+            .time(Phase::Broadcast, async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                42
+            })
+            .await;
+        assert_eq!(r, 42);
+        assert!(profiler.stats()[&Phase::Broadcast].max >= Duration::from_millis(20));
+    }
+}
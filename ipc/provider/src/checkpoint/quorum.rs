@@ -0,0 +1,427 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Relaying of the certificates signed by a quorum of the child validators, whatever the
+//! object they certify.
+//!
+//! The child gateway emits a `QuorumReached` event for every object signed by a quorum, tagged
+//! with the kind of the object, e.g. the bottom-up checkpoints. A [`CertificateKind`] knows how
+//! to fetch the certificates of its kind from the child and how to submit them to the parent,
+//! and the [`QuorumRelayer`] finds the ready ones and relays them in order. A new kind of
+//! certificate only needs its own [`CertificateKind`].
+
+use crate::manager::{BottomUpCheckpointRelayer, CheckpointReceipt, ChildGatewayClient};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::{
+    BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent, Signature,
+};
+use ipc_api::subnet_id::SubnetID;
+use std::cmp::min;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+/// The `obj_kind` of the quorum events of the bottom-up checkpoints.
+pub const CHECKPOINT_OBJ_KIND: u8 = 0;
+
+/// The default number of heights of the child scanned for quorum events at once.
+const DEFAULT_BATCH_SIZE: usize = 50;
+
+/// A payload signed by a quorum of the child validators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumCertificate<T> {
+    pub payload: T,
+    pub signatures: Vec<Signature>,
+    /// The validators that signed, in the order of the signatures.
+    pub signatories: Vec<Address>,
+}
+
+impl<T> QuorumCertificate<T> {
+    /// Checks that every signatory signed once, with one signature each.
+    pub fn validate(&self) -> Result<()> {
+        if self.signatures.is_empty() {
+            return Err(anyhow!("certificate without signatures"));
+        }
+        if self.signatures.len() != self.signatories.len() {
+            return Err(anyhow!(
+                "certificate with {} signatures from {} signatories",
+                self.signatures.len(),
+                self.signatories.len()
+            ));
+        }
+        let mut seen = HashSet::new();
+        if let Some(dup) = self.signatories.iter().find(|s| !seen.insert(*s)) {
+            return Err(anyhow!("certificate signed twice by {dup}"));
+        }
+        Ok(())
+    }
+}
+
+impl From<BottomUpCheckpointBundle> for QuorumCertificate<BottomUpCheckpoint> {
+    fn from(bundle: BottomUpCheckpointBundle) -> Self {
+        Self {
+            payload: bundle.checkpoint,
+            signatures: bundle.signatures,
+            signatories: bundle.signatories,
+        }
+    }
+}
+
+/// A kind of certificate relayed from the child to the parent.
+#[async_trait]
+pub trait CertificateKind: Send + Sync {
+    type Payload: Clone + Debug + Send + Sync;
+
+    /// The `obj_kind` of the quorum events of the certificates in the child gateway.
+    fn obj_kind(&self) -> u8;
+    /// The name of the certificates, for the logs.
+    fn name(&self) -> &'static str;
+    /// The height of the child the payload was cut at.
+    fn height(&self, payload: &Self::Payload) -> ChainEpoch;
+
+    /// The height of the last certificate committed in the parent.
+    async fn last_committed_height(&self) -> Result<ChainEpoch>;
+    /// The quorum events at each of the `heights` of the child, of all the kinds.
+    async fn quorum_events_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<Vec<QuorumReachedEvent>>>;
+    /// The certificates at each of the `heights` of the child.
+    async fn certificates_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<QuorumCertificate<Self::Payload>>>;
+    /// Submits `certificate` to the parent from `submitter`.
+    async fn submit(
+        &self,
+        submitter: &Address,
+        certificate: QuorumCertificate<Self::Payload>,
+    ) -> Result<CheckpointReceipt>;
+}
+
+/// Relays the certificates of a kind that reached their quorum in the child to the parent.
+pub struct QuorumRelayer<K> {
+    kind: K,
+    batch_size: usize,
+}
+
+impl<K: CertificateKind> QuorumRelayer<K> {
+    pub fn new(kind: K) -> Self {
+        Self {
+            kind,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Scans `batch_size` heights of the child for quorum events at once.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn kind(&self) -> &K {
+        &self.kind
+    }
+
+    /// The valid certificates of the kind that reached their quorum in the `range` of heights
+    /// of the child, in the order of their heights.
+    pub async fn ready_certificates(
+        &self,
+        range: RangeInclusive<ChainEpoch>,
+    ) -> Result<Vec<QuorumCertificate<K::Payload>>> {
+        let mut ready = vec![];
+        let mut start = *range.start();
+        while start <= *range.end() {
+            let end = min(*range.end(), start + self.batch_size as ChainEpoch - 1);
+            let heights = (start..=end).collect::<Vec<_>>();
+            start = end + 1;
+
+            let events = self.kind.quorum_events_at(&heights).await?;
+            let quorum_heights = heights
+                .iter()
+                .zip(events)
+                .filter(|(_, events)| events.iter().any(|e| e.obj_kind == self.kind.obj_kind()))
+                .map(|(h, _)| *h)
+                .collect::<Vec<_>>();
+            if quorum_heights.is_empty() {
+                continue;
+            }
+
+            for certificate in self.kind.certificates_at(&quorum_heights).await? {
+                let height = self.kind.height(&certificate.payload);
+                match certificate.validate() {
+                    Ok(()) => ready.push(certificate),
+                    Err(e) => log::warn!("skipping {}({height}): {e}", self.kind.name()),
+                }
+            }
+        }
+        Ok(ready)
+    }
+
+    /// Relays in order the certificates that reached their quorum after the last committed
+    /// one, up to the height `up_to` of the child. Stops at the first failed submission, the
+    /// next ones would be rejected out of order. Returns the receipts of the submissions.
+    pub async fn relay(
+        &self,
+        submitter: &Address,
+        up_to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, CheckpointReceipt)>> {
+        let last_committed = self.kind.last_committed_height().await?;
+        if up_to <= last_committed {
+            return Ok(vec![]);
+        }
+
+        let mut receipts = vec![];
+        for certificate in self.ready_certificates(last_committed + 1..=up_to).await? {
+            let height = self.kind.height(&certificate.payload);
+            let receipt = self
+                .kind
+                .submit(submitter, certificate)
+                .await
+                .map_err(|e| anyhow!("cannot submit {}({height}): {e}", self.kind.name()))?;
+            log::info!(
+                "relayed {}({height}) in parent epoch {}",
+                self.kind.name(),
+                receipt.epoch
+            );
+            receipts.push((height, receipt));
+        }
+        Ok(receipts)
+    }
+}
+
+/// The bottom-up checkpoints of a child subnet, as certificates relayed to its parent.
+pub struct CheckpointCertificates<P, C> {
+    subnet: SubnetID,
+    parent: P,
+    child: C,
+}
+
+impl<P, C> CheckpointCertificates<P, C> {
+    pub fn new(subnet: SubnetID, parent: P, child: C) -> Self {
+        Self {
+            subnet,
+            parent,
+            child,
+        }
+    }
+}
+
+#[async_trait]
+impl<P, C> CertificateKind for CheckpointCertificates<P, C>
+where
+    P: BottomUpCheckpointRelayer,
+    C: ChildGatewayClient,
+{
+    type Payload = BottomUpCheckpoint;
+
+    fn obj_kind(&self) -> u8 {
+        CHECKPOINT_OBJ_KIND
+    }
+
+    fn name(&self) -> &'static str {
+        "checkpoint"
+    }
+
+    fn height(&self, payload: &BottomUpCheckpoint) -> ChainEpoch {
+        payload.block_height
+    }
+
+    async fn last_committed_height(&self) -> Result<ChainEpoch> {
+        self.parent
+            .last_bottom_up_checkpoint_height(&self.subnet)
+            .await
+    }
+
+    async fn quorum_events_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<Vec<QuorumReachedEvent>>> {
+        self.child.quorum_reached_events_at(heights).await
+    }
+
+    async fn certificates_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<QuorumCertificate<BottomUpCheckpoint>>> {
+        let bundles = self.child.checkpoint_bundles_at(heights).await?;
+        Ok(bundles.into_iter().map(QuorumCertificate::from).collect())
+    }
+
+    async fn submit(
+        &self,
+        submitter: &Address,
+        certificate: QuorumCertificate<BottomUpCheckpoint>,
+    ) -> Result<CheckpointReceipt> {
+        self.parent
+            .submit_checkpoint(
+                submitter,
+                certificate.payload,
+                certificate.signatures,
+                certificate.signatories,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CertificateKind, QuorumCertificate, QuorumRelayer};
+    use crate::manager::CheckpointReceipt;
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use fvm_shared::address::Address;
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::checkpoint::QuorumReachedEvent;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    /// Certificates of heights, kept in memory, whose quorum events are of kind 1.
+    struct Heights {
+        /// The kind of the quorum event at each height.
+        events: BTreeMap<ChainEpoch, u8>,
+        committed: Mutex<Vec<ChainEpoch>>,
+        reject: Option<ChainEpoch>,
+    }
+
+    impl Heights {
+        fn new(events: &[(ChainEpoch, u8)]) -> Self {
+            Self {
+                events: events.iter().copied().collect(),
+                committed: Mutex::new(vec![]),
+                reject: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CertificateKind for Heights {
+        type Payload = ChainEpoch;
+
+        fn obj_kind(&self) -> u8 {
+            1
+        }
+
+        fn name(&self) -> &'static str {
+            "height"
+        }
+
+        fn height(&self, payload: &ChainEpoch) -> ChainEpoch {
+            *payload
+        }
+
+        async fn last_committed_height(&self) -> Result<ChainEpoch> {
+            Ok(self.committed.lock().unwrap().last().copied().unwrap_or(0))
+        }
+
+        async fn quorum_events_at(
+            &self,
+            heights: &[ChainEpoch],
+        ) -> Result<Vec<Vec<QuorumReachedEvent>>> {
+            Ok(heights
+                .iter()
+                .map(|h| {
+                    self.events
+                        .get(h)
+                        .map(|kind| QuorumReachedEvent {
+                            obj_kind: *kind,
+                            height: *h,
+                            obj_hash: vec![],
+                            quorum_weight: TokenAmount::from_atto(1),
+                            signatories: None,
+                            block_hash: None,
+                        })
+                        .into_iter()
+                        .collect()
+                })
+                .collect())
+        }
+
+        async fn certificates_at(
+            &self,
+            heights: &[ChainEpoch],
+        ) -> Result<Vec<QuorumCertificate<ChainEpoch>>> {
+            Ok(heights
+                .iter()
+                .map(|h| {
+                    // the certificate at 40 is signed twice by the same validator
+                    let signatories = match h {
+                        40 => vec![Address::new_id(1); 2],
+                        _ => vec![Address::new_id(1)],
+                    };
+                    QuorumCertificate {
+                        payload: *h,
+                        signatures: vec![vec![1]; signatories.len()],
+                        signatories,
+                    }
+                })
+                .collect())
+        }
+
+        async fn submit(
+            &self,
+            _: &Address,
+            certificate: QuorumCertificate<ChainEpoch>,
+        ) -> Result<CheckpointReceipt> {
+            if self.reject == Some(certificate.payload) {
+                return Err(anyhow!("rejected"));
+            }
+            self.committed.lock().unwrap().push(certificate.payload);
+            Ok(CheckpointReceipt {
+                epoch: 100 + certificate.payload,
+                tx_hash: format!("0x{:02x}", certificate.payload),
+                gas_used: None,
+                effective_gas_price: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relays_certificates_of_its_kind_in_order() {
+        let kind = Heights::new(&[(10, 1), (15, 0), (20, 1), (40, 1), (50, 1)]);
+        let relayer = QuorumRelayer::new(kind).with_batch_size(7);
+        let submitter = Address::new_id(100);
+
+        // the event at 15 is of another kind, the certificate at 40 is invalid
+        let heights = |r: Vec<(ChainEpoch, CheckpointReceipt)>| {
+            r.into_iter().map(|(h, _)| h).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            heights(relayer.relay(&submitter, 30).await.unwrap()),
+            vec![10, 20]
+        );
+        assert!(relayer.relay(&submitter, 30).await.unwrap().is_empty());
+        assert_eq!(
+            heights(relayer.relay(&submitter, 60).await.unwrap()),
+            vec![50]
+        );
+        assert_eq!(*relayer.kind().committed.lock().unwrap(), vec![10, 20, 50]);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_the_first_failed_submission() {
+        let mut kind = Heights::new(&[(10, 1), (20, 1), (30, 1)]);
+        kind.reject = Some(20);
+        let relayer = QuorumRelayer::new(kind);
+
+        let err = relayer.relay(&Address::new_id(100), 30).await.unwrap_err();
+        assert!(err.to_string().contains("height(20)"));
+        assert_eq!(*relayer.kind().committed.lock().unwrap(), vec![10]);
+    }
+
+    #[test]
+    fn test_validate_certificate() {
+        let certificate = |signatures: usize, signatories: &[u64]| QuorumCertificate {
+            payload: (),
+            signatures: vec![vec![1]; signatures],
+            signatories: signatories.iter().map(|id| Address::new_id(*id)).collect(),
+        };
+        assert!(certificate(2, &[1, 2]).validate().is_ok());
+        assert!(certificate(0, &[]).validate().is_err());
+        assert!(certificate(1, &[1, 2]).validate().is_err());
+        assert!(certificate(2, &[1, 1]).validate().is_err());
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Recovery of the cross-messages of the committed checkpoints stuck on their way to their
+//! destination.
+//!
+//! The messages of a bottom-up checkpoint are applied in the parent once the checkpoint is
+//! committed: the ones addressed to the parent are executed right away, the others are stored
+//! in the postbox of its gateway until someone propagates them further, up to the grandparent
+//! or down to another child. The messages left in the postbox are reported with the key to
+//! propagate them with, which anyone is permitted to do.
+
+use crate::manager::SubnetManager;
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// A message of a committed checkpoint still waiting in the postbox of the parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StuckMsg {
+    /// The height of the checkpoint that carried the message.
+    pub checkpoint_height: ChainEpoch,
+    /// The key of the message in the postbox, to propagate it with.
+    pub postbox_key: Vec<u8>,
+    pub msg: IpcEnvelope,
+}
+
+impl Display for StuckMsg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "checkpoint {}, nonce {}: {:?} from {} to {}, value {}, postbox key 0x{}",
+            self.checkpoint_height,
+            self.msg.nonce,
+            self.msg.kind,
+            self.msg.from.to_string().unwrap_or_default(),
+            self.msg.to.to_string().unwrap_or_default(),
+            self.msg.value,
+            hex::encode(&self.postbox_key)
+        )
+    }
+}
+
+/// The heights within `range` the checkpoints are committed at, one `period` apart.
+pub(crate) fn checkpoint_heights(
+    range: RangeInclusive<ChainEpoch>,
+    period: ChainEpoch,
+) -> Result<Vec<ChainEpoch>> {
+    if period <= 0 {
+        return Err(anyhow!("invalid bottom up checkpoint period: {period}"));
+    }
+    let first = (*range.start()).max(1);
+    let first = (first + period - 1) / period * period;
+    Ok((first..=*range.end()).step_by(period as usize).collect())
+}
+
+/// Finds the messages of the checkpoints of `subnet` committed in `parent` between the `from`
+/// and `to` heights of the child which are still waiting in the postbox of the parent.
+pub async fn find_stuck_msgs(
+    parent: &dyn SubnetManager,
+    subnet: &SubnetID,
+    from: ChainEpoch,
+    to: ChainEpoch,
+) -> Result<Vec<StuckMsg>> {
+    let parent_id = subnet
+        .parent()
+        .ok_or_else(|| anyhow!("root does not have parent"))?;
+    let status = parent.checkpoint_status(subnet).await?;
+    let to = to.min(status.last_committed_height);
+
+    let mut stuck = vec![];
+    for height in checkpoint_heights(from..=to, status.period)? {
+        let Some(checkpoint) = parent.committed_checkpoint_at(subnet, height).await? else {
+            continue;
+        };
+        for msg in checkpoint.msgs {
+            // the messages to the parent are executed with the checkpoint
+            if msg.to.subnet()? == parent_id {
+                continue;
+            }
+            let postbox_key = parent.postbox_key(&msg)?;
+            if parent.in_postbox(&postbox_key).await? {
+                stuck.push(StuckMsg {
+                    checkpoint_height: height,
+                    postbox_key,
+                    msg,
+                });
+            }
+        }
+    }
+    Ok(stuck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::checkpoint_heights;
+
+    #[test]
+    fn test_checkpoint_heights() {
+        assert_eq!(checkpoint_heights(0..=35, 10).unwrap(), vec![10, 20, 30]);
+        assert_eq!(checkpoint_heights(20..=40, 10).unwrap(), vec![20, 30, 40]);
+        assert_eq!(checkpoint_heights(21..=29, 10).unwrap(), Vec::<i64>::new());
+        assert!(checkpoint_heights(0..=10, 0).is_err());
+    }
+}
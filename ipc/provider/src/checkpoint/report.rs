@@ -0,0 +1,397 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Reports of the gas spent by the relayer on the checkpoints it committed, exported from the
+//! [`CheckpointArchive`] to CSV or Parquet files for the reporting pipelines that do not scrape
+//! the metrics.
+//!
+//! A report is written on demand with [`SpendReport::export`], or periodically by a
+//! [`SpendReportExporter`] running alongside the relayer. The Parquet format is available with
+//! the `parquet` feature.
+
+use crate::checkpoint::archive::{CheckpointArchive, ProofBundle};
+use crate::clock::{unix_secs, TokioClock};
+use crate::pagination::HeightRange;
+use anyhow::{anyhow, Context, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The file formats of the reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ReportFormat::Csv),
+            "parquet" => Ok(ReportFormat::Parquet),
+            _ => Err(anyhow!("unknown report format: {s}")),
+        }
+    }
+}
+
+/// The spend of the submission of a committed checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendRecord {
+    pub subnet: String,
+    pub height: ChainEpoch,
+    /// The parent epoch the checkpoint was committed at.
+    pub parent_epoch: ChainEpoch,
+    pub tx_hash: String,
+    pub gas_used: Option<u64>,
+    /// The price paid per unit of gas, in atto.
+    pub effective_gas_price: Option<u64>,
+    /// The fee paid for the submission, in atto.
+    pub fee: Option<u128>,
+    /// The number of cross-net messages the checkpoint carried.
+    pub messages: u64,
+    pub signatories: u64,
+    /// Unix timestamp in seconds of when the checkpoint was archived.
+    pub archived_at: i64,
+}
+
+impl From<&ProofBundle> for SpendRecord {
+    fn from(bundle: &ProofBundle) -> Self {
+        Self {
+            subnet: bundle.subnet.clone(),
+            height: bundle.height(),
+            parent_epoch: bundle.receipt.epoch,
+            tx_hash: bundle.receipt.tx_hash.clone(),
+            gas_used: bundle.receipt.gas_used,
+            effective_gas_price: bundle.receipt.effective_gas_price,
+            fee: bundle.receipt.fee(),
+            messages: bundle.bundle.checkpoint.msgs.len() as u64,
+            signatories: bundle.bundle.signatories.len() as u64,
+            archived_at: bundle.archived_at,
+        }
+    }
+}
+
+/// The spend of the checkpoints of a subnet committed in a range of heights.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpendReport {
+    pub records: Vec<SpendRecord>,
+}
+
+const COLUMNS: [&str; 10] = [
+    "subnet",
+    "height",
+    "parent_epoch",
+    "tx_hash",
+    "gas_used",
+    "effective_gas_price",
+    "fee",
+    "messages",
+    "signatories",
+    "archived_at",
+];
+
+impl SpendReport {
+    /// The report of the checkpoints of `subnet` archived at `heights`, in ascending order.
+    pub fn from_archive(
+        archive: &CheckpointArchive,
+        subnet: &SubnetID,
+        heights: HeightRange,
+    ) -> Result<Self> {
+        let mut records = vec![];
+        for height in archive.heights(subnet)? {
+            if height < heights.from || heights.to.is_some_and(|to| height > to) {
+                continue;
+            }
+            if let Some(bundle) = archive.get(subnet, height)? {
+                records.push(SpendRecord::from(&bundle));
+            }
+        }
+        Ok(Self { records })
+    }
+
+    /// The total fee paid for the checkpoints whose fee is known, in atto.
+    pub fn total_fee(&self) -> u128 {
+        self.records.iter().filter_map(|r| r.fee).sum()
+    }
+
+    /// Writes the report to the file `out` in `format`, replacing it if it exists.
+    pub fn export(&self, format: ReportFormat, out: impl AsRef<Path>) -> Result<()> {
+        let out = out.as_ref();
+        // written aside and moved in place, so that the readers never see a partial report
+        let tmp = out.with_extension("tmp");
+        let file = fs::File::create(&tmp)
+            .with_context(|| format!("cannot create report at {}", tmp.display()))?;
+        match format {
+            ReportFormat::Csv => self.write_csv(std::io::BufWriter::new(file))?,
+            ReportFormat::Parquet => self.write_parquet(file)?,
+        }
+        fs::rename(&tmp, out)
+            .with_context(|| format!("cannot move report to {}", out.display()))?;
+        Ok(())
+    }
+
+    /// Writes the report as CSV, with a header row and empty cells for the unknown values.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> Result<()> {
+        writeln!(w, "{}", COLUMNS.join(","))?;
+        for r in &self.records {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{}",
+                csv_escape(&r.subnet),
+                r.height,
+                r.parent_epoch,
+                csv_escape(&r.tx_hash),
+                optional(r.gas_used),
+                optional(r.effective_gas_price),
+                optional(r.fee),
+                r.messages,
+                r.signatories,
+                r.archived_at
+            )?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn write_parquet(&self, _file: fs::File) -> Result<()> {
+        Err(anyhow!(
+            "parquet reports require the provider built with the parquet feature"
+        ))
+    }
+
+    /// Writes the report as a single row group of a Parquet file. The fees, which can exceed
+    /// 64 bits, are written as decimal strings.
+    #[cfg(feature = "parquet")]
+    fn write_parquet(&self, file: fs::File) -> Result<()> {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        const SCHEMA: &str = "message spend_report {
+            REQUIRED BYTE_ARRAY subnet (UTF8);
+            REQUIRED INT64 height;
+            REQUIRED INT64 parent_epoch;
+            REQUIRED BYTE_ARRAY tx_hash (UTF8);
+            OPTIONAL INT64 gas_used;
+            OPTIONAL INT64 effective_gas_price;
+            OPTIONAL BYTE_ARRAY fee (UTF8);
+            REQUIRED INT64 messages;
+            REQUIRED INT64 signatories;
+            REQUIRED INT64 archived_at;
+        }";
+
+        enum Column {
+            Strings(Vec<ByteArray>, Vec<i16>),
+            Ints(Vec<i64>, Vec<i16>),
+        }
+
+        fn strings(values: impl Iterator<Item = Option<String>>) -> Column {
+            let mut bytes = vec![];
+            let mut levels = vec![];
+            for v in values {
+                levels.push(v.is_some() as i16);
+                bytes.extend(v.map(|v| ByteArray::from(v.as_str())));
+            }
+            Column::Strings(bytes, levels)
+        }
+
+        fn ints(values: impl Iterator<Item = Option<i64>>) -> Column {
+            let mut ints = vec![];
+            let mut levels = vec![];
+            for v in values {
+                levels.push(v.is_some() as i16);
+                ints.extend(v);
+            }
+            Column::Ints(ints, levels)
+        }
+
+        // in the order of the schema
+        let rs = &self.records;
+        let columns = [
+            strings(rs.iter().map(|r| Some(r.subnet.clone()))),
+            ints(rs.iter().map(|r| Some(r.height))),
+            ints(rs.iter().map(|r| Some(r.parent_epoch))),
+            strings(rs.iter().map(|r| Some(r.tx_hash.clone()))),
+            ints(rs.iter().map(|r| r.gas_used.map(|v| v as i64))),
+            ints(rs.iter().map(|r| r.effective_gas_price.map(|v| v as i64))),
+            strings(rs.iter().map(|r| r.fee.map(|v| v.to_string()))),
+            ints(rs.iter().map(|r| Some(r.messages as i64))),
+            ints(rs.iter().map(|r| Some(r.signatories as i64))),
+            ints(rs.iter().map(|r| Some(r.archived_at))),
+        ];
+
+        let schema = Arc::new(parse_message_type(SCHEMA)?);
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(file, schema, props)?;
+        let mut row_group = writer.next_row_group()?;
+        for column in columns {
+            let mut w = row_group
+                .next_column()?
+                .ok_or_else(|| anyhow!("parquet schema with fewer columns than the report"))?;
+            match column {
+                Column::Strings(values, levels) => {
+                    w.typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+                Column::Ints(values, levels) => {
+                    w.typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)?;
+                }
+            }
+            w.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+/// Writes the spend report of a subnet to a directory periodically, each time to a new file
+/// named after the subnet and the time of the export.
+pub struct SpendReportExporter {
+    archive: CheckpointArchive,
+    subnet: SubnetID,
+    dir: PathBuf,
+    format: ReportFormat,
+    interval: Duration,
+}
+
+impl SpendReportExporter {
+    pub fn new(
+        archive: CheckpointArchive,
+        subnet: SubnetID,
+        dir: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create report directory {}", dir.display()))?;
+        Ok(Self {
+            archive,
+            subnet,
+            dir,
+            format: ReportFormat::default(),
+            interval,
+        })
+    }
+
+    pub fn with_format(mut self, format: ReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Exports the report of all the archived checkpoints, returning the path of the file.
+    pub fn export_now(&self) -> Result<PathBuf> {
+        let now = unix_secs(&TokioClock);
+        let subnet = self.subnet.to_string().replace('/', "_");
+        let path = self
+            .dir
+            .join(format!("spend{subnet}-{now}.{}", self.format.extension()));
+        SpendReport::from_archive(&self.archive, &self.subnet, HeightRange::default())?
+            .export(self.format, &path)?;
+        Ok(path)
+    }
+
+    /// Exports a report every interval, forever. The failed exports are only logged.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            match self.export_now() {
+                Ok(path) => log::info!("exported spend report to {}", path.display()),
+                Err(e) => log::error!("cannot export spend report of {}: {e}", self.subnet),
+            }
+        }
+    }
+}
+
+fn optional<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quotes a CSV cell if it contains a separator, a quote or a line break.
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_escape, ReportFormat, SpendRecord, SpendReport, COLUMNS};
+    use std::str::FromStr;
+
+    fn record(height: i64, fee: Option<u128>) -> SpendRecord {
+        SpendRecord {
+            subnet: "/r314159/t410f".to_string(),
+            height,
+            parent_epoch: height + 1000,
+            tx_hash: format!("0x{height:02x}"),
+            gas_used: fee.map(|_| 21000),
+            effective_gas_price: fee.map(|f| (f / 21000) as u64),
+            fee,
+            messages: 2,
+            signatories: 3,
+            archived_at: 1700000000,
+        }
+    }
+
+    #[test]
+    fn test_csv_report() {
+        let report = SpendReport {
+            records: vec![record(10, Some(21_000_000)), record(20, None)],
+        };
+        assert_eq!(report.total_fee(), 21_000_000);
+
+        let mut out = vec![];
+        report.write_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert_eq!(
+            lines[1..],
+            [
+                "/r314159/t410f,10,1010,0x0a,21000,1000,21000000,2,3,1700000000",
+                "/r314159/t410f,20,1020,0x14,,,,2,3,1700000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_escape_and_format() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+        assert_eq!(ReportFormat::from_str("csv").unwrap(), ReportFormat::Csv);
+        assert_eq!(ReportFormat::Parquet.to_string(), "parquet");
+        assert!(ReportFormat::from_str("xlsx").is_err());
+    }
+}
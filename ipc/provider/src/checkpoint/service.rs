@@ -0,0 +1,619 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Supervisor running several bottom-up relayers, possibly for unrelated subnet trees,
+//! in the same process.
+//!
+//! Each relayer runs in its own task built from its own config and keystore, and is
+//! restarted with an exponential backoff if it crashes.
+//!
+//! The runtime state of the evm relayers can be exported to a single archive and imported
+//! in a service on another host, to migrate the relayers without losing their progress or
+//! submitting their checkpoints twice.
+//!
+//! The relayers share the limits of the rpc requests in flight of the process, the ones of the
+//! endpoints of their subnets and the global one set with
+//! [`RelayerService::with_rpc_concurrency`], so that catching up on many subnets at once
+//! overwhelms neither the endpoints nor the host.
+//!
+//! With [`RelayerService::with_parent_pacing`], the evm relayers sharing a parent also share a
+//! budget of submissions per block of the parent, the subnets of the highest priority going
+//! first and then the ones furthest behind, so that they do not spike the base fee they all
+//! pay.
+
+use crate::checkpoint::pacing::{SubmissionPacer, SubmissionPriority};
+use crate::checkpoint::BottomUpCheckpointManager;
+use crate::clock::{unix_secs, TokioClock};
+use crate::config::Subnet;
+use crate::journal::{JournalEntry, TxJournal};
+use crate::manager::evm::set_global_rpc_concurrency;
+use crate::monitor;
+use anyhow::{anyhow, Context, Result};
+use futures_util::FutureExt;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{EthKeyAddress, PersistentKeyStore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// The delay before the first restart of a crashed relayer, doubled on every crash.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// A relayer that ran for this long before crashing restarts with the initial backoff.
+const STABLE_RUN: Duration = Duration::from_secs(600);
+/// The version of the format of the state archives.
+const STATE_ARCHIVE_VERSION: u32 = 1;
+
+pub type RelayerFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// Builds and runs a relayer, called again on every restart.
+pub type RelayerFactory = Arc<dyn Fn() -> RelayerFuture + Send + Sync>;
+
+/// The config of an evm relayer run by the service.
+#[derive(Clone)]
+pub struct EvmRelayerConfig {
+    pub parent: Subnet,
+    pub child: Subnet,
+    pub keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+    pub journal: Option<Arc<TxJournal>>,
+    pub submitter: Address,
+    pub submission_interval: Duration,
+    pub finalization_blocks: ChainEpoch,
+    /// The maximum random deviation from the submission interval between two rounds.
+    pub submission_jitter: Duration,
+    /// The delay before the first round. If not set, it is derived from the name of the
+    /// relayer, so that the relayers of the service do not all submit at the same time.
+    pub phase_offset: Option<Duration>,
+    /// The priority of the submissions of the subnet over the others of the same parent when
+    /// they are paced, see [`RelayerService::with_parent_pacing`].
+    pub priority: SubmissionPriority,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayerState {
+    Running,
+    /// The relayer crashed and is waiting for the backoff to restart.
+    Restarting,
+    /// The relayer returned without error, it is not restarted.
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayerStatus {
+    pub name: String,
+    pub state: RelayerState,
+    pub restarts: u64,
+    pub last_error: Option<String>,
+}
+
+/// The aggregate status of all the relayers of the service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub relayers: Vec<RelayerStatus>,
+}
+
+impl ServiceStatus {
+    /// Whether all the relayers are running.
+    pub fn healthy(&self) -> bool {
+        self.relayers
+            .iter()
+            .all(|r| r.state == RelayerState::Running)
+    }
+}
+
+/// The runtime state of the evm relayers of a service, exported to move them to another host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceState {
+    pub version: u32,
+    /// Unix timestamp in seconds of the export.
+    pub exported_at: u64,
+    pub relayers: Vec<RelayerSnapshot>,
+}
+
+/// The runtime state of an evm relayer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayerSnapshot {
+    pub name: String,
+    pub child: String,
+    pub submitter: String,
+    /// The height of the last checkpoint the relayer committed, if it committed any since it
+    /// was started or imported.
+    pub last_committed_height: Option<ChainEpoch>,
+    /// The entries of the journal of the relayer, including its pending transactions, whose
+    /// nonces are taken again once they are reconciled.
+    pub journal: Vec<JournalEntry>,
+}
+
+/// What the service keeps of an evm relayer to export its state.
+struct EvmRelayer {
+    child: SubnetID,
+    submitter: Address,
+    journal: Option<Arc<TxJournal>>,
+}
+
+#[derive(Default)]
+pub struct RelayerService {
+    factories: BTreeMap<String, RelayerFactory>,
+    evm_relayers: BTreeMap<String, EvmRelayer>,
+    /// The height of the last checkpoint committed by each evm relayer.
+    heights: Arc<Mutex<BTreeMap<String, ChainEpoch>>>,
+    status: Arc<Mutex<BTreeMap<String, RelayerStatus>>>,
+    tasks: Vec<JoinHandle<()>>,
+    /// The submission budget per block of the parents and their block time, if paced.
+    pacing: Option<(usize, Duration)>,
+    /// The pacers shared by the evm relayers of each parent.
+    pacers: HashMap<SubnetID, Arc<SubmissionPacer>>,
+}
+
+impl RelayerService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caps the rpc requests in flight of all the relayers to `limit`, on top of the
+    /// `provider_max_concurrency` of their subnets. The global limit is the one of the process,
+    /// it can only be set once.
+    pub fn with_rpc_concurrency(self, limit: usize) -> Result<Self> {
+        set_global_rpc_concurrency(limit)?;
+        Ok(self)
+    }
+
+    /// Paces the submissions of the evm relayers added next to at most `budget` per block of
+    /// their parent, which is produced every `block_time`. The relayers of the same parent
+    /// share the budget.
+    pub fn with_parent_pacing(mut self, budget: usize, block_time: Duration) -> Result<Self> {
+        // fails early on an invalid budget
+        SubmissionPacer::new(budget, block_time)?;
+        self.pacing = Some((budget, block_time));
+        Ok(self)
+    }
+
+    /// Adds a relayer under a unique `name`, also used as the label of its metrics.
+    pub fn add(&mut self, name: impl Into<String>, factory: RelayerFactory) -> Result<()> {
+        let name = name.into();
+        if self.factories.contains_key(&name) {
+            return Err(anyhow!("relayer {name} already exists"));
+        }
+        self.factories.insert(name, factory);
+        Ok(())
+    }
+
+    /// Adds an evm bottom-up relayer.
+    pub fn add_evm(&mut self, name: impl Into<String>, config: EvmRelayerConfig) -> Result<()> {
+        let name = name.into();
+        let label = name.clone();
+        let offset = config
+            .phase_offset
+            .unwrap_or_else(|| phase_offset(&name, config.submission_interval));
+        let relayer = EvmRelayer {
+            child: config.child.id.clone(),
+            submitter: config.submitter,
+            journal: config.journal.clone(),
+        };
+        let pacer = match self.pacing {
+            Some((budget, block_time)) => Some(
+                self.pacers
+                    .entry(config.parent.id.clone())
+                    .or_insert(Arc::new(SubmissionPacer::new(budget, block_time)?))
+                    .clone(),
+            ),
+            None => None,
+        };
+        let heights = self.heights.clone();
+        self.add(
+            name.clone(),
+            Arc::new(move || -> RelayerFuture {
+                let config = config.clone();
+                let label = label.clone();
+                let heights = heights.clone();
+                let pacer = pacer.clone();
+                Box::pin(async move {
+                    let mut manager = BottomUpCheckpointManager::new_evm_manager(
+                        config.parent,
+                        config.child,
+                        config.keystore,
+                        config.journal,
+                    )
+                    .await?
+                    .with_finalization_blocks(config.finalization_blocks)
+                    .with_metrics_label(label.clone())
+                    .with_submission_jitter(config.submission_jitter)
+                    .with_phase_offset(offset)
+                    .with_submission_priority(config.priority)
+                    .on_success(move |success| {
+                        let mut heights = heights.lock().unwrap();
+                        let height = heights.entry(label.clone()).or_default();
+                        *height = (*height).max(success.checkpoint.block_height);
+                        async { Ok(()) }
+                    });
+                    if let Some(pacer) = pacer {
+                        manager = manager.with_pacer(pacer);
+                    }
+                    manager
+                        .run(config.submitter, config.submission_interval)
+                        .await;
+                    Ok(())
+                })
+            }),
+        )?;
+        self.evm_relayers.insert(name, relayer);
+        Ok(())
+    }
+
+    /// Spawns all the relayers along with their supervisors.
+    pub fn start(&mut self) {
+        for (name, factory) in self.factories.iter() {
+            self.status.lock().unwrap().insert(
+                name.clone(),
+                RelayerStatus {
+                    name: name.clone(),
+                    state: RelayerState::Running,
+                    restarts: 0,
+                    last_error: None,
+                },
+            );
+            self.tasks.push(tokio::spawn(supervise(
+                name.clone(),
+                factory.clone(),
+                self.status.clone(),
+            )));
+        }
+    }
+
+    pub fn status(&self) -> ServiceStatus {
+        ServiceStatus {
+            relayers: self.status.lock().unwrap().values().cloned().collect(),
+        }
+    }
+
+    /// Stops all the relayers.
+    pub fn shutdown(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+
+    /// The runtime state of the evm relayers: their last committed height and journaled
+    /// transactions.
+    pub fn state(&self) -> ServiceState {
+        let heights = self.heights.lock().unwrap();
+        let relayers = self
+            .evm_relayers
+            .iter()
+            .map(|(name, relayer)| {
+                let journal = relayer
+                    .journal
+                    .as_ref()
+                    .map(|j| j.entries())
+                    .unwrap_or_default();
+                RelayerSnapshot {
+                    name: name.clone(),
+                    child: relayer.child.to_string(),
+                    submitter: relayer.submitter.to_string(),
+                    last_committed_height: heights.get(name).copied(),
+                    journal,
+                }
+            })
+            .collect();
+
+        ServiceState {
+            version: STATE_ARCHIVE_VERSION,
+            exported_at: unix_secs(&TokioClock),
+            relayers,
+        }
+    }
+
+    /// Writes the runtime state of the evm relayers to the archive at `path`. To migrate the
+    /// relayers, the service should be shut down first, so that no transaction is sent after
+    /// the export.
+    pub fn export_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !self.tasks.is_empty() {
+            log::warn!("exporting the state of running relayers, it may be outdated");
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.state())?)
+            .with_context(|| format!("cannot write relayer state to {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("cannot move relayer state to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Restores the state exported by another service from the archive at `path`, before the
+    /// relayers are started. The journaled transactions are added to the journals of the
+    /// relayers of the same name, whose pending transactions are reconciled when they start.
+    pub fn import_state(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read relayer state at {}", path.display()))?;
+        let state: ServiceState = serde_json::from_str(&content)
+            .with_context(|| format!("cannot parse relayer state at {}", path.display()))?;
+        self.restore_state(state)
+    }
+
+    fn restore_state(&mut self, state: ServiceState) -> Result<()> {
+        if !self.tasks.is_empty() {
+            return Err(anyhow!("cannot import the state of running relayers"));
+        }
+        if state.version != STATE_ARCHIVE_VERSION {
+            return Err(anyhow!(
+                "unsupported relayer state version {}, expected {STATE_ARCHIVE_VERSION}",
+                state.version
+            ));
+        }
+
+        // Check all the relayers before importing anything.
+        for snapshot in state.relayers.iter() {
+            let Some(relayer) = self.evm_relayers.get(&snapshot.name) else {
+                continue;
+            };
+            if relayer.child.to_string() != snapshot.child {
+                return Err(anyhow!(
+                    "relayer {} relays subnet {}, the state is of subnet {}",
+                    snapshot.name,
+                    relayer.child,
+                    snapshot.child
+                ));
+            }
+            if relayer.journal.is_none() && snapshot.journal.iter().any(|e| e.status.is_pending()) {
+                return Err(anyhow!(
+                    "relayer {} has pending transactions but no journal to import them into",
+                    snapshot.name
+                ));
+            }
+        }
+
+        for snapshot in state.relayers {
+            let Some(relayer) = self.evm_relayers.get(&snapshot.name) else {
+                log::warn!("no relayer {} to import the state of", snapshot.name);
+                continue;
+            };
+            if relayer.submitter.to_string() != snapshot.submitter {
+                log::warn!(
+                    "relayer {} submits with {}, its state was exported with {}",
+                    snapshot.name,
+                    relayer.submitter,
+                    snapshot.submitter
+                );
+            }
+            if let Some(journal) = &relayer.journal {
+                let imported = journal.import(snapshot.journal)?;
+                log::info!(
+                    "imported {imported} journaled transactions of relayer {}",
+                    snapshot.name
+                );
+            }
+            if let Some(height) = snapshot.last_committed_height {
+                let mut heights = self.heights.lock().unwrap();
+                let h = heights.entry(snapshot.name).or_default();
+                *h = (*h).max(height);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RelayerService {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+async fn supervise(
+    name: String,
+    factory: RelayerFactory,
+    status: Arc<Mutex<BTreeMap<String, RelayerStatus>>>,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let started = Instant::now();
+        // The relayer runs in the supervisor task, so that aborting the latter stops it too.
+        let error = match AssertUnwindSafe(factory()).catch_unwind().await {
+            Ok(Ok(())) => {
+                log::info!("relayer {name} finished");
+                update(&status, &name, |s| s.state = RelayerState::Finished);
+                return;
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "relayer task panicked".to_string(),
+        };
+
+        if started.elapsed() >= STABLE_RUN {
+            backoff = INITIAL_BACKOFF;
+        }
+        log::error!("relayer {name} stopped: {error}, restarting in {backoff:?}");
+        update(&status, &name, |s| {
+            s.state = RelayerState::Restarting;
+            s.last_error = Some(error);
+        });
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+
+        monitor::RELAYER_RESTARTS.with_label_values(&[&name]).inc();
+        update(&status, &name, |s| {
+            s.state = RelayerState::Running;
+            s.restarts += 1;
+        });
+    }
+}
+
+/// An offset within the submission `interval`, stable for the same relayer `name` across
+/// restarts and builds, taken from the sha256 of the name.
+fn phase_offset(name: &str, interval: Duration) -> Duration {
+    let millis = interval.as_millis() as u64;
+    if millis == 0 {
+        return Duration::ZERO;
+    }
+    let digest = Sha256::digest(name.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    Duration::from_millis(u64::from_be_bytes(prefix) % millis)
+}
+
+fn update<F>(status: &Mutex<BTreeMap<String, RelayerStatus>>, name: &str, f: F)
+where
+    F: FnOnce(&mut RelayerStatus),
+{
+    if let Some(s) = status.lock().unwrap().get_mut(name) {
+        f(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        phase_offset, EvmRelayer, RelayerFactory, RelayerFuture, RelayerService, RelayerState,
+    };
+    use crate::journal::{NewEntry, TxIntent, TxJournal, TxStatus};
+    use anyhow::anyhow;
+    use fvm_shared::address::Address;
+    use ipc_api::subnet_id::SubnetID;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn evm_service(child: SubnetID, journal: Option<Arc<TxJournal>>) -> RelayerService {
+        let mut service = RelayerService::new();
+        service.evm_relayers.insert(
+            "relayer".to_string(),
+            EvmRelayer {
+                child,
+                submitter: Address::new_id(100),
+                journal,
+            },
+        );
+        service
+    }
+
+    fn submission(height: i64) -> NewEntry {
+        NewEntry {
+            intent: TxIntent::SubmitCheckpoint {
+                subnet: "/r314159/t410f".to_string(),
+                height,
+            },
+            chain_id: 314159,
+            from: "0x6be1ccf648c74800380d0520d797a170c808b624".to_string(),
+            nonce: height as u64,
+            calldata_hash: "0x00".to_string(),
+            tx_hash: format!("0x{height:064x}"),
+            raw_tx: "0x00".to_string(),
+            idempotency_key: None,
+            params_hash: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_crashed_relayer_is_restarted() {
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let mut service = RelayerService::new();
+        let r = runs.clone();
+        service
+            .add(
+                "flaky",
+                Arc::new(move || -> RelayerFuture {
+                    let r = r.clone();
+                    Box::pin(async move {
+                        if r.fetch_add(1, Ordering::SeqCst) == 0 {
+                            return Err(anyhow!("boom"));
+                        }
+                        Ok(())
+                    })
+                }),
+            )
+            .unwrap();
+        service.start();
+
+        // the paused clock skips the restart backoff
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let status = service.status();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(status.relayers[0].state, RelayerState::Finished);
+        assert_eq!(status.relayers[0].restarts, 1);
+        assert_eq!(status.relayers[0].last_error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_duplicate_relayer_name() {
+        let mut service = RelayerService::new();
+        let factory: RelayerFactory = Arc::new(|| -> RelayerFuture { Box::pin(async { Ok(()) }) });
+        service.add("a", factory.clone()).unwrap();
+        assert!(service.add("a", factory).is_err());
+    }
+
+    #[test]
+    fn test_phase_offset_within_interval() {
+        let interval = Duration::from_secs(15);
+        for name in ["a", "b", "calibration", "mainnet"] {
+            let offset = phase_offset(name, interval);
+            assert!(offset < interval);
+            assert_eq!(offset, phase_offset(name, interval));
+        }
+        assert_eq!(phase_offset("a", Duration::ZERO), Duration::ZERO);
+        // the offset does not depend on the build
+        assert_eq!(
+            phase_offset("calibration", interval),
+            Duration::from_millis(2982)
+        );
+    }
+
+    #[test]
+    fn test_export_and_import_state() {
+        let child = SubnetID::new_root(314159);
+        let journal = Arc::new(TxJournal::in_memory());
+        let id = journal.record(submission(10)).unwrap();
+        journal
+            .set_status(id, TxStatus::Confirmed { block: 5 })
+            .unwrap();
+        journal.record(submission(20)).unwrap();
+
+        let source = evm_service(child.clone(), Some(journal));
+        source
+            .heights
+            .lock()
+            .unwrap()
+            .insert("relayer".to_string(), 10);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        source.export_state(&path).unwrap();
+
+        let state = source.state();
+        assert_eq!(state.relayers[0].last_committed_height, Some(10));
+        assert_eq!(state.relayers[0].journal.len(), 2);
+
+        let journal = Arc::new(TxJournal::in_memory());
+        let mut target = evm_service(child.clone(), Some(journal.clone()));
+        target.import_state(&path).unwrap();
+        assert_eq!(target.heights.lock().unwrap()["relayer"], 10);
+        assert_eq!(journal.entries().len(), 2);
+        assert_eq!(journal.pending()[0].tx_hash, submission(20).tx_hash);
+
+        // importing twice does not duplicate the transactions
+        target.import_state(&path).unwrap();
+        assert_eq!(journal.entries().len(), 2);
+
+        // the pending transactions cannot be dropped
+        let mut target = evm_service(child, None);
+        assert!(target.import_state(&path).is_err());
+
+        let mut target = evm_service(
+            SubnetID::new_root(1),
+            Some(Arc::new(TxJournal::in_memory())),
+        );
+        assert!(target.import_state(&path).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! A compact JSON snapshot of the state of a relayer written to disk periodically, for the
+//! operators without a Prometheus scraping the metrics, and as the single artifact to attach
+//! to a support request.
+//!
+//! Each report replaces the file of the previous one, which is rotated first: `state.json`
+//! becomes `state.json.1`, `state.json.1` becomes `state.json.2` and so on, up to the number of
+//! reports kept. The reports are written to a temporary file first, so that a reader never sees
+//! a partial one.
+
+use crate::clock::{default_clock, unix_secs, Clock, TokioClock};
+use crate::manager::{BottomUpCheckpointRelayer, SubnetQuery};
+use anyhow::{Context, Result};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The version of the format of the reports.
+const STATE_REPORT_VERSION: u32 = 1;
+/// The default number of previous reports kept next to the current one.
+pub const DEFAULT_KEPT_REPORTS: usize = 5;
+/// The default number of recent errors included in a report.
+pub const DEFAULT_RECENT_ERRORS: usize = 10;
+
+/// The state of a relayer at the time of the report. The values that could not be queried
+/// are missing, with the reason in `query_errors`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayerStateReport {
+    pub version: u32,
+    /// Unix timestamp in seconds of the report.
+    pub written_at: u64,
+    pub subnet: String,
+    pub submitter: Option<String>,
+    pub parent_head: Option<ChainEpoch>,
+    pub child_head: Option<ChainEpoch>,
+    pub checkpoint_period: Option<ChainEpoch>,
+    pub last_committed_height: Option<ChainEpoch>,
+    /// The child blocks not covered by a committed checkpoint yet.
+    pub lag: Option<ChainEpoch>,
+    /// The balance of the submitter in the parent, in whole tokens.
+    pub submitter_balance: Option<String>,
+    /// The most recent submission errors, the oldest first.
+    pub last_errors: Vec<RecordedError>,
+    pub query_errors: Vec<String>,
+}
+
+/// A submission error of the relayer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedError {
+    /// Unix timestamp in seconds of the error.
+    pub at: u64,
+    /// The height of the checkpoint whose submission failed.
+    pub height: ChainEpoch,
+    pub error: String,
+}
+
+/// The most recent errors of a relayer, shared between the relayer recording them and the
+/// reporter.
+#[derive(Debug, Clone)]
+pub struct RecentErrors {
+    capacity: usize,
+    errors: Arc<Mutex<VecDeque<RecordedError>>>,
+}
+
+impl Default for RecentErrors {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT_ERRORS)
+    }
+}
+
+impl RecentErrors {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            errors: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn record(&self, height: ChainEpoch, error: impl Into<String>) {
+        let mut errors = self.errors.lock().unwrap();
+        errors.push_back(RecordedError {
+            at: unix_secs(&TokioClock),
+            height,
+            error: error.into(),
+        });
+        while errors.len() > self.capacity {
+            errors.pop_front();
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<RecordedError> {
+        self.errors.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Writes the state of the relayer of `subnet` to a file periodically.
+pub struct StateReporter<P, C> {
+    parent: P,
+    child: C,
+    subnet: SubnetID,
+    submitter: Option<Address>,
+    path: PathBuf,
+    keep: usize,
+    errors: RecentErrors,
+    clock: Arc<dyn Clock>,
+}
+
+impl<P: SubnetQuery + BottomUpCheckpointRelayer, C: SubnetQuery> StateReporter<P, C> {
+    /// Reports the state of the relayer of `subnet` to the file `path`, querying its `parent`
+    /// and `child` subnets.
+    pub fn new(parent: P, child: C, subnet: SubnetID, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create state directory {}", dir.display()))?;
+        }
+        Ok(Self {
+            parent,
+            child,
+            subnet,
+            submitter: None,
+            path,
+            keep: DEFAULT_KEPT_REPORTS,
+            errors: RecentErrors::default(),
+            clock: default_clock(),
+        })
+    }
+
+    /// Includes the balance of `submitter` in the reports.
+    pub fn with_submitter(mut self, submitter: Address) -> Self {
+        self.submitter = Some(submitter);
+        self
+    }
+
+    /// Keeps `keep` previous reports next to the current one.
+    pub fn with_kept_reports(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Includes the errors recorded in `errors` in the reports.
+    pub fn with_errors(mut self, errors: RecentErrors) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Timestamps the reports with `clock`, e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The current state of the relayer.
+    pub async fn report(&self) -> RelayerStateReport {
+        let mut errors = vec![];
+
+        let parent_head = self.parent.chain_head_timestamp().await.map(|(h, _)| h);
+        let parent_head = queried(&mut errors, "parent head", parent_head);
+        let child_head = self.child.chain_head_timestamp().await.map(|(h, _)| h);
+        let child_head = queried(&mut errors, "child head", child_head);
+        let status = self.parent.checkpoint_status(&self.subnet).await;
+        let status = queried(&mut errors, "checkpoint status", status);
+        let submitter_balance = match &self.submitter {
+            Some(submitter) => {
+                let balance = self.parent.wallet_balance(submitter, None).await;
+                queried(&mut errors, "submitter balance", balance).map(|b| b.to_string())
+            }
+            None => None,
+        };
+
+        let last_committed_height = status.map(|s| s.last_committed_height);
+        RelayerStateReport {
+            version: STATE_REPORT_VERSION,
+            written_at: unix_secs(self.clock.as_ref()),
+            subnet: self.subnet.to_string(),
+            submitter: self.submitter.map(|s| s.to_string()),
+            parent_head,
+            child_head,
+            checkpoint_period: status.map(|s| s.period),
+            last_committed_height,
+            lag: child_head
+                .zip(last_committed_height)
+                .map(|(head, last)| (head - last).max(0)),
+            submitter_balance,
+            last_errors: self.errors.snapshot(),
+            query_errors: errors,
+        }
+    }
+
+    /// Writes the current state, rotating the previous reports.
+    pub async fn write_now(&self) -> Result<()> {
+        let report = self.report().await;
+        write_rotated(&self.path, self.keep, &serde_json::to_vec_pretty(&report)?)
+    }
+
+    /// Writes a report every `interval`, forever. The failed writes are only logged.
+    pub async fn run(self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.write_now().await {
+                log::error!(
+                    "cannot write the state of {} to {}: {e}",
+                    self.subnet,
+                    self.path.display()
+                );
+            }
+        }
+    }
+}
+
+/// Writes `content` to `path`, after shifting the previous files, keeping `keep` of them.
+pub(crate) fn write_rotated(path: &Path, keep: usize, content: &[u8]) -> Result<()> {
+    let rotated = |i: usize| PathBuf::from(format!("{}.{i}", path.display()));
+
+    if keep > 0 && path.exists() {
+        for i in (1..keep).rev() {
+            if rotated(i).exists() {
+                fs::rename(rotated(i), rotated(i + 1))?;
+            }
+        }
+        fs::rename(path, rotated(1))?;
+    }
+
+    let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&tmp, content).with_context(|| format!("cannot write {}", tmp.display()))?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// The value of a query, or `None` with its error added to `errors`.
+fn queried<T>(errors: &mut Vec<String>, what: &str, result: Result<T>) -> Option<T> {
+    match result {
+        Ok(v) => Some(v),
+        Err(e) => {
+            errors.push(format!("{what}: {e}"));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_rotated, RecentErrors};
+    use std::fs;
+
+    #[test]
+    fn test_write_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+
+        for i in 0..4 {
+            write_rotated(&path, 2, i.to_string().as_bytes()).unwrap();
+        }
+
+        assert_eq!(read("state.json"), "3");
+        assert_eq!(read("state.json.1"), "2");
+        assert_eq!(read("state.json.2"), "1");
+        assert!(!dir.path().join("state.json.3").exists());
+        assert!(!dir.path().join("state.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_recent_errors() {
+        let errors = RecentErrors::new(2);
+        for height in [10, 20, 30] {
+            errors.record(height, format!("failed at {height}"));
+        }
+
+        let snapshot = errors.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].height, 20);
+        assert_eq!(snapshot[1].error, "failed at 30");
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The source of time of the components scheduling, retrying or timing out work, injected so
+//! that tests can drive them deterministically.
+//!
+//! [`TokioClock`] follows the time of the tokio runtime, which tests can pause and advance with
+//! `tokio::time::pause` and `tokio::time::advance`. [`ManualClock`] only moves when advanced
+//! explicitly, independently of the runtime.
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// The current instant, to measure the time elapsed between two events.
+    fn now(&self) -> Instant;
+
+    /// The current wall-clock time.
+    fn system_time(&self) -> SystemTime;
+
+    /// Waits until `duration` has elapsed.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The clock of the tokio runtime, the default one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// The default clock, shared by the components not given one.
+pub fn default_clock() -> Arc<dyn Clock> {
+    Arc::new(TokioClock)
+}
+
+/// The unix timestamp in seconds of the wall-clock time of `clock`.
+pub fn unix_secs(clock: &dyn Clock) -> u64 {
+    clock
+        .system_time()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A clock standing still until [`ManualClock::advance`]d, waking up the sleepers whose
+/// deadline is reached.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: Instant,
+    start_system: SystemTime,
+    /// The time elapsed since the start.
+    elapsed: Arc<watch::Sender<Duration>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_system: SystemTime::now(),
+            elapsed: Arc::new(watch::channel(Duration::ZERO).0),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += duration);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system + self.elapsed()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let deadline = self.elapsed() + duration;
+        let mut elapsed = self.elapsed.subscribe();
+        // the sender lives as long as the clock
+        let _ = elapsed.wait_for(|e| *e >= deadline).await;
+    }
+}
+
+/// The error of a future that did not complete before its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("deadline of {0:?} elapsed")]
+pub struct Elapsed(pub Duration);
+
+/// Runs `f`, failing with [`Elapsed`] if it does not complete within `duration` of `clock`.
+pub async fn timeout<F: Future>(
+    clock: &dyn Clock,
+    duration: Duration,
+    f: F,
+) -> Result<F::Output, Elapsed> {
+    tokio::select! {
+        biased;
+        r = f => Ok(r),
+        _ = clock.sleep(duration) => Err(Elapsed(duration)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{timeout, Clock, Elapsed, ManualClock, TokioClock};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_manual_clock_wakes_sleepers() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+
+        let sleeper = {
+            let clock = clock.clone();
+            tokio::spawn(async move { clock.sleep(Duration::from_secs(10)).await })
+        };
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::from_secs(9));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+
+        clock.advance(Duration::from_secs(1));
+        sleeper.await.unwrap();
+        assert_eq!(clock.now() - start, Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_with_manual_clock() {
+        let clock = ManualClock::new();
+
+        let r = timeout(&clock, Duration::from_secs(1), async { 42 }).await;
+        assert_eq!(r, Ok(42));
+
+        let pending = {
+            let clock = clock.clone();
+            tokio::spawn(async move {
+                timeout(&clock, Duration::from_secs(5), std::future::pending::<()>()).await
+            })
+        };
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(pending.await.unwrap(), Err(Elapsed(Duration::from_secs(5))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokio_clock_follows_paused_time() {
+        let clock = TokioClock;
+        let start = clock.now();
+
+        // completes at once, the paused runtime jumps to the deadline
+        clock.sleep(Duration::from_secs(3600)).await;
+        assert_eq!(clock.now() - start, Duration::from_secs(3600));
+
+        let r = timeout(
+            &clock,
+            Duration::from_secs(60),
+            std::future::pending::<()>(),
+        )
+        .await;
+        assert!(r.is_err());
+    }
+}
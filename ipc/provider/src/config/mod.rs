@@ -6,6 +6,7 @@
 //! [`Config`] struct.
 
 pub mod deserialize;
+pub mod profile;
 pub mod subnet;
 
 pub mod serialize;
@@ -15,19 +16,50 @@ mod tests;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use crate::logging::LogLevels;
+use anyhow::{bail, Context, Result};
 use deserialize::deserialize_subnets_from_vec;
 use ipc_api::subnet_id::SubnetID;
+use profile::apply_network_profile;
+pub use profile::NetworkProfile;
 use serde::{Deserialize, Serialize};
 use serialize::serialize_subnets_to_str;
 pub use subnet::Subnet;
+use url::Url;
 
 pub const JSON_RPC_VERSION: &str = "2.0";
 
 /// DefaulDEFAULT_CHAIN_IDSUBNET_e
 pub const DEFAULT_CONFIG_TEMPLATE: &str = r#"
 keystore_path = "~/.ipc"
+# Route all the RPC traffic through a proxy (http, https, socks5 or socks5h),
+# subnets can override it with their own `proxy` setting.
+# proxy = "socks5h://127.0.0.1:9050"
+# Add the root subnet of a well-known network (calibration, mainnet or localnet),
+# a [[subnets]] entry with the same id can override any of its fields.
+# network = "calibration"
+# Require a confirmation of the fund, release and join operations moving more than
+# this amount, in whole tokens.
+# confirmation_threshold = 100
+# Limit the rpc requests in flight to all the subnets, subnets can also limit the
+# requests to their own endpoint with `provider_max_concurrency`.
+# rpc_max_concurrency = 64
+# Sign the transactions of some subnets or roles with their own keystore, either a
+# directory like `keystore_path` or a key source like the `--key-source` of the relayer.
+# [keystores.relayer]
+# source = "aws-kms:alias/relayer"
+# roles = ["relayer"]
+# [keystores.admin]
+# path = "~/.ipc-admin"
+# subnets = ["/r314159/<SUBNET_ID>"]
+# Submit the checkpoints of some subnets through a forwarder contract in their parent,
+# signed by the submitter of the relayer but paid for by another account.
+# [forwarders.registry]
+# address = "0x<FORWARDER_ADDR>"
+# payer = "0x<PAYER_ADDR>"
+# subnets = ["/r314159/<SUBNET_ID>"]
 
 # Filecoin Calibration
 [[subnets]]
@@ -52,10 +84,37 @@ registry_addr = "0x0b4e239FF21b40120cDa817fba77bD1B366c1bcD"
 
 /// The top-level struct representing the config. Calls to [`Config::from_file`] deserialize into
 /// this struct.
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     /// Directory of the keystore that wants to be made available by the provider.
     pub keystore_path: Option<String>,
+    /// Global proxy used for the outbound RPC traffic of all subnets, i.e. `socks5h://127.0.0.1:9050`.
+    /// Subnets can override it with their own `proxy` setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Url>,
+    /// The well-known network whose root subnet is added to the subnets, see [`profile`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkProfile>,
+    /// The amount in whole tokens above which the operations moving value require a
+    /// confirmation, see [`crate::confirmation`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_threshold: Option<u64>,
+    /// The maximum number of rpc requests in flight of the process to all the subnets, see
+    /// [`crate::manager::evm::set_global_rpc_concurrency`]. The subnets can also limit the
+    /// requests to their own endpoint with `provider_max_concurrency`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_max_concurrency: Option<usize>,
+    /// The log levels of the components of the provider, see [`crate::logging`].
+    #[serde(default, skip_serializing_if = "LogLevels::is_empty")]
+    pub log_levels: LogLevels,
+    /// The keystores of the subnets and roles not signing with the one of `keystore_path`, by
+    /// name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub keystores: HashMap<String, KeystoreConfig>,
+    /// The forwarders submitting the checkpoints of some subnets on behalf of their submitter,
+    /// by name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub forwarders: HashMap<String, ForwarderConfig>,
     #[serde(deserialize_with = "deserialize_subnets_from_vec", default)]
     #[serde(serialize_with = "serialize_subnets_to_str")]
     pub subnets: HashMap<SubnetID, Subnet>,
@@ -66,13 +125,24 @@ impl Config {
     pub fn new() -> Self {
         Config {
             keystore_path: None,
+            proxy: None,
+            network: None,
+            confirmation_threshold: None,
+            rpc_max_concurrency: None,
+            log_levels: Default::default(),
+            keystores: Default::default(),
+            forwarders: Default::default(),
             subnets: Default::default(),
         }
     }
 
     /// Reads a TOML configuration in the `s` string and returns a [`Config`] struct.
     pub fn from_toml_str(s: &str) -> Result<Self> {
-        let config = toml::from_str(s)?;
+        let mut table: toml::Table = toml::from_str(s)?;
+        apply_network_profile(&mut table)?;
+        let config: Config = toml::Value::Table(table).try_into()?;
+        config.validate_keystores()?;
+        config.validate_forwarders()?;
         Ok(config)
     }
 
@@ -110,6 +180,132 @@ impl Config {
     pub fn remove_subnet(&mut self, subnet_id: &SubnetID) {
         self.subnets.remove(subnet_id);
     }
+
+    /// The name of the keystore signing the transactions of `subnet_id`, if not the one of
+    /// `keystore_path`.
+    pub fn keystore_of_subnet(&self, subnet_id: &SubnetID) -> Option<&str> {
+        self.keystores
+            .iter()
+            .find(|(_, k)| {
+                k.subnets
+                    .iter()
+                    .any(|s| SubnetID::from_str(s).ok().as_ref() == Some(subnet_id))
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The name of the keystore of `role`, e.g. `relayer`, if any.
+    pub fn keystore_of_role(&self, role: &str) -> Option<&str> {
+        self.keystores
+            .iter()
+            .find(|(_, k)| k.roles.iter().any(|r| r == role))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Checks that every keystore has either a path or a source, and that no subnet or role
+    /// is assigned to several keystores.
+    fn validate_keystores(&self) -> Result<()> {
+        let mut subnets = HashMap::new();
+        let mut roles = HashMap::new();
+        for (name, keystore) in &self.keystores {
+            if keystore.path.is_some() == keystore.source.is_some() {
+                bail!("keystore {name} must have either a path or a source");
+            }
+            for subnet in &keystore.subnets {
+                let id = SubnetID::from_str(subnet)
+                    .with_context(|| format!("invalid subnet {subnet} of keystore {name}"))?;
+                if let Some(other) = subnets.insert(id, name) {
+                    bail!("subnet {subnet} is assigned to keystores {other} and {name}");
+                }
+            }
+            for role in &keystore.roles {
+                if let Some(other) = roles.insert(role, name) {
+                    bail!("role {role} is assigned to keystores {other} and {name}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The forwarder submitting the checkpoints of `subnet_id`, if they are not submitted
+    /// directly by the relayer.
+    pub fn forwarder_of_subnet(&self, subnet_id: &SubnetID) -> Option<&ForwarderConfig> {
+        self.forwarders.values().find(|f| {
+            f.subnets
+                .iter()
+                .any(|s| SubnetID::from_str(s).ok().as_ref() == Some(subnet_id))
+        })
+    }
+
+    /// Checks that no subnet is assigned to several forwarders.
+    fn validate_forwarders(&self) -> Result<()> {
+        let mut subnets = HashMap::new();
+        for (name, forwarder) in &self.forwarders {
+            for subnet in &forwarder.subnets {
+                let id = SubnetID::from_str(subnet)
+                    .with_context(|| format!("invalid subnet {subnet} of forwarder {name}"))?;
+                if let Some(other) = subnets.insert(id, name) {
+                    bail!("subnet {subnet} is assigned to forwarders {other} and {name}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the subnet config with the global settings applied to the fields
+    /// the subnet does not override.
+    pub fn subnet(&self, subnet_id: &SubnetID) -> Option<Subnet> {
+        let mut subnet = self.subnets.get(subnet_id)?.clone();
+        if subnet.proxy().is_none() {
+            subnet.set_proxy(self.proxy.clone());
+        }
+        Some(subnet)
+    }
+}
+
+/// A keystore signing the transactions of some subnets or roles, see [`Config::keystores`].
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeystoreConfig {
+    /// The directory of the keystore, like `keystore_path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The source of a single key instead, see [`crate::key_source::KeySource`], e.g.
+    /// `aws-kms:alias/relayer`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The address of the key of a `vault-transit` source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// The subnets whose transactions are signed with this keystore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subnets: Vec<String>,
+    /// The roles signing with this keystore, e.g. `relayer`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+/// A forwarder contract in the parent relaying the checkpoints of some subnets, EIP-2771
+/// style, see [`Config::forwarders`]: the submitter signs a request the forwarder executes on
+/// its behalf, in a transaction sent and paid for by the payer.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ForwarderConfig {
+    /// The address of the forwarder contract.
+    pub address: String,
+    /// The address sending the transactions to the forwarder, its key in the keystore of the
+    /// relayer.
+    pub payer: String,
+    /// The name of the EIP-712 domain of the forwarder, `MinimalForwarder` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The version of the EIP-712 domain of the forwarder, `0.0.1` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The gas forwarded to the submission, estimated if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas: Option<u64>,
+    /// The subnets whose checkpoints are submitted through this forwarder.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subnets: Vec<String>,
 }
 
 impl Default for Config {
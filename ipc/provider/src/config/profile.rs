@@ -0,0 +1,138 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Built-in settings of well-known networks.
+//!
+//! Setting `network = "calibration"` in the config adds the root subnet of that network with
+//! its chain id, endpoint and contract addresses. Any of its fields can be overridden by a
+//! `[[subnets]]` entry with the same id, which only needs to declare the overridden fields.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// The networks whose root subnet is built in.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkProfile {
+    /// Filecoin Calibration.
+    Calibration,
+    /// Filecoin mainnet. The gateway and registry addresses have to be set in the config.
+    Mainnet,
+    /// A local anvil or localnet node. The gateway and registry addresses have to be set in
+    /// the config, as they depend on the deployment.
+    Localnet,
+}
+
+impl Display for NetworkProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkProfile::Calibration => write!(f, "calibration"),
+            NetworkProfile::Mainnet => write!(f, "mainnet"),
+            NetworkProfile::Localnet => write!(f, "localnet"),
+        }
+    }
+}
+
+impl FromStr for NetworkProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "calibration" => Ok(NetworkProfile::Calibration),
+            "mainnet" => Ok(NetworkProfile::Mainnet),
+            "localnet" => Ok(NetworkProfile::Localnet),
+            _ => Err(anyhow!(
+                "unknown network `{s}`, expected calibration, mainnet or localnet"
+            )),
+        }
+    }
+}
+
+impl NetworkProfile {
+    /// The id of the root subnet, from the chain id of the network.
+    pub fn subnet_id(&self) -> &'static str {
+        match self {
+            NetworkProfile::Calibration => "/r314159",
+            NetworkProfile::Mainnet => "/r314",
+            NetworkProfile::Localnet => "/r31337",
+        }
+    }
+
+    /// The `[subnets.config]` of the root subnet.
+    fn subnet_config(&self) -> Table {
+        let mut config = Table::new();
+        config.insert("network_type".into(), "fevm".into());
+        match self {
+            NetworkProfile::Calibration => {
+                config.insert(
+                    "provider_http".into(),
+                    "https://api.calibration.node.glif.io/rpc/v1".into(),
+                );
+                config.insert(
+                    "gateway_addr".into(),
+                    "0x1AEe8A878a22280fc2753b3C63571C8F895D2FE3".into(),
+                );
+                config.insert(
+                    "registry_addr".into(),
+                    "0x0b4e239FF21b40120cDa817fba77bD1B366c1bcD".into(),
+                );
+            }
+            NetworkProfile::Mainnet => {
+                config.insert(
+                    "provider_http".into(),
+                    "https://api.node.glif.io/rpc/v1".into(),
+                );
+            }
+            NetworkProfile::Localnet => {
+                config.insert("provider_http".into(), "http://localhost:8545".into());
+            }
+        }
+        config
+    }
+}
+
+/// Adds the root subnet of the `network` of the `config`, if any, to its `subnets`, merging the
+/// fields of the entry of the config with the same id over the built-in ones.
+pub(crate) fn apply_network_profile(config: &mut Table) -> Result<()> {
+    let Some(network) = config.get("network") else {
+        return Ok(());
+    };
+    let network = NetworkProfile::from_str(
+        network
+            .as_str()
+            .ok_or_else(|| anyhow!("network must be a string"))?,
+    )?;
+
+    let subnets = config
+        .entry("subnets")
+        .or_insert_with(|| Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("subnets must be an array"))?;
+
+    let existing = subnets
+        .iter_mut()
+        .filter_map(Value::as_table_mut)
+        .find(|s| s.get("id").and_then(Value::as_str) == Some(network.subnet_id()));
+
+    match existing {
+        Some(subnet) => {
+            let overrides = subnet
+                .entry("config")
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("the config of {} must be a table", network.subnet_id()))?;
+            for (key, value) in network.subnet_config() {
+                overrides.entry(key).or_insert(value);
+            }
+        }
+        None => {
+            let mut subnet = Table::new();
+            subnet.insert("id".into(), network.subnet_id().into());
+            subnet.insert("config".into(), Value::Table(network.subnet_config()));
+            subnets.push(Value::Table(subnet));
+        }
+    }
+    Ok(())
+}
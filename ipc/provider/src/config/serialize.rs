@@ -121,6 +121,13 @@ mod tests {
     fn test_serialization() {
         let mut config = Config {
             keystore_path: Some(String::from("~/.ipc")),
+            network: None,
+            proxy: None,
+            confirmation_threshold: None,
+            rpc_max_concurrency: None,
+            log_levels: Default::default(),
+            keystores: Default::default(),
+            forwarders: Default::default(),
             subnets: Default::default(),
         };
 
@@ -132,6 +139,10 @@ mod tests {
                 provider_http: "http://127.0.0.1:3030/rpc/v1".parse().unwrap(),
                 provider_timeout: None,
                 auth_token: None,
+                provider_ws: None,
+                proxy: None,
+                block_time: None,
+                provider_max_concurrency: None,
                 registry_addr: Address::from(eth_addr1),
             }),
         };
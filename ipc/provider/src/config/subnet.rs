@@ -5,7 +5,7 @@ use std::time::Duration;
 use fvm_shared::address::Address;
 use ipc_api::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, DurationSeconds};
+use serde_with::{serde_as, DurationSeconds, DurationSecondsWithFrac};
 use url::Url;
 
 use crate::config::deserialize::{
@@ -56,17 +56,56 @@ impl Subnet {
         }
     }
 
+    /// The websocket endpoint of the subnet, if any.
+    pub fn rpc_ws(&self) -> Option<&Url> {
+        match &self.config {
+            SubnetConfig::Fevm(s) => s.provider_ws.as_ref(),
+        }
+    }
+
     pub fn rpc_timeout(&self) -> Option<Duration> {
         match &self.config {
             SubnetConfig::Fevm(s) => s.provider_timeout,
         }
     }
 
+    /// The maximum number of requests in flight to the rpc endpoint of the subnet, if limited.
+    pub fn rpc_max_concurrency(&self) -> Option<usize> {
+        match &self.config {
+            SubnetConfig::Fevm(s) => s.provider_max_concurrency,
+        }
+    }
+
+    /// The configured block time of the subnet, if any.
+    pub fn block_time(&self) -> Option<Duration> {
+        match &self.config {
+            SubnetConfig::Fevm(s) => s.block_time,
+        }
+    }
+
     pub fn gateway_addr(&self) -> Address {
         match &self.config {
             SubnetConfig::Fevm(s) => s.gateway_addr,
         }
     }
+
+    pub fn registry_addr(&self) -> Address {
+        match &self.config {
+            SubnetConfig::Fevm(s) => s.registry_addr,
+        }
+    }
+
+    pub fn proxy(&self) -> Option<&Url> {
+        match &self.config {
+            SubnetConfig::Fevm(s) => s.proxy.as_ref(),
+        }
+    }
+
+    pub fn set_proxy(&mut self, proxy: Option<Url>) {
+        match &mut self.config {
+            SubnetConfig::Fevm(s) => s.proxy = proxy,
+        }
+    }
 }
 
 /// The FVM subnet config parameters
@@ -84,9 +123,25 @@ pub struct FVMSubnet {
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct EVMSubnet {
     pub provider_http: Url,
+    /// Websocket endpoint notifying the new heads, to wait for the transaction receipts
+    /// without polling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_ws: Option<Url>,
     #[serde_as(as = "Option<DurationSeconds<u64>>")]
     pub provider_timeout: Option<Duration>,
     pub auth_token: Option<String>,
+    /// Proxy for the RPC traffic of this subnet, supports `http`, `https`, `socks5` and `socks5h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Url>,
+    /// The average time between two blocks of the subnet, in seconds, for the conversions
+    /// between durations and epochs. Measured on the recent blocks if not set.
+    #[serde_as(as = "Option<DurationSecondsWithFrac<f64>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_time: Option<Duration>,
+    /// The maximum number of requests in flight to `provider_http`, shared by all the
+    /// connections of the process to the same endpoint. Not limited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_max_concurrency: Option<usize>,
 
     #[serde(deserialize_with = "deserialize_eth_address_from_str")]
     #[serde(serialize_with = "serialize_eth_address_to_str")]
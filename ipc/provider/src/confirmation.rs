@@ -0,0 +1,157 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Confirmation of the operations moving value, so that a typo in an amount does not go
+//! through unnoticed in scripts.
+//!
+//! The operations moving more than the threshold of the [`ConfirmationPolicy`] are only sent
+//! once its callback confirms them, and fail without a callback.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::subnet_id::SubnetID;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Fund,
+    FundWithToken,
+    Release,
+    JoinSubnet,
+}
+
+impl Display for OperationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OperationKind::Fund => "fund",
+            OperationKind::FundWithToken => "fund with token",
+            OperationKind::Release => "release",
+            OperationKind::JoinSubnet => "join subnet",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// An operation moving value, before it is sent.
+#[derive(Debug, Clone)]
+pub struct ValueOperation {
+    pub kind: OperationKind,
+    pub subnet: SubnetID,
+    pub from: Address,
+    /// The recipient of the value, if not the subnet itself.
+    pub to: Option<Address>,
+    pub amount: TokenAmount,
+}
+
+impl Display for ValueOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} in subnet {} from {}",
+            self.kind, self.amount, self.subnet, self.from
+        )?;
+        if let Some(to) = &self.to {
+            write!(f, " to {to}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decides whether an operation can go ahead, e.g. by asking the user.
+pub type ConfirmationCallback = Arc<dyn Fn(&ValueOperation) -> bool + Send + Sync>;
+
+/// Requires the confirmation of the operations moving more than a threshold.
+#[derive(Clone)]
+pub struct ConfirmationPolicy {
+    threshold: TokenAmount,
+    confirm: Option<ConfirmationCallback>,
+}
+
+impl ConfirmationPolicy {
+    /// Requires the confirmation of the operations moving more than `threshold`.
+    pub fn new(threshold: TokenAmount) -> Self {
+        Self {
+            threshold,
+            confirm: None,
+        }
+    }
+
+    /// Asks `confirm` whether the operations above the threshold can go ahead.
+    pub fn with_callback<F>(mut self, confirm: F) -> Self
+    where
+        F: Fn(&ValueOperation) -> bool + Send + Sync + 'static,
+    {
+        self.confirm = Some(Arc::new(confirm));
+        self
+    }
+
+    /// Confirms all the operations upfront, e.g. with `--yes` on the command line.
+    pub fn confirmed(self) -> Self {
+        self.with_callback(|_| true)
+    }
+
+    pub fn threshold(&self) -> &TokenAmount {
+        &self.threshold
+    }
+
+    /// Fails if `op` moves more than the threshold and is not confirmed.
+    pub fn check(&self, op: &ValueOperation) -> Result<()> {
+        if op.amount <= self.threshold {
+            return Ok(());
+        }
+        match &self.confirm {
+            Some(confirm) if confirm(op) => Ok(()),
+            Some(_) => Err(anyhow!("{op} was not confirmed")),
+            None => Err(anyhow!(
+                "{op} is above the confirmation threshold of {} and requires a confirmation",
+                self.threshold
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfirmationPolicy, OperationKind, ValueOperation};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::subnet_id::SubnetID;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn fund(amount: u64) -> ValueOperation {
+        ValueOperation {
+            kind: OperationKind::Fund,
+            subnet: SubnetID::new_root(314159),
+            from: Address::new_id(100),
+            to: None,
+            amount: TokenAmount::from_whole(amount),
+        }
+    }
+
+    #[test]
+    fn test_threshold() {
+        let policy = ConfirmationPolicy::new(TokenAmount::from_whole(10));
+        assert!(policy.check(&fund(10)).is_ok());
+        assert!(policy.check(&fund(11)).is_err());
+        assert!(policy.clone().confirmed().check(&fund(1000)).is_ok());
+    }
+
+    #[test]
+    fn test_callback() {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let a = asked.clone();
+        let policy =
+            ConfirmationPolicy::new(TokenAmount::from_whole(10)).with_callback(move |op| {
+                a.fetch_add(1, Ordering::SeqCst);
+                op.amount < TokenAmount::from_whole(100)
+            });
+
+        assert!(policy.check(&fund(5)).is_ok());
+        assert_eq!(asked.load(Ordering::SeqCst), 0);
+        assert!(policy.check(&fund(50)).is_ok());
+        assert!(policy.check(&fund(500)).is_err());
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+    }
+}
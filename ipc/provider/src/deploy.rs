@@ -0,0 +1,408 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Deployment of the IPC contracts to a parent chain.
+//!
+//! [`deploy`] deploys the gateway and the subnet registry, with their facets and libraries, from
+//! the build artifacts of the contracts (`contracts/out` after `make build`), the same way the
+//! deployment scripts of the contracts do. The resulting [`Deployment`] is verified against the
+//! chain, and gives the provider config of the parent chain with the addresses of the contracts.
+
+use crate::config::subnet::{EVMSubnet, SubnetConfig};
+use crate::config::{Config, Subnet};
+use anyhow::{anyhow, Context, Result};
+use ethers::abi::{Abi, Token, Tokenizable};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Eip1559TransactionRequest, H160, U256, U64};
+use fendermint_eth_hardhat::Hardhat;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::i_diamond::FacetCut;
+use ipc_actors_abis::{diamond_loupe_facet, gateway_getter_facet, subnet_getter_facet};
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::subnet_id::SubnetID;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+
+/// The build artifacts of the contracts of this repository.
+pub const DEFAULT_CONTRACTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../contracts/out");
+
+const GATEWAY: &str = "GatewayDiamond";
+const GATEWAY_FACETS: &[&str] = &[
+    "GatewayGetterFacet",
+    "DiamondLoupeFacet",
+    "DiamondCutFacet",
+    "GatewayManagerFacet",
+    "GatewayMessengerFacet",
+    "CheckpointingFacet",
+    "XnetMessagingFacet",
+    "TopDownFinalityFacet",
+    "OwnershipFacet",
+];
+const REGISTRY: &str = "SubnetRegistryDiamond";
+const REGISTRY_FACETS: &[&str] = &[
+    "RegisterSubnetFacet",
+    "SubnetGetterFacet",
+    "DiamondLoupeFacet",
+    "DiamondCutFacet",
+    "OwnershipFacet",
+];
+/// The facets of the subnet actors, deployed once and cut into every subnet by the registry,
+/// in the order of the constructor of the registry.
+const SUBNET_ACTOR_FACETS: &[&str] = &[
+    "SubnetActorGetterFacet",
+    "SubnetActorManagerFacet",
+    "SubnetActorRewardFacet",
+    "SubnetActorCheckpointingFacet",
+    "SubnetActorPauseFacet",
+];
+
+type DeployerMiddleware = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// The parameters of the gateway of the deployment.
+#[derive(Debug, Clone)]
+pub struct DeployParams {
+    /// The directory of the build artifacts of the contracts.
+    pub contracts_dir: PathBuf,
+    pub bottom_up_check_period: ChainEpoch,
+    pub active_validators_limit: u16,
+    pub majority_percentage: u8,
+    /// The commit of the contracts, reported by the gateway.
+    pub commit_sha: [u8; 32],
+}
+
+impl DeployParams {
+    /// The parameters of the deployment scripts of the contracts.
+    pub fn new(contracts_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            contracts_dir: contracts_dir.into(),
+            bottom_up_check_period: 10,
+            active_validators_limit: 100,
+            majority_percentage: 66,
+            commit_sha: [0; 32],
+        }
+    }
+}
+
+impl Default for DeployParams {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONTRACTS_DIR)
+    }
+}
+
+/// The IPC contracts deployed to a parent chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub chain_id: u64,
+    pub gateway: Address,
+    pub registry: Address,
+    /// The account that deployed the contracts, and owns them.
+    pub deployer: Address,
+}
+
+impl Deployment {
+    /// The id of the parent chain, as a root network.
+    pub fn root(&self) -> SubnetID {
+        SubnetID::new_root(self.chain_id)
+    }
+
+    /// The config of the parent chain at `url`, with the addresses of the contracts.
+    pub fn subnet(&self, url: Url) -> Subnet {
+        Subnet {
+            id: self.root(),
+            config: SubnetConfig::Fevm(EVMSubnet {
+                provider_http: url,
+                provider_timeout: None,
+                auth_token: None,
+                provider_ws: None,
+                proxy: None,
+                block_time: None,
+                provider_max_concurrency: None,
+                registry_addr: self.registry,
+                gateway_addr: self.gateway,
+            }),
+        }
+    }
+
+    /// A provider config with only the parent chain at `url`.
+    pub fn config(&self, url: Url) -> Config {
+        let mut config = Config::new();
+        config.add_subnet(self.subnet(url));
+        config
+    }
+
+    /// Checks that the contracts are deployed on the chain of `provider` with the `params`: the
+    /// diamonds have all their facets, the gateway has the parameters and the registry points to
+    /// the gateway.
+    pub async fn verify(&self, provider: &Provider<Http>, params: &DeployParams) -> Result<()> {
+        let chain_id = provider.get_chainid().await?.as_u64();
+        if chain_id != self.chain_id {
+            return Err(anyhow!(
+                "deployed on chain {} but connected to chain {chain_id}",
+                self.chain_id
+            ));
+        }
+
+        let client = Arc::new(provider.clone());
+        let gateway = payload_to_evm_address(self.gateway.payload())?;
+        let registry = payload_to_evm_address(self.registry.payload())?;
+        for (name, address, facets) in [
+            (GATEWAY, gateway, GATEWAY_FACETS),
+            (REGISTRY, registry, REGISTRY_FACETS),
+        ] {
+            if provider.get_code(address, None).await?.is_empty() {
+                return Err(anyhow!("{name} has no code at {address:?}"));
+            }
+            let loupe = diamond_loupe_facet::DiamondLoupeFacet::new(address, client.clone());
+            let cut = loupe.facet_addresses().call().await?.len();
+            if cut != facets.len() {
+                return Err(anyhow!(
+                    "{name} has {cut} facets instead of {}",
+                    facets.len()
+                ));
+            }
+        }
+
+        let getter = gateway_getter_facet::GatewayGetterFacet::new(gateway, client.clone());
+        let period = getter.bottom_up_check_period().call().await?;
+        if period != U256::from(params.bottom_up_check_period) {
+            return Err(anyhow!(
+                "the gateway has a checkpoint period of {period} instead of {}",
+                params.bottom_up_check_period
+            ));
+        }
+        let majority = getter.majority_percentage().call().await?;
+        if majority != params.majority_percentage as u64 {
+            return Err(anyhow!(
+                "the gateway has a majority of {majority}% instead of {}%",
+                params.majority_percentage
+            ));
+        }
+
+        let registry_getter = subnet_getter_facet::SubnetGetterFacet::new(registry, client);
+        let registry_gateway = registry_getter.get_gateway().call().await?;
+        if registry_gateway != gateway {
+            return Err(anyhow!(
+                "the registry points to gateway {registry_gateway:?} instead of {gateway:?}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Deploys the IPC contracts with the `params` to the chain at `url`, from the account of
+/// `wallet`, and verifies the deployment.
+pub async fn deploy(url: &Url, wallet: LocalWallet, params: &DeployParams) -> Result<Deployment> {
+    let provider = Provider::<Http>::try_from(url.as_str())?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = wallet.with_chain_id(chain_id);
+    let deployer = ethers_address_to_fil_address(&wallet.address())?;
+    let client = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+
+    let (gateway, registry) = Deployer::new(client, &params.contracts_dir)
+        .deploy_ipc(params)
+        .await
+        .context("failed to deploy the ipc contracts")?;
+    log::info!("deployed gateway {gateway:?} and registry {registry:?} on chain {chain_id}");
+
+    let deployment = Deployment {
+        chain_id,
+        gateway: ethers_address_to_fil_address(&gateway)?,
+        registry: ethers_address_to_fil_address(&registry)?,
+        deployer,
+    };
+    deployment
+        .verify(&provider, params)
+        .await
+        .context("failed to verify the deployment")?;
+    Ok(deployment)
+}
+
+/// Deploys contracts from their build artifacts, linking them with the libraries they use.
+struct Deployer {
+    client: Arc<DeployerMiddleware>,
+    hardhat: Hardhat,
+    contracts_dir: PathBuf,
+    /// The deployed libraries and facets, by fully qualified name.
+    deployed: HashMap<String, H160>,
+}
+
+impl Deployer {
+    fn new(client: Arc<DeployerMiddleware>, contracts_dir: &Path) -> Self {
+        Self {
+            client,
+            hardhat: Hardhat::new(contracts_dir.to_path_buf()),
+            contracts_dir: contracts_dir.to_path_buf(),
+            deployed: HashMap::new(),
+        }
+    }
+
+    /// Deploys the gateway and the registry, returning their addresses.
+    async fn deploy_ipc(mut self, params: &DeployParams) -> Result<(H160, H160)> {
+        let roots = [GATEWAY, REGISTRY]
+            .iter()
+            .chain(GATEWAY_FACETS)
+            .chain(REGISTRY_FACETS)
+            .chain(SUBNET_ACTOR_FACETS)
+            .map(|name| (source(name), *name))
+            .collect::<Vec<_>>();
+
+        // the libraries and the facets have no constructor, the diamonds are deployed last
+        for (src, name) in self.hardhat.dependencies(&roots)? {
+            if name == GATEWAY || name == REGISTRY {
+                continue;
+            }
+            let fqn = self.hardhat.fqn(&src, &name);
+            let bytecode = self.hardhat.bytecode(&src, &name, &self.deployed)?;
+            let address = self.deploy(bytecode, vec![]).await?;
+            log::debug!("deployed {fqn} at {address:?}");
+            self.deployed.insert(fqn, address);
+        }
+
+        let (root, route) = (self.client.signer().chain_id(), Token::Array(vec![]));
+        let gateway_params = Token::Tuple(vec![
+            Token::Uint(U256::from(params.bottom_up_check_period)),
+            Token::Uint(U256::from(params.active_validators_limit)),
+            Token::Uint(U256::from(params.majority_percentage)),
+            Token::Tuple(vec![Token::Uint(U256::from(root)), route]),
+            // the root network has no validators
+            Token::Array(vec![]),
+            Token::FixedBytes(params.commit_sha.to_vec()),
+        ]);
+        let gateway = self
+            .deploy_diamond(GATEWAY, GATEWAY_FACETS, gateway_params)
+            .await?;
+
+        let subnet_facets = self.facet_cuts(SUBNET_ACTOR_FACETS)?;
+        let mut registry_params = vec![Token::Address(gateway)];
+        registry_params.extend(
+            subnet_facets
+                .iter()
+                .map(|cut| Token::Address(cut.facet_address)),
+        );
+        registry_params.extend(subnet_facets.into_iter().map(|cut| {
+            Token::Array(
+                cut.function_selectors
+                    .into_iter()
+                    .map(|s| Token::FixedBytes(s.to_vec()))
+                    .collect(),
+            )
+        }));
+        let registry = self
+            .deploy_diamond(REGISTRY, REGISTRY_FACETS, Token::Tuple(registry_params))
+            .await?;
+
+        Ok((gateway, registry))
+    }
+
+    /// Deploys a diamond with the cuts of its `facets` and its constructor `params`.
+    async fn deploy_diamond(&self, name: &str, facets: &[&str], params: Token) -> Result<H160> {
+        let cuts = self
+            .facet_cuts(facets)?
+            .into_iter()
+            .map(Tokenizable::into_token)
+            .collect();
+        let bytecode = self.hardhat.bytecode(source(name), name, &self.deployed)?;
+        let address = self
+            .deploy(bytecode, vec![Token::Array(cuts), params])
+            .await
+            .with_context(|| format!("failed to deploy {name}"))?;
+        Ok(address)
+    }
+
+    /// The cuts adding all the functions of the deployed `facets`.
+    fn facet_cuts(&self, facets: &[&str]) -> Result<Vec<FacetCut>> {
+        facets
+            .iter()
+            .map(|name| {
+                let fqn = self.hardhat.fqn(Path::new(&source(name)), name);
+                let facet_address = *self
+                    .deployed
+                    .get(&fqn)
+                    .ok_or_else(|| anyhow!("facet {name} has not been deployed"))?;
+                Ok(FacetCut {
+                    facet_address,
+                    action: 0, // Add
+                    function_selectors: self.selectors(name)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The selectors of the functions of a contract, from its build artifact.
+    fn selectors(&self, name: &str) -> Result<Vec<[u8; 4]>> {
+        #[derive(serde::Deserialize)]
+        struct Artifact {
+            abi: Abi,
+        }
+
+        let path = self
+            .contracts_dir
+            .join(source(name))
+            .join(format!("{name}.json"));
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let artifact: Artifact = serde_json::from_str(&json)?;
+
+        Ok(artifact
+            .abi
+            .functions()
+            .filter(|f| f.signature() != "init(bytes)")
+            .map(|f| f.short_signature())
+            .collect())
+    }
+
+    async fn deploy(&self, mut bytecode: Vec<u8>, constructor: Vec<Token>) -> Result<H160> {
+        bytecode.extend(ethers::abi::encode(&constructor));
+        let tx = Eip1559TransactionRequest::new().data(bytecode);
+        let receipt = self
+            .client
+            .send_transaction(tx, None)
+            .await?
+            .await?
+            .ok_or_else(|| anyhow!("no receipt for the deployment"))?;
+        if receipt.status != Some(U64::from(1)) {
+            return Err(anyhow!(
+                "deployment {:?} reverted",
+                receipt.transaction_hash
+            ));
+        }
+        receipt
+            .contract_address
+            .ok_or_else(|| anyhow!("deployment receipt has no contract address"))
+    }
+}
+
+fn source(name: &str) -> String {
+    format!("{name}.sol")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deployment;
+    use crate::config::subnet::SubnetConfig;
+    use fvm_shared::address::Address;
+    use url::Url;
+
+    #[test]
+    fn test_deployment_config() {
+        let deployment = Deployment {
+            chain_id: 31337,
+            gateway: Address::new_id(10),
+            registry: Address::new_id(11),
+            deployer: Address::new_id(12),
+        };
+        let url = Url::parse("http://localhost:8545").unwrap();
+
+        let config = deployment.config(url.clone());
+        let subnet = config.subnet(&deployment.root()).unwrap();
+        let SubnetConfig::Fevm(evm) = &subnet.config;
+        assert_eq!(evm.provider_http, url);
+        assert_eq!(evm.gateway_addr, deployment.gateway);
+        assert_eq!(evm.registry_addr, deployment.registry);
+    }
+}
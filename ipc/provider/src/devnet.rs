@@ -0,0 +1,184 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Local development networks on an anvil node.
+//!
+//! [`Devnet::deploy`] deploys the gateway and the subnet registry to a local anvil node, see
+//! [`crate::deploy`]. The resulting root network comes with a ready-made provider config, in
+//! which child subnets can be created and accounts funded, e.g. to run integration tests
+//! against this crate.
+
+use crate::config::Config;
+use crate::deploy::{deploy, DeployParams};
+use crate::manager::{EthSubnetManager, SubnetTx};
+use crate::IpcProvider;
+use anyhow::{anyhow, Context, Result};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::LocalWallet;
+use ethers::types::U256;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::eth_to_fil_amount;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::subnet::{ConsensusType, ConstructParams, PermissionMode, SupplyKind, SupplySource};
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{EthKeyAddress, EvmKeyInfo, EvmKeyStore, PersistentKeyStore};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use url::Url;
+
+/// The default endpoint of anvil.
+pub const ANVIL_URL: &str = "http://localhost:8545";
+/// The private key of the first dev account of anvil, funded at its start.
+pub const ANVIL_DEV_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// The parameters of the devnet.
+#[derive(Debug, Clone)]
+pub struct DevnetParams {
+    /// The endpoint of the anvil node.
+    pub url: Url,
+    /// The directory of the build artifacts of the contracts.
+    pub contracts_dir: PathBuf,
+    /// The hex encoded key of the account deploying the contracts, and sending the
+    /// transactions of the devnet.
+    pub deployer_key: String,
+    pub bottom_up_check_period: ChainEpoch,
+    pub active_validators_limit: u16,
+    pub majority_percentage: u8,
+}
+
+impl DevnetParams {
+    /// The parameters of the deployment scripts of the contracts, on the default anvil node.
+    pub fn new(contracts_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            url: Url::parse(ANVIL_URL).expect("valid anvil url"),
+            contracts_dir: contracts_dir.into(),
+            deployer_key: ANVIL_DEV_KEY.to_string(),
+            bottom_up_check_period: 10,
+            active_validators_limit: 100,
+            majority_percentage: 66,
+        }
+    }
+}
+
+/// A root network with the IPC contracts deployed.
+pub struct Devnet {
+    pub root: SubnetID,
+    pub gateway: Address,
+    pub registry: Address,
+    /// The account deploying the contracts, the default key of the keystore.
+    pub deployer: Address,
+    /// The provider config of the root network.
+    pub config: Config,
+    params: DevnetParams,
+    keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+    provider: Provider<Http>,
+}
+
+impl Devnet {
+    /// Deploys the IPC contracts to the anvil node of the `params`.
+    pub async fn deploy(params: DevnetParams) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(params.url.as_str())?;
+
+        let key = hex::decode(params.deployer_key.trim_start_matches("0x"))
+            .context("the deployer key is not hex encoded")?;
+        let deploy_params = DeployParams {
+            contracts_dir: params.contracts_dir.clone(),
+            bottom_up_check_period: params.bottom_up_check_period,
+            active_validators_limit: params.active_validators_limit,
+            majority_percentage: params.majority_percentage,
+            commit_sha: [0; 32],
+        };
+        let deployment =
+            deploy(&params.url, LocalWallet::from_bytes(&key)?, &deploy_params).await?;
+
+        let mut keystore = PersistentKeyStore::ephemeral();
+        let addr = keystore.put(EvmKeyInfo::new(key))?;
+        keystore.set_default(&addr)?;
+
+        Ok(Self {
+            root: deployment.root(),
+            gateway: deployment.gateway,
+            registry: deployment.registry,
+            deployer: deployment.deployer,
+            config: deployment.config(params.url.clone()),
+            params,
+            keystore: Arc::new(RwLock::new(keystore)),
+            provider,
+        })
+    }
+
+    /// A provider of the root network, sending from the deployer.
+    pub fn provider(&self) -> IpcProvider {
+        IpcProvider {
+            sender: Some(self.deployer),
+            config: Arc::new(self.config.clone()),
+            fvm_wallet: None,
+            evm_keystore: Some(self.keystore.clone()),
+            address_book: None,
+            subnet_params: Default::default(),
+            confirmation: None,
+            rpc_middlewares: vec![],
+            head_trackers: Default::default(),
+            keystores: Default::default(),
+            journal: None,
+        }
+    }
+
+    /// The keystore holding the key of the deployer.
+    pub fn keystore(&self) -> Arc<RwLock<PersistentKeyStore<EthKeyAddress>>> {
+        self.keystore.clone()
+    }
+
+    /// Creates a child subnet of the root network with native supply, whose validators join
+    /// with their collateral.
+    pub async fn create_subnet(
+        &self,
+        min_validators: u64,
+        min_validator_stake: TokenAmount,
+    ) -> Result<SubnetID> {
+        let subnet = self
+            .config
+            .subnet(&self.root)
+            .ok_or_else(|| anyhow!("devnet has no root subnet"))?;
+        let manager =
+            EthSubnetManager::from_subnet_with_wallet_store(&subnet, Some(self.keystore()))?;
+
+        let params = ConstructParams {
+            parent: self.root.clone(),
+            ipc_gateway_addr: self.gateway,
+            consensus: ConsensusType::Fendermint,
+            min_validator_stake,
+            min_validators,
+            bottomup_check_period: self.params.bottom_up_check_period,
+            active_validators_limit: self.params.active_validators_limit,
+            min_cross_msg_fee: TokenAmount::from_atto(0),
+            permission_mode: PermissionMode::Collateral,
+            supply_source: SupplySource {
+                kind: SupplyKind::Native,
+                token_address: None,
+            },
+        };
+        let address = manager.create_subnet(self.deployer, params).await?;
+
+        Ok(SubnetID::new_from_parent(&self.root, address))
+    }
+
+    /// Sets the balance of `address` in the root network.
+    pub async fn fund(&self, address: &Address, amount: TokenAmount) -> Result<()> {
+        let address = payload_to_evm_address(address.payload())?;
+        let amount = U256::from_dec_str(&amount.atto().to_string())?;
+        self.provider
+            .request::<_, ()>("anvil_setBalance", (address, amount))
+            .await
+            .context("failed to set the balance, is the node anvil?")?;
+        Ok(())
+    }
+
+    /// The balance of `address` in the root network.
+    pub async fn balance(&self, address: &Address) -> Result<TokenAmount> {
+        let address = payload_to_evm_address(address.payload())?;
+        let balance = self.provider.get_balance(address, None).await?;
+        eth_to_fil_amount(&balance)
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Upgrades of the facets of the diamond contracts, i.e. the gateway, the registry and the
+//! subnet actors.
+//!
+//! An upgrade is staged with [`crate::IpcProvider::stage_facet_upgrade`], which diffs the
+//! selectors installed in the diamond against the target facets into an [`UpgradePlan`] to
+//! review, then executed with [`crate::IpcProvider::execute_facet_upgrade`] from the owner of the
+//! diamond, as long as the installed facets did not change in between.
+
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// A facet of a diamond and the selectors of the functions it serves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Facet {
+    pub address: Address,
+    pub selectors: Vec<[u8; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FacetCutAction {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl FacetCutAction {
+    /// The value of the action in the `FacetCutAction` enum of the contracts.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            FacetCutAction::Add => 0,
+            FacetCutAction::Replace => 1,
+            FacetCutAction::Remove => 2,
+        }
+    }
+}
+
+/// A change of the selectors of a diamond, as passed to `diamondCut`. The facet of the
+/// removals is ignored by the contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCut {
+    pub facet: Address,
+    pub action: FacetCutAction,
+    pub selectors: Vec<[u8; 4]>,
+}
+
+/// The change of the facet serving a selector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorChange {
+    pub selector: [u8; 4],
+    /// The facet serving the selector before the upgrade, none if it is added.
+    pub from: Option<Address>,
+    /// The facet serving the selector after the upgrade, none if it is removed.
+    pub to: Option<Address>,
+}
+
+impl Display for SelectorChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let selector = hex::encode(self.selector);
+        match (&self.from, &self.to) {
+            (None, Some(to)) => write!(f, "+ 0x{selector} {to}"),
+            (Some(from), Some(to)) => write!(f, "~ 0x{selector} {from} -> {to}"),
+            (Some(from), None) => write!(f, "- 0x{selector} {from}"),
+            (None, None) => write!(f, "  0x{selector}"),
+        }
+    }
+}
+
+/// The cuts turning the facets installed in a diamond into the target ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradePlan {
+    /// The facets installed when the upgrade was staged.
+    pub installed: Vec<Facet>,
+    pub cuts: Vec<FacetCut>,
+    pub changes: Vec<SelectorChange>,
+}
+
+impl UpgradePlan {
+    /// Plans the serving of the selectors of the `target` facets by them. The installed
+    /// selectors missing from the targets are removed if `remove_missing`, and kept otherwise.
+    pub fn new(installed: Vec<Facet>, target: &[Facet], remove_missing: bool) -> Self {
+        let current = selector_facets(&installed);
+        let wanted = selector_facets(target);
+
+        let mut changes = vec![];
+        let mut cuts = BTreeMap::<(u8, Vec<u8>), FacetCut>::new();
+        let mut cut = |facet: Address, action: FacetCutAction, selector: [u8; 4]| {
+            cuts.entry((action.as_u8(), facet.to_bytes()))
+                .or_insert_with(|| FacetCut {
+                    facet,
+                    action,
+                    selectors: vec![],
+                })
+                .selectors
+                .push(selector);
+        };
+
+        for (selector, to) in &wanted {
+            match current.get(selector) {
+                Some(from) if from == to => continue,
+                Some(_) => cut(*to, FacetCutAction::Replace, *selector),
+                None => cut(*to, FacetCutAction::Add, *selector),
+            }
+            changes.push(SelectorChange {
+                selector: *selector,
+                from: current.get(selector).copied(),
+                to: Some(*to),
+            });
+        }
+        if remove_missing {
+            for (selector, from) in &current {
+                if !wanted.contains_key(selector) {
+                    cut(Address::new_id(0), FacetCutAction::Remove, *selector);
+                    changes.push(SelectorChange {
+                        selector: *selector,
+                        from: Some(*from),
+                        to: None,
+                    });
+                }
+            }
+        }
+
+        Self {
+            installed,
+            cuts: cuts.into_values().collect(),
+            changes,
+        }
+    }
+
+    /// Whether the diamond already serves the selectors with the target facets.
+    pub fn is_empty(&self) -> bool {
+        self.cuts.is_empty()
+    }
+}
+
+impl Display for UpgradePlan {
+    /// The diff of the selectors, one change per line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "no selector changes");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The facet of every selector, in the order of the selectors.
+fn selector_facets(facets: &[Facet]) -> BTreeMap<[u8; 4], Address> {
+    facets
+        .iter()
+        .flat_map(|f| f.selectors.iter().map(|s| (*s, f.address)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Facet, FacetCutAction, UpgradePlan};
+    use fvm_shared::address::Address;
+
+    #[test]
+    fn test_upgrade_plan() {
+        let (old, new) = (Address::new_id(1), Address::new_id(2));
+        let installed = vec![Facet {
+            address: old,
+            selectors: vec![[0, 0, 0, 1], [0, 0, 0, 2], [0, 0, 0, 3]],
+        }];
+        let target = vec![
+            Facet {
+                address: old,
+                selectors: vec![[0, 0, 0, 1]],
+            },
+            Facet {
+                address: new,
+                selectors: vec![[0, 0, 0, 2], [0, 0, 0, 4]],
+            },
+        ];
+
+        let plan = UpgradePlan::new(installed.clone(), &target, false);
+        let cuts = plan
+            .cuts
+            .iter()
+            .map(|c| (c.action, c.facet, c.selectors.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cuts,
+            vec![
+                (FacetCutAction::Add, new, vec![[0, 0, 0, 4]]),
+                (FacetCutAction::Replace, new, vec![[0, 0, 0, 2]]),
+            ]
+        );
+        assert_eq!(
+            plan.to_string(),
+            format!("~ 0x00000002 {old} -> {new}\n+ 0x00000004 {new}")
+        );
+
+        let plan = UpgradePlan::new(installed.clone(), &target, true);
+        assert_eq!(plan.cuts.len(), 3);
+        assert_eq!(plan.cuts[2].action, FacetCutAction::Remove);
+        assert_eq!(plan.cuts[2].selectors, vec![[0, 0, 0, 3]]);
+
+        assert!(UpgradePlan::new(installed.clone(), &installed, true).is_empty());
+    }
+}
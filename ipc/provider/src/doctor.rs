@@ -0,0 +1,489 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The checks of the setup of a relayer before it is started, each failure with a suggestion
+//! to fix it.
+//!
+//! The checks run in order, from the config to the submitter: the ones depending on a failed
+//! check are skipped rather than failing with a misleading cause, e.g. the gateway of a subnet
+//! whose node cannot be reached.
+
+use crate::config::{Config, Subnet};
+use crate::keystores::{NamedKeystore, RELAYER_ROLE};
+use crate::manager::{
+    BottomUpCheckpointRelayer, EthSubnetManager, SubnetQuery, TopDownFinalityQuery,
+};
+use crate::new_evm_keystore_from_config;
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::evm::payload_to_evm_address;
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::{EthKeyAddress, EvmKeyStore};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, RwLock};
+
+/// The default balance of the submitter below which it is reported as running low.
+pub fn default_min_balance() -> TokenAmount {
+    TokenAmount::from_nano(100_000_000)
+}
+
+/// The relayer the checks are run for.
+#[derive(Debug, Clone)]
+pub struct DoctorOptions {
+    /// The submitter, by default the signer of the keystore.
+    pub submitter: Option<Address>,
+    /// The keystore of the config to sign with, by default the one of the relayer role or the
+    /// keystore of `keystore_path`.
+    pub keystore: Option<String>,
+    pub min_balance: TokenAmount,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            submitter: None,
+            keystore: None,
+            min_balance: default_min_balance(),
+        }
+    }
+}
+
+impl DoctorOptions {
+    pub fn with_submitter(mut self, submitter: Address) -> Self {
+        self.submitter = Some(submitter);
+        self
+    }
+
+    pub fn with_keystore(mut self, keystore: impl Into<String>) -> Self {
+        self.keystore = Some(keystore.into());
+        self
+    }
+
+    pub fn with_min_balance(mut self, min_balance: TokenAmount) -> Self {
+        self.min_balance = min_balance;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    /// Not run, a check it depends on failed.
+    Skipped,
+}
+
+/// The outcome of a check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    /// What was checked, e.g. a subnet or the config file.
+    pub target: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix the failure or the warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Display for DoctorCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = match self.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Failed => "failed",
+            CheckStatus::Skipped => "skipped",
+        };
+        write!(
+            f,
+            "[{status}] {} ({}): {}",
+            self.name, self.target, self.detail
+        )?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n    fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcomes of the checks, in the order they ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Whether none of the checks failed, the warnings aside.
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &DoctorCheck> {
+        self.checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Failed)
+    }
+
+    fn push(&mut self, name: &str, target: impl ToString, status: CheckStatus, detail: String) {
+        self.checks.push(DoctorCheck {
+            name: name.to_string(),
+            target: target.to_string(),
+            status,
+            detail,
+            fix: None,
+        });
+    }
+
+    /// Records the outcome of a check, with `fix` if it failed. Returns the value checked.
+    fn record<T>(
+        &mut self,
+        name: &str,
+        target: impl ToString,
+        outcome: Result<(T, String)>,
+        fix: impl FnOnce() -> String,
+    ) -> Option<T> {
+        match outcome {
+            Ok((value, detail)) => {
+                self.push(name, target, CheckStatus::Ok, detail);
+                Some(value)
+            }
+            Err(e) => {
+                self.push(name, target, CheckStatus::Failed, format!("{e:#}"));
+                self.with_fix(fix());
+                None
+            }
+        }
+    }
+
+    fn skip(&mut self, name: &str, target: impl ToString, cause: &str) {
+        self.push(
+            name,
+            target,
+            CheckStatus::Skipped,
+            format!("skipped, {cause}"),
+        );
+    }
+
+    fn with_fix(&mut self, fix: String) {
+        if let Some(check) = self.checks.last_mut() {
+            check.fix = Some(fix);
+        }
+    }
+}
+
+impl Display for DoctorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{check}")?;
+        }
+        let failures = self.failures().count();
+        match failures {
+            0 => write!(f, "all checks passed"),
+            n => write!(f, "{n} check(s) failed"),
+        }
+    }
+}
+
+/// Runs the checks of the setup of the relayer of `subnet` configured in `config_path`.
+pub async fn doctor(config_path: &str, subnet: &SubnetID, options: &DoctorOptions) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    let config = report.record(
+        "config",
+        config_path,
+        load_config(config_path, subnet),
+        || {
+            format!(
+                "create the config with `ipc-cli config init` and add the entries of {subnet} \
+                 and of its parent to its `subnets`"
+            )
+        },
+    );
+    let Some((config, child, parent)) = config else {
+        return report;
+    };
+
+    let parent_manager = check_subnet(&mut report, &parent).await;
+    check_subnet(&mut report, &child).await;
+
+    match &parent_manager {
+        Some(manager) => {
+            report.record(
+                "checkpoint period",
+                subnet,
+                checkpoint_period(manager, subnet).await,
+                || {
+                    format!(
+                        "check that {subnet} is registered in the gateway {} of its parent",
+                        parent.gateway_addr()
+                    )
+                },
+            );
+        }
+        None => report.skip("checkpoint period", subnet, "the parent cannot be reached"),
+    }
+
+    let submitter = report.record(
+        "keystore",
+        options.keystore.as_deref().unwrap_or("default"),
+        open_keystore(config, options).await,
+        || {
+            "import the key of the submitter with `ipc-cli wallet import`, or set the keystore \
+             of the relayer role in the config"
+                .to_string()
+        },
+    );
+
+    match (&parent_manager, submitter) {
+        (Some(manager), Some(submitter)) => {
+            check_balance(&mut report, manager, &parent.id, &submitter, options).await;
+            report.record(
+                "relayer allowlist",
+                subnet,
+                relayer_allowed(manager, subnet, &submitter).await,
+                || {
+                    format!(
+                        "ask the owner of {subnet} to allow {submitter} with `ipc-cli checkpoint \
+                         allow-relayer --subnet {subnet} --relayer {submitter} --from <owner>`"
+                    )
+                },
+            );
+        }
+        (None, _) => {
+            for name in ["submitter balance", "relayer allowlist"] {
+                report.skip(name, &parent.id, "the parent cannot be reached");
+            }
+        }
+        (_, None) => {
+            for name in ["submitter balance", "relayer allowlist"] {
+                report.skip(name, &parent.id, "no submitter");
+            }
+        }
+    }
+
+    report
+}
+
+/// Reads the config, with the entries of the subnet and of its parent.
+fn load_config(config_path: &str, subnet: &SubnetID) -> Result<((Config, Subnet, Subnet), String)> {
+    let config = Config::from_file(config_path)?;
+    let parent = subnet
+        .parent()
+        .ok_or_else(|| anyhow!("the root subnet {subnet} has no parent to relay to"))?;
+    let child = config
+        .subnet(subnet)
+        .ok_or_else(|| anyhow!("subnet {subnet} is not in the config"))?;
+    let parent = config
+        .subnet(&parent)
+        .ok_or_else(|| anyhow!("parent subnet {parent} is not in the config"))?;
+    let detail = format!("{} subnets configured", config.subnets.len());
+    Ok(((config, child, parent), detail))
+}
+
+/// Checks the node of `subnet`, its chain id and its gateway, returning a manager if the node
+/// can be reached.
+async fn check_subnet(report: &mut DoctorReport, subnet: &Subnet) -> Option<EthSubnetManager> {
+    let id = &subnet.id;
+    let rpc = subnet.rpc_http().to_string();
+
+    let manager = report.record("rpc", id, connect(subnet).await, || {
+        format!("check that a node of {id} listens at {rpc}, the `provider_http` of {id}")
+    })?;
+
+    report.record("chain id", id, chain_id(&manager, id).await, || {
+        format!("check that `provider_http` of {id} points at a node of {id}, not of another chain")
+    });
+
+    let gateway = subnet.gateway_addr();
+    report.record("gateway", id, diamond(&manager, &gateway).await, || {
+        format!("check that `gateway_addr` of {id} is the gateway deployed in {id}")
+    });
+
+    Some(manager)
+}
+
+async fn connect(subnet: &Subnet) -> Result<(EthSubnetManager, String)> {
+    let manager = EthSubnetManager::read_only(subnet)?;
+    let height = manager.chain_head_height().await?;
+    Ok((manager, format!("reachable, at height {height}")))
+}
+
+async fn chain_id(manager: &EthSubnetManager, subnet: &SubnetID) -> Result<((), String)> {
+    let actual = manager.get_chain_id().await?;
+    let expected = subnet.chain_id().to_string();
+    if actual != expected {
+        return Err(anyhow!("chain id {actual}, expected {expected}"));
+    }
+    Ok(((), format!("chain id {actual}")))
+}
+
+async fn diamond(manager: &EthSubnetManager, address: &Address) -> Result<((), String)> {
+    let code = manager.diamond_code(address).await?;
+    Ok(((), format!("{} facets deployed", code.facets.len())))
+}
+
+async fn checkpoint_period(manager: &EthSubnetManager, subnet: &SubnetID) -> Result<((), String)> {
+    let period = manager.checkpoint_period(subnet).await?;
+    if period <= 0 {
+        return Err(anyhow!("invalid bottom up checkpoint period: {period}"));
+    }
+    Ok(((), format!("checkpoint period {period}")))
+}
+
+/// Opens the keystore of the relayer and resolves the submitter, checking its key is there.
+async fn open_keystore(config: Config, options: &DoctorOptions) -> Result<(Address, String)> {
+    let config = Arc::new(config);
+    let named = options
+        .keystore
+        .as_deref()
+        .or_else(|| config.keystore_of_role(RELAYER_ROLE));
+    let (keys, signer) = match named {
+        Some(name) => {
+            let keystore = NamedKeystore::open(config.clone(), name).await?;
+            let signer = keystore
+                .default_address()?
+                .filter(|_| keystore.signer.is_some());
+            (keystore.keys, signer)
+        }
+        None => (
+            Arc::new(RwLock::new(new_evm_keystore_from_config(config)?)),
+            None,
+        ),
+    };
+
+    // a remote signer holds its key itself
+    if let Some(signer) = signer {
+        return match options.submitter {
+            Some(submitter) if submitter != signer => Err(anyhow!(
+                "the remote signer signs for {signer}, not for the submitter {submitter}"
+            )),
+            _ => Ok((signer, format!("remote signer of {signer}"))),
+        };
+    }
+
+    let mut keys = keys.write().unwrap();
+    let submitter = match options.submitter {
+        Some(submitter) => submitter,
+        None => match keys.get_default()? {
+            Some(addr) => Address::try_from(addr)?,
+            None => {
+                return Err(anyhow!(
+                    "no submitter set and no default key in the keystore"
+                ))
+            }
+        },
+    };
+    let key = EthKeyAddress::from(payload_to_evm_address(submitter.payload())?);
+    if keys.get(&key)?.is_none() {
+        return Err(anyhow!(
+            "no key of the submitter {submitter} in the keystore"
+        ));
+    }
+    Ok((
+        submitter,
+        format!("key of the submitter {submitter} readable"),
+    ))
+}
+
+async fn check_balance(
+    report: &mut DoctorReport,
+    manager: &EthSubnetManager,
+    parent: &SubnetID,
+    submitter: &Address,
+    options: &DoctorOptions,
+) {
+    let balance = report.record(
+        "submitter balance",
+        parent,
+        manager
+            .wallet_balance(submitter, None)
+            .await
+            .map(|b| (b.clone(), format!("{submitter} holds {b}"))),
+        || format!("check that the node of {parent} serves the balances"),
+    );
+    let Some(balance) = balance else {
+        return;
+    };
+
+    let fund = || format!("fund {submitter} in {parent} to pay for the checkpoint submissions");
+    if balance.is_zero() {
+        let check = report.checks.last_mut().expect("balance check recorded");
+        check.status = CheckStatus::Failed;
+        check.fix = Some(fund());
+    } else if balance < options.min_balance {
+        let check = report.checks.last_mut().expect("balance check recorded");
+        check.status = CheckStatus::Warning;
+        check.detail = format!("{}, below {}", check.detail, options.min_balance);
+        check.fix = Some(fund());
+    }
+}
+
+async fn relayer_allowed(
+    manager: &EthSubnetManager,
+    subnet: &SubnetID,
+    submitter: &Address,
+) -> Result<((), String)> {
+    let detail = match manager.relayer_allowlist(subnet).await? {
+        None => "the subnet does not restrict its relayers".to_string(),
+        Some(relayers) if relayers.contains(submitter) => {
+            format!(
+                "{submitter} is one of the {} allowed relayers",
+                relayers.len()
+            )
+        }
+        Some(_) => return Err(anyhow!("{submitter} is not an allowed relayer of {subnet}")),
+    };
+    Ok(((), detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{doctor, CheckStatus, DoctorOptions};
+    use fvm_shared::address::Address;
+    use ipc_api::subnet_id::SubnetID;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_missing_parent_fails_config() {
+        let root = SubnetID::new_root(1234);
+        let child = SubnetID::new_from_parent(&root, Address::new_id(100));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+            keystore_path = "~/.ipc"
+
+            [[subnets]]
+            id = "{child}"
+
+            [subnets.config]
+            network_type = "fevm"
+            provider_http = "http://127.0.0.1:1"
+            registry_addr = "0x6be1ccf648c74800380d0520d797a170c808b624"
+            gateway_addr = "0x6be1ccf648c74800380d0520d797a170c808b624"
+            "#
+        )
+        .unwrap();
+
+        let path = file.path().to_string_lossy().to_string();
+        let report = doctor(&path, &child, &DoctorOptions::default()).await;
+        assert!(!report.healthy());
+        assert_eq!(report.checks.len(), 1);
+        let check = &report.checks[0];
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.detail.contains("parent subnet"));
+        assert!(check.fix.is_some());
+
+        let report = doctor(
+            "/nonexistent/config.toml",
+            &child,
+            &DoctorOptions::default(),
+        )
+        .await;
+        assert_eq!(report.failures().count(), 1);
+    }
+}
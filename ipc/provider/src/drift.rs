@@ -0,0 +1,372 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Detection of the clock skew between a child subnet and its parent, and of the drift of the
+//! child height from its expected progression over parent time.
+//!
+//! The monitor samples the heads of both chains. Over the parent time elapsed between the
+//! oldest and the latest samples of its window, the child is expected to produce one block per
+//! block time: a child falling behind or running ahead of that progression beyond a threshold
+//! points to a misconfigured block time or a degraded consensus, and a child not producing any
+//! block to a stalled consensus. The timestamps of the two heads are also expected to be close,
+//! their difference is the clock skew between the chains.
+
+use crate::epoch::BlockTime;
+use crate::manager::SubnetQuery;
+use crate::monitor;
+use anyhow::Result;
+use fvm_shared::clock::ChainEpoch;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The default relative difference between the actual and expected child progression.
+pub const DEFAULT_MAX_HEIGHT_DRIFT: f64 = 0.25;
+/// The default difference between the timestamps of the child and parent heads.
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+/// The default parent time without any child block before the child is reported stalled.
+pub const DEFAULT_STALL_AFTER: Duration = Duration::from_secs(120);
+/// The default parent time the drift is measured over, at least.
+pub const DEFAULT_DRIFT_WINDOW: Duration = Duration::from_secs(300);
+
+/// The thresholds of the alerts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftThresholds {
+    pub max_height_drift: f64,
+    pub max_clock_skew: Duration,
+    pub stall_after: Duration,
+    pub window: Duration,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        Self {
+            max_height_drift: DEFAULT_MAX_HEIGHT_DRIFT,
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+            stall_after: DEFAULT_STALL_AFTER,
+            window: DEFAULT_DRIFT_WINDOW,
+        }
+    }
+}
+
+impl DriftThresholds {
+    pub fn with_max_height_drift(mut self, max_height_drift: f64) -> Self {
+        self.max_height_drift = max_height_drift;
+        self
+    }
+
+    pub fn with_max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    pub fn with_stall_after(mut self, stall_after: Duration) -> Self {
+        self.stall_after = stall_after;
+        self
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+/// The heads of the parent and the child at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriftSample {
+    /// The timestamp of the parent head, in seconds.
+    pub parent_time: u64,
+    pub child_height: ChainEpoch,
+    /// The timestamp of the child head, in seconds.
+    pub child_time: u64,
+}
+
+/// A deviation of the child beyond a threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriftAlert {
+    /// The child head is `skew` seconds ahead of the parent head, behind if negative.
+    ClockSkew { skew: i64 },
+    /// The child has not produced any block after `height` for `stalled_for` of parent time.
+    Stalled {
+        height: ChainEpoch,
+        stalled_for: Duration,
+    },
+    /// The child produced `actual` blocks where `expected` were expected at its block time.
+    HeightDrift {
+        expected: ChainEpoch,
+        actual: ChainEpoch,
+        drift: f64,
+    },
+}
+
+impl DriftAlert {
+    /// The value of the `kind` label of the alert metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DriftAlert::ClockSkew { .. } => "clock_skew",
+            DriftAlert::Stalled { .. } => "stalled",
+            DriftAlert::HeightDrift { .. } => "height_drift",
+        }
+    }
+}
+
+impl Display for DriftAlert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DriftAlert::ClockSkew { skew } => {
+                write!(f, "child clock skewed by {skew}s from the parent")
+            }
+            DriftAlert::Stalled {
+                height,
+                stalled_for,
+            } => write!(
+                f,
+                "child stalled at height {height} for {}s of parent time",
+                stalled_for.as_secs()
+            ),
+            DriftAlert::HeightDrift {
+                expected,
+                actual,
+                drift,
+            } => write!(
+                f,
+                "child produced {actual} blocks instead of {expected}, drifting by {:.0}%",
+                drift * 100.0
+            ),
+        }
+    }
+}
+
+/// The samples of the drift window and the alerts they raise.
+#[derive(Debug)]
+pub struct DriftWindow {
+    block_time: BlockTime,
+    thresholds: DriftThresholds,
+    samples: VecDeque<DriftSample>,
+}
+
+impl DriftWindow {
+    pub fn new(block_time: BlockTime, thresholds: DriftThresholds) -> Self {
+        Self {
+            block_time,
+            thresholds,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records `sample`, returning the alerts it raises. The samples older than the window
+    /// are dropped, except the latest of them that the drift is measured from.
+    pub fn record(&mut self, sample: DriftSample) -> Vec<DriftAlert> {
+        // the parent time going back, e.g. after a reorg, restarts the window
+        if self
+            .samples
+            .back()
+            .map_or(false, |last| sample.parent_time < last.parent_time)
+        {
+            self.samples.clear();
+        }
+        self.samples.push_back(sample);
+        let window = self.thresholds.window.as_secs();
+        while self.samples.len() > 2 && sample.parent_time - self.samples[1].parent_time >= window {
+            self.samples.pop_front();
+        }
+
+        let mut alerts = vec![];
+        let skew = sample.child_time as i64 - sample.parent_time as i64;
+        if skew.unsigned_abs() > self.thresholds.max_clock_skew.as_secs() {
+            alerts.push(DriftAlert::ClockSkew { skew });
+        }
+        if let Some(stalled) = self.stalled() {
+            alerts.push(stalled);
+        } else if let Some(drift) = self.height_drift() {
+            alerts.push(drift);
+        }
+        alerts
+    }
+
+    /// The relative difference between the actual and expected progression of the child over
+    /// the window, once it spans the whole window.
+    pub fn drift(&self) -> Option<(ChainEpoch, ChainEpoch, f64)> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let elapsed = last.parent_time - first.parent_time;
+        if elapsed < self.thresholds.window.as_secs() {
+            return None;
+        }
+        let expected = self.block_time.epochs_in(Duration::from_secs(elapsed));
+        let actual = last.child_height - first.child_height;
+        let drift = (actual - expected) as f64 / expected.max(1) as f64;
+        Some((expected, actual, drift))
+    }
+
+    fn height_drift(&self) -> Option<DriftAlert> {
+        let (expected, actual, drift) = self.drift()?;
+        (drift.abs() > self.thresholds.max_height_drift).then_some(DriftAlert::HeightDrift {
+            expected,
+            actual,
+            drift,
+        })
+    }
+
+    fn stalled(&self) -> Option<DriftAlert> {
+        let last = self.samples.back()?;
+        // the first sample at the height of the last one
+        let since = self
+            .samples
+            .iter()
+            .find(|s| s.child_height == last.child_height)?;
+        let stalled_for = Duration::from_secs(last.parent_time - since.parent_time);
+        (stalled_for >= self.thresholds.stall_after).then_some(DriftAlert::Stalled {
+            height: last.child_height,
+            stalled_for,
+        })
+    }
+}
+
+/// Samples the heads of a child subnet and its parent to detect the clock skew and the drift
+/// of the child.
+pub struct DriftMonitor<P, C> {
+    parent: P,
+    child: C,
+    /// The value of the `subnet` label of the metrics.
+    label: String,
+    window: Mutex<DriftWindow>,
+}
+
+impl<P: SubnetQuery, C: SubnetQuery> DriftMonitor<P, C> {
+    /// Monitors `child`, expected to produce a block every `block_time`, against `parent`.
+    pub fn new(
+        parent: P,
+        child: C,
+        label: impl Into<String>,
+        block_time: BlockTime,
+        thresholds: DriftThresholds,
+    ) -> Self {
+        Self {
+            parent,
+            child,
+            label: label.into(),
+            window: Mutex::new(DriftWindow::new(block_time, thresholds)),
+        }
+    }
+
+    /// Samples the heads of the chains, returning the alerts raised.
+    pub async fn sample(&self) -> Result<Vec<DriftAlert>> {
+        let (_, parent_time) = self.parent.chain_head_timestamp().await?;
+        let (child_height, child_time) = self.child.chain_head_timestamp().await?;
+        let sample = DriftSample {
+            parent_time,
+            child_height,
+            child_time,
+        };
+
+        let mut window = self.window.lock().unwrap();
+        let alerts = window.record(sample);
+
+        monitor::SUBNET_CLOCK_SKEW_SECONDS
+            .with_label_values(&[&self.label])
+            .set(child_time as i64 - parent_time as i64);
+        if let Some((_, _, drift)) = window.drift() {
+            monitor::SUBNET_HEIGHT_DRIFT
+                .with_label_values(&[&self.label])
+                .set(drift);
+        }
+        for alert in &alerts {
+            monitor::SUBNET_DRIFT_ALERTS
+                .with_label_values(&[&self.label, alert.kind()])
+                .inc();
+        }
+        Ok(alerts)
+    }
+
+    /// Samples the heads every `poll_interval` in the foreground, warning about the alerts.
+    pub async fn run(self, poll_interval: Duration) {
+        log::info!("launching the drift monitor of {}", self.label);
+
+        loop {
+            match self.sample().await {
+                Ok(alerts) => {
+                    for alert in alerts {
+                        log::warn!("{}: {alert}", self.label);
+                    }
+                }
+                Err(e) => log::error!("cannot sample the heads of {}: {e}", self.label),
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DriftAlert, DriftSample, DriftThresholds, DriftWindow};
+    use crate::epoch::BlockTime;
+    use std::time::Duration;
+
+    fn window() -> DriftWindow {
+        let thresholds = DriftThresholds::default()
+            .with_window(Duration::from_secs(100))
+            .with_stall_after(Duration::from_secs(30));
+        DriftWindow::new(BlockTime::new(Duration::from_secs(2)).unwrap(), thresholds)
+    }
+
+    fn sample(parent_time: u64, child_height: i64) -> DriftSample {
+        DriftSample {
+            parent_time,
+            child_height,
+            child_time: parent_time,
+        }
+    }
+
+    #[test]
+    fn test_expected_progression() {
+        let mut window = window();
+        for t in (0..=300).step_by(10) {
+            assert!(window.record(sample(t, t as i64 / 2)).is_empty());
+        }
+        let (expected, actual, drift) = window.drift().unwrap();
+        assert_eq!(expected, actual);
+        assert_eq!(drift, 0.0);
+    }
+
+    #[test]
+    fn test_slow_child_drifts() {
+        let mut window = window();
+        let mut alerts = vec![];
+        // a block every 4 seconds instead of 2
+        for t in (0..=100).step_by(10) {
+            alerts = window.record(sample(t, t as i64 / 4));
+        }
+        assert_eq!(
+            alerts,
+            vec![DriftAlert::HeightDrift {
+                expected: 50,
+                actual: 25,
+                drift: -0.5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stalled_child_and_skew() {
+        let mut window = window();
+        for t in (0..=20).step_by(10) {
+            assert!(window.record(sample(t, 5)).is_empty());
+        }
+        let alerts = window.record(DriftSample {
+            parent_time: 30,
+            child_height: 5,
+            child_time: 100,
+        });
+        assert_eq!(
+            alerts,
+            vec![
+                DriftAlert::ClockSkew { skew: 70 },
+                DriftAlert::Stalled {
+                    height: 5,
+                    stalled_for: Duration::from_secs(30)
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Conversions between wall-clock durations and numbers of epochs of a subnet, at its block
+//! time, either configured with `block_time` in the config of the subnet or measured on its
+//! recent blocks, see [`crate::IpcProvider::block_time`].
+
+use crate::manager::CheckpointStatus;
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationSeconds};
+use std::time::Duration;
+
+/// The number of recent blocks the block time of a subnet is measured on by default.
+pub const DEFAULT_BLOCK_TIME_WINDOW: ChainEpoch = 100;
+
+/// The average time between two blocks of a subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlockTime(Duration);
+
+impl BlockTime {
+    pub fn new(block_time: Duration) -> Result<Self> {
+        if block_time.is_zero() {
+            return Err(anyhow!("the block time must not be zero"));
+        }
+        Ok(Self(block_time))
+    }
+
+    /// The average block time between two blocks, each one given as its height and its
+    /// timestamp in seconds.
+    pub fn measure(from: (ChainEpoch, u64), to: (ChainEpoch, u64)) -> Result<Self> {
+        let ((from_height, from_time), (to_height, to_time)) = if from.0 <= to.0 {
+            (from, to)
+        } else {
+            (to, from)
+        };
+        let epochs = u32::try_from(to_height - from_height)?;
+        if epochs == 0 {
+            return Err(anyhow!("cannot measure the block time on a single block"));
+        }
+        let elapsed = to_time
+            .checked_sub(from_time)
+            .ok_or_else(|| anyhow!("the timestamps of the blocks are not increasing"))?;
+        Self::new(Duration::from_secs(elapsed) / epochs)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+
+    /// The number of epochs produced within `duration`, rounded up, e.g. for the number of
+    /// blocks to wait for a delay to elapse.
+    pub fn epochs_in(&self, duration: Duration) -> ChainEpoch {
+        let epochs = duration.as_nanos().div_ceil(self.0.as_nanos());
+        ChainEpoch::try_from(epochs).unwrap_or(ChainEpoch::MAX)
+    }
+
+    /// The time it takes to produce `epochs` epochs, none for negative numbers of epochs.
+    pub fn duration_of(&self, epochs: ChainEpoch) -> Duration {
+        let epochs = u32::try_from(epochs.max(0)).unwrap_or(u32::MAX);
+        self.0.saturating_mul(epochs)
+    }
+
+    /// The estimated time until `target` is reached from `current`, zero if it already is.
+    pub fn until(&self, current: ChainEpoch, target: ChainEpoch) -> Duration {
+        self.duration_of(target - current)
+    }
+}
+
+/// The first height after `height` at which a checkpoint is due with `period`.
+pub fn next_checkpoint_height(height: ChainEpoch, period: ChainEpoch) -> ChainEpoch {
+    if period <= 0 {
+        return height;
+    }
+    (height / period + 1) * period
+}
+
+/// The countdown to the next bottom-up checkpoint of a subnet, see
+/// [`crate::IpcProvider::next_checkpoint_eta`].
+#[serde_as]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointEta {
+    /// The height of the last checkpoint committed in the parent.
+    pub last_committed_height: ChainEpoch,
+    /// The height of the next checkpoint to commit.
+    pub next_height: ChainEpoch,
+    /// The height of the child subnet.
+    pub current_height: ChainEpoch,
+    /// The estimated time until the child reaches the next checkpoint, zero once it did and the
+    /// checkpoint is waiting for its quorum or its submission.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub eta: Duration,
+    /// The share of the period elapsed since the last committed checkpoint, in percents, capped
+    /// at 100.
+    pub progress: f64,
+}
+
+impl CheckpointEta {
+    pub fn new(
+        status: CheckpointStatus,
+        current_height: ChainEpoch,
+        block_time: BlockTime,
+    ) -> Self {
+        let next_height = status.last_committed_height + status.period;
+        let progress = if status.period <= 0 {
+            100.0
+        } else {
+            let elapsed = (current_height - status.last_committed_height).max(0);
+            (elapsed as f64 * 100.0 / status.period as f64).min(100.0)
+        };
+        Self {
+            last_committed_height: status.last_committed_height,
+            next_height,
+            current_height,
+            eta: block_time.until(current_height, next_height),
+            progress,
+        }
+    }
+
+    /// Whether the child reached the next checkpoint, which is not committed yet.
+    pub fn is_due(&self) -> bool {
+        self.current_height >= self.next_height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_checkpoint_height, BlockTime, CheckpointEta};
+    use crate::manager::CheckpointStatus;
+    use std::time::Duration;
+
+    #[test]
+    fn test_block_time_conversions() {
+        let block_time = BlockTime::new(Duration::from_millis(1500)).unwrap();
+        assert_eq!(block_time.epochs_in(Duration::from_secs(3)), 2);
+        assert_eq!(block_time.epochs_in(Duration::from_secs(4)), 3);
+        assert_eq!(block_time.epochs_in(Duration::ZERO), 0);
+        assert_eq!(block_time.duration_of(4), Duration::from_secs(6));
+        assert_eq!(block_time.duration_of(-1), Duration::ZERO);
+        assert_eq!(block_time.until(10, 12), Duration::from_secs(3));
+        assert_eq!(block_time.until(12, 10), Duration::ZERO);
+
+        assert!(BlockTime::new(Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_measure_block_time() {
+        let block_time = BlockTime::measure((100, 1_000), (200, 1_100)).unwrap();
+        assert_eq!(block_time.duration(), Duration::from_secs(1));
+        // the order of the blocks does not matter
+        assert_eq!(
+            BlockTime::measure((200, 1_100), (100, 1_000)).unwrap(),
+            block_time
+        );
+
+        assert!(BlockTime::measure((100, 1_000), (100, 1_000)).is_err());
+        assert!(BlockTime::measure((100, 1_100), (200, 1_000)).is_err());
+    }
+
+    #[test]
+    fn test_next_checkpoint_height() {
+        assert_eq!(next_checkpoint_height(0, 10), 10);
+        assert_eq!(next_checkpoint_height(9, 10), 10);
+        assert_eq!(next_checkpoint_height(10, 10), 20);
+    }
+
+    #[test]
+    fn test_checkpoint_eta() {
+        let block_time = BlockTime::new(Duration::from_secs(2)).unwrap();
+        let status = CheckpointStatus {
+            period: 10,
+            last_committed_height: 20,
+        };
+
+        let eta = CheckpointEta::new(status, 25, block_time);
+        assert_eq!(eta.next_height, 30);
+        assert_eq!(eta.eta, Duration::from_secs(10));
+        assert_eq!(eta.progress, 50.0);
+        assert!(!eta.is_due());
+
+        // the checkpoint is late, e.g. waiting for its quorum
+        let eta = CheckpointEta::new(status, 42, block_time);
+        assert_eq!(eta.eta, Duration::ZERO);
+        assert_eq!(eta.progress, 100.0);
+        assert!(eta.is_due());
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Streams the lifecycle events of a subnet, merged from the logs of its parent and its own.
+//!
+//! The validators joining and leaving and the funds sent to the subnet are logged in the
+//! parent, the funds released from the subnet are logged in the subnet itself. The commitment
+//! of the checkpoints and the killing of the subnet are not logged, they are detected from the
+//! state of the subnet actor in the parent.
+
+use crate::head::ChainHeadTracker;
+use crate::manager::SubnetManager;
+use anyhow::Result;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::address::IPCAddress;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The default interval between two polls of the subnets.
+pub const DEFAULT_EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The maximum number of blocks whose logs are queried at once.
+const MAX_BLOCK_RANGE: ChainEpoch = 1000;
+
+/// The chain an event was observed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSource {
+    Parent,
+    Child,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeDirection {
+    /// From the parent to the subnet.
+    TopDown,
+    /// From the subnet to the parent.
+    BottomUp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubnetEvent {
+    /// A validator joined the active set, or the waiting set if the active one is full.
+    /// A waiting validator promoted to the active set joins it again.
+    ValidatorJoined {
+        validator: Address,
+        power: TokenAmount,
+        active: bool,
+    },
+    /// A validator left the active or the waiting set.
+    ValidatorLeft { validator: Address },
+    /// The checkpoint at `height` of the subnet was committed in the parent.
+    CheckpointCommitted { height: ChainEpoch },
+    /// A cross-net message carrying value was sent between the parent and the subnet.
+    FundsBridged {
+        direction: BridgeDirection,
+        from: IPCAddress,
+        to: IPCAddress,
+        value: TokenAmount,
+        nonce: u64,
+    },
+    /// The subnet was killed, no event follows.
+    SubnetKilled,
+}
+
+impl SubnetEvent {
+    /// The event of `envelope` bridging funds in `direction`, if it carries any value.
+    pub fn bridged(direction: BridgeDirection, envelope: IpcEnvelope) -> Option<Self> {
+        if envelope.value.is_zero() {
+            return None;
+        }
+        Some(SubnetEvent::FundsBridged {
+            direction,
+            from: envelope.from,
+            to: envelope.to,
+            value: envelope.value,
+            nonce: envelope.nonce,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetEventRecord {
+    pub source: EventSource,
+    /// The height of the block of the source chain the event was observed at.
+    pub height: ChainEpoch,
+    pub event: SubnetEvent,
+}
+
+/// Subscribes to the lifecycle events of `subnet`, observed through the managers of its
+/// parent and of the subnet itself, from their current heads on. The subnets are polled
+/// every `poll_interval`, the errors of a poll are yielded and the next poll retries.
+pub fn subscribe(
+    parent: Box<dyn SubnetManager>,
+    child: Box<dyn SubnetManager>,
+    subnet: SubnetID,
+    poll_interval: Duration,
+) -> BoxStream<'static, Result<SubnetEventRecord>> {
+    subscribe_tracked(parent, child, None, subnet, poll_interval)
+}
+
+/// Subscribes to the lifecycle events of `subnet` like [`subscribe`], reading the heads of the
+/// parent and of the subnet from the `heads` trackers if set, instead of querying them on
+/// every poll.
+pub fn subscribe_tracked(
+    parent: Box<dyn SubnetManager>,
+    child: Box<dyn SubnetManager>,
+    heads: Option<(ChainHeadTracker, ChainHeadTracker)>,
+    subnet: SubnetID,
+    poll_interval: Duration,
+) -> BoxStream<'static, Result<SubnetEventRecord>> {
+    let cursor = EventCursor {
+        parent,
+        child,
+        heads,
+        subnet,
+        parent_height: None,
+        child_height: None,
+        last_checkpoint: None,
+        pending: VecDeque::new(),
+        killed: false,
+    };
+    stream::unfold(
+        (cursor, false),
+        move |(mut cursor, mut polled)| async move {
+            loop {
+                if let Some(record) = cursor.pending.pop_front() {
+                    return Some((Ok(record), (cursor, polled)));
+                }
+                if cursor.killed {
+                    return None;
+                }
+                if polled {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                polled = true;
+                if let Err(e) = cursor.poll().await {
+                    return Some((Err(e), (cursor, polled)));
+                }
+            }
+        },
+    )
+    .boxed()
+}
+
+/// Where the subscription is in the parent and in the child.
+struct EventCursor {
+    parent: Box<dyn SubnetManager>,
+    child: Box<dyn SubnetManager>,
+    /// The trackers of the heads of the parent and of the child, if shared.
+    heads: Option<(ChainHeadTracker, ChainHeadTracker)>,
+    subnet: SubnetID,
+    /// The last heights whose logs were queried, none before the first poll.
+    parent_height: Option<ChainEpoch>,
+    child_height: Option<ChainEpoch>,
+    /// The height of the last checkpoint committed in the parent.
+    last_checkpoint: Option<ChainEpoch>,
+    /// The events observed and not yielded yet.
+    pending: VecDeque<SubnetEventRecord>,
+    killed: bool,
+}
+
+impl EventCursor {
+    /// Queries the events of the blocks since the last poll, which only moves the cursors to
+    /// the heads the first time.
+    async fn poll(&mut self) -> Result<()> {
+        let (parent_head, child_head) = match &self.heads {
+            Some((parent, child)) => (parent.latest().await?, child.latest().await?),
+            None => (
+                self.parent.chain_head_height().await?,
+                self.child.chain_head_height().await?,
+            ),
+        };
+
+        if let Some(range) = next_range(self.parent_height, parent_head) {
+            let events = self
+                .parent
+                .subnet_events_in_parent(&self.subnet, *range.start(), *range.end())
+                .await?;
+            self.push(EventSource::Parent, events);
+            self.parent_height = Some(*range.end());
+        } else if self.parent_height.is_none() {
+            self.parent_height = Some(parent_head);
+        }
+
+        if let Some(range) = next_range(self.child_height, child_head) {
+            let events = self
+                .child
+                .subnet_events_in_child(*range.start(), *range.end())
+                .await?;
+            self.push(EventSource::Child, events);
+            self.child_height = Some(*range.end());
+        } else if self.child_height.is_none() {
+            self.child_height = Some(child_head);
+        }
+
+        let status = self.parent.checkpoint_status(&self.subnet).await?;
+        if let Some(prev) = self.last_checkpoint {
+            let committed = committed_between(prev, status.last_committed_height, status.period)
+                .into_iter()
+                .map(|height| (parent_head, SubnetEvent::CheckpointCommitted { height }))
+                .collect();
+            self.push(EventSource::Parent, committed);
+        }
+        self.last_checkpoint = Some(status.last_committed_height);
+
+        if self.parent.subnet_killed(&self.subnet).await? {
+            self.push(
+                EventSource::Parent,
+                vec![(parent_head, SubnetEvent::SubnetKilled)],
+            );
+            self.killed = true;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, source: EventSource, events: Vec<(ChainEpoch, SubnetEvent)>) {
+        self.pending
+            .extend(events.into_iter().map(|(height, event)| SubnetEventRecord {
+                source,
+                height,
+                event,
+            }));
+    }
+}
+
+/// The heights whose logs are queried next, after `last` and up to `head`, or none if there
+/// are no new blocks or if the cursor is not set yet.
+fn next_range(
+    last: Option<ChainEpoch>,
+    head: ChainEpoch,
+) -> Option<std::ops::RangeInclusive<ChainEpoch>> {
+    let last = last?;
+    if head <= last {
+        return None;
+    }
+    Some(last + 1..=min(head, last + MAX_BLOCK_RANGE))
+}
+
+/// The heights of the checkpoints committed after the one at `prev` and up to `last`.
+fn committed_between(prev: ChainEpoch, last: ChainEpoch, period: ChainEpoch) -> Vec<ChainEpoch> {
+    if period <= 0 || last <= prev {
+        return vec![];
+    }
+    let mut heights = vec![];
+    let mut height = prev + period;
+    while height < last {
+        heights.push(height);
+        height += period;
+    }
+    heights.push(last);
+    heights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{committed_between, next_range, BridgeDirection, SubnetEvent, MAX_BLOCK_RANGE};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::cross::IpcEnvelope;
+    use ipc_api::subnet_id::SubnetID;
+
+    #[test]
+    fn test_next_range() {
+        assert_eq!(next_range(None, 100), None);
+        assert_eq!(next_range(Some(100), 100), None);
+        assert_eq!(next_range(Some(100), 90), None);
+        assert_eq!(next_range(Some(100), 110), Some(101..=110));
+        assert_eq!(next_range(Some(0), 5000), Some(1..=MAX_BLOCK_RANGE));
+    }
+
+    #[test]
+    fn test_committed_between() {
+        assert_eq!(committed_between(100, 100, 10), Vec::<i64>::new());
+        assert_eq!(committed_between(100, 110, 10), vec![110]);
+        assert_eq!(committed_between(100, 130, 10), vec![110, 120, 130]);
+        assert_eq!(committed_between(100, 130, 0), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn test_bridged_funds() {
+        let subnet = SubnetID::new(1, vec![Address::new_id(1000)]);
+        let release = |value| {
+            IpcEnvelope::new_release_msg(
+                &subnet,
+                &Address::new_id(100),
+                &Address::new_id(101),
+                TokenAmount::from_atto(value),
+            )
+            .unwrap()
+        };
+
+        assert!(SubnetEvent::bridged(BridgeDirection::BottomUp, release(0)).is_none());
+        assert!(matches!(
+            SubnetEvent::bridged(BridgeDirection::BottomUp, release(5)),
+            Some(SubnetEvent::FundsBridged {
+                direction: BridgeDirection::BottomUp,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The status of the votes of the child validators on the finality of the parent, to debug the
+//! top-down stalls.
+//!
+//! The validators of a child subnet gossip their votes on the latest parent block they see as
+//! final, and a parent finality is proposed once the validators voting for a block, or for one
+//! of its descendants, reach a quorum of the power. Each child node tallies the votes it
+//! received: it serves its tally as an ABCI query at [`FINALITY_VOTES_QUERY_PATH`], which is
+//! read through the CometBFT RPC of the node.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+
+use crate::jsonrpc::JsonRpcClient;
+
+/// The path of the ABCI query of the status of the votes.
+pub const FINALITY_VOTES_QUERY_PATH: &str = "/ipc/parent-finality/votes";
+
+/// The votes on the parent finality tallied by a child node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalityVoteStatus {
+    /// The last parent height finalized in the child ledger.
+    pub last_finalized_height: ChainEpoch,
+    /// The latest parent height the node sees as final, and votes for.
+    pub latest_height: ChainEpoch,
+    /// The power the voters of a block must reach for it to be proposed.
+    pub quorum_threshold: u64,
+    /// The validators and their power, by address.
+    pub validators: Vec<ValidatorPower>,
+    /// The heights after the last finalized one, seen as final by the node or voted on.
+    pub heights: Vec<HeightVotes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorPower {
+    pub validator: String,
+    pub power: u64,
+}
+
+/// The votes at a parent height.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeightVotes {
+    pub height: ChainEpoch,
+    /// The hash of the parent block seen as final by the node, none for a null round or a
+    /// height it did not sync yet.
+    pub block_hash: Option<String>,
+    /// The blocks voted for, more than one when the validators disagree.
+    pub blocks: Vec<BlockVotes>,
+}
+
+/// The votes for a parent block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockVotes {
+    pub block_hash: String,
+    pub voters: Vec<String>,
+    /// The power of the voters.
+    pub weight: u64,
+}
+
+/// The progress of the votes towards a quorum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumProgress {
+    /// The highest height whose block reached a quorum, to be proposed next.
+    pub quorum_height: Option<ChainEpoch>,
+    /// The power of the validators voting for any block seen as final by the node.
+    pub weight: u64,
+    pub threshold: u64,
+    /// The validators which did not vote for any block seen as final by the node.
+    pub missing: Vec<String>,
+}
+
+impl FinalityVoteStatus {
+    pub fn total_power(&self) -> u64 {
+        self.validators.iter().map(|v| v.power).sum()
+    }
+
+    /// The progress towards a quorum, tallied as the node does when proposing: a vote for a
+    /// block counts for its ancestors, so the heights are walked down from the latest one and
+    /// the power of their voters accumulated until it reaches the threshold.
+    pub fn quorum_progress(&self) -> QuorumProgress {
+        let power = |v: &str| {
+            self.validators
+                .iter()
+                .find(|p| p.validator == v)
+                .map_or(0, |p| p.power)
+        };
+
+        let mut voters = HashSet::new();
+        let mut weight = 0;
+        let mut quorum_height = None;
+        let mut heights = self.heights.iter().collect::<Vec<_>>();
+        heights.sort_by_key(|h| std::cmp::Reverse(h.height));
+        for height in heights {
+            if height.height <= self.last_finalized_height {
+                continue;
+            }
+            let Some(hash) = &height.block_hash else {
+                continue;
+            };
+            let Some(block) = height.blocks.iter().find(|b| &b.block_hash == hash) else {
+                continue;
+            };
+            for voter in &block.voters {
+                if voters.insert(voter.as_str()) {
+                    weight += power(voter);
+                }
+            }
+            if quorum_height.is_none() && weight >= self.quorum_threshold {
+                quorum_height = Some(height.height);
+            }
+        }
+
+        let missing = self
+            .validators
+            .iter()
+            .filter(|v| v.power > 0 && !voters.contains(v.validator.as_str()))
+            .map(|v| v.validator.clone())
+            .collect();
+        QuorumProgress {
+            quorum_height,
+            weight,
+            threshold: self.quorum_threshold,
+            missing,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AbciQueryResult {
+    response: AbciQueryResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct AbciQueryResponse {
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    info: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+/// Queries the status of the votes tallied by the child node behind the CometBFT RPC `client`.
+pub async fn query_finality_votes(client: &impl JsonRpcClient) -> Result<FinalityVoteStatus> {
+    let result: AbciQueryResult = client
+        .request(
+            "abci_query",
+            json!({
+                "path": FINALITY_VOTES_QUERY_PATH,
+                "data": "",
+                "height": "0",
+                "prove": false,
+            }),
+        )
+        .await?;
+    let response = result.response;
+    if response.code != 0 {
+        return Err(anyhow!(
+            "cannot query the parent finality votes: {} (code {})",
+            response.info,
+            response.code
+        ));
+    }
+    let value = base64::engine::general_purpose::STANDARD
+        .decode(response.value.unwrap_or_default())
+        .context("invalid parent finality votes encoding")?;
+    serde_json::from_slice(&value).context("invalid parent finality votes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockVotes, FinalityVoteStatus, HeightVotes, ValidatorPower};
+
+    fn validator(name: &str, power: u64) -> ValidatorPower {
+        ValidatorPower {
+            validator: name.to_string(),
+            power,
+        }
+    }
+
+    fn votes(hash: &str, voters: &[&str]) -> BlockVotes {
+        BlockVotes {
+            block_hash: hash.to_string(),
+            voters: voters.iter().map(|v| v.to_string()).collect(),
+            weight: 0,
+        }
+    }
+
+    #[test]
+    fn test_quorum_progress() {
+        let mut status = FinalityVoteStatus {
+            last_finalized_height: 10,
+            latest_height: 12,
+            quorum_threshold: 7,
+            validators: vec![validator("a", 4), validator("b", 3), validator("c", 3)],
+            heights: vec![
+                HeightVotes {
+                    height: 11,
+                    block_hash: Some("0x11".to_string()),
+                    blocks: vec![votes("0x11", &["a"]), votes("0xff", &["c"])],
+                },
+                HeightVotes {
+                    height: 12,
+                    block_hash: Some("0x12".to_string()),
+                    blocks: vec![votes("0x12", &["b"])],
+                },
+            ],
+        };
+
+        // a vote for 12 counts for 11, but the vote for a fork of 11 does not
+        let progress = status.quorum_progress();
+        assert_eq!(progress.quorum_height, Some(11));
+        assert_eq!(progress.weight, 7);
+        assert_eq!(progress.missing, vec!["c".to_string()]);
+
+        status.heights[1].blocks[0].voters.push("a".to_string());
+        assert_eq!(status.quorum_progress().quorum_height, Some(12));
+
+        status.quorum_threshold = 8;
+        let progress = status.quorum_progress();
+        assert_eq!(progress.quorum_height, None);
+        assert_eq!(progress.threshold, 8);
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Signer backed by a `EC_SIGN_SECP256K1_SHA256` key of Google Cloud KMS.
+//!
+//! The key never leaves KMS, the address of the signer is derived from its public key. The
+//! requests are authenticated with the `GOOGLE_OAUTH_ACCESS_TOKEN` env var if set, otherwise
+//! with the tokens of the service account of the instance, from the metadata server.
+
+use crate::manager::evm::recover_signature;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use ethers::types::{Address, Signature, H256, U256};
+use ethers::utils::keccak256;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+const KMS_ENDPOINT: &str = "https://cloudkms.googleapis.com/v1";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const ACCESS_TOKEN_ENV: &str = "GOOGLE_OAUTH_ACCESS_TOKEN";
+/// How long before their expiry the tokens of the metadata server are renewed.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+enum AccessToken {
+    Static(Zeroizing<String>),
+    /// The token of the metadata server and its expiry.
+    Metadata(Arc<Mutex<Option<(Zeroizing<String>, Instant)>>>),
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct PublicKey {
+    pem: String,
+}
+
+#[derive(Deserialize)]
+struct AsymmetricSignature {
+    signature: String,
+}
+
+#[derive(Clone)]
+pub struct GcpKmsSigner {
+    client: reqwest::Client,
+    token: AccessToken,
+    /// The resource name of the key version,
+    /// `projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*`.
+    key_name: String,
+    address: Address,
+    chain_id: u64,
+}
+
+impl std::fmt::Debug for GcpKmsSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GcpKmsSigner")
+            .field("key_name", &self.key_name)
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GcpKmsSigner {
+    /// The signer of the key version `key_name`, whose address is derived from its public key.
+    pub async fn new(key_name: impl Into<String>, chain_id: u64) -> Result<Self> {
+        let token = match std::env::var(ACCESS_TOKEN_ENV) {
+            Ok(token) => AccessToken::Static(Zeroizing::new(token)),
+            Err(_) => AccessToken::Metadata(Arc::new(Mutex::new(None))),
+        };
+        let mut signer = Self {
+            client: reqwest::Client::new(),
+            token,
+            key_name: key_name.into(),
+            address: Address::zero(),
+            chain_id,
+        };
+
+        let url = format!("{KMS_ENDPOINT}/{}/publicKey", signer.key_name);
+        let key: PublicKey = signer.send(signer.client.get(url)).await?;
+        signer.address = address_from_pem(&key.pem)?;
+        log::info!(
+            "gcp kms key {} has address {:?}",
+            signer.key_name,
+            signer.address
+        );
+
+        Ok(signer)
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Signs the `digest`, the `v` of the signature being the recovery id plus 27.
+    pub async fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let url = format!("{KMS_ENDPOINT}/{}:asymmetricSign", self.key_name);
+        // the digest of secp256k1 keys is declared as sha256, but kms signs it as is
+        let body = json!({
+            "digest": {
+                "sha256": base64::engine::general_purpose::STANDARD.encode(digest.as_bytes())
+            }
+        });
+        let signature: AsymmetricSignature = self.send(self.client.post(url).json(&body)).await?;
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(signature.signature)
+            .context("kms signature is not base64 encoded")?;
+
+        let (r, s) = parse_der_signature(&der)?;
+        recover_signature(r, s, digest, self.address)
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let token = self.access_token().await?;
+        let response = request
+            .bearer_auth(token.as_str())
+            .send()
+            .await
+            .context("cannot reach gcp kms")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("gcp kms responded with {status}: {body}"));
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn access_token(&self) -> Result<Zeroizing<String>> {
+        let cached = match &self.token {
+            AccessToken::Static(token) => return Ok(token.clone()),
+            AccessToken::Metadata(cached) => cached,
+        };
+
+        let mut cached = cached.lock().await;
+        if let Some((token, expiry)) = cached.as_ref() {
+            if Instant::now() + TOKEN_EXPIRY_MARGIN < *expiry {
+                return Ok(token.clone());
+            }
+        }
+
+        let token: MetadataToken = self
+            .client
+            .get(METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await
+            .context(
+                "cannot reach the metadata server, set GOOGLE_OAUTH_ACCESS_TOKEN outside of gcp",
+            )?
+            .error_for_status()?
+            .json()
+            .await?;
+        let access_token = Zeroizing::new(token.access_token);
+        *cached = Some((
+            access_token.clone(),
+            Instant::now() + Duration::from_secs(token.expires_in),
+        ));
+        Ok(access_token)
+    }
+}
+
+/// The address of a secp256k1 public key in a PEM encoded SubjectPublicKeyInfo, whose last 65
+/// bytes are the uncompressed point.
+fn address_from_pem(pem: &str) -> Result<Address> {
+    let encoded: String = pem
+        .lines()
+        .filter(|l| !l.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("public key is not pem encoded")?;
+    if der.len() < 65 || der[der.len() - 65] != 0x04 {
+        return Err(anyhow!("public key is not an uncompressed secp256k1 key"));
+    }
+    let hash = keccak256(&der[der.len() - 64..]);
+    Ok(Address::from_slice(&hash[12..]))
+}
+
+/// Parses the DER `SEQUENCE { INTEGER r, INTEGER s }` of an ECDSA signature.
+fn parse_der_signature(der: &[u8]) -> Result<(U256, U256)> {
+    fn integer(der: &[u8]) -> Result<(U256, &[u8])> {
+        match der {
+            [0x02, len, rest @ ..] if (*len as usize) <= rest.len() => {
+                let (value, rest) = rest.split_at(*len as usize);
+                // positive integers with their high bit set are prefixed with a zero
+                let value = value.strip_prefix(&[0]).unwrap_or(value);
+                if value.len() > 32 {
+                    return Err(anyhow!("der integer overflows 256 bits"));
+                }
+                Ok((U256::from_big_endian(value), rest))
+            }
+            _ => Err(anyhow!("invalid der integer")),
+        }
+    }
+
+    let body = match der {
+        [0x30, len, body @ ..] if *len as usize == body.len() => body,
+        _ => return Err(anyhow!("invalid der signature")),
+    };
+    let (r, rest) = integer(body)?;
+    let (s, rest) = integer(rest)?;
+    if !rest.is_empty() {
+        return Err(anyhow!("trailing bytes in der signature"));
+    }
+    Ok((r, s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{address_from_pem, parse_der_signature};
+    use base64::Engine;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::U256;
+
+    /// The SubjectPublicKeyInfo header of the uncompressed secp256k1 keys.
+    const SPKI_PREFIX: &str = "3056301006072a8648ce3d020106052b8104000a034200";
+
+    fn der_integer(value: U256) -> Vec<u8> {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        let mut bytes = bytes.to_vec();
+        while bytes.len() > 1 && bytes[0] == 0 && bytes[1] < 0x80 {
+            bytes.remove(0);
+        }
+        if bytes[0] >= 0x80 {
+            bytes.insert(0, 0);
+        }
+        [vec![0x02, bytes.len() as u8], bytes].concat()
+    }
+
+    #[test]
+    fn test_address_from_pem() {
+        let wallet = LocalWallet::from_bytes(&[1u8; 32]).unwrap();
+        let point = wallet.signer().verifying_key().to_encoded_point(false);
+        let der = [hex::decode(SPKI_PREFIX).unwrap(), point.as_bytes().to_vec()].concat();
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(der)
+        );
+        assert_eq!(address_from_pem(&pem).unwrap(), wallet.address());
+    }
+
+    #[test]
+    fn test_parse_der_signature() {
+        let r = U256::from(u128::MAX) << 128;
+        let s = U256::from(42);
+        let body = [der_integer(r), der_integer(s)].concat();
+        let der = [vec![0x30, body.len() as u8], body].concat();
+        assert_eq!(parse_der_signature(&der).unwrap(), (r, s));
+
+        assert!(parse_der_signature(&der[..der.len() - 1]).is_err());
+        assert!(parse_der_signature(&[0x30, 0]).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The head of the chain of a subnet, tracked once and shared by the components following it.
+//!
+//! Instead of each component querying the height of the chain on every round, a
+//! [`ChainHeadTracker`] refreshes the latest, safe and finalized heights in the background,
+//! on every new head notified by the websocket endpoint if there is one, and at a fixed
+//! interval otherwise or while the subscription is down. The components read the last head
+//! observed, which is only queried on demand when it is stale, e.g. while the subnet is not
+//! reachable.
+
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use url::Url;
+
+/// The default interval between two queries of the head when it is not notified.
+pub const DEFAULT_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long to wait for a new head before querying it anyway, in case the subscription
+/// silently stopped delivering heads.
+const WS_IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The heights of the head of a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// The height of the last block.
+    pub latest: ChainEpoch,
+    /// The height of the last block unlikely to be reorganized.
+    pub safe: ChainEpoch,
+    /// The height of the last block that cannot be reorganized anymore.
+    pub finalized: ChainEpoch,
+}
+
+impl ChainHead {
+    /// The head of a chain whose blocks are final as soon as they are produced.
+    pub fn instant(latest: ChainEpoch) -> Self {
+        Self {
+            latest,
+            safe: latest,
+            finalized: latest,
+        }
+    }
+}
+
+type FetchHead = Arc<dyn Fn() -> BoxFuture<'static, Result<ChainHead>> + Send + Sync>;
+
+/// The last head observed.
+#[derive(Debug, Clone, Copy)]
+struct Observed {
+    head: ChainHead,
+    at: Instant,
+}
+
+/// Tracks the head of the chain of a subnet in the background. The clones share the head and
+/// the background task, which is stopped when the last clone is dropped.
+#[derive(Clone)]
+pub struct ChainHeadTracker {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    label: String,
+    fetch: FetchHead,
+    heads: Arc<watch::Sender<Option<Observed>>>,
+    /// The age after which the last head observed is queried again on read.
+    max_age: Duration,
+    task: AbortHandle,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ChainHeadTracker {
+    /// Spawns the tracking of the head returned by `fetch`, labelled with `label` in the logs.
+    /// The head is queried on every new block notified at `ws_url` if set, and every
+    /// `poll_interval` otherwise. Must be called within a tokio runtime.
+    pub fn spawn<F, Fut>(
+        label: impl Into<String>,
+        poll_interval: Duration,
+        ws_url: Option<Url>,
+        fetch: F,
+    ) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ChainHead>> + Send + 'static,
+    {
+        let label = label.into();
+        let fetch: FetchHead = Arc::new(move || fetch().boxed());
+        let heads = Arc::new(watch::channel(None).0);
+
+        let task = tokio::spawn(track(
+            label.clone(),
+            fetch.clone(),
+            heads.clone(),
+            poll_interval,
+            ws_url,
+        ))
+        .abort_handle();
+
+        Self {
+            inner: Arc::new(Inner {
+                label,
+                fetch,
+                heads,
+                max_age: poll_interval.max(WS_IDLE_POLL_INTERVAL) * 2,
+                task,
+            }),
+        }
+    }
+
+    /// The head of the chain, the last one observed unless it is stale, in which case it is
+    /// queried now.
+    pub async fn head(&self) -> Result<ChainHead> {
+        if let Some(head) = self.last_head() {
+            return Ok(head);
+        }
+        let head = (self.inner.fetch)().await?;
+        observe(&self.inner.heads, head);
+        Ok(head)
+    }
+
+    /// The last head observed, none if there is none yet or if it is stale.
+    pub fn last_head(&self) -> Option<ChainHead> {
+        let observed = (*self.inner.heads.borrow())?;
+        (observed.at.elapsed() <= self.inner.max_age).then_some(observed.head)
+    }
+
+    /// The height of the last block of the chain, see [`Self::head`].
+    pub async fn latest(&self) -> Result<ChainEpoch> {
+        Ok(self.head().await?.latest)
+    }
+
+    /// Waits for a head above `height`, e.g. to follow the chain without polling it.
+    pub async fn wait_above(&self, height: ChainEpoch) -> Result<ChainHead> {
+        let mut rx = self.inner.heads.subscribe();
+        loop {
+            if let Some(observed) = *rx.borrow_and_update() {
+                if observed.head.latest > height {
+                    return Ok(observed.head);
+                }
+            }
+            rx.changed().await?;
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.inner.label
+    }
+}
+
+fn observe(heads: &watch::Sender<Option<Observed>>, head: ChainHead) {
+    heads.send_replace(Some(Observed {
+        head,
+        at: Instant::now(),
+    }));
+}
+
+/// Refreshes the head forever, on the new blocks notified at `ws_url` while the
+/// subscription is up, and every `poll_interval` otherwise.
+async fn track(
+    label: String,
+    fetch: FetchHead,
+    heads: Arc<watch::Sender<Option<Observed>>>,
+    poll_interval: Duration,
+    ws_url: Option<Url>,
+) {
+    loop {
+        if let Some(url) = &ws_url {
+            if let Err(e) = follow_ws(url, &fetch, &heads).await {
+                log::warn!("cannot follow the heads of {label} at {url}, polling instead: {e}");
+            }
+        }
+
+        match fetch().await {
+            Ok(head) => observe(&heads, head),
+            Err(e) => log::warn!("cannot query the head of {label}: {e}"),
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Refreshes the head on every new block notified at `url`, until the subscription ends or
+/// goes idle.
+async fn follow_ws(
+    url: &Url,
+    fetch: &FetchHead,
+    heads: &watch::Sender<Option<Observed>>,
+) -> Result<()> {
+    let ws = Provider::<Ws>::connect(url.as_str()).await?;
+    let mut blocks = ws.subscribe_blocks().await?;
+    while let Ok(Some(_)) = tokio::time::timeout(WS_IDLE_POLL_INTERVAL, blocks.next()).await {
+        // the head is queried rather than read from the block for the safe and finalized
+        // heights, and so that it is the same as the one seen by the other queries
+        observe(heads, fetch().await?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChainHead, ChainHeadTracker};
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_tracker_follows_the_head() {
+        let height = Arc::new(AtomicI64::new(10));
+        let h = height.clone();
+        let tracker = ChainHeadTracker::spawn("test", Duration::from_millis(10), None, move || {
+            let h = h.clone();
+            async move { Ok(ChainHead::instant(h.load(Ordering::SeqCst))) }
+        });
+
+        assert_eq!(tracker.latest().await.unwrap(), 10);
+
+        height.store(12, Ordering::SeqCst);
+        let head = tokio::time::timeout(Duration::from_secs(5), tracker.wait_above(10))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(head, ChainHead::instant(12));
+
+        // the clones share the head tracked in the background
+        assert_eq!(tracker.clone().last_head(), Some(ChainHead::instant(12)));
+    }
+}
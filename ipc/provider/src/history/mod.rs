@@ -0,0 +1,144 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! History of the relayer activity: the quorum events it observed in the child subnet and
+//! every checkpoint submission it attempted in the parent, along with their result.
+//!
+//! The database backend is available with the `sqlx` feature.
+
+#[cfg(feature = "sqlx")]
+mod sql;
+
+#[cfg(feature = "sqlx")]
+pub use sql::SqlHistory;
+
+use crate::pagination::{HeightRange, Page, PageRequest};
+use anyhow::Result;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::checkpoint::QuorumReachedEvent;
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Identifies a submission attempt, a checkpoint height can be attempted several times.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttemptId {
+    pub subnet: SubnetID,
+    pub height: ChainEpoch,
+    pub attempt: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl Display for SubmissionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubmissionStatus::Pending => write!(f, "pending"),
+            SubmissionStatus::Succeeded => write!(f, "succeeded"),
+            SubmissionStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl FromStr for SubmissionStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(SubmissionStatus::Pending),
+            "succeeded" => Ok(SubmissionStatus::Succeeded),
+            "failed" => Ok(SubmissionStatus::Failed),
+            _ => Err(anyhow::anyhow!("unknown submission status: {s}")),
+        }
+    }
+}
+
+/// A quorum event as recorded in the history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumEventRecord {
+    pub subnet: SubnetID,
+    pub height: ChainEpoch,
+    pub obj_kind: u8,
+    /// The hex encoded checkpoint hash.
+    pub obj_hash: String,
+    /// The quorum weight in atto.
+    pub quorum_weight: String,
+    /// Unix timestamp in seconds of when the event was observed.
+    pub observed_at: i64,
+}
+
+/// A submission attempt as recorded in the history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionRecord {
+    pub id: AttemptId,
+    pub submitter: String,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub status: SubmissionStatus,
+    /// The parent epoch the checkpoint was executed at, if it succeeded.
+    pub parent_epoch: Option<ChainEpoch>,
+    pub error: Option<String>,
+}
+
+/// The submission attempts to list, all of them by default. The heights are not bounded above
+/// if `heights.to` is not set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionFilter {
+    pub heights: HeightRange,
+    pub status: Option<SubmissionStatus>,
+    pub submitter: Option<String>,
+}
+
+/// Storage of the relayer history.
+#[async_trait]
+pub trait RelayerHistory: Send + Sync {
+    /// Records a quorum event observed in the child, events already recorded are ignored.
+    async fn record_quorum_event(
+        &self,
+        subnet: &SubnetID,
+        event: &QuorumReachedEvent,
+    ) -> Result<()>;
+
+    /// Records the start of a submission attempt of the checkpoint at `height`.
+    async fn record_attempt(
+        &self,
+        subnet: &SubnetID,
+        height: ChainEpoch,
+        submitter: &Address,
+    ) -> Result<AttemptId>;
+
+    /// Records the result of a submission attempt, the parent epoch it was executed at or
+    /// the error it failed with.
+    async fn record_result(
+        &self,
+        attempt: &AttemptId,
+        result: std::result::Result<ChainEpoch, String>,
+    ) -> Result<()>;
+
+    /// The page of the quorum events of `subnet` observed at `heights`, in the order of their
+    /// height and kind. The cursors are [`crate::pagination::HeightCursor`]s indexed by kind.
+    async fn quorum_events(
+        &self,
+        subnet: &SubnetID,
+        heights: HeightRange,
+        page: &PageRequest,
+    ) -> Result<Page<QuorumEventRecord>>;
+
+    /// The page of the submission attempts for the checkpoints of `subnet` matching `filter`,
+    /// in the order of their height and attempt. The cursors are
+    /// [`crate::pagination::HeightCursor`]s indexed by attempt.
+    async fn submissions(
+        &self,
+        subnet: &SubnetID,
+        filter: &SubmissionFilter,
+        page: &PageRequest,
+    ) -> Result<Page<SubmissionRecord>>;
+}
@@ -0,0 +1,575 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Persistent journal of the outbound transactions sent by the provider.
+//!
+//! Every transaction is recorded, together with its signed payload, before it is broadcast.
+//! After a crash, the pending entries can be reconciled against the chain so that transactions
+//! are neither lost nor duplicated.
+
+use crate::clock::{default_clock, unix_secs, Clock};
+use anyhow::{anyhow, Context, Result};
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long the settled entries are kept before they are pruned, past the top up cap window
+/// so that the transfers it accounts for are still journaled.
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(7 * 86400);
+
+/// The identifier of an entry in the journal.
+pub type EntryId = u64;
+
+/// What an outbound transaction is meant to achieve.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TxIntent {
+    CreateSubnet,
+    JoinSubnet { subnet: String },
+    PreFund { subnet: String },
+    PreRelease { subnet: String },
+    Stake { subnet: String },
+    Unstake { subnet: String },
+    LeaveSubnet { subnet: String },
+    KillSubnet { subnet: String },
+    ClaimCollateral { subnet: String },
+    Fund { subnet: String },
+    FundWithToken { subnet: String },
+    ApproveToken { token: String },
+    RevokeTokenApproval { token: String, spender: String },
+    DiamondCut { diamond: String },
+    Release,
+    Propagate,
+    SendValue(ValueTransfer),
+    AddBootstrap { subnet: String },
+    SetFederatedPower { subnet: String },
+    SetRelayerAllowed { subnet: String },
+    SubmitCheckpoint { subnet: String, height: ChainEpoch },
+    BroadcastRaw,
+}
+
+/// The recipient and amount of a transfer. Both are empty in the entries journaled before they
+/// were recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValueTransfer {
+    pub to: String,
+    /// The amount in atto.
+    pub amount: String,
+}
+
+/// The lifecycle status of a journaled transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    /// The transaction is signed and about to be broadcast.
+    Signed,
+    /// The transaction was accepted by the node.
+    Broadcast,
+    /// The transaction was included in a block successfully.
+    Confirmed { block: ChainEpoch },
+    /// The transaction was rejected, reverted or could not be resumed.
+    Failed { reason: String },
+    /// The nonce of the transaction was consumed by another transaction.
+    Replaced,
+}
+
+impl TxStatus {
+    /// Whether the transaction may still land on chain.
+    pub fn is_pending(&self) -> bool {
+        matches!(self, TxStatus::Signed | TxStatus::Broadcast)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: EntryId,
+    pub intent: TxIntent,
+    /// The chain id of the network the transaction was signed for.
+    pub chain_id: u64,
+    /// The hex encoded sender address.
+    pub from: String,
+    pub nonce: u64,
+    /// The hex encoded keccak hash of the calldata.
+    pub calldata_hash: String,
+    /// The hex encoded transaction hash.
+    pub tx_hash: String,
+    /// The hex encoded signed transaction, used to resume the broadcast after a crash.
+    pub raw_tx: String,
+    /// The key the caller sent the transaction with, to get the same transaction back when
+    /// retrying instead of sending a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// The hex encoded keccak hash of the recipient, value and calldata of the transaction,
+    /// which a retry with the same idempotency key must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params_hash: Option<String>,
+    #[serde(flatten)]
+    pub status: TxStatus,
+    /// Unix timestamp in seconds of the creation of the entry.
+    pub created_at: u64,
+    /// Unix timestamp in seconds of the last status change.
+    pub updated_at: u64,
+}
+
+/// The options of the provider calls sending a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxOptions {
+    /// The key to send the transaction with, so that a retry with the same key waits for the
+    /// transaction already sent instead of sending a new one. Requires a journal.
+    pub idempotency_key: Option<String>,
+}
+
+impl TxOptions {
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+}
+
+/// The new entry to be recorded in the journal.
+pub struct NewEntry {
+    pub intent: TxIntent,
+    pub chain_id: u64,
+    pub from: String,
+    pub nonce: u64,
+    pub calldata_hash: String,
+    pub tx_hash: String,
+    pub raw_tx: String,
+    pub idempotency_key: Option<String>,
+    pub params_hash: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct JournalState {
+    next_id: EntryId,
+    entries: BTreeMap<EntryId, JournalEntry>,
+}
+
+impl JournalState {
+    /// The latest entry with `intent` sent with the idempotency `key`, unless it failed or was
+    /// replaced. Fails if it was sent with other parameters than `params_hash`.
+    fn find_by_key(
+        &self,
+        key: &str,
+        intent: &TxIntent,
+        params_hash: &str,
+    ) -> Result<Option<&JournalEntry>> {
+        let entry = self.entries.values().rev().find(|e| {
+            e.idempotency_key.as_deref() == Some(key)
+                && &e.intent == intent
+                && (e.status.is_pending() || matches!(e.status, TxStatus::Confirmed { .. }))
+        });
+        match entry {
+            Some(e) if e.params_hash.as_deref() != Some(params_hash) => Err(anyhow!(
+                "idempotency key {key} reused with other parameters than tx {}",
+                e.tx_hash
+            )),
+            _ => Ok(entry),
+        }
+    }
+
+    /// Drops the entries settled more than `retention` before `now`. The pending entries are
+    /// kept whatever their age, since they still have to be reconciled.
+    fn prune(&mut self, now: u64, retention: Duration) {
+        let cutoff = now.saturating_sub(retention.as_secs());
+        self.entries
+            .retain(|_, e| e.status.is_pending() || e.updated_at >= cutoff);
+    }
+}
+
+/// The transaction journal. It is written to disk atomically on every change, unless it was
+/// created with [`TxJournal::in_memory`]. The settled entries are pruned once they are older
+/// than the retention.
+pub struct TxJournal {
+    path: Option<PathBuf>,
+    state: Mutex<JournalState>,
+    clock: Arc<dyn Clock>,
+    retention: Duration,
+}
+
+impl TxJournal {
+    /// Opens the journal at `path`, creating an empty one if it does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("cannot read tx journal at {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("cannot parse tx journal at {}", path.display()))?
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            JournalState::default()
+        };
+
+        Ok(Self {
+            path: Some(path),
+            state: Mutex::new(state),
+            clock: default_clock(),
+            retention: DEFAULT_RETENTION,
+        })
+    }
+
+    /// A journal that is not persisted, mostly useful for tests.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            state: Mutex::new(JournalState::default()),
+            clock: default_clock(),
+            retention: DEFAULT_RETENTION,
+        }
+    }
+
+    /// Timestamps the entries with `clock`, e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keeps the settled entries for `retention` after their last status change.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Records a signed transaction before it is broadcast. Fails if a transaction with the
+    /// same intent was already sent with the idempotency key of the entry.
+    pub fn record(&self, entry: NewEntry) -> Result<EntryId> {
+        let now = unix_secs(self.clock.as_ref());
+        self.update(|state| {
+            if let Some(key) = &entry.idempotency_key {
+                let params_hash = entry.params_hash.as_deref().unwrap_or_default();
+                if let Some(sent) = state.find_by_key(key, &entry.intent, params_hash)? {
+                    return Err(anyhow!(
+                        "idempotency key {key} already used by tx {}",
+                        sent.tx_hash
+                    ));
+                }
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            state.entries.insert(
+                id,
+                JournalEntry {
+                    id,
+                    intent: entry.intent,
+                    chain_id: entry.chain_id,
+                    from: entry.from,
+                    nonce: entry.nonce,
+                    calldata_hash: entry.calldata_hash,
+                    tx_hash: entry.tx_hash,
+                    raw_tx: entry.raw_tx,
+                    idempotency_key: entry.idempotency_key,
+                    params_hash: entry.params_hash,
+                    status: TxStatus::Signed,
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            Ok(id)
+        })
+    }
+
+    /// Updates the status of an entry.
+    pub fn set_status(&self, id: EntryId, status: TxStatus) -> Result<()> {
+        self.update(|state| {
+            let entry = state
+                .entries
+                .get_mut(&id)
+                .ok_or_else(|| anyhow!("tx journal entry {id} not found"))?;
+            entry.status = status;
+            entry.updated_at = unix_secs(self.clock.as_ref());
+            Ok(())
+        })
+    }
+
+    /// Returns an entry by id.
+    pub fn get(&self, id: EntryId) -> Option<JournalEntry> {
+        self.state.lock().unwrap().entries.get(&id).cloned()
+    }
+
+    /// Returns all the entries that may still land on chain.
+    pub fn pending(&self) -> Vec<JournalEntry> {
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .filter(|e| e.status.is_pending())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the latest pending entry with the given intent, if any.
+    pub fn find_pending(&self, intent: &TxIntent) -> Option<JournalEntry> {
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .rev()
+            .find(|e| e.status.is_pending() && &e.intent == intent)
+            .cloned()
+    }
+
+    /// Returns the latest entry with the given intent sent with the idempotency `key`, unless
+    /// it failed or was replaced, i.e. the transaction to wait for instead of sending a new one.
+    /// A transaction whose broadcast may not have reached the node is still signed, and has to
+    /// be reconciled. Fails if the key was used with other parameters than `params_hash`.
+    pub fn find_by_key(
+        &self,
+        key: &str,
+        intent: &TxIntent,
+        params_hash: &str,
+    ) -> Result<Option<JournalEntry>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.find_by_key(key, intent, params_hash)?.cloned())
+    }
+
+    /// Returns all the entries in the journal in insertion order.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Imports entries exported from another journal, keeping their status and timestamps.
+    /// The entries are given new ids in their order, and the ones whose transaction is
+    /// already journaled are skipped. Returns the number of imported entries.
+    pub fn import(&self, entries: Vec<JournalEntry>) -> Result<usize> {
+        self.update(|state| {
+            let mut imported = 0;
+            for mut entry in entries {
+                if state.entries.values().any(|e| e.tx_hash == entry.tx_hash) {
+                    continue;
+                }
+                entry.id = state.next_id;
+                state.next_id += 1;
+                state.entries.insert(entry.id, entry);
+                imported += 1;
+            }
+            Ok(imported)
+        })
+    }
+
+    /// Applies `f` to a copy of the state, which replaces the current one only once it is
+    /// persisted, so that the journal in memory never gets ahead of the one on disk.
+    fn update<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut JournalState) -> Result<T>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let mut next = state.clone();
+        let r = f(&mut next)?;
+        next.prune(unix_secs(self.clock.as_ref()), self.retention);
+        if let Some(path) = &self.path {
+            persist(path, &next)?;
+        }
+        *state = next;
+        Ok(r)
+    }
+}
+
+/// Writes the journal to a temporary file synced to disk and moves it in place, so that a
+/// crash never leaves a truncated journal behind nor loses a change once it returned.
+fn persist(path: &Path, state: &JournalState) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec_pretty(state)?)?;
+        file.sync_all()
+    };
+    write().with_context(|| format!("cannot write tx journal to {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("cannot move tx journal to {}", path.display()))?;
+    // the rename is only durable once the directory holding the journal is synced
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .with_context(|| format!("cannot sync tx journal directory {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NewEntry, TxIntent, TxJournal, TxStatus};
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn new_entry(height: i64) -> NewEntry {
+        NewEntry {
+            intent: TxIntent::SubmitCheckpoint {
+                subnet: "/r314159/t410f".to_string(),
+                height,
+            },
+            chain_id: 314159,
+            from: "0x6be1ccf648c74800380d0520d797a170c808b624".to_string(),
+            nonce: height as u64,
+            calldata_hash: "0x00".to_string(),
+            tx_hash: format!("0x{height:064x}"),
+            raw_tx: "0x00".to_string(),
+            idempotency_key: None,
+            params_hash: Some("0x01".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_journal_lifecycle() {
+        let journal = TxJournal::in_memory();
+
+        let a = journal.record(new_entry(10)).unwrap();
+        let b = journal.record(new_entry(20)).unwrap();
+        assert_eq!(journal.pending().len(), 2);
+
+        journal.set_status(a, TxStatus::Broadcast).unwrap();
+        journal
+            .set_status(b, TxStatus::Confirmed { block: 100 })
+            .unwrap();
+
+        let pending = journal.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, a);
+
+        assert!(journal.find_pending(&new_entry(10).intent).is_some());
+        assert!(journal.find_pending(&new_entry(20).intent).is_none());
+    }
+
+    #[test]
+    fn test_journal_idempotency_keys() {
+        let journal = TxJournal::in_memory();
+        let keyed = |height, key: &str| NewEntry {
+            idempotency_key: Some(key.to_string()),
+            ..new_entry(height)
+        };
+        let intent = new_entry(10).intent;
+        let find = |key, intent| journal.find_by_key(key, intent, "0x01").unwrap();
+
+        let a = journal.record(keyed(10, "fund-1")).unwrap();
+        assert_eq!(find("fund-1", &intent).unwrap().id, a);
+        assert!(find("fund-2", &intent).is_none());
+        assert!(find("fund-1", &new_entry(20).intent).is_none());
+
+        // the key cannot send the same intent twice while its transaction may land
+        assert!(journal.record(keyed(10, "fund-1")).is_err());
+        journal
+            .set_status(a, TxStatus::Confirmed { block: 100 })
+            .unwrap();
+        assert!(journal.record(keyed(10, "fund-1")).is_err());
+        assert!(journal.record(keyed(20, "fund-1")).is_ok());
+
+        // nor be reused with other parameters
+        assert!(journal.find_by_key("fund-1", &intent, "0x02").is_err());
+        let other = NewEntry {
+            params_hash: Some("0x02".to_string()),
+            ..keyed(10, "fund-1")
+        };
+        assert!(journal.record(other).is_err());
+
+        // a retry sends a new transaction once the previous one failed
+        journal
+            .set_status(
+                a,
+                TxStatus::Failed {
+                    reason: "reverted".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(find("fund-1", &intent).is_none());
+        let b = journal.record(keyed(10, "fund-1")).unwrap();
+        assert_eq!(find("fund-1", &intent).unwrap().id, b);
+        assert_eq!(find("fund-1", &intent).unwrap().status, TxStatus::Signed);
+    }
+
+    #[test]
+    fn test_send_value_without_transfer() {
+        let intent: TxIntent = serde_json::from_str(r#"{"kind":"send_value"}"#).unwrap();
+        assert_eq!(intent, TxIntent::SendValue(Default::default()));
+    }
+
+    #[test]
+    fn test_journal_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.json");
+
+        let journal = TxJournal::open(&path).unwrap();
+        let id = journal.record(new_entry(10)).unwrap();
+        journal.set_status(id, TxStatus::Broadcast).unwrap();
+        drop(journal);
+
+        let journal = TxJournal::open(&path).unwrap();
+        let entry = journal.get(id).unwrap();
+        assert_eq!(entry.status, TxStatus::Broadcast);
+        assert_eq!(journal.record(new_entry(20)).unwrap(), id + 1);
+    }
+
+    #[test]
+    fn test_journal_prunes_settled_entries() {
+        let clock = ManualClock::new();
+        let journal = TxJournal::in_memory()
+            .with_clock(Arc::new(clock.clone()))
+            .with_retention(Duration::from_secs(100));
+
+        let a = journal.record(new_entry(10)).unwrap();
+        let b = journal.record(new_entry(20)).unwrap();
+        journal
+            .set_status(b, TxStatus::Confirmed { block: 100 })
+            .unwrap();
+
+        clock.advance(Duration::from_secs(101));
+        let c = journal.record(new_entry(30)).unwrap();
+
+        // the pending entry is kept however old, the settled one is gone
+        let ids = journal.entries().iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![a, c]);
+    }
+
+    #[test]
+    fn test_journal_failed_persist_keeps_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.json");
+
+        let journal = TxJournal::open(&path).unwrap();
+        journal.record(new_entry(10)).unwrap();
+
+        // the temporary file cannot be created in place of a directory
+        std::fs::create_dir(path.with_extension("tmp")).unwrap();
+        assert!(journal.record(new_entry(20)).is_err());
+        assert_eq!(journal.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_journal_import() {
+        let source = TxJournal::in_memory();
+        let a = source.record(new_entry(10)).unwrap();
+        source.set_status(a, TxStatus::Broadcast).unwrap();
+        source.record(new_entry(20)).unwrap();
+
+        let target = TxJournal::in_memory();
+        target.record(new_entry(20)).unwrap();
+        target.record(new_entry(30)).unwrap();
+
+        // the entry of height 20 is already journaled
+        assert_eq!(target.import(source.entries()).unwrap(), 1);
+        assert_eq!(target.import(source.entries()).unwrap(), 0);
+
+        let entries = target.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].id, 2);
+        assert_eq!(entries[2].tx_hash, new_entry(10).tx_hash);
+        assert_eq!(entries[2].status, TxStatus::Broadcast);
+        assert_eq!(target.record(new_entry(40)).unwrap(), 3);
+    }
+}
@@ -16,7 +16,7 @@ use tokio::spawn;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::MaybeTlsStream;
-use tokio_tungstenite::{connect_async, WebSocketStream};
+use tokio_tungstenite::{client_async_tls, connect_async, WebSocketStream};
 use url::Url;
 
 #[cfg(test)]
@@ -35,7 +35,7 @@ pub const NO_PARAMS: Value = json!([]);
 /// and subscribe to push-based notifications via Websockets. The returned
 /// results are of type [`Value`] from the [`serde_json`] crate.
 #[async_trait]
-pub trait JsonRpcClient {
+pub trait JsonRpcClient: Send + Sync {
     /// Sends a JSON-RPC request with `method` and `params` via HTTP/HTTPS.
     async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T>;
 
@@ -50,6 +50,7 @@ pub struct JsonRpcClientImpl {
     http_client: Client,
     url: Url,
     bearer_token: Option<String>,
+    proxy: Option<Url>,
 }
 
 impl JsonRpcClientImpl {
@@ -59,8 +60,18 @@ impl JsonRpcClientImpl {
             http_client: Client::default(),
             url,
             bearer_token: bearer_token.map(String::from),
+            proxy: None,
         }
     }
+
+    /// Routes both the HTTP requests and the websocket subscriptions through `proxy`.
+    pub fn with_proxy(mut self, proxy: Url) -> Result<Self> {
+        self.http_client = Client::builder()
+            .proxy(crate::proxy::reqwest_proxy(&proxy)?)
+            .build()?;
+        self.proxy = Some(proxy);
+        Ok(self)
+    }
 }
 
 #[async_trait]
@@ -107,7 +118,13 @@ impl JsonRpcClient for JsonRpcClientImpl {
             request.headers_mut().insert("Authorization", header_value);
         }
 
-        let (mut ws_stream, _) = connect_async(request).await?;
+        let (mut ws_stream, _) = match &self.proxy {
+            Some(proxy) => {
+                let stream = crate::proxy::connect(&self.url, proxy).await?;
+                client_async_tls(request, stream).await?
+            }
+            None => connect_async(request).await?,
+        };
         let request_body = build_jsonrpc_request(method, NO_PARAMS)?;
         ws_stream
             .send(Message::text(request_body.to_string()))
@@ -0,0 +1,314 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Where the private key of a submitter comes from.
+//!
+//! Besides the keystore of the config, the hex encoded key can be read at startup from an
+//! environment variable, stdin or an open file descriptor, so that containers can be handed
+//! their key as a secret without a keystore file being written. With the `vault` feature, the
+//! key can also be read from a KV engine of HashiCorp Vault, or be kept in its transit engine
+//! which then signs the submissions. With the `aws-kms` and `gcp-kms` features, the key can be
+//! kept in a cloud KMS, the address of the submitter being derived from its public key.
+
+use crate::config::Config;
+use crate::manager::EvmSigner;
+use crate::new_evm_keystore_from_config;
+use anyhow::{anyhow, Context, Result};
+use ethers::types::H160;
+use ipc_wallet::{EthKeyAddress, EvmKeyInfo, EvmKeyStore, PersistentKeyStore};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum KeySource {
+    /// The keystore of the config.
+    #[default]
+    Keystore,
+    /// An environment variable, removed from the environment once read.
+    Env(String),
+    /// The first line of stdin.
+    Stdin,
+    /// An open file descriptor, read until its end.
+    Fd(i32),
+    /// A secret of a Vault KV v2 engine, at `<mount>/<path>`.
+    #[cfg(feature = "vault")]
+    VaultKv { mount: String, path: String },
+    /// A key of a Vault transit engine, at `<mount>/<key>`, which never leaves Vault.
+    #[cfg(feature = "vault")]
+    VaultTransit { mount: String, key: String },
+    /// A key of AWS KMS, by key id, alias or ARN.
+    #[cfg(feature = "aws-kms")]
+    AwsKms(String),
+    /// A key version of Google Cloud KMS, by resource name.
+    #[cfg(feature = "gcp-kms")]
+    GcpKms(String),
+}
+
+impl Display for KeySource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeySource::Keystore => write!(f, "keystore"),
+            KeySource::Env(name) => write!(f, "env:{name}"),
+            KeySource::Stdin => write!(f, "stdin"),
+            KeySource::Fd(fd) => write!(f, "fd:{fd}"),
+            #[cfg(feature = "vault")]
+            KeySource::VaultKv { mount, path } => write!(f, "vault-kv:{mount}/{path}"),
+            #[cfg(feature = "vault")]
+            KeySource::VaultTransit { mount, key } => write!(f, "vault-transit:{mount}/{key}"),
+            #[cfg(feature = "aws-kms")]
+            KeySource::AwsKms(key_id) => write!(f, "aws-kms:{key_id}"),
+            #[cfg(feature = "gcp-kms")]
+            KeySource::GcpKms(key_name) => write!(f, "gcp-kms:{key_name}"),
+        }
+    }
+}
+
+impl FromStr for KeySource {
+    type Err = anyhow::Error;
+
+    /// Parses `keystore`, `env:<VAR>`, `stdin` or `fd:<N>`, and with the `vault` feature
+    /// `vault-kv:<mount>/<path>` or `vault-transit:<mount>/<key>`, with the `aws-kms` feature
+    /// `aws-kms:<key id>` and with the `gcp-kms` feature `gcp-kms:<key version name>`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s == "keystore" => Ok(KeySource::Keystore),
+            None if s == "stdin" => Ok(KeySource::Stdin),
+            Some(("env", name)) if !name.is_empty() => Ok(KeySource::Env(name.to_string())),
+            Some(("fd", fd)) => Ok(KeySource::Fd(
+                fd.parse().context("invalid file descriptor")?,
+            )),
+            #[cfg(feature = "vault")]
+            Some(("vault-kv", rest)) => {
+                let (mount, path) = split_vault_path(rest)?;
+                Ok(KeySource::VaultKv { mount, path })
+            }
+            #[cfg(feature = "vault")]
+            Some(("vault-transit", rest)) => {
+                let (mount, key) = split_vault_path(rest)?;
+                Ok(KeySource::VaultTransit { mount, key })
+            }
+            #[cfg(feature = "aws-kms")]
+            Some(("aws-kms", key_id)) if !key_id.is_empty() => {
+                Ok(KeySource::AwsKms(key_id.to_string()))
+            }
+            #[cfg(feature = "gcp-kms")]
+            Some(("gcp-kms", key_name)) if !key_name.is_empty() => {
+                Ok(KeySource::GcpKms(key_name.to_string()))
+            }
+            _ => Err(anyhow!(
+                "invalid key source `{s}`, expected keystore, env:<VAR>, stdin or fd:<N>"
+            )),
+        }
+    }
+}
+
+impl KeySource {
+    /// The key store to sign with: the keystore of the config, or an ephemeral one holding
+    /// only the key read from the source, set as the default key.
+    /// The store is empty for the remote signers, see [`KeySource::remote_signer`].
+    pub async fn evm_keystore(
+        &self,
+        config: Arc<Config>,
+    ) -> Result<PersistentKeyStore<EthKeyAddress>> {
+        if self.is_remote() {
+            return Ok(PersistentKeyStore::ephemeral());
+        }
+
+        let Some(private_key) = self.read_private_key().await? else {
+            return new_evm_keystore_from_config(config);
+        };
+
+        let mut keystore = PersistentKeyStore::ephemeral();
+        let addr = keystore.put(EvmKeyInfo::new(private_key.to_vec()))?;
+        keystore.set_default(&addr)?;
+        log::info!("loaded the key of {} from {self}", addr.to_string());
+
+        Ok(keystore)
+    }
+
+    /// Whether the key stays out of the process, signing with a [`KeySource::remote_signer`].
+    pub fn is_remote(&self) -> bool {
+        #[cfg(feature = "vault")]
+        if let KeySource::VaultTransit { .. } = self {
+            return true;
+        }
+        #[cfg(feature = "aws-kms")]
+        if let KeySource::AwsKms(_) = self {
+            return true;
+        }
+        #[cfg(feature = "gcp-kms")]
+        if let KeySource::GcpKms(_) = self {
+            return true;
+        }
+        false
+    }
+
+    /// The signer of the sources keeping the key out of the process. The KMS signers derive
+    /// their address from their key, the Vault transit signer needs the `address` of its key.
+    /// The chain id of the signer is set by the manager signing with it.
+    #[cfg_attr(not(feature = "vault"), allow(unused_variables))]
+    pub async fn remote_signer(&self, address: Option<H160>) -> Result<Option<EvmSigner>> {
+        #[cfg(feature = "vault")]
+        if let KeySource::VaultTransit { mount, key } = self {
+            let address = address
+                .ok_or_else(|| anyhow!("the address of the vault transit key must be provided"))?;
+            let config = crate::vault::VaultConfig::from_env()?;
+            let signer = crate::vault::VaultTransitSigner::new(config, mount, key, address, 0);
+            return Ok(Some(signer.into()));
+        }
+        #[cfg(feature = "aws-kms")]
+        if let KeySource::AwsKms(key_id) = self {
+            let kms = rusoto_kms::KmsClient::new(rusoto_core::Region::default());
+            let signer = ethers::signers::AwsSigner::new(kms, key_id, 0).await?;
+            return Ok(Some(signer.into()));
+        }
+        #[cfg(feature = "gcp-kms")]
+        if let KeySource::GcpKms(key_name) = self {
+            let signer = crate::gcp_kms::GcpKmsSigner::new(key_name, 0).await?;
+            return Ok(Some(signer.into()));
+        }
+        Ok(None)
+    }
+
+    /// Reads the private key from the source, `None` for the keystore and the remote signers.
+    pub async fn read_private_key(&self) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        let encoded = match self {
+            KeySource::Keystore => return Ok(None),
+            KeySource::Env(name) => {
+                let value = Zeroizing::new(
+                    std::env::var(name).with_context(|| format!("cannot read env var {name}"))?,
+                );
+                // so that the key does not leak to child processes
+                std::env::remove_var(name);
+                value
+            }
+            KeySource::Stdin => {
+                let mut line = Zeroizing::new(String::new());
+                std::io::stdin()
+                    .read_line(&mut line)
+                    .context("cannot read the key from stdin")?;
+                line
+            }
+            KeySource::Fd(fd) => read_fd(*fd)?,
+            #[cfg(feature = "vault")]
+            KeySource::VaultKv { mount, path } => {
+                let kv = crate::vault::VaultKv::new(crate::vault::VaultConfig::from_env()?, mount);
+                let key = kv.get_key(path).await?;
+                Zeroizing::new(hex::encode(key.private_key()))
+            }
+            #[cfg(feature = "vault")]
+            KeySource::VaultTransit { .. } => return Ok(None),
+            #[cfg(feature = "aws-kms")]
+            KeySource::AwsKms(_) => return Ok(None),
+            #[cfg(feature = "gcp-kms")]
+            KeySource::GcpKms(_) => return Ok(None),
+        };
+
+        parse_private_key(&encoded).map(Some)
+    }
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<Zeroizing<String>> {
+    use std::os::fd::FromRawFd;
+
+    // Safety: the descriptor is handed over by the caller for us to consume and close.
+    let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+    let mut content = Zeroizing::new(String::new());
+    file.read_to_string(&mut content)
+        .with_context(|| format!("cannot read the key from file descriptor {fd}"))?;
+    Ok(content)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<Zeroizing<String>> {
+    Err(anyhow!("file descriptors are only supported on unix"))
+}
+
+#[cfg(feature = "vault")]
+fn split_vault_path(s: &str) -> Result<(String, String)> {
+    match s.split_once('/') {
+        Some((mount, path)) if !mount.is_empty() && !path.is_empty() => {
+            Ok((mount.to_string(), path.to_string()))
+        }
+        _ => Err(anyhow!(
+            "expected a vault path as <mount>/<path>, got `{s}`"
+        )),
+    }
+}
+
+fn parse_private_key(encoded: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let encoded = encoded.trim();
+    let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+    let key = Zeroizing::new(hex::decode(encoded).context("the key is not hex encoded")?);
+    if key.len() != 32 {
+        return Err(anyhow!("expected a 32 bytes key, got {} bytes", key.len()));
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_private_key, KeySource};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_key_source() {
+        for s in ["keystore", "env:RELAYER_KEY", "stdin", "fd:3"] {
+            assert_eq!(KeySource::from_str(s).unwrap().to_string(), s);
+        }
+        assert!(KeySource::from_str("env:").is_err());
+        assert!(KeySource::from_str("fd:three").is_err());
+        assert!(KeySource::from_str("vault").is_err());
+    }
+
+    #[cfg(all(feature = "aws-kms", feature = "gcp-kms"))]
+    #[test]
+    fn test_parse_kms_key_source() {
+        for s in [
+            "aws-kms:arn:aws:kms:us-east-1:111122223333:key/1234abcd",
+            "gcp-kms:projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1",
+        ] {
+            assert_eq!(KeySource::from_str(s).unwrap().to_string(), s);
+        }
+        assert!(KeySource::from_str("aws-kms:").is_err());
+    }
+
+    #[cfg(feature = "vault")]
+    #[test]
+    fn test_parse_vault_key_source() {
+        for s in ["vault-kv:secret/relayer", "vault-transit:transit/relayer"] {
+            assert_eq!(KeySource::from_str(s).unwrap().to_string(), s);
+        }
+        assert_eq!(
+            KeySource::from_str("vault-kv:secret/relayers/calibration").unwrap(),
+            KeySource::VaultKv {
+                mount: "secret".to_string(),
+                path: "relayers/calibration".to_string()
+            }
+        );
+        assert!(KeySource::from_str("vault-kv:secret").is_err());
+        assert!(KeySource::from_str("vault-transit:/relayer").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_env_key() {
+        let key = "0x".to_string() + &"01".repeat(32);
+        std::env::set_var("IPC_TEST_KEY_SOURCE", &key);
+
+        let source = KeySource::Env("IPC_TEST_KEY_SOURCE".to_string());
+        let read = source.read_private_key().await.unwrap().unwrap();
+        assert_eq!(*read, vec![1u8; 32]);
+        // the key is not left in the environment
+        assert!(std::env::var("IPC_TEST_KEY_SOURCE").is_err());
+    }
+
+    #[test]
+    fn test_parse_private_key() {
+        assert!(parse_private_key(&format!("{}\n", "ab".repeat(32))).is_ok());
+        assert!(parse_private_key("abcd").is_err());
+        assert!(parse_private_key("not hex").is_err());
+    }
+}
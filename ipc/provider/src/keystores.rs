@@ -0,0 +1,103 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The named keystores of the config, signing the transactions of some subnets or roles
+//! instead of the keystore of `keystore_path`, e.g. the keys of the relayer in a cloud KMS and
+//! the keys of the subnet admins in their own keystore.
+//!
+//! A named keystore is either a keystore directory, or a [`KeySource`] holding a single key.
+//! The subnets are assigned to their keystore in the config, and [`crate::IpcProvider`] signs
+//! the transactions of a subnet with its keystore once opened with
+//! [`crate::IpcProvider::with_keystores`]. The roles, e.g. `relayer`, select theirs by name.
+
+use crate::config::Config;
+use crate::key_source::KeySource;
+use crate::manager::EvmSigner;
+use crate::new_evm_keystore_from_path;
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::signers::Signer;
+use ethers::types::H160;
+use fvm_shared::address::Address;
+use ipc_api::ethers_address_to_fil_address;
+use ipc_wallet::{EthKeyAddress, EvmKeyStore, PersistentKeyStore};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The role of the keystores of the config signing for the relayer.
+pub const RELAYER_ROLE: &str = "relayer";
+
+/// A keystore of the config, opened.
+#[derive(Clone)]
+pub struct NamedKeystore {
+    pub name: String,
+    pub keys: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+    /// The signer of the sources keeping the key out of the process, whose keys are empty.
+    pub signer: Option<EvmSigner>,
+}
+
+impl NamedKeystore {
+    /// Opens the keystore `name` of `config`, reading its key if it is a key source.
+    pub async fn open(config: Arc<Config>, name: &str) -> Result<Self> {
+        let keystore = config
+            .keystores
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown keystore {name}"))?;
+
+        let (keys, signer) = match (&keystore.path, &keystore.source) {
+            (Some(path), None) => (new_evm_keystore_from_path(path)?, None),
+            (None, Some(source)) => {
+                let source = KeySource::from_str(source)
+                    .with_context(|| format!("invalid source of keystore {name}"))?;
+                let address = keystore
+                    .address
+                    .as_deref()
+                    .map(H160::from_str)
+                    .transpose()
+                    .with_context(|| format!("invalid address of keystore {name}"))?;
+                let keys = source.evm_keystore(config.clone()).await?;
+                (keys, source.remote_signer(address).await?)
+            }
+            _ => bail!("keystore {name} must have either a path or a source"),
+        };
+        log::info!("opened keystore {name}");
+
+        Ok(Self {
+            name: name.to_string(),
+            keys: Arc::new(RwLock::new(keys)),
+            signer,
+        })
+    }
+
+    /// The address signing with the keystore by default: the one of its remote signer, or
+    /// else its default key.
+    pub fn default_address(&self) -> Result<Option<Address>> {
+        if let Some(signer) = &self.signer {
+            return Ok(Some(ethers_address_to_fil_address(&signer.address())?));
+        }
+        match self.keys.write().unwrap().get_default()? {
+            Some(addr) => Ok(Some(Address::try_from(addr)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Zeroizes the keys of `keystore` every `period` once their unlock expired, see
+/// [`PersistentKeyStore::unlock`]. The task stops with the last reference to the keystore.
+pub fn spawn_auto_lock(
+    keystore: &Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+    period: Duration,
+) -> tokio::task::JoinHandle<()> {
+    let keystore = Arc::downgrade(keystore);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let Some(keystore) = keystore.upgrade() else {
+                return;
+            };
+            if keystore.write().unwrap().lock_if_expired() {
+                log::info!("keystore locked after its unlock expired");
+            }
+        }
+    })
+}
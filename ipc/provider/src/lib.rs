@@ -4,39 +4,96 @@
 
 use crate::manager::{GetBlockHashResult, TopDownQueryPayload};
 use anyhow::anyhow;
+use approval::{IpcContract, TokenApproval, TokenApprovals};
 use base64::Engine;
+use bootstrap::SubnetBootstrapInfo;
+use bridge::{BridgeOptions, BridgeProgress};
 use config::Config;
+use confirmation::{ConfirmationPolicy, OperationKind, ValueOperation};
+use diamond::{Facet, UpgradePlan};
+use epoch::{BlockTime, CheckpointEta};
+use futures_util::future::join_all;
+use futures_util::stream::BoxStream;
 use fvm_shared::{
     address::Address, clock::ChainEpoch, crypto::signature::SignatureType, econ::TokenAmount,
 };
+use head::{ChainHeadTracker, DEFAULT_HEAD_POLL_INTERVAL};
 use ipc_api::checkpoint::{BottomUpCheckpointBundle, QuorumReachedEvent};
 use ipc_api::staking::{StakingChangeRequest, ValidatorInfo};
-use ipc_api::subnet::{PermissionMode, SupplySource};
+use ipc_api::subnet::{PermissionMode, SupplyKind, SupplySource};
 use ipc_api::{
     cross::IpcEnvelope,
     subnet::{ConsensusType, ConstructParams},
     subnet_id::SubnetID,
 };
 use ipc_wallet::{
-    EthKeyAddress, EvmKeyStore, KeyStore, KeyStoreConfig, PersistentKeyStore, Wallet,
+    AddressBook, EthKeyAddress, EvmKeyStore, KeyStore, KeyStoreConfig, PersistentKeyStore, Wallet,
 };
+use journal::{TxJournal, TxOptions};
+use keystores::NamedKeystore;
 use lotus::message::wallet::WalletKeyType;
-use manager::{EthSubnetManager, SubnetGenesisInfo, SubnetInfo, SubnetManager};
+use manager::evm::{set_global_rpc_concurrency, RpcMiddleware, SubnetParamsCache};
+use manager::{
+    EthSubnetManager, NoSigner, SubnetGenesisInfo, SubnetInfo, SubnetManager, SubnetParams,
+    UnsignedTransaction, ValidatorPosition,
+};
+use pagination::{paginate, HeightRange, Page, PageRequest};
+use portfolio::{Denomination, Portfolio, SubnetBalance, SubnetBalanceError};
+use recipient::Recipient;
+use release::{ContractCheck, ContractRole, ContractsReport, KnownReleases};
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Borrow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 use zeroize::Zeroize;
 
+pub mod approval;
+pub mod bootstrap;
+pub mod breaker;
+pub mod bridge;
 pub mod checkpoint;
+pub mod clock;
 pub mod config;
+pub mod confirmation;
+#[cfg(feature = "deploy")]
+pub mod deploy;
+#[cfg(feature = "devnet")]
+pub mod devnet;
+pub mod diamond;
+pub mod doctor;
+pub mod drift;
+pub mod epoch;
+pub mod events;
+pub mod finality_votes;
+#[cfg(feature = "gcp-kms")]
+pub mod gcp_kms;
+pub mod head;
+pub mod history;
+pub mod journal;
 pub mod jsonrpc;
+pub mod key_source;
+pub mod keystores;
+pub mod liveness;
+pub mod logging;
 pub mod lotus;
 pub mod manager;
+pub mod monitor;
+pub mod pagination;
+pub mod portfolio;
+pub mod postbox;
+pub mod proxy;
+pub mod recipient;
+pub mod release;
+pub mod schema;
+pub mod topup;
+#[cfg(feature = "vault")]
+pub mod vault;
+pub mod webhook;
 
 const DEFAULT_REPO_PATH: &str = ".ipc";
 const DEFAULT_CONFIG_NAME: &str = "config.toml";
@@ -65,6 +122,20 @@ pub struct IpcProvider {
     config: Arc<Config>,
     fvm_wallet: Option<Arc<RwLock<Wallet>>>,
     evm_keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+    /// The named recipients of the operations moving value.
+    address_book: Option<Arc<RwLock<AddressBook>>>,
+    /// The parameters of the subnets, shared by all the connections.
+    subnet_params: Arc<SubnetParamsCache>,
+    /// The confirmation required by the operations moving value, if any.
+    confirmation: Option<ConfirmationPolicy>,
+    /// The middlewares of the requests of the connections to the subnets.
+    rpc_middlewares: Vec<Arc<dyn RpcMiddleware>>,
+    /// The trackers of the heads of the subnets, shared by all the components following them.
+    head_trackers: Arc<Mutex<HashMap<SubnetID, ChainHeadTracker>>>,
+    /// The named keystores of the config, signing for the subnets assigned to them.
+    keystores: Arc<HashMap<String, NamedKeystore>>,
+    /// The journal the transactions of the connections are recorded in, if any.
+    journal: Option<Arc<TxJournal>>,
 }
 
 impl IpcProvider {
@@ -72,24 +143,63 @@ impl IpcProvider {
         config: Arc<Config>,
         fvm_wallet: Arc<RwLock<Wallet>>,
         evm_keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+        address_book: Arc<RwLock<AddressBook>>,
     ) -> Self {
+        let confirmation = config
+            .confirmation_threshold
+            .map(|t| ConfirmationPolicy::new(TokenAmount::from_whole(t)));
         Self {
             sender: None,
             config,
             fvm_wallet: Some(fvm_wallet),
             evm_keystore: Some(evm_keystore),
+            address_book: Some(address_book),
+            subnet_params: Arc::new(SubnetParamsCache::default()),
+            confirmation,
+            rpc_middlewares: vec![],
+            head_trackers: Default::default(),
+            keystores: Default::default(),
+            journal: None,
         }
     }
 
     /// Initializes an `IpcProvider` from the config specified in the
-    /// argument's config path.
+    /// argument's config path. The `rpc_max_concurrency` of the config, if any, caps the rpc
+    /// requests in flight of the whole process.
     pub fn new_from_config(config_path: String) -> anyhow::Result<Self> {
         let config = Arc::new(Config::from_file(config_path)?);
+        if let Some(limit) = config.rpc_max_concurrency {
+            set_global_rpc_concurrency(limit)?;
+        }
         let fvm_wallet = Arc::new(RwLock::new(Wallet::new(new_fvm_wallet_from_config(
             config.clone(),
         )?)));
         let evm_keystore = Arc::new(RwLock::new(new_evm_keystore_from_config(config.clone())?));
-        Ok(Self::new(config, fvm_wallet, evm_keystore))
+        let address_book = Arc::new(RwLock::new(new_address_book_from_config(config.clone())?));
+        Ok(Self::new(config, fvm_wallet, evm_keystore, address_book))
+    }
+
+    /// Initializes a read-only `IpcProvider` from the config in `config_path`, without opening
+    /// the keystores, e.g. for monitoring. The queries work while the transactions fail with
+    /// [`NoSigner`].
+    pub fn new_read_only_from_config(config_path: String) -> anyhow::Result<Self> {
+        let config = Config::from_file(config_path)?;
+        if let Some(limit) = config.rpc_max_concurrency {
+            set_global_rpc_concurrency(limit)?;
+        }
+        Ok(Self {
+            sender: None,
+            config: Arc::new(config),
+            fvm_wallet: None,
+            evm_keystore: None,
+            address_book: None,
+            subnet_params: Arc::new(SubnetParamsCache::default()),
+            confirmation: None,
+            rpc_middlewares: vec![],
+            head_trackers: Default::default(),
+            keystores: Default::default(),
+            journal: None,
+        })
     }
 
     /// Initializes a new `IpcProvider` configured to interact with
@@ -107,13 +217,21 @@ impl IpcProvider {
                 &repo_path,
             )?)));
             let evm_keystore = Arc::new(RwLock::new(new_evm_keystore_from_path(&repo_path)?));
-            Ok(Self::new(config, fvm_wallet, evm_keystore))
+            let address_book = Arc::new(RwLock::new(new_address_book_from_path(&repo_path)?));
+            Ok(Self::new(config, fvm_wallet, evm_keystore, address_book))
         } else {
             Ok(Self {
                 sender: None,
                 config,
                 fvm_wallet: None,
                 evm_keystore: None,
+                address_book: None,
+                subnet_params: Arc::new(SubnetParamsCache::default()),
+                confirmation: None,
+                rpc_middlewares: vec![],
+                head_trackers: Default::default(),
+                keystores: Default::default(),
+                journal: None,
             })
         }
     }
@@ -125,28 +243,64 @@ impl IpcProvider {
 
     /// Get the connection instance for the subnet.
     pub fn connection(&self, subnet: &SubnetID) -> Option<Connection> {
-        let subnets = &self.config.subnets;
-        match subnets.get(subnet) {
+        self.connect(subnet, None)
+    }
+
+    /// The connection to `subnet` journaling its transactions with the idempotency key of
+    /// `options`, if any, which requires a journal.
+    fn keyed_connection(
+        &self,
+        subnet: &SubnetID,
+        options: &TxOptions,
+    ) -> anyhow::Result<Option<Connection>> {
+        let idempotency_key = options.idempotency_key.as_deref();
+        if idempotency_key.is_some() && self.journal.is_none() {
+            return Err(anyhow!("idempotency keys require a tx journal"));
+        }
+        Ok(self.connect(subnet, idempotency_key))
+    }
+
+    fn connect(&self, subnet: &SubnetID, idempotency_key: Option<&str>) -> Option<Connection> {
+        match self.config.subnet(subnet) {
             Some(subnet) => match &subnet.config {
                 config::subnet::SubnetConfig::Fevm(_) => {
-                    let wallet = match self.evm_wallet() {
-                        Ok(w) => Some(w),
+                    let keystore = match self.subnet_keystore(&subnet.id) {
+                        Ok(k) => k,
+                        Err(e) => {
+                            log::warn!("error initializing evm wallet: {e}");
+                            return None;
+                        }
+                    };
+                    // without a keystore the manager is read-only
+                    let evm_keystore = match keystore {
+                        Some(k) => Some(k.keys.clone()),
+                        None => self.evm_keystore.clone(),
+                    };
+                    let mut manager = match EthSubnetManager::from_subnet_with_middlewares(
+                        &subnet,
+                        evm_keystore,
+                        self.rpc_middlewares.clone(),
+                    ) {
+                        Ok(w) => w,
                         Err(e) => {
                             log::warn!("error initializing evm wallet: {e}");
-                            None
+                            return None;
                         }
                     };
-                    let manager =
-                        match EthSubnetManager::from_subnet_with_wallet_store(subnet, wallet) {
-                            Ok(w) => Some(w),
-                            Err(e) => {
-                                log::warn!("error initializing evm wallet: {e}");
-                                return None;
-                            }
-                        };
+                    if let Some(signer) = keystore.and_then(|k| k.signer.clone()) {
+                        manager = manager.with_signer(signer);
+                    }
+                    if let Some(journal) = &self.journal {
+                        manager = manager.with_journal(journal.clone());
+                    }
+                    if let Some(key) = idempotency_key {
+                        manager = manager.with_idempotency_key(key.to_string());
+                    }
                     Some(Connection {
-                        manager: Box::new(manager.unwrap()),
-                        subnet: subnet.clone(),
+                        manager: Box::new(
+                            manager.with_subnet_params_cache(self.subnet_params.clone()),
+                        ),
+                        subnet,
                     })
                 }
             },
@@ -154,11 +308,114 @@ impl IpcProvider {
         }
     }
 
+    /// Records the transactions of all the connections in `journal` before broadcasting them.
+    /// The journal makes the calls taking an idempotency key, e.g. [`IpcProvider::fund`] or
+    /// [`IpcProvider::join_subnet`], safe to retry with the same key: the retry waits for the
+    /// transactions already sent and returns their result instead of sending them again. The
+    /// key must be unique to the call, a retry with other parameters fails.
+    pub fn with_journal(mut self, journal: Arc<TxJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Opens the named keystores of the config, signing the transactions of the subnets
+    /// assigned to them instead of the keystore of `keystore_path`.
+    pub async fn with_keystores(mut self) -> anyhow::Result<Self> {
+        let mut keystores = HashMap::new();
+        for name in self.config.keystores.keys() {
+            let keystore = NamedKeystore::open(self.config.clone(), name).await?;
+            keystores.insert(name.clone(), keystore);
+        }
+        self.keystores = Arc::new(keystores);
+        Ok(self)
+    }
+
+    /// The named keystore signing for `subnet`, if the config assigns one to it. The keystore
+    /// must have been opened with [`IpcProvider::with_keystores`], unless the provider is
+    /// read-only.
+    fn subnet_keystore(&self, subnet: &SubnetID) -> anyhow::Result<Option<&NamedKeystore>> {
+        let Some(name) = self.config.keystore_of_subnet(subnet) else {
+            return Ok(None);
+        };
+        if self.is_read_only() {
+            return Ok(None);
+        }
+        self.keystores
+            .get(name)
+            .map(Some)
+            .ok_or_else(|| anyhow!("keystore {name} of subnet {subnet} not opened"))
+    }
+
+    /// The tracker of the head of `subnet`, created on first use and shared afterwards. The
+    /// queries of the provider read the head from it once it exists.
+    pub fn chain_head_tracker(&self, subnet: &SubnetID) -> anyhow::Result<ChainHeadTracker> {
+        let mut trackers = self.head_trackers.lock().unwrap();
+        if let Some(tracker) = trackers.get(subnet) {
+            return Ok(tracker.clone());
+        }
+
+        let config = self
+            .config
+            .subnet(subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+        let manager = EthSubnetManager::from_subnet_with_middlewares(
+            &config,
+            None,
+            self.rpc_middlewares.clone(),
+        )?;
+        let tracker = manager.chain_head_tracker(subnet.to_string(), DEFAULT_HEAD_POLL_INTERVAL);
+        trackers.insert(subnet.clone(), tracker.clone());
+        Ok(tracker)
+    }
+
+    /// The height of the head of `subnet`, from its tracker if there is one.
+    async fn head_height(
+        &self,
+        subnet: &SubnetID,
+        manager: &dyn SubnetManager,
+    ) -> anyhow::Result<ChainEpoch> {
+        let tracker = self.head_trackers.lock().unwrap().get(subnet).cloned();
+        match tracker {
+            Some(tracker) => tracker.latest().await,
+            None => manager.chain_head_height().await,
+        }
+    }
+
     /// Set the default account for the provider
     pub fn with_sender(&mut self, from: Address) {
         self.sender = Some(from);
     }
 
+    /// Adds `middleware` around the requests of the connections to the subnets, inside the
+    /// middlewares added before it.
+    pub fn with_rpc_middleware(&mut self, middleware: Arc<dyn RpcMiddleware>) {
+        self.rpc_middlewares.push(middleware);
+    }
+
+    /// The confirmation required by the operations moving value, from the
+    /// `confirmation_threshold` of the config unless set explicitly.
+    pub fn confirmation_policy(&self) -> Option<&ConfirmationPolicy> {
+        self.confirmation.as_ref()
+    }
+
+    /// Requires the confirmation of the operations moving value according to `policy`, or
+    /// none if `None`.
+    pub fn set_confirmation_policy(&mut self, policy: Option<ConfirmationPolicy>) {
+        self.confirmation = policy;
+    }
+
+    fn confirm(&self, op: ValueOperation) -> anyhow::Result<()> {
+        match &self.confirmation {
+            Some(policy) => policy.check(&op),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the provider has no keystore to sign the transactions with.
+    pub fn is_read_only(&self) -> bool {
+        self.evm_keystore.is_none()
+    }
+
     /// Returns the evm wallet if it is configured, and throws an error if no wallet configured.
     ///
     /// This method should be used when we want the wallet retrieval to throw an error
@@ -181,6 +438,41 @@ impl IpcProvider {
         }
     }
 
+    /// Returns the address book if a keystore is configured.
+    pub fn address_book(&self) -> anyhow::Result<Arc<RwLock<AddressBook>>> {
+        if let Some(book) = &self.address_book {
+            Ok(book.clone())
+        } else {
+            Err(anyhow!("No address book found in provider"))
+        }
+    }
+
+    /// Resolves a recipient of `subnet`, looking its name up in the address book if needed,
+    /// e.g. into the `to` of [`IpcProvider::fund`].
+    pub fn resolve_recipient(
+        &self,
+        subnet: &SubnetID,
+        recipient: &Recipient,
+    ) -> anyhow::Result<Address> {
+        match &self.address_book {
+            Some(book) => recipient.resolve(Some(&book.read().unwrap()), subnet),
+            None => recipient.resolve(None, subnet),
+        }
+    }
+
+    /// Resolves an optional recipient of `subnet`, defaulting to `sender`.
+    fn recipient_or(
+        &self,
+        subnet: &SubnetID,
+        to: Option<Recipient>,
+        sender: Address,
+    ) -> anyhow::Result<Address> {
+        match to {
+            Some(to) => self.resolve_recipient(subnet, &to),
+            None => Ok(sender),
+        }
+    }
+
     fn check_sender(
         &mut self,
         subnet: &config::Subnet,
@@ -201,7 +493,17 @@ impl IpcProvider {
         match &subnet.config {
             config::subnet::SubnetConfig::Fevm(_) => {
                 if self.sender.is_none() {
-                    let wallet = self.evm_wallet()?;
+                    // the default of the keystore of the subnet is not the default sender
+                    // of the other subnets
+                    if let Some(keystore) = self.subnet_keystore(&subnet.id)? {
+                        return keystore.default_address()?.ok_or_else(|| {
+                            anyhow!("no default evm account in keystore {}", keystore.name)
+                        });
+                    }
+                    let wallet = self
+                        .evm_keystore
+                        .clone()
+                        .ok_or(NoSigner { address: None })?;
                     let addr = match wallet.write().unwrap().get_default()? {
                         None => return Err(anyhow!("no default evm account configured")),
                         Some(addr) => Address::try_from(addr)?,
@@ -275,15 +577,36 @@ impl IpcProvider {
         from: Option<Address>,
         collateral: TokenAmount,
         public_key: Vec<u8>,
+    ) -> anyhow::Result<ChainEpoch> {
+        self.join_subnet_with_options(subnet, from, collateral, public_key, &TxOptions::default())
+            .await
+    }
+
+    /// Joins `subnet` with `collateral`. A retry with the idempotency key of `options`, if
+    /// any, waits for the transaction already sent, see [`IpcProvider::with_journal`].
+    pub async fn join_subnet_with_options(
+        &mut self,
+        subnet: SubnetID,
+        from: Option<Address>,
+        collateral: TokenAmount,
+        public_key: Vec<u8>,
+        options: &TxOptions,
     ) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.keyed_connection(&parent, options)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
 
         let subnet_config = conn.subnet();
         let sender = self.check_sender(subnet_config, from)?;
+        self.confirm(ValueOperation {
+            kind: OperationKind::JoinSubnet,
+            subnet: subnet.clone(),
+            from: sender,
+            to: None,
+            amount: collateral.clone(),
+        })?;
 
         conn.manager()
             .join_subnet(subnet, sender, collateral, public_key)
@@ -417,6 +740,17 @@ impl IpcProvider {
         &self,
         gateway_addr: Option<Address>,
         subnet: &SubnetID,
+    ) -> anyhow::Result<HashMap<SubnetID, SubnetInfo>> {
+        self.list_child_subnets_at(gateway_addr, subnet, None).await
+    }
+
+    /// Lists the child subnets registered in the gateway, at `at_height` if set, which
+    /// requires an archive node, at the head otherwise.
+    pub async fn list_child_subnets_at(
+        &self,
+        gateway_addr: Option<Address>,
+        subnet: &SubnetID,
+        at_height: Option<ChainEpoch>,
     ) -> anyhow::Result<HashMap<SubnetID, SubnetInfo>> {
         let conn = match self.connection(subnet) {
             None => return Err(anyhow!("target subnet not found")),
@@ -430,7 +764,35 @@ impl IpcProvider {
             Some(addr) => addr,
         };
 
-        conn.manager().list_child_subnets(gateway_addr).await
+        conn.manager()
+            .list_child_subnets(gateway_addr, at_height)
+            .await
+    }
+
+    /// A page of the child subnets registered in the gateway, in the order of their ids.
+    pub async fn list_child_subnets_page(
+        &self,
+        gateway_addr: Option<Address>,
+        subnet: &SubnetID,
+        page: &PageRequest,
+    ) -> anyhow::Result<Page<SubnetInfo>> {
+        self.list_child_subnets_page_at(gateway_addr, subnet, None, page)
+            .await
+    }
+
+    /// A page of the child subnets registered in the gateway at `at_height` if set, in the
+    /// order of their ids.
+    pub async fn list_child_subnets_page_at(
+        &self,
+        gateway_addr: Option<Address>,
+        subnet: &SubnetID,
+        at_height: Option<ChainEpoch>,
+        page: &PageRequest,
+    ) -> anyhow::Result<Page<SubnetInfo>> {
+        let subnets = self
+            .list_child_subnets_at(gateway_addr, subnet, at_height)
+            .await?;
+        paginate(subnets.into_values().collect(), |s| s.id.to_string(), page)
     }
 
     /// Funds an account in a child subnet, if `to` is `None`, the self account
@@ -442,9 +804,31 @@ impl IpcProvider {
         from: Option<Address>,
         to: Option<Address>,
         amount: TokenAmount,
+    ) -> anyhow::Result<ChainEpoch> {
+        self.fund_with_options(
+            subnet,
+            gateway_addr,
+            from,
+            to,
+            amount,
+            &TxOptions::default(),
+        )
+        .await
+    }
+
+    /// [`IpcProvider::fund`], where a retry with the idempotency key of `options`, if any,
+    /// waits for the transaction already sent, see [`IpcProvider::with_journal`].
+    pub async fn fund_with_options(
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: TokenAmount,
+        options: &TxOptions,
     ) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.keyed_connection(&parent, options)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -456,9 +840,17 @@ impl IpcProvider {
             None => subnet_config.gateway_addr(),
             Some(addr) => addr,
         };
+        let to = to.unwrap_or(sender);
+        self.confirm(ValueOperation {
+            kind: OperationKind::Fund,
+            subnet: subnet.clone(),
+            from: sender,
+            to: Some(to),
+            amount: amount.clone(),
+        })?;
 
         conn.manager()
-            .fund(subnet, gateway_addr, sender, to.unwrap_or(sender), amount)
+            .fund(subnet, gateway_addr, sender, to, amount)
             .await
     }
 
@@ -471,21 +863,176 @@ impl IpcProvider {
         from: Option<Address>,
         to: Option<Address>,
         amount: TokenAmount,
+    ) -> anyhow::Result<ChainEpoch> {
+        self.fund_with_token_with_options(subnet, from, to, amount, &TxOptions::default())
+            .await
+    }
+
+    /// [`IpcProvider::fund_with_token`], where a retry with the idempotency key of `options`,
+    /// if any, waits for the transaction already sent, see [`IpcProvider::with_journal`].
+    pub async fn fund_with_token_with_options(
+        &mut self,
+        subnet: SubnetID,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: TokenAmount,
+        options: &TxOptions,
     ) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.keyed_connection(&parent, options)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
 
         let subnet_config = conn.subnet();
         let sender = self.check_sender(subnet_config, from)?;
+        let to = to.unwrap_or(sender);
+        self.confirm(ValueOperation {
+            kind: OperationKind::FundWithToken,
+            subnet: subnet.clone(),
+            from: sender,
+            to: Some(to),
+            amount: amount.clone(),
+        })?;
 
         conn.manager()
-            .fund_with_token(subnet, sender, to.unwrap_or(sender), amount)
+            .fund_with_token(subnet, sender, to, amount)
             .await
     }
 
+    /// Deposits `amount` of the ERC20 token supplying `subnet` to an account in it, approving
+    /// the gateway of the parent to take the tokens first if needed, and waits for the subnet
+    /// to mint the funds. If `to` is `None`, the `from` account is funded. The steps are
+    /// reported to `progress` as they are done. Returns the height of the finality of the
+    /// parent committed in the subnet, past the deposit.
+    pub async fn deposit_token(
+        &mut self,
+        subnet: SubnetID,
+        from: Option<Address>,
+        to: Option<Recipient>,
+        amount: TokenAmount,
+        options: &BridgeOptions,
+        progress: impl Fn(BridgeProgress),
+    ) -> anyhow::Result<ChainEpoch> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = self
+            .connection(&parent)
+            .ok_or_else(|| anyhow!("target parent subnet not found"))?;
+        let child_conn = self
+            .connection(&subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        let token = self.supply_token(&parent_conn, &subnet).await?;
+        let sender = self.check_sender(parent_conn.subnet(), from)?;
+        let to = self.recipient_or(&subnet, to, sender)?;
+        self.confirm(ValueOperation {
+            kind: OperationKind::FundWithToken,
+            subnet: subnet.clone(),
+            from: sender,
+            to: Some(to),
+            amount: amount.clone(),
+        })?;
+
+        let parent_manager = parent_conn.manager();
+        let gateway = parent_conn.subnet().gateway_addr();
+        let allowance = parent_manager
+            .token_allowance(&token, &sender, &gateway)
+            .await?;
+        if allowance < amount {
+            let height = parent_manager
+                .approve_token(&token, sender, amount.clone())
+                .await?;
+            progress(BridgeProgress::Approved { height });
+        } else {
+            progress(BridgeProgress::AlreadyApproved);
+        }
+
+        let height = parent_manager
+            .fund_with_token(subnet.clone(), sender, to, amount)
+            .await?;
+        progress(BridgeProgress::Deposited { height });
+
+        let child_manager = child_conn.manager();
+        let parent_height = bridge::wait_for_height(
+            &format!("parent finality of {subnet}"),
+            height,
+            options,
+            || child_manager.latest_parent_finality(),
+        )
+        .await?;
+        progress(BridgeProgress::Minted { parent_height });
+        Ok(parent_height)
+    }
+
+    /// Withdraws `amount` from `subnet` to an account in its parent as the ERC20 token
+    /// supplying the subnet, and waits for the checkpoint carrying the withdrawal to be
+    /// committed in the parent, which transfers the tokens. If `to` is `None`, the `from`
+    /// account receives them. The steps are reported to `progress` as they are done. Returns
+    /// the height of the checkpoint committed in the parent, past the withdrawal.
+    pub async fn withdraw_token(
+        &mut self,
+        subnet: SubnetID,
+        from: Option<Address>,
+        to: Option<Recipient>,
+        amount: TokenAmount,
+        options: &BridgeOptions,
+        progress: impl Fn(BridgeProgress),
+    ) -> anyhow::Result<ChainEpoch> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = self
+            .connection(&parent)
+            .ok_or_else(|| anyhow!("target parent subnet not found"))?;
+        let child_conn = self
+            .connection(&subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        self.supply_token(&parent_conn, &subnet).await?;
+        let sender = self.check_sender(child_conn.subnet(), from)?;
+        let to = self.recipient_or(&parent, to, sender)?;
+        self.confirm(ValueOperation {
+            kind: OperationKind::Release,
+            subnet: subnet.clone(),
+            from: sender,
+            to: Some(to),
+            amount: amount.clone(),
+        })?;
+
+        let height = child_conn
+            .manager()
+            .release(child_conn.subnet().gateway_addr(), sender, to, amount)
+            .await?;
+        progress(BridgeProgress::Released { height });
+
+        let parent_manager = parent_conn.manager();
+        let checkpoint_height = bridge::wait_for_height(
+            &format!("bottom-up checkpoints of {subnet}"),
+            height,
+            options,
+            || parent_manager.last_bottom_up_checkpoint_height(&subnet),
+        )
+        .await?;
+        progress(BridgeProgress::Withdrawn { checkpoint_height });
+        Ok(checkpoint_height)
+    }
+
+    /// The ERC20 token supplying `subnet`, looked up through the connection to its parent.
+    async fn supply_token(
+        &self,
+        parent_conn: &Connection,
+        subnet: &SubnetID,
+    ) -> anyhow::Result<Address> {
+        let params = parent_conn.manager().subnet_params(subnet).await?;
+        match (
+            params.supply_source.kind,
+            params.supply_source.token_address,
+        ) {
+            (SupplyKind::ERC20, Some(token)) => Ok(token),
+            _ => Err(anyhow!(
+                "subnet {subnet} is not supplied with an erc20 token"
+            )),
+        }
+    }
+
     /// Release to an account in a child subnet, if `to` is `None`, the self account
     /// is funded.
     pub async fn release(
@@ -496,7 +1043,29 @@ impl IpcProvider {
         to: Option<Address>,
         amount: TokenAmount,
     ) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(&subnet) {
+        self.release_with_options(
+            subnet,
+            gateway_addr,
+            from,
+            to,
+            amount,
+            &TxOptions::default(),
+        )
+        .await
+    }
+
+    /// [`IpcProvider::release`], where a retry with the idempotency key of `options`, if any,
+    /// waits for the transaction already sent, see [`IpcProvider::with_journal`].
+    pub async fn release_with_options(
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: TokenAmount,
+        options: &TxOptions,
+    ) -> anyhow::Result<ChainEpoch> {
+        let conn = match self.keyed_connection(&subnet, options)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -508,34 +1077,60 @@ impl IpcProvider {
             None => subnet_config.gateway_addr(),
             Some(addr) => addr,
         };
+        let to = to.unwrap_or(sender);
+        self.confirm(ValueOperation {
+            kind: OperationKind::Release,
+            subnet: subnet.clone(),
+            from: sender,
+            to: Some(to),
+            amount: amount.clone(),
+        })?;
 
         conn.manager()
-            .release(gateway_addr, sender, to.unwrap_or(sender), amount)
+            .release(gateway_addr, sender, to, amount)
             .await
     }
 
-    /// Propagate a cross-net message forward. For `postbox_msg_key`, we are using bytes because different
-    /// runtime have different representations. For FVM, it should be `CID` as bytes. For EVM, it is
-    /// `bytes32`.
-    pub async fn propagate(
-        &self,
-        _subnet: SubnetID,
-        _gateway_addr: Address,
-        _from: Address,
-        _postbox_msg_key: Vec<u8>,
-    ) -> anyhow::Result<()> {
-        todo!()
+    /// The unsigned transaction of [`IpcProvider::fund`], to be signed out of band.
+    pub async fn unsigned_fund(
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: TokenAmount,
+    ) -> anyhow::Result<UnsignedTransaction> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
+
+        let gateway_addr = match gateway_addr {
+            None => subnet_config.gateway_addr(),
+            Some(addr) => addr,
+        };
+
+        let to = to.unwrap_or(sender);
+
+        conn.manager()
+            .unsigned_fund(subnet, gateway_addr, sender, to, amount)
+            .await
     }
 
-    /// Send value between two addresses in a subnet
-    pub async fn send_value(
+    /// The unsigned transaction of [`IpcProvider::release`], to be signed out of band.
+    pub async fn unsigned_release(
         &mut self,
-        subnet: &SubnetID,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
         from: Option<Address>,
-        to: Address,
+        to: Option<Address>,
         amount: TokenAmount,
-    ) -> anyhow::Result<()> {
-        let conn = match self.connection(subnet) {
+    ) -> anyhow::Result<UnsignedTransaction> {
+        let conn = match self.connection(&subnet) {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -543,68 +1138,526 @@ impl IpcProvider {
         let subnet_config = conn.subnet();
         let sender = self.check_sender(subnet_config, from)?;
 
-        // FIXME: This limits that only value to f-addresses can be sent
-        // with the provider (which requires translating eth-addresses into
-        // their corresponding delegated address). This should be fixed with the
-        // new address wrapper type planned: https://github.com/consensus-shipyard/ipc-agent/issues/263
-        // let to = match Address::from_str(&request.to) {
-        //     Ok(addr) => addr,
-        //     Err(_) => {
-        //         // we need to check if an 0x address was passed and convert
-        //         // to a delegated address
-        //         ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.to)?)?
-        //     }
-        // };
+        let gateway_addr = match gateway_addr {
+            None => subnet_config.gateway_addr(),
+            Some(addr) => addr,
+        };
 
-        conn.manager().send_value(sender, to, amount).await
+        let to = to.unwrap_or(sender);
+
+        conn.manager()
+            .unsigned_release(gateway_addr, sender, to, amount)
+            .await
     }
 
-    /// Get the balance of an address
-    pub async fn wallet_balance(
+    /// Broadcasts a transaction signed out of band, e.g. the signed [`UnsignedTransaction`] of
+    /// [`IpcProvider::unsigned_fund`], to the subnet and waits for it to be executed.
+    pub async fn broadcast_raw(
         &self,
         subnet: &SubnetID,
-        address: &Address,
-    ) -> anyhow::Result<TokenAmount> {
+        raw_tx: Vec<u8>,
+    ) -> anyhow::Result<ChainEpoch> {
         let conn = match self.connection(subnet) {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
 
-        conn.manager().wallet_balance(address).await
+        conn.manager().broadcast_raw(raw_tx).await
     }
 
-    pub async fn chain_head(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(subnet) {
+    /// Propagate a cross-net message forward. For `postbox_msg_key`, we are using bytes because different
+    /// runtime have different representations. For FVM, it should be `CID` as bytes. For EVM, it is
+    /// `bytes32`.
+    pub async fn propagate(
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
+    ) -> anyhow::Result<()> {
+        let conn = match self.connection(&subnet) {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
 
-        conn.manager().chain_head_height().await
-    }
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
 
-    /// Obtain the genesis epoch of the input subnet.
-    pub async fn genesis_epoch(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
-        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
-            None => return Err(anyhow!("parent subnet config not found")),
-            Some(conn) => conn,
+        let gateway_addr = match gateway_addr {
+            None => subnet_config.gateway_addr(),
+            Some(addr) => addr,
         };
-        conn.manager().genesis_epoch(subnet).await
+
+        conn.manager()
+            .propagate(subnet, gateway_addr, sender, postbox_msg_key, fee)
+            .await
     }
 
-    /// Get the validator information.
-    pub async fn get_validator_info(
+    /// Propagates the messages with `postbox_msg_keys` in the postbox of `subnet`, paying `fee`
+    /// for each of them, and returns the outcome of each propagation in their order. The
+    /// failure of a propagation does not stop the next ones.
+    pub async fn propagate_postbox_msgs(
+        &mut self,
+        subnet: &SubnetID,
+        from: Option<Address>,
+        postbox_msg_keys: Vec<Vec<u8>>,
+        fee: TokenAmount,
+    ) -> Vec<(Vec<u8>, anyhow::Result<()>)> {
+        let mut outcomes = Vec::with_capacity(postbox_msg_keys.len());
+        for key in postbox_msg_keys {
+            let outcome = self
+                .propagate(subnet.clone(), None, from, key.clone(), fee.clone())
+                .await;
+            outcomes.push((key, outcome));
+        }
+        outcomes
+    }
+
+    /// The messages which arrived in `subnet` from `source`, its parent or one of its children,
+    /// between the `from` and `to` heights of `source`, and are still in the postbox of the
+    /// gateway of `subnet`, waiting to be propagated with [`IpcProvider::propagate`].
+    pub async fn postbox_msgs(
         &self,
         subnet: &SubnetID,
-        validator: &Address,
-    ) -> anyhow::Result<ValidatorInfo> {
-        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
-            None => return Err(anyhow!("target subnet parent not found")),
+        source: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<Vec<postbox::PostboxMsg>> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
 
-        conn.manager().get_validator_info(subnet, validator).await
+        match postbox::neighbour(subnet, source)? {
+            postbox::Neighbour::Child => {
+                postbox::from_child(conn.manager(), source, from, to).await
+            }
+            postbox::Neighbour::Parent => {
+                let parent_conn = self
+                    .connection(source)
+                    .ok_or_else(|| anyhow!("parent subnet not found"))?;
+                postbox::from_parent(parent_conn.manager(), conn.manager(), subnet, from, to).await
+            }
+        }
+    }
+
+    /// The transaction of [`IpcProvider::propagate`], to sign out of band.
+    pub async fn unsigned_propagate(
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
+    ) -> anyhow::Result<UnsignedTransaction> {
+        let conn = match self.connection(&subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
+
+        let gateway_addr = match gateway_addr {
+            None => subnet_config.gateway_addr(),
+            Some(addr) => addr,
+        };
+
+        conn.manager()
+            .unsigned_propagate(gateway_addr, sender, postbox_msg_key, fee)
+            .await
+    }
+
+    /// The cross-messages of the checkpoints of `subnet` committed in its parent which are
+    /// still waiting in the postbox of the parent, from the checkpoint at child height `from`
+    /// up to the ones older than `timeout`, at the block time of the child. Each of them can
+    /// be propagated with [`IpcProvider::propagate`] in the parent.
+    pub async fn stuck_cross_msgs(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<checkpoint::replay::StuckMsg>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = self
+            .connection(&parent)
+            .ok_or_else(|| anyhow!("parent subnet not found"))?;
+        let child_conn = self
+            .connection(subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        let block_time = self.block_time(subnet).await?;
+        let head = self.head_height(subnet, child_conn.manager()).await?;
+        let to = head.saturating_sub(block_time.epochs_in(timeout));
+
+        checkpoint::replay::find_stuck_msgs(parent_conn.manager(), subnet, from, to).await
+    }
+
+    /// Send value between two addresses in a subnet
+    pub async fn send_value(
+        &mut self,
+        subnet: &SubnetID,
+        from: Option<Address>,
+        to: Address,
+        amount: TokenAmount,
+    ) -> anyhow::Result<()> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
+
+        // FIXME: This limits that only value to f-addresses can be sent
+        // with the provider (which requires translating eth-addresses into
+        // their corresponding delegated address). This should be fixed with the
+        // new address wrapper type planned: https://github.com/consensus-shipyard/ipc-agent/issues/263
+        // let to = match Address::from_str(&request.to) {
+        //     Ok(addr) => addr,
+        //     Err(_) => {
+        //         // we need to check if an 0x address was passed and convert
+        //         // to a delegated address
+        //         ethers_address_to_fil_address(&ethers::types::Address::from_str(&request.to)?)?
+        //     }
+        // };
+
+        conn.manager().send_value(sender, to, amount).await
+    }
+
+    /// Get the balance of an address
+    pub async fn wallet_balance(
+        &self,
+        subnet: &SubnetID,
+        address: &Address,
+    ) -> anyhow::Result<TokenAmount> {
+        self.wallet_balance_at(subnet, address, None).await
+    }
+
+    /// Get the balance of an address, at `at_height` if set.
+    pub async fn wallet_balance_at(
+        &self,
+        subnet: &SubnetID,
+        address: &Address,
+        at_height: Option<ChainEpoch>,
+    ) -> anyhow::Result<TokenAmount> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager().wallet_balance(address, at_height).await
+    }
+
+    /// Get the balances of several addresses, read at once if the subnet supports it.
+    pub async fn wallet_balances(
+        &self,
+        subnet: &SubnetID,
+        addresses: &[Address],
+    ) -> anyhow::Result<Vec<TokenAmount>> {
+        self.wallet_balances_at(subnet, addresses, None).await
+    }
+
+    /// Get the balances of several addresses, read at once if the subnet supports it, at
+    /// `at_height` if set.
+    pub async fn wallet_balances_at(
+        &self,
+        subnet: &SubnetID,
+        addresses: &[Address],
+        at_height: Option<ChainEpoch>,
+    ) -> anyhow::Result<Vec<TokenAmount>> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager().wallet_balances(addresses, at_height).await
+    }
+
+    /// The balances of `address` in every configured subnet, queried concurrently, with their
+    /// totals by denomination. The subnets that cannot be queried are reported apart.
+    pub async fn portfolio(&self, address: &Address) -> Portfolio {
+        let queries = self.config.subnets.keys().map(|subnet| async move {
+            let balance = async {
+                Ok::<_, anyhow::Error>(SubnetBalance {
+                    subnet: subnet.to_string(),
+                    denomination: self.denomination(subnet).await?,
+                    balance: self.wallet_balance(subnet, address).await?,
+                })
+            };
+            balance.await.map_err(|e| SubnetBalanceError {
+                subnet: subnet.to_string(),
+                error: e.to_string(),
+            })
+        });
+
+        let (mut balances, mut errors) = (vec![], vec![]);
+        for result in join_all(queries).await {
+            match result {
+                Ok(balance) => balances.push(balance),
+                Err(error) => errors.push(error),
+            }
+        }
+        Portfolio::new(address.to_string(), balances, errors)
+    }
+
+    /// The token the balances of `subnet` are denominated in, from the supply sources of the
+    /// subnet and of its ancestors, the nearest ERC20 one winning over the root coin.
+    async fn denomination(&self, subnet: &SubnetID) -> anyhow::Result<Denomination> {
+        let mut current = subnet.clone();
+        while let Some(parent) = current.parent() {
+            let supply = self.subnet_params(&current).await?.supply_source;
+            if let (SupplyKind::ERC20, Some(token)) = (supply.kind, supply.token_address) {
+                return Ok(Denomination::Erc20 {
+                    subnet: parent.to_string(),
+                    token: token.to_string(),
+                });
+            }
+            current = parent;
+        }
+        Ok(Denomination::Native {
+            root: current.to_string(),
+        })
+    }
+
+    /// The allowances over the ERC20 tokens supplying the configured subnets that `owner`
+    /// granted to the IPC contracts of their parents, e.g. left over from the deposits to the
+    /// subnets. The allowances spent or revoked are left out.
+    pub async fn list_approvals(&self, owner: &Address) -> anyhow::Result<TokenApprovals> {
+        let mut tokens = HashSet::new();
+        for subnet in self.config.subnets.keys() {
+            let Some(parent) = subnet.parent() else {
+                continue;
+            };
+            if !self.config.subnets.contains_key(&parent) {
+                continue;
+            }
+            let supply = self.subnet_params(subnet).await?.supply_source;
+            if let (SupplyKind::ERC20, Some(token)) = (supply.kind, supply.token_address) {
+                tokens.insert((parent, token));
+            }
+        }
+
+        let mut approvals = vec![];
+        for (parent, token) in tokens {
+            let conn = self
+                .connection(&parent)
+                .ok_or_else(|| anyhow!("target subnet not found"))?;
+            for contract in IpcContract::ALL {
+                let spender = contract.address(conn.subnet());
+                let allowance = conn
+                    .manager()
+                    .token_allowance(&token, owner, &spender)
+                    .await?;
+                approvals.push(TokenApproval {
+                    subnet: parent.to_string(),
+                    token: token.to_string(),
+                    contract,
+                    spender: spender.to_string(),
+                    allowance,
+                });
+            }
+        }
+        Ok(TokenApprovals::new(owner.to_string(), approvals))
+    }
+
+    /// Revokes the allowance over the ERC20 `token` that `from` granted to `spender`, which
+    /// must be one of the IPC contracts of `subnet`. Returns the epoch the revocation is
+    /// executed at.
+    pub async fn revoke_approval(
+        &mut self,
+        subnet: &SubnetID,
+        from: Option<Address>,
+        token: &Address,
+        spender: &Address,
+    ) -> anyhow::Result<ChainEpoch> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+        if IpcContract::of(conn.subnet(), spender).is_none() {
+            return Err(anyhow!(
+                "{spender} is not an IPC contract of subnet {subnet}"
+            ));
+        }
+
+        let sender = self.check_sender(conn.subnet(), from)?;
+        conn.manager()
+            .revoke_token_approval(token, sender, spender)
+            .await
+    }
+
+    pub async fn chain_head(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        self.head_height(subnet, conn.manager()).await
+    }
+
+    /// The block time of `subnet`, the one of its config if set, measured on its last
+    /// [`epoch::DEFAULT_BLOCK_TIME_WINDOW`] blocks otherwise.
+    pub async fn block_time(&self, subnet: &SubnetID) -> anyhow::Result<BlockTime> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        match conn.subnet().block_time() {
+            Some(block_time) => BlockTime::new(block_time),
+            None => {
+                conn.manager()
+                    .measure_block_time(epoch::DEFAULT_BLOCK_TIME_WINDOW)
+                    .await
+            }
+        }
+    }
+
+    /// The estimated time until the next bottom-up checkpoint of `subnet` is due, at its block
+    /// time, along with the height of the checkpoint.
+    pub async fn time_until_next_checkpoint(
+        &self,
+        subnet: &SubnetID,
+    ) -> anyhow::Result<(ChainEpoch, Duration)> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = match self.connection(&parent) {
+            None => return Err(anyhow!("parent subnet config not found")),
+            Some(conn) => conn,
+        };
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let period = parent_conn
+            .manager()
+            .subnet_params(subnet)
+            .await?
+            .checkpoint_period;
+        let height = self.head_height(subnet, conn.manager()).await?;
+        let next = epoch::next_checkpoint_height(height, period);
+        let block_time = self.block_time(subnet).await?;
+        Ok((next, block_time.until(height, next)))
+    }
+
+    /// The countdown to the next bottom-up checkpoint of `subnet`, from its period and its last
+    /// checkpoint committed in the parent, its current height and its block time.
+    pub async fn next_checkpoint_eta(&self, subnet: &SubnetID) -> anyhow::Result<CheckpointEta> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = match self.connection(&parent) {
+            None => return Err(anyhow!("parent subnet config not found")),
+            Some(conn) => conn,
+        };
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let status = parent_conn.manager().checkpoint_status(subnet).await?;
+        let height = self.head_height(subnet, conn.manager()).await?;
+        let block_time = self.block_time(subnet).await?;
+        Ok(CheckpointEta::new(status, height, block_time))
+    }
+
+    /// Obtain the genesis epoch of the input subnet.
+    pub async fn genesis_epoch(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("parent subnet config not found")),
+            Some(conn) => conn,
+        };
+        conn.manager().genesis_epoch(subnet).await
+    }
+
+    /// The staking position of `validator` in `subnet`: its collateral, the changes of it
+    /// waiting for the confirmation of the subnet, and the releases of it logged since the
+    /// genesis of the subnet.
+    pub async fn validator_position(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> anyhow::Result<ValidatorPosition> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        let genesis_epoch = conn.manager().genesis_epoch(subnet).await?;
+        conn.manager()
+            .validator_position(subnet, validator, genesis_epoch)
+            .await
+    }
+
+    /// Get the validator information.
+    pub async fn get_validator_info(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> anyhow::Result<ValidatorInfo> {
+        self.get_validator_info_at(subnet, validator, None).await
+    }
+
+    /// Get the validator information, at `at_height` of the parent if set.
+    pub async fn get_validator_info_at(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+        at_height: Option<ChainEpoch>,
+    ) -> anyhow::Result<ValidatorInfo> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager()
+            .get_validator_info(subnet, validator, at_height)
+            .await
+    }
+
+    /// The active validators of a subnet with their information, at `at_height` of the parent
+    /// if set.
+    pub async fn validator_set(
+        &self,
+        subnet: &SubnetID,
+        at_height: Option<ChainEpoch>,
+    ) -> anyhow::Result<Vec<(Address, ValidatorInfo)>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        let validators = conn.manager().active_validators(subnet, at_height).await?;
+        let mut set = Vec::with_capacity(validators.len());
+        for validator in validators {
+            let info = conn
+                .manager()
+                .get_validator_info(subnet, &validator, at_height)
+                .await?;
+            set.push((validator, info));
+        }
+        Ok(set)
+    }
+
+    /// Get the parameters of a subnet that do not change after its creation. They are cached
+    /// for all the connections of the provider.
+    pub async fn subnet_params(&self, subnet: &SubnetID) -> anyhow::Result<SubnetParams> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager().subnet_params(subnet).await
+    }
+
+    /// Drops the cached parameters of `subnet`, or of all the subnets if `None`.
+    pub fn invalidate_subnet_params(&self, subnet: Option<&SubnetID>) {
+        self.subnet_params.invalidate(subnet);
     }
 
     /// Get the changes in subnet validators. This is fetched from parent.
@@ -622,6 +1675,32 @@ impl IpcProvider {
         conn.manager().get_validator_changeset(subnet, epoch).await
     }
 
+    /// A page of the validator changes of `subnet` committed in the parent at the `heights`,
+    /// only of `validator` if set.
+    pub async fn list_validator_changes(
+        &self,
+        subnet: &SubnetID,
+        heights: HeightRange,
+        validator: Option<Address>,
+        page: &PageRequest,
+    ) -> anyhow::Result<Page<(ChainEpoch, StakingChangeRequest)>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        let manager = conn.manager();
+        let head = self.head_height(&parent, manager).await?;
+        pagination::scan_heights(
+            heights.resolve(head),
+            page,
+            |c: &StakingChangeRequest| validator.map_or(true, |v| c.change.validator == v),
+            |h| async move { Ok(manager.get_validator_changeset(subnet, h).await?.value) },
+        )
+        .await
+    }
+
     /// Get genesis info for a child subnet. This can be used to deterministically
     /// generate the genesis of the subnet
     pub async fn get_genesis_info(&self, subnet: &SubnetID) -> anyhow::Result<SubnetGenesisInfo> {
@@ -647,6 +1726,36 @@ impl IpcProvider {
         conn.manager().get_top_down_msgs(subnet, epoch).await
     }
 
+    /// A page of the top down messages of `subnet` committed in the parent at the `heights`,
+    /// only from or to `address` if set.
+    pub async fn list_top_down_msgs(
+        &self,
+        subnet: &SubnetID,
+        heights: HeightRange,
+        address: Option<Address>,
+        page: &PageRequest,
+    ) -> anyhow::Result<Page<(ChainEpoch, IpcEnvelope)>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let manager = conn.manager();
+        let head = self.head_height(&parent, manager).await?;
+        pagination::scan_heights(
+            heights.resolve(head),
+            page,
+            |m: &IpcEnvelope| {
+                address.map_or(true, |a| {
+                    m.from.raw_addr().is_ok_and(|f| f == a) || m.to.raw_addr().is_ok_and(|t| t == a)
+                })
+            },
+            |h| async move { Ok(manager.get_top_down_msgs(subnet, h).await?.value) },
+        )
+        .await
+    }
+
     pub async fn get_block_hash(
         &self,
         subnet: &SubnetID,
@@ -678,13 +1787,125 @@ impl IpcProvider {
         conn.manager().get_commit_sha().await
     }
 
+    /// Verifies the code of the gateway and the registry of `subnet`, with their facets, and of
+    /// its subnet actor in its parent if the parent is configured, against the code of the known
+    /// `releases`. The unknown and outdated contracts are logged as warnings.
+    pub async fn verify_contracts(
+        &self,
+        subnet: &SubnetID,
+        releases: &KnownReleases,
+    ) -> anyhow::Result<ContractsReport> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let parent_conn = subnet.parent().and_then(|p| self.connection(&p));
+        let mut diamonds = vec![
+            (ContractRole::Gateway, conn.subnet().gateway_addr(), &conn),
+            (ContractRole::Registry, conn.subnet().registry_addr(), &conn),
+        ];
+        if let Some(parent_conn) = &parent_conn {
+            diamonds.push((
+                ContractRole::SubnetActor,
+                subnet.subnet_actor(),
+                parent_conn,
+            ));
+        }
+
+        let mut report = ContractsReport::default();
+        for (role, address, conn) in diamonds {
+            let code = conn
+                .manager()
+                .diamond_code(&address)
+                .await
+                .map_err(|e| anyhow!("cannot get the code of the {role} at {address}: {e}"))?;
+            report
+                .checks
+                .push(ContractCheck::new(role, false, &code.diamond, releases));
+            report.checks.extend(
+                code.facets
+                    .iter()
+                    .map(|facet| ContractCheck::new(role, true, facet, releases)),
+            );
+        }
+
+        for check in report.warnings() {
+            log::warn!("contract of subnet {subnet}: {check}");
+        }
+        Ok(report)
+    }
+
+    /// The facets installed in the diamond at `diamond` in `subnet`, e.g. its gateway.
+    pub async fn diamond_facets(
+        &self,
+        subnet: &SubnetID,
+        diamond: &Address,
+    ) -> anyhow::Result<Vec<Facet>> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager().diamond_facets(diamond).await
+    }
+
+    /// Stages the upgrade of the diamond at `diamond` in `subnet` to the `target` facets, see
+    /// [`UpgradePlan::new`]. Nothing is sent, the plan is to review before executing it.
+    pub async fn stage_facet_upgrade(
+        &self,
+        subnet: &SubnetID,
+        diamond: &Address,
+        target: &[Facet],
+        remove_missing: bool,
+    ) -> anyhow::Result<UpgradePlan> {
+        let installed = self.diamond_facets(subnet, diamond).await?;
+        Ok(UpgradePlan::new(installed, target, remove_missing))
+    }
+
+    /// Executes the staged upgrade `plan` of the diamond at `diamond` in `subnet` from `from`,
+    /// its owner. Fails without sending anything if the installed facets changed since the
+    /// upgrade was staged. Returns the epoch the upgrade is executed at, none if there was
+    /// nothing to upgrade.
+    pub async fn execute_facet_upgrade(
+        &mut self,
+        subnet: &SubnetID,
+        from: Option<Address>,
+        diamond: &Address,
+        plan: &UpgradePlan,
+    ) -> anyhow::Result<Option<ChainEpoch>> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
+
+        if conn.manager().diamond_facets(diamond).await? != plan.installed {
+            return Err(anyhow!(
+                "the facets of diamond {diamond} changed since the upgrade was staged"
+            ));
+        }
+        if plan.is_empty() {
+            return Ok(None);
+        }
+        log::info!("upgrading the facets of diamond {diamond}:\n{plan}");
+
+        let epoch = conn
+            .manager()
+            .diamond_cut(sender, diamond, plan.cuts.clone())
+            .await?;
+        Ok(Some(epoch))
+    }
+
     pub async fn get_chain_head_height(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
         let conn = match self.connection(subnet) {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
 
-        conn.manager().chain_head_height().await
+        self.head_height(subnet, conn.manager()).await
     }
 
     pub async fn get_bottom_up_bundle(
@@ -715,6 +1936,64 @@ impl IpcProvider {
             .await
     }
 
+    /// Inspects the state of the bottom-up checkpoints of `subnet`: the checkpoints committed
+    /// in the parent, the quorums reached in the child since then and, if given, the balance
+    /// and nonce of the `submitter` in the parent.
+    pub async fn inspect_checkpoints(
+        &self,
+        subnet: &SubnetID,
+        submitter: Option<&Address>,
+        scan_limit: ChainEpoch,
+    ) -> anyhow::Result<checkpoint::inspect::RelayerInspection> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = self
+            .connection(&parent)
+            .ok_or_else(|| anyhow!("parent subnet not found"))?;
+        let child_conn = self
+            .connection(subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        checkpoint::inspect::inspect(
+            parent_conn.manager(),
+            child_conn.manager(),
+            subnet,
+            submitter,
+            scan_limit,
+        )
+        .await
+    }
+
+    /// Subscribes to the lifecycle events of `subnet` from now on, merged from the logs of its
+    /// parent and its own, polling them every `poll_interval`, or
+    /// [`events::DEFAULT_EVENTS_POLL_INTERVAL`] if not set. The heads of the subnets are read
+    /// from their trackers, see [`Self::chain_head_tracker`].
+    pub fn subscribe_subnet_events(
+        &self,
+        subnet: &SubnetID,
+        poll_interval: Option<Duration>,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<events::SubnetEventRecord>>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent_conn = self
+            .connection(&parent)
+            .ok_or_else(|| anyhow!("parent subnet not found"))?;
+        let child_conn = self
+            .connection(subnet)
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+
+        let heads = (
+            self.chain_head_tracker(&parent)?,
+            self.chain_head_tracker(subnet)?,
+        );
+
+        Ok(events::subscribe_tracked(
+            parent_conn.manager,
+            child_conn.manager,
+            Some(heads),
+            subnet.clone(),
+            poll_interval.unwrap_or(events::DEFAULT_EVENTS_POLL_INTERVAL),
+        ))
+    }
+
     pub async fn quorum_reached_events(
         &self,
         subnet: &SubnetID,
@@ -728,6 +2007,29 @@ impl IpcProvider {
         conn.manager().quorum_reached_events(height).await
     }
 
+    /// A page of the quorum reached events of `subnet` at the `heights`.
+    pub async fn list_quorum_reached_events(
+        &self,
+        subnet: &SubnetID,
+        heights: HeightRange,
+        page: &PageRequest,
+    ) -> anyhow::Result<Page<(ChainEpoch, QuorumReachedEvent)>> {
+        let conn = match self.connection(subnet) {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let manager = conn.manager();
+        let head = self.head_height(subnet, manager).await?;
+        pagination::scan_heights(
+            heights.resolve(head),
+            page,
+            |_| true,
+            |h| async move { manager.quorum_reached_events(h).await },
+        )
+        .await
+    }
+
     /// Advertises the endpoint of a bootstrap node for the subnet.
     pub async fn add_bootstrap(
         &mut self,
@@ -760,6 +2062,33 @@ impl IpcProvider {
         conn.manager().list_bootstrap_nodes(subnet).await
     }
 
+    /// How a new validator of `subnet` bootstraps its node: the genesis and bootstrap nodes of
+    /// the subnet, and the snapshots offered by the child node behind the CometBFT RPC `node`
+    /// to state sync from.
+    pub async fn bootstrap_info(
+        &self,
+        subnet: &SubnetID,
+        node: &url::Url,
+    ) -> anyhow::Result<SubnetBootstrapInfo> {
+        let genesis_epoch = self.genesis_epoch(subnet).await?;
+        let bootstrap_nodes = self.list_bootstrap_nodes(subnet).await?;
+
+        let client = jsonrpc::JsonRpcClientImpl::new(node.clone(), None);
+        let snapshots = bootstrap::query_snapshot_offers(&client).await?;
+        let trust = match snapshots.iter().map(|s| s.block_height).max() {
+            Some(height) => Some(bootstrap::query_state_sync_trust(&client, node, height).await?),
+            None => None,
+        };
+
+        Ok(SubnetBootstrapInfo {
+            subnet: subnet.to_string(),
+            genesis_epoch,
+            bootstrap_nodes,
+            snapshots,
+            trust,
+        })
+    }
+
     /// Returns the latest finality from the parent committed in a child subnet.
     pub async fn latest_parent_finality(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
         let conn = match self.connection(subnet) {
@@ -787,16 +2116,72 @@ impl IpcProvider {
             .set_federated_power(from, subnet, validators, public_keys, federated_power)
             .await
     }
+
+    /// The relayers allowed to submit the checkpoints of `subnet` to its parent, or `None` if
+    /// the subnet does not restrict them.
+    pub async fn relayer_allowlist(
+        &self,
+        subnet: &SubnetID,
+    ) -> anyhow::Result<Option<Vec<Address>>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+        conn.manager().relayer_allowlist(subnet).await
+    }
+
+    /// Whether `relayer` can submit the checkpoints of `subnet` to its parent.
+    pub async fn is_relayer_allowed(
+        &self,
+        subnet: &SubnetID,
+        relayer: &Address,
+    ) -> anyhow::Result<bool> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+        conn.manager().is_relayer_allowed(subnet, relayer).await
+    }
+
+    /// Allows `relayer` to submit the checkpoints of the permissioned `subnet`, or revokes it,
+    /// signed by `from`, the owner of the subnet.
+    pub async fn set_relayer_allowed(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        relayer: &Address,
+        allowed: bool,
+    ) -> anyhow::Result<ChainEpoch> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent) {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+        conn.manager()
+            .set_relayer_allowed(from, subnet, relayer, allowed)
+            .await
+    }
 }
 
 /// Lotus JSON keytype format
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct LotusJsonKeyType {
     pub r#type: String,
     pub private_key: String,
 }
 
+impl std::fmt::Debug for LotusJsonKeyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LotusJsonKeyType")
+            .field("type", &self.r#type)
+            .field("private_key", &"<redacted>")
+            .finish()
+    }
+}
+
 impl FromStr for LotusJsonKeyType {
     type Err = anyhow::Error;
 
@@ -859,18 +2244,13 @@ impl IpcProvider {
         let keystore = self.evm_wallet()?;
         let mut keystore = keystore.write().unwrap();
 
-        let private_key = if !private_key.starts_with("0x") {
-            hex::decode(private_key)?
-        } else {
-            hex::decode(&private_key[2..])?
-        };
-        keystore.put(ipc_wallet::EvmKeyInfo::new(private_key))
+        let private_key = private_key.strip_prefix("0x").unwrap_or(private_key);
+        keystore.put(ipc_wallet::EvmKeyInfo::new(hex::decode(private_key)?))
     }
 
     pub fn import_evm_key_from_json(&self, keyinfo: &str) -> anyhow::Result<EthKeyAddress> {
         let persisted: ipc_wallet::PersistentKeyInfo = serde_json::from_str(keyinfo)?;
-        let persisted: String = persisted.private_key().parse()?;
-        self.import_evm_key_from_privkey(&persisted)
+        self.import_evm_key_from_privkey(persisted.private_key())
     }
 }
 
@@ -904,6 +2284,21 @@ pub fn new_evm_keystore_from_path(
     PersistentKeyStore::new(repo).map_err(|e| anyhow!("Failed to create evm keystore: {}", e))
 }
 
+fn new_address_book_from_config(config: Arc<Config>) -> anyhow::Result<AddressBook> {
+    let repo_str = &config.keystore_path;
+    if let Some(repo_str) = repo_str {
+        new_address_book_from_path(repo_str)
+    } else {
+        Err(anyhow!("No keystore repo found in config"))
+    }
+}
+
+pub fn new_address_book_from_path(repo_str: &str) -> anyhow::Result<AddressBook> {
+    let path = Path::new(&repo_str).join(ipc_wallet::DEFAULT_ADDRESS_BOOK_NAME);
+    let path = expand_tilde(path);
+    AddressBook::new(path).map_err(|e| anyhow!("Failed to open address book: {}", e))
+}
+
 pub fn new_fvm_keystore_from_path(repo_str: &str) -> anyhow::Result<KeyStore> {
     let repo = Path::new(&repo_str);
     let repo = expand_tilde(repo);
@@ -0,0 +1,229 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Liveness of the validators of a child subnet, from the proposers of its recent blocks.
+//!
+//! The tracker samples the blocks of the child as they are produced and attributes each of
+//! them to its proposer. The uptime of a validator over a window of blocks is the number and
+//! share of the blocks of the window it proposed, along with the last height it proposed at.
+//! A validator that proposed no block in the window is missing from the uptimes.
+
+use crate::head::ChainHeadTracker;
+use crate::manager::ChildGatewayClient;
+use crate::monitor;
+use anyhow::Result;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The blocks proposed by a validator over a window of blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorUptime {
+    pub proposed: u64,
+    /// The share of the blocks of the window proposed by the validator, between 0 and 1.
+    pub share: f64,
+    pub last_proposed: ChainEpoch,
+}
+
+/// The proposers of the most recent sampled blocks.
+#[derive(Debug)]
+struct ProposerWindow {
+    /// The number of blocks kept.
+    capacity: usize,
+    proposers: BTreeMap<ChainEpoch, Address>,
+}
+
+impl ProposerWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            proposers: BTreeMap::new(),
+        }
+    }
+
+    fn last_height(&self) -> Option<ChainEpoch> {
+        self.proposers.keys().next_back().copied()
+    }
+
+    fn record(&mut self, height: ChainEpoch, proposer: Address) {
+        self.proposers.insert(height, proposer);
+        while self.proposers.len() > self.capacity {
+            self.proposers.pop_first();
+        }
+    }
+
+    /// The uptime of the validators over the last `window` blocks up to the last sampled one.
+    fn uptime(&self, window: ChainEpoch) -> HashMap<Address, ValidatorUptime> {
+        let Some(last) = self.last_height() else {
+            return HashMap::new();
+        };
+        let blocks = self.proposers.range(last - window.max(1) + 1..);
+
+        let mut uptimes: HashMap<Address, ValidatorUptime> = HashMap::new();
+        let mut total = 0;
+        for (height, proposer) in blocks {
+            total += 1;
+            let uptime = uptimes.entry(*proposer).or_insert(ValidatorUptime {
+                proposed: 0,
+                share: 0.0,
+                last_proposed: *height,
+            });
+            uptime.proposed += 1;
+            uptime.last_proposed = *height;
+        }
+        for uptime in uptimes.values_mut() {
+            uptime.share = uptime.proposed as f64 / total as f64;
+        }
+        uptimes
+    }
+}
+
+/// Samples the blocks of a child subnet to track the liveness of its validators.
+pub struct LivenessTracker<C> {
+    child: C,
+    /// The value of the `subnet` label of the metrics.
+    label: String,
+    window: Mutex<ProposerWindow>,
+    /// The validators that proposed any sampled block.
+    validators: Mutex<HashSet<Address>>,
+    /// The head of the child shared with the other components following it, if any.
+    head: Option<ChainHeadTracker>,
+}
+
+impl<C: ChildGatewayClient> LivenessTracker<C> {
+    /// Tracks the proposers of the last `capacity` blocks of `child`, the largest window the
+    /// uptime can be computed over.
+    pub fn new(child: C, label: impl Into<String>, capacity: usize) -> Self {
+        Self {
+            child,
+            label: label.into(),
+            window: Mutex::new(ProposerWindow::new(capacity)),
+            validators: Mutex::new(HashSet::new()),
+            head: None,
+        }
+    }
+
+    /// Reads the head of the child from `tracker`, instead of querying it on every sample.
+    pub fn with_head(mut self, tracker: ChainHeadTracker) -> Self {
+        self.head = Some(tracker);
+        self
+    }
+
+    /// Samples the blocks produced since the last sample, up to the current head of the child,
+    /// returning the number of blocks sampled. The first sample goes back `capacity` blocks.
+    pub async fn sample(&self) -> Result<usize> {
+        let head = match &self.head {
+            Some(tracker) => tracker.latest().await?,
+            None => self.child.current_epoch().await?,
+        };
+        let (last, capacity) = {
+            let window = self.window.lock().unwrap();
+            (window.last_height(), window.capacity as ChainEpoch)
+        };
+        // The genesis block has no proposer.
+        let from = last.map_or(1, |h| h + 1).max(head - capacity + 1);
+
+        let mut sampled = 0;
+        for height in from..=head {
+            let proposer = self.child.block_proposer_at(height).await?;
+            self.record(height, proposer);
+            sampled += 1;
+        }
+        if sampled > 0 {
+            self.update_share_metrics();
+        }
+        Ok(sampled)
+    }
+
+    /// The uptime of the validators that proposed a block within the last `window` sampled
+    /// blocks.
+    pub fn validator_uptime(&self, window: ChainEpoch) -> HashMap<Address, ValidatorUptime> {
+        self.window.lock().unwrap().uptime(window)
+    }
+
+    /// Samples the child every `poll_interval` in the foreground.
+    pub async fn run(self, poll_interval: Duration) {
+        log::info!("launching validator liveness tracker of {}", self.label);
+
+        loop {
+            match self.sample().await {
+                Ok(sampled) => log::debug!("sampled {sampled} blocks of {}", self.label),
+                Err(e) => log::error!("cannot sample blocks of {}: {e}", self.label),
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    fn record(&self, height: ChainEpoch, proposer: Address) {
+        self.window.lock().unwrap().record(height, proposer);
+        self.validators.lock().unwrap().insert(proposer);
+
+        let validator = proposer.to_string();
+        monitor::VALIDATOR_PROPOSED_BLOCKS
+            .with_label_values(&[&self.label, &validator])
+            .inc();
+        monitor::VALIDATOR_LAST_PROPOSED_HEIGHT
+            .with_label_values(&[&self.label, &validator])
+            .set(height);
+    }
+
+    fn update_share_metrics(&self) {
+        let uptimes = {
+            let window = self.window.lock().unwrap();
+            window.uptime(window.capacity as ChainEpoch)
+        };
+        // The validators that are not in the window anymore proposed nothing recently.
+        for validator in self.validators.lock().unwrap().iter() {
+            monitor::VALIDATOR_PROPOSAL_SHARE
+                .with_label_values(&[&self.label, &validator.to_string()])
+                .set(uptimes.get(validator).map_or(0.0, |u| u.share));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProposerWindow;
+    use fvm_shared::address::Address;
+
+    #[test]
+    fn test_uptime_over_window() {
+        let (a, b) = (Address::new_id(1), Address::new_id(2));
+        let mut window = ProposerWindow::new(10);
+        for h in 1..=8 {
+            window.record(h, if h % 4 == 0 { b } else { a });
+        }
+
+        let uptime = window.uptime(8);
+        assert_eq!(uptime[&a].proposed, 6);
+        assert_eq!(uptime[&b].proposed, 2);
+        assert_eq!(uptime[&b].share, 0.25);
+        assert_eq!(uptime[&a].last_proposed, 7);
+        assert_eq!(uptime[&b].last_proposed, 8);
+
+        // only the heights 7 and 8 are in the window
+        let uptime = window.uptime(2);
+        assert_eq!(uptime[&a].proposed, 1);
+        assert_eq!(uptime[&a].share, 0.5);
+
+        assert!(ProposerWindow::new(10).uptime(5).is_empty());
+    }
+
+    #[test]
+    fn test_window_keeps_recent_blocks() {
+        let (a, b) = (Address::new_id(1), Address::new_id(2));
+        let mut window = ProposerWindow::new(3);
+        window.record(1, a);
+        for h in 2..=4 {
+            window.record(h, b);
+        }
+
+        assert_eq!(window.last_height(), Some(4));
+        let uptime = window.uptime(100);
+        assert!(!uptime.contains_key(&a));
+        assert_eq!(uptime[&b].proposed, 3);
+        assert_eq!(uptime[&b].share, 1.0);
+    }
+}
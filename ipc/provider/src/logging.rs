@@ -0,0 +1,167 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Log levels per component of the provider and the relayer.
+//!
+//! A component is a set of log targets, so that e.g. the per-height messages of the scanning
+//! can be silenced during a catch-up while the submissions are still logged. The levels are
+//! turned into the filter directives of the logger, see [`LogLevels::directives`].
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub use log::LevelFilter;
+
+/// The target of the logs of the scanning of the child for checkpoints to submit.
+pub const SCANNER_TARGET: &str = "ipc_provider::checkpoint::scanner";
+/// The target of the logs of the submissions of the checkpoints to the parent.
+pub const SUBMITTER_TARGET: &str = "ipc_provider::checkpoint::submitter";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogComponent {
+    Scanner,
+    Submitter,
+    /// The calls to the subnets.
+    Rpc,
+    /// The keystores and signers.
+    Wallet,
+}
+
+impl LogComponent {
+    /// The log targets of the component, which are prefixes of the targets it logs with.
+    pub fn targets(&self) -> &'static [&'static str] {
+        match self {
+            LogComponent::Scanner => &[SCANNER_TARGET],
+            LogComponent::Submitter => &[SUBMITTER_TARGET],
+            LogComponent::Rpc => &[
+                "ipc_provider::jsonrpc",
+                "ipc_provider::manager",
+                "ipc_provider::lotus",
+                "ethers_providers",
+            ],
+            LogComponent::Wallet => &["ipc_wallet", "ipc_provider::key_source"],
+        }
+    }
+}
+
+impl Display for LogComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogComponent::Scanner => write!(f, "scanner"),
+            LogComponent::Submitter => write!(f, "submitter"),
+            LogComponent::Rpc => write!(f, "rpc"),
+            LogComponent::Wallet => write!(f, "wallet"),
+        }
+    }
+}
+
+impl FromStr for LogComponent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "scanner" => Ok(LogComponent::Scanner),
+            "submitter" => Ok(LogComponent::Submitter),
+            "rpc" => Ok(LogComponent::Rpc),
+            "wallet" => Ok(LogComponent::Wallet),
+            _ => Err(anyhow::anyhow!("unknown log component: {s}")),
+        }
+    }
+}
+
+/// The log level of each component, the ones not set follow the level of the logger.
+#[serde_as]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogLevels {
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanner: Option<LevelFilter>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitter: Option<LevelFilter>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc: Option<LevelFilter>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<LevelFilter>,
+}
+
+impl LogLevels {
+    pub fn with_level(mut self, component: LogComponent, level: LevelFilter) -> Self {
+        *self.level_mut(component) = Some(level);
+        self
+    }
+
+    pub fn level(&self, component: LogComponent) -> Option<LevelFilter> {
+        match component {
+            LogComponent::Scanner => self.scanner,
+            LogComponent::Submitter => self.submitter,
+            LogComponent::Rpc => self.rpc,
+            LogComponent::Wallet => self.wallet,
+        }
+    }
+
+    fn level_mut(&mut self, component: LogComponent) -> &mut Option<LevelFilter> {
+        match component {
+            LogComponent::Scanner => &mut self.scanner,
+            LogComponent::Submitter => &mut self.submitter,
+            LogComponent::Rpc => &mut self.rpc,
+            LogComponent::Wallet => &mut self.wallet,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The levels as the comma separated `target=level` directives of `env_logger`, e.g.
+    /// `ipc_provider::checkpoint::scanner=warn`. The directives of the more specific targets
+    /// take precedence, so they can be appended to a global level.
+    pub fn directives(&self) -> String {
+        [
+            LogComponent::Scanner,
+            LogComponent::Submitter,
+            LogComponent::Rpc,
+            LogComponent::Wallet,
+        ]
+        .into_iter()
+        .filter_map(|c| self.level(c).map(|l| (c, l)))
+        .flat_map(|(c, l)| {
+            c.targets()
+                .iter()
+                .map(move |t| format!("{t}={}", l.as_str().to_lowercase()))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LevelFilter, LogComponent, LogLevels};
+
+    #[test]
+    fn test_directives() {
+        assert_eq!(LogLevels::default().directives(), "");
+
+        let levels = LogLevels::default()
+            .with_level(LogComponent::Scanner, LevelFilter::Warn)
+            .with_level(LogComponent::Wallet, LevelFilter::Debug);
+        assert_eq!(
+            levels.directives(),
+            "ipc_provider::checkpoint::scanner=warn,ipc_wallet=debug,ipc_provider::key_source=debug"
+        );
+    }
+
+    #[test]
+    fn test_levels_from_toml() {
+        let levels: LogLevels = toml::from_str("scanner = \"warn\"\nrpc = \"trace\"").unwrap();
+        assert_eq!(levels.scanner, Some(LevelFilter::Warn));
+        assert_eq!(levels.rpc, Some(LevelFilter::Trace));
+        assert_eq!(levels.submitter, None);
+
+        assert!(toml::from_str::<LogLevels>("scanner = \"loud\"").is_err());
+    }
+}
@@ -112,7 +112,7 @@ impl<T: JsonRpcClient> LotusJsonRPCClient<T> {
 }
 
 #[async_trait]
-impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
+impl<T: JsonRpcClient> LotusClient for LotusJsonRPCClient<T> {
     async fn mpool_push_message(
         &self,
         msg: MpoolPushMessage,
@@ -350,7 +350,7 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
     }
 }
 
-impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
+impl<T: JsonRpcClient> LotusJsonRPCClient<T> {
     fn sign_mpool_message(&self, msg: &MpoolPushMessage) -> anyhow::Result<Signature> {
         if self.wallet_store.is_none() {
             return Err(anyhow!("key store not set, function not supported"));
@@ -445,22 +445,33 @@ impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
 
 impl LotusJsonRPCClient<JsonRpcClientImpl> {
     /// A constructor that returns a `LotusJsonRPCClient` from a `Subnet`. The returned
-    /// `LotusJsonRPCClient` makes requests to the URL defined in the `Subnet`.
-    pub fn from_subnet(subnet: &crate::config::Subnet) -> Self {
-        let url = subnet.rpc_http().clone();
-        let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
-        LotusJsonRPCClient::new(jsonrpc_client, subnet.id.clone())
+    /// `LotusJsonRPCClient` makes requests to the URL defined in the `Subnet`, through its
+    /// proxy if any.
+    pub fn from_subnet(subnet: &crate::config::Subnet) -> anyhow::Result<Self> {
+        let jsonrpc_client = jsonrpc_client(subnet)?;
+        Ok(LotusJsonRPCClient::new(jsonrpc_client, subnet.id.clone()))
     }
 
     pub fn from_subnet_with_wallet_store(
         subnet: &crate::config::Subnet,
         wallet_store: Arc<RwLock<Wallet>>,
-    ) -> Self {
-        let url = subnet.rpc_http().clone();
-        let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
-        LotusJsonRPCClient::new_with_wallet_store(jsonrpc_client, subnet.id.clone(), wallet_store)
+    ) -> anyhow::Result<Self> {
+        let jsonrpc_client = jsonrpc_client(subnet)?;
+        Ok(LotusJsonRPCClient::new_with_wallet_store(
+            jsonrpc_client,
+            subnet.id.clone(),
+            wallet_store,
+        ))
+    }
+}
+
+fn jsonrpc_client(subnet: &crate::config::Subnet) -> anyhow::Result<JsonRpcClientImpl> {
+    let url = subnet.rpc_http().clone();
+    let auth_token = subnet.auth_token();
+    let client = JsonRpcClientImpl::new(url, auth_token.as_deref());
+    match subnet.proxy() {
+        Some(proxy) => client.with_proxy(proxy.clone()),
+        None => Ok(client),
     }
 }
 
@@ -91,6 +91,15 @@ where
     deserializer.deserialize_map(SubnetIdVisitor)
 }
 
+/// A serde deserialization method to deserialize a subnet id from string
+pub fn deserialize_subnet_id_from_str<'de, D>(deserializer: D) -> anyhow::Result<SubnetID, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    SubnetID::from_str(&s).map_err(D::Error::custom)
+}
+
 /// A serde deserialization method to deserialize a token amount from string
 pub fn deserialize_token_amount_from_str<'de, D>(
     deserializer: D,
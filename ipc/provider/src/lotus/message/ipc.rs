@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::lotus::message::deserialize::{
     deserialize_ipc_address_from_map, deserialize_subnet_id_from_map,
-    deserialize_token_amount_from_str,
+    deserialize_subnet_id_from_str, deserialize_token_amount_from_str,
 };
 use crate::lotus::message::serialize::{
     serialize_subnet_id_to_str, serialize_token_amount_to_atto,
@@ -59,22 +59,19 @@ pub struct Voting {
 
 /// SubnetInfo is an auxiliary struct that collects relevant information about the state of a subnet
 ///
-/// Note that the serialization and deserialization casing are different. Reason because for deserialization,
-/// it is from the fvm actor, which is `PascalCase`. When serialize, we are using rust's default casing
-#[derive(Debug, Serialize, Deserialize)]
+/// The subnet id is serialized as a string and the amounts as strings of atto, so that the
+/// struct reads back from the JSON it is written to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SubnetInfo {
     /// Id of the subnet.
-    #[serde(rename(deserialize = "ID"))]
-    #[serde(deserialize_with = "deserialize_subnet_id_from_map")]
+    #[serde(deserialize_with = "deserialize_subnet_id_from_str")]
     #[serde(serialize_with = "serialize_subnet_id_to_str")]
     pub id: SubnetID,
     /// Collateral staked in the subnet.
-    #[serde(rename(deserialize = "Stake"))]
     #[serde(deserialize_with = "deserialize_token_amount_from_str")]
     #[serde(serialize_with = "serialize_token_amount_to_atto")]
     pub stake: TokenAmount,
     /// Circulating supply available in the subnet.
-    #[serde(rename(deserialize = "CircSupply"))]
     #[serde(deserialize_with = "deserialize_token_amount_from_str")]
     #[serde(serialize_with = "serialize_token_amount_to_atto")]
     pub circ_supply: TokenAmount,
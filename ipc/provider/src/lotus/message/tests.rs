@@ -134,3 +134,20 @@ fn test_subnet_info_to_str() {
     let w = serde_json::to_string(&s);
     assert!(w.is_ok());
 }
+
+#[test]
+fn test_subnet_info_roundtrip() {
+    let s = SubnetInfo {
+        id: SubnetID::from_str("/r123/f0100").unwrap(),
+        stake: TokenAmount::from_whole(10),
+        circ_supply: TokenAmount::from_atto(5),
+        genesis_epoch: 42,
+    };
+
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(
+        json,
+        r#"{"id":"/r123/f0100","stake":"10000000000000000000","circ_supply":"5","genesis_epoch":42}"#
+    );
+    assert_eq!(serde_json::from_str::<SubnetInfo>(&json).unwrap(), s);
+}
@@ -28,7 +28,7 @@ pub type NetworkVersion = u32;
 
 /// The Lotus client api to interact with the Lotus node.
 #[async_trait]
-pub trait LotusClient {
+pub trait LotusClient: Send + Sync {
     /// Push the message to memory pool, see: https://lotus.filecoin.io/reference/lotus/mpool/#mpoolpushmessage
     async fn mpool_push_message(
         &self,
@@ -0,0 +1,47 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The allowlist of the relayers of the subnets restricting the submission of their checkpoints.
+//!
+//! The subnet actors of this repository accept the checkpoints of any relayer. Deployments
+//! restricting them install a facet with this interface in the subnet actor, managed by its
+//! owner; a subnet actor without it is not permissioned.
+
+use crate::diamond::Facet;
+use ethers::contract::{abigen, EthCall};
+
+abigen!(
+    RelayerAllowlistFacet,
+    r#"[
+        function isRelayerAllowed(address relayer) external view returns (bool)
+        function allowedRelayers() external view returns (address[])
+        function setRelayerAllowed(address relayer, bool allowed) external
+    ]"#
+);
+
+/// Whether the `facets` of a subnet actor serve the allowlist of its relayers.
+pub(crate) fn serves_allowlist(facets: &[Facet]) -> bool {
+    let selector = IsRelayerAllowedCall::selector();
+    facets.iter().any(|f| f.selectors.contains(&selector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::serves_allowlist;
+    use crate::diamond::Facet;
+    use ethers::utils::id;
+    use fvm_shared::address::Address;
+
+    #[test]
+    fn test_serves_allowlist() {
+        let facet = |signatures: &[&str]| Facet {
+            address: Address::new_id(1),
+            selectors: signatures.iter().map(id).collect(),
+        };
+
+        let getter = facet(&["getParent()", "bottomUpCheckPeriod()"]);
+        assert!(!serves_allowlist(&[getter.clone()]));
+
+        let allowlist = facet(&["isRelayerAllowed(address)", "allowedRelayers()"]);
+        assert!(serves_allowlist(&[getter, allowlist]));
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! JSON-RPC batch requests, to query several heights of a subnet in one HTTP round-trip.
+//!
+//! Not all endpoints accept batches. The first time a batch is rejected the endpoint is
+//! considered not to support them, and the callers fall back to serial requests.
+
+use super::limits::RpcPermits;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use url::Url;
+
+pub(crate) struct BatchRpc {
+    client: Client,
+    url: Url,
+    supported: AtomicBool,
+    /// A batch holds a single permit of the concurrency limits, as a single request.
+    permits: RpcPermits,
+}
+
+impl BatchRpc {
+    pub fn new(client: Client, url: Url) -> Self {
+        Self {
+            client,
+            url,
+            supported: AtomicBool::new(true),
+            permits: RpcPermits::default(),
+        }
+    }
+
+    pub fn with_permits(mut self, permits: RpcPermits) -> Self {
+        self.permits = permits;
+        self
+    }
+
+    /// Whether the endpoint was not found to reject batches yet.
+    pub fn is_supported(&self) -> bool {
+        self.supported.load(Ordering::Relaxed)
+    }
+
+    /// Sends one `method` request per entry of `params` in a single batch, returning their
+    /// results in the same order, or `None` if the endpoint does not support batches.
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+    ) -> Result<Option<Vec<T>>> {
+        if !self.is_supported() {
+            return Ok(None);
+        }
+        if params.is_empty() {
+            return Ok(Some(vec![]));
+        }
+
+        let len = params.len();
+        let _permit = self.permits.acquire().await;
+        let response = self
+            .client
+            .post(self.url.clone())
+            .json(&batch_request(method, params))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            self.unsupported(&format!("status {}", response.status()));
+            return Ok(None);
+        }
+
+        let body = match response.json::<Value>().await {
+            Ok(body) => body,
+            Err(e) => {
+                self.unsupported(&e.to_string());
+                return Ok(None);
+            }
+        };
+        match parse_batch_response(body, len)? {
+            Some(results) => Ok(Some(
+                results
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            None => {
+                self.unsupported("the response is not a batch");
+                Ok(None)
+            }
+        }
+    }
+
+    fn unsupported(&self, reason: &str) {
+        if self.supported.swap(false, Ordering::Relaxed) {
+            log::warn!(
+                "endpoint {} does not support batch requests ({reason}), using serial requests",
+                self.url
+            );
+        }
+    }
+}
+
+fn batch_request(method: &str, params: Vec<Value>) -> Value {
+    Value::Array(
+        params
+            .into_iter()
+            .enumerate()
+            .map(|(id, params)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": method,
+                    "params": params,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// The results of the `len` requests of a batch, in the order of the requests, or `None` if
+/// the response is not a batch. The responses of a batch can come in any order.
+fn parse_batch_response(body: Value, len: usize) -> Result<Option<Vec<Value>>> {
+    let Value::Array(responses) = body else {
+        return Ok(None);
+    };
+
+    let mut results = vec![None; len];
+    for mut response in responses {
+        let id = response
+            .get("id")
+            .and_then(Value::as_u64)
+            .filter(|id| (*id as usize) < len)
+            .ok_or_else(|| anyhow!("batch response with an unknown id: {response}"))?
+            as usize;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("batch request {id} failed: {error}"));
+        }
+        results[id] = Some(
+            response
+                .get_mut("result")
+                .map(Value::take)
+                .unwrap_or_default(),
+        );
+    }
+
+    results
+        .into_iter()
+        .enumerate()
+        .map(|(id, r)| r.ok_or_else(|| anyhow!("no response to batch request {id}")))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_request, parse_batch_response};
+    use serde_json::json;
+
+    #[test]
+    fn test_batch_request() {
+        let request = batch_request("eth_getLogs", vec![json!([1]), json!([2])]);
+        assert_eq!(
+            request,
+            json!([
+                {"jsonrpc": "2.0", "id": 0, "method": "eth_getLogs", "params": [1]},
+                {"jsonrpc": "2.0", "id": 1, "method": "eth_getLogs", "params": [2]},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_responses_are_reordered() {
+        let body = json!([
+            {"jsonrpc": "2.0", "id": 1, "result": "b"},
+            {"jsonrpc": "2.0", "id": 0, "result": "a"},
+        ]);
+        assert_eq!(
+            parse_batch_response(body, 2).unwrap(),
+            Some(vec![json!("a"), json!("b")])
+        );
+    }
+
+    #[test]
+    fn test_not_a_batch() {
+        let body = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600}});
+        assert_eq!(parse_batch_response(body, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_or_missing_responses() {
+        let body = json!([
+            {"jsonrpc": "2.0", "id": 0, "result": "a"},
+            {"jsonrpc": "2.0", "id": 1, "error": {"code": 3, "message": "execution reverted"}},
+        ]);
+        assert!(parse_batch_response(body, 2).is_err());
+
+        let body = json!([{"jsonrpc": "2.0", "id": 0, "result": "a"}]);
+        assert!(parse_batch_response(body, 2).is_err());
+
+        let body = json!([{"jsonrpc": "2.0", "id": 5, "result": "a"}]);
+        assert!(parse_batch_response(body, 2).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Contract bindings of the checkpoint calls, per contract version.
+//!
+//! The layout of the checkpoint changes across contract upgrades. Each supported version has
+//! its own bindings implementing [`CheckpointBindings`]. The version deployed in a gateway or
+//! subnet actor diamond is detected through the diamond loupe, by looking up which of the
+//! known function selectors it implements, so that a single relayer can serve subnets running
+//! different contract versions. It can also be pinned with
+//! [`super::EthSubnetManager::with_abi_version`].
+
+mod v1;
+mod v2;
+#[cfg(test)]
+mod vectors;
+
+use crate::manager::evm::client::EvmClient;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::abi::{Token, Tokenizable};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Bytes, Selector, H160};
+use ethers::utils::keccak256;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::diamond_loupe_facet;
+use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle};
+use ipc_api::ethers_address_to_fil_address;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The checkpoint calls of a contract version.
+#[async_trait]
+pub trait CheckpointBindings: Send + Sync {
+    /// The selector of `submitCheckpoint` in the subnet actor.
+    fn submit_checkpoint_selector(&self) -> Selector;
+
+    /// The calldata of the `submitCheckpoint` call of the subnet actor.
+    fn encode_submit_checkpoint(
+        &self,
+        checkpoint: BottomUpCheckpoint,
+        signatories: Vec<H160>,
+        signatures: Vec<Bytes>,
+    ) -> Result<Bytes>;
+
+    /// Queries the checkpoint bundle at `height` from the gateway.
+    async fn checkpoint_bundle_at(
+        &self,
+        provider: Arc<Provider<EvmClient>>,
+        gateway: H160,
+        height: ChainEpoch,
+    ) -> Result<BottomUpCheckpointBundle>;
+
+    /// The calldata of the gateway call returning the checkpoint bundle at `height`, if the
+    /// bundle is the output of a single call, so that the queries of several heights can be
+    /// batched.
+    fn checkpoint_bundle_call(&self, _height: ChainEpoch) -> Option<Bytes> {
+        None
+    }
+
+    /// Decodes the output of the call of [`CheckpointBindings::checkpoint_bundle_call`].
+    fn decode_checkpoint_bundle(&self, _output: &[u8]) -> Result<BottomUpCheckpointBundle> {
+        Err(anyhow!(
+            "checkpoint bundles are not queried with a single call"
+        ))
+    }
+}
+
+/// The layouts of the bottom-up checkpoint, from the oldest supported to the latest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CheckpointAbiVersion {
+    /// The checkpoint commits to the hash of its messages, which are submitted alongside it
+    /// and queried from the gateway separately.
+    V1,
+    /// The checkpoint carries its messages.
+    V2,
+}
+
+impl Display for CheckpointAbiVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointAbiVersion::V1 => write!(f, "v1"),
+            CheckpointAbiVersion::V2 => write!(f, "v2"),
+        }
+    }
+}
+
+impl FromStr for CheckpointAbiVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "v1" => Ok(CheckpointAbiVersion::V1),
+            "v2" => Ok(CheckpointAbiVersion::V2),
+            _ => Err(anyhow!("unknown checkpoint abi version: {s}")),
+        }
+    }
+}
+
+impl CheckpointAbiVersion {
+    pub const LATEST: Self = CheckpointAbiVersion::V2;
+
+    pub fn bindings(&self) -> &'static dyn CheckpointBindings {
+        match self {
+            CheckpointAbiVersion::V1 => &v1::Bindings,
+            CheckpointAbiVersion::V2 => &v2::Bindings,
+        }
+    }
+
+    /// Detects the version of a subnet actor from the `submitCheckpoint` it implements.
+    pub async fn detect_subnet_actor<M: Middleware + 'static>(
+        client: Arc<M>,
+        subnet_actor: H160,
+    ) -> Result<Self> {
+        for version in [CheckpointAbiVersion::V2, CheckpointAbiVersion::V1] {
+            let selector = version.bindings().submit_checkpoint_selector();
+            if implements(client.clone(), subnet_actor, selector).await? {
+                return Ok(version);
+            }
+        }
+        Err(anyhow!(
+            "subnet actor {subnet_actor:?} implements no supported checkpoint version"
+        ))
+    }
+
+    /// Detects the version of a gateway, only the V1 gateways serve the messages of the
+    /// checkpoints separately.
+    pub async fn detect_gateway<M: Middleware + 'static>(
+        client: Arc<M>,
+        gateway: H160,
+    ) -> Result<Self> {
+        if !implements(
+            client.clone(),
+            gateway,
+            v2::checkpoint_signature_bundle_selector(),
+        )
+        .await?
+        {
+            return Err(anyhow!(
+                "gateway {gateway:?} implements no supported checkpoint version"
+            ));
+        }
+        if implements(client, gateway, v1::bottom_up_messages_selector()).await? {
+            Ok(CheckpointAbiVersion::V1)
+        } else {
+            Ok(CheckpointAbiVersion::V2)
+        }
+    }
+}
+
+/// Whether the diamond has a facet implementing the function `selector`.
+async fn implements<M: Middleware + 'static>(
+    client: Arc<M>,
+    diamond: H160,
+    selector: Selector,
+) -> Result<bool> {
+    let loupe = diamond_loupe_facet::DiamondLoupeFacet::new(diamond, client);
+    let facet = loupe.facet_address(selector).call().await?;
+    Ok(!facet.is_zero())
+}
+
+/// Converts between the bindings of the same solidity type generated for different contracts
+/// or versions.
+fn retype<A: Tokenizable, B: Tokenizable>(value: A) -> Result<B> {
+    Ok(B::from_token(value.into_token())?)
+}
+
+/// The hash the checkpoints commit their messages with, `keccak256(abi.encode(msgs))`.
+fn messages_hash<T: Tokenizable + Clone>(msgs: &[T]) -> [u8; 32] {
+    keccak256(ethers::abi::encode(&[Token::Array(
+        msgs.iter().cloned().map(Tokenizable::into_token).collect(),
+    )]))
+}
+
+fn to_bundle(
+    checkpoint: BottomUpCheckpoint,
+    signatories: Vec<H160>,
+    signatures: Vec<Bytes>,
+) -> Result<BottomUpCheckpointBundle> {
+    Ok(BottomUpCheckpointBundle {
+        checkpoint,
+        signatories: signatories
+            .iter()
+            .map(ethers_address_to_fil_address)
+            .collect::<Result<Vec<_>>>()?,
+        signatures: signatures.into_iter().map(|s| s.to_vec()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CheckpointAbiVersion;
+    use ethers::abi::AbiEncode;
+    use ethers::types::{Bytes, H160, U256};
+    use ethers_contract::EthCall;
+    use ipc_actors_abis::gateway_getter_facet;
+    use ipc_api::checkpoint::BottomUpCheckpoint;
+    use ipc_api::subnet_id::SubnetID;
+    use std::str::FromStr;
+
+    fn checkpoint() -> BottomUpCheckpoint {
+        BottomUpCheckpoint {
+            subnet_id: SubnetID::from_str("/r314159/t410f6b2qto756ox3qfoonq4ii6pdrylxwyretgpixuy")
+                .unwrap(),
+            block_height: 10,
+            block_hash: vec![1; 32],
+            next_configuration_number: 0,
+            msgs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_versions_have_distinct_selectors() {
+        assert_ne!(
+            CheckpointAbiVersion::V1
+                .bindings()
+                .submit_checkpoint_selector(),
+            CheckpointAbiVersion::V2
+                .bindings()
+                .submit_checkpoint_selector()
+        );
+    }
+
+    #[test]
+    fn test_encode_submit_checkpoint() {
+        for version in [CheckpointAbiVersion::V1, CheckpointAbiVersion::V2] {
+            let bindings = version.bindings();
+            let data = bindings
+                .encode_submit_checkpoint(checkpoint(), vec![], vec![])
+                .unwrap();
+            assert_eq!(data[..4], bindings.submit_checkpoint_selector());
+        }
+    }
+
+    #[test]
+    fn test_batched_checkpoint_bundle() {
+        assert!(CheckpointAbiVersion::V1
+            .bindings()
+            .checkpoint_bundle_call(10)
+            .is_none());
+
+        let bindings = CheckpointAbiVersion::V2.bindings();
+        let call = bindings.checkpoint_bundle_call(10).unwrap();
+        assert_eq!(
+            call[..4],
+            gateway_getter_facet::GetCheckpointSignatureBundleCall::selector()
+        );
+
+        let output = gateway_getter_facet::GetCheckpointSignatureBundleReturn {
+            ch: gateway_getter_facet::BottomUpCheckpoint::try_from(checkpoint()).unwrap(),
+            info: gateway_getter_facet::QuorumInfo {
+                hash: [0; 32],
+                root_hash: [0; 32],
+                threshold: U256::zero(),
+                current_weight: U256::zero(),
+                reached: true,
+            },
+            signatories: vec![H160::repeat_byte(1)],
+            signatures: vec![Bytes::from(vec![2; 65])],
+        }
+        .encode();
+        let bundle = bindings.decode_checkpoint_bundle(&output).unwrap();
+        assert_eq!(bundle.checkpoint, checkpoint());
+        assert_eq!(bundle.signatures, vec![vec![2; 65]]);
+        assert_eq!(bundle.signatories.len(), 1);
+    }
+
+    #[test]
+    fn test_version_from_str() {
+        for version in [CheckpointAbiVersion::V1, CheckpointAbiVersion::V2] {
+            assert_eq!(
+                CheckpointAbiVersion::from_str(&version.to_string()).unwrap(),
+                version
+            );
+        }
+    }
+}
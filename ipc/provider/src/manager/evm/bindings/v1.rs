@@ -0,0 +1,118 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bindings of the V1 contracts, whose checkpoints commit to the hash of their messages.
+//!
+//! These contracts are no longer part of this repository, so only the fragment of their ABI
+//! used by the relayer is kept here.
+
+use super::{messages_hash, retype, to_bundle, CheckpointBindings};
+use crate::manager::evm::client::EvmClient;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::abi::{AbiEncode, Token, Tokenizable};
+use ethers::contract::abigen;
+use ethers::providers::Provider;
+use ethers::types::{Bytes, Selector, H160, U256};
+use ethers_contract::EthCall;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::{gateway_getter_facet, subnet_actor_checkpointing_facet};
+use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle};
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::evm::vec_to_bytes32;
+use ipc_api::subnet_id::SubnetID;
+use std::sync::Arc;
+
+abigen!(
+    CheckpointingV1,
+    r#"[
+        struct SubnetID { uint64 root; address[] route; }
+        struct FvmAddress { uint8 addrType; bytes payload; }
+        struct IPCAddress { SubnetID subnetId; FvmAddress rawAddress; }
+        struct IpcEnvelope { uint8 kind; IPCAddress to; IPCAddress from; uint64 nonce; uint256 value; bytes message; }
+        struct BottomUpCheckpoint { SubnetID subnetID; uint256 blockHeight; bytes32 blockHash; uint64 nextConfigurationNumber; bytes32 crossMessagesHash; }
+        struct QuorumInfo { bytes32 hash; bytes32 rootHash; uint256 threshold; uint256 currentWeight; bool reached; }
+        function submitCheckpoint(BottomUpCheckpoint checkpoint, IpcEnvelope[] messages, address[] signatories, bytes[] signatures) external
+        function getCheckpointSignatureBundle(uint256 h) external view returns (BottomUpCheckpoint ch, QuorumInfo info, address[] signatories, bytes[] signatures)
+        function bottomUpMessages(uint256 epoch) external view returns (IpcEnvelope[] msgs)
+    ]"#
+);
+
+pub(super) struct Bindings;
+
+#[async_trait]
+impl CheckpointBindings for Bindings {
+    fn submit_checkpoint_selector(&self) -> Selector {
+        SubmitCheckpointCall::selector()
+    }
+
+    fn encode_submit_checkpoint(
+        &self,
+        checkpoint: BottomUpCheckpoint,
+        signatories: Vec<H160>,
+        signatures: Vec<Bytes>,
+    ) -> Result<Bytes> {
+        let msgs = checkpoint
+            .msgs
+            .into_iter()
+            .map(subnet_actor_checkpointing_facet::IpcEnvelope::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let subnet_id =
+            subnet_actor_checkpointing_facet::SubnetID::try_from(&checkpoint.subnet_id)?;
+
+        let call = SubmitCheckpointCall {
+            checkpoint: retype(Token::Tuple(vec![
+                subnet_id.into_token(),
+                Token::Uint(U256::from(checkpoint.block_height)),
+                Token::FixedBytes(vec_to_bytes32(checkpoint.block_hash)?.to_vec()),
+                Token::Uint(U256::from(checkpoint.next_configuration_number)),
+                Token::FixedBytes(messages_hash(&msgs).to_vec()),
+            ]))?,
+            messages: msgs.into_iter().map(retype).collect::<Result<Vec<_>>>()?,
+            signatories,
+            signatures,
+        };
+        Ok(Bytes::from(call.encode()))
+    }
+
+    async fn checkpoint_bundle_at(
+        &self,
+        provider: Arc<Provider<EvmClient>>,
+        gateway: H160,
+        height: ChainEpoch,
+    ) -> Result<BottomUpCheckpointBundle> {
+        let contract = CheckpointingV1::new(gateway, provider);
+        let (checkpoint, _, signatories, signatures) = contract
+            .get_checkpoint_signature_bundle(U256::from(height))
+            .call()
+            .await?;
+        let msgs = contract
+            .bottom_up_messages(U256::from(height))
+            .call()
+            .await?;
+
+        if messages_hash(&msgs) != checkpoint.cross_messages_hash {
+            return Err(anyhow!(
+                "messages of checkpoint({height}) do not match the committed hash"
+            ));
+        }
+
+        let checkpoint = BottomUpCheckpoint {
+            subnet_id: SubnetID::try_from(retype::<_, gateway_getter_facet::SubnetID>(
+                checkpoint.subnet_id,
+            )?)?,
+            block_height: checkpoint.block_height.as_u128() as ChainEpoch,
+            block_hash: checkpoint.block_hash.to_vec(),
+            next_configuration_number: checkpoint.next_configuration_number,
+            msgs: msgs
+                .into_iter()
+                .map(|m| IpcEnvelope::try_from(retype::<_, gateway_getter_facet::IpcEnvelope>(m)?))
+                .collect::<Result<Vec<_>>>()?,
+        };
+        to_bundle(checkpoint, signatories, signatures)
+    }
+}
+
+/// Only implemented by the V1 gateways.
+pub(super) fn bottom_up_messages_selector() -> Selector {
+    BottomUpMessagesCall::selector()
+}
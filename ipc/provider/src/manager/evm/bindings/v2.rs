@@ -0,0 +1,78 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Bindings of the V2 contracts, generated from the solidity actors of this repository.
+
+use super::{to_bundle, CheckpointBindings};
+use crate::manager::evm::client::EvmClient;
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::providers::Provider;
+use ethers::types::{Bytes, Selector, H160, U256};
+use ethers_contract::EthCall;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::{gateway_getter_facet, subnet_actor_checkpointing_facet};
+use ipc_api::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle};
+use std::sync::Arc;
+
+pub(super) struct Bindings;
+
+#[async_trait]
+impl CheckpointBindings for Bindings {
+    fn submit_checkpoint_selector(&self) -> Selector {
+        subnet_actor_checkpointing_facet::SubmitCheckpointCall::selector()
+    }
+
+    fn encode_submit_checkpoint(
+        &self,
+        checkpoint: BottomUpCheckpoint,
+        signatories: Vec<H160>,
+        signatures: Vec<Bytes>,
+    ) -> Result<Bytes> {
+        let call = subnet_actor_checkpointing_facet::SubmitCheckpointCall {
+            checkpoint: subnet_actor_checkpointing_facet::BottomUpCheckpoint::try_from(checkpoint)?,
+            signatories,
+            signatures,
+        };
+        Ok(Bytes::from(call.encode()))
+    }
+
+    async fn checkpoint_bundle_at(
+        &self,
+        provider: Arc<Provider<EvmClient>>,
+        gateway: H160,
+        height: ChainEpoch,
+    ) -> Result<BottomUpCheckpointBundle> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(gateway, provider);
+        let (checkpoint, _, signatories, signatures) = contract
+            .get_checkpoint_signature_bundle(U256::from(height))
+            .call()
+            .await?;
+        to_bundle(
+            BottomUpCheckpoint::try_from(checkpoint)?,
+            signatories,
+            signatures,
+        )
+    }
+
+    fn checkpoint_bundle_call(&self, height: ChainEpoch) -> Option<Bytes> {
+        let call = gateway_getter_facet::GetCheckpointSignatureBundleCall {
+            h: U256::from(height),
+        };
+        Some(Bytes::from(call.encode()))
+    }
+
+    fn decode_checkpoint_bundle(&self, output: &[u8]) -> Result<BottomUpCheckpointBundle> {
+        let bundle = gateway_getter_facet::GetCheckpointSignatureBundleReturn::decode(output)?;
+        to_bundle(
+            BottomUpCheckpoint::try_from(bundle.ch)?,
+            bundle.signatories,
+            bundle.signatures,
+        )
+    }
+}
+
+/// Implemented by the gateways of all the supported versions.
+pub(super) fn checkpoint_signature_bundle_selector() -> Selector {
+    gateway_getter_facet::GetCheckpointSignatureBundleCall::selector()
+}
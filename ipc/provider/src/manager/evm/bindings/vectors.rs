@@ -0,0 +1,166 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Canonical test vectors of the checkpoint encodings, checked against the golden file at
+//! `testdata/checkpoint_vectors.json`.
+//!
+//! A change of the bindings or of the conversions of the checkpoints altering what is sent on
+//! the wire, or the hash the validators sign, fails the test here instead of on a live parent.
+//! When the change is intended, the golden file is regenerated with `UPDATE_GOLDEN=1`.
+
+use super::{messages_hash, CheckpointAbiVersion};
+use ethers::abi::Tokenizable;
+use ethers::types::{Bytes, H160};
+use ethers::utils::keccak256;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_actors_abis::subnet_actor_checkpointing_facet;
+use ipc_api::address::IPCAddress;
+use ipc_api::checkpoint::BottomUpCheckpoint;
+use ipc_api::cross::{IpcEnvelope, IpcMsgKind};
+use ipc_api::subnet_id::SubnetID;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const GOLDEN_FILE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/testdata/checkpoint_vectors.json"
+);
+
+/// A checkpoint submitted with its signatures.
+struct CheckpointVector {
+    name: &'static str,
+    checkpoint: BottomUpCheckpoint,
+    signatories: Vec<H160>,
+    signatures: Vec<Bytes>,
+}
+
+/// The encodings of a vector, as recorded in the golden file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct EncodedVector {
+    name: String,
+    /// `keccak256(abi.encode(checkpoint))`, the hash signed by the validators.
+    checkpoint_hash: String,
+    /// `keccak256(abi.encode(msgs))`, the hash the V1 checkpoints commit their messages with.
+    messages_hash: String,
+    /// The calldata of `submitCheckpoint` by contract version.
+    submit_calldata: BTreeMap<String, String>,
+}
+
+fn delegated(byte: u8) -> Address {
+    Address::new_delegated(10, &[byte; 20]).unwrap()
+}
+
+fn envelope(
+    kind: IpcMsgKind,
+    to: u8,
+    nonce: u64,
+    value: TokenAmount,
+    message: Vec<u8>,
+) -> IpcEnvelope {
+    let root = SubnetID::new_root(314159);
+    let child = SubnetID::new(314159, vec![delegated(0x11)]);
+    IpcEnvelope {
+        kind,
+        to: IPCAddress::new(&root, &delegated(to)).unwrap(),
+        value,
+        from: IPCAddress::new(&child, &delegated(0x44)).unwrap(),
+        message,
+        nonce,
+    }
+}
+
+fn vectors() -> Vec<CheckpointVector> {
+    let child = SubnetID::new(314159, vec![delegated(0x11)]);
+    vec![
+        CheckpointVector {
+            name: "empty",
+            checkpoint: BottomUpCheckpoint {
+                subnet_id: child.clone(),
+                block_height: 100,
+                block_hash: vec![1; 32],
+                next_configuration_number: 0,
+                msgs: vec![],
+            },
+            signatories: vec![],
+            signatures: vec![],
+        },
+        CheckpointVector {
+            name: "signed_with_messages",
+            checkpoint: BottomUpCheckpoint {
+                subnet_id: child,
+                block_height: 200,
+                block_hash: vec![2; 32],
+                next_configuration_number: 3,
+                msgs: vec![
+                    envelope(
+                        IpcMsgKind::Transfer,
+                        0x55,
+                        7,
+                        TokenAmount::from_whole(1),
+                        vec![],
+                    ),
+                    envelope(
+                        IpcMsgKind::Call,
+                        0x66,
+                        8,
+                        TokenAmount::from_atto(0),
+                        vec![0xde, 0xad, 0xbe, 0xef],
+                    ),
+                ],
+            },
+            signatories: vec![H160::repeat_byte(0x22), H160::repeat_byte(0x33)],
+            signatures: vec![Bytes::from(vec![0xaa; 65]), Bytes::from(vec![0xbb; 65])],
+        },
+    ]
+}
+
+fn encode(vector: &CheckpointVector) -> EncodedVector {
+    let checkpoint =
+        subnet_actor_checkpointing_facet::BottomUpCheckpoint::try_from(vector.checkpoint.clone())
+            .unwrap();
+    let messages_hash = messages_hash(&checkpoint.msgs);
+    let checkpoint_hash = keccak256(ethers::abi::encode(&[checkpoint.into_token()]));
+
+    let submit_calldata = [CheckpointAbiVersion::V1, CheckpointAbiVersion::V2]
+        .into_iter()
+        .map(|version| {
+            let calldata = version
+                .bindings()
+                .encode_submit_checkpoint(
+                    vector.checkpoint.clone(),
+                    vector.signatories.clone(),
+                    vector.signatures.clone(),
+                )
+                .unwrap();
+            (version.to_string(), format!("0x{}", hex::encode(calldata)))
+        })
+        .collect();
+
+    EncodedVector {
+        name: vector.name.to_string(),
+        checkpoint_hash: format!("0x{}", hex::encode(checkpoint_hash)),
+        messages_hash: format!("0x{}", hex::encode(messages_hash)),
+        submit_calldata,
+    }
+}
+
+#[test]
+fn test_checkpoint_vectors() {
+    let encoded = vectors().iter().map(encode).collect::<Vec<_>>();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let json = serde_json::to_string_pretty(&encoded).unwrap();
+        std::fs::write(GOLDEN_FILE, json + "\n").unwrap();
+    }
+
+    let golden: Vec<EncodedVector> =
+        serde_json::from_str(&std::fs::read_to_string(GOLDEN_FILE).unwrap()).unwrap();
+    assert_eq!(encoded.len(), golden.len(), "the vectors changed");
+    for (encoded, golden) in encoded.iter().zip(&golden) {
+        assert_eq!(
+            encoded, golden,
+            "the encoding of vector {} changed, regenerate the golden file with UPDATE_GOLDEN=1 \
+             if intended",
+            golden.name
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The features supported by the JSON-RPC endpoint of a subnet, probed once on startup so that
+//! the manager picks the strategies the endpoint supports instead of failing over on every
+//! call, see [`super::EthSubnetManager::with_detected_capabilities`].
+
+use crate::manager::evm::batch::BatchRpc;
+use crate::manager::evm::client::EvmClient;
+use ethers::providers::{Middleware, Provider, Ws};
+use ethers::types::{BlockNumber, Filter};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use url::Url;
+
+/// The ranges of blocks `eth_getLogs` is probed with, from the largest one.
+const LOGS_RANGE_CANDIDATES: [u64; 4] = [100_000, 10_000, 1_000, 100];
+/// How long to wait for the websocket endpoint to accept a subscription.
+const WS_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcCapabilities {
+    /// `eth_feeHistory` is supported, for the fee oracle.
+    pub fee_history: bool,
+    /// The blocks have a base fee, for EIP-1559 transactions instead of legacy ones.
+    pub eip1559: bool,
+    /// The `safe` and `finalized` block tags are supported.
+    pub finality_tags: bool,
+    /// The websocket endpoint accepts `newHeads` subscriptions, for the receipts and the head
+    /// tracking instead of polling.
+    pub ws_subscriptions: bool,
+    pub batch_requests: bool,
+    /// The largest range of blocks `eth_getLogs` accepted among the ones probed, none if it
+    /// accepted the largest one.
+    pub max_logs_range: Option<u64>,
+}
+
+impl Default for RpcCapabilities {
+    /// What the manager assumes of an endpoint that was not probed.
+    fn default() -> Self {
+        Self {
+            fee_history: true,
+            eip1559: true,
+            finality_tags: true,
+            ws_subscriptions: true,
+            batch_requests: true,
+            max_logs_range: None,
+        }
+    }
+}
+
+impl Display for RpcCapabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        write!(
+            f,
+            "fee history: {}, eip1559: {}, finality tags: {}, ws subscriptions: {}, batches: {}, ",
+            yes_no(self.fee_history),
+            yes_no(self.eip1559),
+            yes_no(self.finality_tags),
+            yes_no(self.ws_subscriptions),
+            yes_no(self.batch_requests),
+        )?;
+        match self.max_logs_range {
+            Some(range) => write!(f, "logs range: {range}"),
+            None => write!(f, "logs range: unlimited"),
+        }
+    }
+}
+
+/// Probes the features of the endpoint of `provider`, of its batches if `batch` is set and of
+/// its websocket endpoint if `ws_url` is set. The probes failing for any reason, e.g. the
+/// endpoint being down, count as the feature not being supported.
+pub(crate) async fn probe(
+    provider: &Provider<EvmClient>,
+    batch: Option<&BatchRpc>,
+    ws_url: Option<&Url>,
+) -> RpcCapabilities {
+    let fee_history = provider
+        .fee_history(1, BlockNumber::Latest, &[50.0])
+        .await
+        .is_ok();
+    let eip1559 = matches!(
+        provider.get_block(BlockNumber::Latest).await,
+        Ok(Some(block)) if block.base_fee_per_gas.is_some()
+    );
+    let finality_tags = matches!(
+        provider.get_block(BlockNumber::Finalized).await,
+        Ok(Some(_))
+    ) && matches!(provider.get_block(BlockNumber::Safe).await, Ok(Some(_)));
+
+    let ws_subscriptions = match ws_url {
+        Some(url) => probe_ws(url).await,
+        None => false,
+    };
+    let batch_requests = match batch {
+        Some(batch) => matches!(
+            batch
+                .call::<serde_json::Value>("eth_blockNumber", vec![json!([]), json!([])])
+                .await,
+            Ok(Some(_))
+        ),
+        None => false,
+    };
+
+    RpcCapabilities {
+        fee_history,
+        eip1559,
+        finality_tags,
+        ws_subscriptions,
+        batch_requests,
+        max_logs_range: probe_logs_range(provider).await,
+    }
+}
+
+async fn probe_ws(url: &Url) -> bool {
+    let subscribe = async {
+        let ws = Provider::<Ws>::connect(url.as_str()).await?;
+        ws.subscribe_blocks().await?;
+        anyhow::Ok(())
+    };
+    matches!(
+        tokio::time::timeout(WS_PROBE_TIMEOUT, subscribe).await,
+        Ok(Ok(()))
+    )
+}
+
+/// The largest range of the candidates `eth_getLogs` accepts, none if it accepts them all.
+async fn probe_logs_range(provider: &Provider<EvmClient>) -> Option<u64> {
+    let latest = match provider.get_block_number().await {
+        Ok(latest) => latest.as_u64(),
+        Err(_) => return LOGS_RANGE_CANDIDATES.last().copied(),
+    };
+    for (i, range) in LOGS_RANGE_CANDIDATES.iter().enumerate() {
+        // the logs of no address, to only test the range
+        let filter = Filter::new()
+            .from_block(latest.saturating_sub(range - 1))
+            .to_block(latest)
+            .address(ethers::types::Address::zero());
+        if provider.get_logs(&filter).await.is_ok() {
+            return (i > 0).then_some(*range);
+        }
+    }
+    LOGS_RANGE_CANDIDATES.last().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RpcCapabilities;
+
+    #[test]
+    fn test_capability_report() {
+        let capabilities = RpcCapabilities {
+            eip1559: false,
+            max_logs_range: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(
+            capabilities.to_string(),
+            "fee history: yes, eip1559: no, finality tags: yes, ws subscriptions: yes, \
+             batches: yes, logs range: 1000"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The JSON-RPC client of [`super::EthSubnetManager`], whose requests go through the
+//! middlewares of the embedder before reaching the transport.
+//!
+//! A middleware sees every request of the manager as its method and JSON params, and either
+//! answers it or passes it on to the next middleware, e.g. to add authentication, to mirror
+//! the requests to another node or to intercept them in tests.
+//!
+//! The requests hold the permits of the concurrency limits of the endpoint while in flight,
+//! see [`super::limits`].
+
+use super::limits::RpcPermits;
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A layer around the requests of the manager to its subnet.
+#[async_trait]
+pub trait RpcMiddleware: Debug + Send + Sync {
+    /// Answers the request of `method` with `params`, calling `next` to pass it on.
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        next: Next<'_>,
+    ) -> Result<Value, EvmClientError>;
+}
+
+/// The rest of the middlewares of a request, down to the transport.
+pub struct Next<'a> {
+    transport: &'a Http,
+    middlewares: &'a [Arc<dyn RpcMiddleware>],
+}
+
+impl<'a> Next<'a> {
+    pub async fn run(self, method: &str, params: Value) -> Result<Value, EvmClientError> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next {
+                    transport: self.transport,
+                    middlewares: rest,
+                };
+                middleware.request(method, params, next).await
+            }
+            None => Ok(self.transport.request(method, params).await?),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvmClientError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("rpc middleware: {0}")]
+    Middleware(String),
+}
+
+impl RpcError for EvmClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            EvmClientError::Http(e) => e.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            EvmClientError::Http(e) => e.as_serde_error(),
+            EvmClientError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<EvmClientError> for ProviderError {
+    fn from(e: EvmClientError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+/// The HTTP transport of a subnet with the middlewares of the embedder, the first one being
+/// the outermost.
+#[derive(Debug, Clone)]
+pub struct EvmClient {
+    transport: Http,
+    middlewares: Arc<Vec<Arc<dyn RpcMiddleware>>>,
+    permits: RpcPermits,
+}
+
+impl EvmClient {
+    pub fn new(transport: Http, middlewares: Vec<Arc<dyn RpcMiddleware>>) -> Self {
+        Self {
+            transport,
+            middlewares: Arc::new(middlewares),
+            permits: RpcPermits::default(),
+        }
+    }
+
+    pub(crate) fn with_permits(mut self, permits: RpcPermits) -> Self {
+        self.permits = permits;
+        self
+    }
+}
+
+impl From<Http> for EvmClient {
+    fn from(transport: Http) -> Self {
+        Self::new(transport, vec![])
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for EvmClient {
+    type Error = EvmClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let _permit = self.permits.acquire().await;
+        if self.middlewares.is_empty() {
+            return Ok(self.transport.request(method, params).await?);
+        }
+
+        let next = Next {
+            transport: &self.transport,
+            middlewares: &self.middlewares,
+        };
+        let result = next.run(method, serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EvmClient, EvmClientError, Next, RpcMiddleware};
+    use async_trait::async_trait;
+    use ethers::providers::{Http, Middleware, Provider};
+    use serde_json::Value;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the methods it sees, and answers `eth_chainId` without the transport.
+    #[derive(Debug, Default)]
+    struct Intercept {
+        seen: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl RpcMiddleware for Intercept {
+        async fn request(
+            &self,
+            method: &str,
+            params: Value,
+            next: Next<'_>,
+        ) -> Result<Value, EvmClientError> {
+            self.seen.lock().unwrap().push(method.to_string());
+            match method {
+                "eth_chainId" => Ok(Value::String("0x7b".to_string())),
+                _ => next.run(method, params).await,
+            }
+        }
+    }
+
+    /// Rejects every request.
+    #[derive(Debug)]
+    struct Reject;
+
+    #[async_trait]
+    impl RpcMiddleware for Reject {
+        async fn request(
+            &self,
+            method: &str,
+            _: Value,
+            _: Next<'_>,
+        ) -> Result<Value, EvmClientError> {
+            Err(EvmClientError::Middleware(format!("{method} rejected")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middlewares_in_order() {
+        // nothing listens there, the requests must not reach the transport
+        let transport = Http::from_str("http://127.0.0.1:1").unwrap();
+        let intercept = Arc::new(Intercept::default());
+        let middlewares: Vec<Arc<dyn RpcMiddleware>> = vec![intercept.clone(), Arc::new(Reject)];
+        let client = EvmClient::new(transport, middlewares);
+        let provider = Provider::new(client);
+
+        assert_eq!(provider.get_chainid().await.unwrap(), 123.into());
+
+        let err = provider.get_block_number().await.unwrap_err();
+        assert!(err.to_string().contains("eth_blockNumber rejected"));
+
+        assert_eq!(
+            *intercept.seen.lock().unwrap(),
+            vec!["eth_chainId", "eth_blockNumber"]
+        );
+    }
+}
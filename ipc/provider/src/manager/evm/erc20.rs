@@ -0,0 +1,14 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The fragment of the ERC20 interface used to approve the gateway to take the tokens of the
+//! deposits to the subnets supplied with an ERC20 token.
+
+use ethers::contract::abigen;
+
+abigen!(
+    IERC20,
+    r#"[
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+    ]"#
+);
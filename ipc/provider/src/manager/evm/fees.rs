@@ -0,0 +1,253 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Fee oracle of the chain a manager sends its transactions to.
+//!
+//! The oracle keeps the base fees and the priority tips of the recent blocks, as reported by
+//! `eth_feeHistory`, and suggests the fees of a transaction from their exponentially weighted
+//! moving average instead of a single estimation per transaction. The more urgent the
+//! transaction, the higher the percentile of the tips and the headroom over the predicted
+//! base fee.
+
+use anyhow::{anyhow, Result};
+use ethers::providers::Middleware;
+use ethers::types::{BlockNumber, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// The number of blocks of fee history queried on every refresh.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+/// The number of blocks kept in the history.
+const FEE_HISTORY_CAPACITY: usize = 50;
+/// The weight of the most recent block in the moving averages.
+const EWMA_ALPHA: f64 = 0.3;
+/// The percentiles of the tips of the blocks, for each urgency.
+const TIP_PERCENTILES: [f64; 3] = [25.0, 50.0, 90.0];
+
+/// How fast a transaction needs to be included, from the least to the most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Normal,
+    /// E.g. the checkpoint submissions, which hold up the relayer until they are included.
+    High,
+}
+
+impl Urgency {
+    fn index(&self) -> usize {
+        match self {
+            Urgency::Low => 0,
+            Urgency::Normal => 1,
+            Urgency::High => 2,
+        }
+    }
+
+    /// The next more urgent tier, if any.
+    pub fn raised(&self) -> Urgency {
+        match self {
+            Urgency::Low => Urgency::Normal,
+            Urgency::Normal | Urgency::High => Urgency::High,
+        }
+    }
+
+    /// The maximum fee as a multiple of the predicted base fee, in percent.
+    fn base_fee_headroom(&self) -> u64 {
+        match self {
+            Urgency::Low => 125,
+            Urgency::Normal => 200,
+            Urgency::High => 300,
+        }
+    }
+}
+
+impl FromStr for Urgency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(Urgency::Low),
+            "normal" => Ok(Urgency::Normal),
+            "high" => Ok(Urgency::High),
+            _ => Err(anyhow!("unknown fee urgency: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuggestedFees {
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+}
+
+/// The fees paid in a block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FeeSample {
+    base_fee: U256,
+    /// The tips at the percentiles of [`TIP_PERCENTILES`].
+    tips: [U256; 3],
+}
+
+#[derive(Debug, Default)]
+struct FeeHistory {
+    samples: BTreeMap<u64, FeeSample>,
+    /// The base fee of the block after the last sampled one.
+    next_base_fee: Option<U256>,
+}
+
+impl FeeHistory {
+    fn record(&mut self, block: u64, sample: FeeSample) {
+        self.samples.insert(block, sample);
+        while self.samples.len() > FEE_HISTORY_CAPACITY {
+            self.samples.pop_first();
+        }
+    }
+
+    fn last_block(&self) -> Option<u64> {
+        self.samples.keys().next_back().copied()
+    }
+
+    fn suggest(&self, urgency: Urgency) -> Option<SuggestedFees> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let tip = ewma(self.samples.values().map(|s| s.tips[urgency.index()]));
+        let base_fee = ewma(self.samples.values().map(|s| s.base_fee));
+        // the base fee of the next block is known, the average only smooths out the drops
+        let base_fee = self
+            .next_base_fee
+            .map_or(base_fee, |next| next.max(base_fee));
+
+        Some(SuggestedFees {
+            max_priority_fee_per_gas: tip,
+            max_fee_per_gas: base_fee * urgency.base_fee_headroom() / 100 + tip,
+        })
+    }
+}
+
+/// The exponentially weighted moving average of `values`, from the oldest to the newest.
+fn ewma(values: impl Iterator<Item = U256>) -> U256 {
+    let average = values
+        .map(|v| v.min(U256::from(u128::MAX)).as_u128() as f64)
+        .reduce(|avg, v| EWMA_ALPHA * v + (1.0 - EWMA_ALPHA) * avg)
+        .unwrap_or_default();
+    U256::from(average.round() as u128)
+}
+
+/// Suggests the fees of the transactions from the recent blocks of the chain.
+#[derive(Default)]
+pub struct FeeOracle {
+    history: Mutex<FeeHistory>,
+}
+
+impl FeeOracle {
+    /// Records the fees of the recent blocks of the chain of `client`.
+    pub async fn refresh<M: Middleware>(&self, client: &M) -> Result<()> {
+        let history = client
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &TIP_PERCENTILES)
+            .await
+            .map_err(|e| anyhow!("cannot query the fee history: {e}"))?;
+
+        let oldest = history.oldest_block.as_u64();
+        let mut state = self.history.lock().unwrap();
+        let last = state.last_block();
+        for (i, (base_fee, rewards)) in history
+            .base_fee_per_gas
+            .iter()
+            .zip(history.reward.iter())
+            .enumerate()
+        {
+            let block = oldest + i as u64;
+            if last.is_some_and(|last| block <= last) {
+                continue;
+            }
+            let mut tips = [U256::zero(); 3];
+            for (tip, reward) in tips.iter_mut().zip(rewards.iter()) {
+                *tip = *reward;
+            }
+            state.record(
+                block,
+                FeeSample {
+                    base_fee: *base_fee,
+                    tips,
+                },
+            );
+        }
+        // the history includes the base fee of the block after the newest one
+        if history.base_fee_per_gas.len() > history.reward.len() {
+            state.next_base_fee = history.base_fee_per_gas.last().copied();
+        }
+        Ok(())
+    }
+
+    /// Refreshes the history and suggests the fees of a transaction of `urgency`.
+    pub async fn suggest_fees<M: Middleware>(
+        &self,
+        client: &M,
+        urgency: Urgency,
+    ) -> Result<SuggestedFees> {
+        self.refresh(client).await?;
+        self.history
+            .lock()
+            .unwrap()
+            .suggest(urgency)
+            .ok_or_else(|| anyhow!("no fee history"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ewma, FeeHistory, FeeSample, Urgency, FEE_HISTORY_CAPACITY};
+    use ethers::types::U256;
+
+    fn sample(base_fee: u64, tip: u64) -> FeeSample {
+        FeeSample {
+            base_fee: base_fee.into(),
+            tips: [(tip / 2).into(), tip.into(), (tip * 2).into()],
+        }
+    }
+
+    #[test]
+    fn test_ewma() {
+        assert_eq!(ewma([100u64].map(U256::from).into_iter()), 100.into());
+        // 0.3 * 200 + 0.7 * 100
+        assert_eq!(ewma([100u64, 200].map(U256::from).into_iter()), 130.into());
+        assert_eq!(ewma(std::iter::empty()), 0.into());
+    }
+
+    #[test]
+    fn test_suggest_by_urgency() {
+        let mut history = FeeHistory::default();
+        assert!(history.suggest(Urgency::Normal).is_none());
+
+        for block in 1..=5 {
+            history.record(block, sample(1000, 100));
+        }
+        let low = history.suggest(Urgency::Low).unwrap();
+        let normal = history.suggest(Urgency::Normal).unwrap();
+        let high = history.suggest(Urgency::High).unwrap();
+        assert_eq!(normal.max_priority_fee_per_gas, 100.into());
+        assert_eq!(normal.max_fee_per_gas, 2100.into());
+        assert_eq!(low.max_priority_fee_per_gas, 50.into());
+        assert_eq!(low.max_fee_per_gas, 1300.into());
+        assert_eq!(high.max_fee_per_gas, 3200.into());
+
+        // a rising base fee in the next block is not averaged out
+        history.next_base_fee = Some(2000.into());
+        let normal = history.suggest(Urgency::Normal).unwrap();
+        assert_eq!(normal.max_fee_per_gas, 4100.into());
+    }
+
+    #[test]
+    fn test_history_capacity() {
+        let mut history = FeeHistory::default();
+        for block in 0..(FEE_HISTORY_CAPACITY as u64 + 10) {
+            history.record(block, sample(1000, 100));
+        }
+        assert_eq!(history.samples.len(), FEE_HISTORY_CAPACITY);
+        assert_eq!(history.samples.keys().next(), Some(&10));
+        assert_eq!(history.last_block(), Some(FEE_HISTORY_CAPACITY as u64 + 9));
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Submission of the checkpoints through a forwarder contract, EIP-2771 style, so that the
+//! account paying for them differs from the submitter.
+//!
+//! The submitter signs an EIP-712 request to call the subnet actor, and the payer sends it to
+//! the forwarder, which checks the signature and nonce of the request before executing it.
+//! The interface is the one of the `MinimalForwarder` of OpenZeppelin.
+
+use crate::config::ForwarderConfig;
+use anyhow::{anyhow, Result};
+use ethers::abi::Token;
+use ethers::contract::abigen;
+use ethers::types::transaction::eip712::{EIP712Domain, Eip712, Eip712Error};
+use ethers::types::{H160, U256};
+use ethers::utils::keccak256;
+use std::str::FromStr;
+
+abigen!(
+    MinimalForwarder,
+    r#"[
+        struct ForwardRequest { address from; address to; uint256 value; uint256 gas; uint256 nonce; bytes data; }
+        function getNonce(address from) external view returns (uint256)
+        function execute(ForwardRequest req, bytes signature) external payable returns (bool, bytes)
+    ]"#
+);
+
+const FORWARD_REQUEST_TYPE: &str =
+    "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,bytes data)";
+
+pub const DEFAULT_FORWARDER_NAME: &str = "MinimalForwarder";
+pub const DEFAULT_FORWARDER_VERSION: &str = "0.0.1";
+
+/// A forwarder submitting the checkpoints signed by the submitter, paid for by `payer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forwarder {
+    pub address: H160,
+    pub payer: H160,
+    /// The name and version of the EIP-712 domain of the forwarder.
+    pub name: String,
+    pub version: String,
+    /// The gas forwarded to the submission, estimated if not set.
+    pub gas: Option<u64>,
+}
+
+impl Forwarder {
+    pub fn new(address: H160, payer: H160) -> Self {
+        Self {
+            address,
+            payer,
+            name: DEFAULT_FORWARDER_NAME.to_string(),
+            version: DEFAULT_FORWARDER_VERSION.to_string(),
+            gas: None,
+        }
+    }
+
+    pub fn with_domain(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.version = version.into();
+        self
+    }
+
+    pub fn with_gas(mut self, gas: u64) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    /// The typed data of `request` the submitter signs, for the forwarder on `chain_id`.
+    pub(crate) fn typed_request(&self, chain_id: u64, request: ForwardRequest) -> TypedRequest {
+        TypedRequest {
+            domain: EIP712Domain {
+                name: Some(self.name.clone()),
+                version: Some(self.version.clone()),
+                chain_id: Some(U256::from(chain_id)),
+                verifying_contract: Some(self.address),
+                salt: None,
+            },
+            request,
+        }
+    }
+}
+
+impl TryFrom<&ForwarderConfig> for Forwarder {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &ForwarderConfig) -> Result<Self> {
+        let address = H160::from_str(&config.address)
+            .map_err(|e| anyhow!("invalid forwarder address {}: {e}", config.address))?;
+        let payer = H160::from_str(&config.payer)
+            .map_err(|e| anyhow!("invalid forwarder payer {}: {e}", config.payer))?;
+        let mut forwarder = Forwarder::new(address, payer).with_domain(
+            config.name.as_deref().unwrap_or(DEFAULT_FORWARDER_NAME),
+            config
+                .version
+                .as_deref()
+                .unwrap_or(DEFAULT_FORWARDER_VERSION),
+        );
+        if let Some(gas) = config.gas {
+            forwarder = forwarder.with_gas(gas);
+        }
+        Ok(forwarder)
+    }
+}
+
+/// A forward request with the EIP-712 domain of its forwarder.
+#[derive(Debug, Clone)]
+pub(crate) struct TypedRequest {
+    domain: EIP712Domain,
+    request: ForwardRequest,
+}
+
+impl Eip712 for TypedRequest {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(self.domain.clone())
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(FORWARD_REQUEST_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let r = &self.request;
+        Ok(keccak256(ethers::abi::encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::Address(r.from),
+            Token::Address(r.to),
+            Token::Uint(r.value),
+            Token::Uint(r.gas),
+            Token::Uint(r.nonce),
+            Token::FixedBytes(keccak256(&r.data).to_vec()),
+        ])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ForwardRequest, Forwarder};
+    use ethers::types::transaction::eip712::{Eip712, TypedData};
+    use ethers::types::{H160, U256};
+
+    #[test]
+    fn test_typed_request_hash() {
+        let forwarder = Forwarder::new(H160::repeat_byte(1), H160::repeat_byte(2));
+        let request = ForwardRequest {
+            from: H160::repeat_byte(3),
+            to: H160::repeat_byte(4),
+            value: U256::zero(),
+            gas: U256::from(500000),
+            nonce: U256::from(7),
+            data: vec![0xde, 0xad, 0xbe, 0xef].into(),
+        };
+        let typed = forwarder.typed_request(314159, request);
+
+        // the same request hashed by the generic EIP-712 encoder
+        let json = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    {"name": "name", "type": "string"},
+                    {"name": "version", "type": "string"},
+                    {"name": "chainId", "type": "uint256"},
+                    {"name": "verifyingContract", "type": "address"}
+                ],
+                "ForwardRequest": [
+                    {"name": "from", "type": "address"},
+                    {"name": "to", "type": "address"},
+                    {"name": "value", "type": "uint256"},
+                    {"name": "gas", "type": "uint256"},
+                    {"name": "nonce", "type": "uint256"},
+                    {"name": "data", "type": "bytes"}
+                ]
+            },
+            "primaryType": "ForwardRequest",
+            "domain": {
+                "name": "MinimalForwarder",
+                "version": "0.0.1",
+                "chainId": 314159,
+                "verifyingContract": "0x0101010101010101010101010101010101010101"
+            },
+            "message": {
+                "from": "0x0303030303030303030303030303030303030303",
+                "to": "0x0404040404040404040404040404040404040404",
+                "value": 0,
+                "gas": 500000,
+                "nonce": 7,
+                "data": "0xdeadbeef"
+            }
+        });
+        let expected: TypedData = serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            typed.encode_eip712().unwrap(),
+            expected.encode_eip712().unwrap()
+        );
+    }
+}
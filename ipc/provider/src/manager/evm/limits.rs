@@ -0,0 +1,171 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Limits of the concurrent requests to the rpc endpoints, so that running many managers in
+//! the same process, e.g. the relayers of a service catching up on many subnets at once,
+//! overwhelms neither a single endpoint nor the sockets of the host.
+//!
+//! The limits have two levels:
+//! - the `provider_max_concurrency` of a subnet limits the requests in flight to its endpoint,
+//!   shared by all the managers connected to the same endpoint;
+//! - the process-global limit, set once with [`set_global_rpc_concurrency`] before connecting
+//!   to the subnets, caps the requests in flight to all the endpoints.
+//!
+//! A request waits for the permit of its endpoint before the global one, so that the requests
+//! queued behind a saturated endpoint do not hold the global permits the others need.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+static GLOBAL: OnceLock<(usize, Arc<Semaphore>)> = OnceLock::new();
+static ENDPOINTS: OnceLock<Mutex<HashMap<Url, (usize, Arc<Semaphore>)>>> = OnceLock::new();
+
+/// Caps the rpc requests in flight of the process to `limit`. The limit can only be set once,
+/// and only applies to the connections made after it is set.
+pub fn set_global_rpc_concurrency(limit: usize) -> Result<()> {
+    if limit == 0 {
+        return Err(anyhow!("the global rpc concurrency must be positive"));
+    }
+    let (current, _) = GLOBAL.get_or_init(|| (limit, Arc::new(Semaphore::new(limit))));
+    if *current != limit {
+        return Err(anyhow!("the global rpc concurrency is already {current}"));
+    }
+    Ok(())
+}
+
+/// The process-global limit of the rpc requests in flight, if set.
+pub fn global_rpc_concurrency() -> Option<usize> {
+    GLOBAL.get().map(|(limit, _)| *limit)
+}
+
+/// The permits a request to an endpoint must hold while in flight.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RpcPermits {
+    endpoint: Option<Arc<Semaphore>>,
+    global: Option<Arc<Semaphore>>,
+}
+
+/// The permits of a request, released when dropped.
+pub(crate) struct RpcPermit {
+    _endpoint: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+}
+
+impl RpcPermits {
+    /// The permits of the requests to `url`, limited to `limit` in flight if set, and to the
+    /// global limit of the process.
+    pub fn for_endpoint(url: &Url, limit: Option<usize>) -> Self {
+        let endpoint = limit.filter(|l| *l > 0).map(|limit| {
+            let mut endpoints = ENDPOINTS.get_or_init(Default::default).lock().unwrap();
+            let (current, semaphore) = endpoints
+                .entry(url.clone())
+                .or_insert_with(|| (limit, Arc::new(Semaphore::new(limit))));
+            if *current != limit {
+                log::warn!(
+                    "rpc concurrency of {url} already limited to {current}, ignoring limit {limit}"
+                );
+            }
+            semaphore.clone()
+        });
+        Self::new(endpoint, GLOBAL.get().map(|(_, s)| s.clone()))
+    }
+
+    fn new(endpoint: Option<Arc<Semaphore>>, global: Option<Arc<Semaphore>>) -> Self {
+        Self { endpoint, global }
+    }
+
+    /// Waits for the permit of the endpoint, then for the global one.
+    pub async fn acquire(&self) -> RpcPermit {
+        RpcPermit {
+            _endpoint: acquire(&self.endpoint).await,
+            _global: acquire(&self.global).await,
+        }
+    }
+}
+
+async fn acquire(semaphore: &Option<Arc<Semaphore>>) -> Option<OwnedSemaphorePermit> {
+    match semaphore {
+        // the semaphores are never closed
+        Some(s) => s.clone().acquire_owned().await.ok(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RpcPermits;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    /// Runs `tasks` requests on each of the `permits` at once, returning the maximum number of
+    /// requests in flight overall and on the busiest endpoint.
+    async fn max_in_flight(permits: Vec<RpcPermits>, tasks: usize) -> (usize, usize) {
+        let total = Arc::new(AtomicUsize::new(0));
+        let max_total = Arc::new(AtomicUsize::new(0));
+        let max_endpoint = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = vec![];
+        for permits in permits {
+            let endpoint = Arc::new(AtomicUsize::new(0));
+            for _ in 0..tasks {
+                let permits = permits.clone();
+                let (total, max_total) = (total.clone(), max_total.clone());
+                let (endpoint, max_endpoint) = (endpoint.clone(), max_endpoint.clone());
+                handles.push(tokio::spawn(async move {
+                    let _permit = permits.acquire().await;
+                    max_total.fetch_max(total.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    max_endpoint.fetch_max(
+                        endpoint.fetch_add(1, Ordering::SeqCst) + 1,
+                        Ordering::SeqCst,
+                    );
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    total.fetch_sub(1, Ordering::SeqCst);
+                    endpoint.fetch_sub(1, Ordering::SeqCst);
+                }));
+            }
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        (
+            max_total.load(Ordering::SeqCst),
+            max_endpoint.load(Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_and_global_limits() {
+        let global = Arc::new(Semaphore::new(3));
+        let endpoint = || Some(Arc::new(Semaphore::new(2)));
+
+        // one endpoint is limited by its own limit
+        let permits = vec![RpcPermits::new(endpoint(), Some(global.clone()))];
+        assert_eq!(max_in_flight(permits, 10).await, (2, 2));
+
+        // several endpoints are limited by the global one
+        let permits = (0..3)
+            .map(|_| RpcPermits::new(endpoint(), Some(global.clone())))
+            .collect();
+        assert_eq!(max_in_flight(permits, 10).await, (3, 2));
+
+        // no limit at all
+        let permits = vec![RpcPermits::default()];
+        assert_eq!(max_in_flight(permits, 5).await, (5, 5));
+    }
+
+    #[test]
+    fn test_endpoint_limit_is_shared() {
+        let url = "http://127.0.0.1:1/shared".parse().unwrap();
+        let a = RpcPermits::for_endpoint(&url, Some(4));
+        let b = RpcPermits::for_endpoint(&url, Some(4));
+        assert!(Arc::ptr_eq(
+            a.endpoint.as_ref().unwrap(),
+            b.endpoint.as_ref().unwrap()
+        ));
+        assert!(RpcPermits::for_endpoint(&url, None).endpoint.is_none());
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Decodes the lifecycle events of the subnets from the logs of their contracts.
+
+use crate::events::{BridgeDirection, SubnetEvent};
+use crate::manager::evm::manager::query_with_meta;
+use crate::manager::CollateralRelease;
+use anyhow::Result;
+use ethers::contract::abigen;
+use ethers::providers::Middleware;
+use ethers::types::ValueOrArray;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::lib_staking::LibStakingEvents;
+use ipc_actors_abis::{gateway_manager_facet, lib_gateway, lib_staking};
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::{eth_to_fil_amount, ethers_address_to_fil_address};
+use std::sync::Arc;
+
+// the release queue is a library of `LibStaking.sol` without bindings of its own
+abigen!(
+    LibStakingReleaseQueue,
+    r#"[
+        event NewCollateralRelease(address validator, uint256 amount, uint256 releaseBlock)
+    ]"#
+);
+
+/// The releases of the collateral of `validator` logged by the subnet of `subnet_actor`
+/// between the `from` and `to` blocks.
+pub(super) async fn collateral_releases<M: Middleware>(
+    client: Arc<M>,
+    subnet_actor: ethers::types::Address,
+    validator: ethers::types::Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<CollateralRelease>> {
+    let contract = LibStakingReleaseQueue::new(subnet_actor, client);
+    let ev = contract
+        .event::<NewCollateralReleaseFilter>()
+        .from_block(from)
+        .to_block(to)
+        .address(ValueOrArray::Value(subnet_actor));
+
+    let mut releases = vec![];
+    // the validator is not indexed, the releases of all the validators are filtered here
+    for (event, _) in query_with_meta(ev, contract.client()).await? {
+        if event.validator == validator {
+            releases.push(CollateralRelease {
+                amount: eth_to_fil_amount(&event.amount)?,
+                release_at: event.release_block.as_u64() as ChainEpoch,
+            });
+        }
+    }
+    Ok(releases)
+}
+
+/// The validators joining and leaving the subnet of `subnet_actor` between the `from` and `to`
+/// blocks.
+pub(super) async fn staking_events<M: Middleware>(
+    client: Arc<M>,
+    subnet_actor: ethers::types::Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<(ChainEpoch, SubnetEvent)>> {
+    let contract = lib_staking::LibStaking::new(subnet_actor, client);
+    let ev = contract
+        .events()
+        .from_block(from)
+        .to_block(to)
+        .address(ValueOrArray::Value(subnet_actor));
+
+    let mut events = vec![];
+    for (event, meta) in query_with_meta(ev, contract.client()).await? {
+        let event = match event {
+            LibStakingEvents::NewActiveValidatorFilter(e) => SubnetEvent::ValidatorJoined {
+                validator: ethers_address_to_fil_address(&e.validator)?,
+                power: eth_to_fil_amount(&e.power)?,
+                active: true,
+            },
+            LibStakingEvents::NewWaitingValidatorFilter(e) => SubnetEvent::ValidatorJoined {
+                validator: ethers_address_to_fil_address(&e.validator)?,
+                power: eth_to_fil_amount(&e.power)?,
+                active: false,
+            },
+            LibStakingEvents::ActiveValidatorLeftFilter(e) => SubnetEvent::ValidatorLeft {
+                validator: ethers_address_to_fil_address(&e.validator)?,
+            },
+            LibStakingEvents::WaitingValidatorLeftFilter(e) => SubnetEvent::ValidatorLeft {
+                validator: ethers_address_to_fil_address(&e.validator)?,
+            },
+            _ => continue,
+        };
+        events.push((meta.block_number.as_u64() as ChainEpoch, event));
+    }
+    Ok(events)
+}
+
+/// The messages sent from the `gateway` of the parent to the subnet of `subnet_actor` between
+/// the `from` and `to` blocks.
+pub(super) async fn top_down_messages<M: Middleware>(
+    client: Arc<M>,
+    gateway: ethers::types::Address,
+    subnet_actor: ethers::types::Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<(ChainEpoch, IpcEnvelope)>> {
+    let contract = gateway_manager_facet::GatewayManagerFacet::new(gateway, client);
+    let ev = contract
+        .event::<lib_gateway::NewTopDownMessageFilter>()
+        .from_block(from)
+        .to_block(to)
+        .topic1(subnet_actor)
+        .address(ValueOrArray::Value(gateway));
+
+    let mut messages = vec![];
+    for (event, meta) in query_with_meta(ev, contract.client()).await? {
+        let height = meta.block_number.as_u64() as ChainEpoch;
+        messages.push((height, IpcEnvelope::try_from(event.message)?));
+    }
+    Ok(messages)
+}
+
+/// The funds sent from the `gateway` of the parent to the subnet of `subnet_actor` between the
+/// `from` and `to` blocks.
+pub(super) async fn top_down_transfers<M: Middleware>(
+    client: Arc<M>,
+    gateway: ethers::types::Address,
+    subnet_actor: ethers::types::Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<(ChainEpoch, SubnetEvent)>> {
+    let messages = top_down_messages(client, gateway, subnet_actor, from, to).await?;
+    Ok(messages
+        .into_iter()
+        .filter_map(|(height, envelope)| {
+            SubnetEvent::bridged(BridgeDirection::TopDown, envelope).map(|event| (height, event))
+        })
+        .collect())
+}
+
+/// The funds released to the parent in the batches of bottom-up messages of the `gateway` of
+/// the subnet between the `from` and `to` blocks.
+pub(super) async fn bottom_up_transfers<M: Middleware>(
+    client: Arc<M>,
+    gateway: ethers::types::Address,
+    from: u64,
+    to: u64,
+) -> Result<Vec<(ChainEpoch, SubnetEvent)>> {
+    let contract = gateway_manager_facet::GatewayManagerFacet::new(gateway, client);
+    let ev = contract
+        .event::<lib_gateway::NewBottomUpMsgBatchFilter>()
+        .from_block(from)
+        .to_block(to)
+        .address(ValueOrArray::Value(gateway));
+
+    let mut events = vec![];
+    for (event, meta) in query_with_meta(ev, contract.client()).await? {
+        let height = meta.block_number.as_u64() as ChainEpoch;
+        for msg in event.batch.msgs {
+            let envelope = IpcEnvelope::try_from(msg)?;
+            if let Some(event) = SubnetEvent::bridged(BridgeDirection::BottomUp, envelope) {
+                events.push((height, event));
+            }
+        }
+    }
+    Ok(events)
+}
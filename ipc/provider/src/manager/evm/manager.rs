@@ -8,10 +8,10 @@ use std::time::Duration;
 
 use ethers_contract::{ContractError, EthLogDecode, LogMeta};
 use ipc_actors_abis::{
-    checkpointing_facet, gateway_getter_facet, gateway_manager_facet, gateway_messenger_facet,
-    lib_gateway, lib_quorum, lib_staking_change_log, register_subnet_facet,
-    subnet_actor_checkpointing_facet, subnet_actor_getter_facet, subnet_actor_manager_facet,
-    subnet_actor_reward_facet,
+    checkpointing_facet, diamond_cut_facet, diamond_loupe_facet, gateway_getter_facet,
+    gateway_manager_facet, gateway_messenger_facet, lib_gateway, lib_quorum,
+    lib_staking_change_log, register_subnet_facet, subnet_actor_checkpointing_facet,
+    subnet_actor_getter_facet, subnet_actor_manager_facet, subnet_actor_reward_facet,
 };
 use ipc_api::evm::{fil_to_eth_amount, payload_to_evm_address, subnet_id_to_evm_addresses};
 use ipc_api::validator::from_contract_validators;
@@ -22,22 +22,50 @@ use std::net::{IpAddr, SocketAddr};
 use ipc_api::subnet::{PermissionMode, SupplyKind, SupplySource};
 use ipc_api::{eth_to_fil_amount, ethers_address_to_fil_address};
 
+use crate::checkpoint::audit::{
+    AuditOutcome, DecodedCheckpoint, SubmissionAudit, SubmissionAuditRecord,
+};
+use crate::checkpoint::profile::{timed, Phase, SubmissionProfiler};
 use crate::config::subnet::SubnetConfig;
 use crate::config::Subnet;
+use crate::diamond::{Facet, FacetCut, FacetCutAction};
+use crate::epoch::BlockTime;
+use crate::events::SubnetEvent;
+use crate::head::{ChainHead, ChainHeadTracker};
+use crate::journal::{
+    EntryId, JournalEntry, NewEntry, TxIntent, TxJournal, TxStatus, ValueTransfer,
+};
 use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::evm::allowlist;
+use crate::manager::evm::batch::BatchRpc;
+use crate::manager::evm::bindings::CheckpointAbiVersion;
+use crate::manager::evm::capabilities::{self, RpcCapabilities};
+use crate::manager::evm::client::{EvmClient, RpcMiddleware};
+use crate::manager::evm::erc20;
+use crate::manager::evm::fees::{FeeOracle, SuggestedFees, Urgency};
+use crate::manager::evm::forwarder::{ForwardRequest, Forwarder, MinimalForwarder};
+use crate::manager::evm::limits::RpcPermits;
+use crate::manager::evm::logs;
+use crate::manager::evm::multicall::{decode_eth_balance, Multicall3, ViewCall};
+use crate::manager::evm::params::{permission_mode, supply_source, SubnetParamsCache};
+use crate::manager::evm::receipt::{ReceiptOutcome, ReceiptWaiter, WatchedTx};
+use crate::manager::evm::signer::{EvmSigner, NoSigner};
 use crate::manager::subnet::{
-    BottomUpCheckpointRelayer, GetBlockHashResult, SubnetGenesisInfo, TopDownFinalityQuery,
-    TopDownQueryPayload,
+    BottomUpCheckpointRelayer, CheckpointQuorum, CheckpointReceipt, CheckpointStatus,
+    ChildGatewayClient, ChildSubnetStatus, ContractCode, DiamondCode, EventProof,
+    GetBlockHashResult, SubmissionProof, SubnetGenesisInfo, SubnetParams, SubnetQuery, SubnetTx,
+    TopDownFinalityQuery, TopDownQueryPayload, UnsignedTransaction, UnsignedTransactionBuilder,
+    ValidatorPosition,
 };
-use crate::manager::{EthManager, SubnetManager};
+use crate::manager::EthManager;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use ethers::abi::Tokenizable;
-use ethers::prelude::k256::ecdsa::SigningKey;
+use ethers::abi::{Detokenize, Tokenizable};
 use ethers::prelude::{Signer, SignerMiddleware};
-use ethers::providers::{Authorization, Http, Middleware, Provider};
-use ethers::signers::{LocalWallet, Wallet};
-use ethers::types::{BlockId, Eip1559TransactionRequest, ValueOrArray, I256, U256};
+use ethers::providers::{Authorization, Http, Middleware, Provider, ProviderError, RpcError};
+use ethers::signers::LocalWallet;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, Eip1559TransactionRequest, ValueOrArray, U256};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_api::checkpoint::{
@@ -50,21 +78,14 @@ use ipc_api::subnet_id::SubnetID;
 use ipc_wallet::{EthKeyAddress, EvmKeyStore, PersistentKeyStore};
 use num_traits::ToPrimitive;
 use std::result;
+use std::str::FromStr;
 
-pub type DefaultSignerMiddleware = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
+pub type DefaultSignerMiddleware = SignerMiddleware<Provider<EvmClient>, EvmSigner>;
 
 /// Default polling time used by the Ethers provider to check for pending
 /// transactions and events. Default is 7, and for our child subnets we
 /// can reduce it to the block time (or potentially less)
 const ETH_PROVIDER_POLLING_TIME: Duration = Duration::from_secs(1);
-/// Maximum number of retries to fetch a transaction receipt.
-/// The number of retries should ensure that for the block time
-/// of the network the number of retires considering the polling
-/// time above waits enough tie to get the transaction receipt.
-/// We currently support a low polling time and high number of
-/// retries so these numbers accommodate fast subnets with slow
-/// roots (like Calibration and mainnet).
-const TRANSACTION_RECEIPT_RETRIES: usize = 200;
 
 /// The majority vote percentage for checkpoint submission when creating a subnet.
 const SUBNET_MAJORITY_PERCENTAGE: u8 = 67;
@@ -72,6 +93,56 @@ const SUBNET_MAJORITY_PERCENTAGE: u8 = 67;
 pub struct EthSubnetManager {
     keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
     ipc_contract_info: IPCContractInfo,
+    /// The journal every outbound transaction is recorded in before being broadcast.
+    journal: Option<Arc<TxJournal>>,
+    /// The key the transactions are journaled with, so that a retry of the same call waits for
+    /// the transaction already sent instead of sending a new one.
+    idempotency_key: Option<String>,
+    /// The checkpoint version of all the contracts, instead of detecting it.
+    pinned_abi_version: Option<CheckpointAbiVersion>,
+    /// The checkpoint versions detected in the gateway and subnet actors, by contract address.
+    abi_versions: RwLock<HashMap<ethers::types::Address, CheckpointAbiVersion>>,
+    /// Signers used instead of the keystore for their address, e.g. keys held by a remote service.
+    signers: HashMap<ethers::types::Address, EvmSigner>,
+    /// Records the timings of the transactions, if profiling.
+    profiler: Option<Arc<SubmissionProfiler>>,
+    /// Records the calldata of the checkpoint submissions, in audit mode.
+    audit: Option<Arc<SubmissionAudit>>,
+    /// Batches the queries of several heights, if the manager was built from a subnet config.
+    batch: Option<BatchRpc>,
+    /// Aggregates the view calls of several getters, if deployed.
+    multicall: Multicall3,
+    /// The parameters of the subnets queried so far, possibly shared with other managers.
+    subnet_params: Arc<SubnetParamsCache>,
+    /// Waits for the receipts of the transactions sent by the manager.
+    receipts: ReceiptWaiter<EvmClient>,
+    /// Suggests the fees of the transactions from the recent blocks.
+    fees: Arc<FeeOracle>,
+    /// The features of the endpoint, assumed supported until they are detected.
+    capabilities: RpcCapabilities,
+    /// Submits the checkpoints on behalf of the submitter, if set.
+    forwarder: Option<Forwarder>,
+}
+
+/// A transaction that was broadcast by the manager.
+struct SentTx {
+    tx_hash: ethers::types::H256,
+    from: ethers::types::Address,
+    nonce: Option<u64>,
+    /// The journal entry of the transaction, if the manager has a journal.
+    entry: Option<EntryId>,
+}
+
+impl SentTx {
+    /// The transaction of a journal entry, to wait for it again.
+    fn journaled(entry: &JournalEntry) -> Result<Self> {
+        Ok(Self {
+            tx_hash: ethers::types::H256::from_str(&entry.tx_hash)?,
+            from: ethers::types::Address::from_str(&entry.from)?,
+            nonce: Some(entry.nonce),
+            entry: Some(entry.id),
+        })
+    }
 }
 
 /// Keep track of the on chain information for the subnet manager
@@ -79,7 +150,7 @@ struct IPCContractInfo {
     gateway_addr: ethers::types::Address,
     registry_addr: ethers::types::Address,
     chain_id: u64,
-    provider: Provider<Http>,
+    provider: Provider<EvmClient>,
 }
 
 #[async_trait]
@@ -234,7 +305,7 @@ impl TopDownFinalityQuery for EthSubnetManager {
 }
 
 #[async_trait]
-impl SubnetManager for EthSubnetManager {
+impl SubnetTx for EthSubnetManager {
     async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
         self.ensure_same_gateway(&params.ipc_gateway_addr)?;
 
@@ -275,14 +346,17 @@ impl SubnetManager for EthSubnetManager {
             signer.clone(),
         );
 
-        let call = call_with_premium_estimation(signer, registry_contract.new_subnet_actor(params))
+        let call = self
+            .call_with_fees(registry_contract.new_subnet_actor(params))
             .await?;
         // TODO: Edit call to get estimate premium
-        let pending_tx = call.send().await?;
+        let sent = self
+            .send_call(&signer, call, TxIntent::CreateSubnet)
+            .await?;
         // We need the retry to parse the deployment event. At the time of this writing, it's a bug
         // in current FEVM that without the retries, events are not picked up.
         // See https://github.com/filecoin-project/community/discussions/638 for more info and updates.
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        let receipt = self.wait_receipt(sent).await?;
         match receipt {
             Some(r) => {
                 for log in r.logs {
@@ -333,13 +407,16 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.join(ethers::types::Bytes::from(pub_key));
         txn.tx.set_value(collateral);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
 
         // Use the pending state to get the nonce because there could have been a pre-fund. Best would be to use this for everything.
         let txn = txn.block(BlockId::Number(ethers::types::BlockNumber::Pending));
 
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        let intent = TxIntent::JoinSubnet {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
         block_number_from_receipt(receipt)
     }
 
@@ -358,9 +435,12 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.pre_fund();
         txn.tx.set_value(balance);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
 
-        txn.send().await?;
+        let intent = TxIntent::PreFund {
+            subnet: subnet.to_string(),
+        };
+        self.send_call(&signer, txn, intent).await?;
         Ok(())
     }
 
@@ -382,11 +462,14 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.pre_release(amount.into()))
-            .await?
-            .send()
-            .await?
+        let txn = self
+            .call_with_fees(contract.pre_release(amount.into()))
             .await?;
+        let intent = TxIntent::PreRelease {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
@@ -408,9 +491,13 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.stake();
         txn.tx.set_value(collateral);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
 
-        txn.send().await?.await?;
+        let intent = TxIntent::Stake {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
@@ -435,8 +522,14 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        let txn = call_with_premium_estimation(signer, contract.unstake(collateral.into())).await?;
-        txn.send().await?.await?;
+        let txn = self
+            .call_with_fees(contract.unstake(collateral.into()))
+            .await?;
+        let intent = TxIntent::Unstake {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
@@ -449,11 +542,12 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.leave())
-            .await?
-            .send()
-            .await?
-            .await?;
+        let txn = self.call_with_fees(contract.leave()).await?;
+        let intent = TxIntent::LeaveSubnet {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
@@ -466,39 +560,16 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.kill())
-            .await?
-            .send()
-            .await?
-            .await?;
+        let txn = self.call_with_fees(contract.kill()).await?;
+        let intent = TxIntent::KillSubnet {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
 
-    async fn list_child_subnets(
-        &self,
-        gateway_addr: Address,
-    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
-        self.ensure_same_gateway(&gateway_addr)?;
-
-        let gateway_contract = gateway_getter_facet::GatewayGetterFacet::new(
-            self.ipc_contract_info.gateway_addr,
-            Arc::new(self.ipc_contract_info.provider.clone()),
-        );
-
-        let mut s = HashMap::new();
-
-        let evm_subnets = gateway_contract.list_subnets().call().await?;
-        log::debug!("raw subnet: {evm_subnets:?}");
-
-        for subnet in evm_subnets {
-            let info = SubnetInfo::try_from(subnet)?;
-            s.insert(info.id.clone(), info);
-        }
-
-        Ok(s)
-    }
-
     async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<()> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("claim collateral evm subnet: {subnet:} at contract: {address:}");
@@ -507,11 +578,12 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_reward_facet::SubnetActorRewardFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.claim())
-            .await?
-            .send()
-            .await?
-            .await?;
+        let txn = self.call_with_fees(contract.claim()).await?;
+        let intent = TxIntent::ClaimCollateral {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
         Ok(())
     }
@@ -547,10 +619,13 @@ impl SubnetManager for EthSubnetManager {
             gateway_manager_facet::FvmAddress::try_from(to)?,
         );
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
 
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        let intent = TxIntent::Fund {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
         block_number_from_receipt(receipt)
     }
 
@@ -577,10 +652,102 @@ impl SubnetManager for EthSubnetManager {
             gateway_manager_facet::FvmAddress::try_from(to)?,
             value,
         );
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
+
+        let intent = TxIntent::FundWithToken {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
+        block_number_from_receipt(receipt)
+    }
+
+    async fn approve_token(
+        &self,
+        token: &Address,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch> {
+        let value = fil_amount_to_eth_amount(&amount)?;
+        let token_address = payload_to_evm_address(token.payload())?;
+        log::info!("approve gateway to take {value} of token {token_address:?} from {from}");
+
+        let intent = TxIntent::ApproveToken {
+            token: token.to_string(),
+        };
+        self.approve_spender(
+            token_address,
+            from,
+            self.ipc_contract_info.gateway_addr,
+            value,
+            intent,
+        )
+        .await
+    }
+
+    async fn revoke_token_approval(
+        &self,
+        token: &Address,
+        from: Address,
+        spender: &Address,
+    ) -> Result<ChainEpoch> {
+        let token_address = payload_to_evm_address(token.payload())?;
+        let spender_address = payload_to_evm_address(spender.payload())?;
+        log::info!(
+            "revoke approval of {spender_address:?} to take token {token_address:?} from {from}"
+        );
+
+        let intent = TxIntent::RevokeTokenApproval {
+            token: token.to_string(),
+            spender: spender.to_string(),
+        };
+        self.approve_spender(token_address, from, spender_address, U256::zero(), intent)
+            .await
+    }
+
+    async fn diamond_cut(
+        &self,
+        from: Address,
+        diamond: &Address,
+        cuts: Vec<FacetCut>,
+    ) -> Result<ChainEpoch> {
+        let diamond_address = payload_to_evm_address(diamond.payload())?;
+        log::info!(
+            "cut {} facets into diamond {diamond_address:?} from {from}",
+            cuts.len()
+        );
+
+        let cuts = cuts
+            .into_iter()
+            .map(|cut| {
+                // the contracts require the zero address for the removals
+                let facet_address = match cut.action {
+                    FacetCutAction::Remove => ethers::types::Address::zero(),
+                    _ => payload_to_evm_address(cut.facet.payload())?,
+                };
+                Ok(diamond_cut_facet::FacetCut {
+                    facet_address,
+                    action: cut.action.as_u8(),
+                    function_selectors: cut.selectors,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        let signer = Arc::new(self.get_signer(&from)?);
+        let contract = diamond_cut_facet::DiamondCutFacet::new(diamond_address, signer.clone());
+        let txn = self
+            .call_with_fees(contract.diamond_cut(
+                cuts,
+                ethers::types::Address::zero(),
+                ethers::types::Bytes::default(),
+            ))
+            .await?;
+
+        let intent = TxIntent::DiamondCut {
+            diamond: diamond.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
         block_number_from_receipt(receipt)
     }
 
@@ -607,10 +774,10 @@ impl SubnetManager for EthSubnetManager {
         );
         let mut txn = gateway_contract.release(gateway_manager_facet::FvmAddress::try_from(to)?);
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
+        let txn = self.call_with_fees(txn).await?;
 
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
+        let sent = self.send_call(&signer, txn, TxIntent::Release).await?;
+        let receipt = self.wait_receipt(sent).await?;
         block_number_from_receipt(receipt)
     }
 
@@ -621,6 +788,7 @@ impl SubnetManager for EthSubnetManager {
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
     ) -> Result<()> {
         if postbox_msg_key.len() != 32 {
             return Err(anyhow!(
@@ -642,10 +810,11 @@ impl SubnetManager for EthSubnetManager {
         let mut key = [0u8; 32];
         key.copy_from_slice(&postbox_msg_key);
 
-        call_with_premium_estimation(signer, gateway_contract.propagate(key))
-            .await?
-            .send()
-            .await?;
+        let call = gateway_contract
+            .propagate(key)
+            .value(fil_to_eth_amount(&fee)?);
+        let txn = self.call_with_fees(call).await?;
+        self.send_call(&signer, txn, TxIntent::Propagate).await?;
 
         Ok(())
     }
@@ -653,33 +822,273 @@ impl SubnetManager for EthSubnetManager {
     /// Send value between two addresses in a subnet
     async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
         let signer = Arc::new(self.get_signer(&from)?);
-        let (fee, fee_cap) = premium_estimation(signer.clone()).await?;
+        let fees = self.suggest_fees(Urgency::Normal).await?;
         let tx = Eip1559TransactionRequest::new()
             .to(payload_to_evm_address(to.payload())?)
             .value(fil_to_eth_amount(&amount)?)
-            .max_priority_fee_per_gas(fee)
-            .max_fee_per_gas(fee_cap);
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .max_fee_per_gas(fees.max_fee_per_gas);
+
+        let intent = TxIntent::SendValue(ValueTransfer {
+            to: to.to_string(),
+            amount: amount.atto().to_string(),
+        });
+        let sent = self
+            .send_transaction(&signer, tx.into(), None, intent)
+            .await?;
+
+        log::info!("sending FIL from {from:} to {to:} in tx {:?}", sent.tx_hash);
+        self.wait_receipt(sent).await?;
+        Ok(())
+    }
 
-        let tx_pending = signer.send_transaction(tx, None).await?;
+    async fn broadcast_raw(&self, raw_tx: Vec<u8>) -> Result<ChainEpoch> {
+        let raw_tx = ethers::types::Bytes::from(raw_tx);
+        let (tx, signature) =
+            TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(&raw_tx))
+                .context("cannot decode the signed transaction")?;
+
+        let chain_id = tx.chain_id().map(|c| c.as_u64());
+        if chain_id != Some(self.ipc_contract_info.chain_id) {
+            return Err(anyhow!(
+                "transaction signed for chain {chain_id:?}, but the subnet chain is {}",
+                self.ipc_contract_info.chain_id
+            ));
+        }
+        let from = signature.recover(tx.sighash())?;
+
+        log::info!("broadcasting transaction signed by {from:?}");
+        let sent = self
+            .broadcast(raw_tx, &tx, from, TxIntent::BroadcastRaw)
+            .await?;
+        let tx_hash = sent.tx_hash;
+
+        let receipt = self.wait_receipt(sent).await?;
+        if let Some(r) = &receipt {
+            if r.status == Some(0.into()) {
+                return Err(anyhow!("transaction {tx_hash:?} reverted"));
+            }
+        }
+        block_number_from_receipt(receipt)
+    }
+
+    async fn add_bootstrap(
+        &self,
+        subnet: &SubnetID,
+        from: &Address,
+        endpoint: String,
+    ) -> Result<()> {
+        let address = contract_address_from_subnet(subnet)?;
+
+        if is_valid_bootstrap_addr(&endpoint).is_none() {
+            return Err(anyhow!("wrong format for bootstrap endpoint"));
+        }
+
+        let signer = Arc::new(self.get_signer(from)?);
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
+
+        let txn = self
+            .call_with_fees(contract.add_bootstrap_node(endpoint))
+            .await?;
+        let intent = TxIntent::AddBootstrap {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        self.wait_receipt(sent).await?;
 
-        log::info!(
-            "sending FIL from {from:} to {to:} in tx {:?}",
-            tx_pending.tx_hash()
-        );
-        tx_pending.await?;
         Ok(())
     }
 
-    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
+    async fn set_federated_power(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        validators: &[Address],
+        public_keys: &[Vec<u8>],
+        federated_power: &[u128],
+    ) -> Result<ChainEpoch> {
+        let address = contract_address_from_subnet(subnet)?;
+        log::info!("interacting with evm subnet contract: {address:}");
+
+        let signer = Arc::new(self.get_signer(from)?);
+        let contract =
+            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
+
+        let addresses: Vec<ethers::core::types::Address> = validators
+            .iter()
+            .map(|validator_address| payload_to_evm_address(validator_address.payload()).unwrap())
+            .collect();
+        log::debug!("converted addresses: {:?}", addresses);
+
+        let pubkeys: Vec<ethers::core::types::Bytes> = public_keys
+            .iter()
+            .map(|key| ethers::core::types::Bytes::from(key.clone()))
+            .collect();
+        log::debug!("converted pubkeys: {:?}", pubkeys);
+
+        let power_u256: Vec<ethers::core::types::U256> = federated_power
+            .iter()
+            .map(|power| ethers::core::types::U256::from(*power))
+            .collect();
+        log::debug!("converted power: {:?}", power_u256);
+
+        log::debug!("from address: {:?}", from);
+
+        let call = contract.set_federated_power(addresses, pubkeys, power_u256);
+        let txn = self.call_with_fees(call).await?;
+        let intent = TxIntent::SetFederatedPower {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
+        block_number_from_receipt(receipt)
+    }
+
+    async fn set_relayer_allowed(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        relayer: &Address,
+        allowed: bool,
+    ) -> Result<ChainEpoch> {
+        let address = contract_address_from_subnet(subnet)?;
+        if !self.restricts_relayers(address).await? {
+            return Err(anyhow!(
+                "subnet {subnet} does not restrict its relayers, any relayer can submit its \
+                 checkpoints"
+            ));
+        }
+
+        let signer = Arc::new(self.get_signer(from)?);
+        let contract = allowlist::RelayerAllowlistFacet::new(address, signer.clone());
+        let call =
+            contract.set_relayer_allowed(payload_to_evm_address(relayer.payload())?, allowed);
+        let txn = self.call_with_fees(call).await?;
+        let intent = TxIntent::SetRelayerAllowed {
+            subnet: subnet.to_string(),
+        };
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
+        block_number_from_receipt(receipt)
+    }
+}
+
+#[async_trait]
+impl SubnetQuery for EthSubnetManager {
+    async fn list_child_subnets(
+        &self,
+        gateway_addr: Address,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let gateway_contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        let mut s = HashMap::new();
+
+        let block = state_block(at_height)?;
+        let evm_subnets = at_block(gateway_contract.list_subnets(), block)
+            .call()
+            .await
+            .map_err(|e| state_error(e.into(), at_height))?;
+        log::debug!("raw subnet: {evm_subnets:?}");
+
+        for subnet in evm_subnets {
+            let info = SubnetInfo::try_from(subnet)?;
+            s.insert(info.id.clone(), info);
+        }
+
+        Ok(s)
+    }
+
+    async fn wallet_balance(
+        &self,
+        address: &Address,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<TokenAmount> {
         let balance = self
             .ipc_contract_info
             .provider
             .clone()
-            .get_balance(payload_to_evm_address(address.payload())?, None)
-            .await?;
+            .get_balance(
+                payload_to_evm_address(address.payload())?,
+                state_block(at_height)?,
+            )
+            .await
+            .map_err(|e| state_error(e.into(), at_height))?;
         Ok(TokenAmount::from_atto(balance.as_u128()))
     }
 
+    async fn token_allowance(
+        &self,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+    ) -> Result<TokenAmount> {
+        let contract = erc20::IERC20::new(
+            payload_to_evm_address(token.payload())?,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let allowance = contract
+            .allowance(
+                payload_to_evm_address(owner.payload())?,
+                payload_to_evm_address(spender.payload())?,
+            )
+            .call()
+            .await?;
+        eth_to_fil_amount(&allowance)
+    }
+
+    async fn next_nonce(&self, address: &Address) -> Result<u64> {
+        let nonce = self
+            .ipc_contract_info
+            .provider
+            .get_transaction_count(
+                payload_to_evm_address(address.payload())?,
+                Some(ethers::types::BlockNumber::Pending.into()),
+            )
+            .await?;
+        Ok(nonce.as_u64())
+    }
+
+    async fn wallet_balances(
+        &self,
+        addresses: &[Address],
+        at_height: Option<ChainEpoch>,
+    ) -> Result<Vec<TokenAmount>> {
+        if addresses.len() > 1 {
+            let calls = addresses
+                .iter()
+                .map(|a| {
+                    Ok(self
+                        .multicall
+                        .eth_balance_call(payload_to_evm_address(a.payload())?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let outputs = self
+                .multicall
+                .aggregate_at(calls, state_block(at_height)?)
+                .await
+                .map_err(|e| state_error(e, at_height))?;
+            if let Some(outputs) = outputs {
+                return outputs
+                    .iter()
+                    .map(|o| Ok(TokenAmount::from_atto(decode_eth_balance(o)?.as_u128())))
+                    .collect();
+            }
+        }
+
+        let mut balances = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            balances.push(self.wallet_balance(address, at_height).await?);
+        }
+        Ok(balances)
+    }
+
     async fn get_chain_id(&self) -> Result<String> {
         Ok(self
             .ipc_contract_info
@@ -745,31 +1154,6 @@ impl SubnetManager for EthSubnetManager {
         })
     }
 
-    async fn add_bootstrap(
-        &self,
-        subnet: &SubnetID,
-        from: &Address,
-        endpoint: String,
-    ) -> Result<()> {
-        let address = contract_address_from_subnet(subnet)?;
-
-        if is_valid_bootstrap_addr(&endpoint).is_none() {
-            return Err(anyhow!("wrong format for bootstrap endpoint"));
-        }
-
-        let signer = Arc::new(self.get_signer(from)?);
-        let contract =
-            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
-
-        call_with_premium_estimation(signer, contract.add_bootstrap_node(endpoint))
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
-    }
-
     async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
         let address = contract_address_from_subnet(subnet)?;
         let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
@@ -783,65 +1167,364 @@ impl SubnetManager for EthSubnetManager {
         &self,
         subnet: &SubnetID,
         validator: &Address,
+        at_height: Option<ChainEpoch>,
     ) -> Result<ValidatorInfo> {
+        self.validator_info_at(subnet, validator, at_height)
+            .await
+            .map_err(|e| state_error(e, at_height))
+    }
+
+    async fn active_validators(
+        &self,
+        subnet: &SubnetID,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<Vec<Address>> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let validators = at_block(contract.get_active_validators(), state_block(at_height)?)
+            .call()
+            .await
+            .map_err(|e| state_error(e.into(), at_height))?;
+        validators
+            .iter()
+            .map(ethers_address_to_fil_address)
+            .collect()
+    }
+
+    async fn subnet_params(&self, subnet: &SubnetID) -> Result<SubnetParams> {
+        if let Some(params) = self.subnet_params.get(subnet) {
+            return Ok(params);
+        }
+
         let address = contract_address_from_subnet(subnet)?;
         let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
+        let gateway_call = contract.ipc_gateway_addr();
+        let period_call = contract.bottom_up_check_period();
+        let mode_call = contract.permission_mode();
+        let supply_call = contract.supply_source();
+        let (gateway, period, mode, supply) = match self
+            .multicall
+            .aggregate(vec![
+                view_call(&gateway_call)?,
+                view_call(&period_call)?,
+                view_call(&mode_call)?,
+                view_call(&supply_call)?,
+            ])
+            .await?
+        {
+            Some(outputs) => (
+                decode_view(&gateway_call, &outputs[0])?,
+                decode_view(&period_call, &outputs[1])?,
+                decode_view(&mode_call, &outputs[2])?,
+                decode_view(&supply_call, &outputs[3])?,
+            ),
+            None => (
+                gateway_call.call().await?,
+                period_call.call().await?,
+                mode_call.call().await?,
+                supply_call.call().await?,
+            ),
+        };
+
+        let params = SubnetParams {
+            gateway: ethers_address_to_fil_address(&gateway)?,
+            subnet_actor: ethers_address_to_fil_address(&address)?,
+            checkpoint_period: period.as_u64() as ChainEpoch,
+            permission_mode: permission_mode(mode)?,
+            supply_source: supply_source(supply.kind, supply.token_address)?,
+        };
+        self.subnet_params.insert(subnet.clone(), params.clone());
+        Ok(params)
+    }
+
+    fn invalidate_subnet_params(&self, subnet: Option<&SubnetID>) {
+        self.subnet_params.invalidate(subnet);
+    }
+
+    async fn chain_head_timestamp(&self) -> Result<(ChainEpoch, u64)> {
+        let latest = self
+            .ipc_contract_info
+            .provider
+            .get_block(ethers::types::BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("latest block not found"))?;
+        let height = latest
+            .number
+            .ok_or_else(|| anyhow!("latest block has no number"))?
+            .as_u64() as ChainEpoch;
+        Ok((height, latest.timestamp.as_u64()))
+    }
+
+    async fn measure_block_time(&self, window: ChainEpoch) -> Result<BlockTime> {
+        let provider = &self.ipc_contract_info.provider;
+        let latest = provider
+            .get_block(ethers::types::BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("latest block not found"))?;
+        let latest_height = latest
+            .number
+            .ok_or_else(|| anyhow!("latest block has no number"))?
+            .as_u64() as ChainEpoch;
+
+        let from_height = (latest_height - window).max(0);
+        let from = provider
+            .get_block(from_height as u64)
+            .await?
+            .ok_or_else(|| anyhow!("height {from_height} does not exist"))?;
+
+        BlockTime::measure(
+            (from_height, from.timestamp.as_u64()),
+            (latest_height, latest.timestamp.as_u64()),
+        )
+    }
+
+    async fn validator_position(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+        releases_from: ChainEpoch,
+    ) -> Result<ValidatorPosition> {
+        let address = contract_address_from_subnet(subnet)?;
+        let client = Arc::new(self.ipc_contract_info.provider.clone());
+        let contract =
+            subnet_actor_getter_facet::SubnetActorGetterFacet::new(address, client.clone());
         let validator = payload_to_evm_address(validator.payload())?;
 
-        let validator_info = contract.get_validator(validator).call().await?;
-        let is_active = contract.is_active_validator(validator).call().await?;
-        let is_waiting = contract.is_waiting_validator(validator).call().await?;
+        let info_call = contract.get_validator(validator);
+        let power_call = contract.get_power(validator);
+        let active_call = contract.is_active_validator(validator);
+        let waiting_call = contract.is_waiting_validator(validator);
+        let (info, power, is_active, is_waiting) = match self
+            .multicall
+            .aggregate(vec![
+                view_call(&info_call)?,
+                view_call(&power_call)?,
+                view_call(&active_call)?,
+                view_call(&waiting_call)?,
+            ])
+            .await?
+        {
+            Some(outputs) => (
+                decode_view(&info_call, &outputs[0])?,
+                decode_view(&power_call, &outputs[1])?,
+                decode_view(&active_call, &outputs[2])?,
+                decode_view(&waiting_call, &outputs[3])?,
+            ),
+            None => (
+                info_call.call().await?,
+                power_call.call().await?,
+                active_call.call().await?,
+                waiting_call.call().await?,
+            ),
+        };
 
-        Ok(ValidatorInfo {
-            staking: ValidatorStakingInfo::try_from(validator_info)?,
+        let height = client.get_block_number().await?.as_u64();
+        let releases = logs::collateral_releases(
+            client,
+            address,
+            validator,
+            releases_from.max(0) as u64,
+            height,
+        )
+        .await?;
+        let (unlocked, locked): (Vec<_>, Vec<_>) = releases
+            .into_iter()
+            .partition(|r| r.release_at <= height as ChainEpoch);
+
+        Ok(ValidatorPosition {
+            confirmed_collateral: eth_to_fil_amount(&info.confirmed_collateral)?,
+            total_collateral: eth_to_fil_amount(&info.total_collateral)?,
+            power: eth_to_fil_amount(&power)?,
             is_active,
             is_waiting,
+            pending_stake: eth_to_fil_amount(
+                &info
+                    .total_collateral
+                    .saturating_sub(info.confirmed_collateral),
+            )?,
+            pending_unstake: eth_to_fil_amount(
+                &info
+                    .confirmed_collateral
+                    .saturating_sub(info.total_collateral),
+            )?,
+            locked_releases: locked,
+            unlocked_collateral: unlocked
+                .into_iter()
+                .fold(TokenAmount::default(), |sum, r| sum + r.amount),
         })
     }
 
-    async fn set_federated_power(
+    async fn subnet_events_in_parent(
         &self,
-        from: &Address,
         subnet: &SubnetID,
-        validators: &[Address],
-        public_keys: &[Vec<u8>],
-        federated_power: &[u128],
-    ) -> Result<ChainEpoch> {
-        let address = contract_address_from_subnet(subnet)?;
-        log::info!("interacting with evm subnet contract: {address:}");
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, SubnetEvent)>> {
+        let client = Arc::new(self.ipc_contract_info.provider.clone());
+        let subnet_actor = contract_address_from_subnet(subnet)?;
 
-        let signer = Arc::new(self.get_signer(from)?);
-        let contract =
-            subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
+        let mut events = vec![];
+        for (from, to) in logs_ranges(from, to, self.capabilities.max_logs_range) {
+            events.extend(logs::staking_events(client.clone(), subnet_actor, from, to).await?);
+            events.extend(
+                logs::top_down_transfers(
+                    client.clone(),
+                    self.ipc_contract_info.gateway_addr,
+                    subnet_actor,
+                    from,
+                    to,
+                )
+                .await?,
+            );
+        }
+        // stable, the events of a contract keep their order within a block
+        events.sort_by_key(|(height, _)| *height);
+        Ok(events)
+    }
 
-        let addresses: Vec<ethers::core::types::Address> = validators
-            .iter()
-            .map(|validator_address| payload_to_evm_address(validator_address.payload()).unwrap())
-            .collect();
-        log::debug!("converted addresses: {:?}", addresses);
+    async fn top_down_msgs_in_parent(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, IpcEnvelope)>> {
+        let client = Arc::new(self.ipc_contract_info.provider.clone());
+        let subnet_actor = contract_address_from_subnet(subnet)?;
 
-        let pubkeys: Vec<ethers::core::types::Bytes> = public_keys
-            .iter()
-            .map(|key| ethers::core::types::Bytes::from(key.clone()))
-            .collect();
-        log::debug!("converted pubkeys: {:?}", pubkeys);
+        let mut messages = vec![];
+        for (from, to) in logs_ranges(from, to, self.capabilities.max_logs_range) {
+            messages.extend(
+                logs::top_down_messages(
+                    client.clone(),
+                    self.ipc_contract_info.gateway_addr,
+                    subnet_actor,
+                    from,
+                    to,
+                )
+                .await?,
+            );
+        }
+        Ok(messages)
+    }
 
-        let power_u256: Vec<ethers::core::types::U256> = federated_power
+    async fn subnet_events_in_child(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, SubnetEvent)>> {
+        logs::bottom_up_transfers(
+            Arc::new(self.ipc_contract_info.provider.clone()),
+            self.ipc_contract_info.gateway_addr,
+            from as u64,
+            to as u64,
+        )
+        .await
+    }
+
+    async fn subnet_killed(&self, subnet: &SubnetID) -> Result<bool> {
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            contract_address_from_subnet(subnet)?,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        Ok(contract.killed().call().await?)
+    }
+
+    async fn diamond_facets(&self, address: &Address) -> Result<Vec<Facet>> {
+        let loupe = diamond_loupe_facet::DiamondLoupeFacet::new(
+            payload_to_evm_address(address.payload())?,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        loupe
+            .facets()
+            .call()
+            .await?
+            .into_iter()
+            .map(|facet| {
+                Ok(Facet {
+                    address: ethers_address_to_fil_address(&facet.facet_address)?,
+                    selectors: facet.function_selectors,
+                })
+            })
+            .collect()
+    }
+
+    async fn relayer_allowlist(&self, subnet: &SubnetID) -> Result<Option<Vec<Address>>> {
+        let address = contract_address_from_subnet(subnet)?;
+        if !self.restricts_relayers(address).await? {
+            return Ok(None);
+        }
+
+        let contract = allowlist::RelayerAllowlistFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let relayers = contract
+            .allowed_relayers()
+            .call()
+            .await?
             .iter()
-            .map(|power| ethers::core::types::U256::from(*power))
-            .collect();
-        log::debug!("converted power: {:?}", power_u256);
+            .map(ethers_address_to_fil_address)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(relayers))
+    }
 
-        log::debug!("from address: {:?}", from);
+    async fn is_relayer_allowed(&self, subnet: &SubnetID, relayer: &Address) -> Result<bool> {
+        let address = contract_address_from_subnet(subnet)?;
+        if !self.restricts_relayers(address).await? {
+            return Ok(true);
+        }
 
-        let call = contract.set_federated_power(addresses, pubkeys, power_u256);
-        let txn = call_with_premium_estimation(signer, call).await?;
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        let contract = allowlist::RelayerAllowlistFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let allowed = contract
+            .is_relayer_allowed(payload_to_evm_address(relayer.payload())?)
+            .call()
+            .await?;
+        Ok(allowed)
+    }
+
+    async fn diamond_code(&self, address: &Address) -> Result<DiamondCode> {
+        let provider = &self.ipc_contract_info.provider;
+        let diamond = payload_to_evm_address(address.payload())?;
+        let loupe =
+            diamond_loupe_facet::DiamondLoupeFacet::new(diamond, Arc::new(provider.clone()));
+
+        let mut facets = vec![];
+        for facet in loupe.facet_addresses().call().await? {
+            facets.push(contract_code(provider, facet).await?);
+        }
+        Ok(DiamondCode {
+            diamond: contract_code(provider, diamond).await?,
+            facets,
+        })
+    }
+
+    fn postbox_key(&self, msg: &IpcEnvelope) -> Result<Vec<u8>> {
+        postbox_key(msg)
+    }
+
+    async fn in_postbox(&self, postbox_msg_key: &[u8]) -> Result<bool> {
+        let key = <[u8; 32]>::try_from(postbox_msg_key).map_err(|_| {
+            anyhow!(
+                "invalid message cid length, expect 32 but found {}",
+                postbox_msg_key.len()
+            )
+        })?;
+        let gateway_contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let msg = gateway_contract.postbox(key).call().await?;
+        // a missing message reads as the zero envelope, whose destination has no root
+        Ok(msg.to.subnet_id.root != 0)
     }
 }
 
@@ -947,22 +1630,666 @@ impl EthManager for EthSubnetManager {
 }
 
 impl EthSubnetManager {
+    /// The information of `validator` in the child `subnet`, in the state at `at_height`, the
+    /// latest one if `None`.
+    async fn validator_info_at(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<ValidatorInfo> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let validator = payload_to_evm_address(validator.payload())?;
+        let block = state_block(at_height)?;
+
+        let info_call = at_block(contract.get_validator(validator), block);
+        let active_call = at_block(contract.is_active_validator(validator), block);
+        let waiting_call = at_block(contract.is_waiting_validator(validator), block);
+        let (validator_info, is_active, is_waiting) = match self
+            .multicall
+            .aggregate_at(
+                vec![
+                    view_call(&info_call)?,
+                    view_call(&active_call)?,
+                    view_call(&waiting_call)?,
+                ],
+                block,
+            )
+            .await?
+        {
+            Some(outputs) => (
+                decode_view(&info_call, &outputs[0])?,
+                decode_view(&active_call, &outputs[1])?,
+                decode_view(&waiting_call, &outputs[2])?,
+            ),
+            None => (
+                info_call.call().await?,
+                active_call.call().await?,
+                waiting_call.call().await?,
+            ),
+        };
+
+        Ok(ValidatorInfo {
+            staking: ValidatorStakingInfo::try_from(validator_info)?,
+            is_active,
+            is_waiting,
+        })
+    }
+
     pub fn new(
         gateway_addr: ethers::types::Address,
         registry_addr: ethers::types::Address,
         chain_id: u64,
-        provider: Provider<Http>,
+        provider: Provider<EvmClient>,
         keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
     ) -> Self {
         Self {
             keystore,
+            multicall: Multicall3::new(provider.clone()),
+            receipts: ReceiptWaiter::new(provider.clone(), ETH_PROVIDER_POLLING_TIME),
+            fees: Arc::new(FeeOracle::default()),
             ipc_contract_info: IPCContractInfo {
                 gateway_addr,
                 registry_addr,
                 chain_id,
                 provider,
             },
+            journal: None,
+            idempotency_key: None,
+            pinned_abi_version: None,
+            abi_versions: RwLock::new(HashMap::new()),
+            signers: HashMap::new(),
+            profiler: None,
+            audit: None,
+            batch: None,
+            subnet_params: Arc::new(SubnetParamsCache::default()),
+            capabilities: RpcCapabilities::default(),
+            forwarder: None,
+        }
+    }
+
+    /// Records every transaction sent by the manager in `journal` before broadcasting it.
+    pub fn with_journal(mut self, journal: Arc<TxJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Journals the transactions with the idempotency `key`. A transaction whose intent was
+    /// already sent with the key is not sent again, the one journaled is waited for instead,
+    /// and sending it with other parameters fails. Requires a journal, see
+    /// [`Self::with_journal`].
+    pub fn with_idempotency_key(mut self, key: String) -> Self {
+        self.idempotency_key = Some(key);
+        self
+    }
+
+    /// Uses the bindings of `version` for all the contracts, instead of detecting their version.
+    pub fn with_abi_version(mut self, version: CheckpointAbiVersion) -> Self {
+        self.pinned_abi_version = Some(version);
+        self
+    }
+
+    /// Signs the transactions of the address of `signer` with it, instead of with the keystore.
+    pub fn with_signer(mut self, signer: impl Into<EvmSigner>) -> Self {
+        let signer = signer.into().with_chain_id(self.ipc_contract_info.chain_id);
+        self.signers.insert(signer.address(), signer);
+        self
+    }
+
+    /// Keeps the parameters of the subnets for `ttl` before querying them again.
+    pub fn with_subnet_params_ttl(mut self, ttl: Duration) -> Self {
+        self.subnet_params = Arc::new(SubnetParamsCache::new(ttl));
+        self
+    }
+
+    /// Shares the parameters of the subnets with the other managers using `cache`.
+    pub(crate) fn with_subnet_params_cache(mut self, cache: Arc<SubnetParamsCache>) -> Self {
+        self.subnet_params = cache;
+        self
+    }
+
+    /// Waits at most `timeout` for the receipt of a transaction.
+    pub fn with_receipt_timeout(mut self, timeout: Duration) -> Self {
+        self.receipts = self.receipts.with_timeout(timeout);
+        self
+    }
+
+    /// Checks the receipts of the transactions on every new head of the chain, as notified by
+    /// the websocket endpoint at `ws_url`, instead of polling for them.
+    pub fn with_ws_url(mut self, ws_url: Option<url::Url>) -> Self {
+        self.receipts = self.receipts.with_ws_url(ws_url);
+        self
+    }
+
+    /// Records the timings of the gas estimation, broadcast and receipt wait of the
+    /// transactions with `profiler`.
+    pub fn with_profiler(mut self, profiler: Arc<SubmissionProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Records every checkpoint submission attempt in `audit`, with its exact calldata.
+    pub fn with_audit(mut self, audit: Arc<SubmissionAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Submits the checkpoints through `forwarder`: the submitter only signs them, the payer
+    /// of the forwarder sends and pays for the transactions.
+    pub fn with_forwarder(mut self, forwarder: Forwarder) -> Self {
+        self.forwarder = Some(forwarder);
+        self
+    }
+
+    /// Probes the features of the endpoint, logs them and adapts the strategies of the manager
+    /// to them: polling for the receipts and the chain head without websocket subscriptions,
+    /// serial queries without batches, legacy transactions without EIP-1559 and the gas price
+    /// without the fee history, and chunked log queries if their range is limited.
+    pub async fn with_detected_capabilities(mut self) -> Self {
+        let capabilities = capabilities::probe(
+            &self.ipc_contract_info.provider,
+            self.batch.as_ref(),
+            self.receipts.ws_url(),
+        )
+        .await;
+        log::info!(
+            "rpc capabilities of chain {}: {capabilities}",
+            self.ipc_contract_info.chain_id
+        );
+
+        if !capabilities.ws_subscriptions {
+            self.receipts = self.receipts.with_ws_url(None);
+        }
+        if !capabilities.batch_requests {
+            self.batch = None;
+        }
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// The features of the endpoint, see [`Self::with_detected_capabilities`].
+    pub fn capabilities(&self) -> &RpcCapabilities {
+        &self.capabilities
+    }
+
+    /// Reconciles the pending journal entries signed for this chain against the chain state.
+    /// Transactions unknown to the node whose nonce is still available are broadcast again, and
+    /// only marked as failed if the node rejects them. The ones whose nonce was consumed by
+    /// another transaction are marked as replaced.
+    pub async fn reconcile_journal(&self) -> Result<()> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+        for entry in journal
+            .pending()
+            .into_iter()
+            .filter(|e| e.chain_id == self.ipc_contract_info.chain_id)
+        {
+            self.reconcile_entry(journal, &entry).await?;
+        }
+        Ok(())
+    }
+
+    /// Reconciles a pending journal entry against the chain state, see
+    /// [`Self::reconcile_journal`], and returns its new status.
+    async fn reconcile_entry(&self, journal: &TxJournal, entry: &JournalEntry) -> Result<TxStatus> {
+        let provider = &self.ipc_contract_info.provider;
+        let tx_hash = ethers::types::H256::from_str(&entry.tx_hash)?;
+
+        let status = if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+            log::info!("journaled tx {} was included on chain", entry.tx_hash);
+            status_from_receipt(&receipt)
+        } else if provider.get_transaction(tx_hash).await?.is_some() {
+            log::info!("journaled tx {} is still in the mempool", entry.tx_hash);
+            TxStatus::Broadcast
+        } else {
+            let from = ethers::types::Address::from_str(&entry.from)?;
+            let nonce = provider
+                .get_transaction_count(from, Some(ethers::types::BlockNumber::Latest.into()))
+                .await?;
+            if nonce.as_u64() > entry.nonce {
+                log::warn!(
+                    "nonce {} of journaled tx {} was consumed by another transaction",
+                    entry.nonce,
+                    entry.tx_hash
+                );
+                TxStatus::Replaced
+            } else {
+                let raw_tx = ethers::types::Bytes::from_str(&entry.raw_tx)?;
+                match provider.send_raw_transaction(raw_tx).await {
+                    Ok(_) => {
+                        log::info!("resumed broadcast of journaled tx {}", entry.tx_hash);
+                        TxStatus::Broadcast
+                    }
+                    Err(e) => match BroadcastError::classify(&e) {
+                        BroadcastError::Known => TxStatus::Broadcast,
+                        BroadcastError::Rejected => {
+                            log::error!("journaled tx {} rejected: {e}", entry.tx_hash);
+                            TxStatus::Failed {
+                                reason: e.to_string(),
+                            }
+                        }
+                        BroadcastError::Unknown => {
+                            log::warn!(
+                                "cannot resume journaled tx {}, left pending: {e}",
+                                entry.tx_hash
+                            );
+                            entry.status.clone()
+                        }
+                    },
+                }
+            }
+        };
+
+        if status != entry.status {
+            journal.set_status(entry.id, status.clone())?;
+        }
+        Ok(status)
+    }
+
+    /// The fees of a transaction of `urgency`, from the recent blocks of the chain.
+    async fn suggest_fees(&self, urgency: Urgency) -> Result<SuggestedFees> {
+        if !self.capabilities.fee_history || !self.capabilities.eip1559 {
+            let gas_price = self.ipc_contract_info.provider.get_gas_price().await?;
+            return Ok(SuggestedFees {
+                max_priority_fee_per_gas: gas_price,
+                max_fee_per_gas: gas_price,
+            });
+        }
+        self.fees
+            .suggest_fees(&self.ipc_contract_info.provider, urgency)
+            .await
+    }
+
+    /// Sets the allowance of `spender` over the ERC20 `token` of `from` to `value`.
+    async fn approve_spender(
+        &self,
+        token: ethers::types::Address,
+        from: Address,
+        spender: ethers::types::Address,
+        value: U256,
+        intent: TxIntent,
+    ) -> Result<ChainEpoch> {
+        let signer = Arc::new(self.get_signer(&from)?);
+        let contract = erc20::IERC20::new(token, signer.clone());
+        let txn = self
+            .call_with_fees(contract.approve(spender, value))
+            .await?;
+
+        let sent = self.send_call(&signer, txn, intent).await?;
+        let receipt = self.wait_receipt(sent).await?;
+        block_number_from_receipt(receipt)
+    }
+
+    /// Sets the fees of a contract call with normal urgency.
+    async fn call_with_fees<B, D, M>(
+        &self,
+        mut call: ethers_contract::FunctionCall<B, D, M>,
+    ) -> Result<ethers_contract::FunctionCall<B, D, M>>
+    where
+        B: std::borrow::Borrow<D>,
+        M: ethers::abi::Detokenize,
+    {
+        let fees = self.suggest_fees(Urgency::Normal).await?;
+        set_fees(&mut call.tx, fees, !self.capabilities.eip1559);
+        Ok(call)
+    }
+
+    /// Signs and broadcasts the transaction of a contract call, see [`Self::send_transaction`].
+    async fn send_call<D>(
+        &self,
+        signer: &Arc<DefaultSignerMiddleware>,
+        call: ethers_contract::FunctionCall<
+            Arc<DefaultSignerMiddleware>,
+            DefaultSignerMiddleware,
+            D,
+        >,
+        intent: TxIntent,
+    ) -> Result<SentTx>
+    where
+        D: ethers::abi::Detokenize,
+    {
+        self.send_transaction(signer, call.tx, call.block, intent)
+            .await
+    }
+
+    /// Fills in and signs the transaction, records it in the journal, if any, and broadcasts it.
+    /// The transaction already sent for `intent` with the idempotency key, if any, is returned
+    /// instead.
+    async fn send_transaction(
+        &self,
+        signer: &Arc<DefaultSignerMiddleware>,
+        mut tx: TypedTransaction,
+        block: Option<BlockId>,
+        intent: TxIntent,
+    ) -> Result<SentTx> {
+        if let (Some(journal), Some(key)) = (&self.journal, &self.idempotency_key) {
+            if let Some(entry) = journal.find_by_key(key, &intent, &params_hash(&tx))? {
+                // the broadcast of a transaction still signed may have failed, or not
+                let status = match entry.status {
+                    TxStatus::Signed => self.reconcile_entry(journal, &entry).await?,
+                    ref status => status.clone(),
+                };
+                if status.is_pending() || matches!(status, TxStatus::Confirmed { .. }) {
+                    log::info!(
+                        "tx {} already sent with idempotency key {key}, waiting for it",
+                        entry.tx_hash
+                    );
+                    return SentTx::journaled(&entry);
+                }
+            }
+        }
+
+        let profiler = self.profiler.as_deref();
+        timed(
+            profiler,
+            Phase::GasEstimate,
+            signer.fill_transaction(&mut tx, block),
+        )
+        .await?;
+
+        timed(profiler, Phase::Broadcast, async {
+            let signature = signer.signer().sign_transaction(&tx).await?;
+            let raw_tx = tx.rlp_signed(&signature);
+            self.broadcast(raw_tx, &tx, signer.address(), intent).await
+        })
+        .await
+    }
+
+    /// Records the signed transaction in the journal, if any, and broadcasts it.
+    async fn broadcast(
+        &self,
+        raw_tx: ethers::types::Bytes,
+        tx: &TypedTransaction,
+        from: ethers::types::Address,
+        intent: TxIntent,
+    ) -> Result<SentTx> {
+        let tx_hash = ethers::types::H256::from(ethers::utils::keccak256(&raw_tx));
+
+        let entry = match &self.journal {
+            Some(journal) => {
+                let calldata = tx.data().map(|d| d.to_vec()).unwrap_or_default();
+                Some(journal.record(NewEntry {
+                    intent,
+                    chain_id: self.ipc_contract_info.chain_id,
+                    from: format!("{from:?}"),
+                    nonce: tx.nonce().map(|n| n.as_u64()).unwrap_or_default(),
+                    calldata_hash: format!("0x{}", hex::encode(ethers::utils::keccak256(calldata))),
+                    tx_hash: format!("{tx_hash:?}"),
+                    raw_tx: raw_tx.to_string(),
+                    idempotency_key: self.idempotency_key.clone(),
+                    params_hash: Some(params_hash(tx)),
+                })?)
+            }
+            None => None,
+        };
+
+        if let Err(e) = self
+            .ipc_contract_info
+            .provider
+            .send_raw_transaction(raw_tx)
+            .await
+        {
+            match BroadcastError::classify(&e) {
+                BroadcastError::Known => {
+                    log::info!("tx {tx_hash:?} known to the node already");
+                }
+                BroadcastError::Rejected => {
+                    if let (Some(journal), Some(id)) = (&self.journal, entry) {
+                        journal.set_status(
+                            id,
+                            TxStatus::Failed {
+                                reason: e.to_string(),
+                            },
+                        )?;
+                    }
+                    return Err(e.into());
+                }
+                // the transaction may have reached the node, the entry stays signed for the
+                // reconciliation to find it from its hash and nonce
+                BroadcastError::Unknown => return Err(e.into()),
+            }
         }
+
+        if let (Some(journal), Some(id)) = (&self.journal, entry) {
+            journal.set_status(id, TxStatus::Broadcast)?;
+        }
+
+        Ok(SentTx {
+            tx_hash,
+            from,
+            nonce: tx.nonce().map(|n| n.as_u64()),
+            entry,
+        })
+    }
+
+    /// Waits for the receipt of a broadcast transaction and updates its journal entry. There is
+    /// no receipt if the transaction is still pending at the deadline, and it fails if the
+    /// transaction was replaced.
+    async fn wait_receipt(
+        &self,
+        sent: SentTx,
+    ) -> Result<Option<ethers::types::TransactionReceipt>> {
+        let watched = WatchedTx {
+            hash: sent.tx_hash,
+            from: sent.from,
+            nonce: sent.nonce,
+        };
+        let outcome = timed(
+            self.profiler.as_deref(),
+            Phase::ReceiptWait,
+            self.receipts.wait(&watched),
+        )
+        .await?;
+
+        match outcome {
+            ReceiptOutcome::Mined(receipt) => {
+                if let (Some(journal), Some(id)) = (&self.journal, sent.entry) {
+                    journal.set_status(id, status_from_receipt(&receipt))?;
+                }
+                Ok(Some(receipt))
+            }
+            ReceiptOutcome::Replaced => {
+                if let (Some(journal), Some(id)) = (&self.journal, sent.entry) {
+                    journal.set_status(id, TxStatus::Replaced)?;
+                }
+                Err(anyhow!(
+                    "tx {:?} was replaced by another transaction with the same nonce",
+                    sent.tx_hash
+                ))
+            }
+            ReceiptOutcome::TimedOut => {
+                log::warn!("no receipt for tx {:?} before the deadline", sent.tx_hash);
+                Ok(None)
+            }
+        }
+    }
+
+    /// The checkpoint version of the contract at `address`, detected the first time it is
+    /// needed. Contract upgrades are picked up on restart.
+    async fn abi_version(&self, address: ethers::types::Address) -> Result<CheckpointAbiVersion> {
+        if let Some(v) = self.pinned_abi_version {
+            return Ok(v);
+        }
+        if let Some(v) = self.abi_versions.read().unwrap().get(&address) {
+            return Ok(*v);
+        }
+
+        let client = Arc::new(self.ipc_contract_info.provider.clone());
+        let version = if address == self.ipc_contract_info.gateway_addr {
+            CheckpointAbiVersion::detect_gateway(client, address).await?
+        } else {
+            CheckpointAbiVersion::detect_subnet_actor(client, address).await?
+        };
+        log::info!("detected checkpoint version {version} in contract {address:?}");
+
+        self.abi_versions.write().unwrap().insert(address, version);
+        Ok(version)
+    }
+
+    async fn submit_checkpoint_calldata(
+        &self,
+        checkpoint: BottomUpCheckpoint,
+        signatures: Vec<Signature>,
+        signatories: Vec<Address>,
+    ) -> Result<ethers::types::Bytes> {
+        let address = contract_address_from_subnet(&checkpoint.subnet_id)?;
+        let signatures = signatures
+            .into_iter()
+            .map(ethers::types::Bytes::from)
+            .collect::<Vec<_>>();
+        let signatories = signatories
+            .into_iter()
+            .map(|addr| payload_to_evm_address(addr.payload()))
+            .collect::<result::Result<Vec<_>, _>>()?;
+
+        self.abi_version(address)
+            .await?
+            .bindings()
+            .encode_submit_checkpoint(checkpoint, signatories, signatures)
+    }
+
+    /// Whether the subnet actor at `address` restricts its relayers to an allowlist.
+    async fn restricts_relayers(&self, address: ethers::types::Address) -> Result<bool> {
+        let facets = self
+            .diamond_facets(&ethers_address_to_fil_address(&address)?)
+            .await?;
+        Ok(allowlist::serves_allowlist(&facets))
+    }
+
+    /// Sends the submission of a checkpoint with `calldata` and waits for its receipt, setting
+    /// `tx_hash` once sent.
+    async fn send_checkpoint(
+        &self,
+        submitter: &Address,
+        address: ethers::types::Address,
+        calldata: ethers::types::Bytes,
+        urgency: Urgency,
+        intent: TxIntent,
+        tx_hash: &mut Option<ethers::types::H256>,
+    ) -> Result<CheckpointReceipt> {
+        let (signer, address, calldata) = match &self.forwarder {
+            Some(forwarder) => {
+                let calldata = self
+                    .forward_calldata(forwarder, submitter, address, calldata)
+                    .await?;
+                let payer = ethers_address_to_fil_address(&forwarder.payer)?;
+                (self.get_signer(&payer)?, forwarder.address, calldata)
+            }
+            None => (self.get_signer(submitter)?, address, calldata),
+        };
+        let signer = Arc::new(signer);
+        let fees = timed(
+            self.profiler.as_deref(),
+            Phase::GasEstimate,
+            self.suggest_fees(urgency),
+        )
+        .await?;
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(address)
+            .data(calldata)
+            .into();
+        set_fees(&mut tx, fees, !self.capabilities.eip1559);
+
+        let sent = self.send_transaction(&signer, tx, None, intent).await?;
+        *tx_hash = Some(sent.tx_hash);
+        let receipt = self.wait_receipt(sent).await?;
+        checkpoint_receipt(receipt)
+    }
+
+    /// The calldata executing the call of `calldata` to `to` through `forwarder`, in a request
+    /// signed by `submitter`.
+    async fn forward_calldata(
+        &self,
+        forwarder: &Forwarder,
+        submitter: &Address,
+        to: ethers::types::Address,
+        calldata: ethers::types::Bytes,
+    ) -> Result<ethers::types::Bytes> {
+        let signer = self.get_signer(submitter)?;
+        let from = signer.address();
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
+        let contract = MinimalForwarder::new(forwarder.address, provider.clone());
+
+        let nonce = contract.get_nonce(from).call().await?;
+        let gas = match forwarder.gas {
+            Some(gas) => U256::from(gas),
+            None => {
+                let tx: TypedTransaction = Eip1559TransactionRequest::new()
+                    .from(from)
+                    .to(to)
+                    .data(calldata.clone())
+                    .into();
+                provider.estimate_gas(&tx, None).await?
+            }
+        };
+        let request = ForwardRequest {
+            from,
+            to,
+            value: U256::zero(),
+            gas,
+            nonce,
+            data: calldata,
+        };
+        let typed = forwarder.typed_request(self.ipc_contract_info.chain_id, request.clone());
+        let signature = signer.signer().sign_typed_data(&typed).await?;
+
+        let call = contract
+            .execute(request, signature.to_vec().into())
+            .from(forwarder.payer);
+        // the forwarder does not revert when the forwarded call does
+        let (success, output) = call.call().await?;
+        if !success {
+            return Err(anyhow!(
+                "forwarded call reverted: 0x{}",
+                hex::encode(output)
+            ));
+        }
+        call.calldata()
+            .ok_or_else(|| anyhow!("cannot encode the forward request"))
+    }
+
+    /// Fills in the fields of a transaction from `from` without signing it: the next nonce of
+    /// the sender, the estimated gas and fees.
+    async fn unsigned_transaction(
+        &self,
+        from: &Address,
+        to: ethers::types::Address,
+        data: ethers::types::Bytes,
+        value: U256,
+    ) -> Result<UnsignedTransaction> {
+        let from = payload_to_evm_address(from.payload())?;
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
+
+        let nonce = provider
+            .get_transaction_count(from, Some(ethers::types::BlockNumber::Pending.into()))
+            .await?;
+        let fees = self.suggest_fees(Urgency::Normal).await?;
+
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .nonce(nonce)
+            .value(value)
+            .data(data.clone())
+            .into();
+        let gas_limit = provider.estimate_gas(&tx, None).await?;
+
+        Ok(UnsignedTransaction {
+            chain_id: self.ipc_contract_info.chain_id,
+            from,
+            to,
+            nonce: nonce.as_u64(),
+            value,
+            data,
+            gas_limit: gas_limit.as_u64(),
+            max_fee_per_gas: fees.max_fee_per_gas,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        })
     }
 
     pub fn ensure_same_gateway(&self, gateway: &Address) -> Result<()> {
@@ -986,23 +2313,58 @@ impl EthSubnetManager {
     fn get_signer(&self, addr: &Address) -> Result<DefaultSignerMiddleware> {
         // convert to its underlying eth address
         let addr = payload_to_evm_address(addr.payload())?;
-        let keystore = self.keystore()?;
+        if let Some(signer) = self.signers.get(&addr) {
+            return Ok(SignerMiddleware::new(
+                self.ipc_contract_info.provider.clone(),
+                signer.clone(),
+            ));
+        }
+
+        let no_signer = NoSigner {
+            address: Some(addr),
+        };
+        let keystore = self.keystore.as_ref().ok_or_else(|| no_signer.clone())?;
         let keystore = keystore.read().unwrap();
-        let private_key = keystore
-            .get(&addr.into())?
-            .ok_or_else(|| anyhow!("address {addr:} does not have private key in key store"))?;
+        let private_key = keystore.get(&addr.into())?.ok_or_else(|| {
+            anyhow!(no_signer).context(format!(
+                "address {addr:} does not have private key in key store"
+            ))
+        })?;
         let wallet = LocalWallet::from_bytes(private_key.private_key())?
             .with_chain_id(self.ipc_contract_info.chain_id);
 
         Ok(SignerMiddleware::new(
             self.ipc_contract_info.provider.clone(),
-            wallet,
+            EvmSigner::Local(wallet),
         ))
     }
 
+    /// Connects to `subnet` without a keystore, for monitoring: the queries work while the
+    /// transactions fail with [`NoSigner`], unless a signer is added with [`Self::with_signer`].
+    pub fn read_only(subnet: &Subnet) -> Result<Self> {
+        Self::from_subnet_with_wallet_store(subnet, None)
+    }
+
+    /// Whether the manager has neither a keystore nor signers to send transactions with.
+    pub fn is_read_only(&self) -> bool {
+        self.keystore.is_none() && self.signers.is_empty()
+    }
+
     pub fn from_subnet_with_wallet_store(
         subnet: &Subnet,
         keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+    ) -> Result<Self> {
+        Self::from_subnet_with_middlewares(subnet, keystore, vec![])
+    }
+
+    /// Connects to `subnet` like [`Self::from_subnet_with_wallet_store`], with the requests to
+    /// the subnet going through `middlewares`, the first one being the outermost. The batched
+    /// queries are then sent one by one, for the middlewares to see them, while the websocket
+    /// subscriptions to the receipts do not go through the middlewares.
+    pub fn from_subnet_with_middlewares(
+        subnet: &Subnet,
+        keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+        middlewares: Vec<Arc<dyn RpcMiddleware>>,
     ) -> Result<Self> {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
@@ -1016,35 +2378,409 @@ impl EthSubnetManager {
             let mut auth_value = HeaderValue::from_str(&auth.to_string())?;
             auth_value.set_sensitive(true);
 
-            let mut headers = reqwest::header::HeaderMap::new();
-            headers.insert(reqwest::header::AUTHORIZATION, auth_value);
+            let mut headers = reqwest::header::HeaderMap::new();
+            headers.insert(reqwest::header::AUTHORIZATION, auth_value);
+
+            client = client.default_headers(headers);
+        }
+
+        if let Some(timeout) = subnet.rpc_timeout() {
+            client = client.timeout(timeout);
+        }
+
+        if let Some(proxy) = subnet.proxy() {
+            log::debug!("routing rpc traffic of subnet {} through proxy", subnet.id);
+            client = client.proxy(crate::proxy::reqwest_proxy(proxy)?);
+        }
+
+        let client = client.build()?;
+        let permits = RpcPermits::for_endpoint(&url, subnet.rpc_max_concurrency());
+
+        // the batches go to the transport directly, they are skipped for the middlewares to see
+        // every request
+        let batch = middlewares
+            .is_empty()
+            .then(|| BatchRpc::new(client.clone(), url.clone()).with_permits(permits.clone()));
+        let provider =
+            EvmClient::new(Http::new_with_client(url, client), middlewares).with_permits(permits);
+
+        let mut provider = Provider::new(provider);
+        // set polling interval for provider to fit fast child subnets block times.
+        // TODO: We may want to make it dynamic so it adjusts depending on the type of network
+        // so we don't have a too slow or too fast polling for the underlying block times.
+        provider.set_interval(ETH_PROVIDER_POLLING_TIME);
+        let gateway_address = payload_to_evm_address(config.gateway_addr.payload())?;
+        let registry_address = payload_to_evm_address(config.registry_addr.payload())?;
+
+        // the websocket connection does not go through the proxy
+        let ws_url = match subnet.rpc_ws() {
+            Some(ws) if subnet.proxy().is_none() => Some(ws.clone()),
+            Some(_) => {
+                log::debug!(
+                    "not using the websocket of subnet {} with a proxy",
+                    subnet.id
+                );
+                None
+            }
+            None => None,
+        };
+
+        let mut manager = Self::new(
+            gateway_address,
+            registry_address,
+            subnet.id.chain_id(),
+            provider,
+            keystore,
+        )
+        .with_ws_url(ws_url);
+        manager.batch = batch;
+        Ok(manager)
+    }
+
+    /// The latest, safe and finalized heights of the chain. The chains without the `safe`
+    /// and `finalized` block tags, like the subnets with instant finality, are final at their
+    /// latest height.
+    pub async fn chain_head(&self) -> Result<ChainHead> {
+        query_chain_head(
+            &self.ipc_contract_info.provider,
+            self.capabilities.finality_tags,
+        )
+        .await
+    }
+
+    /// Tracks the head of the chain in the background, on the new heads notified by the
+    /// websocket endpoint if there is one and every `poll_interval` otherwise.
+    pub fn chain_head_tracker(
+        &self,
+        label: impl Into<String>,
+        poll_interval: Duration,
+    ) -> ChainHeadTracker {
+        let provider = self.ipc_contract_info.provider.clone();
+        let finality_tags = self.capabilities.finality_tags;
+        ChainHeadTracker::spawn(
+            label,
+            poll_interval,
+            self.receipts.ws_url().cloned(),
+            move || {
+                let provider = provider.clone();
+                async move { query_chain_head(&provider, finality_tags).await }
+            },
+        )
+    }
+
+    /// The batch client, if there is more than one height to query.
+    fn batch_for(&self, heights: &[ChainEpoch]) -> Option<&BatchRpc> {
+        self.batch
+            .as_ref()
+            .filter(|b| heights.len() > 1 && b.is_supported())
+    }
+
+    /// The filter of the quorum reached events at `height`.
+    fn quorum_reached_filter(&self, height: ChainEpoch) -> ethers::types::Filter {
+        let contract = checkpointing_facet::CheckpointingFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        contract
+            .event::<lib_quorum::QuorumReachedFilter>()
+            .from_block(height as u64)
+            .to_block(height as u64)
+            .address(ValueOrArray::Value(contract.address()))
+            .filter
+    }
+}
+
+#[async_trait]
+impl UnsignedTransactionBuilder for EthSubnetManager {
+    async fn unsigned_fund(
+        &self,
+        subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<UnsignedTransaction> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let value = amount
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid value to fund"))?;
+
+        let gateway_contract = gateway_manager_facet::GatewayManagerFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let call = gateway_contract.fund(
+            gateway_manager_facet::SubnetID::try_from(&subnet)?,
+            gateway_manager_facet::FvmAddress::try_from(to)?,
+        );
+
+        self.unsigned_transaction(
+            &from,
+            self.ipc_contract_info.gateway_addr,
+            call.calldata().unwrap_or_default(),
+            U256::from(value),
+        )
+        .await
+    }
+
+    async fn unsigned_release(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<UnsignedTransaction> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let value = amount
+            .atto()
+            .to_u128()
+            .ok_or_else(|| anyhow!("invalid value to fund"))?;
+
+        let gateway_contract = gateway_manager_facet::GatewayManagerFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let call = gateway_contract.release(gateway_manager_facet::FvmAddress::try_from(to)?);
+
+        self.unsigned_transaction(
+            &from,
+            self.ipc_contract_info.gateway_addr,
+            call.calldata().unwrap_or_default(),
+            U256::from(value),
+        )
+        .await
+    }
+
+    async fn unsigned_propagate(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
+    ) -> Result<UnsignedTransaction> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let key = <[u8; 32]>::try_from(postbox_msg_key.as_slice()).map_err(|_| {
+            anyhow!(
+                "invalid message cid length, expect 32 but found {}",
+                postbox_msg_key.len()
+            )
+        })?;
+        let gateway_contract = gateway_messenger_facet::GatewayMessengerFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let call = gateway_contract.propagate(key);
+
+        self.unsigned_transaction(
+            &from,
+            self.ipc_contract_info.gateway_addr,
+            call.calldata().unwrap_or_default(),
+            fil_to_eth_amount(&fee)?,
+        )
+        .await
+    }
+
+    async fn unsigned_submit_checkpoint(
+        &self,
+        submitter: &Address,
+        checkpoint: BottomUpCheckpoint,
+        signatures: Vec<Signature>,
+        signatories: Vec<Address>,
+    ) -> Result<UnsignedTransaction> {
+        let address = contract_address_from_subnet(&checkpoint.subnet_id)?;
+        let calldata = self
+            .submit_checkpoint_calldata(checkpoint, signatures, signatories)
+            .await?;
+        self.unsigned_transaction(submitter, address, calldata, U256::zero())
+            .await
+    }
+}
+
+#[async_trait]
+impl ChildGatewayClient for EthSubnetManager {
+    async fn checkpoint_bundle_at(
+        &self,
+        height: ChainEpoch,
+    ) -> anyhow::Result<BottomUpCheckpointBundle> {
+        let gateway = self.ipc_contract_info.gateway_addr;
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
+        self.abi_version(gateway)
+            .await?
+            .bindings()
+            .checkpoint_bundle_at(provider, gateway, height)
+            .await
+    }
+
+    async fn checkpoint_bundles_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<BottomUpCheckpointBundle>> {
+        let gateway = self.ipc_contract_info.gateway_addr;
+        if let Some(batch) = self.batch_for(heights) {
+            let bindings = self.abi_version(gateway).await?.bindings();
+            // the bundles of the versions needing several calls are queried serially
+            if let Some(calls) = heights
+                .iter()
+                .map(|h| bindings.checkpoint_bundle_call(*h))
+                .collect::<Option<Vec<_>>>()
+            {
+                let params = calls
+                    .into_iter()
+                    .map(|data| serde_json::json!([{ "to": gateway, "data": data }, "latest"]))
+                    .collect();
+                if let Some(outputs) = batch
+                    .call::<ethers::types::Bytes>("eth_call", params)
+                    .await?
+                {
+                    return outputs
+                        .iter()
+                        .map(|output| bindings.decode_checkpoint_bundle(output))
+                        .collect();
+                }
+            }
+        }
+
+        let mut bundles = Vec::with_capacity(heights.len());
+        for h in heights {
+            bundles.push(self.checkpoint_bundle_at(*h).await?);
+        }
+        Ok(bundles)
+    }
+
+    async fn checkpoint_quorum_at(&self, height: ChainEpoch) -> Result<CheckpointQuorum> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        let (_, info, _, _) = contract
+            .get_checkpoint_signature_bundle(U256::from(height))
+            .call()
+            .await?;
+        let majority_percentage = contract.majority_percentage().call().await?;
+
+        Ok(CheckpointQuorum {
+            threshold: eth_to_fil_amount(&info.threshold)?,
+            current_weight: eth_to_fil_amount(&info.current_weight)?,
+            majority_percentage,
+        })
+    }
+
+    async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>> {
+        let contract = checkpointing_facet::CheckpointingFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        let ev = contract
+            .event::<lib_quorum::QuorumReachedFilter>()
+            .from_block(height as u64)
+            .to_block(height as u64)
+            .address(ValueOrArray::Value(contract.address()));
+
+        let mut events = vec![];
+        for (event, meta) in query_with_meta(ev, contract.client()).await? {
+            events.push(quorum_reached_event(event, Some(meta.block_hash))?);
+        }
 
-            client = client.default_headers(headers);
+        Ok(events)
+    }
+
+    async fn quorum_reached_events_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<Vec<QuorumReachedEvent>>> {
+        if let Some(batch) = self.batch_for(heights) {
+            let params = heights
+                .iter()
+                .map(|h| serde_json::to_value([self.quorum_reached_filter(*h)]))
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(logs) = batch
+                .call::<Vec<ethers::types::Log>>("eth_getLogs", params)
+                .await?
+            {
+                return logs
+                    .into_iter()
+                    .map(|logs| {
+                        logs.into_iter()
+                            .filter(|l| !l.removed.unwrap_or_default())
+                            .map(|log| {
+                                let block_hash = log.block_hash;
+                                quorum_reached_event(
+                                    ethers::contract::parse_log::<lib_quorum::QuorumReachedFilter>(
+                                        log,
+                                    )?,
+                                    block_hash,
+                                )
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })
+                    .collect();
+            }
         }
 
-        if let Some(timeout) = subnet.rpc_timeout() {
-            client = client.timeout(timeout);
+        let mut events = Vec::with_capacity(heights.len());
+        for h in heights {
+            events.push(self.quorum_reached_events(*h).await?);
         }
+        Ok(events)
+    }
+    async fn current_epoch(&self) -> Result<ChainEpoch> {
+        let epoch = self
+            .ipc_contract_info
+            .provider
+            .get_block_number()
+            .await?
+            .as_u64();
+        Ok(epoch as ChainEpoch)
+    }
 
-        let client = client.build()?;
+    async fn block_hash_at(&self, height: ChainEpoch) -> Result<Vec<u8>> {
+        Ok(self.get_block_hash(height).await?.block_hash)
+    }
+
+    async fn block_proposer_at(&self, height: ChainEpoch) -> Result<Address> {
+        let block = self
+            .ipc_contract_info
+            .provider
+            .get_block(height as u64)
+            .await?
+            .ok_or_else(|| anyhow!("height does not exist"))?;
+        let author = block
+            .author
+            .ok_or_else(|| anyhow!("block {height} has no proposer"))?;
+        ethers_address_to_fil_address(&author)
+    }
 
-        let provider = Http::new_with_client(url, client);
+    async fn block_timestamp_at(&self, height: ChainEpoch) -> Result<u64> {
+        let block = self
+            .ipc_contract_info
+            .provider
+            .get_block(height as u64)
+            .await?
+            .ok_or_else(|| anyhow!("height does not exist"))?;
+        Ok(block.timestamp.as_u64())
+    }
 
-        let mut provider = Provider::new(provider);
-        // set polling interval for provider to fit fast child subnets block times.
-        // TODO: We may want to make it dynamic so it adjusts depending on the type of network
-        // so we don't have a too slow or too fast polling for the underlying block times.
-        provider.set_interval(ETH_PROVIDER_POLLING_TIME);
-        let gateway_address = payload_to_evm_address(config.gateway_addr.payload())?;
-        let registry_address = payload_to_evm_address(config.registry_addr.payload())?;
+    async fn gateway_checkpoint_period(&self) -> Result<ChainEpoch> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let period = contract.bottom_up_check_period().call().await?.as_u64();
+        Ok(period as ChainEpoch)
+    }
 
-        Ok(Self::new(
-            gateway_address,
-            registry_address,
-            subnet.id.chain_id(),
-            provider,
-            keystore,
-        ))
+    async fn gateway_subnet_id(&self) -> Result<SubnetID> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        SubnetID::try_from(contract.get_network_name().call().await?)
     }
 }
 
@@ -1056,35 +2792,91 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
         checkpoint: BottomUpCheckpoint,
         signatures: Vec<Signature>,
         signatories: Vec<Address>,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<CheckpointReceipt> {
+        // the relayer does not move on until the checkpoint is committed
+        self.submit_checkpoint_with_urgency(
+            submitter,
+            checkpoint,
+            signatures,
+            signatories,
+            Urgency::High,
+        )
+        .await
+    }
+
+    async fn submit_checkpoint_with_urgency(
+        &self,
+        submitter: &Address,
+        checkpoint: BottomUpCheckpoint,
+        signatures: Vec<Signature>,
+        signatories: Vec<Address>,
+        urgency: Urgency,
+    ) -> anyhow::Result<CheckpointReceipt> {
         let address = contract_address_from_subnet(&checkpoint.subnet_id)?;
         log::debug!(
             "submit bottom up checkpoint: {checkpoint:?} in evm subnet contract: {address:}"
         );
 
-        let signatures = signatures
-            .into_iter()
-            .map(ethers::types::Bytes::from)
-            .collect::<Vec<_>>();
-        let signatories = signatories
-            .into_iter()
-            .map(|addr| payload_to_evm_address(addr.payload()))
-            .collect::<result::Result<Vec<_>, _>>()?;
-
-        let checkpoint =
-            subnet_actor_checkpointing_facet::BottomUpCheckpoint::try_from(checkpoint)?;
+        let intent = TxIntent::SubmitCheckpoint {
+            subnet: checkpoint.subnet_id.to_string(),
+            height: checkpoint.block_height,
+        };
+        if let Some(journal) = &self.journal {
+            if let Some(entry) = journal.find_pending(&intent) {
+                // the transaction may have dropped out of the mempool since it was sent, it is
+                // broadcast again or, if it can no longer land, submitted anew
+                let status = self.reconcile_entry(journal, &entry).await?;
+                if status.is_pending() || matches!(status, TxStatus::Confirmed { .. }) {
+                    log::info!(
+                        "checkpoint at height {} already submitted in tx {}, waiting for it",
+                        checkpoint.block_height,
+                        entry.tx_hash
+                    );
+                    let sent = SentTx::journaled(&entry)?;
+                    return checkpoint_receipt(self.wait_receipt(sent).await?);
+                }
+            }
+        }
 
-        let signer = Arc::new(self.get_signer(submitter)?);
-        let contract = subnet_actor_checkpointing_facet::SubnetActorCheckpointingFacet::new(
-            address,
-            signer.clone(),
-        );
-        let call = contract.submit_checkpoint(checkpoint, signatories, signatures);
-        let call = call_with_premium_estimation(signer, call).await?;
+        let decoded = self
+            .audit
+            .as_ref()
+            .map(|_| DecodedCheckpoint::new(&checkpoint, &signatures, &signatories));
+        let calldata = self
+            .submit_checkpoint_calldata(checkpoint, signatures, signatories)
+            .await?;
 
-        let pending_tx = call.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        let mut tx_hash = None;
+        let result = self
+            .send_checkpoint(
+                submitter,
+                address,
+                calldata.clone(),
+                urgency,
+                intent,
+                &mut tx_hash,
+            )
+            .await;
+
+        if let (Some(audit), Some(decoded)) = (&self.audit, decoded) {
+            // detected already to encode the calldata
+            let version = match self.abi_version(address).await {
+                Ok(v) => v.to_string(),
+                Err(e) => format!("unknown: {e}"),
+            };
+            let record = SubmissionAuditRecord::new(
+                submitter,
+                address,
+                version,
+                &calldata,
+                decoded,
+                AuditOutcome::new(&result, tx_hash),
+            );
+            if let Err(e) = audit.record(&record) {
+                log::warn!("cannot record the submission audit: {e:#}");
+            }
+        }
+        result
     }
 
     async fn last_bottom_up_checkpoint_height(
@@ -1100,200 +2892,347 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
         Ok(epoch.as_u64() as ChainEpoch)
     }
 
-    async fn checkpoint_period(&self, subnet_id: &SubnetID) -> anyhow::Result<ChainEpoch> {
+    async fn committed_checkpoint_at(
+        &self,
+        subnet_id: &SubnetID,
+        height: ChainEpoch,
+    ) -> anyhow::Result<Option<BottomUpCheckpoint>> {
         let address = contract_address_from_subnet(subnet_id)?;
         let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let epoch = contract.bottom_up_check_period().call().await?;
-        Ok(epoch.as_u64() as ChainEpoch)
+        let (exists, checkpoint) = contract
+            .bottom_up_checkpoint_at_epoch(U256::from(height))
+            .call()
+            .await?;
+        if !exists {
+            return Ok(None);
+        }
+        Ok(Some(BottomUpCheckpoint::try_from(checkpoint)?))
     }
 
-    async fn checkpoint_bundle_at(
-        &self,
-        height: ChainEpoch,
-    ) -> anyhow::Result<BottomUpCheckpointBundle> {
-        let contract = gateway_getter_facet::GatewayGetterFacet::new(
-            self.ipc_contract_info.gateway_addr,
+    async fn checkpoint_period(&self, subnet_id: &SubnetID) -> anyhow::Result<ChainEpoch> {
+        Ok(self.subnet_params(subnet_id).await?.checkpoint_period)
+    }
+
+    async fn checkpoint_status(&self, subnet_id: &SubnetID) -> Result<CheckpointStatus> {
+        let address = contract_address_from_subnet(subnet_id)?;
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
+        let period_call = contract.bottom_up_check_period();
+        let height_call = contract.last_bottom_up_checkpoint_height();
+        let (period, height) = match self
+            .multicall
+            .aggregate(vec![view_call(&period_call)?, view_call(&height_call)?])
+            .await?
+        {
+            Some(outputs) => (
+                decode_view(&period_call, &outputs[0])?,
+                decode_view(&height_call, &outputs[1])?,
+            ),
+            None => (period_call.call().await?, height_call.call().await?),
+        };
+        Ok(CheckpointStatus {
+            period: period.as_u64() as ChainEpoch,
+            last_committed_height: height.as_u64() as ChainEpoch,
+        })
+    }
 
-        let (checkpoint, _, signatories, signatures) = contract
-            .get_checkpoint_signature_bundle(U256::from(height))
-            .call()
-            .await?;
-        let checkpoint = BottomUpCheckpoint::try_from(checkpoint)?;
-        let signatories = signatories
-            .into_iter()
-            .map(|s| ethers_address_to_fil_address(&s))
-            .collect::<Result<Vec<_>, _>>()?;
-        let signatures = signatures
-            .into_iter()
-            .map(|s| s.to_vec())
-            .collect::<Vec<_>>();
+    async fn reconcile_pending_txs(&self) -> Result<()> {
+        self.reconcile_journal().await
+    }
 
-        Ok(BottomUpCheckpointBundle {
-            checkpoint,
-            signatures,
-            signatories,
+    async fn submission_proof(&self, receipt: &CheckpointReceipt) -> Result<SubmissionProof> {
+        let provider = &self.ipc_contract_info.provider;
+        let tx_hash = ethers::types::H256::from_str(&receipt.tx_hash)?;
+
+        let tx = provider
+            .get_transaction(tx_hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {tx_hash:?} not found"))?;
+        let tx_receipt = provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .ok_or_else(|| anyhow!("no receipt for transaction {tx_hash:?}"))?;
+        let block_hash = tx_receipt
+            .block_hash
+            .ok_or_else(|| anyhow!("transaction {tx_hash:?} is not in a block"))?;
+        let block = provider
+            .get_block(block_hash)
+            .await?
+            .ok_or_else(|| anyhow!("block {block_hash:?} not found"))?;
+
+        Ok(SubmissionProof {
+            tx_hash: format!("{tx_hash:?}"),
+            calldata: format!("0x{}", hex::encode(&tx.input)),
+            block_number: receipt.epoch,
+            block_hash: format!("{block_hash:?}"),
+            receipts_root: format!("{:?}", block.receipts_root),
+            events: tx_receipt
+                .logs
+                .iter()
+                .map(|log| EventProof {
+                    address: format!("{:?}", log.address),
+                    topics: log.topics.iter().map(|t| format!("{t:?}")).collect(),
+                    data: format!("0x{}", hex::encode(&log.data)),
+                    log_index: log.log_index.map(|i| i.as_u64()),
+                })
+                .collect(),
         })
     }
 
-    async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>> {
-        let contract = checkpointing_facet::CheckpointingFacet::new(
-            self.ipc_contract_info.gateway_addr,
-            Arc::new(self.ipc_contract_info.provider.clone()),
-        );
-
-        let ev = contract
-            .event::<lib_quorum::QuorumReachedFilter>()
-            .from_block(height as u64)
-            .to_block(height as u64)
-            .address(ValueOrArray::Value(contract.address()));
+    async fn child_subnet_status(&self, subnet_id: &SubnetID) -> Result<ChildSubnetStatus> {
+        let address = contract_address_from_subnet(subnet_id)?;
+        let provider = Arc::new(self.ipc_contract_info.provider.clone());
 
-        let mut events = vec![];
-        for (event, _meta) in query_with_meta(ev, contract.client()).await? {
-            events.push(QuorumReachedEvent {
-                obj_kind: event.obj_kind,
-                height: event.height.as_u64() as ChainEpoch,
-                obj_hash: event.obj_hash.to_vec(),
-                quorum_weight: eth_to_fil_amount(&event.quorum_weight)?,
-            });
+        if provider.get_code(address, None).await?.is_empty() {
+            return Ok(ChildSubnetStatus::Deregistered);
+        }
+        let actor =
+            subnet_actor_getter_facet::SubnetActorGetterFacet::new(address, provider.clone());
+        if actor.killed().call().await? {
+            return Ok(ChildSubnetStatus::Killed);
         }
 
-        Ok(events)
+        // the subnets are registered in the gateway when they bootstrap, until they are killed
+        if actor.bootstrapped().call().await? {
+            let gateway = gateway_getter_facet::GatewayGetterFacet::new(
+                self.ipc_contract_info.gateway_addr,
+                provider,
+            );
+            let (exists, _) = gateway
+                .get_subnet(gateway_getter_facet::SubnetID::try_from(subnet_id)?)
+                .call()
+                .await?;
+            if !exists {
+                return Ok(ChildSubnetStatus::Deregistered);
+            }
+        }
+        Ok(ChildSubnetStatus::Active)
     }
-    async fn current_epoch(&self) -> Result<ChainEpoch> {
-        let epoch = self
-            .ipc_contract_info
-            .provider
-            .get_block_number()
-            .await?
-            .as_u64();
-        Ok(epoch as ChainEpoch)
+
+    async fn sign_message(&self, signer: &Address, message: &[u8]) -> Result<Vec<u8>> {
+        let signer = self.get_signer(signer)?;
+        let signature = signer.signer().sign_message(message).await?;
+        Ok(signature.to_vec())
     }
 }
 
-/// Receives an input `FunctionCall` and returns a new instance
-/// after estimating an optimal `gas_premium` for the transaction
-pub(crate) async fn call_with_premium_estimation<B, D, M>(
-    signer: Arc<DefaultSignerMiddleware>,
-    call: ethers_contract::FunctionCall<B, D, M>,
-) -> Result<ethers_contract::FunctionCall<B, D, M>>
+/// The contract address and calldata of a getter call, to aggregate it with others.
+fn view_call<B, M, D>(call: &ethers_contract::FunctionCall<B, M, D>) -> Result<ViewCall>
 where
-    B: std::borrow::Borrow<D>,
-    M: ethers::abi::Detokenize,
+    B: Borrow<M>,
+    M: Middleware,
+    D: Detokenize,
 {
-    let (max_priority_fee_per_gas, _) = premium_estimation(signer).await?;
-    Ok(call.gas_price(max_priority_fee_per_gas))
+    let to = call
+        .tx
+        .to_addr()
+        .copied()
+        .ok_or_else(|| anyhow!("view call without contract address"))?;
+    let data = call
+        .calldata()
+        .ok_or_else(|| anyhow!("view call without calldata"))?;
+    Ok((to, data))
 }
 
-/// Returns an estimation of an optimal `gas_premium` and `gas_fee_cap`
-/// for a transaction considering the average premium, base_fee and reward percentile from
-/// past blocks
-/// This is adaptation of ethers' `eip1559_default_estimator`:
-/// https://github.com/gakonst/ethers-rs/blob/5dcd3b7e754174448f9a8cbfc0523896609629f9/ethers-core/src/utils/mod.rs#L476
-async fn premium_estimation(
-    signer: Arc<DefaultSignerMiddleware>,
-) -> Result<(ethers::types::U256, ethers::types::U256)> {
-    let base_fee_per_gas = signer
-        .get_block(ethers::types::BlockNumber::Latest)
-        .await?
-        .ok_or_else(|| anyhow!("Latest block not found"))?
-        .base_fee_per_gas
-        .ok_or_else(|| anyhow!("EIP-1559 not activated"))?;
-
-    let fee_history = signer
-        .fee_history(
-            ethers::utils::EIP1559_FEE_ESTIMATION_PAST_BLOCKS,
-            ethers::types::BlockNumber::Latest,
-            &[ethers::utils::EIP1559_FEE_ESTIMATION_REWARD_PERCENTILE],
-        )
-        .await?;
+/// Decodes the output of an aggregated getter call.
+/// The block of the state at `at_height`, the latest one if `None`.
+fn state_block(at_height: Option<ChainEpoch>) -> Result<Option<BlockId>> {
+    at_height
+        .map(|h| {
+            let h = u64::try_from(h).map_err(|_| anyhow!("invalid height {h}"))?;
+            Ok(BlockId::Number(ethers::types::BlockNumber::Number(
+                h.into(),
+            )))
+        })
+        .transpose()
+}
 
-    let max_priority_fee_per_gas = estimate_priority_fee(fee_history.reward); //overestimate?
-    let potential_max_fee = base_fee_surged(base_fee_per_gas);
-    let max_fee_per_gas = if max_priority_fee_per_gas > potential_max_fee {
-        max_priority_fee_per_gas + potential_max_fee
-    } else {
-        potential_max_fee
-    };
+/// Sets the block of the state read by `call`, the latest one if `None`.
+fn at_block<B, M, D>(
+    call: ethers_contract::FunctionCall<B, M, D>,
+    block: Option<BlockId>,
+) -> ethers_contract::FunctionCall<B, M, D>
+where
+    B: Borrow<M>,
+    M: Middleware,
+    D: Detokenize,
+{
+    match block {
+        Some(block) => call.block(block),
+        None => call,
+    }
+}
+
+/// Explains the failures of the queries of past states, which only archive nodes keep.
+fn state_error(e: anyhow::Error, at_height: Option<ChainEpoch>) -> anyhow::Error {
+    match at_height {
+        Some(h) => e.context(format!(
+            "cannot query the state at height {h}, the node must be an archive node keeping it"
+        )),
+        None => e,
+    }
+}
+
+fn decode_view<B, M, D: Detokenize>(
+    call: &ethers_contract::FunctionCall<B, M, D>,
+    output: &[u8],
+) -> Result<D> {
+    Ok(D::from_tokens(call.function.decode_output(output)?)?)
+}
 
-    Ok((max_priority_fee_per_gas, max_fee_per_gas))
+/// The event does not carry its signatories, they are only known once the bundle is read.
+fn quorum_reached_event(
+    event: lib_quorum::QuorumReachedFilter,
+    block_hash: Option<ethers::types::H256>,
+) -> Result<QuorumReachedEvent> {
+    Ok(QuorumReachedEvent {
+        obj_kind: event.obj_kind,
+        height: event.height.as_u64() as ChainEpoch,
+        obj_hash: event.obj_hash.to_vec(),
+        quorum_weight: eth_to_fil_amount(&event.quorum_weight)?,
+        signatories: None,
+        block_hash: block_hash.map(|h| h.as_bytes().to_vec()),
+    })
 }
 
-/// Implementation borrowed from
-/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L582
-/// Refer to the implementation for unit tests
-fn base_fee_surged(base_fee_per_gas: U256) -> U256 {
-    if base_fee_per_gas <= U256::from(40_000_000_000u64) {
-        base_fee_per_gas * 2
-    } else if base_fee_per_gas <= U256::from(100_000_000_000u64) {
-        base_fee_per_gas * 16 / 10
-    } else if base_fee_per_gas <= U256::from(200_000_000_000u64) {
-        base_fee_per_gas * 14 / 10
-    } else {
-        base_fee_per_gas * 12 / 10
+/// Sets the suggested fees of a transaction, turning it into a legacy one if `legacy`.
+fn set_fees(tx: &mut TypedTransaction, fees: SuggestedFees, legacy: bool) {
+    if legacy {
+        if let TypedTransaction::Eip1559(inner) = tx {
+            *tx = TypedTransaction::Legacy(inner.clone().into());
+        }
+    }
+    match tx {
+        TypedTransaction::Eip1559(tx) => {
+            tx.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+            tx.max_fee_per_gas = Some(fees.max_fee_per_gas);
+        }
+        tx => tx.set_gas_price(fees.max_fee_per_gas),
     }
 }
 
-/// Implementation borrowed from
-/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L536
-/// Refer to the implementation for unit tests
-fn estimate_priority_fee(rewards: Vec<Vec<U256>>) -> U256 {
-    let mut rewards: Vec<U256> = rewards
-        .iter()
-        .map(|r| r[0])
-        .filter(|r| *r > U256::zero())
-        .collect();
-    if rewards.is_empty() {
-        return U256::zero();
-    }
-    if rewards.len() == 1 {
-        return rewards[0];
-    }
-    // Sort the rewards as we will eventually take the median.
-    rewards.sort();
-
-    // A copy of the same vector is created for convenience to calculate percentage change
-    // between subsequent fee values.
-    let mut rewards_copy = rewards.clone();
-    rewards_copy.rotate_left(1);
-
-    let mut percentage_change: Vec<I256> = rewards
-        .iter()
-        .zip(rewards_copy.iter())
-        .map(|(a, b)| {
-            let a = I256::try_from(*a).expect("priority fee overflow");
-            let b = I256::try_from(*b).expect("priority fee overflow");
-            ((b - a) * 100) / a
-        })
-        .collect();
-    percentage_change.pop();
-
-    // Fetch the max of the percentage change, and that element's index.
-    let max_change = percentage_change.iter().max().unwrap();
-    let max_change_index = percentage_change
-        .iter()
-        .position(|&c| c == *max_change)
-        .unwrap();
-
-    // If we encountered a big change in fees at a certain position, then consider only
-    // the values >= it.
-    let values = if *max_change >= ethers::utils::EIP1559_FEE_ESTIMATION_THRESHOLD_MAX_CHANGE.into()
-        && (max_change_index >= (rewards.len() / 2))
-    {
-        rewards[max_change_index..].to_vec()
-    } else {
-        rewards
+/// The hash of the code deployed at `address`, failing if there is none.
+async fn contract_code(
+    provider: &Provider<EvmClient>,
+    address: ethers::types::Address,
+) -> Result<ContractCode> {
+    let code = provider.get_code(address, None).await?;
+    if code.is_empty() {
+        return Err(anyhow!("no contract deployed at {address:?}"));
+    }
+    Ok(ContractCode {
+        address: ethers_address_to_fil_address(&address)?,
+        code_hash: ethers::utils::keccak256(&code),
+    })
+}
+
+/// The key of `msg` in the postbox of the gateway, the hash of its ABI encoding.
+pub(crate) fn postbox_key(msg: &IpcEnvelope) -> Result<Vec<u8>> {
+    let msg = gateway_getter_facet::IpcEnvelope::try_from(msg.clone())?;
+    let encoded = ethers::abi::encode(&[msg.into_token()]);
+    Ok(ethers::utils::keccak256(encoded).to_vec())
+}
+
+/// Splits the blocks from `from` to `to`, inclusive, in ranges of at most `max_range` blocks.
+fn logs_ranges(from: ChainEpoch, to: ChainEpoch, max_range: Option<u64>) -> Vec<(u64, u64)> {
+    let (from, to) = (from.max(0) as u64, to.max(0) as u64);
+    let Some(max_range) = max_range.filter(|r| *r > 0) else {
+        return vec![(from, to)];
     };
+    (from..=to)
+        .step_by(max_range as usize)
+        .map(|start| (start, to.min(start + max_range - 1)))
+        .collect()
+}
+
+/// The hex encoded hash of the recipient, value and calldata of `tx`, i.e. of the parameters of
+/// the call it makes, unlike its fees and nonce.
+fn params_hash(tx: &TypedTransaction) -> String {
+    let mut value = [0u8; 32];
+    tx.value()
+        .copied()
+        .unwrap_or_default()
+        .to_big_endian(&mut value);
+    let to = tx.to_addr().copied().unwrap_or_default();
+    let data = tx.data().map(|d| d.to_vec()).unwrap_or_default();
+    let params = [to.as_bytes(), &value, &data].concat();
+    format!("0x{}", hex::encode(ethers::utils::keccak256(params)))
+}
+
+/// The outcome of a failed broadcast of a raw transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BroadcastError {
+    /// The node has the transaction already, e.g. from a previous attempt.
+    Known,
+    /// The node rejected the transaction, it is never mined.
+    Rejected,
+    /// The transaction may or may not have reached the node, e.g. after a timeout.
+    Unknown,
+}
+
+impl BroadcastError {
+    /// The answers of the nodes meaning the transaction is known or its nonce is used already,
+    /// which the transaction itself may have done.
+    const KNOWN: [&'static str; 3] = ["already known", "known transaction", "already imported"];
+    const NONCE_USED: [&'static str; 2] = ["nonce too low", "replacement transaction underpriced"];
+
+    fn classify(e: &ProviderError) -> Self {
+        // only an error answer of the node is definitive, not a failure of the transport
+        let Some(response) = e.as_error_response() else {
+            return BroadcastError::Unknown;
+        };
+        let message = response.message.to_lowercase();
+        if Self::KNOWN.iter().any(|m| message.contains(m)) {
+            BroadcastError::Known
+        } else if Self::NONCE_USED.iter().any(|m| message.contains(m)) {
+            BroadcastError::Unknown
+        } else {
+            BroadcastError::Rejected
+        }
+    }
+}
 
-    // Return the median.
-    values[values.len() / 2]
+/// The journal status of a transaction from its receipt
+fn status_from_receipt(receipt: &ethers::types::TransactionReceipt) -> TxStatus {
+    match (receipt.status.map(|s| s.as_u64()), receipt.block_number) {
+        (Some(1), Some(block)) => TxStatus::Confirmed {
+            block: block.as_u64() as ChainEpoch,
+        },
+        _ => TxStatus::Failed {
+            reason: "transaction reverted".to_string(),
+        },
+    }
 }
 
 /// Get the block number from the transaction receipt
+fn checkpoint_receipt(
+    receipt: Option<ethers::types::TransactionReceipt>,
+) -> Result<CheckpointReceipt> {
+    let gas_used = receipt
+        .as_ref()
+        .and_then(|r| r.gas_used)
+        .map(|g| g.as_u64());
+    let effective_gas_price = receipt
+        .as_ref()
+        .and_then(|r| r.effective_gas_price)
+        .map(|p| p.as_u64());
+    let tx_hash = receipt
+        .as_ref()
+        .map(|r| format!("{:?}", r.transaction_hash));
+    let epoch = block_number_from_receipt(receipt)?;
+    Ok(CheckpointReceipt {
+        epoch,
+        tx_hash: tx_hash.unwrap_or_default(),
+        gas_used,
+        effective_gas_price,
+    })
+}
+
 fn block_number_from_receipt(
     receipt: Option<ethers::types::TransactionReceipt>,
 ) -> Result<ChainEpoch> {
@@ -1328,7 +3267,7 @@ fn is_valid_bootstrap_addr(input: &str) -> Option<(String, IpAddr, u16)> {
 /// This is a replacement for `Event::query_with_meta` in `ethers-contract`
 /// because in that one we don't get access to the `reverted` field, which
 /// we need to filteron in the currently deployed `1.25-rc4` version of Lotus.
-async fn query_with_meta<B, M, D>(
+pub(super) async fn query_with_meta<B, M, D>(
     event: ethers::contract::Event<B, M, D>,
     client: B,
 ) -> Result<Vec<(D, LogMeta)>, ContractError<M>>
@@ -1383,6 +3322,35 @@ pub(crate) fn contract_address_from_subnet(subnet: &SubnetID) -> Result<ethers::
     payload_to_evm_address(ipc_addr.payload())
 }
 
+/// The head of the chain of `provider`, see [`EthSubnetManager::chain_head`]. The block tags
+/// are not queried without `finality_tags`.
+async fn query_chain_head(
+    provider: &Provider<EvmClient>,
+    finality_tags: bool,
+) -> Result<ChainHead> {
+    let latest = provider.get_block_number().await?.as_u64() as ChainEpoch;
+    if !finality_tags {
+        return Ok(ChainHead {
+            latest,
+            safe: latest,
+            finalized: latest,
+        });
+    }
+    let tagged = |tag: ethers::types::BlockNumber| async move {
+        match provider.get_block(tag).await {
+            Ok(Some(block)) => block.number.map(|n| n.as_u64() as ChainEpoch),
+            _ => None,
+        }
+    };
+    let safe = tagged(ethers::types::BlockNumber::Safe).await;
+    let finalized = tagged(ethers::types::BlockNumber::Finalized).await;
+    Ok(ChainHead {
+        latest,
+        safe: safe.unwrap_or(latest),
+        finalized: finalized.unwrap_or(latest),
+    })
+}
+
 impl TryFrom<gateway_getter_facet::Subnet> for SubnetInfo {
     type Error = anyhow::Error;
 
@@ -1398,8 +3366,16 @@ impl TryFrom<gateway_getter_facet::Subnet> for SubnetInfo {
 
 #[cfg(test)]
 mod tests {
-    use crate::manager::evm::manager::contract_address_from_subnet;
+    use crate::config::subnet::{EVMSubnet, SubnetConfig};
+    use crate::config::Subnet;
+    use crate::manager::evm::manager::{
+        contract_address_from_subnet, logs_ranges, postbox_key, state_block, state_error,
+        BroadcastError,
+    };
+    use crate::manager::{EthSubnetManager, NoSigner, SubnetTx};
     use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::evm::payload_to_evm_address;
     use ipc_api::subnet_id::SubnetID;
     use std::str::FromStr;
 
@@ -1414,4 +3390,115 @@ mod tests {
             "0x2e714a3c385ea88a09998ed74db265dae9853667"
         );
     }
+
+    #[test]
+    fn test_logs_ranges() {
+        assert_eq!(logs_ranges(10, 20, None), vec![(10, 20)]);
+        assert_eq!(
+            logs_ranges(10, 34, Some(10)),
+            vec![(10, 19), (20, 29), (30, 34)]
+        );
+        assert_eq!(logs_ranges(10, 10, Some(10)), vec![(10, 10)]);
+    }
+
+    #[test]
+    fn test_state_block() {
+        use ethers::types::{BlockId, BlockNumber};
+
+        assert_eq!(state_block(None).unwrap(), None);
+        assert_eq!(
+            state_block(Some(42)).unwrap(),
+            Some(BlockId::Number(BlockNumber::Number(42.into())))
+        );
+        assert!(state_block(Some(-1)).is_err());
+
+        let err = state_error(anyhow::anyhow!("missing trie node"), Some(42));
+        assert!(format!("{err:#}").contains("at height 42"));
+        let err = state_error(anyhow::anyhow!("missing trie node"), None);
+        assert_eq!(err.to_string(), "missing trie node");
+    }
+
+    #[test]
+    fn test_postbox_key() {
+        use ipc_api::cross::IpcEnvelope;
+
+        let addr = Address::from_str("f410ffzyuupbyl2uiucmzr3lu3mtf3luyknthaz4xsrq").unwrap();
+        let subnet = SubnetID::new(1234, vec![addr]);
+        let mut msg =
+            IpcEnvelope::new_release_msg(&subnet, &addr, &addr, TokenAmount::from_whole(1))
+                .unwrap();
+
+        let key = postbox_key(&msg).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(postbox_key(&msg).unwrap(), key);
+
+        // the nonce tells apart the messages otherwise identical
+        msg.nonce += 1;
+        assert_ne!(postbox_key(&msg).unwrap(), key);
+    }
+
+    #[test]
+    fn test_broadcast_errors() {
+        use ethers::providers::{HttpClientError, JsonRpcError, ProviderError};
+
+        let answer = |message: &str| {
+            ProviderError::JsonRpcClientError(Box::new(HttpClientError::JsonRpcError(
+                JsonRpcError {
+                    code: -32000,
+                    message: message.to_string(),
+                    data: None,
+                },
+            )))
+        };
+        assert_eq!(
+            BroadcastError::classify(&answer("already known")),
+            BroadcastError::Known
+        );
+        assert_eq!(
+            BroadcastError::classify(&answer("nonce too low")),
+            BroadcastError::Unknown
+        );
+        assert_eq!(
+            BroadcastError::classify(&answer("insufficient funds for gas * price + value")),
+            BroadcastError::Rejected
+        );
+        // no answer from the node
+        assert_eq!(
+            BroadcastError::classify(&ProviderError::CustomError("timeout".to_string())),
+            BroadcastError::Unknown
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_manager_has_no_signer() {
+        let contract = Address::from_str("f410ffzyuupbyl2uiucmzr3lu3mtf3luyknthaz4xsrq").unwrap();
+        let subnet = Subnet {
+            id: SubnetID::new_root(1234),
+            config: SubnetConfig::Fevm(EVMSubnet {
+                // nothing listens there, the transaction must fail before reaching the subnet
+                provider_http: "http://127.0.0.1:1".parse().unwrap(),
+                provider_ws: None,
+                provider_timeout: None,
+                auth_token: None,
+                proxy: None,
+                block_time: None,
+                provider_max_concurrency: None,
+                registry_addr: contract,
+                gateway_addr: contract,
+            }),
+        };
+        let manager = EthSubnetManager::read_only(&subnet).unwrap();
+        assert!(manager.is_read_only());
+
+        let child = SubnetID::new(1234, vec![contract]);
+        let err = manager
+            .join_subnet(child, contract, TokenAmount::from_whole(1), vec![])
+            .await
+            .unwrap_err();
+        let no_signer = err.downcast_ref::<NoSigner>().unwrap();
+        assert_eq!(
+            no_signer.address,
+            Some(payload_to_evm_address(contract.payload()).unwrap())
+        );
+    }
 }
@@ -1,14 +1,38 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 
+mod allowlist;
+mod batch;
+mod bindings;
+mod capabilities;
+mod client;
+mod erc20;
+mod fees;
+mod forwarder;
+mod limits;
+mod logs;
 mod manager;
+mod multicall;
+mod params;
+mod receipt;
+mod signer;
 
 use async_trait::async_trait;
 use fvm_shared::clock::ChainEpoch;
 use ipc_api::subnet_id::SubnetID;
 
 use super::subnet::SubnetManager;
+pub use bindings::{CheckpointAbiVersion, CheckpointBindings};
+pub use capabilities::RpcCapabilities;
+pub use client::{EvmClient, EvmClientError, Next, RpcMiddleware};
+pub use fees::{FeeOracle, SuggestedFees, Urgency};
+pub use forwarder::Forwarder;
+pub use limits::{global_rpc_concurrency, set_global_rpc_concurrency};
 pub use manager::EthSubnetManager;
+pub(crate) use params::SubnetParamsCache;
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+pub(crate) use signer::{recover_signature, SECP256K1_ORDER};
+pub use signer::{EvmSigner, EvmSignerError, NoSigner};
 
 use ipc_actors_abis::subnet_actor_checkpointing_facet;
 
@@ -0,0 +1,213 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Aggregation of view calls through the Multicall3 contract, so that the reads of several
+//! contract getters take a single RPC call.
+//!
+//! Multicall3 is deployed at the same address on most chains, but not necessarily on every
+//! subnet. Its deployment is checked the first time it is needed, and the callers fall back
+//! to individual calls if it is missing.
+
+use crate::manager::evm::client::EvmClient;
+use anyhow::{anyhow, Result};
+use ethers::abi::{ParamType, Token};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, Bytes, TransactionRequest, H160, U256};
+use std::sync::RwLock;
+
+/// The address Multicall3 is deployed at on every chain it is deployed on.
+pub(crate) const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// A view call of a contract: its address and calldata.
+pub(crate) type ViewCall = (H160, Bytes);
+
+pub(crate) struct Multicall3 {
+    provider: Provider<EvmClient>,
+    address: H160,
+    /// Whether the contract is deployed, once checked.
+    deployed: RwLock<Option<bool>>,
+}
+
+impl Multicall3 {
+    pub fn new(provider: Provider<EvmClient>) -> Self {
+        Self {
+            provider,
+            address: MULTICALL3_ADDRESS
+                .parse()
+                .expect("valid multicall3 address"),
+            deployed: RwLock::new(None),
+        }
+    }
+
+    async fn is_deployed(&self) -> Result<bool> {
+        if let Some(deployed) = *self.deployed.read().unwrap() {
+            return Ok(deployed);
+        }
+        let deployed = !self.provider.get_code(self.address, None).await?.is_empty();
+        if !deployed {
+            log::info!("multicall3 is not deployed, using individual view calls");
+        }
+        *self.deployed.write().unwrap() = Some(deployed);
+        Ok(deployed)
+    }
+
+    /// Runs the `calls` in a single call, returning their outputs in the same order, or `None`
+    /// if Multicall3 is not deployed. Fails if any of the calls fails.
+    pub async fn aggregate(&self, calls: Vec<ViewCall>) -> Result<Option<Vec<Bytes>>> {
+        self.aggregate_at(calls, None).await
+    }
+
+    /// Runs the `calls` in a single call on the state at `block`, the latest one if `None`, or
+    /// returns `None` if Multicall3 was not deployed yet at `block`.
+    pub async fn aggregate_at(
+        &self,
+        calls: Vec<ViewCall>,
+        block: Option<BlockId>,
+    ) -> Result<Option<Vec<Bytes>>> {
+        let deployed = match block {
+            None => self.is_deployed().await?,
+            // it may have been deployed after the block
+            Some(block) => {
+                self.is_deployed().await?
+                    && !self
+                        .provider
+                        .get_code(self.address, Some(block))
+                        .await?
+                        .is_empty()
+            }
+        };
+        if !deployed {
+            return Ok(None);
+        }
+        let len = calls.len();
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(self.address)
+            .data(encode_aggregate3(calls))
+            .into();
+        let outputs = decode_aggregate3(&self.provider.call(&tx, block).await?)?;
+        if outputs.len() != len {
+            return Err(anyhow!(
+                "multicall3 returned {} outputs for {len} calls",
+                outputs.len()
+            ));
+        }
+        Ok(Some(outputs))
+    }
+
+    /// The call of the balance of `address` in the native token.
+    pub fn eth_balance_call(&self, address: H160) -> ViewCall {
+        let mut data = ethers::utils::id("getEthBalance(address)").to_vec();
+        data.extend(ethers::abi::encode(&[Token::Address(address)]));
+        (self.address, Bytes::from(data))
+    }
+}
+
+/// The calldata of `aggregate3`, with every call required to succeed.
+fn encode_aggregate3(calls: Vec<ViewCall>) -> Bytes {
+    let calls = calls
+        .into_iter()
+        .map(|(target, data)| {
+            Token::Tuple(vec![
+                Token::Address(target),
+                Token::Bool(false),
+                Token::Bytes(data.to_vec()),
+            ])
+        })
+        .collect();
+    let mut data = ethers::utils::id("aggregate3((address,bool,bytes)[])").to_vec();
+    data.extend(ethers::abi::encode(&[Token::Array(calls)]));
+    Bytes::from(data)
+}
+
+/// The return data of the calls of an `aggregate3` output.
+fn decode_aggregate3(output: &[u8]) -> Result<Vec<Bytes>> {
+    let tokens = ethers::abi::decode(
+        &[ParamType::Array(Box::new(ParamType::Tuple(vec![
+            ParamType::Bool,
+            ParamType::Bytes,
+        ])))],
+        output,
+    )?;
+    let Some(Token::Array(results)) = tokens.into_iter().next() else {
+        return Err(anyhow!("unexpected multicall3 output"));
+    };
+
+    results
+        .into_iter()
+        .enumerate()
+        .map(|(i, result)| match result {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(true), Token::Bytes(data)] => Ok(Bytes::from(data.clone())),
+                [Token::Bool(false), _] => Err(anyhow!("multicall3 call {i} failed")),
+                _ => Err(anyhow!("unexpected multicall3 result {i}")),
+            },
+            _ => Err(anyhow!("unexpected multicall3 result {i}")),
+        })
+        .collect()
+}
+
+/// Decodes the output of `getEthBalance`.
+pub(crate) fn decode_eth_balance(output: &[u8]) -> Result<U256> {
+    match ethers::abi::decode(&[ParamType::Uint(256)], output)?.as_slice() {
+        [Token::Uint(balance)] => Ok(*balance),
+        _ => Err(anyhow!("unexpected getEthBalance output")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_aggregate3, decode_eth_balance, encode_aggregate3};
+    use ethers::abi::{ParamType, Token};
+    use ethers::types::{Bytes, H160, U256};
+
+    #[test]
+    fn test_encode_aggregate3() {
+        let data = encode_aggregate3(vec![
+            (H160::repeat_byte(1), Bytes::from(vec![1, 2, 3, 4])),
+            (H160::repeat_byte(2), Bytes::from(vec![5, 6, 7, 8])),
+        ]);
+        assert_eq!(data[..4], [0x82, 0xad, 0x56, 0xcb]);
+
+        let tokens = ethers::abi::decode(
+            &[ParamType::Array(Box::new(ParamType::Tuple(vec![
+                ParamType::Address,
+                ParamType::Bool,
+                ParamType::Bytes,
+            ])))],
+            &data[4..],
+        )
+        .unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Array(vec![
+                Token::Tuple(vec![
+                    Token::Address(H160::repeat_byte(1)),
+                    Token::Bool(false),
+                    Token::Bytes(vec![1, 2, 3, 4]),
+                ]),
+                Token::Tuple(vec![
+                    Token::Address(H160::repeat_byte(2)),
+                    Token::Bool(false),
+                    Token::Bytes(vec![5, 6, 7, 8]),
+                ]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_decode_aggregate3() {
+        let result =
+            |success, data: Vec<u8>| Token::Tuple(vec![Token::Bool(success), Token::Bytes(data)]);
+
+        let output = ethers::abi::encode(&[Token::Array(vec![
+            result(true, ethers::abi::encode(&[Token::Uint(U256::from(10))])),
+            result(true, vec![]),
+        ])]);
+        let outputs = decode_aggregate3(&output).unwrap();
+        assert_eq!(decode_eth_balance(&outputs[0]).unwrap(), U256::from(10));
+        assert!(outputs[1].is_empty());
+
+        let output = ethers::abi::encode(&[Token::Array(vec![result(false, vec![])])]);
+        assert!(decode_aggregate3(&output).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Cache of the parameters of the subnets that do not change after their creation, so that
+//! the hot paths do not query them from the subnet actors over and over.
+//!
+//! The parameters are queried on first use and kept for a time to live, or until they are
+//! invalidated explicitly.
+
+use crate::manager::subnet::SubnetParams;
+use anyhow::{anyhow, Result};
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::subnet::{PermissionMode, SupplyKind, SupplySource};
+use ipc_api::subnet_id::SubnetID;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How long the parameters of a subnet are cached by default.
+const DEFAULT_SUBNET_PARAMS_TTL: Duration = Duration::from_secs(600);
+
+pub(crate) struct SubnetParamsCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<SubnetID, (Instant, SubnetParams)>>,
+}
+
+impl Default for SubnetParamsCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SUBNET_PARAMS_TTL)
+    }
+}
+
+impl SubnetParamsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The parameters of `subnet`, if cached and not expired.
+    pub fn get(&self, subnet: &SubnetID) -> Option<SubnetParams> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(subnet)
+            .filter(|(fetched, _)| fetched.elapsed() < self.ttl)
+            .map(|(_, params)| params.clone())
+    }
+
+    pub fn insert(&self, subnet: SubnetID, params: SubnetParams) {
+        self.entries
+            .write()
+            .unwrap()
+            .insert(subnet, (Instant::now(), params));
+    }
+
+    /// Drops the parameters of `subnet`, or of all the subnets.
+    pub fn invalidate(&self, subnet: Option<&SubnetID>) {
+        let mut entries = self.entries.write().unwrap();
+        match subnet {
+            Some(subnet) => {
+                entries.remove(subnet);
+            }
+            None => entries.clear(),
+        }
+    }
+}
+
+/// Converts the permission mode of a subnet actor.
+pub(crate) fn permission_mode(mode: u8) -> Result<PermissionMode> {
+    match mode {
+        0 => Ok(PermissionMode::Collateral),
+        1 => Ok(PermissionMode::Federated),
+        2 => Ok(PermissionMode::Static),
+        _ => Err(anyhow!("unknown permission mode {mode}")),
+    }
+}
+
+/// Converts the supply source of a subnet actor.
+pub(crate) fn supply_source(
+    kind: u8,
+    token_address: ethers::types::Address,
+) -> Result<SupplySource> {
+    match kind {
+        0 => Ok(SupplySource {
+            kind: SupplyKind::Native,
+            token_address: None,
+        }),
+        1 => Ok(SupplySource {
+            kind: SupplyKind::ERC20,
+            token_address: Some(ethers_address_to_fil_address(&token_address)?),
+        }),
+        _ => Err(anyhow!("unknown supply kind {kind}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{permission_mode, supply_source, SubnetParamsCache};
+    use crate::manager::subnet::SubnetParams;
+    use fvm_shared::address::Address;
+    use ipc_api::subnet::{PermissionMode, SupplyKind};
+    use ipc_api::subnet_id::SubnetID;
+    use std::time::Duration;
+
+    fn params(period: i64) -> SubnetParams {
+        SubnetParams {
+            gateway: Address::new_id(64),
+            subnet_actor: Address::new_id(65),
+            checkpoint_period: period,
+            permission_mode: PermissionMode::Collateral,
+            supply_source: supply_source(0, Default::default()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let (a, b) = (SubnetID::new_root(1), SubnetID::new_root(2));
+        let cache = SubnetParamsCache::new(Duration::from_secs(60));
+        assert!(cache.get(&a).is_none());
+
+        cache.insert(a.clone(), params(10));
+        cache.insert(b.clone(), params(20));
+        assert_eq!(cache.get(&a), Some(params(10)));
+
+        cache.invalidate(Some(&a));
+        assert!(cache.get(&a).is_none());
+        assert_eq!(cache.get(&b), Some(params(20)));
+
+        cache.invalidate(None);
+        assert!(cache.get(&b).is_none());
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let subnet = SubnetID::new_root(1);
+        let cache = SubnetParamsCache::new(Duration::ZERO);
+        cache.insert(subnet.clone(), params(10));
+        assert!(cache.get(&subnet).is_none());
+    }
+
+    #[test]
+    fn test_contract_conversions() {
+        assert_eq!(permission_mode(1).unwrap(), PermissionMode::Federated);
+        assert!(permission_mode(3).is_err());
+
+        let native = supply_source(0, Default::default()).unwrap();
+        assert_eq!(native.kind, SupplyKind::Native);
+        assert!(native.token_address.is_none());
+
+        let erc20 = supply_source(1, ethers::types::Address::repeat_byte(1)).unwrap();
+        assert_eq!(erc20.kind, SupplyKind::ERC20);
+        assert!(erc20.token_address.is_some());
+        assert!(supply_source(2, Default::default()).is_err());
+    }
+}
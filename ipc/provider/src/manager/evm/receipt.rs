@@ -0,0 +1,227 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Waits for the receipts of the transactions sent by the manager.
+//!
+//! The receipt is checked on every new head of the chain when a websocket endpoint is
+//! configured, and at a fixed interval otherwise, or when the subscription is not available.
+//! The websocket connection is opened by the first wait and shared by the next ones.
+//! A transaction whose nonce is consumed by another transaction of the sender is reported as
+//! replaced instead of being waited for until the deadline.
+
+use anyhow::Result;
+use ethers::providers::{JsonRpcClient, Middleware, Provider, StreamExt, Ws};
+use ethers::types::{BlockNumber, TransactionReceipt, H256};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use url::Url;
+
+/// How long to wait for the receipt of a transaction by default.
+const DEFAULT_RECEIPT_TIMEOUT: Duration = Duration::from_secs(200);
+/// How long to wait for a new head before checking the receipt anyway, in case the
+/// subscription silently stopped delivering heads.
+const WS_IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A broadcast transaction to wait for.
+#[derive(Debug, Clone)]
+pub(crate) struct WatchedTx {
+    pub hash: H256,
+    pub from: ethers::types::Address,
+    /// The nonce of the transaction, used to detect its replacement.
+    pub nonce: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ReceiptOutcome {
+    Mined(TransactionReceipt),
+    /// The nonce of the transaction was consumed by another transaction.
+    Replaced,
+    /// There was no receipt before the deadline.
+    TimedOut,
+}
+
+pub(crate) struct ReceiptWaiter<P> {
+    provider: Provider<P>,
+    /// The websocket endpoint to subscribe to the new heads, if any.
+    ws_url: Option<Url>,
+    /// The connection to `ws_url`, once opened.
+    ws: Arc<Mutex<Option<Provider<Ws>>>>,
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+impl<P: JsonRpcClient> ReceiptWaiter<P> {
+    pub fn new(provider: Provider<P>, poll_interval: Duration) -> Self {
+        Self {
+            provider,
+            ws_url: None,
+            ws: Default::default(),
+            poll_interval,
+            timeout: DEFAULT_RECEIPT_TIMEOUT,
+        }
+    }
+
+    pub fn with_ws_url(mut self, ws_url: Option<Url>) -> Self {
+        self.ws_url = ws_url;
+        self.ws = Default::default();
+        self
+    }
+
+    pub fn ws_url(&self) -> Option<&Url> {
+        self.ws_url.as_ref()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Waits for the receipt of `tx` until its deadline.
+    pub async fn wait(&self, tx: &WatchedTx) -> Result<ReceiptOutcome> {
+        let deadline = Instant::now() + self.timeout;
+
+        let ws = match &self.ws_url {
+            Some(url) => self.connect(url).await,
+            None => None,
+        };
+        let mut heads = match &ws {
+            Some(ws) => match ws.subscribe_blocks().await {
+                Ok(heads) => Some(heads),
+                Err(e) => {
+                    log::warn!("cannot subscribe to new heads, polling for receipts instead: {e}");
+                    self.disconnect().await;
+                    None
+                }
+            },
+            None => None,
+        };
+
+        loop {
+            match self.check(tx).await {
+                Ok(Some(outcome)) => return Ok(outcome),
+                Ok(None) => {}
+                Err(e) => log::warn!("cannot check the receipt of tx {:?}: {e}", tx.hash),
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(ReceiptOutcome::TimedOut);
+            }
+            let remaining = deadline - now;
+
+            match heads.as_mut() {
+                Some(stream) => {
+                    let idle = remaining.min(WS_IDLE_POLL_INTERVAL);
+                    if let Ok(None) = tokio::time::timeout(idle, stream.next()).await {
+                        log::warn!("new heads subscription ended, polling for receipts instead");
+                        heads = None;
+                        self.disconnect().await;
+                    }
+                }
+                None => tokio::time::sleep(remaining.min(self.poll_interval)).await,
+            }
+        }
+    }
+
+    /// The connection to the websocket endpoint at `url`, opened unless a previous wait did.
+    async fn connect(&self, url: &Url) -> Option<Provider<Ws>> {
+        let mut ws = self.ws.lock().await;
+        if ws.is_none() {
+            match Provider::<Ws>::connect(url.as_str()).await {
+                Ok(connected) => *ws = Some(connected),
+                Err(e) => {
+                    log::warn!("cannot connect to {url}, polling for receipts instead: {e}")
+                }
+            }
+        }
+        ws.clone()
+    }
+
+    /// Drops the connection to the websocket endpoint, which may be closed, for the next wait
+    /// to open a new one.
+    async fn disconnect(&self) {
+        self.ws.lock().await.take();
+    }
+
+    /// The outcome of `tx` if it is known already.
+    async fn check(&self, tx: &WatchedTx) -> Result<Option<ReceiptOutcome>> {
+        if let Some(receipt) = self.provider.get_transaction_receipt(tx.hash).await? {
+            return Ok(Some(ReceiptOutcome::Mined(receipt)));
+        }
+
+        let Some(nonce) = tx.nonce else {
+            return Ok(None);
+        };
+        let next_nonce = self
+            .provider
+            .get_transaction_count(tx.from, Some(BlockNumber::Latest.into()))
+            .await?;
+        if next_nonce.as_u64() <= nonce {
+            return Ok(None);
+        }
+
+        // the transaction may have been included since the receipt was queried
+        match self.provider.get_transaction_receipt(tx.hash).await? {
+            Some(receipt) => Ok(Some(ReceiptOutcome::Mined(receipt))),
+            None => Ok(Some(ReceiptOutcome::Replaced)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReceiptOutcome, ReceiptWaiter, WatchedTx};
+    use ethers::providers::Provider;
+    use ethers::types::{TransactionReceipt, H256, U256};
+    use std::time::Duration;
+
+    fn watched(nonce: Option<u64>) -> WatchedTx {
+        WatchedTx {
+            hash: H256::repeat_byte(1),
+            from: ethers::types::Address::repeat_byte(2),
+            nonce,
+        }
+    }
+
+    // the mock provider answers the requests in the reverse order of the pushes
+    #[tokio::test]
+    async fn test_receipt_after_polling() {
+        let (provider, mock) = Provider::mocked();
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::repeat_byte(1),
+            block_number: Some(10.into()),
+            ..Default::default()
+        };
+        mock.push(receipt.clone()).unwrap();
+        mock.push(U256::from(5)).unwrap();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+
+        let waiter = ReceiptWaiter::new(provider, Duration::from_millis(1));
+        let outcome = waiter.wait(&watched(Some(5))).await.unwrap();
+        assert_eq!(outcome, ReceiptOutcome::Mined(receipt));
+    }
+
+    #[tokio::test]
+    async fn test_replaced_transaction() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(U256::from(6)).unwrap();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+
+        let waiter = ReceiptWaiter::new(provider, Duration::from_millis(1));
+        let outcome = waiter.wait(&watched(Some(5))).await.unwrap();
+        assert_eq!(outcome, ReceiptOutcome::Replaced);
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+
+        let waiter =
+            ReceiptWaiter::new(provider, Duration::from_millis(1)).with_timeout(Duration::ZERO);
+        let outcome = waiter.wait(&watched(None)).await.unwrap();
+        assert_eq!(outcome, ReceiptOutcome::TimedOut);
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The signers of the transactions sent by [`super::EthSubnetManager`].
+
+use async_trait::async_trait;
+use ethers::signers::{LocalWallet, Signer, WalletError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Signature};
+
+#[cfg(feature = "gcp-kms")]
+use crate::gcp_kms::GcpKmsSigner;
+#[cfg(feature = "vault")]
+use crate::vault::VaultTransitSigner;
+#[cfg(feature = "aws-kms")]
+use ethers::signers::{AwsSigner, AwsSignerError};
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+use ethers::types::{H256, U256};
+
+/// The order of the secp256k1 curve.
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+pub(crate) const SECP256K1_ORDER: &str =
+    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141";
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvmSignerError {
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    #[cfg(feature = "aws-kms")]
+    #[error(transparent)]
+    AwsKms(#[from] AwsSignerError),
+    #[error("remote signer: {0}")]
+    Remote(String),
+}
+
+/// The error of the transactions without a signer for their sender, e.g. the ones of a
+/// read-only manager, which has no keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoSigner {
+    /// The sender of the transaction, none if it is the default one.
+    pub address: Option<Address>,
+}
+
+impl std::fmt::Display for NoSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.address {
+            Some(address) => write!(f, "no signer for {address:?}"),
+            None => write!(f, "no signer for the default sender"),
+        }
+    }
+}
+
+impl std::error::Error for NoSigner {}
+
+/// Signs with a key of the keystore, or with a key held by a remote service.
+#[derive(Debug, Clone)]
+pub enum EvmSigner {
+    Local(LocalWallet),
+    #[cfg(feature = "vault")]
+    VaultTransit(VaultTransitSigner),
+    #[cfg(feature = "aws-kms")]
+    AwsKms(AwsSigner),
+    #[cfg(feature = "gcp-kms")]
+    GcpKms(GcpKmsSigner),
+}
+
+impl From<LocalWallet> for EvmSigner {
+    fn from(wallet: LocalWallet) -> Self {
+        EvmSigner::Local(wallet)
+    }
+}
+
+#[cfg(feature = "vault")]
+impl From<VaultTransitSigner> for EvmSigner {
+    fn from(signer: VaultTransitSigner) -> Self {
+        EvmSigner::VaultTransit(signer)
+    }
+}
+
+#[cfg(feature = "aws-kms")]
+impl From<AwsSigner> for EvmSigner {
+    fn from(signer: AwsSigner) -> Self {
+        EvmSigner::AwsKms(signer)
+    }
+}
+
+#[cfg(feature = "gcp-kms")]
+impl From<GcpKmsSigner> for EvmSigner {
+    fn from(signer: GcpKmsSigner) -> Self {
+        EvmSigner::GcpKms(signer)
+    }
+}
+
+/// Normalizes `s` to the lower half of the curve order, as required by ethereum, and finds the
+/// recovery id under which the signature of `digest` recovers to `address`. Remote services
+/// only return `r` and `s`.
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+pub(crate) fn recover_signature(
+    r: U256,
+    s: U256,
+    digest: H256,
+    address: Address,
+) -> anyhow::Result<Signature> {
+    let order = U256::from_str_radix(SECP256K1_ORDER, 16).expect("valid curve order");
+    let s = if s > order / 2 { order - s } else { s };
+
+    for v in [27, 28] {
+        let signature = Signature { r, s, v };
+        if signature.recover(digest).ok() == Some(address) {
+            return Ok(signature);
+        }
+    }
+    Err(anyhow::anyhow!(
+        "signature does not recover to {address:?}, is the key secp256k1?"
+    ))
+}
+
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+fn remote(result: anyhow::Result<Signature>) -> Result<Signature, EvmSignerError> {
+    result.map_err(|e| EvmSignerError::Remote(format!("{e:#}")))
+}
+
+/// Signs `tx` with a remote signer of digests, the same way as [`LocalWallet`] does.
+#[cfg(any(feature = "vault", feature = "gcp-kms"))]
+async fn sign_transaction_digest<F, Fut>(
+    tx: &TypedTransaction,
+    chain_id: u64,
+    sign: F,
+) -> Result<Signature, EvmSignerError>
+where
+    F: FnOnce(H256) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<Signature>>,
+{
+    let mut tx = tx.clone();
+    if tx.chain_id().is_none() {
+        tx.set_chain_id(chain_id);
+    }
+    let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or_default();
+
+    let mut signature = remote(sign(tx.sighash()).await)?;
+    signature.v = ethers::utils::to_eip155_v((signature.v - 27) as u8, chain_id);
+    Ok(signature)
+}
+
+#[async_trait]
+impl Signer for EvmSigner {
+    type Error = EvmSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            EvmSigner::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => remote(
+                signer
+                    .sign_digest(ethers::utils::hash_message(message))
+                    .await,
+            ),
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => Ok(signer.sign_message(message).await?),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => remote(
+                signer
+                    .sign_digest(ethers::utils::hash_message(message))
+                    .await,
+            ),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            EvmSigner::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => {
+                sign_transaction_digest(tx, signer.chain_id(), |d| signer.sign_digest(d)).await
+            }
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => Ok(signer.sign_transaction(tx).await?),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => {
+                sign_transaction_digest(tx, signer.chain_id(), |d| signer.sign_digest(d)).await
+            }
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        #[cfg(any(feature = "vault", feature = "gcp-kms"))]
+        let digest = || {
+            payload
+                .encode_eip712()
+                .map(H256::from)
+                .map_err(|e| EvmSignerError::Remote(e.to_string()))
+        };
+
+        match self {
+            EvmSigner::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => remote(signer.sign_digest(digest()?).await),
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => Ok(signer.sign_typed_data(payload).await?),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => remote(signer.sign_digest(digest()?).await),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            EvmSigner::Local(wallet) => wallet.address(),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => signer.address(),
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => signer.address(),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            EvmSigner::Local(wallet) => wallet.chain_id(),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => signer.chain_id(),
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => signer.chain_id(),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            EvmSigner::Local(wallet) => EvmSigner::Local(wallet.with_chain_id(chain_id)),
+            #[cfg(feature = "vault")]
+            EvmSigner::VaultTransit(signer) => {
+                EvmSigner::VaultTransit(signer.with_chain_id(chain_id.into()))
+            }
+            #[cfg(feature = "aws-kms")]
+            EvmSigner::AwsKms(signer) => EvmSigner::AwsKms(signer.with_chain_id(chain_id)),
+            #[cfg(feature = "gcp-kms")]
+            EvmSigner::GcpKms(signer) => EvmSigner::GcpKms(signer.with_chain_id(chain_id.into())),
+        }
+    }
+}
@@ -1,10 +1,13 @@
 // Copyright 2022-2024 Protocol Labs
 // SPDX-License-Identifier: MIT
 pub use crate::lotus::message::ipc::SubnetInfo;
-pub use evm::{EthManager, EthSubnetManager};
+pub use evm::{EthManager, EthSubnetManager, EvmSigner, NoSigner};
 pub use subnet::{
-    BottomUpCheckpointRelayer, GetBlockHashResult, SubnetGenesisInfo, SubnetManager,
-    TopDownFinalityQuery, TopDownQueryPayload,
+    BottomUpCheckpointRelayer, CheckpointQuorum, CheckpointReceipt, CheckpointStatus,
+    ChildGatewayClient, ChildSubnetStatus, CollateralRelease, ContractCode, DiamondCode,
+    EventProof, GetBlockHashResult, SubmissionProof, SubnetGenesisInfo, SubnetManager,
+    SubnetParams, SubnetQuery, SubnetTx, TopDownFinalityQuery, TopDownQueryPayload,
+    UnsignedTransaction, UnsignedTransactionBuilder, ValidatorPosition,
 };
 
 pub mod evm;
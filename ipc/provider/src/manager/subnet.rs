@@ -2,9 +2,12 @@
 // SPDX-License-Identifier: MIT
 
 use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, Eip1559TransactionRequest, H160, H256, U256};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_api::checkpoint::{
@@ -15,12 +18,44 @@ use ipc_api::staking::{StakingChangeRequest, ValidatorInfo};
 use ipc_api::subnet::{ConstructParams, PermissionMode, SupplySource};
 use ipc_api::subnet_id::SubnetID;
 use ipc_api::validator::Validator;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 
+use crate::diamond::{Facet, FacetCut};
+use crate::epoch::BlockTime;
+use crate::events::SubnetEvent;
 use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::evm::Urgency;
 
-/// Trait to interact with a subnet and handle its lifecycle.
+/// Trait to interact with a subnet and handle its lifecycle, i.e. all the capabilities of a
+/// subnet. It is implemented for any type implementing them, the services needing only some of
+/// them depend on the traits of those instead, e.g. [`SubnetQuery`] for read-only services.
+pub trait SubnetManager:
+    Send
+    + Sync
+    + SubnetQuery
+    + SubnetTx
+    + TopDownFinalityQuery
+    + BottomUpCheckpointRelayer
+    + UnsignedTransactionBuilder
+{
+}
+
+impl<T> SubnetManager for T where
+    T: Send
+        + Sync
+        + SubnetQuery
+        + SubnetTx
+        + TopDownFinalityQuery
+        + BottomUpCheckpointRelayer
+        + UnsignedTransactionBuilder
+{
+}
+
+/// The transactions changing the state of a subnet and of its children, signed by their
+/// sender.
 #[async_trait]
-pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpointRelayer {
+pub trait SubnetTx: Send + Sync {
     /// Deploys a new subnet actor on the `parent` subnet and with the
     /// configuration passed in `ConstructParams`.
     /// The result of the function is the ID address for the subnet actor from which the final
@@ -62,12 +97,6 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
     /// Sends a signal to kill a subnet
     async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<()>;
 
-    /// Lists all the registered children in a gateway.
-    async fn list_child_subnets(
-        &self,
-        gateway_addr: Address,
-    ) -> Result<HashMap<SubnetID, SubnetInfo>>;
-
     /// Claims any collateral that may be available to claim by validators that
     /// have left the subnet.
     async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<()>;
@@ -102,6 +131,33 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         amount: TokenAmount,
     ) -> Result<ChainEpoch>;
 
+    /// Approves the gateway to take up to `amount` of the ERC20 `token` of `from`, e.g.
+    /// before [`Self::fund_with_token`]. Returns the epoch the approval is executed at.
+    async fn approve_token(
+        &self,
+        token: &Address,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<ChainEpoch>;
+
+    /// Revokes the approval of `spender` to take the ERC20 `token` of `from`, setting its
+    /// allowance to zero. Returns the epoch the revocation is executed at.
+    async fn revoke_token_approval(
+        &self,
+        token: &Address,
+        from: Address,
+        spender: &Address,
+    ) -> Result<ChainEpoch>;
+
+    /// Applies the `cuts` to the facets of the diamond at `diamond` from `from`, its owner.
+    /// Returns the epoch the cuts are executed at.
+    async fn diamond_cut(
+        &self,
+        from: Address,
+        diamond: &Address,
+        cuts: Vec<FacetCut>,
+    ) -> Result<ChainEpoch>;
+
     /// Release creates a new check message to release funds in parent chain
     /// Returns the epoch that the released is executed in the child.
     async fn release(
@@ -114,20 +170,96 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
 
     /// Propagate a cross-net message forward. For `postbox_msg_key`, we are using bytes because different
     /// runtime have different representations. For FVM, it should be `CID` as bytes. For EVM, it is
-    /// `bytes32`.
+    /// `bytes32`. The `fee` is paid to the gateway along with the call, zero for the gateways
+    /// not charging any.
     async fn propagate(
         &self,
         subnet: SubnetID,
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
     ) -> Result<()>;
 
     /// Send value between two addresses in a subnet
     async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()>;
 
+    /// Broadcasts a transaction signed out of band, e.g. an [`UnsignedTransaction`] signed
+    /// externally, and waits for it to be executed. Returns the epoch it was executed at.
+    async fn broadcast_raw(&self, raw_tx: Vec<u8>) -> Result<ChainEpoch>;
+
+    /// Advertises the endpoint of a bootstrap node for the subnet.
+    async fn add_bootstrap(
+        &self,
+        subnet: &SubnetID,
+        from: &Address,
+        endpoint: String,
+    ) -> Result<()>;
+
+    async fn set_federated_power(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        validators: &[Address],
+        public_keys: &[Vec<u8>],
+        federated_power: &[u128],
+    ) -> Result<ChainEpoch>;
+
+    /// Adds `relayer` to the allowlist of the relayers of the permissioned `subnet` or removes
+    /// it, signed by the owner of the subnet. Fails if the subnet does not restrict its relayers.
+    async fn set_relayer_allowed(
+        &self,
+        from: &Address,
+        subnet: &SubnetID,
+        relayer: &Address,
+        allowed: bool,
+    ) -> Result<ChainEpoch>;
+}
+
+/// The queries of the state of a subnet and of its children, which do not need a signer.
+///
+/// The queries taking an `at_height` read the state at that height instead of the latest one,
+/// e.g. to reconstruct the past state of a subnet, which requires an archive node.
+#[async_trait]
+pub trait SubnetQuery: Send + Sync {
+    /// Lists all the registered children in a gateway.
+    async fn list_child_subnets(
+        &self,
+        gateway_addr: Address,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>>;
+
     /// Get the balance of an address
-    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount>;
+    async fn wallet_balance(
+        &self,
+        address: &Address,
+        at_height: Option<ChainEpoch>,
+    ) -> Result<TokenAmount>;
+
+    /// The amount of the ERC20 `token` of `owner` the `spender` is approved to take.
+    async fn token_allowance(
+        &self,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+    ) -> Result<TokenAmount>;
+
+    /// Get the nonce of the next transaction of an address, including its pending ones.
+    async fn next_nonce(&self, address: &Address) -> Result<u64>;
+
+    /// Get the balances of several addresses, in their order. Managers able to read them at
+    /// once override the individual queries.
+    async fn wallet_balances(
+        &self,
+        addresses: &[Address],
+        at_height: Option<ChainEpoch>,
+    ) -> Result<Vec<TokenAmount>> {
+        let mut balances = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            balances.push(self.wallet_balance(address, at_height).await?);
+        }
+        Ok(balances)
+    }
 
     /// Get chainID for the network.
     /// Returning as a `String` because the maximum value for an EVM
@@ -140,14 +272,6 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
     /// Gets the genesis information required to bootstrap a child subnet
     async fn get_genesis_info(&self, subnet: &SubnetID) -> Result<SubnetGenesisInfo>;
 
-    /// Advertises the endpoint of a bootstrap node for the subnet.
-    async fn add_bootstrap(
-        &self,
-        subnet: &SubnetID,
-        from: &Address,
-        endpoint: String,
-    ) -> Result<()>;
-
     /// Lists the bootstrap nodes of a subnet
     async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>>;
 
@@ -156,19 +280,105 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         &self,
         subnet: &SubnetID,
         validator: &Address,
+        at_height: Option<ChainEpoch>,
     ) -> Result<ValidatorInfo>;
 
-    async fn set_federated_power(
+    /// The active validators of the child `subnet`.
+    async fn active_validators(
         &self,
-        from: &Address,
         subnet: &SubnetID,
-        validators: &[Address],
-        public_keys: &[Vec<u8>],
-        federated_power: &[u128],
-    ) -> Result<ChainEpoch>;
+        at_height: Option<ChainEpoch>,
+    ) -> Result<Vec<Address>>;
+
+    /// The parameters of a subnet that do not change after its creation, cached after they
+    /// are first queried.
+    async fn subnet_params(&self, subnet: &SubnetID) -> Result<SubnetParams>;
+
+    /// Drops the cached parameters of `subnet`, or of all the subnets if `None`, so that they
+    /// are queried again on next use.
+    fn invalidate_subnet_params(&self, subnet: Option<&SubnetID>);
+
+    /// The average block time of this subnet, measured on its last `window` blocks.
+    async fn measure_block_time(&self, window: ChainEpoch) -> Result<BlockTime>;
+
+    /// The height and the timestamp, in seconds, of the head of this subnet.
+    async fn chain_head_timestamp(&self) -> Result<(ChainEpoch, u64)>;
+
+    /// The staking position of `validator` in the child `subnet`, with the releases of its
+    /// collateral logged since `releases_from`.
+    async fn validator_position(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+        releases_from: ChainEpoch,
+    ) -> Result<ValidatorPosition>;
+
+    /// The events of the child `subnet` logged in this subnet, its parent, between the `from`
+    /// and `to` heights: the validators joining and leaving and the funds sent to it, with the
+    /// heights they were logged at.
+    async fn subnet_events_in_parent(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, SubnetEvent)>>;
+
+    /// The messages sent from this subnet, its parent, to the child `subnet` between the `from`
+    /// and `to` heights, with the heights they were sent at.
+    async fn top_down_msgs_in_parent(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, IpcEnvelope)>>;
+
+    /// The funds released to the parent logged in this subnet between the `from` and `to`
+    /// heights, with the heights they were logged at.
+    async fn subnet_events_in_child(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, SubnetEvent)>>;
+
+    /// Whether the child `subnet` was killed.
+    async fn subnet_killed(&self, subnet: &SubnetID) -> Result<bool>;
+
+    /// The hashes of the code of the diamond at `address` in this subnet and of its facets.
+    async fn diamond_code(&self, address: &Address) -> Result<DiamondCode>;
+
+    /// The facets installed in the diamond at `address` in this subnet, with their selectors.
+    async fn diamond_facets(&self, address: &Address) -> Result<Vec<Facet>>;
+
+    /// The relayers allowed to submit the checkpoints of the child `subnet`, or `None` if any
+    /// relayer can.
+    async fn relayer_allowlist(&self, subnet: &SubnetID) -> Result<Option<Vec<Address>>>;
+
+    /// Whether `relayer` can submit the checkpoints of the child `subnet`.
+    async fn is_relayer_allowed(&self, subnet: &SubnetID, relayer: &Address) -> Result<bool>;
+
+    /// The key of `msg` in the postbox of the gateway of this subnet, see
+    /// [`SubnetManager::propagate`].
+    fn postbox_key(&self, msg: &IpcEnvelope) -> Result<Vec<u8>>;
+
+    /// Whether the message with `postbox_msg_key` is in the postbox of the gateway of this
+    /// subnet, waiting to be propagated.
+    async fn in_postbox(&self, postbox_msg_key: &[u8]) -> Result<bool>;
 }
 
-#[derive(Debug)]
+/// The parameters of a subnet that do not change after its creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetParams {
+    /// The gateway the subnet is registered in.
+    pub gateway: Address,
+    /// The subnet actor of the subnet in its parent.
+    pub subnet_actor: Address,
+    pub checkpoint_period: ChainEpoch,
+    pub permission_mode: PermissionMode,
+    pub supply_source: SupplySource,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubnetGenesisInfo {
     pub bottom_up_checkpoint_period: u64,
     pub majority_percentage: u8,
@@ -176,6 +386,8 @@ pub struct SubnetGenesisInfo {
     pub min_collateral: TokenAmount,
     pub genesis_epoch: ChainEpoch,
     pub validators: Vec<Validator>,
+    /// Serialized as a list of pairs, the addresses are not strings.
+    #[serde_as(as = "Vec<(_, _)>")]
     pub genesis_balances: BTreeMap<Address, TokenAmount>,
     pub permission_mode: PermissionMode,
     pub supply_source: SupplySource,
@@ -183,13 +395,13 @@ pub struct SubnetGenesisInfo {
 
 /// The generic payload that returns the block hash of the data returning block with the actual
 /// data payload.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TopDownQueryPayload<T> {
     pub value: T,
     pub block_hash: Vec<u8>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct GetBlockHashResult {
     pub parent_block_hash: Vec<u8>,
     pub block_hash: Vec<u8>,
@@ -220,28 +432,569 @@ pub trait TopDownFinalityQuery: Send + Sync {
     async fn latest_parent_finality(&self) -> Result<ChainEpoch>;
 }
 
+/// The calls of the relayer to the child subnet whose checkpoints it relays: the checkpoint
+/// bundles and quorum events of its gateway, and the blocks of its chain.
+///
+/// They are kept apart from the calls to the parent so that a child runtime other than the EVM,
+/// e.g. a Wasm or CosmWasm gateway, only has to implement these, in terms of the types of
+/// `ipc_api`. [`super::EthSubnetManager`] is the EVM backend.
+#[async_trait]
+pub trait ChildGatewayClient: Send + Sync {
+    /// Get the checkpoint bundle at a specific height. If it does not exist, it will through error.
+    async fn checkpoint_bundle_at(&self, height: ChainEpoch) -> Result<BottomUpCheckpointBundle>;
+    /// Get the signature weight collected for the checkpoint at a specific height.
+    async fn checkpoint_quorum_at(&self, height: ChainEpoch) -> Result<CheckpointQuorum>;
+    /// Queries the signature quorum reached events at target height.
+    async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>>;
+    /// Queries the signature quorum reached events at each of the `heights`, in their order.
+    /// Handlers able to query several heights at once override the serial queries.
+    async fn quorum_reached_events_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<Vec<QuorumReachedEvent>>> {
+        let mut events = Vec::with_capacity(heights.len());
+        for h in heights {
+            events.push(self.quorum_reached_events(*h).await?);
+        }
+        Ok(events)
+    }
+    /// Get the checkpoint bundles at each of the `heights`, in their order. Handlers able to
+    /// query several heights at once override the serial queries.
+    async fn checkpoint_bundles_at(
+        &self,
+        heights: &[ChainEpoch],
+    ) -> Result<Vec<BottomUpCheckpointBundle>> {
+        let mut bundles = Vec::with_capacity(heights.len());
+        for h in heights {
+            bundles.push(self.checkpoint_bundle_at(*h).await?);
+        }
+        Ok(bundles)
+    }
+    /// Get the current epoch of the child subnet
+    async fn current_epoch(&self) -> Result<ChainEpoch>;
+    /// Get the hash of the block at a specific height in the child subnet.
+    async fn block_hash_at(&self, height: ChainEpoch) -> Result<Vec<u8>>;
+    /// Get the address of the validator that proposed the block at a specific height in the
+    /// child subnet.
+    async fn block_proposer_at(&self, height: ChainEpoch) -> Result<Address>;
+    /// Get the timestamp, in seconds, of the block at a specific height in the child subnet.
+    async fn block_timestamp_at(&self, height: ChainEpoch) -> Result<u64>;
+    /// The checkpoint period of the child subnet as set in its own gateway, to fall back to
+    /// when the parent cannot be queried.
+    async fn gateway_checkpoint_period(&self) -> Result<ChainEpoch>;
+    /// The id of the subnet whose gateway is queried, to check it is the expected child.
+    async fn gateway_subnet_id(&self) -> Result<SubnetID>;
+}
+
+/// Forwards [`ChildGatewayClient`] through a smart pointer, so that the child handler of
+/// [`crate::checkpoint::BottomUpCheckpointManager`] can be a trait object.
+macro_rules! forward_child_gateway {
+    ($pointer:ident) => {
+        #[async_trait]
+        impl<T: ChildGatewayClient + ?Sized> ChildGatewayClient for $pointer<T> {
+            async fn checkpoint_bundle_at(
+                &self,
+                height: ChainEpoch,
+            ) -> Result<BottomUpCheckpointBundle> {
+                (**self).checkpoint_bundle_at(height).await
+            }
+            async fn checkpoint_quorum_at(&self, height: ChainEpoch) -> Result<CheckpointQuorum> {
+                (**self).checkpoint_quorum_at(height).await
+            }
+            async fn quorum_reached_events(
+                &self,
+                height: ChainEpoch,
+            ) -> Result<Vec<QuorumReachedEvent>> {
+                (**self).quorum_reached_events(height).await
+            }
+            async fn quorum_reached_events_at(
+                &self,
+                heights: &[ChainEpoch],
+            ) -> Result<Vec<Vec<QuorumReachedEvent>>> {
+                (**self).quorum_reached_events_at(heights).await
+            }
+            async fn checkpoint_bundles_at(
+                &self,
+                heights: &[ChainEpoch],
+            ) -> Result<Vec<BottomUpCheckpointBundle>> {
+                (**self).checkpoint_bundles_at(heights).await
+            }
+            async fn current_epoch(&self) -> Result<ChainEpoch> {
+                (**self).current_epoch().await
+            }
+            async fn block_hash_at(&self, height: ChainEpoch) -> Result<Vec<u8>> {
+                (**self).block_hash_at(height).await
+            }
+            async fn block_proposer_at(&self, height: ChainEpoch) -> Result<Address> {
+                (**self).block_proposer_at(height).await
+            }
+            async fn block_timestamp_at(&self, height: ChainEpoch) -> Result<u64> {
+                (**self).block_timestamp_at(height).await
+            }
+            async fn gateway_checkpoint_period(&self) -> Result<ChainEpoch> {
+                (**self).gateway_checkpoint_period().await
+            }
+            async fn gateway_subnet_id(&self) -> Result<SubnetID> {
+                (**self).gateway_subnet_id().await
+            }
+        }
+    };
+}
+
+forward_child_gateway!(Box);
+forward_child_gateway!(Arc);
+
 /// The bottom up checkpoint manager that handles the bottom up relaying from child subnet to the parent
-/// subnet.
+/// subnet. The backends relaying to the parent serve the calls to the child too.
 #[async_trait]
-pub trait BottomUpCheckpointRelayer: Send + Sync {
+pub trait BottomUpCheckpointRelayer: ChildGatewayClient + Send + Sync {
     /// Submit a checkpoint for execution.
     /// It triggers the commitment of the checkpoint and the execution of related cross-net messages.
-    /// Returns the epoch that the execution is successful
+    /// Returns the receipt of the successful execution.
     async fn submit_checkpoint(
         &self,
         submitter: &Address,
         checkpoint: BottomUpCheckpoint,
         signatures: Vec<Signature>,
         signatories: Vec<Address>,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<CheckpointReceipt>;
+    /// Submit a checkpoint with the fees of `urgency`, e.g. to catch up on overdue checkpoints.
+    /// Handlers without a notion of fee urgency submit it as any other checkpoint.
+    async fn submit_checkpoint_with_urgency(
+        &self,
+        submitter: &Address,
+        checkpoint: BottomUpCheckpoint,
+        signatures: Vec<Signature>,
+        signatories: Vec<Address>,
+        _urgency: Urgency,
+    ) -> Result<CheckpointReceipt> {
+        self.submit_checkpoint(submitter, checkpoint, signatures, signatories)
+            .await
+    }
     /// The last confirmed/submitted checkpoint height.
     async fn last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<ChainEpoch>;
+    /// The checkpoint of the child subnet committed in the parent at a specific height, if any.
+    async fn committed_checkpoint_at(
+        &self,
+        subnet_id: &SubnetID,
+        height: ChainEpoch,
+    ) -> Result<Option<BottomUpCheckpoint>>;
     /// Get the checkpoint period, i.e the number of blocks to submit bottom up checkpoints.
     async fn checkpoint_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch>;
-    /// Get the checkpoint bundle at a specific height. If it does not exist, it will through error.
-    async fn checkpoint_bundle_at(&self, height: ChainEpoch) -> Result<BottomUpCheckpointBundle>;
-    /// Queries the signature quorum reached events at target height.
-    async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>>;
-    /// Get the current epoch in the current subnet
-    async fn current_epoch(&self) -> Result<ChainEpoch>;
+    /// The checkpoint period and the last committed checkpoint height of the child subnet.
+    /// Handlers able to read them at once override the individual queries.
+    async fn checkpoint_status(&self, subnet_id: &SubnetID) -> Result<CheckpointStatus> {
+        Ok(CheckpointStatus {
+            period: self.checkpoint_period(subnet_id).await?,
+            last_committed_height: self.last_bottom_up_checkpoint_height(subnet_id).await?,
+        })
+    }
+    /// Reconciles the transactions left in flight by a previous run against the chain,
+    /// resuming or discarding them as needed.
+    async fn reconcile_pending_txs(&self) -> Result<()>;
+    /// The on-chain evidence of the submission of `receipt` in the current subnet.
+    async fn submission_proof(&self, receipt: &CheckpointReceipt) -> Result<SubmissionProof>;
+    /// Whether the child subnet is still active in the current subnet, its parent.
+    async fn child_subnet_status(&self, subnet_id: &SubnetID) -> Result<ChildSubnetStatus>;
+    /// Signs `message` with the key of `signer`, as an EIP-191 personal message, returning the
+    /// 65 bytes of the signature.
+    async fn sign_message(&self, signer: &Address, message: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Forwards [`BottomUpCheckpointRelayer`] through a smart pointer, so that the handlers of
+/// [`crate::checkpoint::BottomUpCheckpointManager`] can be trait objects.
+macro_rules! forward_relayer {
+    ($pointer:ident) => {
+        #[async_trait]
+        impl<T: BottomUpCheckpointRelayer + ?Sized> BottomUpCheckpointRelayer for $pointer<T> {
+            async fn submit_checkpoint(
+                &self,
+                submitter: &Address,
+                checkpoint: BottomUpCheckpoint,
+                signatures: Vec<Signature>,
+                signatories: Vec<Address>,
+            ) -> Result<CheckpointReceipt> {
+                (**self)
+                    .submit_checkpoint(submitter, checkpoint, signatures, signatories)
+                    .await
+            }
+            async fn submit_checkpoint_with_urgency(
+                &self,
+                submitter: &Address,
+                checkpoint: BottomUpCheckpoint,
+                signatures: Vec<Signature>,
+                signatories: Vec<Address>,
+                urgency: Urgency,
+            ) -> Result<CheckpointReceipt> {
+                (**self)
+                    .submit_checkpoint_with_urgency(
+                        submitter,
+                        checkpoint,
+                        signatures,
+                        signatories,
+                        urgency,
+                    )
+                    .await
+            }
+            async fn last_bottom_up_checkpoint_height(
+                &self,
+                subnet_id: &SubnetID,
+            ) -> Result<ChainEpoch> {
+                (**self).last_bottom_up_checkpoint_height(subnet_id).await
+            }
+            async fn committed_checkpoint_at(
+                &self,
+                subnet_id: &SubnetID,
+                height: ChainEpoch,
+            ) -> Result<Option<BottomUpCheckpoint>> {
+                (**self).committed_checkpoint_at(subnet_id, height).await
+            }
+            async fn checkpoint_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+                (**self).checkpoint_period(subnet_id).await
+            }
+            async fn checkpoint_status(&self, subnet_id: &SubnetID) -> Result<CheckpointStatus> {
+                (**self).checkpoint_status(subnet_id).await
+            }
+            async fn reconcile_pending_txs(&self) -> Result<()> {
+                (**self).reconcile_pending_txs().await
+            }
+            async fn submission_proof(
+                &self,
+                receipt: &CheckpointReceipt,
+            ) -> Result<SubmissionProof> {
+                (**self).submission_proof(receipt).await
+            }
+            async fn child_subnet_status(&self, subnet_id: &SubnetID) -> Result<ChildSubnetStatus> {
+                (**self).child_subnet_status(subnet_id).await
+            }
+            async fn sign_message(&self, signer: &Address, message: &[u8]) -> Result<Vec<u8>> {
+                (**self).sign_message(signer, message).await
+            }
+        }
+    };
+}
+
+forward_relayer!(Box);
+forward_relayer!(Arc);
+
+/// Builds the transactions of the subnet operations without signing nor sending them, so that
+/// they can be signed out of band, e.g. on an air-gapped machine.
+#[async_trait]
+pub trait UnsignedTransactionBuilder: Send + Sync {
+    /// The transaction of [`SubnetManager::fund`].
+    async fn unsigned_fund(
+        &self,
+        subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<UnsignedTransaction>;
+
+    /// The transaction of [`SubnetManager::release`].
+    async fn unsigned_release(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<UnsignedTransaction>;
+
+    /// The transaction of [`SubnetManager::propagate`].
+    async fn unsigned_propagate(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        postbox_msg_key: Vec<u8>,
+        fee: TokenAmount,
+    ) -> Result<UnsignedTransaction>;
+
+    /// The transaction of [`BottomUpCheckpointRelayer::submit_checkpoint`].
+    async fn unsigned_submit_checkpoint(
+        &self,
+        submitter: &Address,
+        checkpoint: BottomUpCheckpoint,
+        signatures: Vec<Signature>,
+        signatories: Vec<Address>,
+    ) -> Result<UnsignedTransaction>;
+}
+
+/// An EIP-1559 transaction with every field needed to sign it filled in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub chain_id: u64,
+    pub from: H160,
+    pub to: H160,
+    pub nonce: u64,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl UnsignedTransaction {
+    pub fn to_typed_transaction(&self) -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .chain_id(self.chain_id)
+            .from(self.from)
+            .to(self.to)
+            .nonce(self.nonce)
+            .value(self.value)
+            .data(self.data.clone())
+            .gas(self.gas_limit)
+            .max_fee_per_gas(self.max_fee_per_gas)
+            .max_priority_fee_per_gas(self.max_priority_fee_per_gas)
+            .into()
+    }
+
+    /// The hash the signer signs.
+    pub fn sighash(&self) -> H256 {
+        self.to_typed_transaction().sighash()
+    }
+}
+
+/// The receipt of a checkpoint submission executed in the parent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointReceipt {
+    /// The parent epoch the submission was executed at.
+    pub epoch: ChainEpoch,
+    /// The hex encoded hash of the submission transaction.
+    pub tx_hash: String,
+    pub gas_used: Option<u64>,
+    /// The price paid per unit of gas, in atto.
+    pub effective_gas_price: Option<u64>,
+}
+
+impl CheckpointReceipt {
+    /// The fee paid for the submission in atto, if known.
+    pub fn fee(&self) -> Option<u128> {
+        Some(self.gas_used? as u128 * self.effective_gas_price? as u128)
+    }
+}
+
+/// The evidence of a checkpoint submission in the parent chain, see
+/// [`BottomUpCheckpointRelayer::submission_proof`]. Everything is hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionProof {
+    pub tx_hash: String,
+    /// The calldata of the submission transaction, with the checkpoint and its signatures.
+    pub calldata: String,
+    pub block_number: ChainEpoch,
+    pub block_hash: String,
+    /// The receipts root of the block, committing to the receipt of the submission.
+    pub receipts_root: String,
+    /// The events emitted by the submission, in their order.
+    pub events: Vec<EventProof>,
+}
+
+/// An event emitted by a transaction. Everything is hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventProof {
+    /// The contract emitting the event.
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    /// The index of the event in its block.
+    pub log_index: Option<u64>,
+}
+
+/// The staking position of a validator in a child subnet, see
+/// [`SubnetQuery::validator_position`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorPosition {
+    /// The collateral confirmed by the child subnet.
+    pub confirmed_collateral: TokenAmount,
+    /// The collateral including the changes not confirmed by the child subnet yet.
+    pub total_collateral: TokenAmount,
+    pub power: TokenAmount,
+    pub is_active: bool,
+    pub is_waiting: bool,
+    /// The collateral staked and waiting for the child subnet to confirm it.
+    pub pending_stake: TokenAmount,
+    /// The collateral unstaked and waiting for the child subnet to confirm it, after which it
+    /// is locked in a release.
+    pub pending_unstake: TokenAmount,
+    /// The releases of collateral still locked, by release height.
+    pub locked_releases: Vec<CollateralRelease>,
+    /// The collateral of the releases unlocked since the heights scanned. The claims are not
+    /// logged, so this is an upper bound of the collateral left to claim with
+    /// [`SubnetTx::claim_collateral`], the only payout of the subnet actor.
+    pub unlocked_collateral: TokenAmount,
+}
+
+/// Collateral released by the child subnet to its validator, claimable from `release_at`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollateralRelease {
+    pub amount: TokenAmount,
+    /// The parent height the collateral is unlocked at.
+    pub release_at: ChainEpoch,
+}
+
+/// The code of a diamond contract and of its facets, see [`SubnetQuery::diamond_code`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiamondCode {
+    pub diamond: ContractCode,
+    pub facets: Vec<ContractCode>,
+}
+
+/// The keccak256 hash of the runtime code of a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContractCode {
+    pub address: Address,
+    pub code_hash: [u8; 32],
+}
+
+/// The checkpointing state of a child subnet in its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointStatus {
+    /// The number of blocks between two bottom-up checkpoints.
+    pub period: ChainEpoch,
+    /// The height of the last checkpoint committed in the parent.
+    pub last_committed_height: ChainEpoch,
+}
+
+/// The standing of a child subnet in its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildSubnetStatus {
+    Active,
+    /// The subnet was killed, its checkpoints are no longer accepted.
+    Killed,
+    /// The subnet is no longer registered in the gateway of the parent, or its actor is gone.
+    Deregistered,
+}
+
+impl ChildSubnetStatus {
+    /// Whether the subnet will never accept checkpoints again.
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, ChildSubnetStatus::Active)
+    }
+}
+
+impl std::fmt::Display for ChildSubnetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChildSubnetStatus::Active => write!(f, "active"),
+            ChildSubnetStatus::Killed => write!(f, "killed"),
+            ChildSubnetStatus::Deregistered => write!(f, "deregistered"),
+        }
+    }
+}
+
+/// The signature weight collected for a bottom-up checkpoint in the child subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointQuorum {
+    /// The weight the contract requires for the quorum to be reached.
+    pub threshold: TokenAmount,
+    /// The weight of the signatures collected so far.
+    pub current_weight: TokenAmount,
+    /// The majority percentage of the total weight the contract `threshold` is derived from.
+    pub majority_percentage: u64,
+}
+
+impl CheckpointQuorum {
+    /// Checks if the collected weight is at least `percentage` of the total validator weight.
+    pub fn reaches(&self, percentage: u8) -> bool {
+        // The contract threshold is `total * majority_percentage / 100`, so we compare
+        // `current / total >= percentage / 100` without dividing.
+        self.current_weight.atto() * self.majority_percentage
+            >= self.threshold.atto() * percentage as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BottomUpCheckpointRelayer, CheckpointQuorum, SubnetManager, SubnetQuery, SubnetTx,
+        TopDownFinalityQuery, UnsignedTransaction, UnsignedTransactionBuilder,
+    };
+    use crate::manager::EthSubnetManager;
+    use ethers::types::{Bytes, H160, U256};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::subnet_id::SubnetID;
+    use std::sync::Arc;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_relayer_trait_objects() {
+        fn assert_relayer<T: BottomUpCheckpointRelayer + 'static>() {}
+        assert_relayer::<Box<dyn BottomUpCheckpointRelayer>>();
+        assert_relayer::<Arc<dyn BottomUpCheckpointRelayer>>();
+        assert_relayer::<Box<dyn SubnetManager>>();
+    }
+
+    #[test]
+    fn test_split_capabilities() {
+        fn assert_manager<T: SubnetManager + ?Sized>() {}
+        fn assert_query<T: SubnetQuery + ?Sized>() {}
+        fn assert_tx<T: SubnetTx + ?Sized>() {}
+        assert_manager::<EthSubnetManager>();
+        assert_query::<dyn SubnetManager>();
+        assert_tx::<dyn SubnetManager>();
+        assert_query::<dyn SubnetQuery>();
+    }
+
+    /// Never called, it does not compile if the futures of the traits cannot be spawned on a
+    /// multi-threaded runtime.
+    #[allow(dead_code)]
+    fn assert_send_futures(
+        manager: &dyn SubnetManager,
+        query: &dyn SubnetQuery,
+        relayer: &dyn BottomUpCheckpointRelayer,
+        subnet: &SubnetID,
+    ) {
+        assert_send(&manager.list_child_subnets(Address::new_id(0)));
+        assert_send(&manager.chain_head_height());
+        assert_send(&manager.get_top_down_msgs(subnet, 0));
+        assert_send(&manager.subnet_events_in_parent(subnet, 0, 0));
+        assert_send(&manager.leave_subnet(subnet.clone(), Address::new_id(0)));
+        assert_send(&query.subnet_killed(subnet));
+        assert_send(&manager.unsigned_release(
+            Address::new_id(0),
+            Address::new_id(0),
+            Address::new_id(0),
+            TokenAmount::from_atto(0),
+        ));
+        assert_send(&relayer.last_bottom_up_checkpoint_height(subnet));
+        assert_send(&relayer.quorum_reached_events(0));
+        assert_send(&relayer.quorum_reached_events_at(&[0]));
+        assert_send(&relayer.checkpoint_status(subnet));
+        assert_send(&relayer.reconcile_pending_txs());
+    }
+
+    #[test]
+    fn test_checkpoint_quorum_reaches() {
+        // total weight of 100 with a majority of 67%
+        let quorum = CheckpointQuorum {
+            threshold: TokenAmount::from_atto(67),
+            current_weight: TokenAmount::from_atto(75),
+            majority_percentage: 67,
+        };
+        assert!(quorum.reaches(67));
+        assert!(quorum.reaches(75));
+        assert!(!quorum.reaches(80));
+    }
+
+    #[test]
+    fn test_unsigned_transaction_is_deterministic() {
+        let tx = UnsignedTransaction {
+            chain_id: 314159,
+            from: H160::repeat_byte(1),
+            to: H160::repeat_byte(2),
+            nonce: 7,
+            value: U256::from(100),
+            data: Bytes::from(vec![1, 2, 3]),
+            gas_limit: 21000,
+            max_fee_per_gas: U256::from(1000),
+            max_priority_fee_per_gas: U256::from(10),
+        };
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let parsed: UnsignedTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.sighash(), tx.sighash());
+    }
 }
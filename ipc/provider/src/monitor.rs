@@ -0,0 +1,335 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Prometheus metrics of the provider and the relayer.
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use prometheus::core::Collector;
+use prometheus::{
+    CounterVec, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+macro_rules! metrics {
+    ($($name:ident : $type:ty = $make:expr);* $(;)?) => {
+        $(
+          lazy_static! {
+            pub static ref $name: $type = $make.unwrap();
+          }
+        )*
+
+        pub fn register_metrics(registry: &Registry) -> anyhow::Result<()> {
+          $(registry.register(Box::new($name.clone()))?;)*
+          Ok(())
+        }
+    };
+}
+
+metrics! {
+    RELAYER_SUBMITTED_CHECKPOINTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_submitted_checkpoints",
+            "Number of bottom-up checkpoints submitted"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_FAILED_SUBMISSIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_failed_submissions",
+            "Number of submission attempts that failed with an error"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_TIMED_OUT_SUBMISSIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_timed_out_submissions",
+            "Number of submission attempts that exceeded their deadline"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_SKIPPED_EMPTY_CHECKPOINTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_skipped_empty_checkpoints",
+            "Number of empty checkpoint submissions skipped"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_HELD_EMPTY_CHECKPOINTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "relayer_held_empty_checkpoints",
+            "Number of empty checkpoints currently held back until a non-empty one is ready"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_PERIODS_BEHIND: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "relayer_periods_behind",
+            "Number of checkpoint periods the parent is behind the child, besides the one in flight"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_PIPELINE_QUEUED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "relayer_pipeline_queued",
+            "Number of checkpoints fetched from the child and waiting for their submission"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_PIPELINE_STALLS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_pipeline_stalls",
+            "Number of times the scanning of the child waited for the submissions to catch up"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_PIPELINE_STALLED_SECONDS: CounterVec = CounterVec::new(
+        Opts::new(
+            "relayer_pipeline_stalled_seconds",
+            "Time the scanning of the child spent waiting for the submissions to catch up"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_REFUSED_CHECKPOINTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_refused_checkpoints",
+            "Number of checkpoints not relayed because they violate a submission policy"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_TIMED_OUT_CALLS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_timed_out_calls",
+            "Number of subnet calls that exceeded their deadline"
+        ),
+        &["relayer", "call"]
+    );
+
+    RELAYER_PHASE_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "relayer_phase_seconds",
+            "Time spent in each phase of the checkpoint submissions, when profiling"
+        )
+        .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0]),
+        &["relayer", "phase"]
+    );
+
+    RELAYER_QUORUM_FORMATION_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "relayer_quorum_formation_seconds",
+            "Time between the child block at the checkpoint height and the one where its quorum was reached"
+        )
+        .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0]),
+        &["relayer"]
+    );
+
+    RELAYER_QUORUM_TO_SUBMISSION_SECONDS: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "relayer_quorum_to_submission_seconds",
+            "Time between the quorum of a checkpoint in the child and its successful submission"
+        )
+        .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0]),
+        &["relayer"]
+    );
+
+    RELAYER_QUORUM_SIGNATORIES: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "relayer_quorum_signatories",
+            "Number of validators that signed the last checkpoint whose quorum was reached"
+        ),
+        &["relayer"]
+    );
+
+    RELAYER_RESTARTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "relayer_restarts",
+            "Number of times a relayer task was restarted by the service"
+        ),
+        &["relayer"]
+    );
+
+    OBSERVER_VERIFIED_CHECKPOINTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "observer_verified_checkpoints",
+            "Number of committed checkpoints matching the child"
+        ),
+        &["relayer"]
+    );
+
+    OBSERVER_DIVERGENT_CHECKPOINTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "observer_divergent_checkpoints",
+            "Number of committed checkpoints diverging from the child"
+        ),
+        &["relayer"]
+    );
+
+    OBSERVER_LAST_VERIFIED_HEIGHT: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "observer_last_verified_height",
+            "Height of the last committed checkpoint matching the child"
+        ),
+        &["relayer"]
+    );
+
+    OBSERVER_LAST_DIVERGENT_HEIGHT: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "observer_last_divergent_height",
+            "Height of the last committed checkpoint diverging from the child"
+        ),
+        &["relayer"]
+    );
+
+    OBSERVER_DIVERGENCE_ALERTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "observer_divergence_alerts",
+            "Number of divergences between committed checkpoints and the child chain, by kind"
+        ),
+        &["relayer", "kind"]
+    );
+
+    OBSERVER_COMMIT_LAG: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "observer_commit_lag",
+            "Number of child blocks since the last committed checkpoint"
+        ),
+        &["relayer"]
+    );
+
+    VALIDATOR_PROPOSED_BLOCKS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "validator_proposed_blocks",
+            "Number of sampled child blocks proposed by a validator"
+        ),
+        &["subnet", "validator"]
+    );
+
+    VALIDATOR_LAST_PROPOSED_HEIGHT: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "validator_last_proposed_height",
+            "Height of the last sampled child block proposed by a validator"
+        ),
+        &["subnet", "validator"]
+    );
+
+    VALIDATOR_PROPOSAL_SHARE: GaugeVec = GaugeVec::new(
+        Opts::new(
+            "validator_proposal_share",
+            "Share of the recent sampled child blocks proposed by a validator"
+        ),
+        &["subnet", "validator"]
+    );
+
+    SUBNET_HEIGHT_DRIFT: GaugeVec = GaugeVec::new(
+        Opts::new(
+            "subnet_height_drift",
+            "Relative difference between the actual and the expected progression of the child height over parent time"
+        ),
+        &["subnet"]
+    );
+
+    SUBNET_CLOCK_SKEW_SECONDS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "subnet_clock_skew_seconds",
+            "Difference between the timestamps of the child and the parent heads"
+        ),
+        &["subnet"]
+    );
+
+    SUBNET_DRIFT_ALERTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "subnet_drift_alerts",
+            "Number of alerts of clock skew, stall or height drift of a child subnet"
+        ),
+        &["subnet", "kind"]
+    );
+
+    SUBMITTER_TOP_UPS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "submitter_top_ups",
+            "Number of top-ups of the submitter from the treasury"
+        ),
+        &["relayer"]
+    );
+
+    SUBMITTER_TOP_UP_ALERTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "submitter_top_up_alerts",
+            "Number of top-ups of the submitter needed but not sent, by cause"
+        ),
+        &["relayer", "kind"]
+    );
+
+    CIRCUIT_BREAKER_STATE: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "circuit_breaker_state",
+            "State of the circuit breaker of an endpoint: 0 closed, 1 open, 2 half-open"
+        ),
+        &["endpoint"]
+    );
+
+    CIRCUIT_BREAKER_TRANSITIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "circuit_breaker_transitions",
+            "Number of state changes of the circuit breaker of an endpoint"
+        ),
+        &["endpoint", "state"]
+    );
+}
+
+/// A registry of the metrics along with the `collectors` of the embedder, which is in charge
+/// of serving them, e.g. [`encode`]d at a `/metrics` endpoint.
+pub fn setup(collectors: Vec<Box<dyn Collector>>) -> anyhow::Result<Registry> {
+    let registry = Registry::new();
+    register_metrics(&registry)?;
+    for collector in collectors {
+        let names = collector
+            .desc()
+            .iter()
+            .map(|d| d.fq_name.clone())
+            .collect::<Vec<_>>();
+        registry
+            .register(collector)
+            .with_context(|| format!("cannot register the collector of {names:?}"))?;
+    }
+    Ok(registry)
+}
+
+/// The metrics of `registry` in the Prometheus text format.
+pub fn encode(registry: &Registry) -> String {
+    let mut buffer = String::new();
+    if let Err(e) = TextEncoder::new().encode_utf8(&registry.gather(), &mut buffer) {
+        log::error!("cannot encode metrics: {e}");
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, setup};
+    use prometheus::{IntCounter, Opts};
+
+    #[test]
+    fn test_custom_collectors() {
+        let deposits = IntCounter::with_opts(Opts::new("app_deposits", "Deposits")).unwrap();
+        let registry = setup(vec![Box::new(deposits.clone())]).unwrap();
+
+        deposits.inc();
+        let metrics = encode(&registry);
+        assert!(metrics.contains("app_deposits 1"));
+        assert!(metrics.contains("relayer_submitted_checkpoints"));
+
+        // the names of the built-ins are taken
+        let clash = IntCounter::new("circuit_breaker_state", "Clash").unwrap();
+        assert!(registry.register(Box::new(clash)).is_err());
+    }
+}
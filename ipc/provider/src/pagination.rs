@@ -0,0 +1,240 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Pagination and filtering of the list APIs.
+//!
+//! A page starts after the opaque cursor returned with the previous one, so that the pages
+//! stay consistent as new items are appended, e.g. in the blocks after the current head.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// The number of items of a page when its limit is not set.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// The cursor returned with the previous page, if any.
+    pub cursor: Option<String>,
+    /// The maximum number of items of the page, [`DEFAULT_PAGE_LIMIT`] if not set.
+    pub limit: Option<usize>,
+}
+
+impl PageRequest {
+    pub fn new(cursor: Option<String>, limit: Option<usize>) -> Self {
+        Self { cursor, limit }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).max(1)
+    }
+
+    /// The cursor the page starts after, parsed.
+    pub fn after<K>(&self) -> Result<Option<K>>
+    where
+        K: FromStr,
+        K::Err: Display,
+    {
+        self.cursor
+            .as_deref()
+            .map(|c| K::from_str(c).map_err(|e| anyhow!("invalid cursor {c}: {e}")))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The cursor of the next page, none if this is the last one.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// A range of heights, up to the chain head if `to` is not set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeightRange {
+    pub from: ChainEpoch,
+    pub to: Option<ChainEpoch>,
+}
+
+impl HeightRange {
+    pub fn new(from: ChainEpoch, to: Option<ChainEpoch>) -> Self {
+        Self { from, to }
+    }
+
+    /// The inclusive range of heights, given the current `head` of the chain.
+    pub fn resolve(&self, head: ChainEpoch) -> RangeInclusive<ChainEpoch> {
+        self.from..=self.to.map_or(head, |to| to.min(head))
+    }
+}
+
+/// The position of an item in a list ordered by height, e.g. the index of a message among the
+/// messages of its block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct HeightCursor {
+    pub height: ChainEpoch,
+    pub index: u64,
+}
+
+impl Display for HeightCursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.height, self.index)
+    }
+}
+
+impl FromStr for HeightCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (height, index) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected <height>:<index>"))?;
+        Ok(Self {
+            height: height.parse()?,
+            index: index.parse()?,
+        })
+    }
+}
+
+/// The page of `items` after the cursor of `page`, in the order of their `key`, which is
+/// also their cursor.
+pub fn paginate<T, K>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> K,
+    page: &PageRequest,
+) -> Result<Page<T>>
+where
+    K: Ord + Display + FromStr,
+    K::Err: Display,
+{
+    let after = page.after::<K>()?;
+    let limit = page.limit();
+
+    items.sort_by(|a, b| key(a).cmp(&key(b)));
+    let mut items = items
+        .into_iter()
+        .filter(|i| after.as_ref().map_or(true, |a| key(i) > *a))
+        .take(limit + 1)
+        .collect::<Vec<_>>();
+
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|i| key(i).to_string())
+    } else {
+        None
+    };
+    Ok(Page { items, next_cursor })
+}
+
+/// The page of the items at the `heights` matching `filter`, after the cursor of `page`.
+/// The items are fetched one height at a time with `fetch`, and the scan stops as soon as
+/// the page is full.
+pub async fn scan_heights<T, F, Fut>(
+    heights: RangeInclusive<ChainEpoch>,
+    page: &PageRequest,
+    filter: impl Fn(&T) -> bool,
+    mut fetch: F,
+) -> Result<Page<(ChainEpoch, T)>>
+where
+    F: FnMut(ChainEpoch) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let after = page.after::<HeightCursor>()?;
+    let limit = page.limit();
+    let start = after.map_or(*heights.start(), |a| a.height.max(*heights.start()));
+
+    let mut items = Vec::new();
+    let mut last = None;
+    for height in start..=*heights.end() {
+        for (index, item) in fetch(height).await?.into_iter().enumerate() {
+            // the index counts the filtered out items too, for the cursors to stay stable
+            let position = HeightCursor {
+                height,
+                index: index as u64,
+            };
+            if after.is_some_and(|a| position <= a) || !filter(&item) {
+                continue;
+            }
+            if items.len() == limit {
+                return Ok(Page {
+                    items,
+                    next_cursor: last.map(|c: HeightCursor| c.to_string()),
+                });
+            }
+            items.push((height, item));
+            last = Some(position);
+        }
+    }
+    Ok(Page {
+        items,
+        next_cursor: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{paginate, scan_heights, HeightCursor, HeightRange, PageRequest};
+    use fvm_shared::clock::ChainEpoch;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_paginate() {
+        let items = vec!["c", "a", "e", "b", "d"];
+        let page = PageRequest::new(None, Some(2));
+        let first = paginate(items.clone(), |i| i.to_string(), &page).unwrap();
+        assert_eq!(first.items, vec!["a", "b"]);
+        assert_eq!(first.next_cursor.as_deref(), Some("b"));
+
+        let page = PageRequest::new(first.next_cursor, Some(2));
+        let second = paginate(items.clone(), |i| i.to_string(), &page).unwrap();
+        assert_eq!(second.items, vec!["c", "d"]);
+
+        let page = PageRequest::new(second.next_cursor, Some(2));
+        let last = paginate(items, |i| i.to_string(), &page).unwrap();
+        assert_eq!(last.items, vec!["e"]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn test_height_cursor() {
+        let cursor = HeightCursor {
+            height: 10,
+            index: 2,
+        };
+        assert_eq!(HeightCursor::from_str(&cursor.to_string()).unwrap(), cursor);
+        assert!(HeightCursor::from_str("10").is_err());
+        assert_eq!(HeightRange::new(5, None).resolve(20), 5..=20);
+        assert_eq!(HeightRange::new(5, Some(30)).resolve(20), 5..=20);
+    }
+
+    #[tokio::test]
+    async fn test_scan_heights() {
+        // three items per height, the odd ones filtered out
+        let fetch = |h: ChainEpoch| async move {
+            Ok::<_, anyhow::Error>(vec![h * 10, h * 10 + 1, h * 10 + 2])
+        };
+        let even = |i: &ChainEpoch| i % 2 == 0;
+
+        let page = PageRequest::new(None, Some(3));
+        let first = scan_heights(1..=3, &page, even, fetch).await.unwrap();
+        assert_eq!(first.items, vec![(1, 10), (1, 12), (2, 20)]);
+        assert_eq!(first.next_cursor.as_deref(), Some("2:0"));
+
+        let page = PageRequest::new(first.next_cursor, Some(3));
+        let second = scan_heights(1..=3, &page, even, fetch).await.unwrap();
+        assert_eq!(second.items, vec![(2, 22), (3, 30), (3, 32)]);
+        assert_eq!(second.next_cursor, None);
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The balances of an address across the configured subnets of the hierarchy, for the
+//! operators managing funds at several levels.
+//!
+//! The balances of a subnet are in the native coin of the root of its hierarchy, unless the
+//! subnet or one of its ancestors is funded with an ERC20 token of its parent, in which case
+//! they are in that token. The totals add up the balances of the same [`Denomination`] only.
+
+use crate::lotus::message::deserialize::deserialize_token_amount_from_str;
+use crate::lotus::message::serialize::serialize_token_amount_to_atto;
+use fvm_shared::econ::TokenAmount;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The token the balances of a subnet are denominated in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Denomination {
+    /// The native coin of the `root` network, e.g. FIL.
+    Native { root: String },
+    /// The ERC20 `token` deployed in `subnet`, the parent of the subnet funded with it.
+    Erc20 { subnet: String, token: String },
+}
+
+impl Display for Denomination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Denomination::Native { root } => write!(f, "native coin of {root}"),
+            Denomination::Erc20 { subnet, token } => write!(f, "ERC20 {token} of {subnet}"),
+        }
+    }
+}
+
+/// The balance of the address in a subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetBalance {
+    pub subnet: String,
+    pub denomination: Denomination,
+    /// The balance in atto units of the denomination.
+    #[serde(
+        serialize_with = "serialize_token_amount_to_atto",
+        deserialize_with = "deserialize_token_amount_from_str"
+    )]
+    pub balance: TokenAmount,
+}
+
+/// The sum of the balances of the same denomination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DenominationTotal {
+    pub denomination: Denomination,
+    #[serde(
+        serialize_with = "serialize_token_amount_to_atto",
+        deserialize_with = "deserialize_token_amount_from_str"
+    )]
+    pub total: TokenAmount,
+}
+
+/// A subnet whose balance could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetBalanceError {
+    pub subnet: String,
+    pub error: String,
+}
+
+/// The balances of an address in every configured subnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub address: String,
+    /// The balances by subnet, in the order of the subnets.
+    pub balances: Vec<SubnetBalance>,
+    pub totals: Vec<DenominationTotal>,
+    /// The subnets left out of the totals because they could not be queried.
+    pub errors: Vec<SubnetBalanceError>,
+}
+
+impl Portfolio {
+    /// Sums the `balances` of `address` by denomination.
+    pub fn new(
+        address: String,
+        mut balances: Vec<SubnetBalance>,
+        mut errors: Vec<SubnetBalanceError>,
+    ) -> Self {
+        balances.sort_by(|a, b| a.subnet.cmp(&b.subnet));
+        errors.sort_by(|a, b| a.subnet.cmp(&b.subnet));
+
+        let mut totals = BTreeMap::<Denomination, TokenAmount>::new();
+        for b in &balances {
+            *totals.entry(b.denomination.clone()).or_default() += &b.balance;
+        }
+        Self {
+            address,
+            balances,
+            totals: totals
+                .into_iter()
+                .map(|(denomination, total)| DenominationTotal {
+                    denomination,
+                    total,
+                })
+                .collect(),
+            errors,
+        }
+    }
+}
+
+impl Display for Portfolio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "address: {}", self.address)?;
+        for b in &self.balances {
+            writeln!(f, "{}: {} ({})", b.subnet, b.balance, b.denomination)?;
+        }
+        for t in &self.totals {
+            writeln!(f, "total: {} ({})", t.total, t.denomination)?;
+        }
+        for e in &self.errors {
+            writeln!(f, "{}: cannot read the balance: {}", e.subnet, e.error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Denomination, Portfolio, SubnetBalance};
+    use fvm_shared::econ::TokenAmount;
+
+    fn balance(subnet: &str, denomination: &Denomination, atto: u64) -> SubnetBalance {
+        SubnetBalance {
+            subnet: subnet.to_string(),
+            denomination: denomination.clone(),
+            balance: TokenAmount::from_atto(atto),
+        }
+    }
+
+    #[test]
+    fn test_totals_by_denomination() {
+        let fil = Denomination::Native {
+            root: "/r314159".to_string(),
+        };
+        let erc20 = Denomination::Erc20 {
+            subnet: "/r314159".to_string(),
+            token: "0x0101010101010101010101010101010101010101".to_string(),
+        };
+        let portfolio = Portfolio::new(
+            "t410f".to_string(),
+            vec![
+                balance("/r314159/t410fb", &erc20, 7),
+                balance("/r314159/t410fa", &fil, 10),
+                balance("/r314159", &fil, 5),
+            ],
+            vec![],
+        );
+
+        assert_eq!(portfolio.balances[0].subnet, "/r314159");
+        assert_eq!(portfolio.totals.len(), 2);
+        assert_eq!(portfolio.totals[0].denomination, fil);
+        assert_eq!(portfolio.totals[0].total, TokenAmount::from_atto(15));
+        assert_eq!(portfolio.totals[1].denomination, erc20);
+        assert_eq!(portfolio.totals[1].total, TokenAmount::from_atto(7));
+
+        let json = serde_json::to_string(&portfolio).unwrap();
+        assert_eq!(serde_json::from_str::<Portfolio>(&json).unwrap(), portfolio);
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Inspection of the postbox of the gateway of a subnet, holding the multi-hop cross-messages
+//! it applied until someone propagates them to their next hop.
+//!
+//! The postbox is a mapping of the gateway that cannot be listed: its candidates are rebuilt
+//! from the messages the subnet received from a neighbour, the checkpoints committed by one of
+//! its children or the top-down messages sent by its parent, and the ones still in the postbox
+//! are kept.
+
+use crate::checkpoint::replay::find_stuck_msgs;
+use crate::manager::SubnetManager;
+use anyhow::{anyhow, Result};
+use fvm_shared::clock::ChainEpoch;
+use ipc_api::cross::IpcEnvelope;
+use ipc_api::subnet_id::SubnetID;
+use std::fmt::{Display, Formatter};
+
+/// How a message reached the postbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostboxSource {
+    /// In the checkpoint of the child `subnet` committed at child `height`.
+    Checkpoint {
+        subnet: SubnetID,
+        height: ChainEpoch,
+    },
+    /// Sent by the parent at parent `height`.
+    TopDown { height: ChainEpoch },
+}
+
+/// A message in the postbox, waiting to be propagated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostboxMsg {
+    /// The key of the message in the postbox, to propagate it with.
+    pub key: Vec<u8>,
+    pub msg: IpcEnvelope,
+    pub source: PostboxSource,
+}
+
+impl Display for PostboxMsg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            PostboxSource::Checkpoint { subnet, height } => {
+                write!(f, "checkpoint of {subnet} at {height}")?
+            }
+            PostboxSource::TopDown { height } => write!(f, "top-down at {height}")?,
+        }
+        write!(
+            f,
+            ", nonce {}: {:?} from {} to {}, value {}, key 0x{}",
+            self.msg.nonce,
+            self.msg.kind,
+            self.msg.from.to_string().unwrap_or_default(),
+            self.msg.to.to_string().unwrap_or_default(),
+            self.msg.value,
+            hex::encode(&self.key)
+        )
+    }
+}
+
+/// Where the messages of `source` arrive in `subnet` from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Neighbour {
+    Child,
+    Parent,
+}
+
+pub(crate) fn neighbour(subnet: &SubnetID, source: &SubnetID) -> Result<Neighbour> {
+    if source.parent().as_ref() == Some(subnet) {
+        Ok(Neighbour::Child)
+    } else if subnet.parent().as_ref() == Some(source) {
+        Ok(Neighbour::Parent)
+    } else {
+        Err(anyhow!(
+            "{source} is neither the parent nor a child of {subnet}"
+        ))
+    }
+}
+
+/// The messages of the checkpoints of the child `child` committed between its `from` and `to`
+/// heights still in the postbox of `gateway`, the manager of its parent.
+pub async fn from_child(
+    gateway: &dyn SubnetManager,
+    child: &SubnetID,
+    from: ChainEpoch,
+    to: ChainEpoch,
+) -> Result<Vec<PostboxMsg>> {
+    Ok(find_stuck_msgs(gateway, child, from, to)
+        .await?
+        .into_iter()
+        .map(|stuck| PostboxMsg {
+            key: stuck.postbox_key,
+            msg: stuck.msg,
+            source: PostboxSource::Checkpoint {
+                subnet: child.clone(),
+                height: stuck.checkpoint_height,
+            },
+        })
+        .collect())
+}
+
+/// The messages sent by `parent` to `subnet` between its `from` and `to` heights still in the
+/// postbox of `gateway`, the manager of the subnet.
+pub async fn from_parent(
+    parent: &dyn SubnetManager,
+    gateway: &dyn SubnetManager,
+    subnet: &SubnetID,
+    from: ChainEpoch,
+    to: ChainEpoch,
+) -> Result<Vec<PostboxMsg>> {
+    let mut msgs = vec![];
+    for (height, msg) in parent.top_down_msgs_in_parent(subnet, from, to).await? {
+        // the messages to the subnet itself are executed on arrival
+        if &msg.to.subnet()? == subnet {
+            continue;
+        }
+        let key = gateway.postbox_key(&msg)?;
+        if gateway.in_postbox(&key).await? {
+            msgs.push(PostboxMsg {
+                key,
+                msg,
+                source: PostboxSource::TopDown { height },
+            });
+        }
+    }
+    Ok(msgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{neighbour, Neighbour};
+    use fvm_shared::address::Address;
+    use ipc_api::subnet_id::SubnetID;
+
+    #[test]
+    fn test_neighbour() {
+        let root = SubnetID::new_root(1234);
+        let child = SubnetID::new_from_parent(&root, Address::new_id(100));
+        let grandchild = SubnetID::new_from_parent(&child, Address::new_id(101));
+
+        assert_eq!(neighbour(&child, &grandchild).unwrap(), Neighbour::Child);
+        assert_eq!(neighbour(&child, &root).unwrap(), Neighbour::Parent);
+        assert!(neighbour(&root, &grandchild).is_err());
+        assert!(neighbour(&child, &child).is_err());
+    }
+}
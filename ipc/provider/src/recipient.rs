@@ -0,0 +1,136 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The recipients of the operations moving value, given either by address or by their name in
+//! the [`AddressBook`] of the wallet.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::subnet_id::SubnetID;
+use ipc_wallet::AddressBook;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recipient {
+    Address(Address),
+    /// The name of an entry of the address book.
+    Name(String),
+}
+
+impl Recipient {
+    /// Resolves the recipient in `subnet`, looking names up in `book`.
+    pub fn resolve(&self, book: Option<&AddressBook>, subnet: &SubnetID) -> Result<Address> {
+        match self {
+            Recipient::Address(addr) => Ok(*addr),
+            Recipient::Name(name) => {
+                let book = book.ok_or_else(|| {
+                    anyhow!("cannot resolve recipient {name}: no address book in provider")
+                })?;
+                let entry = book
+                    .lookup(name, Some(&subnet.to_string()))
+                    .ok_or_else(|| anyhow!("recipient {name} not found in the address book"))?;
+                parse_address(&entry.address)
+                    .map_err(|e| anyhow!("invalid address of recipient {name}: {e}"))
+            }
+        }
+    }
+}
+
+impl From<Address> for Recipient {
+    fn from(addr: Address) -> Self {
+        Recipient::Address(addr)
+    }
+}
+
+impl FromStr for Recipient {
+    type Err = anyhow::Error;
+
+    /// Parses a filecoin or an ethereum address, and falls back to a name otherwise.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(anyhow!("empty recipient"));
+        }
+        match parse_address(s) {
+            Ok(addr) => Ok(Recipient::Address(addr)),
+            Err(_) if s.starts_with("0x") => Err(anyhow!("invalid ethereum address {s}")),
+            Err(_) => Ok(Recipient::Name(s.to_string())),
+        }
+    }
+}
+
+impl Display for Recipient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recipient::Address(addr) => write!(f, "{addr}"),
+            Recipient::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Parses a filecoin address, or an ethereum address into its delegated address.
+pub fn parse_address(s: &str) -> Result<Address> {
+    match Address::from_str(s) {
+        Ok(addr) => Ok(addr),
+        Err(_) => {
+            let addr = ethers::types::Address::from_str(s)?;
+            ethers_address_to_fil_address(&addr)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Recipient;
+    use fvm_shared::address::Address;
+    use ipc_api::subnet_id::SubnetID;
+    use ipc_wallet::{AddressBook, AddressBookEntry};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_recipient() {
+        assert_eq!(
+            Recipient::from_str("f0100").unwrap(),
+            Recipient::Address(Address::new_id(100))
+        );
+        assert!(matches!(
+            Recipient::from_str("0x6be1ccf648c74800380d0520d797a170c808b624").unwrap(),
+            Recipient::Address(_)
+        ));
+        assert_eq!(
+            Recipient::from_str("treasury").unwrap(),
+            Recipient::Name("treasury".to_string())
+        );
+        assert!(Recipient::from_str("0x6be1").is_err());
+    }
+
+    #[test]
+    fn test_resolve_recipient() {
+        let subnet = SubnetID::new_root(314159);
+        let mut book = AddressBook::in_memory();
+        book.insert(AddressBookEntry {
+            name: "treasury".to_string(),
+            address: "0x6be1ccf648c74800380d0520d797a170c808b624".to_string(),
+            subnet: Some(subnet.to_string()),
+        })
+        .unwrap();
+
+        let treasury = Recipient::Name("treasury".to_string());
+        let addr = treasury.resolve(Some(&book), &subnet).unwrap();
+        assert_eq!(
+            addr,
+            super::parse_address("0x6be1ccf648c74800380d0520d797a170c808b624").unwrap()
+        );
+
+        assert!(treasury
+            .resolve(Some(&book), &SubnetID::new_root(1))
+            .is_err());
+        assert!(treasury.resolve(None, &subnet).is_err());
+        assert_eq!(
+            Recipient::Address(Address::new_id(100))
+                .resolve(None, &subnet)
+                .unwrap(),
+            Address::new_id(100)
+        );
+    }
+}
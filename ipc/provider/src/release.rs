@@ -0,0 +1,226 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Verification of the deployed contracts against the code of known releases of the contracts,
+//! see [`crate::IpcProvider::verify_contracts`].
+//!
+//! The releases are listed from the oldest to the latest in a JSON file, with the keccak256
+//! hashes of the runtime code of their diamonds and facets by contract name:
+//!
+//! ```json
+//! {
+//!   "releases": [
+//!     {
+//!       "version": "v0.1.0",
+//!       "contracts": { "GatewayDiamond": "0x...", "GatewayGetterFacet": "0x..." }
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::manager::ContractCode;
+use anyhow::{Context, Result};
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// A release of the contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String,
+    /// The hex encoded code hashes of the contracts, by contract name.
+    pub contracts: HashMap<String, String>,
+}
+
+/// The known releases of the contracts, from the oldest to the latest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownReleases {
+    pub releases: Vec<Release>,
+}
+
+impl KnownReleases {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse the releases of {}", path.display()))
+    }
+
+    /// The status of the code with `code_hash`: of the latest release it is part of, and
+    /// whether a later release changed the code of that contract.
+    pub fn status(&self, code_hash: &[u8; 32]) -> CodeStatus {
+        let hash = hex::encode(code_hash);
+        let same = |h: &String| h.trim_start_matches("0x").eq_ignore_ascii_case(&hash);
+
+        let Some((index, contract)) = self.releases.iter().enumerate().rev().find_map(|(i, r)| {
+            r.contracts
+                .iter()
+                .find(|(_, h)| same(h))
+                .map(|(name, _)| (i, name.clone()))
+        }) else {
+            return CodeStatus::Unknown;
+        };
+
+        let version = self.releases[index].version.clone();
+        let latest = self.releases[index + 1..]
+            .iter()
+            .rev()
+            .find(|r| matches!(r.contracts.get(&contract), Some(h) if !same(h)));
+        match latest {
+            Some(latest) => CodeStatus::Outdated {
+                contract,
+                version,
+                latest: latest.version.clone(),
+            },
+            None => CodeStatus::Current { contract, version },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CodeStatus {
+    /// The code is the one of `contract` in its latest release.
+    Current { contract: String, version: String },
+    /// The code is the one of `contract` in `version`, which changed in `latest`.
+    Outdated {
+        contract: String,
+        version: String,
+        latest: String,
+    },
+    /// The code is not part of any known release.
+    Unknown,
+}
+
+/// The contracts verified by [`crate::IpcProvider::verify_contracts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractRole {
+    Gateway,
+    Registry,
+    /// The subnet actor of the subnet in its parent.
+    SubnetActor,
+}
+
+impl Display for ContractRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractRole::Gateway => write!(f, "gateway"),
+            ContractRole::Registry => write!(f, "registry"),
+            ContractRole::SubnetActor => write!(f, "subnet actor"),
+        }
+    }
+}
+
+/// The verification of the code of a diamond or of one of its facets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractCheck {
+    pub role: ContractRole,
+    /// Whether the contract is a facet of the diamond of the role, or the diamond itself.
+    pub facet: bool,
+    pub address: Address,
+    pub code_hash: String,
+    pub status: CodeStatus,
+}
+
+impl ContractCheck {
+    pub(crate) fn new(
+        role: ContractRole,
+        facet: bool,
+        code: &ContractCode,
+        releases: &KnownReleases,
+    ) -> Self {
+        Self {
+            role,
+            facet,
+            address: code.address,
+            code_hash: format!("0x{}", hex::encode(code.code_hash)),
+            status: releases.status(&code.code_hash),
+        }
+    }
+
+    pub fn is_current(&self) -> bool {
+        matches!(self.status, CodeStatus::Current { .. })
+    }
+}
+
+impl Display for ContractCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.facet { " facet" } else { "" };
+        write!(f, "{}{kind} at {}: ", self.role, self.address)?;
+        match &self.status {
+            CodeStatus::Current { contract, version } => write!(f, "{contract} of {version}"),
+            CodeStatus::Outdated {
+                contract,
+                version,
+                latest,
+            } => write!(f, "outdated {contract} of {version}, changed in {latest}"),
+            CodeStatus::Unknown => write!(f, "unknown code {}", self.code_hash),
+        }
+    }
+}
+
+/// The verification of all the contracts of a subnet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractsReport {
+    pub checks: Vec<ContractCheck>,
+}
+
+impl ContractsReport {
+    /// The contracts that are unknown or outdated.
+    pub fn warnings(&self) -> impl Iterator<Item = &ContractCheck> {
+        self.checks.iter().filter(|c| !c.is_current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodeStatus, KnownReleases};
+
+    fn releases() -> KnownReleases {
+        serde_json::from_str(&format!(
+            r#"{{
+              "releases": [
+                {{ "version": "v1", "contracts": {{ "Getter": "0x{a}", "Manager": "0x{b}" }} }},
+                {{ "version": "v2", "contracts": {{ "Getter": "0x{c}", "Manager": "0x{b}" }} }}
+              ]
+            }}"#,
+            a = hex::encode([1; 32]),
+            b = hex::encode([2; 32]),
+            c = hex::encode([3; 32]),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_code_status() {
+        let releases = releases();
+
+        assert_eq!(
+            releases.status(&[3; 32]),
+            CodeStatus::Current {
+                contract: "Getter".to_string(),
+                version: "v2".to_string()
+            }
+        );
+        // unchanged since the first release
+        assert_eq!(
+            releases.status(&[2; 32]),
+            CodeStatus::Current {
+                contract: "Manager".to_string(),
+                version: "v2".to_string()
+            }
+        );
+        assert_eq!(
+            releases.status(&[1; 32]),
+            CodeStatus::Outdated {
+                contract: "Getter".to_string(),
+                version: "v1".to_string(),
+                latest: "v2".to_string()
+            }
+        );
+        assert_eq!(releases.status(&[4; 32]), CodeStatus::Unknown);
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Versioned JSON schemas of the machine-readable outputs of the relayer.
+//!
+//! Every output is wrapped in an envelope naming its schema and the version of the schema, so
+//! that a consumer detects a change of the fields instead of silently reading defaults. The
+//! version of a schema is bumped on every change that is not the addition of a field.
+
+use crate::approval::TokenApprovals;
+use crate::bootstrap::SubnetBootstrapInfo;
+use crate::checkpoint::attestation::SignedAttestation;
+use crate::checkpoint::inspect::RelayerInspection;
+use crate::checkpoint::service::ServiceStatus;
+use crate::doctor::DoctorReport;
+use crate::events::SubnetEventRecord;
+use crate::portfolio::Portfolio;
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// An output with a versioned schema.
+pub trait OutputSchema: Serialize {
+    /// The name of the schema, e.g. `checkpoint_inspection`.
+    const SCHEMA: &'static str;
+    /// The version of the schema the output is written with.
+    const SCHEMA_VERSION: u32;
+}
+
+impl OutputSchema for RelayerInspection {
+    const SCHEMA: &'static str = "checkpoint_inspection";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for ServiceStatus {
+    const SCHEMA: &'static str = "relayer_service_status";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for SubnetEventRecord {
+    const SCHEMA: &'static str = "subnet_event";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for DoctorReport {
+    const SCHEMA: &'static str = "relayer_doctor";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for SubnetBootstrapInfo {
+    const SCHEMA: &'static str = "subnet_bootstrap_info";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for SignedAttestation {
+    const SCHEMA: &'static str = "relayer_status_attestation";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for Portfolio {
+    const SCHEMA: &'static str = "wallet_portfolio";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl OutputSchema for TokenApprovals {
+    const SCHEMA: &'static str = "wallet_approvals";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl<T: OutputSchema> OutputSchema for &T {
+    const SCHEMA: &'static str = T::SCHEMA;
+    const SCHEMA_VERSION: u32 = T::SCHEMA_VERSION;
+}
+
+/// The envelope of an output, whose fields are flattened next to `schema` and
+/// `schema_version`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub schema: String,
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T: OutputSchema> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            schema: T::SCHEMA.to_string(),
+            schema_version: T::SCHEMA_VERSION,
+            data,
+        }
+    }
+}
+
+/// Serializes `data` in its versioned envelope, on a single line, e.g. for event streams.
+pub fn to_json<T: OutputSchema>(data: &T) -> Result<String> {
+    Ok(serde_json::to_string(&Versioned::new(data))?)
+}
+
+/// Serializes `data` in its versioned envelope, indented for humans to read.
+pub fn to_json_pretty<T: OutputSchema>(data: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&Versioned::new(data))?)
+}
+
+/// Deserializes an output of the schema of `T`, which must be of the version `T` is written
+/// with.
+pub fn from_json<T: OutputSchema + DeserializeOwned>(json: &str) -> Result<T> {
+    #[derive(Deserialize)]
+    struct Header {
+        schema: String,
+        schema_version: u32,
+    }
+
+    let header: Header = serde_json::from_str(json)?;
+    if header.schema != T::SCHEMA {
+        return Err(anyhow!(
+            "expected schema {}, got {}",
+            T::SCHEMA,
+            header.schema
+        ));
+    }
+    if header.schema_version != T::SCHEMA_VERSION {
+        return Err(anyhow!(
+            "unsupported version {} of schema {}, expected {}",
+            header.schema_version,
+            T::SCHEMA,
+            T::SCHEMA_VERSION
+        ));
+    }
+    Ok(serde_json::from_str::<Versioned<T>>(json)?.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_json, to_json};
+    use crate::events::{EventSource, SubnetEvent, SubnetEventRecord};
+    use serde_json::Value;
+
+    #[test]
+    fn test_versioned_roundtrip() {
+        let record = SubnetEventRecord {
+            source: EventSource::Parent,
+            height: 10,
+            event: SubnetEvent::CheckpointCommitted { height: 5 },
+        };
+
+        let json = to_json(&record).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema"], "subnet_event");
+        assert_eq!(value["schema_version"], 1);
+        assert_eq!(value["source"], "parent");
+        assert_eq!(value["event"]["type"], "checkpoint_committed");
+
+        assert_eq!(from_json::<SubnetEventRecord>(&json).unwrap(), record);
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let json = r#"{"schema":"subnet_event","schema_version":2,"source":"parent","height":10,"event":{"type":"subnet_killed"}}"#;
+        assert!(from_json::<SubnetEventRecord>(json).is_err());
+
+        let json = r#"{"schema":"relayer_service_status","schema_version":1,"source":"parent","height":10,"event":{"type":"subnet_killed"}}"#;
+        assert!(from_json::<SubnetEventRecord>(json).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The automatic funding of the submitter of a relayer from a treasury account, keeping an
+//! unattended relayer able to pay for its submissions.
+//!
+//! Whenever the balance of the submitter drops below a threshold, a fixed amount is sent to it
+//! from the treasury. The amounts sent within a window are capped, so that a submitter burning
+//! through its funds, e.g. stuck resubmitting a refused checkpoint, cannot drain the treasury:
+//! once the cap is reached, or if the treasury cannot afford the amount, an alert is raised
+//! instead. A transfer counts against the cap as soon as it may have been broadcast, and the
+//! transfers are read back from the transaction journal, if any, to keep the cap across
+//! restarts. The transfers sent since the start count all the same, should the journal miss
+//! them.
+
+use crate::clock::{default_clock, unix_secs, Clock};
+use crate::journal::{TxIntent, TxJournal, TxStatus};
+use crate::manager::{SubnetQuery, SubnetTx};
+use crate::monitor;
+use crate::recipient::parse_address;
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use ipc_api::evm::payload_to_evm_address;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The default window the amounts sent are capped over.
+pub const DEFAULT_CAP_WINDOW: Duration = Duration::from_secs(86400);
+
+/// When and how much to top up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopUpPolicy {
+    /// The balance of the submitter below which it is topped up.
+    pub threshold: TokenAmount,
+    /// The amount sent by each top-up.
+    pub amount: TokenAmount,
+    /// The total amount that can be sent within `cap_window`.
+    pub cap: TokenAmount,
+    pub cap_window: Duration,
+}
+
+impl TopUpPolicy {
+    /// Sends `amount` whenever the balance drops below `threshold`, at most once per window by
+    /// default.
+    pub fn new(threshold: TokenAmount, amount: TokenAmount) -> Result<Self> {
+        if !amount.is_positive() {
+            return Err(anyhow!("the top-up amount must be positive"));
+        }
+        Ok(Self {
+            threshold,
+            cap: amount.clone(),
+            amount,
+            cap_window: DEFAULT_CAP_WINDOW,
+        })
+    }
+
+    pub fn with_cap(mut self, cap: TokenAmount) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_cap_window(mut self, cap_window: Duration) -> Self {
+        self.cap_window = cap_window;
+        self
+    }
+}
+
+/// A top-up needed but not sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopUpAlert {
+    /// The amounts sent within the window reached the cap.
+    CapReached {
+        spent: TokenAmount,
+        cap: TokenAmount,
+    },
+    /// The treasury holds less than the amount to send.
+    TreasuryLow { balance: TokenAmount },
+    /// The transfer from the treasury failed.
+    TransferFailed { error: String },
+}
+
+impl TopUpAlert {
+    /// The value of the `kind` label of the alert metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TopUpAlert::CapReached { .. } => "cap_reached",
+            TopUpAlert::TreasuryLow { .. } => "treasury_low",
+            TopUpAlert::TransferFailed { .. } => "transfer_failed",
+        }
+    }
+}
+
+impl Display for TopUpAlert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopUpAlert::CapReached { spent, cap } => {
+                write!(f, "top-up cap reached, {spent} sent out of {cap}")
+            }
+            TopUpAlert::TreasuryLow { balance } => {
+                write!(f, "treasury too low to top up, holding {balance}")
+            }
+            TopUpAlert::TransferFailed { error } => write!(f, "top-up failed: {error}"),
+        }
+    }
+}
+
+/// The outcome of a check of the balance of the submitter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopUpOutcome {
+    /// The balance is above the threshold.
+    NotNeeded {
+        balance: TokenAmount,
+    },
+    ToppedUp {
+        amount: TokenAmount,
+    },
+    Alert(TopUpAlert),
+}
+
+/// The amounts sent within the window, by the unix timestamp in seconds they were sent at.
+#[derive(Debug, Default)]
+pub(crate) struct TopUpLedger {
+    sent: VecDeque<(u64, TokenAmount)>,
+}
+
+impl TopUpLedger {
+    /// The transfers to `to` journaled in `journal` which may still land or landed, whether
+    /// their receipt was seen or not. The recipients are compared by the ethereum address the
+    /// transfers are sent to, whichever form of the address they were journaled with.
+    pub fn from_journal(journal: &TxJournal, to: &Address) -> Self {
+        let to = payload_to_evm_address(to.payload()).ok();
+        let sent = journal
+            .entries()
+            .into_iter()
+            .filter(|e| e.status.is_pending() || matches!(e.status, TxStatus::Confirmed { .. }))
+            .filter_map(|e| match e.intent {
+                TxIntent::SendValue(transfer) if evm_recipient(&transfer.to) == to => {
+                    let amount = transfer.amount.parse::<BigInt>().ok()?;
+                    Some((e.created_at, TokenAmount::from_atto(amount)))
+                }
+                _ => None,
+            })
+            .collect();
+        Self { sent }
+    }
+
+    /// The amount to send for the `balance` of the submitter at `now`, or the alert if the cap
+    /// forbids it. Nothing is recorded until the transfer is [`TopUpLedger::record`]ed.
+    pub fn plan(
+        &mut self,
+        policy: &TopUpPolicy,
+        balance: &TokenAmount,
+        now: u64,
+    ) -> Result<Option<TokenAmount>, TopUpAlert> {
+        self.prune(policy.cap_window, now);
+        if balance >= &policy.threshold {
+            return Ok(None);
+        }
+        let spent = self.spent();
+        if spent.clone() + &policy.amount > policy.cap {
+            return Err(TopUpAlert::CapReached {
+                spent,
+                cap: policy.cap.clone(),
+            });
+        }
+        Ok(Some(policy.amount.clone()))
+    }
+
+    pub fn record(&mut self, amount: TokenAmount, now: u64) {
+        self.sent.push_back((now, amount));
+    }
+
+    /// Drops the amounts sent before the window ending at `now`.
+    fn prune(&mut self, window: Duration, now: u64) {
+        while let Some((at, _)) = self.sent.front() {
+            if now.saturating_sub(*at) < window.as_secs() {
+                break;
+            }
+            self.sent.pop_front();
+        }
+    }
+
+    fn spent(&self) -> TokenAmount {
+        self.sent
+            .iter()
+            .fold(TokenAmount::default(), |sum, (_, amount)| sum + amount)
+    }
+}
+
+/// The ethereum address the transfers to `to` are sent to, from its filecoin or ethereum form.
+fn evm_recipient(to: &str) -> Option<ethers::types::Address> {
+    let to = parse_address(to).ok()?;
+    payload_to_evm_address(to.payload()).ok()
+}
+
+/// Tops up the submitter from the treasury, signing with a manager holding the key of the
+/// treasury.
+pub struct SubmitterTopUp<M> {
+    manager: M,
+    treasury: Address,
+    submitter: Address,
+    policy: TopUpPolicy,
+    /// The value of the `relayer` label of the metrics.
+    label: String,
+    /// The amounts sent since the start, a lower bound of the ones read from the journal.
+    ledger: Mutex<TopUpLedger>,
+    /// The journal the transactions of `manager` are recorded in, the ledger is read from.
+    journal: Option<Arc<TxJournal>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<M: SubnetQuery + SubnetTx> SubmitterTopUp<M> {
+    pub fn new(
+        manager: M,
+        treasury: Address,
+        submitter: Address,
+        policy: TopUpPolicy,
+        label: impl Into<String>,
+    ) -> Self {
+        Self {
+            manager,
+            treasury,
+            submitter,
+            policy,
+            label: label.into(),
+            ledger: Mutex::new(TopUpLedger::default()),
+            journal: None,
+            clock: default_clock(),
+        }
+    }
+
+    /// Times the top-ups with `clock`, e.g. a [`crate::clock::ManualClock`] in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Reads the transfers sent to the submitter from `journal`, which the manager should
+    /// record its transactions in. The transfers sent since the start are kept in memory all
+    /// the same, so that a journal missing them cannot lift the cap.
+    pub fn with_journal(mut self, journal: Arc<TxJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Checks the balance of the submitter, topping it up if needed.
+    pub async fn check(&self) -> Result<TopUpOutcome> {
+        let balance = self.manager.wallet_balance(&self.submitter, None).await?;
+        let planned = {
+            let now = unix_secs(self.clock.as_ref());
+            let mut ledger = self.ledger.lock().unwrap();
+            if let Some(journal) = &self.journal {
+                let mut journaled = TopUpLedger::from_journal(journal, &self.submitter);
+                journaled.prune(self.policy.cap_window, now);
+                ledger.prune(self.policy.cap_window, now);
+                if journaled.spent() > ledger.spent() {
+                    *ledger = journaled;
+                }
+            }
+            ledger.plan(&self.policy, &balance, now)
+        };
+        let amount = match planned {
+            Ok(None) => return Ok(TopUpOutcome::NotNeeded { balance }),
+            Ok(Some(amount)) => amount,
+            Err(alert) => return Ok(self.alert(alert)),
+        };
+
+        let treasury = self.manager.wallet_balance(&self.treasury, None).await?;
+        if treasury < amount {
+            return Ok(self.alert(TopUpAlert::TreasuryLow { balance: treasury }));
+        }
+
+        let sent = self
+            .manager
+            .send_value(self.treasury, self.submitter, amount.clone())
+            .await;
+        // A failed transfer is counted all the same, as it may have been broadcast, e.g. when
+        // its receipt timed out.
+        self.ledger
+            .lock()
+            .unwrap()
+            .record(amount.clone(), unix_secs(self.clock.as_ref()));
+        if let Err(e) = sent {
+            return Ok(self.alert(TopUpAlert::TransferFailed {
+                error: e.to_string(),
+            }));
+        }
+        monitor::SUBMITTER_TOP_UPS
+            .with_label_values(&[&self.label])
+            .inc();
+        Ok(TopUpOutcome::ToppedUp { amount })
+    }
+
+    fn alert(&self, alert: TopUpAlert) -> TopUpOutcome {
+        monitor::SUBMITTER_TOP_UP_ALERTS
+            .with_label_values(&[&self.label, alert.kind()])
+            .inc();
+        TopUpOutcome::Alert(alert)
+    }
+
+    /// Checks the balance every `poll_interval` in the foreground, warning about the alerts.
+    pub async fn run(self, poll_interval: Duration) {
+        log::info!(
+            "launching the top-up of submitter {} from treasury {}",
+            self.submitter,
+            self.treasury
+        );
+
+        loop {
+            match self.check().await {
+                Ok(TopUpOutcome::NotNeeded { .. }) => {}
+                Ok(TopUpOutcome::ToppedUp { amount }) => {
+                    log::info!("topped up submitter {} with {amount}", self.submitter)
+                }
+                Ok(TopUpOutcome::Alert(alert)) => log::warn!("{}: {alert}", self.submitter),
+                Err(e) => log::error!("cannot check the balance of {}: {e}", self.submitter),
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TopUpAlert, TopUpLedger, TopUpPolicy};
+    use crate::journal::{NewEntry, TxIntent, TxJournal, TxStatus, ValueTransfer};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_api::ethers_address_to_fil_address;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn test_top_up_capped_within_window() {
+        let policy = TopUpPolicy::new(TokenAmount::from_whole(1), TokenAmount::from_whole(2))
+            .unwrap()
+            .with_cap(TokenAmount::from_whole(4))
+            .with_cap_window(Duration::from_secs(100));
+        let mut ledger = TopUpLedger::default();
+        let now = 1000;
+
+        assert_eq!(
+            ledger.plan(&policy, &TokenAmount::from_whole(1), now),
+            Ok(None)
+        );
+
+        let low = TokenAmount::from_nano(1);
+        for i in 0..2 {
+            let at = now + i;
+            let amount = ledger.plan(&policy, &low, at).unwrap().unwrap();
+            assert_eq!(amount, TokenAmount::from_whole(2));
+            ledger.record(amount, at);
+        }
+        assert_eq!(
+            ledger.plan(&policy, &low, now + 50),
+            Err(TopUpAlert::CapReached {
+                spent: TokenAmount::from_whole(4),
+                cap: TokenAmount::from_whole(4)
+            })
+        );
+
+        // the first top-up leaves the window
+        let later = now + 100;
+        assert!(ledger.plan(&policy, &low, later).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_ledger_from_journal() {
+        let submitter = Address::new_id(1001);
+        let journal = TxJournal::in_memory();
+        let send = |nonce: u64, to: &Address, status| {
+            let id = journal
+                .record(NewEntry {
+                    intent: TxIntent::SendValue(ValueTransfer {
+                        to: to.to_string(),
+                        amount: TokenAmount::from_whole(2).atto().to_string(),
+                    }),
+                    chain_id: 314159,
+                    from: "0x6be1ccf648c74800380d0520d797a170c808b624".to_string(),
+                    nonce,
+                    calldata_hash: "0x00".to_string(),
+                    tx_hash: format!("0x{nonce:064x}"),
+                    raw_tx: "0x00".to_string(),
+                    idempotency_key: None,
+                    params_hash: None,
+                })
+                .unwrap();
+            journal.set_status(id, status).unwrap();
+        };
+
+        // a transfer whose receipt timed out is still broadcast
+        send(0, &submitter, TxStatus::Broadcast);
+        send(1, &submitter, TxStatus::Confirmed { block: 10 });
+        send(
+            2,
+            &submitter,
+            TxStatus::Failed {
+                reason: "rejected".to_string(),
+            },
+        );
+        send(3, &Address::new_id(1002), TxStatus::Broadcast);
+
+        let policy = TopUpPolicy::new(TokenAmount::from_whole(1), TokenAmount::from_whole(2))
+            .unwrap()
+            .with_cap(TokenAmount::from_whole(4));
+        let now = journal.entries()[0].created_at;
+        assert_eq!(
+            TopUpLedger::from_journal(&journal, &submitter).plan(
+                &policy,
+                &TokenAmount::default(),
+                now
+            ),
+            Err(TopUpAlert::CapReached {
+                spent: TokenAmount::from_whole(4),
+                cap: TokenAmount::from_whole(4)
+            })
+        );
+    }
+
+    #[test]
+    fn test_ledger_from_journal_address_forms() {
+        let evm =
+            ethers::types::Address::from_str("0x6be1ccf648c74800380d0520d797a170c808b624").unwrap();
+        let submitter = ethers_address_to_fil_address(&evm).unwrap();
+        let journal = TxJournal::in_memory();
+        journal
+            .record(NewEntry {
+                intent: TxIntent::SendValue(ValueTransfer {
+                    to: format!("{evm:?}"),
+                    amount: TokenAmount::from_whole(2).atto().to_string(),
+                }),
+                chain_id: 314159,
+                from: "0x6be1ccf648c74800380d0520d797a170c808b624".to_string(),
+                nonce: 0,
+                calldata_hash: "0x00".to_string(),
+                tx_hash: format!("0x{:064x}", 0),
+                raw_tx: "0x00".to_string(),
+                idempotency_key: None,
+                params_hash: None,
+            })
+            .unwrap();
+
+        let ledger = TopUpLedger::from_journal(&journal, &submitter);
+        assert_eq!(ledger.spent(), TokenAmount::from_whole(2));
+    }
+
+    #[test]
+    fn test_invalid_amount() {
+        assert!(TopUpPolicy::new(TokenAmount::from_whole(1), TokenAmount::default()).is_err());
+    }
+}
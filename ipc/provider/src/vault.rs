@@ -0,0 +1,275 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! HashiCorp Vault backend for the keys of the submitters.
+//!
+//! Keys can either be stored in a KV v2 secrets engine, from which they are read at startup,
+//! or be kept in a transit secrets engine, which signs the transactions so that the private
+//! key never enters the process. Transit keys must be secp256k1 keys, which requires a
+//! transit engine or plugin supporting that curve.
+
+use crate::manager::evm::recover_signature;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use ethers::types::{Address, Signature, H256, U256};
+use ipc_wallet::EvmKeyInfo;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+use zeroize::Zeroizing;
+
+/// The field of the KV secrets holding the hex encoded private key.
+const PRIVATE_KEY_FIELD: &str = "private_key";
+
+/// The address of the Vault server and the token to authenticate with.
+#[derive(Clone)]
+pub struct VaultConfig {
+    pub address: Url,
+    pub token: Zeroizing<String>,
+}
+
+impl std::fmt::Debug for VaultConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultConfig")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VaultConfig {
+    /// Reads the config from `VAULT_ADDR` and `VAULT_TOKEN`, like the Vault CLI does.
+    pub fn from_env() -> Result<Self> {
+        let address = std::env::var("VAULT_ADDR").context("VAULT_ADDR is not set")?;
+        let token = std::env::var("VAULT_TOKEN").context("VAULT_TOKEN is not set")?;
+        Ok(Self {
+            address: Url::parse(&address).context("invalid VAULT_ADDR")?,
+            token: Zeroizing::new(token),
+        })
+    }
+
+    fn url(&self, mount: &str, path: &str) -> Result<Url> {
+        Ok(self.address.join(&format!("v1/{mount}/{path}"))?)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct VaultClient {
+    config: VaultConfig,
+    client: reqwest::Client,
+}
+
+impl VaultClient {
+    fn new(config: VaultConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let response = request
+            .header("X-Vault-Token", self.config.token.as_str())
+            .send()
+            .await
+            .context("cannot reach vault")?;
+        let status = response.status();
+        if !status.is_success() {
+            // the errors of vault never contain secrets
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("vault responded with {status}: {body}"));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct KvSecret {
+    data: std::collections::HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct TransitSignature {
+    signature: String,
+}
+
+/// The keys stored in a KV v2 secrets engine, under the `private_key` field of the secrets.
+#[derive(Clone, Debug)]
+pub struct VaultKv {
+    client: VaultClient,
+    mount: String,
+}
+
+impl VaultKv {
+    pub fn new(config: VaultConfig, mount: impl Into<String>) -> Self {
+        Self {
+            client: VaultClient::new(config),
+            mount: mount.into(),
+        }
+    }
+
+    /// Reads the key stored at `path`.
+    pub async fn get_key(&self, path: &str) -> Result<EvmKeyInfo> {
+        let url = self
+            .client
+            .config
+            .url(&self.mount, &format!("data/{path}"))?;
+        let mut secret: Response<KvSecret> = self.client.send(self.client.client.get(url)).await?;
+        let encoded = Zeroizing::new(
+            secret
+                .data
+                .data
+                .remove(PRIVATE_KEY_FIELD)
+                .ok_or_else(|| anyhow!("secret {path} has no {PRIVATE_KEY_FIELD} field"))?,
+        );
+        let encoded = encoded.trim();
+        let key = Zeroizing::new(
+            hex::decode(encoded.strip_prefix("0x").unwrap_or(encoded))
+                .context("the key is not hex encoded")?,
+        );
+        Ok(EvmKeyInfo::new(key.to_vec()))
+    }
+
+    /// Stores `key` at `path`, creating a new version of the secret if it exists.
+    pub async fn put_key(&self, path: &str, key: &EvmKeyInfo) -> Result<()> {
+        let url = self
+            .client
+            .config
+            .url(&self.mount, &format!("data/{path}"))?;
+        let body = json!({
+            "data": { PRIVATE_KEY_FIELD: hex::encode(key.private_key()) }
+        });
+        let _: serde_json::Value = self
+            .client
+            .send(self.client.client.post(url).json(&body))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Signs with a secp256k1 key of a transit secrets engine.
+#[derive(Clone, Debug)]
+pub struct VaultTransitSigner {
+    client: VaultClient,
+    mount: String,
+    key_name: String,
+    address: Address,
+    chain_id: u64,
+}
+
+impl VaultTransitSigner {
+    /// The signer of the transit key `key_name`, whose address is `address`. The address is
+    /// checked against every signature, so that a misconfigured key cannot go unnoticed.
+    pub fn new(
+        config: VaultConfig,
+        mount: impl Into<String>,
+        key_name: impl Into<String>,
+        address: Address,
+        chain_id: u64,
+    ) -> Self {
+        Self {
+            client: VaultClient::new(config),
+            mount: mount.into(),
+            key_name: key_name.into(),
+            address,
+            chain_id,
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Signs the `digest`, the `v` of the signature being the recovery id plus 27.
+    pub async fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let url = self
+            .client
+            .config
+            .url(&self.mount, &format!("sign/{}", self.key_name))?;
+        let body = json!({
+            "input": base64::engine::general_purpose::STANDARD.encode(digest.as_bytes()),
+            "prehashed": true,
+            "marshaling_algorithm": "jws",
+        });
+        let signature: Response<TransitSignature> = self
+            .client
+            .send(self.client.client.post(url).json(&body))
+            .await?;
+
+        let (r, s) = parse_transit_signature(&signature.data.signature)?;
+        recover_signature(r, s, digest, self.address)
+    }
+}
+
+/// Parses the `vault:v<N>:<base64url r||s>` signatures of the transit engine.
+fn parse_transit_signature(signature: &str) -> Result<(U256, U256)> {
+    let encoded = signature
+        .strip_prefix("vault:v")
+        .and_then(|s| s.split_once(':'))
+        .map(|(_, encoded)| encoded)
+        .ok_or_else(|| anyhow!("unexpected transit signature format: {signature}"))?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded.trim_end_matches('='))
+        .context("transit signature is not base64url encoded")?;
+    if bytes.len() != 64 {
+        return Err(anyhow!(
+            "expected a 64 bytes transit signature, got {} bytes",
+            bytes.len()
+        ));
+    }
+    Ok((
+        U256::from_big_endian(&bytes[..32]),
+        U256::from_big_endian(&bytes[32..]),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_transit_signature;
+    use crate::manager::evm::{recover_signature, SECP256K1_ORDER};
+    use base64::Engine;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{H256, U256};
+
+    #[test]
+    fn test_recover_transit_signature() {
+        let wallet = LocalWallet::from_bytes(&[1u8; 32]).unwrap();
+        let digest = H256::repeat_byte(7);
+        let expected = wallet.sign_hash(digest).unwrap();
+
+        let mut bytes = [0u8; 64];
+        expected.r.to_big_endian(&mut bytes[..32]);
+        // vault may return the high-s form of the signature
+        let order = U256::from_str_radix(SECP256K1_ORDER, 16).unwrap();
+        (order - expected.s).to_big_endian(&mut bytes[32..]);
+        let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+
+        let (r, s) = parse_transit_signature(&format!("vault:v1:{encoded}")).unwrap();
+        let signature = recover_signature(r, s, digest, wallet.address()).unwrap();
+        assert_eq!(signature, expected);
+
+        let other = LocalWallet::from_bytes(&[2u8; 32]).unwrap();
+        assert!(recover_signature(r, s, digest, other.address()).is_err());
+    }
+
+    #[test]
+    fn test_parse_transit_signature() {
+        assert!(parse_transit_signature("vault:v1:abcd").is_err());
+        assert!(parse_transit_signature("not a signature").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Webhooks notifying external systems of the checkpoints committed or refused by the relayer,
+//! of the committed checkpoints found diverging from the child chain by an observer, and of the
+//! termination of the child subnet.
+//!
+//! Every notification is a JSON `POST`. When a secret is configured, the body is signed with
+//! HMAC-SHA256 and the hex encoded signature is sent in the [`SIGNATURE_HEADER`] header as
+//! `sha256=<signature>`, so that receivers can authenticate it.
+
+use crate::checkpoint::hooks::{
+    CheckpointDivergence, SubmissionRefused, SubmissionSuccess, SubnetTermination,
+};
+use crate::clock::{unix_secs, TokioClock};
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use url::Url;
+
+pub const SIGNATURE_HEADER: &str = "X-IPC-Signature";
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: Url,
+    /// The secret used to sign the payloads, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// The payloads posted to the webhooks, tagged with their `event` name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    CheckpointCommitted(CheckpointCommitted),
+    CheckpointDivergence(DivergenceDetected),
+    CheckpointRefused(CheckpointRefused),
+    SubnetTerminated(SubnetTerminated),
+}
+
+impl WebhookEvent {
+    pub fn height(&self) -> i64 {
+        match self {
+            WebhookEvent::CheckpointCommitted(e) => e.height,
+            WebhookEvent::CheckpointDivergence(e) => e.height,
+            WebhookEvent::CheckpointRefused(e) => e.height,
+            WebhookEvent::SubnetTerminated(e) => e.height,
+        }
+    }
+}
+
+/// The payload posted when a checkpoint is committed in the parent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointCommitted {
+    pub subnet: String,
+    pub height: i64,
+    pub parent_epoch: i64,
+    pub tx_hash: String,
+    pub message_count: usize,
+    pub gas_used: Option<u64>,
+    /// Unix timestamp in seconds of the notification.
+    pub timestamp: u64,
+}
+
+impl From<&SubmissionSuccess> for CheckpointCommitted {
+    fn from(s: &SubmissionSuccess) -> Self {
+        Self {
+            subnet: s.checkpoint.subnet_id.to_string(),
+            height: s.checkpoint.block_height,
+            parent_epoch: s.receipt.epoch,
+            tx_hash: s.receipt.tx_hash.clone(),
+            message_count: s.checkpoint.msgs.len(),
+            gas_used: s.receipt.gas_used,
+            timestamp: unix_secs(&TokioClock),
+        }
+    }
+}
+
+/// The payload posted when the relayer refuses to relay a checkpoint violating its submission
+/// policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointRefused {
+    pub subnet: String,
+    pub height: i64,
+    /// The messages of the checkpoint violating the policy, and why.
+    pub violations: Vec<String>,
+    /// Unix timestamp in seconds of the notification.
+    pub timestamp: u64,
+}
+
+impl From<&SubmissionRefused> for CheckpointRefused {
+    fn from(s: &SubmissionRefused) -> Self {
+        Self {
+            subnet: s.checkpoint.subnet_id.to_string(),
+            height: s.checkpoint.block_height,
+            violations: s.violations.iter().map(|v| v.to_string()).collect(),
+            timestamp: unix_secs(&TokioClock),
+        }
+    }
+}
+
+/// The payload posted when a committed checkpoint diverges from the child chain, the hashes
+/// are hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergenceDetected {
+    pub subnet: String,
+    pub height: i64,
+    /// The kinds of divergence, e.g. `block_hash` or `messages_root`.
+    pub kinds: Vec<String>,
+    pub committed_block_hash: String,
+    pub chain_block_hash: String,
+    pub committed_messages_root: String,
+    pub expected_messages_root: String,
+    /// Unix timestamp in seconds of the notification.
+    pub timestamp: u64,
+}
+
+impl From<&CheckpointDivergence> for DivergenceDetected {
+    fn from(d: &CheckpointDivergence) -> Self {
+        Self {
+            subnet: d.committed.subnet_id.to_string(),
+            height: d.committed.block_height,
+            kinds: d.kinds.iter().map(|k| k.to_string()).collect(),
+            committed_block_hash: hex::encode(&d.committed.block_hash),
+            chain_block_hash: hex::encode(&d.chain_block_hash),
+            committed_messages_root: hex::encode(d.committed_messages_root),
+            expected_messages_root: hex::encode(d.expected_messages_root),
+            timestamp: unix_secs(&TokioClock),
+        }
+    }
+}
+
+/// The payload posted when the child subnet is found killed or de-registered in the parent,
+/// after which the relayer stops.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetTerminated {
+    pub subnet: String,
+    /// The height of the last checkpoint committed in the parent.
+    pub height: i64,
+    /// Either `killed` or `deregistered`.
+    pub status: String,
+    /// Unix timestamp in seconds of the notification.
+    pub timestamp: u64,
+}
+
+impl From<&SubnetTermination> for SubnetTerminated {
+    fn from(t: &SubnetTermination) -> Self {
+        Self {
+            subnet: t.subnet.to_string(),
+            height: t.last_committed_height,
+            status: t.status.to_string(),
+            timestamp: unix_secs(&TokioClock),
+        }
+    }
+}
+
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    webhooks: Vec<WebhookConfig>,
+    max_retries: u32,
+}
+
+impl WebhookDispatcher {
+    pub fn new(webhooks: Vec<WebhookConfig>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            client,
+            webhooks,
+            max_retries: DEFAULT_MAX_RETRIES,
+        })
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Posts the payload to all the webhooks, retrying the failed deliveries with backoff.
+    /// Fails if any of the webhooks could not be delivered to.
+    pub async fn dispatch(&self, payload: &WebhookEvent) -> Result<()> {
+        let body = serde_json::to_vec(payload)?;
+
+        let mut failed = vec![];
+        for webhook in &self.webhooks {
+            if let Err(e) = self.deliver(webhook, &body).await {
+                log::error!("cannot deliver webhook to {}: {e}", webhook.url);
+                failed.push(webhook.url.to_string());
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("webhooks not delivered to: {}", failed.join(", ")))
+        }
+    }
+
+    async fn deliver(&self, webhook: &WebhookConfig, body: &[u8]) -> Result<()> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self
+                .client
+                .post(webhook.url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+            if let Some(secret) = &webhook.secret {
+                request =
+                    request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, body)));
+            }
+
+            let error = match request.send().await {
+                Ok(r) if r.status().is_success() => return Ok(()),
+                // the receiver rejected the payload, retrying won't help
+                Ok(r) if r.status().is_client_error() => {
+                    return Err(anyhow!("rejected with status {}", r.status()))
+                }
+                Ok(r) => anyhow!("failed with status {}", r.status()),
+                Err(e) => anyhow!(e),
+            };
+
+            attempt += 1;
+            if attempt > self.max_retries {
+                return Err(error);
+            }
+            log::warn!(
+                "webhook delivery to {} failed: {error}, retrying in {backoff:?}",
+                webhook.url
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+/// Returns the hex encoded HMAC-SHA256 of `body` with `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign, CheckpointCommitted, CheckpointRefused, SubnetTerminated, WebhookEvent};
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_event_tag() {
+        let event = WebhookEvent::CheckpointCommitted(CheckpointCommitted {
+            subnet: "/r314159".to_string(),
+            height: 10,
+            parent_epoch: 100,
+            tx_hash: "0x01".to_string(),
+            message_count: 0,
+            gas_used: None,
+            timestamp: 0,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "checkpoint_committed");
+        assert_eq!(json["height"], 10);
+    }
+
+    #[test]
+    fn test_refused_event_tag() {
+        let event = WebhookEvent::CheckpointRefused(CheckpointRefused {
+            subnet: "/r314159".to_string(),
+            height: 20,
+            violations: vec!["message 0 to denied recipient f010".to_string()],
+            timestamp: 0,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "checkpoint_refused");
+        assert_eq!(event.height(), 20);
+    }
+
+    #[test]
+    fn test_terminated_event_tag() {
+        let event = WebhookEvent::SubnetTerminated(SubnetTerminated {
+            subnet: "/r314159/t410f".to_string(),
+            height: 30,
+            status: "killed".to_string(),
+            timestamp: 0,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "subnet_terminated");
+        assert_eq!(json["status"], "killed");
+        assert_eq!(event.height(), 30);
+    }
+}
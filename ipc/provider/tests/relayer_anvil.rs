@@ -0,0 +1,298 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! End-to-end tests of the bottom-up relayer against two anvil nodes: a parent running
+//! `SubnetActorCheckpointMock` and a child running `GatewayCheckpointMock`, both from
+//! `contracts/test/mocks`. The quorum of the checkpoints is reached by the tests, the real
+//! [`BottomUpCheckpointManager`] relays them from the child to the parent.
+//!
+//! They need `anvil` in the `PATH` and the contracts built with `make build` in `contracts`, or
+//! their build artifacts at `IPC_CONTRACTS_OUT`, so they are ignored by default:
+//!
+//! ```text
+//! cargo test -p ipc-provider --test relayer_anvil -- --ignored
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use ethers::abi::{Abi, Tokenizable, Tokenize};
+use ethers::contract::{Contract, ContractFactory};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Bytes, H160, U256};
+use ethers::utils::{Anvil, AnvilInstance};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::gateway_getter_facet;
+use ipc_api::checkpoint::BottomUpCheckpoint;
+use ipc_api::ethers_address_to_fil_address;
+use ipc_api::subnet_id::SubnetID;
+use ipc_provider::checkpoint::BottomUpCheckpointManager;
+use ipc_provider::config::subnet::{EVMSubnet, SubnetConfig};
+use ipc_provider::config::Subnet;
+use ipc_provider::manager::{BottomUpCheckpointRelayer, EthSubnetManager};
+use ipc_wallet::{EthKeyAddress, EvmKeyInfo, EvmKeyStore, PersistentKeyStore};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+const PARENT_CHAIN_ID: u64 = 31337;
+const CHILD_CHAIN_ID: u64 = 31338;
+const CHECKPOINT_PERIOD: ChainEpoch = 10;
+
+type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// The build artifacts of the contracts.
+fn contracts_out() -> PathBuf {
+    match std::env::var("IPC_CONTRACTS_OUT") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../contracts/out"),
+    }
+}
+
+/// Deploys the contract `name` from its build artifact.
+async fn deploy<T: Tokenize>(
+    client: Arc<Client>,
+    name: &str,
+    constructor: T,
+) -> Result<Contract<Client>> {
+    let path = contracts_out()
+        .join(format!("{name}.sol"))
+        .join(format!("{name}.json"));
+    let artifact: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).with_context(|| {
+            format!(
+                "failed to read {}, are the contracts built?",
+                path.display()
+            )
+        })?)?;
+    let abi: Abi = serde_json::from_value(artifact["abi"].clone())?;
+    let bytecode = artifact["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| anyhow!("artifact of {name} has no bytecode"))?;
+    let bytecode = Bytes::from(hex::decode(bytecode.trim_start_matches("0x"))?);
+
+    let contract = ContractFactory::new(abi, bytecode, client)
+        .deploy(constructor)?
+        .send()
+        .await
+        .with_context(|| format!("failed to deploy {name}"))?;
+    Ok(contract)
+}
+
+fn client(anvil: &AnvilInstance) -> Result<Arc<Client>> {
+    let provider = Provider::<Http>::try_from(anvil.endpoint())?;
+    let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+    Ok(Arc::new(SignerMiddleware::new(provider, wallet)))
+}
+
+fn subnet(id: SubnetID, anvil: &AnvilInstance, gateway: H160) -> Result<Subnet> {
+    Ok(Subnet {
+        id,
+        config: SubnetConfig::Fevm(EVMSubnet {
+            provider_http: anvil.endpoint().parse()?,
+            provider_timeout: None,
+            auth_token: None,
+            provider_ws: None,
+            proxy: None,
+            block_time: None,
+            provider_max_concurrency: None,
+            registry_addr: ethers_address_to_fil_address(&H160::zero())?,
+            gateway_addr: ethers_address_to_fil_address(&gateway)?,
+        }),
+    })
+}
+
+/// A parent and a child anvil node with the mocks deployed.
+struct Harness {
+    // the nodes are killed when dropped
+    _parent_anvil: AnvilInstance,
+    child_anvil: AnvilInstance,
+    parent: Subnet,
+    child: Subnet,
+    gateway: Contract<Client>,
+    subnet_actor: Contract<Client>,
+    keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+    submitter: Address,
+}
+
+impl Harness {
+    async fn start() -> Result<Self> {
+        let parent_anvil = Anvil::new().chain_id(PARENT_CHAIN_ID).spawn();
+        let child_anvil = Anvil::new().chain_id(CHILD_CHAIN_ID).spawn();
+
+        let subnet_actor = deploy(
+            client(&parent_anvil)?,
+            "SubnetActorCheckpointMock",
+            U256::from(CHECKPOINT_PERIOD),
+        )
+        .await?;
+        let gateway = deploy(
+            client(&child_anvil)?,
+            "GatewayCheckpointMock",
+            (U256::from(CHECKPOINT_PERIOD), 66u64),
+        )
+        .await?;
+
+        let root = SubnetID::new_root(PARENT_CHAIN_ID);
+        let child_id = SubnetID::new_from_parent(
+            &root,
+            ethers_address_to_fil_address(&subnet_actor.address())?,
+        );
+        let parent = subnet(root, &parent_anvil, H160::zero())?;
+        let child = subnet(child_id, &child_anvil, gateway.address())?;
+
+        // the submitter is the second dev account, funded in the parent
+        let mut keystore = PersistentKeyStore::ephemeral();
+        let key = parent_anvil.keys()[1].to_bytes().to_vec();
+        let submitter = Address::try_from(keystore.put(EvmKeyInfo::new(key))?)?;
+
+        Ok(Self {
+            _parent_anvil: parent_anvil,
+            child_anvil,
+            parent,
+            child,
+            gateway,
+            subnet_actor,
+            keystore: Arc::new(RwLock::new(keystore)),
+            submitter,
+        })
+    }
+
+    async fn relayer(&self) -> Result<BottomUpCheckpointManager<EthSubnetManager>> {
+        BottomUpCheckpointManager::new_evm_manager(
+            self.parent.clone(),
+            self.child.clone(),
+            self.keystore.clone(),
+            None,
+        )
+        .await
+    }
+
+    /// The handler of the parent, to check what the relayer committed.
+    fn parent_handler(&self) -> Result<EthSubnetManager> {
+        EthSubnetManager::from_subnet_with_wallet_store(&self.parent, None)
+    }
+
+    fn checkpoint(&self, height: ChainEpoch) -> BottomUpCheckpoint {
+        BottomUpCheckpoint {
+            subnet_id: self.child.id.clone(),
+            block_height: height,
+            block_hash: vec![height as u8; 32],
+            next_configuration_number: 0,
+            msgs: vec![],
+        }
+    }
+
+    /// Stores the checkpoint at `height` in the child gateway, signed by a single validator,
+    /// and emits its `QuorumReached` event.
+    async fn reach_quorum(&self, height: ChainEpoch) -> Result<()> {
+        let checkpoint =
+            gateway_getter_facet::BottomUpCheckpoint::try_from(self.checkpoint(height))?;
+        let encoded = Bytes::from(ethers::abi::encode(&[checkpoint.into_token()]));
+        let validator = self.child_anvil.addresses()[2];
+
+        self.gateway
+            .method::<_, ()>(
+                "reachQuorum",
+                (
+                    encoded,
+                    U256::from(100),
+                    vec![validator],
+                    vec![Bytes::from(vec![1u8; 65])],
+                ),
+            )?
+            .send()
+            .await?
+            .await?
+            .ok_or_else(|| anyhow!("no receipt for reachQuorum"))?;
+        Ok(())
+    }
+
+    /// Mines `blocks` empty blocks in the child.
+    async fn mine_child(&self, blocks: u64) -> Result<()> {
+        self.gateway
+            .client()
+            .provider()
+            .request::<_, ()>("anvil_mine", [U256::from(blocks)])
+            .await?;
+        Ok(())
+    }
+
+    /// The number of submissions accepted by the parent, re-submissions included.
+    async fn submissions(&self) -> Result<u64> {
+        let submissions: U256 = self.subnet_actor.method("submissions", ())?.call().await?;
+        Ok(submissions.as_u64())
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and the built contracts"]
+async fn test_relays_checkpoint_with_quorum() {
+    let harness = Harness::start().await.unwrap();
+    let relayer = harness.relayer().await.unwrap();
+    assert_eq!(relayer.checkpoint_period(), CHECKPOINT_PERIOD);
+
+    harness.reach_quorum(CHECKPOINT_PERIOD).await.unwrap();
+    harness.mine_child(CHECKPOINT_PERIOD as u64).await.unwrap();
+    relayer.submit_checkpoint(&harness.submitter).await.unwrap();
+
+    let parent = harness.parent_handler().unwrap();
+    assert_eq!(
+        parent
+            .last_bottom_up_checkpoint_height(&harness.child.id)
+            .await
+            .unwrap(),
+        CHECKPOINT_PERIOD
+    );
+    assert_eq!(
+        parent
+            .committed_checkpoint_at(&harness.child.id, CHECKPOINT_PERIOD)
+            .await
+            .unwrap(),
+        Some(harness.checkpoint(CHECKPOINT_PERIOD))
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and the built contracts"]
+async fn test_waits_for_finalized_checkpoints() {
+    let harness = Harness::start().await.unwrap();
+    let relayer = harness.relayer().await.unwrap().with_finalization_blocks(5);
+
+    harness.reach_quorum(CHECKPOINT_PERIOD).await.unwrap();
+    harness.mine_child(CHECKPOINT_PERIOD as u64).await.unwrap();
+    relayer.submit_checkpoint(&harness.submitter).await.unwrap();
+    assert_eq!(harness.submissions().await.unwrap(), 0);
+
+    harness.mine_child(5).await.unwrap();
+    relayer.submit_checkpoint(&harness.submitter).await.unwrap();
+    assert_eq!(harness.submissions().await.unwrap(), 1);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and the built contracts"]
+async fn test_relays_checkpoints_in_order() {
+    let harness = Harness::start().await.unwrap();
+    let relayer = harness.relayer().await.unwrap();
+
+    harness.reach_quorum(CHECKPOINT_PERIOD).await.unwrap();
+    harness.reach_quorum(2 * CHECKPOINT_PERIOD).await.unwrap();
+    harness
+        .mine_child(2 * CHECKPOINT_PERIOD as u64)
+        .await
+        .unwrap();
+    relayer.submit_checkpoint(&harness.submitter).await.unwrap();
+
+    let parent = harness.parent_handler().unwrap();
+    assert_eq!(
+        parent
+            .last_bottom_up_checkpoint_height(&harness.child.id)
+            .await
+            .unwrap(),
+        2 * CHECKPOINT_PERIOD
+    );
+    assert_eq!(harness.submissions().await.unwrap(), 2);
+
+    // the last committed checkpoint is re-submitted, which the parent accepts
+    relayer.submit_checkpoint(&harness.submitter).await.unwrap();
+    assert_eq!(harness.submissions().await.unwrap(), 3);
+}
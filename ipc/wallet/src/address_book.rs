@@ -0,0 +1,191 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Persistent address book of the frequently used recipients, so that they can be referred to
+//! by name, e.g. `treasury`, instead of their raw address.
+//!
+//! An entry is either global or scoped to a subnet, in which case it takes precedence over the
+//! global entry of the same name when resolving in that subnet.
+
+use anyhow::{anyhow, Result};
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const DEFAULT_ADDRESS_BOOK_NAME: &str = "address_book.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBookEntry {
+    pub name: String,
+    /// The address of the recipient, either a filecoin or a hex encoded ethereum address.
+    pub address: String,
+    /// The subnet the entry is scoped to, `None` for the entries of all the subnets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AddressBook {
+    entries: Vec<AddressBookEntry>,
+    /// The file the entries are written to, `None` for in memory address books.
+    file_path: Option<PathBuf>,
+}
+
+impl AddressBook {
+    /// Opens the address book at `path`, starting with an empty one if it does not exist yet.
+    pub fn new(path: PathBuf) -> Result<Self> {
+        let entries = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| anyhow!("cannot parse address book at {:?}: {e}", path))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(anyhow!("cannot open address book at {:?}: {e}", path)),
+        };
+
+        Ok(Self {
+            entries,
+            file_path: Some(path),
+        })
+    }
+
+    /// An address book that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[AddressBookEntry] {
+        &self.entries
+    }
+
+    /// Adds an entry, replacing the entry of the same name and scope if any.
+    pub fn insert(&mut self, entry: AddressBookEntry) -> Result<()> {
+        validate_name(&entry.name)?;
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.name == entry.name && e.subnet == entry.subnet)
+        {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        self.flush()
+    }
+
+    /// Removes the entry of `name` in the scope of `subnet`, returning whether it existed.
+    pub fn remove(&mut self, name: &str, subnet: Option<&str>) -> Result<bool> {
+        let len = self.entries.len();
+        self.entries
+            .retain(|e| !(e.name == name && e.subnet.as_deref() == subnet));
+        if self.entries.len() == len {
+            return Ok(false);
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// The entry of `name` to be used in `subnet`, preferring the entry scoped to the subnet
+    /// over the global one.
+    pub fn lookup(&self, name: &str, subnet: Option<&str>) -> Option<&AddressBookEntry> {
+        let scoped = subnet.and_then(|subnet| {
+            self.entries
+                .iter()
+                .find(|e| e.name == name && e.subnet.as_deref() == Some(subnet))
+        });
+        scoped.or_else(|| {
+            self.entries
+                .iter()
+                .find(|e| e.name == name && e.subnet.is_none())
+        })
+    }
+
+    /// Writes the entries to a temporary file and moves it in place.
+    fn flush(&self) -> Result<()> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let tmp = file_path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.entries)?)?;
+        fs::rename(&tmp, file_path)?;
+        Ok(())
+    }
+}
+
+/// The names must not be mistaken for an address when resolving a recipient.
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(anyhow!("invalid address book name: {name:?}"));
+    }
+    if name.starts_with("0x") || Address::from_str(name).is_ok() {
+        return Err(anyhow!("address book name {name} looks like an address"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddressBook, AddressBookEntry};
+
+    fn entry(name: &str, address: &str, subnet: Option<&str>) -> AddressBookEntry {
+        AddressBookEntry {
+            name: name.to_string(),
+            address: address.to_string(),
+            subnet: subnet.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_lookup_prefers_subnet_scope() {
+        let mut book = AddressBook::in_memory();
+        book.insert(entry("treasury", "f01", None)).unwrap();
+        book.insert(entry("treasury", "f02", Some("/r314159/f03")))
+            .unwrap();
+
+        let lookup = |subnet| book.lookup("treasury", subnet).unwrap().address.clone();
+        assert_eq!(lookup(Some("/r314159/f03")), "f02");
+        assert_eq!(lookup(Some("/r314159/f04")), "f01");
+        assert_eq!(lookup(None), "f01");
+        assert!(book.lookup("ops", None).is_none());
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut book = AddressBook::in_memory();
+        book.insert(entry("treasury", "f01", None)).unwrap();
+        book.insert(entry("treasury", "f02", None)).unwrap();
+        assert_eq!(book.entries().len(), 1);
+        assert_eq!(book.lookup("treasury", None).unwrap().address, "f02");
+
+        assert!(book.insert(entry("f01", "f02", None)).is_err());
+        assert!(book.insert(entry("0xtreasury", "f02", None)).is_err());
+        assert!(book.insert(entry("", "f02", None)).is_err());
+
+        assert!(!book.remove("treasury", Some("/r314159")).unwrap());
+        assert!(book.remove("treasury", None).unwrap());
+        assert!(book.entries().is_empty());
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("address_book.json");
+
+        let mut book = AddressBook::new(path.clone()).unwrap();
+        book.insert(entry("treasury", "f01", Some("/r314159")))
+            .unwrap();
+        drop(book);
+
+        let book = AddressBook::new(path).unwrap();
+        assert_eq!(
+            book.entries(),
+            &[entry("treasury", "f01", Some("/r314159"))]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! The lock of a key store: while locked, its keys are only held encrypted with a passphrase,
+//! and they are decrypted in memory when unlocked, for a limited time.
+
+use crate::evm::memory::InMemoryKeyStore;
+use crate::evm::KeyInfo;
+use crate::fvm::{EncryptedKeyStore, SaltByteArray};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// The error of the operations needing the keys of a locked key store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the key store is locked")]
+pub struct KeyStoreLocked;
+
+/// The keys of a key store encrypted with its passphrase, and the key decrypting them while
+/// unlocked.
+pub(crate) struct KeyLock<T> {
+    salt: SaltByteArray,
+    sealed: HashMap<T, Vec<u8>>,
+    default: Option<T>,
+    unlocked: Option<Unlocked>,
+}
+
+struct Unlocked {
+    encryption_key: Zeroizing<Vec<u8>>,
+    until: Instant,
+}
+
+impl<T: Clone + Eq + Hash> KeyLock<T> {
+    /// Seals the keys of `memory` with `passphrase`, locked.
+    pub fn new(memory: &InMemoryKeyStore<T>, passphrase: &str) -> Result<Self> {
+        let (salt, encryption_key) = EncryptedKeyStore::derive_key(passphrase, None)?;
+        let mut lock = Self {
+            salt,
+            sealed: HashMap::new(),
+            default: None,
+            unlocked: Some(Unlocked {
+                encryption_key: Zeroizing::new(encryption_key),
+                until: Instant::now(),
+            }),
+        };
+        lock.seal(memory)?;
+        lock.unlocked = None;
+        Ok(lock)
+    }
+
+    /// Whether the keys are locked, explicitly or once their unlock expired.
+    pub fn is_locked(&self) -> bool {
+        match &self.unlocked {
+            Some(unlocked) => Instant::now() >= unlocked.until,
+            None => true,
+        }
+    }
+
+    /// Whether the keys were unlocked until an instant now past, and are still decrypted.
+    pub fn is_expired(&self) -> bool {
+        matches!(&self.unlocked, Some(unlocked) if Instant::now() >= unlocked.until)
+    }
+
+    pub fn check_unlocked(&self) -> Result<()> {
+        if self.is_locked() {
+            return Err(KeyStoreLocked.into());
+        }
+        Ok(())
+    }
+
+    /// The instant the keys lock again, if unlocked.
+    pub fn unlocked_until(&self) -> Option<Instant> {
+        self.unlocked
+            .as_ref()
+            .map(|u| u.until)
+            .filter(|_| !self.is_locked())
+    }
+
+    /// Decrypts the keys into `memory` until `duration` elapses, failing with the wrong
+    /// passphrase.
+    pub fn unlock(
+        &mut self,
+        memory: &mut InMemoryKeyStore<T>,
+        passphrase: &str,
+        duration: Duration,
+    ) -> Result<()> {
+        let (_, encryption_key) = EncryptedKeyStore::derive_key(passphrase, Some(self.salt))?;
+        let encryption_key = Zeroizing::new(encryption_key);
+
+        let mut data = HashMap::with_capacity(self.sealed.len());
+        for (addr, sealed) in &self.sealed {
+            let private_key = EncryptedKeyStore::decrypt(&encryption_key, sealed)
+                .map_err(|_| anyhow!("cannot unlock the key store: invalid passphrase"))?;
+            data.insert(addr.clone(), KeyInfo::new(private_key));
+        }
+        memory.data = data;
+        memory.default = self.default.clone();
+
+        self.unlocked = Some(Unlocked {
+            encryption_key,
+            until: Instant::now() + duration,
+        });
+        Ok(())
+    }
+
+    /// Locks the keys, dropping the decrypted keys of `memory`, zeroized on drop.
+    pub fn lock(&mut self, memory: &mut InMemoryKeyStore<T>) {
+        self.unlocked = None;
+        memory.data.clear();
+        memory.default = None;
+    }
+
+    /// Seals the keys of `memory` again after a change, which requires the keys unlocked.
+    pub fn seal(&mut self, memory: &InMemoryKeyStore<T>) -> Result<()> {
+        let unlocked = self.unlocked.as_ref().ok_or(KeyStoreLocked)?;
+        let mut sealed = HashMap::with_capacity(memory.data.len());
+        for (addr, info) in &memory.data {
+            let encrypted =
+                EncryptedKeyStore::encrypt(&unlocked.encryption_key, info.private_key())?;
+            sealed.insert(addr.clone(), encrypted);
+        }
+        self.sealed = sealed;
+        self.default = memory.default.clone();
+        Ok(())
+    }
+
+    /// The addresses of the keys, known while locked.
+    pub fn list(&self) -> Vec<T> {
+        self.sealed.keys().cloned().collect()
+    }
+
+    /// The default address, known while locked.
+    pub fn default_key(&self) -> Option<T> {
+        self.default.clone()
+    }
+}
@@ -3,18 +3,59 @@
 
 //! Memory key store
 
+use crate::blake2b_256;
 use crate::evm::{KeyInfo, KeyStore};
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
 use std::hash::Hash;
 
+/// The domain separating the keys derived from seeds from any other use of the hash.
+const SEED_DOMAIN: &[u8] = b"ipc-wallet/evm-seed";
+
+/// A key store that only lives in memory, never writing its keys to disk, e.g. for the tests
+/// and the ephemeral environments.
 #[derive(Default)]
-pub struct MemoryKeyStore<T> {
+pub struct InMemoryKeyStore<T> {
     pub(crate) data: HashMap<T, KeyInfo>,
     pub(crate) default: Option<T>,
 }
 
-impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> KeyStore for MemoryKeyStore<T> {
+impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> InMemoryKeyStore<T> {
+    pub fn new() -> Self {
+        Self {
+            data: Default::default(),
+            default: None,
+        }
+    }
+
+    /// A key store holding the keys derived from `seeds`, the same for the same seeds, with
+    /// the key of the first seed as the default.
+    pub fn from_seeds(seeds: impl IntoIterator<Item = u64>) -> Result<Self> {
+        let mut store = Self::new();
+        for seed in seeds {
+            let addr = store.put(seeded_key_info(seed))?;
+            if store.default.is_none() {
+                store.set_default(&addr)?;
+            }
+        }
+        Ok(store)
+    }
+}
+
+/// The key derived from `seed`, a valid secp256k1 secret key. Only meant for tests: anyone
+/// knowing the seed knows the key.
+pub fn seeded_key_info(seed: u64) -> KeyInfo {
+    let mut key = blake2b_256(&[SEED_DOMAIN, &seed.to_be_bytes()].concat());
+    // out of the range of the secret keys with a negligible probability, hash again if so
+    while libsecp256k1::SecretKey::parse(&key).is_err() {
+        key = blake2b_256(&key);
+    }
+    KeyInfo::new(key.to_vec())
+}
+
+impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> KeyStore
+    for InMemoryKeyStore<T>
+{
     type Key = T;
 
     fn get(&self, addr: &Self::Key) -> Result<Option<KeyInfo>> {
@@ -70,3 +111,38 @@ impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> KeyStore for
         Ok(self.default.clone())
     }
 }
+
+#[cfg(all(test, feature = "with-ethers"))]
+mod tests {
+    use super::{seeded_key_info, InMemoryKeyStore};
+    use crate::{EthKeyAddress, EvmKeyStore, PersistentKeyStore};
+
+    #[test]
+    fn test_seeded_keys_are_deterministic() {
+        let mut a = InMemoryKeyStore::<EthKeyAddress>::from_seeds(0..3).unwrap();
+        let mut b = InMemoryKeyStore::<EthKeyAddress>::from_seeds(0..3).unwrap();
+
+        let mut keys = a.list().unwrap();
+        keys.sort_by_key(|k| k.to_string());
+        let mut other = b.list().unwrap();
+        other.sort_by_key(|k| k.to_string());
+        // the three keys and the default
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys, other);
+
+        let first = EthKeyAddress::try_from(seeded_key_info(0)).unwrap();
+        assert_eq!(a.get_default().unwrap(), Some(first.clone()));
+        assert_eq!(b.get_default().unwrap(), Some(first));
+        assert_ne!(seeded_key_info(1), seeded_key_info(2));
+    }
+
+    #[test]
+    fn test_into_persistent_keystore() {
+        let memory = InMemoryKeyStore::<EthKeyAddress>::from_seeds([7]).unwrap();
+        let mut ks = PersistentKeyStore::from(memory);
+
+        let addr = EthKeyAddress::try_from(seeded_key_info(7)).unwrap();
+        assert_eq!(ks.get_default().unwrap(), Some(addr.clone()));
+        assert_eq!(ks.get(&addr).unwrap(), Some(seeded_key_info(7)));
+    }
+}
@@ -3,13 +3,17 @@
 
 //! Ethereum wallet key store.
 
+mod lock;
 mod memory;
 mod persistent;
 
+use crate::secret::REDACTED;
 use anyhow::Result;
-use std::{hash::Hash, str::FromStr};
+use std::{fmt, hash::Hash, str::FromStr};
 use zeroize::Zeroize;
 
+pub use crate::evm::lock::KeyStoreLocked;
+pub use crate::evm::memory::{seeded_key_info, InMemoryKeyStore};
 pub use crate::evm::persistent::{PersistentKeyInfo, PersistentKeyStore};
 
 pub const DEFAULT_KEYSTORE_NAME: &str = "evm_keystore.json";
@@ -33,8 +37,8 @@ pub trait KeyStore {
     fn get_default(&mut self) -> Result<Option<Self::Key>>;
 }
 
-/// The struct that contains evm private key info
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// The struct that contains evm private key info, zeroized on drop and redacted in `Debug`.
+#[derive(Clone, PartialEq, Eq)]
 pub struct KeyInfo {
     private_key: Vec<u8>,
 }
@@ -51,6 +55,14 @@ impl KeyInfo {
     }
 }
 
+impl fmt::Debug for KeyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyInfo")
+            .field("private_key", &REDACTED)
+            .finish()
+    }
+}
+
 impl Drop for KeyInfo {
     fn drop(&mut self) {
         self.private_key.zeroize();
@@ -3,7 +3,8 @@
 
 //! Persistent file key store
 
-use crate::evm::memory::MemoryKeyStore;
+use crate::evm::lock::KeyLock;
+use crate::evm::memory::InMemoryKeyStore;
 use crate::evm::{KeyInfo, KeyStore};
 use anyhow::anyhow;
 use anyhow::Result;
@@ -14,12 +15,16 @@ use std::fs::File;
 use std::hash::Hash;
 use std::io::{BufReader, BufWriter, ErrorKind};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use zeroize::Zeroize;
 
 #[derive(Default)]
 pub struct PersistentKeyStore<T> {
-    memory: MemoryKeyStore<T>,
-    file_path: PathBuf,
+    memory: InMemoryKeyStore<T>,
+    /// The file the keys are written to, `None` for ephemeral key stores.
+    file_path: Option<PathBuf>,
+    /// The lock of the keys, for the key stores with a passphrase.
+    lock: Option<KeyLock<T>>,
 }
 
 /// The persistent key information written to disk
@@ -57,36 +62,58 @@ impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> KeyStore
     type Key = T;
 
     fn get(&self, addr: &Self::Key) -> Result<Option<KeyInfo>> {
+        self.check_unlocked()?;
         self.memory.get(addr)
     }
 
     fn list(&self) -> Result<Vec<Self::Key>> {
-        self.memory.list()
+        match &self.lock {
+            Some(lock) if lock.is_locked() => Ok(lock.list()),
+            _ => self.memory.list(),
+        }
     }
 
     fn put(&mut self, info: KeyInfo) -> Result<Self::Key> {
+        self.check_unlocked()?;
         let addr = self.memory.put(info)?;
         self.flush_no_encryption()?;
         Ok(addr)
     }
 
     fn remove(&mut self, addr: &Self::Key) -> Result<()> {
+        self.check_unlocked()?;
         self.memory.remove(addr)?;
         self.flush_no_encryption()
     }
 
     fn set_default(&mut self, addr: &Self::Key) -> Result<()> {
+        self.check_unlocked()?;
         self.memory.set_default(addr)?;
         self.flush_no_encryption()
     }
 
     fn get_default(&mut self) -> Result<Option<Self::Key>> {
+        if let Some(lock) = self.lock.as_ref().filter(|l| l.is_locked()) {
+            return Ok(lock.default_key());
+        }
         let default = self.memory.get_default()?;
         self.flush_no_encryption()?;
         Ok(default)
     }
 }
 
+/// An ephemeral key store holding the keys of `memory`, for the code taking a
+/// [`PersistentKeyStore`].
+impl<T> From<InMemoryKeyStore<T>> for PersistentKeyStore<T> {
+    fn from(memory: InMemoryKeyStore<T>) -> Self {
+        Self {
+            memory,
+            file_path: None,
+            lock: None,
+        }
+    }
+}
+
 impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> PersistentKeyStore<T> {
     pub fn new(path: PathBuf) -> Result<Self> {
         if let Some(p) = path.parent() {
@@ -101,11 +128,12 @@ impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> PersistentKey
                 return if e.kind() == ErrorKind::NotFound {
                     log::info!("key store does not exist, initialized to empty key store");
                     Ok(Self {
-                        memory: MemoryKeyStore {
+                        memory: InMemoryKeyStore {
                             data: Default::default(),
                             default: None,
                         },
-                        file_path: path,
+                        file_path: Some(path),
+                        lock: None,
                     })
                 } else {
                     Err(anyhow!("cannot create key store: {e:}"))
@@ -146,24 +174,106 @@ impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> PersistentKey
         };
 
         Ok(Self {
-            memory: MemoryKeyStore {
+            memory: InMemoryKeyStore {
                 data: key_infos,
                 default,
             },
-            file_path: path,
+            file_path: Some(path),
+            lock: None,
         })
     }
 
-    /// Write all keys to file without any encryption.
-    fn flush_no_encryption(&self) -> Result<()> {
-        let dir = self
-            .file_path
+    /// A key store that is never written to disk, for keys provided at startup, e.g. from
+    /// secrets mounted in a container.
+    pub fn ephemeral() -> Self {
+        Self {
+            memory: InMemoryKeyStore {
+                data: Default::default(),
+                default: None,
+            },
+            file_path: None,
+            lock: None,
+        }
+    }
+
+    /// Locks the key store with `passphrase`: its keys are then only held encrypted until
+    /// [`PersistentKeyStore::unlock`]ed, e.g. between the operations of a long-running process.
+    /// The file of the key store is left as it is.
+    pub fn set_passphrase(&mut self, passphrase: &str) -> Result<()> {
+        self.check_unlocked()?;
+        let mut lock = KeyLock::new(&self.memory, passphrase)?;
+        lock.lock(&mut self.memory);
+        self.lock = Some(lock);
+        Ok(())
+    }
+
+    /// Decrypts the keys for `duration`, after which the key store locks again on its own.
+    pub fn unlock(&mut self, passphrase: &str, duration: Duration) -> Result<()> {
+        let lock = self
+            .lock
+            .as_mut()
+            .ok_or_else(|| anyhow!("the key store has no passphrase"))?;
+        lock.unlock(&mut self.memory, passphrase, duration)
+    }
+
+    /// Locks the keys, zeroizing their decrypted copies.
+    pub fn lock(&mut self) -> Result<()> {
+        let lock = self
+            .lock
+            .as_mut()
+            .ok_or_else(|| anyhow!("the key store has no passphrase"))?;
+        lock.lock(&mut self.memory);
+        Ok(())
+    }
+
+    /// Zeroizes the decrypted keys once their unlock expired, returning whether it did. The
+    /// keys cannot be read after the expiry anyway, this drops them from memory.
+    pub fn lock_if_expired(&mut self) -> bool {
+        match &mut self.lock {
+            Some(lock) if lock.is_expired() => {
+                lock.lock(&mut self.memory);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the keys are locked. The key stores without a passphrase are never locked.
+    pub fn is_locked(&self) -> bool {
+        self.lock
+            .as_ref()
+            .map(KeyLock::is_locked)
+            .unwrap_or_default()
+    }
+
+    /// The instant the keys lock again, if they are unlocked for a limited time.
+    pub fn unlocked_until(&self) -> Option<Instant> {
+        self.lock.as_ref().and_then(KeyLock::unlocked_until)
+    }
+
+    fn check_unlocked(&self) -> Result<()> {
+        match &self.lock {
+            Some(lock) => lock.check_unlocked(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write all keys to file without any encryption, sealing them again if the key store has
+    /// a passphrase.
+    fn flush_no_encryption(&mut self) -> Result<()> {
+        if let Some(lock) = &mut self.lock {
+            lock.seal(&self.memory)?;
+        }
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        let dir = file_path
             .parent()
             .ok_or_else(|| anyhow!("Key store parent path not exists"))?;
 
         fs::create_dir_all(dir)?;
 
-        let file = File::create(&self.file_path)?;
+        let file = File::create(file_path)?;
 
         // TODO: do we need to set path permission?
 
@@ -193,7 +303,8 @@ impl<T: Clone + Eq + Hash + TryFrom<KeyInfo> + Default + ToString> PersistentKey
 #[cfg(test)]
 mod tests {
     use crate::evm::KeyInfo;
-    use crate::{EvmKeyStore, PersistentKeyStore};
+    use crate::{EvmKeyStore, KeyStoreLocked, PersistentKeyStore};
+    use std::time::Duration;
 
     #[derive(Clone, Eq, PartialEq, Hash, Debug)]
     struct Key {
@@ -286,4 +397,75 @@ mod tests {
         // the default is also recovered from persistent storage
         assert_eq!(ks.get_default().unwrap().unwrap(), new_addr);
     }
+
+    #[test]
+    fn test_ephemeral_keystore() {
+        let mut ks = PersistentKeyStore::<Key>::ephemeral();
+
+        let key_info = KeyInfo {
+            private_key: vec![0, 1, 2],
+        };
+        let addr = ks.put(key_info.clone()).unwrap();
+        ks.set_default(&addr).unwrap();
+
+        assert_eq!(ks.get(&addr).unwrap().unwrap(), key_info);
+        assert_eq!(ks.get_default().unwrap().unwrap(), addr);
+    }
+
+    #[test]
+    fn test_lock_unlock_keystore() {
+        let mut ks = PersistentKeyStore::<Key>::ephemeral();
+        let key_info = KeyInfo {
+            private_key: vec![0, 1, 2],
+        };
+        let addr = ks.put(key_info.clone()).unwrap();
+        ks.set_default(&addr).unwrap();
+
+        ks.set_passphrase("secret").unwrap();
+        assert!(ks.is_locked());
+        assert!(ks.memory.data.is_empty());
+        assert!(ks.get(&addr).unwrap_err().is::<KeyStoreLocked>());
+        assert!(ks.put(key_info.clone()).is_err());
+        // the addresses are known while locked
+        assert!(ks.list().unwrap().contains(&addr));
+        assert_eq!(ks.get_default().unwrap(), Some(addr.clone()));
+
+        assert!(ks.unlock("wrong", Duration::from_secs(60)).is_err());
+        assert!(ks.is_locked());
+
+        ks.unlock("secret", Duration::from_secs(60)).unwrap();
+        assert!(ks.unlocked_until().is_some());
+        assert_eq!(ks.get(&addr).unwrap().unwrap(), key_info);
+        // the keys added while unlocked are sealed too
+        let other = KeyInfo {
+            private_key: vec![3, 4, 5],
+        };
+        let other_addr = ks.put(other.clone()).unwrap();
+
+        ks.lock().unwrap();
+        assert!(ks.memory.data.is_empty());
+        ks.unlock("secret", Duration::from_secs(60)).unwrap();
+        assert_eq!(ks.get(&other_addr).unwrap().unwrap(), other);
+    }
+
+    #[test]
+    fn test_auto_lock_keystore() {
+        let mut ks = PersistentKeyStore::<Key>::ephemeral();
+        let addr = ks
+            .put(KeyInfo {
+                private_key: vec![0, 1, 2],
+            })
+            .unwrap();
+        ks.set_passphrase("secret").unwrap();
+
+        ks.unlock("secret", Duration::ZERO).unwrap();
+        // expired right away, unreadable even before being zeroized
+        assert!(ks.is_locked());
+        assert!(ks.get(&addr).is_err());
+        assert!(!ks.memory.data.is_empty());
+
+        assert!(ks.lock_if_expired());
+        assert!(ks.memory.data.is_empty());
+        assert!(!ks.lock_if_expired());
+    }
 }
@@ -4,7 +4,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::{
-    fmt::Display,
+    fmt::{self, Display},
     fs::{self, create_dir, File},
     io::{BufReader, BufWriter, ErrorKind, Read, Write},
     path::{Path, PathBuf},
@@ -24,8 +24,10 @@ use xsalsa20poly1305::{
     aead::{generic_array::GenericArray, Aead},
     KeyInit, XSalsa20Poly1305, NONCE_SIZE,
 };
+use zeroize::{Zeroize, Zeroizing};
 
 use super::errors::Error;
+use crate::secret::{SecretString, REDACTED};
 
 pub const KEYSTORE_NAME: &str = "keystore.json";
 pub const ENCRYPTED_KEYSTORE_NAME: &str = "keystore";
@@ -33,25 +35,56 @@ pub const ENCRYPTED_KEYSTORE_NAME: &str = "keystore";
 /// Environmental variable which holds the `KeyStore` encryption phrase.
 pub const FOREST_KEYSTORE_PHRASE_ENV: &str = "FOREST_KEYSTORE_PHRASE";
 
-type SaltByteArray = [u8; RECOMMENDED_SALT_LEN];
+pub(crate) type SaltByteArray = [u8; RECOMMENDED_SALT_LEN];
 
 // TODO need to update keyinfo to not use SignatureType, use string instead to
 // save keys like jwt secret
 /// `KeyInfo` structure, this contains the type of key (stored as a string) and
-/// the private key. Note how the private key is stored as a byte vector
-#[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
+/// the private key. Note how the private key is stored as a byte vector. It is zeroized on drop
+/// and redacted in `Debug`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KeyInfo {
     key_type: SignatureType,
     // Vec<u8> is used because The private keys for BLS and SECP256K1 are not of the same type
     private_key: Vec<u8>,
 }
 
-#[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PersistentKeyInfo {
     key_type: SignatureType,
     private_key: String,
 }
 
+impl fmt::Debug for KeyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyInfo")
+            .field("key_type", &self.key_type)
+            .field("private_key", &REDACTED)
+            .finish()
+    }
+}
+
+impl Drop for KeyInfo {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+impl fmt::Debug for PersistentKeyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistentKeyInfo")
+            .field("key_type", &self.key_type)
+            .field("private_key", &REDACTED)
+            .finish()
+    }
+}
+
+impl Drop for PersistentKeyInfo {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
 impl KeyInfo {
     /// Return a new `KeyInfo` given the key type and private key
     pub fn new(key_type: SignatureType, private_key: Vec<u8>) -> Self {
@@ -140,7 +173,8 @@ pub struct KeyStore {
 pub enum KeyStoreConfig {
     Memory,
     Persistent(PathBuf),
-    Encrypted(PathBuf, String),
+    /// The location of the key store and its passphrase.
+    Encrypted(PathBuf, SecretString),
 }
 
 /// Persistent `KeyStore` in JSON clear text in `KEYSTORE_LOCATION`
@@ -153,12 +187,27 @@ struct PersistentKeyStore {
 /// `Argon2id` hash key derivation
 /// `XSalsa20Poly1305` authenticated encryption
 /// CBOR encoding
-#[derive(Clone, PartialEq, Debug, Eq)]
-struct EncryptedKeyStore {
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct EncryptedKeyStore {
     salt: SaltByteArray,
     encryption_key: Vec<u8>,
 }
 
+impl fmt::Debug for EncryptedKeyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedKeyStore")
+            .field("salt", &self.salt)
+            .field("encryption_key", &REDACTED)
+            .finish()
+    }
+}
+
+impl Drop for EncryptedKeyStore {
+    fn drop(&mut self) {
+        self.encryption_key.zeroize();
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum EncryptedKeyStoreError {
     /// Possibly indicates incorrect passphrase
@@ -206,7 +255,7 @@ impl KeyStore {
                                 key.to_string(),
                                 KeyInfo {
                                     private_key: BASE64_STANDARD
-                                        .decode(value.private_key.clone())
+                                        .decode(&value.private_key)
                                         .map_err(|error| Error::Other(error.to_string()))?,
                                     key_type: value.key_type,
                                 },
@@ -261,12 +310,11 @@ impl KeyStore {
                             );
 
                             let (salt, encryption_key) =
-                                EncryptedKeyStore::derive_key(&passphrase, None).map_err(
-                                    |error| {
+                                EncryptedKeyStore::derive_key(passphrase.expose_secret(), None)
+                                    .map_err(|error| {
                                         error!("Failed to create key from passphrase");
                                         Error::Other(error.to_string())
-                                    },
-                                )?;
+                                    })?;
                             Ok(Self {
                                 key_info: HashMap::new(),
                                 persistence: Some(PersistentKeyStore { file_path }),
@@ -281,14 +329,17 @@ impl KeyStore {
                             let data = buf.split_off(RECOMMENDED_SALT_LEN);
                             let mut prev_salt = [0; RECOMMENDED_SALT_LEN];
                             prev_salt.copy_from_slice(&buf);
-                            let (salt, encryption_key) =
-                                EncryptedKeyStore::derive_key(&passphrase, Some(prev_salt))
-                                    .map_err(|error| {
-                                        error!("Failed to create key from passphrase");
-                                        Error::Other(error.to_string())
-                                    })?;
+                            let (salt, encryption_key) = EncryptedKeyStore::derive_key(
+                                passphrase.expose_secret(),
+                                Some(prev_salt),
+                            )
+                            .map_err(|error| {
+                                error!("Failed to create key from passphrase");
+                                Error::Other(error.to_string())
+                            })?;
 
                             let decrypted_data = EncryptedKeyStore::decrypt(&encryption_key, &data)
+                                .map(Zeroizing::new)
                                 .map_err(|error| Error::Other(error.to_string()))?;
 
                             let key_info = serde_ipld_dagcbor::from_slice(&decrypted_data)
@@ -312,10 +363,11 @@ impl KeyStore {
                         debug!("Encrypted keystore does not exist, initializing new keystore");
 
                         let (salt, encryption_key) =
-                            EncryptedKeyStore::derive_key(&passphrase, None).map_err(|error| {
-                                error!("Failed to create key from passphrase");
-                                Error::Other(error.to_string())
-                            })?;
+                            EncryptedKeyStore::derive_key(passphrase.expose_secret(), None)
+                                .map_err(|error| {
+                                    error!("Failed to create key from passphrase");
+                                    Error::Other(error.to_string())
+                                })?;
 
                         Ok(Self {
                             key_info: HashMap::new(),
@@ -350,9 +402,11 @@ impl KeyStore {
                 match &self.encryption {
                     Some(encrypted_keystore) => {
                         // Flush For EncryptedKeyStore
-                        let data = serde_ipld_dagcbor::to_vec(&self.key_info).map_err(|e| {
-                            Error::Other(format!("failed to serialize and write key info: {e}"))
-                        })?;
+                        let data = serde_ipld_dagcbor::to_vec(&self.key_info)
+                            .map(Zeroizing::new)
+                            .map_err(|e| {
+                                Error::Other(format!("failed to serialize and write key info: {e}"))
+                            })?;
 
                         let encrypted_data =
                             EncryptedKeyStore::encrypt(&encrypted_keystore.encryption_key, &data)?;
@@ -368,7 +422,7 @@ impl KeyStore {
                             key_info.insert(
                                 key.to_string(),
                                 PersistentKeyInfo {
-                                    private_key: BASE64_STANDARD.encode(value.private_key.clone()),
+                                    private_key: BASE64_STANDARD.encode(&value.private_key),
                                     key_type: value.key_type,
                                 },
                             );
@@ -427,7 +481,7 @@ impl KeyStore {
 }
 
 impl EncryptedKeyStore {
-    fn derive_key(
+    pub(crate) fn derive_key(
         passphrase: &str,
         prev_salt: Option<SaltByteArray>,
     ) -> anyhow::Result<(SaltByteArray, Vec<u8>)> {
@@ -469,7 +523,7 @@ impl EncryptedKeyStore {
         }
     }
 
-    fn encrypt(encryption_key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+    pub(crate) fn encrypt(encryption_key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
         let mut nonce = [0; NONCE_SIZE];
         OsRng.fill_bytes(&mut nonce);
         let nonce = GenericArray::from_slice(&nonce);
@@ -480,7 +534,7 @@ impl EncryptedKeyStore {
         Ok(ciphertext)
     }
 
-    fn decrypt(encryption_key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+    pub(crate) fn decrypt(encryption_key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
         let cyphertext_len = msg.len() - NONCE_SIZE;
         let ciphertext = &msg[..cyphertext_len];
         let nonce = GenericArray::from_slice(&msg[cyphertext_len..]);
@@ -551,7 +605,7 @@ mod test {
     fn test_read_old_encrypted_keystore() -> Result<()> {
         let dir: PathBuf = "tests/keystore_encrypted_old".into();
         ensure!(dir.exists());
-        let ks = KeyStore::new(KeyStoreConfig::Encrypted(dir, PASSPHRASE.to_string()))?;
+        let ks = KeyStore::new(KeyStoreConfig::Encrypted(dir, PASSPHRASE.into()))?;
         ensure!(ks.persistence.is_some());
         Ok(())
     }
@@ -561,13 +615,13 @@ mod test {
         let keystore_location = tempfile::tempdir()?.into_path();
         let ks = KeyStore::new(KeyStoreConfig::Encrypted(
             keystore_location.clone(),
-            PASSPHRASE.to_string(),
+            PASSPHRASE.into(),
         ))?;
         ks.flush()?;
 
         let ks_read = KeyStore::new(KeyStoreConfig::Encrypted(
             keystore_location,
-            PASSPHRASE.to_string(),
+            PASSPHRASE.into(),
         ))?;
 
         ensure!(ks == ks_read);
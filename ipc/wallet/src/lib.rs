@@ -6,16 +6,20 @@ use std::str::FromStr;
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
+mod address_book;
 mod evm;
 mod fvm;
+mod secret;
 
+pub use crate::address_book::{AddressBook, AddressBookEntry, DEFAULT_ADDRESS_BOOK_NAME};
 #[cfg(feature = "with-ethers")]
 pub use crate::evm::{random_eth_key_info, EthKeyAddress};
 pub use crate::evm::{
-    KeyInfo as EvmKeyInfo, KeyStore as EvmKeyStore, PersistentKeyInfo, PersistentKeyStore,
-    DEFAULT_KEYSTORE_NAME,
+    seeded_key_info, InMemoryKeyStore, KeyInfo as EvmKeyInfo, KeyStore as EvmKeyStore,
+    KeyStoreLocked, PersistentKeyInfo, PersistentKeyStore, DEFAULT_KEYSTORE_NAME,
 };
 pub use crate::fvm::*;
+pub use crate::secret::SecretString;
 
 /// WalletType determines the kind of keys and wallets
 /// supported in the keystore
@@ -0,0 +1,66 @@
+// Copyright 2022-2024 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! Secrets kept out of the logs and zeroized once dropped.
+
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// The placeholder of the secrets in the `Debug` output.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// A secret string, e.g. a password or an encoded private key. It is zeroized on drop, has no
+/// `Display`, and its `Debug` output is redacted, so that it can't end up in the logs by
+/// accident: reading it requires an explicit [`SecretString::expose_secret`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(secret: String) -> Self {
+        Self(Zeroizing::new(secret))
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretString").field(&REDACTED).finish()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self::new(secret.to_string())
+    }
+}
+
+impl FromStr for SecretString {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretString;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = SecretString::from("hunter2");
+        assert_eq!(secret.expose_secret(), "hunter2");
+        assert!(!format!("{secret:?}").contains("hunter2"));
+        assert!(!format!("{:?}", Some(secret)).contains("hunter2"));
+    }
+}